@@ -50,6 +50,7 @@ pub struct Files {
     use_guards: Option<Rc<dyn Guard>>,
     guards: Vec<Rc<dyn Guard>>,
     hidden_files: bool,
+    precompressed: bool,
 }
 
 impl fmt::Debug for Files {
@@ -74,6 +75,7 @@ impl Clone for Files {
             use_guards: self.use_guards.clone(),
             guards: self.guards.clone(),
             hidden_files: self.hidden_files,
+            precompressed: self.precompressed,
         }
     }
 }
@@ -120,6 +122,7 @@ impl Files {
             use_guards: None,
             guards: Vec::new(),
             hidden_files: false,
+            precompressed: false,
         }
     }
 
@@ -321,6 +324,19 @@ impl Files {
         self.hidden_files = true;
         self
     }
+
+    /// Enables serving precompressed variants of files.
+    ///
+    /// When a file is requested, siblings with a `.br` or `.gz` extension (such as `app.js.br` for
+    /// `app.js`) are served instead if the client accepts that encoding, with the `Content-Type`
+    /// of the original file and a matching `Content-Encoding`, so compression middleware leaves
+    /// them untouched. Responses for files that have variants include `Vary: Accept-Encoding`.
+    ///
+    /// The original file must exist; it is served to clients that accept neither encoding.
+    pub fn use_precompressed_files(mut self) -> Self {
+        self.precompressed = true;
+        self
+    }
 }
 
 impl HttpServiceFactory for Files {
@@ -372,6 +388,7 @@ impl ServiceFactory<ServiceRequest> for Files {
             file_flags: self.file_flags,
             guards: self.use_guards.clone(),
             hidden_files: self.hidden_files,
+            precompressed: self.precompressed,
         };
 
         if let Some(ref default) = *self.default.borrow() {
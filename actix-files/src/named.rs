@@ -238,6 +238,17 @@ impl NamedFile {
         Self::from_file(file, path)
     }
 
+    /// Opens `encoded_path`, a variant of the file at `path` compressed with `encoding`, so that
+    /// it is served with the headers of the original file.
+    pub(crate) async fn open_precompressed(
+        path: &Path,
+        encoded_path: &Path,
+        encoding: ContentEncoding,
+    ) -> io::Result<NamedFile> {
+        let encoded = Self::open_async(encoded_path).await?;
+        Ok(Self::from_file(encoded.file, path)?.set_content_encoding(encoding))
+    }
+
     /// Returns reference to the underlying file object.
     #[inline]
     pub fn file(&self) -> &File {
@@ -529,10 +540,12 @@ impl NamedFile {
                     offset = ranges[0].start;
 
                     // don't allow compression middleware to modify partial content
-                    res.insert_header((
-                        header::CONTENT_ENCODING,
-                        HeaderValue::from_static("identity"),
-                    ));
+                    if self.encoding.is_none() {
+                        res.insert_header((
+                            header::CONTENT_ENCODING,
+                            HeaderValue::from_static("identity"),
+                        ));
+                    }
 
                     res.insert_header((
                         header::CONTENT_RANGE,
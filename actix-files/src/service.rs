@@ -1,11 +1,20 @@
-use std::{fmt, io, ops::Deref, path::PathBuf, rc::Rc};
+use std::{
+    ffi::OsString,
+    fmt, io, iter,
+    ops::Deref,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use actix_web::{
     body::BoxBody,
     dev::{self, Service, ServiceRequest, ServiceResponse},
     error::Error,
     guard::Guard,
-    http::{header, Method},
+    http::{
+        header::{self, AcceptEncoding, ContentEncoding, Encoding, Header as _, HeaderValue},
+        Method,
+    },
     HttpResponse,
 };
 use futures_core::future::LocalBoxFuture;
@@ -39,6 +48,7 @@ pub struct FilesServiceInner {
     pub(crate) file_flags: named::Flags,
     pub(crate) guards: Option<Rc<dyn Guard>>,
     pub(crate) hidden_files: bool,
+    pub(crate) precompressed: bool,
 }
 
 impl fmt::Debug for FilesServiceInner {
@@ -78,6 +88,70 @@ impl FilesService {
         ServiceResponse::new(req, res)
     }
 
+    /// Opens the file at `path`, or one of its precompressed variants that the client accepts.
+    async fn open_file(&self, req: &ServiceRequest, path: &Path) -> io::Result<Opened> {
+        if !self.precompressed {
+            return Ok(Opened::plain(NamedFile::open_async(path).await?));
+        }
+
+        let candidates = [
+            (Encoding::brotli(), ContentEncoding::Brotli, "br"),
+            (Encoding::gzip(), ContentEncoding::Gzip, "gz"),
+        ];
+
+        let variants = candidates
+            .iter()
+            .filter_map(|(enc, content_enc, ext)| {
+                let mut encoded_path = OsString::from(path);
+                encoded_path.push(".");
+                encoded_path.push(ext);
+                let encoded_path = PathBuf::from(encoded_path);
+
+                encoded_path
+                    .is_file()
+                    .then(|| (enc.clone(), *content_enc, encoded_path))
+            })
+            .collect::<Vec<_>>();
+
+        if variants.is_empty() {
+            return Ok(Opened::plain(NamedFile::open_async(path).await?));
+        }
+
+        // malformed headers are treated as accepting only unencoded files
+        let identity = Encoding::identity();
+        let chosen = AcceptEncoding::parse(req).ok().and_then(|accept| {
+            accept.negotiate(
+                variants
+                    .iter()
+                    .map(|(enc, _, _)| enc)
+                    .chain(iter::once(&identity)),
+            )
+        });
+
+        let file = match variants
+            .iter()
+            .find(|(enc, _, _)| Some(enc) == chosen.as_ref())
+        {
+            Some((_, content_enc, encoded_path)) => {
+                NamedFile::open_precompressed(path, encoded_path, *content_enc).await?
+            }
+            None => NamedFile::open_async(path).await?,
+        };
+
+        Ok(Opened { file, vary: true })
+    }
+
+    fn serve_opened(&self, req: ServiceRequest, opened: Opened) -> ServiceResponse {
+        let mut res = self.serve_named_file(req, opened.file);
+
+        if opened.vary {
+            res.headers_mut()
+                .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+        }
+
+        res
+    }
+
     fn show_index(&self, req: ServiceRequest, path: PathBuf) -> ServiceResponse {
         let dir = Directory::new(self.directory.clone(), path);
 
@@ -87,6 +161,20 @@ impl FilesService {
     }
 }
 
+/// A file opened for a request.
+struct Opened {
+    file: NamedFile,
+
+    /// Whether precompressed variants exist, so that responses vary on `Accept-Encoding`.
+    vary: bool,
+}
+
+impl Opened {
+    fn plain(file: NamedFile) -> Self {
+        Opened { file, vary: false }
+    }
+}
+
 impl fmt::Debug for FilesService {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str("FilesService")
@@ -161,8 +249,8 @@ impl Service<ServiceRequest> for FilesService {
                 match this.index {
                     Some(ref index) => {
                         let named_path = path.join(index);
-                        match NamedFile::open_async(named_path).await {
-                            Ok(named_file) => Ok(this.serve_named_file(req, named_file)),
+                        match this.open_file(&req, &named_path).await {
+                            Ok(opened) => Ok(this.serve_opened(req, opened)),
                             Err(_) if this.show_index => Ok(this.show_index(req, path)),
                             Err(err) => this.handle_err(err, req).await,
                         }
@@ -174,19 +262,8 @@ impl Service<ServiceRequest> for FilesService {
                     )),
                 }
             } else {
-                match NamedFile::open_async(&path).await {
-                    Ok(mut named_file) => {
-                        if let Some(ref mime_override) = this.mime_override {
-                            let new_disposition =
-                                mime_override(&named_file.content_type.type_());
-                            named_file.content_disposition.disposition = new_disposition;
-                        }
-                        named_file.flags = this.file_flags;
-
-                        let (req, _) = req.into_parts();
-                        let res = named_file.into_response(&req);
-                        Ok(ServiceResponse::new(req, res))
-                    }
+                match this.open_file(&req, &path).await {
+                    Ok(opened) => Ok(this.serve_opened(req, opened)),
                     Err(err) => this.handle_err(err, req).await,
                 }
             }
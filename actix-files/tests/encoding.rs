@@ -36,3 +36,61 @@ async fn test_utf8_file_contents() {
         Some(&HeaderValue::from_static("text/plain")),
     );
 }
+
+#[actix_web::test]
+async fn test_precompressed_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("app.txt"), "plain").unwrap();
+    std::fs::write(dir.path().join("app.txt.gz"), "gzip").unwrap();
+    std::fs::write(dir.path().join("app.txt.br"), "brotli").unwrap();
+    std::fs::write(dir.path().join("style.css"), "plain").unwrap();
+    std::fs::write(dir.path().join("style.css.gz"), "gzip").unwrap();
+
+    let srv = test::init_service(
+        App::new().service(Files::new("/", dir.path()).use_precompressed_files()),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/app.txt")
+        .insert_header((header::ACCEPT_ENCODING, "br, gzip"))
+        .to_request();
+    let res = test::call_service(&srv, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "br");
+    assert_eq!(res.headers().get(header::VARY).unwrap(), "accept-encoding");
+    assert_eq!(
+        res.headers().get(header::CONTENT_TYPE).unwrap(),
+        "text/plain; charset=utf-8"
+    );
+    assert_eq!(test::read_body(res).await, "brotli");
+
+    let req = TestRequest::with_uri("/app.txt")
+        .insert_header((header::ACCEPT_ENCODING, "gzip, br;q=0.5"))
+        .to_request();
+    let res = test::call_service(&srv, req).await;
+    assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+    assert_eq!(test::read_body(res).await, "gzip");
+
+    let req = TestRequest::with_uri("/style.css")
+        .insert_header((header::ACCEPT_ENCODING, "br"))
+        .to_request();
+    let res = test::call_service(&srv, req).await;
+    assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+    assert_eq!(res.headers().get(header::VARY).unwrap(), "accept-encoding");
+    assert_eq!(test::read_body(res).await, "plain");
+
+    let req = TestRequest::with_uri("/app.txt").to_request();
+    let res = test::call_service(&srv, req).await;
+    assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+    assert_eq!(test::read_body(res).await, "plain");
+
+    // ranges apply to the encoded file
+    let req = TestRequest::with_uri("/app.txt")
+        .insert_header((header::ACCEPT_ENCODING, "gzip"))
+        .insert_header((header::RANGE, "bytes=0-1"))
+        .to_request();
+    let res = test::call_service(&srv, req).await;
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+    assert_eq!(test::read_body(res).await, "gz");
+}
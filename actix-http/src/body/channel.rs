@@ -0,0 +1,353 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    error::Error as StdError,
+    fmt,
+    pin::Pin,
+    rc::{Rc, Weak},
+    task::{Context, Poll, Waker},
+};
+
+use actix_utils::future::poll_fn;
+use bytes::Bytes;
+
+use super::{BodySize, MessageBody};
+
+/// Default number of chunks buffered by [`channel`] bodies before senders are made to wait.
+pub(crate) const DEFAULT_CHANNEL_CAPACITY: usize = 16;
+
+/// Creates a streaming body fed from a bounded channel.
+///
+/// Returns the sending half, which can be moved to another (local) task, and a body which can be
+/// used in a response immediately. At most `capacity` chunks are buffered; once the buffer is
+/// full, [`BodySender::send`] waits until the peer has consumed some data.
+///
+/// The body ends when the sender is dropped. To abort the response mid-flight, use
+/// [`BodySender::abort`].
+///
+/// # Panics
+/// Panics if `capacity` is zero.
+///
+/// # Examples
+/// ```
+/// use actix_http::{body, Response, StatusCode};
+/// use bytes::Bytes;
+///
+/// # async fn handler() -> Response<body::ChannelBody> {
+/// let (mut tx, body) = body::channel(8);
+///
+/// actix_rt::spawn(async move {
+///     for chunk in ["hello", " ", "world"] {
+///         if tx.send(Bytes::from(chunk)).await.is_err() {
+///             // peer went away
+///             break;
+///         }
+///     }
+/// });
+///
+/// Response::with_body(StatusCode::OK, body)
+/// # }
+/// ```
+pub fn channel(capacity: usize) -> (BodySender, ChannelBody) {
+    assert!(
+        capacity > 0,
+        "channel body capacity must be greater than zero"
+    );
+
+    let shared = Rc::new(RefCell::new(Inner {
+        buf: VecDeque::with_capacity(capacity),
+        capacity,
+        eof: false,
+        err: None,
+        rx_task: None,
+        tx_task: None,
+    }));
+
+    (
+        BodySender {
+            inner: Rc::downgrade(&shared),
+        },
+        ChannelBody { inner: shared },
+    )
+}
+
+struct Inner {
+    buf: VecDeque<Bytes>,
+    capacity: usize,
+    eof: bool,
+    err: Option<Box<dyn StdError>>,
+    rx_task: Option<Waker>,
+    tx_task: Option<Waker>,
+}
+
+impl Inner {
+    fn wake_rx(&mut self) {
+        if let Some(waker) = self.rx_task.take() {
+            waker.wake();
+        }
+    }
+
+    fn wake_tx(&mut self) {
+        if let Some(waker) = self.tx_task.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Error returned from [`BodySender`] when the receiving body has been dropped.
+///
+/// Contains the chunk that could not be sent.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SendError(pub Bytes);
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("channel body was dropped")
+    }
+}
+
+impl StdError for SendError {}
+
+/// Sending half of a [`channel`] body.
+pub struct BodySender {
+    inner: Weak<RefCell<Inner>>,
+}
+
+impl BodySender {
+    /// Sends a chunk, waiting for buffer space if the peer is reading slower than data is produced.
+    ///
+    /// Empty chunks are ignored. Returns an error containing the chunk if the body has been
+    /// dropped, usually because the client disconnected.
+    pub async fn send(&mut self, chunk: Bytes) -> Result<(), SendError> {
+        let mut chunk = Some(chunk);
+        poll_fn(|cx| self.poll_send(cx, &mut chunk)).await
+    }
+
+    fn poll_send(
+        &mut self,
+        cx: &mut Context<'_>,
+        chunk: &mut Option<Bytes>,
+    ) -> Poll<Result<(), SendError>> {
+        let shared = match self.inner.upgrade() {
+            Some(shared) => shared,
+            None => return Poll::Ready(Err(SendError(chunk.take().unwrap_or_default()))),
+        };
+
+        let mut inner = shared.borrow_mut();
+
+        if inner.buf.len() >= inner.capacity {
+            inner.tx_task = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        match chunk.take() {
+            Some(chunk) if !chunk.is_empty() => {
+                inner.buf.push_back(chunk);
+                inner.wake_rx();
+            }
+            _ => {}
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    /// Attempts to send a chunk without waiting.
+    ///
+    /// Returns the chunk back if the buffer is full or the body has been dropped.
+    pub fn try_send(&mut self, chunk: Bytes) -> Result<(), SendError> {
+        let shared = match self.inner.upgrade() {
+            Some(shared) => shared,
+            None => return Err(SendError(chunk)),
+        };
+
+        let mut inner = shared.borrow_mut();
+
+        if inner.buf.len() >= inner.capacity {
+            return Err(SendError(chunk));
+        }
+
+        if !chunk.is_empty() {
+            inner.buf.push_back(chunk);
+            inner.wake_rx();
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the receiving body has been dropped.
+    pub fn is_closed(&self) -> bool {
+        self.inner.strong_count() == 0
+    }
+
+    /// Aborts the stream with an error.
+    ///
+    /// Chunks already buffered are still yielded before the error. The error is then passed to
+    /// the dispatcher, which stops writing the response and closes the connection.
+    pub fn abort(self, err: impl Into<Box<dyn StdError>>) {
+        if let Some(shared) = self.inner.upgrade() {
+            let mut inner = shared.borrow_mut();
+            inner.err = Some(err.into());
+            inner.wake_rx();
+        }
+    }
+}
+
+impl Drop for BodySender {
+    fn drop(&mut self) {
+        if let Some(shared) = self.inner.upgrade() {
+            let mut inner = shared.borrow_mut();
+            inner.eof = true;
+            inner.wake_rx();
+        }
+    }
+}
+
+impl fmt::Debug for BodySender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodySender")
+            .field("closed", &self.is_closed())
+            .finish()
+    }
+}
+
+/// Receiving half of a [`channel`] body.
+///
+/// Response does not contain `Content-Length` header and appropriate transfer encoding is used.
+pub struct ChannelBody {
+    inner: Rc<RefCell<Inner>>,
+}
+
+impl MessageBody for ChannelBody {
+    type Error = Box<dyn StdError>;
+
+    #[inline]
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let mut inner = self.inner.borrow_mut();
+
+        if let Some(chunk) = inner.buf.pop_front() {
+            inner.wake_tx();
+            return Poll::Ready(Some(Ok(chunk)));
+        }
+
+        if let Some(err) = inner.err.take() {
+            return Poll::Ready(Some(Err(err)));
+        }
+
+        if inner.eof {
+            return Poll::Ready(None);
+        }
+
+        inner.rx_task = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for ChannelBody {
+    fn drop(&mut self) {
+        // sender will observe the dropped body when woken
+        self.inner.borrow_mut().wake_tx();
+    }
+}
+
+impl fmt::Debug for ChannelBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChannelBody")
+            .field("buffered", &self.inner.borrow().buf.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_rt::pin;
+    use static_assertions::{assert_impl_all, assert_not_impl_any};
+
+    use super::*;
+    use crate::body::to_bytes;
+
+    assert_impl_all!(ChannelBody: MessageBody, fmt::Debug);
+    assert_not_impl_any!(BodySender: Send, Sync);
+
+    #[actix_rt::test]
+    async fn sends_and_ends_on_drop() {
+        let (mut tx, body) = channel(4);
+
+        tx.send(Bytes::from_static(b"hello ")).await.unwrap();
+        tx.send(Bytes::new()).await.unwrap();
+        tx.send(Bytes::from_static(b"world")).await.unwrap();
+        drop(tx);
+
+        assert_eq!(
+            to_bytes(body).await.unwrap(),
+            Bytes::from_static(b"hello world")
+        );
+    }
+
+    #[actix_rt::test]
+    async fn streams_from_other_task() {
+        let (mut tx, body) = channel(1);
+
+        actix_rt::spawn(async move {
+            for n in 0..10 {
+                tx.send(Bytes::from(n.to_string())).await.unwrap();
+            }
+        });
+
+        assert_eq!(
+            to_bytes(body).await.unwrap(),
+            Bytes::from_static(b"0123456789")
+        );
+    }
+
+    #[actix_rt::test]
+    async fn backpressure() {
+        let (mut tx, body) = channel(2);
+
+        tx.try_send(Bytes::from_static(b"1")).unwrap();
+        tx.try_send(Bytes::from_static(b"2")).unwrap();
+        assert_eq!(
+            tx.try_send(Bytes::from_static(b"3")),
+            Err(SendError(Bytes::from_static(b"3")))
+        );
+
+        pin!(body);
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        assert_eq!(chunk.unwrap().unwrap(), Bytes::from_static(b"1"));
+
+        tx.try_send(Bytes::from_static(b"3")).unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn abort_after_buffered_chunks() {
+        let (mut tx, body) = channel(4);
+
+        tx.send(Bytes::from_static(b"1")).await.unwrap();
+        tx.abort("stream aborted");
+
+        pin!(body);
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        assert_eq!(chunk.unwrap().unwrap(), Bytes::from_static(b"1"));
+
+        let err = poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        assert_eq!(err.unwrap().unwrap_err().to_string(), "stream aborted");
+    }
+
+    #[actix_rt::test]
+    async fn send_after_body_dropped() {
+        let (mut tx, body) = channel(4);
+        assert!(!tx.is_closed());
+
+        drop(body);
+        assert!(tx.is_closed());
+
+        let res = tx.send(Bytes::from_static(b"1")).await;
+        assert_eq!(res, Err(SendError(Bytes::from_static(b"1"))));
+    }
+}
@@ -7,19 +7,24 @@
 
 mod body_stream;
 mod boxed;
+mod channel;
 mod either;
 mod message_body;
 mod none;
 mod size;
 mod sized_stream;
+mod transform;
 mod utils;
 
 pub use self::body_stream::BodyStream;
 pub use self::boxed::BoxBody;
+pub(crate) use self::channel::DEFAULT_CHANNEL_CAPACITY;
+pub use self::channel::{channel, BodySender, ChannelBody, SendError};
 pub use self::either::EitherBody;
 pub use self::message_body::MessageBody;
 pub(crate) use self::message_body::MessageBodyMapErr;
 pub use self::none::None;
 pub use self::size::BodySize;
 pub use self::sized_stream::SizedStream;
+pub use self::transform::{BodyTransform, TransformBody};
 pub use self::utils::to_bytes;
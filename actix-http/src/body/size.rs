@@ -1,3 +1,8 @@
+use crate::{
+    error::FramingError,
+    header::{self, HeaderMap},
+};
+
 /// Body size hint.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BodySize {
@@ -38,4 +43,51 @@ impl BodySize {
     pub fn is_eof(&self) -> bool {
         matches!(self, BodySize::None | BodySize::Sized(0))
     }
+
+    /// Checks that manually set `Content-Length` and `Transfer-Encoding` headers agree with a body
+    /// of this size.
+    ///
+    /// The HTTP/1 encoder picks `Content-Length` or chunked framing from the body size, so neither
+    /// header needs to be set by hand. A `Content-Length` header is accepted for streams, to disable
+    /// chunking, and for empty bodies, as in responses to `HEAD` requests. It is rejected when it
+    /// differs from the size of a non-empty body or is combined with `Transfer-Encoding`.
+    ///
+    /// ```
+    /// # use actix_http::{body::BodySize, error::FramingError, header::{self, HeaderMap, HeaderValue}};
+    /// let mut headers = HeaderMap::new();
+    /// headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("4"));
+    ///
+    /// assert!(BodySize::Sized(4).check_framing(&headers).is_ok());
+    /// assert!(BodySize::Stream.check_framing(&headers).is_ok());
+    /// assert!(matches!(
+    ///     BodySize::Sized(5).check_framing(&headers),
+    ///     Err(FramingError::ContentLengthMismatch { header: 4, body: 5 }),
+    /// ));
+    /// ```
+    pub fn check_framing(&self, headers: &HeaderMap) -> Result<(), FramingError> {
+        let length = match headers.get(&header::CONTENT_LENGTH) {
+            Some(length) => length,
+            None => return Ok(()),
+        };
+
+        if headers.contains_key(&header::TRANSFER_ENCODING) {
+            return Err(FramingError::ContentLengthWithTransferEncoding);
+        }
+
+        let length = length
+            .to_str()
+            .ok()
+            .and_then(|length| length.trim().parse::<u64>().ok())
+            .ok_or(FramingError::InvalidContentLength)?;
+
+        match *self {
+            BodySize::Sized(size) if size != 0 && size != length => {
+                Err(FramingError::ContentLengthMismatch {
+                    header: length,
+                    body: size,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
 }
@@ -0,0 +1,220 @@
+use std::{
+    convert::Infallible,
+    error::Error as StdError,
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use super::{BodySize, MessageBody};
+
+/// A rewrite of body chunks, applied by [`TransformBody`].
+///
+/// Closures taking and returning [`Bytes`] implement this trait for simple chunk-by-chunk
+/// rewrites. Transforms that buffer data, such as compressors or HTML rewriters, implement it
+/// directly so they can emit what remains when the body ends.
+pub trait BodyTransform {
+    /// Error produced while transforming.
+    type Error: Into<Box<dyn StdError>>;
+
+    /// Returns the size of the transformed body, given the size of the original one.
+    ///
+    /// By default, the size of a body that has content is unknown after transforming. Transforms
+    /// that keep the length of the content should return `original`.
+    fn size(&self, original: BodySize) -> BodySize {
+        match original {
+            BodySize::None => BodySize::None,
+            BodySize::Sized(_) | BodySize::Stream => BodySize::Stream,
+        }
+    }
+
+    /// Transforms a chunk of the original body.
+    ///
+    /// Returning an empty chunk is allowed; nothing is emitted for it.
+    fn transform(&mut self, chunk: Bytes) -> Result<Bytes, Self::Error>;
+
+    /// Returns the last chunk of the transformed body, once the original body ends.
+    ///
+    /// By default, nothing is added.
+    fn finish(&mut self) -> Result<Bytes, Self::Error> {
+        Ok(Bytes::new())
+    }
+}
+
+impl<F> BodyTransform for F
+where
+    F: FnMut(Bytes) -> Bytes,
+{
+    type Error = Infallible;
+
+    fn transform(&mut self, chunk: Bytes) -> Result<Bytes, Self::Error> {
+        Ok((self)(chunk))
+    }
+}
+
+pin_project! {
+    /// A body with a [`BodyTransform`] applied to its chunks.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_http::body::{self, TransformBody};
+    /// use bytes::Bytes;
+    ///
+    /// # actix_rt::System::new().block_on(async {
+    /// let body = TransformBody::new("hello world", |chunk: Bytes| {
+    ///     Bytes::from(chunk.to_ascii_uppercase())
+    /// });
+    ///
+    /// assert_eq!(body::to_bytes(body).await.unwrap(), "HELLO WORLD");
+    /// # })
+    /// ```
+    pub struct TransformBody<B, T> {
+        #[pin]
+        body: B,
+        transform: T,
+        finished: bool,
+    }
+}
+
+impl<B, T> TransformBody<B, T>
+where
+    B: MessageBody,
+    T: BodyTransform,
+{
+    /// Constructs a body applying `transform` to the chunks of `body`.
+    #[inline]
+    pub fn new(body: B, transform: T) -> Self {
+        TransformBody {
+            body,
+            transform,
+            finished: false,
+        }
+    }
+}
+
+impl<B, T> MessageBody for TransformBody<B, T>
+where
+    B: MessageBody,
+    T: BodyTransform,
+{
+    type Error = Box<dyn StdError>;
+
+    #[inline]
+    fn size(&self) -> BodySize {
+        self.transform.size(self.body.size())
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let mut this = self.project();
+
+        if *this.finished {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            let chunk = match ready!(this.body.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => this.transform.transform(chunk),
+                Some(Err(err)) => return Poll::Ready(Some(Err(err.into()))),
+                None => {
+                    *this.finished = true;
+
+                    return Poll::Ready(match this.transform.finish() {
+                        Ok(chunk) if chunk.is_empty() => None,
+                        res => Some(res.map_err(Into::into)),
+                    });
+                }
+            };
+
+            match chunk {
+                // empty chunks would end chunked responses early
+                Ok(chunk) if chunk.is_empty() => continue,
+                res => return Poll::Ready(Some(res.map_err(Into::into))),
+            }
+        }
+    }
+}
+
+impl<B, T> fmt::Debug for TransformBody<B, T>
+where
+    B: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransformBody")
+            .field("body", &self.body)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use static_assertions::assert_impl_all;
+
+    use super::*;
+    use crate::body::{to_bytes, BodyStream, None};
+
+    assert_impl_all!(TransformBody<(), fn(Bytes) -> Bytes>: MessageBody, fmt::Debug);
+
+    /// Counts the chunks of a body, appending the count at the end.
+    struct CountChunks(usize);
+
+    impl BodyTransform for CountChunks {
+        type Error = io::Error;
+
+        fn size(&self, original: BodySize) -> BodySize {
+            match original {
+                BodySize::None => BodySize::Sized(1),
+                _ => BodySize::Stream,
+            }
+        }
+
+        fn transform(&mut self, chunk: Bytes) -> Result<Bytes, Self::Error> {
+            self.0 += 1;
+            Ok(chunk)
+        }
+
+        fn finish(&mut self) -> Result<Bytes, Self::Error> {
+            Ok(Bytes::from(self.0.to_string()))
+        }
+    }
+
+    #[actix_rt::test]
+    async fn transforms_chunks() {
+        let body = TransformBody::new("abc", |chunk: Bytes| chunk.slice(1..));
+        assert_eq!(body.size(), BodySize::Stream);
+        assert_eq!(to_bytes(body).await.unwrap(), "bc");
+
+        let body = TransformBody::new(None::new(), |chunk: Bytes| chunk);
+        assert_eq!(body.size(), BodySize::None);
+        assert!(to_bytes(body).await.unwrap().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn skips_empty_chunks_and_finishes() {
+        let chunks = |chunks: &'static [&'static str]| {
+            BodyStream::new(futures_util::stream::iter(
+                chunks
+                    .iter()
+                    .map(|&chunk| Ok::<_, io::Error>(Bytes::from(chunk))),
+            ))
+        };
+
+        let body = TransformBody::new(chunks(&["a", "", "b"]), |chunk: Bytes| chunk);
+        assert_eq!(to_bytes(body).await.unwrap(), "ab");
+
+        let body = TransformBody::new(chunks(&["a", "b"]), CountChunks(0));
+        assert_eq!(to_bytes(body).await.unwrap(), "ab2");
+
+        let body = TransformBody::new(None::new(), CountChunks(0));
+        assert_eq!(body.size(), BodySize::Sized(1));
+        assert_eq!(to_bytes(body).await.unwrap(), "0");
+    }
+}
@@ -5,6 +5,7 @@ use actix_service::{IntoServiceFactory, Service, ServiceFactory};
 
 use crate::{
     body::{BoxBody, MessageBody},
+    config::{ServerHeader, DEFAULT_WRITE_BUFFER_SIZE},
     h1::{self, ExpectHandler, H1Service, UpgradeHandler},
     service::HttpService,
     ConnectCallback, Extensions, KeepAlive, Request, Response, ServiceConfig,
@@ -19,6 +20,14 @@ pub struct HttpServiceBuilder<T, S, X = ExpectHandler, U = UpgradeHandler> {
     client_disconnect_timeout: Duration,
     secure: bool,
     local_addr: Option<net::SocketAddr>,
+    max_payload_size: Option<usize>,
+    write_buffer_size: usize,
+    server_header: ServerHeader,
+    max_request_line: Option<usize>,
+    max_header_size: Option<usize>,
+    max_header_count: usize,
+    #[cfg(feature = "wire-tap")]
+    wire_tap: Option<crate::tap::WireTap>,
     expect: X,
     upgrade: Option<U>,
     on_connect_ext: Option<Rc<ConnectCallback<T>>>,
@@ -40,6 +49,14 @@ where
             client_disconnect_timeout: Duration::ZERO,
             secure: false,
             local_addr: None,
+            max_payload_size: None,
+            write_buffer_size: DEFAULT_WRITE_BUFFER_SIZE,
+            server_header: ServerHeader::Passthrough,
+            max_request_line: None,
+            max_header_size: None,
+            max_header_count: crate::h1::MAX_HEADERS,
+            #[cfg(feature = "wire-tap")]
+            wire_tap: None,
 
             // dispatcher parts
             expect: ExpectHandler,
@@ -85,6 +102,115 @@ where
         self
     }
 
+    /// Set the maximum size, in bytes, of request payloads.
+    ///
+    /// Requests with a `Content-Length` over the limit are rejected with a `413 Payload Too Large`
+    /// response before any of the payload is read. Payloads without a known length (e.g., using
+    /// chunked transfer encoding) are read until they exceed the limit, at which point the payload
+    /// stream yields [`PayloadError::Overflow`](crate::error::PayloadError::Overflow) to the
+    /// handler and the connection is closed once its response has been sent.
+    ///
+    /// This complements the per-extractor limits in frameworks, protecting handlers that read the
+    /// raw payload stream.
+    ///
+    /// By default, payload size is not limited.
+    pub fn max_payload_size(mut self, limit: usize) -> Self {
+        self.max_payload_size = Some(limit);
+        self
+    }
+
+    /// Set the size, in bytes, that the HTTP/1 response write buffer can reach before it is flushed.
+    ///
+    /// Response heads and body chunks are collected in the write buffer and flushed once it reaches
+    /// this size or the body has no more data ready. Pipelined responses share the buffer too, so a
+    /// batch of small responses can go out in a single write call. Larger values mean fewer writes
+    /// for big or streaming bodies at the cost of more memory per connection.
+    ///
+    /// By default, the write buffer is flushed at 32 KiB.
+    pub fn write_buffer_size(mut self, size: usize) -> Self {
+        self.write_buffer_size = size;
+        self
+    }
+
+    /// Set what to send in the `Server` header of responses.
+    ///
+    /// By default, a `Server` header is only sent if the response sets one.
+    pub fn server_header(mut self, server_header: ServerHeader) -> Self {
+        self.server_header = server_header;
+        self
+    }
+
+    /// Set the maximum length, in bytes, of HTTP/1 request lines.
+    ///
+    /// Requests whose request line (method, target and version) is longer are rejected with
+    /// `414 URI Too Long`. The line terminator is not counted.
+    ///
+    /// By default, request lines are only limited by the total size of the request head.
+    pub fn max_request_line_length(mut self, limit: usize) -> Self {
+        self.max_request_line = Some(limit);
+        self
+    }
+
+    /// Set the maximum length, in bytes, of individual HTTP/1 request header lines.
+    ///
+    /// Requests with a longer header line (name, colon and value) are rejected with
+    /// `431 Request Header Fields Too Large`. The line terminator is not counted.
+    ///
+    /// By default, header lines are only limited by the total size of the request head.
+    pub fn max_header_size(mut self, limit: usize) -> Self {
+        self.max_header_size = Some(limit);
+        self
+    }
+
+    /// Set the maximum number of HTTP/1 request headers.
+    ///
+    /// Requests with more headers are rejected with `431 Request Header Fields Too Large`.
+    ///
+    /// By default, up to 96 headers are accepted.
+    pub fn max_header_count(mut self, limit: usize) -> Self {
+        self.max_header_count = limit;
+        self
+    }
+
+    /// Set a wire tap, given a copy of the raw bytes of each HTTP/1 connection.
+    ///
+    /// For each new connection, `tap` is called with the peer address and returns a callback that
+    /// receives the connection's [`TapEvent`](crate::TapEvent)s: the bytes read from and written
+    /// to the client, exactly as they appear on the wire (after TLS decryption), followed by a
+    /// final event when the connection closes or is upgraded. WebSocket traffic carried over the
+    /// HTTP/1 connection is included as raw frames, which can be parsed with the `ws` module's
+    /// codec. HTTP/2 connections are not tapped.
+    ///
+    /// The callback runs synchronously in the dispatcher, so it should only copy the bytes, e.g.
+    /// into a log or a snapshot buffer. This is meant for debugging protocol issues and recording
+    /// traffic in tests; only available with the `wire-tap` feature.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use actix_http::{HttpService, Response, TapEvent};
+    ///
+    /// let factory = HttpService::build()
+    ///     .wire_tap(|peer_addr| {
+    ///         move |event: TapEvent<'_>| match event {
+    ///             TapEvent::Inbound(bytes) => eprintln!("{:?} <- {:?}", peer_addr, bytes),
+    ///             TapEvent::Outbound(bytes) => eprintln!("{:?} -> {:?}", peer_addr, bytes),
+    ///             _ => eprintln!("{:?} closed", peer_addr),
+    ///         }
+    ///     })
+    ///     .finish(|_req| async { Ok::<_, std::convert::Infallible>(Response::ok()) })
+    ///     .tcp();
+    /// # drop(factory);
+    /// ```
+    #[cfg(feature = "wire-tap")]
+    pub fn wire_tap<F, C>(mut self, tap: F) -> Self
+    where
+        F: Fn(Option<net::SocketAddr>) -> C + 'static,
+        C: FnMut(crate::TapEvent<'_>) + 'static,
+    {
+        self.wire_tap = Some(crate::tap::WireTap::new(tap));
+        self
+    }
+
     /// Set client request timeout (for first request).
     ///
     /// Defines a timeout for reading client request header. If the client does not transmit the
@@ -142,6 +268,14 @@ where
             client_disconnect_timeout: self.client_disconnect_timeout,
             secure: self.secure,
             local_addr: self.local_addr,
+            max_payload_size: self.max_payload_size,
+            write_buffer_size: self.write_buffer_size,
+            server_header: self.server_header,
+            max_request_line: self.max_request_line,
+            max_header_size: self.max_header_size,
+            max_header_count: self.max_header_count,
+            #[cfg(feature = "wire-tap")]
+            wire_tap: self.wire_tap,
             expect: expect.into_factory(),
             upgrade: self.upgrade,
             on_connect_ext: self.on_connect_ext,
@@ -166,6 +300,14 @@ where
             client_disconnect_timeout: self.client_disconnect_timeout,
             secure: self.secure,
             local_addr: self.local_addr,
+            max_payload_size: self.max_payload_size,
+            write_buffer_size: self.write_buffer_size,
+            server_header: self.server_header,
+            max_request_line: self.max_request_line,
+            max_header_size: self.max_header_size,
+            max_header_count: self.max_header_count,
+            #[cfg(feature = "wire-tap")]
+            wire_tap: self.wire_tap,
             expect: self.expect,
             upgrade: Some(upgrade.into_factory()),
             on_connect_ext: self.on_connect_ext,
@@ -201,7 +343,16 @@ where
             self.client_disconnect_timeout,
             self.secure,
             self.local_addr,
-        );
+        )
+        .with_max_payload_size(self.max_payload_size)
+        .with_write_buffer_size(self.write_buffer_size)
+        .with_server_header(self.server_header)
+        .with_max_request_line(self.max_request_line)
+        .with_max_header_size(self.max_header_size)
+        .with_max_header_count(self.max_header_count);
+
+        #[cfg(feature = "wire-tap")]
+        let cfg = cfg.with_wire_tap(self.wire_tap);
 
         H1Service::with_config(cfg, service.into_factory())
             .expect(self.expect)
@@ -226,7 +377,16 @@ where
             self.client_disconnect_timeout,
             self.secure,
             self.local_addr,
-        );
+        )
+        .with_max_payload_size(self.max_payload_size)
+        .with_write_buffer_size(self.write_buffer_size)
+        .with_server_header(self.server_header)
+        .with_max_request_line(self.max_request_line)
+        .with_max_header_size(self.max_header_size)
+        .with_max_header_count(self.max_header_count);
+
+        #[cfg(feature = "wire-tap")]
+        let cfg = cfg.with_wire_tap(self.wire_tap);
 
         crate::h2::H2Service::with_config(cfg, service.into_factory())
             .on_connect_ext(self.on_connect_ext)
@@ -248,7 +408,16 @@ where
             self.client_disconnect_timeout,
             self.secure,
             self.local_addr,
-        );
+        )
+        .with_max_payload_size(self.max_payload_size)
+        .with_write_buffer_size(self.write_buffer_size)
+        .with_server_header(self.server_header)
+        .with_max_request_line(self.max_request_line)
+        .with_max_header_size(self.max_header_size)
+        .with_max_header_count(self.max_header_count);
+
+        #[cfg(feature = "wire-tap")]
+        let cfg = cfg.with_wire_tap(self.wire_tap);
 
         HttpService::with_config(cfg, service.into_factory())
             .expect(self.expect)
@@ -6,7 +6,32 @@ use std::{
 
 use bytes::BytesMut;
 
-use crate::{date::DateService, KeepAlive};
+use crate::{date::DateService, header::HeaderValue, KeepAlive};
+
+/// Default size, in bytes, that the response write buffer can reach before it is flushed.
+pub(crate) const DEFAULT_WRITE_BUFFER_SIZE: usize = 32_768;
+
+/// What to send in the `Server` header of responses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerHeader {
+    /// Sends a `Server` header only if the response sets one.
+    ///
+    /// This is the default.
+    Passthrough,
+
+    /// Sends the given value in the `Server` header of responses that do not set one, including
+    /// error responses generated by the dispatcher.
+    Default(HeaderValue),
+
+    /// Never sends a `Server` header, removing it from responses that set one.
+    Suppress,
+}
+
+impl Default for ServerHeader {
+    fn default() -> Self {
+        ServerHeader::Passthrough
+    }
+}
 
 /// HTTP service configuration.
 #[derive(Debug, Clone)]
@@ -19,6 +44,14 @@ struct Inner {
     client_disconnect_timeout: Duration,
     secure: bool,
     local_addr: Option<std::net::SocketAddr>,
+    max_payload_size: Option<usize>,
+    write_buffer_size: usize,
+    server_header: ServerHeader,
+    max_request_line: Option<usize>,
+    max_header_size: Option<usize>,
+    max_header_count: usize,
+    #[cfg(feature = "wire-tap")]
+    wire_tap: Option<crate::tap::WireTap>,
     date_service: DateService,
 }
 
@@ -49,10 +82,75 @@ impl ServiceConfig {
             client_disconnect_timeout,
             secure,
             local_addr,
+            max_payload_size: None,
+            write_buffer_size: DEFAULT_WRITE_BUFFER_SIZE,
+            server_header: ServerHeader::Passthrough,
+            max_request_line: None,
+            max_header_size: None,
+            max_header_count: crate::h1::MAX_HEADERS,
+            #[cfg(feature = "wire-tap")]
+            wire_tap: None,
             date_service: DateService::new(),
         }))
     }
 
+    /// Sets the maximum request payload size, in bytes, accepted by the dispatcher.
+    pub(crate) fn with_max_payload_size(mut self, limit: Option<usize>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("ServiceConfig must be configured before it is shared")
+            .max_payload_size = limit;
+        self
+    }
+
+    /// Sets the size, in bytes, that the response write buffer can reach before it is flushed.
+    pub(crate) fn with_write_buffer_size(mut self, size: usize) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("ServiceConfig must be configured before it is shared")
+            .write_buffer_size = size;
+        self
+    }
+
+    /// Sets what to send in the `Server` header of responses.
+    pub(crate) fn with_server_header(mut self, server_header: ServerHeader) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("ServiceConfig must be configured before it is shared")
+            .server_header = server_header;
+        self
+    }
+
+    /// Sets the maximum length, in bytes, of HTTP/1 request lines.
+    pub(crate) fn with_max_request_line(mut self, limit: Option<usize>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("ServiceConfig must be configured before it is shared")
+            .max_request_line = limit;
+        self
+    }
+
+    /// Sets the maximum length, in bytes, of HTTP/1 request header lines.
+    pub(crate) fn with_max_header_size(mut self, limit: Option<usize>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("ServiceConfig must be configured before it is shared")
+            .max_header_size = limit;
+        self
+    }
+
+    /// Sets the maximum number of HTTP/1 request headers.
+    pub(crate) fn with_max_header_count(mut self, limit: usize) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("ServiceConfig must be configured before it is shared")
+            .max_header_count = limit;
+        self
+    }
+
+    /// Sets the wire tap given a copy of HTTP/1 connection traffic.
+    #[cfg(feature = "wire-tap")]
+    pub(crate) fn with_wire_tap(mut self, wire_tap: Option<crate::tap::WireTap>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("ServiceConfig must be configured before it is shared")
+            .wire_tap = wire_tap;
+        self
+    }
+
     /// Returns `true` if connection is secure (i.e., using TLS / HTTPS).
     #[inline]
     pub fn secure(&self) -> bool {
@@ -67,6 +165,76 @@ impl ServiceConfig {
         self.0.local_addr
     }
 
+    /// Maximum request payload size, in bytes, if limited.
+    ///
+    /// Requests with a larger `Content-Length` are rejected with `413 Payload Too Large` before
+    /// their payload is read, and payloads without a known length are aborted with
+    /// [`PayloadError::Overflow`](crate::error::PayloadError::Overflow) once they exceed it.
+    #[inline]
+    pub fn max_payload_size(&self) -> Option<usize> {
+        self.0.max_payload_size
+    }
+
+    /// Size, in bytes, that the HTTP/1 response write buffer can reach before it is flushed.
+    ///
+    /// Response heads and body chunks are collected in the buffer until it reaches this size or
+    /// the body has no more data ready, so small responses are written with a single write call.
+    #[inline]
+    pub fn write_buffer_size(&self) -> usize {
+        self.0.write_buffer_size
+    }
+
+    /// What to send in the `Server` header of responses.
+    #[inline]
+    pub fn server_header(&self) -> &ServerHeader {
+        &self.0.server_header
+    }
+
+    /// Maximum length, in bytes, of HTTP/1 request lines, if limited.
+    ///
+    /// Requests with a longer request line, excluding its line terminator, are rejected with
+    /// `414 URI Too Long`.
+    #[inline]
+    pub fn max_request_line(&self) -> Option<usize> {
+        self.0.max_request_line
+    }
+
+    /// Maximum length, in bytes, of individual HTTP/1 request header lines, if limited.
+    ///
+    /// Requests with a longer header line, excluding its line terminator, are rejected with
+    /// `431 Request Header Fields Too Large`.
+    #[inline]
+    pub fn max_header_size(&self) -> Option<usize> {
+        self.0.max_header_size
+    }
+
+    /// Maximum number of HTTP/1 request headers.
+    ///
+    /// Requests with more headers are rejected with `431 Request Header Fields Too Large`.
+    #[inline]
+    pub fn max_header_count(&self) -> usize {
+        self.0.max_header_count
+    }
+
+    /// Wire tap given a copy of HTTP/1 connection traffic, if any.
+    #[cfg(feature = "wire-tap")]
+    pub(crate) fn wire_tap(&self) -> Option<&crate::tap::WireTap> {
+        self.0.wire_tap.as_ref()
+    }
+
+    /// Returns true if a request's `Content-Length` value is larger than the maximum payload size.
+    pub(crate) fn exceeds_payload_limit(&self, content_length: Option<&HeaderValue>) -> bool {
+        let limit = match self.0.max_payload_size {
+            Some(limit) => limit as u64,
+            None => return false,
+        };
+
+        content_length
+            .and_then(|len| len.to_str().ok())
+            .and_then(|len| len.trim().parse::<u64>().ok())
+            .map_or(false, |len| len > limit)
+    }
+
     /// Connection keep-alive setting.
     #[inline]
     pub fn keep_alive(&self) -> KeepAlive {
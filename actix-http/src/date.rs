@@ -10,9 +10,20 @@ use actix_rt::{task::JoinHandle, time::interval};
 /// "Thu, 01 Jan 1970 00:00:00 GMT".len()
 pub(crate) const DATE_VALUE_LENGTH: usize = 29;
 
+thread_local! {
+    /// Number of date services running on this thread and the latest date one of them formatted.
+    static LATEST: Cell<(usize, Option<Date>)> = Cell::new((0, None));
+}
+
+/// Returns the latest date formatted by a date service running on this thread, if any.
+pub(crate) fn cached_date() -> Option<Date> {
+    LATEST.with(|latest| latest.get().1)
+}
+
 #[derive(Clone, Copy)]
 pub(crate) struct Date {
     pub(crate) bytes: [u8; DATE_VALUE_LENGTH],
+    pub(crate) time: SystemTime,
     pos: usize,
 }
 
@@ -20,6 +31,7 @@ impl Date {
     fn new() -> Date {
         let mut date = Date {
             bytes: [0; DATE_VALUE_LENGTH],
+            time: SystemTime::now(),
             pos: 0,
         };
         date.update();
@@ -28,7 +40,7 @@ impl Date {
 
     fn update(&mut self) {
         self.pos = 0;
-        write!(self, "{}", httpdate::fmt_http_date(SystemTime::now())).unwrap();
+        write!(self, "{}", httpdate::fmt_http_date(self.time)).unwrap();
     }
 }
 
@@ -42,6 +54,8 @@ impl fmt::Write for Date {
 }
 
 /// Service for update Date and Instant periodically at 500 millis interval.
+///
+/// The latest date is also published to a thread local, see [`cached_date`].
 pub(crate) struct DateService {
     current: Rc<Cell<(Date, Instant)>>,
     handle: JoinHandle<()>,
@@ -50,7 +64,10 @@ pub(crate) struct DateService {
 impl DateService {
     pub(crate) fn new() -> Self {
         // shared date and timer for DateService and update async task.
-        let current = Rc::new(Cell::new((Date::new(), Instant::now())));
+        let date = Date::new();
+        let current = Rc::new(Cell::new((date, Instant::now())));
+        LATEST.with(|latest| latest.set((latest.get().0 + 1, Some(date))));
+
         let current_clone = Rc::clone(&current);
         // spawn an async task sleep for 500 millis and update current date/timer in a loop.
         // handle is used to stop the task on DateService drop.
@@ -63,6 +80,7 @@ impl DateService {
                 let now = interval.tick().await;
                 let date = Date::new();
                 current_clone.set((date, now.into_std()));
+                LATEST.with(|latest| latest.set((latest.get().0, Some(date))));
             }
         });
 
@@ -88,5 +106,12 @@ impl Drop for DateService {
     fn drop(&mut self) {
         // stop the timer update async task on drop.
         self.handle.abort();
+
+        // the published date would go stale without any running date service
+        LATEST.with(|latest| {
+            let (count, date) = latest.get();
+            let count = count.saturating_sub(1);
+            latest.set((count, if count == 0 { None } else { date }));
+        });
     }
 }
@@ -149,6 +149,13 @@ impl From<crate::ws::HandshakeError> for Error {
     }
 }
 
+#[cfg(feature = "ws")]
+impl From<crate::ws::OriginHandshakeError> for Error {
+    fn from(err: crate::ws::OriginHandshakeError) -> Self {
+        Self::new_ws().with_cause(err)
+    }
+}
+
 #[cfg(feature = "ws")]
 impl From<crate::ws::ProtocolError> for Error {
     fn from(err: crate::ws::ProtocolError) -> Self {
@@ -180,6 +187,10 @@ pub enum ParseError {
     #[display(fmt = "Message head is too large")]
     TooLarge,
 
+    /// A request line is longer than the configured limit.
+    #[display(fmt = "URI is too long")]
+    UriTooLong,
+
     /// A message reached EOF, but is not complete.
     #[display(fmt = "Message is incomplete")]
     Incomplete,
@@ -365,6 +376,10 @@ pub enum DispatchError {
     #[display(fmt = "Handler dropped payload before reading EOF")]
     HandlerDroppedPayload,
 
+    /// Request payload is larger than the configured maximum payload size.
+    #[display(fmt = "Request payload exceeded the maximum payload size")]
+    PayloadTooLarge,
+
     /// Internal error.
     #[display(fmt = "Internal error")]
     InternalError,
@@ -400,6 +415,41 @@ pub enum ContentTypeError {
     UnknownEncoding,
 }
 
+/// A set of errors that can occur when manually set headers conflict with the framing of a
+/// message body.
+#[derive(Debug, Display, Error)]
+#[cfg_attr(test, derive(PartialEq))]
+#[non_exhaustive]
+pub enum FramingError {
+    /// The `Content-Length` header is not a valid body length.
+    #[display(fmt = "Content-Length header is not a valid body length")]
+    InvalidContentLength,
+
+    /// Both the `Content-Length` and `Transfer-Encoding` headers are set.
+    #[display(fmt = "Content-Length and Transfer-Encoding headers are both set")]
+    ContentLengthWithTransferEncoding,
+
+    /// The `Content-Length` header does not match the size of the body.
+    #[display(
+        fmt = "Content-Length header ({}) does not match body size ({})",
+        header,
+        body
+    )]
+    ContentLengthMismatch {
+        /// Length set in the `Content-Length` header.
+        header: u64,
+
+        /// Size of the body.
+        body: u64,
+    },
+}
+
+impl From<FramingError> for Error {
+    fn from(err: FramingError) -> Self {
+        Self::new_http().with_cause(err)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::io;
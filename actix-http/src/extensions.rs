@@ -83,6 +83,46 @@ impl Extensions {
             .and_then(|boxed| boxed.downcast_mut())
     }
 
+    /// Inserts the given `value` into the extensions if it is not present, then returns a mutable
+    /// reference to the value in the extensions.
+    ///
+    /// ```
+    /// # use actix_http::Extensions;
+    /// let mut map = Extensions::new();
+    /// assert_eq!(map.get::<Vec<u32>>(), None);
+    ///
+    /// map.get_or_insert(Vec::<u32>::new()).push(1);
+    /// assert_eq!(map.get::<Vec<u32>>(), Some(&vec![1]));
+    ///
+    /// map.get_or_insert(Vec::<u32>::new()).push(2);
+    /// assert_eq!(map.get::<Vec<u32>>(), Some(&vec![1,2]));
+    /// ```
+    pub fn get_or_insert<T: 'static>(&mut self, value: T) -> &mut T {
+        self.get_or_insert_with(|| value)
+    }
+
+    /// Inserts a value computed from `default` into the extensions if it is not present, then
+    /// returns a mutable reference to the value in the extensions.
+    ///
+    /// ```
+    /// # use actix_http::Extensions;
+    /// let mut map = Extensions::new();
+    /// assert_eq!(map.get::<Vec<u32>>(), None);
+    ///
+    /// map.get_or_insert_with(Vec::<u32>::new).push(1);
+    /// assert_eq!(map.get::<Vec<u32>>(), Some(&vec![1]));
+    ///
+    /// map.get_or_insert_with(Vec::<u32>::new).push(2);
+    /// assert_eq!(map.get::<Vec<u32>>(), Some(&vec![1,2]));
+    /// ```
+    pub fn get_or_insert_with<T: 'static, F: FnOnce() -> T>(&mut self, default: F) -> &mut T {
+        self.map
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(default()))
+            .downcast_mut()
+            .expect("extensions map should now contain a T value")
+    }
+
     /// Remove an item from the map of a given type.
     ///
     /// If an item of this type was already stored, it will be returned.
@@ -149,6 +189,21 @@ mod tests {
         assert!(map.get::<i8>().is_none());
     }
 
+    #[test]
+    fn test_get_or_insert() {
+        let mut map = Extensions::new();
+
+        *map.get_or_insert(1u32) += 1;
+        assert_eq!(map.get::<u32>(), Some(&2));
+
+        // existing value is kept
+        assert_eq!(*map.get_or_insert(10u32), 2);
+        assert_eq!(*map.get_or_insert_with(|| -> u32 { unreachable!() }), 2);
+
+        assert_eq!(*map.get_or_insert_with(|| 8i8), 8);
+        assert_eq!(map.get::<u32>(), Some(&2));
+    }
+
     #[test]
     fn test_clear() {
         let mut map = Extensions::new();
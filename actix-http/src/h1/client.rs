@@ -145,7 +145,8 @@ impl Decoder for ClientCodec {
 
             if !self.inner.flags.contains(Flags::HEAD) {
                 match payload {
-                    PayloadType::None => self.inner.payload = None,
+                    // responses never offer upgrades
+                    PayloadType::None | PayloadType::UpgradeOffer => self.inner.payload = None,
                     PayloadType::Payload(pl) => self.inner.payload = Some(pl),
                     PayloadType::Stream(pl) => {
                         self.inner.payload = Some(pl);
@@ -2,12 +2,13 @@ use std::{fmt, io};
 
 use actix_codec::{Decoder, Encoder};
 use bitflags::bitflags;
-use bytes::BytesMut;
-use http::{Method, Version};
+use bytes::{Bytes, BytesMut};
+use http::{Method, StatusCode, Version};
 
 use super::{
     decoder::{self, PayloadDecoder, PayloadItem, PayloadType},
-    encoder, Message, MessageType,
+    encoder::{self, WriteQueue},
+    Message, MessageType,
 };
 use crate::{
     body::BodySize, error::ParseError, ConnectionType, Request, Response, ServiceConfig,
@@ -18,6 +19,8 @@ bitflags! {
         const HEAD               = 0b0000_0001;
         const KEEP_ALIVE_ENABLED = 0b0000_0010;
         const STREAM             = 0b0000_0100;
+        const UPGRADE_PENDING    = 0b0000_1000;
+        const UPGRADE_DECLINED   = 0b0001_0000;
     }
 }
 
@@ -59,10 +62,16 @@ impl Codec {
             Flags::empty()
         };
 
+        let limits = decoder::HeadLimits {
+            max_request_line: config.max_request_line(),
+            max_header_size: config.max_header_size(),
+            max_header_count: config.max_header_count(),
+        };
+
         Codec {
             config,
             flags,
-            decoder: decoder::MessageDecoder::default(),
+            decoder: decoder::MessageDecoder::new(limits),
             payload: None,
             version: Version::HTTP_11,
             conn_type: ConnectionType::Close,
@@ -93,17 +102,51 @@ impl Codec {
     pub fn message_type(&self) -> MessageType {
         if self.flags.contains(Flags::STREAM) {
             MessageType::Stream
-        } else if self.payload.is_none() {
+        } else if self.payload.is_none() && !self.flags.contains(Flags::UPGRADE_PENDING) {
             MessageType::None
         } else {
             MessageType::Payload
         }
     }
 
+    /// Ends the payload of a request offering a protocol upgrade before its response is sent.
+    ///
+    /// Used when the service drops the payload, since it can then not use the upgraded connection.
+    pub(super) fn decline_upgrade(&mut self) {
+        if !self.flags.contains(Flags::UPGRADE_PENDING) {
+            return;
+        }
+
+        self.flags.insert(Flags::UPGRADE_DECLINED);
+
+        // the connection stays usable for further requests
+        if self.conn_type == ConnectionType::Upgrade {
+            self.conn_type = if self.version >= Version::HTTP_11
+                && self.flags.contains(Flags::KEEP_ALIVE_ENABLED)
+            {
+                ConnectionType::KeepAlive
+            } else {
+                ConnectionType::Close
+            };
+        }
+    }
+
     #[inline]
     pub fn config(&self) -> &ServiceConfig {
         &self.config
     }
+
+    /// Encodes a body chunk for a vectored write, queueing it behind the contents of `dst` instead
+    /// of copying it.
+    pub(crate) fn encode_chunk_vectored(
+        &mut self,
+        chunk: Bytes,
+        dst: &mut BytesMut,
+        queue: &mut WriteQueue,
+    ) -> io::Result<()> {
+        self.encoder.encode_chunk_vectored(chunk, dst, queue)?;
+        Ok(())
+    }
 }
 
 impl Decoder for Codec {
@@ -111,7 +154,17 @@ impl Decoder for Codec {
     type Error = ParseError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        if let Some(ref mut payload) = self.payload {
+        if self.flags.contains(Flags::UPGRADE_PENDING) {
+            // whether the rest of the connection is the payload of the request or the next request
+            // is only known once the response is sent
+            if self.flags.contains(Flags::UPGRADE_DECLINED) {
+                self.flags
+                    .remove(Flags::UPGRADE_PENDING | Flags::UPGRADE_DECLINED);
+                Ok(Some(Message::Chunk(None)))
+            } else {
+                Ok(None)
+            }
+        } else if let Some(ref mut payload) = self.payload {
             Ok(match payload.decode(src)? {
                 Some(PayloadItem::Chunk(chunk)) => Some(Message::Chunk(Some(chunk))),
                 Some(PayloadItem::Eof) => {
@@ -139,6 +192,10 @@ impl Decoder for Codec {
                     self.payload = Some(pl);
                     self.flags.insert(Flags::STREAM);
                 }
+                PayloadType::UpgradeOffer => {
+                    self.payload = None;
+                    self.flags.insert(Flags::UPGRADE_PENDING);
+                }
             }
             Ok(Some(Message::Item(req)))
         } else {
@@ -160,6 +217,20 @@ impl Encoder<Message<(Response<()>, BodySize)>> for Codec {
                 // set response version
                 res.head_mut().version = self.version;
 
+                // a pending upgrade is accepted by switching protocols; the rest of the connection
+                // is then streamed to the request payload, otherwise the request payload is empty
+                if self.flags.contains(Flags::UPGRADE_PENDING)
+                    && !self.flags.contains(Flags::UPGRADE_DECLINED)
+                {
+                    if res.status() == StatusCode::SWITCHING_PROTOCOLS {
+                        self.flags.remove(Flags::UPGRADE_PENDING);
+                        self.flags.insert(Flags::STREAM);
+                        self.payload = Some(PayloadDecoder::eof());
+                    } else {
+                        self.decline_upgrade();
+                    }
+                }
+
                 // connection status
                 self.conn_type = if let Some(ct) = res.head().conn_type() {
                     if ct == ConnectionType::KeepAlive {
@@ -171,6 +242,16 @@ impl Encoder<Message<(Response<()>, BodySize)>> for Codec {
                     self.conn_type
                 };
 
+                // HTTP/1.0 clients can not receive chunked bodies so streaming bodies are delimited
+                // by closing the connection instead
+                if self.version < Version::HTTP_11
+                    && length == BodySize::Stream
+                    && !self.flags.contains(Flags::HEAD)
+                    && self.conn_type != ConnectionType::Upgrade
+                {
+                    self.conn_type = ConnectionType::Close;
+                }
+
                 // encode message
                 self.encoder.encode(
                     dst,
@@ -241,4 +322,86 @@ mod tests {
         assert_eq!(*req.method(), Method::POST);
         assert!(req.chunked().unwrap());
     }
+
+    #[actix_rt::test]
+    async fn test_upgrade_offer() {
+        let mut codec = Codec::default();
+
+        // declined upgrades keep the connection framing
+        let mut buf = BytesMut::from(
+            "GET /test HTTP/1.1\r\n\
+             connection: upgrade\r\n\
+             upgrade: irc/1.0\r\n\r\n\
+             GET /next HTTP/1.1\r\n\r\n",
+        );
+        codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(codec.message_type(), MessageType::Payload);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+
+        let mut out = BytesMut::new();
+        codec
+            .encode(
+                Message::Item((Response::ok().drop_body(), BodySize::Sized(0))),
+                &mut out,
+            )
+            .unwrap();
+        assert!(codec.keep_alive());
+
+        assert!(codec.decode(&mut buf).unwrap().unwrap().eof());
+        let item = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(item.message().path(), "/next");
+        assert_eq!(codec.message_type(), MessageType::None);
+
+        // switching protocols streams the rest of the connection
+        let mut buf = BytesMut::from(
+            "GET /test HTTP/1.1\r\n\
+             connection: upgrade\r\n\
+             upgrade: irc/1.0\r\n\r\n\
+             NICK actix\r\n",
+        );
+        codec.decode(&mut buf).unwrap().unwrap();
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+
+        let mut res = Response::with_body(StatusCode::SWITCHING_PROTOCOLS, ());
+        res.head_mut().set_connection_type(ConnectionType::Upgrade);
+        codec
+            .encode(Message::Item((res, BodySize::Stream)), &mut out)
+            .unwrap();
+
+        let msg = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(msg.chunk().as_ref(), b"NICK actix\r\n");
+    }
+
+    #[actix_rt::test]
+    async fn test_http10_stream_response() {
+        let mut codec = Codec::default();
+
+        let mut buf = BytesMut::from(
+            "GET /test HTTP/1.0\r\n\
+             connection: keep-alive\r\n\r\n",
+        );
+        codec.decode(&mut buf).unwrap().unwrap();
+        assert!(codec.keep_alive());
+
+        let mut buf = BytesMut::new();
+        codec
+            .encode(
+                Message::Item((Response::ok().drop_body(), BodySize::Stream)),
+                &mut buf,
+            )
+            .unwrap();
+        codec
+            .encode(Message::Chunk(Some("data".into())), &mut buf)
+            .unwrap();
+        codec.encode(Message::Chunk(None), &mut buf).unwrap();
+
+        let data = String::from_utf8(buf.to_vec()).unwrap();
+        assert!(data.starts_with("HTTP/1.0 200 OK\r\n"), "{}", data);
+        assert!(!data.contains("transfer-encoding"), "{}", data);
+        assert!(!data.contains("connection: keep-alive"), "{}", data);
+        assert!(data.ends_with("\r\n\r\ndata"), "{}", data);
+
+        // body is delimited by closing the connection
+        assert!(!codec.keep_alive());
+    }
 }
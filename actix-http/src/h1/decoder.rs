@@ -1,4 +1,4 @@
-use std::{convert::TryFrom, io, marker::PhantomData, mem::MaybeUninit, task::Poll};
+use std::{convert::TryFrom, io, iter, marker::PhantomData, mem::MaybeUninit, task::Poll};
 
 use actix_codec::Decoder;
 use bytes::{Bytes, BytesMut};
@@ -12,10 +12,108 @@ use super::chunked::ChunkedState;
 use crate::{error::ParseError, header::HeaderMap, ConnectionType, Request, ResponseHead};
 
 pub(crate) const MAX_BUFFER_SIZE: usize = 131_072;
-const MAX_HEADERS: usize = 96;
+pub(crate) const MAX_HEADERS: usize = 96;
+
+/// Limits on the head of incoming requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct HeadLimits {
+    /// Maximum length of the request line, excluding the line terminator.
+    pub(crate) max_request_line: Option<usize>,
+
+    /// Maximum length of a header field line, excluding the line terminator.
+    pub(crate) max_header_size: Option<usize>,
+
+    /// Maximum number of header fields.
+    pub(crate) max_header_count: usize,
+}
+
+impl Default for HeadLimits {
+    fn default() -> Self {
+        HeadLimits {
+            max_request_line: None,
+            max_header_size: None,
+            max_header_count: MAX_HEADERS,
+        }
+    }
+}
+
+/// Progress of the [`HeadLimits`] checks through a partially received request head.
+///
+/// Bytes are only scanned once, as they are received, instead of re-checking the whole head each
+/// time more of it arrives.
+#[derive(Debug, Default)]
+pub(crate) struct HeadScan {
+    /// Number of bytes of the head that have been scanned.
+    scanned: usize,
+
+    /// Offset of the start of the current, incomplete line.
+    line_start: usize,
+
+    /// Number of complete lines, including the request line.
+    lines: usize,
+}
+
+impl HeadScan {
+    /// Checks the lines of a complete or partial message head, scanning only the bytes received
+    /// since the last check.
+    fn check(&mut self, limits: &HeadLimits, head: &[u8]) -> Result<(), ParseError> {
+        if limits.max_request_line.is_none() && limits.max_header_size.is_none() {
+            return Ok(());
+        }
+
+        if self.scanned > head.len() {
+            // buffer does not continue the previously scanned head
+            *self = HeadScan::default();
+        }
+
+        while let Some(pos) = head[self.scanned..].iter().position(|&b| b == b'\n') {
+            let end = self.scanned + pos;
+            self.check_line(limits, &head[self.line_start..end])?;
+
+            self.lines += 1;
+            self.scanned = end + 1;
+            self.line_start = self.scanned;
+        }
+
+        self.scanned = head.len();
+        self.check_line(limits, &head[self.line_start..])
+    }
+
+    /// Checks the length of the current line against the limit for its position in the head.
+    fn check_line(&self, limits: &HeadLimits, line: &[u8]) -> Result<(), ParseError> {
+        let len = line.strip_suffix(b"\r").unwrap_or(line).len();
+
+        if self.lines == 0 {
+            match limits.max_request_line {
+                Some(max) if len > max => Err(ParseError::UriTooLong),
+                _ => Ok(()),
+            }
+        } else {
+            match limits.max_header_size {
+                Some(max) if len > max => Err(ParseError::TooLarge),
+                _ => Ok(()),
+            }
+        }
+    }
+}
 
 /// Incoming message decoder
-pub(crate) struct MessageDecoder<T: MessageType>(PhantomData<T>);
+pub(crate) struct MessageDecoder<T: MessageType> {
+    limits: HeadLimits,
+    scan: HeadScan,
+    _message: PhantomData<T>,
+}
+
+impl<T: MessageType> MessageDecoder<T> {
+    /// Constructs a decoder enforcing the given request head limits.
+    pub(crate) fn new(limits: HeadLimits) -> Self {
+        MessageDecoder {
+            limits,
+            scan: HeadScan::default(),
+            _message: PhantomData,
+        }
+    }
+}
 
 #[derive(Debug)]
 /// Incoming request type
@@ -23,11 +121,15 @@ pub(crate) enum PayloadType {
     None,
     Payload(PayloadDecoder),
     Stream(PayloadDecoder),
+
+    /// Request without a body that asks to upgrade to another protocol; the connection only
+    /// switches to a stream if the response is `101 Switching Protocols`.
+    UpgradeOffer,
 }
 
 impl<T: MessageType> Default for MessageDecoder<T> {
     fn default() -> Self {
-        MessageDecoder(PhantomData)
+        MessageDecoder::new(HeadLimits::default())
     }
 }
 
@@ -36,13 +138,14 @@ impl<T: MessageType> Decoder for MessageDecoder<T> {
     type Error = ParseError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        T::decode(src)
+        T::decode(src, &self.limits, &mut self.scan)
     }
 }
 
 pub(crate) enum PayloadLength {
     Payload(PayloadType),
-    UpgradeWebSocket,
+    Upgrade,
+    UpgradeOffer,
     None,
 }
 
@@ -53,7 +156,11 @@ pub(crate) trait MessageType: Sized {
 
     fn headers_mut(&mut self) -> &mut HeaderMap;
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError>;
+    fn decode(
+        src: &mut BytesMut,
+        limits: &HeadLimits,
+        scan: &mut HeadScan,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError>;
 
     fn set_headers(
         &mut self,
@@ -62,6 +169,8 @@ pub(crate) trait MessageType: Sized {
     ) -> Result<PayloadLength, ParseError> {
         let mut ka = None;
         let mut has_upgrade_websocket = false;
+        let mut has_upgrade = false;
+        let mut conn_upgrade = false;
         let mut expect = false;
         let mut chunked = false;
         let mut seen_te = false;
@@ -132,6 +241,12 @@ pub(crate) trait MessageType: Sized {
                     }
                     // connection keep-alive state
                     header::CONNECTION => {
+                        if let Ok(conn) = value.to_str() {
+                            conn_upgrade |= conn
+                                .split(',')
+                                .any(|token| token.trim().eq_ignore_ascii_case("upgrade"));
+                        }
+
                         ka = if let Ok(conn) = value.to_str().map(str::trim) {
                             if conn.eq_ignore_ascii_case("keep-alive") {
                                 Some(ConnectionType::KeepAlive)
@@ -151,6 +266,12 @@ pub(crate) trait MessageType: Sized {
                             if val.eq_ignore_ascii_case("websocket") {
                                 has_upgrade_websocket = true;
                             }
+
+                            // h2c upgrades are optional for servers and usually offered
+                            // opportunistically, so such requests are handled as normal requests
+                            if !val.is_empty() && !val.eq_ignore_ascii_case("h2c") {
+                                has_upgrade = true;
+                            }
                         }
                     }
                     header::EXPECT => {
@@ -177,12 +298,15 @@ pub(crate) trait MessageType: Sized {
                 PayloadDecoder::chunked(),
             )))
         } else if has_upgrade_websocket {
-            Ok(PayloadLength::UpgradeWebSocket)
+            Ok(PayloadLength::Upgrade)
         } else if let Some(len) = content_length {
             // Content-Length
             Ok(PayloadLength::Payload(PayloadType::Payload(
                 PayloadDecoder::length(len),
             )))
+        } else if has_upgrade && conn_upgrade {
+            // other protocol upgrades, which the service may decline
+            Ok(PayloadLength::UpgradeOffer)
         } else {
             Ok(PayloadLength::None)
         }
@@ -197,6 +321,12 @@ impl MessageType for Request {
     }
 
     fn set_expect(&mut self) {
+        // servers must ignore 100-continue expectations from HTTP/1.0 clients
+        // see https://datatracker.ietf.org/doc/html/rfc7231#section-5.1.1
+        if self.head().version < Version::HTTP_11 {
+            return;
+        }
+
         self.head_mut().set_expect();
     }
 
@@ -204,10 +334,25 @@ impl MessageType for Request {
         &mut self.head_mut().headers
     }
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
-        let mut headers: [HeaderIndex; MAX_HEADERS] = EMPTY_HEADER_INDEX_ARRAY;
+    fn decode(
+        src: &mut BytesMut,
+        limits: &HeadLimits,
+        scan: &mut HeadScan,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
+        let count = limits.max_header_count;
+
+        let mut stack_headers: [HeaderIndex; MAX_HEADERS] = EMPTY_HEADER_INDEX_ARRAY;
+        let mut heap_headers = Vec::new();
 
-        let (len, method, uri, ver, h_len) = {
+        let headers: &mut [HeaderIndex] = if count <= MAX_HEADERS {
+            &mut stack_headers[..count]
+        } else {
+            // more headers are allowed than fit the stack buffers
+            heap_headers.resize(count, EMPTY_HEADER_INDEX);
+            &mut heap_headers
+        };
+
+        let head = if count <= MAX_HEADERS {
             // SAFETY:
             // Create an uninitialized array of `MaybeUninit`. The `assume_init` is safe because the
             // type we are claiming to have initialized here is a bunch of `MaybeUninit`s, which
@@ -217,47 +362,45 @@ impl MessageType for Request {
                     .assume_init()
             };
 
-            let mut req = httparse::Request::new(&mut []);
-
-            match req.parse_with_uninit_headers(src, &mut parsed)? {
-                httparse::Status::Complete(len) => {
-                    let method = Method::from_bytes(req.method.unwrap().as_bytes())
-                        .map_err(|_| ParseError::Method)?;
-                    let uri = Uri::try_from(req.path.unwrap())?;
-                    let version = if req.version.unwrap() == 1 {
-                        Version::HTTP_11
-                    } else {
-                        Version::HTTP_10
-                    };
-                    HeaderIndex::record(src, req.headers, &mut headers);
+            parse_request_head(src, limits, scan, &mut parsed[..count], headers)?
+        } else {
+            let mut parsed = iter::repeat_with(MaybeUninit::uninit)
+                .take(count)
+                .collect::<Vec<_>>();
 
-                    (len, method, uri, version, req.headers.len())
-                }
+            parse_request_head(src, limits, scan, &mut parsed, headers)?
+        };
 
-                httparse::Status::Partial => {
-                    return if src.len() >= MAX_BUFFER_SIZE {
-                        trace!("MAX_BUFFER_SIZE unprocessed data reached, closing");
-                        Err(ParseError::TooLarge)
-                    } else {
-                        // Return None to notify more read are needed for parsing request
-                        Ok(None)
-                    };
-                }
-            }
+        let (len, method, uri, ver, h_len) = match head {
+            Some(head) => head,
+            // Return None to notify more read are needed for parsing request
+            None => return Ok(None),
         };
 
         let mut msg = Request::new();
 
+        // set early so that version dependent header handling can see it
+        msg.head_mut().version = ver;
+
         // convert headers
         let length = msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len])?;
 
+        // HTTP/1.0 has no transfer codings; a Transfer-Encoding header shows faulty framing
+        // see https://datatracker.ietf.org/doc/html/rfc9112#section-6.1
+        if ver == Version::HTTP_10 && msg.head().headers.contains_key(header::TRANSFER_ENCODING)
+        {
+            debug!("Transfer-Encoding is not allowed in HTTP/1.0 requests");
+            return Err(ParseError::Header);
+        }
+
         // payload decoder
         let decoder = match length {
             PayloadLength::Payload(pl) => pl,
-            PayloadLength::UpgradeWebSocket => {
+            PayloadLength::Upgrade => {
                 // upgrade (WebSocket)
                 PayloadType::Stream(PayloadDecoder::eof())
             }
+            PayloadLength::UpgradeOffer => PayloadType::UpgradeOffer,
             PayloadLength::None => {
                 if method == Method::CONNECT {
                     PayloadType::Stream(PayloadDecoder::eof())
@@ -276,6 +419,53 @@ impl MessageType for Request {
     }
 }
 
+/// Parses a request head into `headers`, accepting at most as many headers as `parsed` holds.
+///
+/// Returns the length of the head, its method, URI, version and header count, or `None` if the
+/// head is incomplete.
+fn parse_request_head<'a>(
+    src: &'a [u8],
+    limits: &HeadLimits,
+    scan: &mut HeadScan,
+    parsed: &mut [MaybeUninit<httparse::Header<'a>>],
+    headers: &mut [HeaderIndex],
+) -> Result<Option<(usize, Method, Uri, Version, usize)>, ParseError> {
+    let mut req = httparse::Request::new(&mut []);
+
+    match req.parse_with_uninit_headers(src, parsed)? {
+        httparse::Status::Complete(len) => {
+            // the next head starts a new scan
+            let checked = scan.check(limits, &src[..len]);
+            *scan = HeadScan::default();
+            checked?;
+
+            let method = Method::from_bytes(req.method.unwrap().as_bytes())
+                .map_err(|_| ParseError::Method)?;
+            let uri = Uri::try_from(req.path.unwrap())?;
+            let version = if req.version.unwrap() == 1 {
+                Version::HTTP_11
+            } else {
+                Version::HTTP_10
+            };
+            HeaderIndex::record(src, req.headers, headers);
+
+            Ok(Some((len, method, uri, version, req.headers.len())))
+        }
+
+        httparse::Status::Partial => {
+            // reject oversized lines without waiting for the rest of the head
+            scan.check(limits, src)?;
+
+            if src.len() >= MAX_BUFFER_SIZE {
+                trace!("MAX_BUFFER_SIZE unprocessed data reached, closing");
+                Err(ParseError::TooLarge)
+            } else {
+                Ok(None)
+            }
+        }
+    }
+}
+
 impl MessageType for ResponseHead {
     fn set_connection_type(&mut self, conn_type: Option<ConnectionType>) {
         if let Some(ctype) = conn_type {
@@ -289,7 +479,11 @@ impl MessageType for ResponseHead {
         &mut self.headers
     }
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
+    fn decode(
+        src: &mut BytesMut,
+        _: &HeadLimits,
+        _: &mut HeadScan,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
         let mut headers: [HeaderIndex; MAX_HEADERS] = EMPTY_HEADER_INDEX_ARRAY;
 
         let (len, ver, status, h_len) = {
@@ -539,6 +733,10 @@ mod tests {
         pub(crate) fn is_unhandled(&self) -> bool {
             matches!(self, PayloadType::Stream(_))
         }
+
+        pub(crate) fn is_upgrade_offer(&self) -> bool {
+            matches!(self, PayloadType::UpgradeOffer)
+        }
     }
 
     impl PayloadItem {
@@ -901,6 +1099,28 @@ mod tests {
         assert!(pl.is_unhandled());
     }
 
+    #[test]
+    fn test_http_request_upgrade_other_protocol() {
+        let mut buf = BytesMut::from(
+            "GET /test HTTP/1.1\r\n\
+             connection: keep-alive, upgrade\r\n\
+             upgrade: irc/1.0\r\n\r\n",
+        );
+        let mut reader = MessageDecoder::<Request>::default();
+        let (req, pl) = reader.decode(&mut buf).unwrap().unwrap();
+        assert!(req.upgrade());
+        assert!(pl.is_upgrade_offer());
+
+        // an upgrade header alone does not request an upgrade
+        let mut buf = BytesMut::from(
+            "GET /test HTTP/1.1\r\n\
+             upgrade: irc/1.0\r\n\r\n",
+        );
+        let mut reader = MessageDecoder::<Request>::default();
+        let (_req, pl) = reader.decode(&mut buf).unwrap().unwrap();
+        assert!(!pl.is_unhandled());
+    }
+
     #[test]
     fn test_http_request_upgrade_h2c() {
         let mut buf = BytesMut::from(
@@ -957,6 +1177,32 @@ mod tests {
         expect_parse_err!(&mut buf);
     }
 
+    #[test]
+    fn test_http_request_http10_transfer_encoding() {
+        let mut buf = BytesMut::from(
+            "POST /test HTTP/1.0\r\n\
+            transfer-encoding: chunked\r\n\r\n",
+        );
+        expect_parse_err!(&mut buf);
+    }
+
+    #[test]
+    fn test_http_request_http10_expect() {
+        let mut buf = BytesMut::from(
+            "POST /test HTTP/1.0\r\n\
+            expect: 100-continue\r\n\r\n",
+        );
+        let req = parse_ready!(&mut buf);
+        assert!(!req.head().expect());
+
+        let mut buf = BytesMut::from(
+            "POST /test HTTP/1.1\r\n\
+            expect: 100-continue\r\n\r\n",
+        );
+        let req = parse_ready!(&mut buf);
+        assert!(req.head().expect());
+    }
+
     #[test]
     fn test_response_http10_read_until_eof() {
         let mut buf = BytesMut::from("HTTP/1.0 200 Ok\r\n\r\ntest data");
@@ -1048,4 +1294,82 @@ mod tests {
         let chunk = pl.decode(&mut buf).unwrap().unwrap();
         assert_eq!(chunk, PayloadItem::Chunk(Bytes::from_static(b"0\r\n")));
     }
+
+    #[test]
+    fn head_limits() {
+        let limits = HeadLimits {
+            max_request_line: Some(20),
+            max_header_size: Some(20),
+            max_header_count: 2,
+        };
+
+        let mut buf = BytesMut::from("GET /test HTTP/1.1\r\nHost: example.com\r\n\r\n");
+        let mut reader = MessageDecoder::<Request>::new(limits);
+        assert!(reader.decode(&mut buf).unwrap().is_some());
+
+        // limits apply before the head is complete
+        let mut buf = BytesMut::from("GET /too-long HTTP/1.1\r\n");
+        let mut reader = MessageDecoder::<Request>::new(limits);
+        assert!(matches!(
+            reader.decode(&mut buf),
+            Err(ParseError::UriTooLong)
+        ));
+
+        let mut buf = BytesMut::from("GET / HTTP/1.1\r\nX-Long: 0123456789abcdef\r\n");
+        let mut reader = MessageDecoder::<Request>::new(limits);
+        assert!(matches!(reader.decode(&mut buf), Err(ParseError::TooLarge)));
+
+        let mut buf = BytesMut::from("GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n");
+        let mut reader = MessageDecoder::<Request>::new(limits);
+        assert!(matches!(reader.decode(&mut buf), Err(ParseError::TooLarge)));
+
+        // heads received in parts are only scanned once
+        let mut buf = BytesMut::from("GET /test HTTP/1.1\r\nHost: exa");
+        let mut reader = MessageDecoder::<Request>::new(limits);
+        assert!(reader.decode(&mut buf).unwrap().is_none());
+        assert_eq!(reader.scan.scanned, buf.len());
+        assert_eq!(reader.scan.line_start, 20);
+        assert_eq!(reader.scan.lines, 1);
+
+        buf.extend_from_slice(b"mple.com\r\nX-Long: 0123");
+        assert!(reader.decode(&mut buf).unwrap().is_none());
+        assert_eq!(reader.scan.scanned, buf.len());
+        assert_eq!(reader.scan.lines, 2);
+
+        buf.extend_from_slice(b"456789abcdef\r\n");
+        assert!(matches!(reader.decode(&mut buf), Err(ParseError::TooLarge)));
+
+        let mut buf = BytesMut::from("GET /test HTTP/1.1\r");
+        let mut reader = MessageDecoder::<Request>::new(limits);
+        assert!(reader.decode(&mut buf).unwrap().is_none());
+        buf.extend_from_slice(b"\nHost: example.com\r\n\r\nGET / HTTP/1.1\r\n");
+        assert!(reader.decode(&mut buf).unwrap().is_some());
+
+        // scanning restarts for the next head
+        assert_eq!(reader.scan.scanned, 0);
+        assert!(reader.decode(&mut buf).unwrap().is_none());
+        assert_eq!(reader.scan.lines, 1);
+
+        // header counts beyond the default use heap buffers
+        let limits = HeadLimits {
+            max_header_count: MAX_HEADERS + 4,
+            ..HeadLimits::default()
+        };
+
+        let mut head = "GET / HTTP/1.1\r\n".to_owned();
+        for i in 0..MAX_HEADERS + 4 {
+            head.push_str(&format!("X-{}: {}\r\n", i, i));
+        }
+        head.push_str("\r\n");
+
+        let mut buf = BytesMut::from(head.as_str());
+        assert!(matches!(
+            MessageDecoder::<Request>::default().decode(&mut buf.clone()),
+            Err(ParseError::TooLarge)
+        ));
+
+        let mut reader = MessageDecoder::<Request>::new(limits);
+        let (req, _) = reader.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(req.headers().len(), MAX_HEADERS + 4);
+    }
 }
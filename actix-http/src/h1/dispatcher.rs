@@ -2,7 +2,8 @@ use std::{
     collections::VecDeque,
     fmt,
     future::Future,
-    io, mem, net,
+    io::{self, IoSlice},
+    mem, net,
     pin::Pin,
     rc::Rc,
     task::{Context, Poll},
@@ -13,7 +14,7 @@ use actix_rt::time::sleep_until;
 use actix_service::Service;
 use bitflags::bitflags;
 use bytes::{Buf, BytesMut};
-use futures_core::ready;
+use futures_core::{future::LocalBoxFuture, ready};
 use pin_project_lite::pin_project;
 use tracing::{debug, error, trace};
 
@@ -21,6 +22,7 @@ use crate::{
     body::{BodySize, BoxBody, MessageBody},
     config::ServiceConfig,
     error::{DispatchError, ParseError, PayloadError},
+    header,
     service::HttpFlow,
     ConnectionType, Error, Extensions, OnConnectData, Request, Response, StatusCode,
 };
@@ -28,8 +30,11 @@ use crate::{
 use super::{
     codec::Codec,
     decoder::MAX_BUFFER_SIZE,
+    encoder::WriteQueue,
+    informational::Informational,
     payload::{Payload, PayloadSender, PayloadStatus},
     timer::TimerState,
+    upgraded::{OnUpgrade, Upgraded},
     Message, MessageType,
 };
 
@@ -37,6 +42,9 @@ const LW_BUFFER_SIZE: usize = 1024;
 const HW_BUFFER_SIZE: usize = 1024 * 8;
 const MAX_PIPELINED_MESSAGES: usize = 16;
 
+/// Maximum number of buffers passed to a single vectored write.
+const MAX_IO_SLICES: usize = 64;
+
 bitflags! {
     pub struct Flags: u8 {
         /// Set when stream is read for first time.
@@ -126,6 +134,7 @@ pin_project! {
     {
         Normal { #[pin] inner: InnerDispatcher<T, S, B, X, U> },
         Upgrade { #[pin] fut: U::Future },
+        Upgraded { fut: LocalBoxFuture<'static, ()> },
     }
 }
 
@@ -155,6 +164,8 @@ pin_project! {
         pub(super) state: State<S, B, X>,
         // when Some(_) dispatcher is in state of receiving request payload
         payload: Option<PayloadSender>,
+        // number of bytes of the current request payload received so far
+        payload_len: usize,
         messages: VecDeque<DispatcherMessage>,
 
         head_timer: TimerState,
@@ -164,7 +175,14 @@ pin_project! {
         pub(super) io: Option<T>,
         read_buf: BytesMut,
         write_buf: BytesMut,
+        // body chunks, and what was encoded before them, waiting for a vectored write ahead of
+        // `write_buf`
+        write_queue: WriteQueue,
         codec: Codec,
+        informational: Informational,
+        // handler the connection is handed to once a `101 Switching Protocols` response is sent
+        on_upgrade: Option<OnUpgrade>,
+        tap: crate::tap::Tap,
     }
 }
 
@@ -257,18 +275,24 @@ where
         peer_addr: Option<net::SocketAddr>,
         conn_data: OnConnectData,
     ) -> Self {
+        // the sender is only looked up by services sending informational responses
+        let informational = Informational::default();
+        let mut conn_data = conn_data.0.unwrap_or_default();
+        conn_data.insert(informational.sender());
+
         Dispatcher {
             inner: DispatcherState::Normal {
                 inner: InnerDispatcher {
                     flow,
                     flags: Flags::empty(),
                     peer_addr,
-                    conn_data: conn_data.0.map(Rc::new),
+                    conn_data: Some(Rc::new(conn_data)),
                     config: config.clone(),
                     error: None,
 
                     state: State::None,
                     payload: None,
+                    payload_len: 0,
                     messages: VecDeque::new(),
 
                     head_timer: TimerState::new(config.client_request_deadline().is_some()),
@@ -280,7 +304,11 @@ where
                     io: Some(io),
                     read_buf: BytesMut::with_capacity(HW_BUFFER_SIZE),
                     write_buf: BytesMut::with_capacity(HW_BUFFER_SIZE),
+                    write_queue: WriteQueue::default(),
+                    tap: crate::tap::connect(&config, peer_addr),
                     codec: Codec::new(config),
+                    informational,
+                    on_upgrade: None,
                 },
             },
 
@@ -328,9 +356,36 @@ where
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
-        let InnerDispatcherProj { io, write_buf, .. } = self.project();
+        let InnerDispatcherProj {
+            io,
+            write_buf,
+            write_queue,
+            tap,
+            ..
+        } = self.project();
         let mut io = Pin::new(io.as_mut().unwrap());
 
+        // queued body chunks are written together with the write buffer
+        while !write_queue.is_empty() {
+            let mut slices = [IoSlice::new(&[]); MAX_IO_SLICES];
+            let cnt = write_queue.io_slices(write_buf, &mut slices);
+
+            match io.as_mut().poll_write_vectored(cx, &slices[..cnt])? {
+                Poll::Ready(0) => {
+                    error!("write zero; closing");
+                    return Poll::Ready(Err(io::Error::new(io::ErrorKind::WriteZero, "")));
+                }
+
+                Poll::Ready(n) => {
+                    let rest = write_queue.advance(n, |buf| crate::tap::outbound(tap, buf));
+                    crate::tap::outbound(tap, &write_buf[..rest]);
+                    write_buf.advance(rest);
+                }
+
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
         let len = write_buf.len();
         let mut written = 0;
 
@@ -341,7 +396,10 @@ where
                     return Poll::Ready(Err(io::Error::new(io::ErrorKind::WriteZero, "")));
                 }
 
-                Poll::Ready(n) => written += n,
+                Poll::Ready(n) => {
+                    crate::tap::outbound(tap, &write_buf[written..written + n]);
+                    written += n;
+                }
 
                 Poll::Pending => {
                     write_buf.advance(written);
@@ -359,13 +417,19 @@ where
 
     fn send_response_inner(
         self: Pin<&mut Self>,
-        res: Response<()>,
+        mut res: Response<()>,
         body: &impl MessageBody,
     ) -> Result<BodySize, DispatchError> {
         let this = self.project();
 
         let size = body.size();
 
+        let on_upgrade = if res.status() == StatusCode::SWITCHING_PROTOCOLS {
+            res.extensions_mut().remove::<OnUpgrade>()
+        } else {
+            None
+        };
+
         this.codec
             .encode(Message::Item((res, size)), this.write_buf)
             .map_err(|err| {
@@ -376,6 +440,11 @@ where
                 DispatchError::Io(err)
             })?;
 
+        // the connection is only handed over if the codec switched to streaming it
+        if this.codec.message_type() == MessageType::Stream {
+            *this.on_upgrade = on_upgrade;
+        }
+
         Ok(size)
     }
 
@@ -439,6 +508,7 @@ where
                             this.state.set(State::ExpectCall { fut });
                         } else {
                             // set InnerDispatcher state and continue loop to poll it
+                            this.informational.attach(&req);
                             let fut = this.flow.service.call(req);
                             this.state.set(State::ServiceCall { fut });
                         };
@@ -467,15 +537,22 @@ where
                 },
 
                 StateProj::ServiceCall { fut } => {
-                    match fut.poll(cx) {
+                    let poll = fut.poll(cx);
+
+                    // informational responses sent by the service go ahead of its response
+                    this.informational.write_to(this.write_buf);
+
+                    match poll {
                         // service call resolved. send response.
                         Poll::Ready(Ok(res)) => {
+                            this.informational.close();
                             let (res, body) = res.into().replace_body(());
                             self.as_mut().send_response(res, body)?;
                         }
 
                         // send service call error as response
                         Poll::Ready(Err(err)) => {
+                            this.informational.close();
                             let res: Response<BoxBody> = err.into();
                             let (res, body) = res.replace_body(());
                             self.as_mut().send_error_response(res, body)?;
@@ -484,6 +561,8 @@ where
                         // service call pending and could be waiting for more chunk messages
                         // (pipeline message limit and/or payload can_read limit)
                         Poll::Pending => {
+                            this.informational.register(cx.waker());
+
                             // no new message is decoded and no new payload is fed
                             // nothing to do except waiting for new incoming data from client
                             if !self.as_mut().poll_request(cx)? {
@@ -497,8 +576,20 @@ where
                 StateProj::SendPayload { mut body } => {
                     // keep populate writer buffer until buffer size limit hit,
                     // get blocked or finished.
-                    while this.write_buf.len() < super::payload::MAX_BUFFER_SIZE {
+                    let vectored = this.io.as_ref().map_or(false, |io| io.is_write_vectored());
+
+                    while this.write_buf.len() + this.write_queue.len()
+                        < this.config.write_buffer_size()
+                    {
                         match body.as_mut().poll_next(cx) {
+                            Poll::Ready(Some(Ok(item))) if vectored => {
+                                this.codec.encode_chunk_vectored(
+                                    item,
+                                    this.write_buf,
+                                    this.write_queue,
+                                )?;
+                            }
+
                             Poll::Ready(Some(Ok(item))) => {
                                 this.codec
                                     .encode(Message::Chunk(Some(item)), this.write_buf)?;
@@ -534,8 +625,20 @@ where
 
                     // keep populate writer buffer until buffer size limit hit,
                     // get blocked or finished.
-                    while this.write_buf.len() < super::payload::MAX_BUFFER_SIZE {
+                    let vectored = this.io.as_ref().map_or(false, |io| io.is_write_vectored());
+
+                    while this.write_buf.len() + this.write_queue.len()
+                        < this.config.write_buffer_size()
+                    {
                         match body.as_mut().poll_next(cx) {
+                            Poll::Ready(Some(Ok(item))) if vectored => {
+                                this.codec.encode_chunk_vectored(
+                                    item,
+                                    this.write_buf,
+                                    this.write_queue,
+                                )?;
+                            }
+
                             Poll::Ready(Some(Ok(item))) => {
                                 this.codec
                                     .encode(Message::Chunk(Some(item)), this.write_buf)?;
@@ -577,6 +680,7 @@ where
                         Poll::Ready(Ok(req)) => {
                             this.write_buf
                                 .extend_from_slice(b"HTTP/1.1 100 Continue\r\n\r\n");
+                            this.informational.attach(&req);
                             let fut = this.flow.service.call(req);
                             this.state.set(State::ServiceCall { fut });
                         }
@@ -612,6 +716,7 @@ where
                 this.state.set(State::ExpectCall { fut });
             } else {
                 // set dispatcher state to call service handler
+                this.informational.attach(&req);
                 let fut = this.flow.service.call(req);
                 this.state.set(State::ServiceCall { fut });
             };
@@ -627,6 +732,7 @@ where
                             self.as_mut().send_continue();
 
                             let mut this = self.as_mut().project();
+                            this.informational.attach(&req);
                             let fut = this.flow.service.call(req);
                             this.state.set(State::ServiceCall { fut });
 
@@ -649,21 +755,31 @@ where
                 }
 
                 StateProj::ServiceCall { fut } => {
+                    let poll = fut.poll(cx);
+
+                    let this = self.as_mut().project();
+                    this.informational.write_to(this.write_buf);
+
                     // return no matter the service call future's result.
-                    return match fut.poll(cx) {
+                    return match poll {
                         // Future is resolved. Send response and return a result. On success
                         // to notify the dispatcher a new state is set and the outer loop
                         // should be continue.
                         Poll::Ready(Ok(res)) => {
+                            this.informational.close();
                             let (res, body) = res.into().replace_body(());
                             self.as_mut().send_response(res, body)
                         }
 
                         // see the comment on ExpectCall state branch's Pending
-                        Poll::Pending => Ok(()),
+                        Poll::Pending => {
+                            this.informational.register(cx.waker());
+                            Ok(())
+                        }
 
                         // see the comment on ExpectCall state branch's Ready(Err(_))
                         Poll::Ready(Err(err)) => {
+                            this.informational.close();
                             let res: Response<BoxBody> = err.into();
                             let (res, body) = res.replace_body(());
                             self.as_mut().send_error_response(res, body)
@@ -687,6 +803,11 @@ where
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Result<bool, DispatchError> {
+        // what follows the switching protocols response is left to the upgrade handler
+        if self.on_upgrade.is_some() {
+            return Ok(false);
+        }
+
         let pipeline_queue_full = self.messages.len() >= MAX_PIPELINED_MESSAGES;
         let can_not_read = !self.can_read(cx);
 
@@ -704,6 +825,10 @@ where
                 // ...maybe handler does not want to read any more payload...
                 if let PayloadStatus::Dropped = sender.need_read(cx) {
                     debug!("handler dropped payload early; attempt to clean connection");
+
+                    // without the payload, the handler can not take over the connection
+                    this.codec.decline_upgrade();
+
                     // ...in which case poll request payload a few times
                     loop {
                         match this.codec.decode(this.read_buf)? {
@@ -763,6 +888,11 @@ where
 
         // decode from read buf as many full requests as possible
         loop {
+            // bytes after a sent switching protocols response are left for the upgrade handler
+            if this.on_upgrade.is_some() {
+                break;
+            }
+
             match this.codec.decode(this.read_buf) {
                 Ok(Some(msg)) => {
                     updated = true;
@@ -790,6 +920,26 @@ where
 
                                 // request is not upgradable
                                 MessageType::Payload | MessageType::Stream => {
+                                    if this.config.exceeds_payload_limit(
+                                        req.head().headers.get(&header::CONTENT_LENGTH),
+                                    ) {
+                                        trace!(
+                                            "request content-length exceeds payload limit; \
+                                            returning 413 response"
+                                        );
+
+                                        this.messages.push_back(DispatcherMessage::Error(
+                                            Response::with_body(
+                                                StatusCode::PAYLOAD_TOO_LARGE,
+                                                (),
+                                            ),
+                                        ));
+
+                                        this.flags.insert(Flags::READ_DISCONNECT);
+                                        *this.error = Some(DispatchError::PayloadTooLarge);
+                                        break;
+                                    }
+
                                     // PayloadSender and Payload are smart pointers share the
                                     // same state. PayloadSender is attached to dispatcher and used
                                     // to sink new chunked request data to state. Payload is
@@ -798,6 +948,7 @@ where
                                     let (sender, payload) = Payload::create(false);
                                     *req.payload() = crate::Payload::H1 { payload };
                                     *this.payload = Some(sender);
+                                    *this.payload_len = 0;
                                 }
                             }
 
@@ -812,6 +963,24 @@ where
 
                         Message::Chunk(Some(chunk)) => {
                             if let Some(ref mut payload) = this.payload {
+                                *this.payload_len += chunk.len();
+                                let payload_len = *this.payload_len;
+
+                                if this
+                                    .config
+                                    .max_payload_size()
+                                    .map_or(false, |limit| payload_len > limit)
+                                {
+                                    trace!("request payload exceeds payload limit; aborting");
+
+                                    // handler observes the error; connection is closed once its
+                                    // response has been sent
+                                    payload.set_error(PayloadError::Overflow);
+                                    *this.payload = None;
+                                    this.flags.insert(Flags::READ_DISCONNECT);
+                                    break;
+                                }
+
                                 payload.feed_data(chunk);
                             } else {
                                 error!("Internal server error: unexpected payload chunk");
@@ -872,6 +1041,25 @@ where
                     break;
                 }
 
+                Err(ParseError::UriTooLong) => {
+                    trace!("request line was too long; returning 414 response");
+
+                    if let Some(mut payload) = this.payload.take() {
+                        payload.set_error(PayloadError::Overflow);
+                    }
+
+                    this.messages
+                        .push_back(DispatcherMessage::Error(Response::with_body(
+                            StatusCode::URI_TOO_LONG,
+                            (),
+                        )));
+
+                    this.flags.insert(Flags::READ_DISCONNECT);
+                    *this.error = Some(ParseError::UriTooLong.into());
+
+                    break;
+                }
+
                 Err(err) => {
                     trace!("parse error {}", &err);
 
@@ -1074,6 +1262,8 @@ where
                         return Ok(true);
                     }
 
+                    crate::tap::inbound(this.tap, &this.read_buf[this.read_buf.len() - n..]);
+
                     read_some = true;
                 }
 
@@ -1096,6 +1286,22 @@ where
         }
     }
 
+    /// Hands the connection to the handler of a sent `101 Switching Protocols` response.
+    fn hand_off(self: Pin<&mut Self>) -> LocalBoxFuture<'static, ()>
+    where
+        T: 'static,
+    {
+        let this = self.project();
+
+        // the rest of the connection is read by the handler instead of the request payload
+        if let Some(mut payload) = this.payload.take() {
+            payload.feed_eof();
+        }
+
+        let io = Upgraded::new(this.io.take().unwrap(), mem::take(this.read_buf));
+        this.on_upgrade.take().unwrap().call(io)
+    }
+
     /// call upgrade service with request.
     fn upgrade(self: Pin<&mut Self>, req: Request) -> U::Future {
         let this = self.project();
@@ -1104,7 +1310,7 @@ where
             mem::take(this.codec),
             mem::take(this.read_buf),
         );
-        parts.write_buf = mem::take(this.write_buf);
+        parts.write_buf = this.write_queue.flatten(mem::take(this.write_buf));
         let framed = Framed::from_parts(parts);
         this.flow.upgrade.as_ref().unwrap().call((req, framed))
     }
@@ -1112,7 +1318,7 @@ where
 
 impl<T, S, B, X, U> Future for Dispatcher<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
 
     S: Service<Request>,
     S::Error: Into<Response<BoxBody>>,
@@ -1143,6 +1349,8 @@ where
                 DispatchError::Upgrade
             }),
 
+            DispatcherStateProj::Upgraded { fut } => fut.as_mut().poll(cx).map(Ok),
+
             DispatcherStateProj::Normal { mut inner } => {
                 trace!("start flags: {:?}", &inner.flags);
 
@@ -1255,6 +1463,20 @@ where
                         return Poll::Ready(Ok(()));
                     }
 
+                    // switching protocols response is written; hand over the connection
+                    if inner.on_upgrade.is_some()
+                        && inner.state.is_none()
+                        && inner.write_buf.is_empty()
+                        && inner.write_queue.is_empty()
+                    {
+                        let fut = inner.hand_off();
+                        self.as_mut()
+                            .project()
+                            .inner
+                            .set(DispatcherState::Upgraded { fut });
+                        return self.poll(cx);
+                    }
+
                     let inner_p = inner.as_mut().project();
                     let state_is_none = inner_p.state.is_none();
 
@@ -1265,7 +1487,10 @@ where
                     }
 
                     // keep-alive and stream errors
-                    if state_is_none && inner_p.write_buf.is_empty() {
+                    if state_is_none
+                        && inner_p.write_buf.is_empty()
+                        && inner_p.write_queue.is_empty()
+                    {
                         if let Some(err) = inner_p.error.take() {
                             error!("stream error: {}", &err);
                             return Poll::Ready(Err(err));
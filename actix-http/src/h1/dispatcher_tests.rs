@@ -1,4 +1,14 @@
-use std::{future::Future, str, task::Poll, time::Duration};
+use std::{
+    cell::RefCell,
+    cmp,
+    future::Future,
+    io::{self, IoSlice},
+    pin::Pin,
+    rc::Rc,
+    str,
+    task::{Context, Poll},
+    time::Duration,
+};
 
 use actix_rt::{pin, time::sleep};
 use actix_service::fn_service;
@@ -6,18 +16,19 @@ use actix_utils::future::{ready, Ready};
 use bytes::Bytes;
 use futures_util::future::lazy;
 
-use actix_codec::Framed;
+use actix_codec::{AsyncRead, AsyncWrite, Framed, ReadBuf};
 use actix_service::Service;
 use bytes::{Buf, BytesMut};
 
 use super::dispatcher::{Dispatcher, DispatcherState, DispatcherStateProj, Flags};
 use crate::{
-    body::MessageBody,
+    body::{BodyStream, MessageBody},
     config::ServiceConfig,
-    h1::{Codec, ExpectHandler, UpgradeHandler},
+    h1::{Codec, ExpectHandler, OnUpgrade, UpgradeHandler},
     service::HttpFlow,
     test::{TestBuffer, TestSeqBuffer},
-    Error, HttpMessage, KeepAlive, Method, OnConnectData, Request, Response, StatusCode,
+    ConnectionType, Error, HttpMessage, KeepAlive, Method, OnConnectData, Request, Response,
+    StatusCode,
 };
 
 fn find_slice(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
@@ -783,6 +794,70 @@ async fn upgrade_handling() {
     .await;
 }
 
+#[actix_rt::test]
+async fn upgrade_hand_off() {
+    let buf =
+        TestBuffer::new("GET / HTTP/1.1\r\nconnection: upgrade\r\nupgrade: shout\r\n\r\nhello");
+    let written = Rc::new(buf.clone());
+
+    let services = HttpFlow::new(
+        fn_service(move |req: Request| {
+            let written = Rc::clone(&written);
+
+            let mut res = Response::new(StatusCode::SWITCHING_PROTOCOLS);
+            res.head_mut().set_connection_type(ConnectionType::Upgrade);
+            res.extensions_mut()
+                .insert(OnUpgrade::new(|mut io| async move {
+                    // keep the request payload alive so that the upgrade is not declined
+                    let _req = req;
+
+                    // the handler only runs once the response head is written
+                    assert!(written
+                        .write_buf_slice()
+                        .starts_with(b"HTTP/1.1 101 Switching Protocols\r\n"));
+
+                    let mut data = [0; 5];
+                    let mut read_buf = ReadBuf::new(&mut data);
+                    futures_util::future::poll_fn(|cx| {
+                        Pin::new(&mut io).poll_read(cx, &mut read_buf)
+                    })
+                    .await
+                    .unwrap();
+                    assert_eq!(read_buf.filled(), b"hello");
+
+                    futures_util::future::poll_fn(|cx| {
+                        Pin::new(&mut io).poll_write(cx, b"HELLO")
+                    })
+                    .await
+                    .unwrap();
+                }));
+
+            ready(Ok::<_, Error>(res))
+        }),
+        ExpectHandler,
+        None,
+    );
+
+    let h1 = Dispatcher::<_, _, _, _, UpgradeHandler>::new(
+        buf.clone(),
+        services,
+        ServiceConfig::default(),
+        None,
+        OnConnectData::default(),
+    );
+    pin!(h1);
+
+    lazy(|cx| {
+        assert!(h1.as_mut().poll(cx).is_ready());
+        assert!(matches!(&h1.inner, DispatcherState::Upgraded { .. }));
+    })
+    .await;
+
+    let res = buf.take_write_buf();
+    assert!(res.starts_with(b"HTTP/1.1 101 Switching Protocols\r\n"));
+    assert!(res.ends_with(b"\r\n\r\nHELLO"), "{:?}", res);
+}
+
 #[actix_rt::test]
 async fn handler_drop_payload() {
     let _ = env_logger::try_init();
@@ -924,6 +999,151 @@ async fn handler_drop_payload() {
     .await;
 }
 
+/// Test I/O supporting vectored writes, recording the buffers passed to each of them.
+struct VectoredBuffer {
+    buf: TestBuffer,
+    /// Maximum number of bytes accepted by each write.
+    max_write: usize,
+    writes: Rc<RefCell<Vec<Vec<Bytes>>>>,
+}
+
+impl AsyncRead for VectoredBuffer {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().buf).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for VectoredBuffer {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let n = cmp::min(buf.len(), this.max_write);
+        Pin::new(&mut this.buf).poll_write(cx, &buf[..n])
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        this.writes
+            .borrow_mut()
+            .push(bufs.iter().map(|buf| Bytes::copy_from_slice(buf)).collect());
+
+        let mut written = 0;
+
+        for buf in bufs {
+            let n = cmp::min(buf.len(), this.max_write - written);
+            this.buf.write_buf.borrow_mut().extend_from_slice(&buf[..n]);
+            written += n;
+        }
+
+        Poll::Ready(Ok(written))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().buf).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().buf).poll_shutdown(cx)
+    }
+}
+
+/// Dispatches a request with a chunked response body to `VectoredBuffer` I/O, returning what was
+/// written and the recorded vectored writes.
+async fn dispatch_vectored(max_write: usize) -> (Vec<u8>, Vec<Vec<Bytes>>) {
+    let buf = TestBuffer::new("GET / HTTP/1.1\r\n\r\n");
+    let writes = Rc::new(RefCell::new(Vec::new()));
+
+    let io = VectoredBuffer {
+        buf: buf.clone(),
+        max_write,
+        writes: Rc::clone(&writes),
+    };
+
+    let cfg = ServiceConfig::new(
+        KeepAlive::Disabled,
+        Duration::from_millis(100),
+        Duration::ZERO,
+        false,
+        None,
+    );
+
+    let services = HttpFlow::new(
+        fn_service(|_req: Request| {
+            let chunks = futures_util::stream::iter([
+                Ok::<_, Error>(Bytes::from_static(b"hello")),
+                Ok(Bytes::from_static(b"world")),
+            ]);
+
+            ready(Ok::<_, Error>(
+                Response::ok().set_body(BodyStream::new(chunks)),
+            ))
+        }),
+        ExpectHandler,
+        None,
+    );
+
+    let h1 = Dispatcher::<_, _, _, _, UpgradeHandler>::new(
+        io,
+        services,
+        cfg,
+        None,
+        OnConnectData::default(),
+    );
+    pin!(h1);
+
+    lazy(|cx| match h1.as_mut().poll(cx) {
+        Poll::Pending => panic!("first poll should not be pending"),
+        Poll::Ready(res) => assert!(res.is_ok()),
+    })
+    .await;
+
+    let mut res = buf.take_write_buf().to_vec();
+    stabilize_date_header(&mut res);
+
+    let writes = writes.take();
+    (res, writes)
+}
+
+#[actix_rt::test]
+async fn vectored_write() {
+    let (res, writes) = dispatch_vectored(usize::MAX).await;
+
+    assert!(res.starts_with(b"HTTP/1.1 200 OK\r\n"));
+    assert!(
+        res.ends_with(b"\r\n\r\n5\r\nhello\r\n5\r\nworld\r\n0\r\n\r\n"),
+        "unexpected response: {:?}",
+        String::from_utf8_lossy(&res),
+    );
+
+    // response head and body chunks go out in one write, without copying the chunks
+    assert_eq!(writes.len(), 1);
+    let bufs = &writes[0];
+    assert_eq!(bufs.len(), 5);
+    assert!(bufs[0].ends_with(b"\r\n\r\n5\r\n"));
+    assert_eq!(bufs[1..], ["hello", "\r\n5\r\n", "world", "\r\n0\r\n\r\n"]);
+
+    // partial writes continue where the previous write stopped
+    let (partial_res, writes) = dispatch_vectored(4).await;
+    assert_eq!(partial_res, res);
+    assert!(writes.len() > 1);
+}
+
 fn http_msg(msg: impl AsRef<str>) -> BytesMut {
     let mut msg = msg
         .as_ref()
@@ -1,19 +1,23 @@
 use std::{
     cmp,
-    io::{self, Write as _},
+    collections::VecDeque,
+    io::{self, IoSlice, Write as _},
+    iter,
     marker::PhantomData,
     ptr::copy_nonoverlapping,
     slice::from_raw_parts_mut,
 };
 
-use bytes::{BufMut, BytesMut};
+use bytes::{Buf as _, BufMut, Bytes, BytesMut};
 
 use crate::{
     body::BodySize,
     header::{
-        map::Value, HeaderMap, HeaderName, CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING,
+        map::Value, HeaderMap, HeaderName, CONNECTION, CONTENT_LENGTH, DATE, SERVER,
+        TRANSFER_ENCODING,
     },
-    helpers, ConnectionType, RequestHeadType, Response, ServiceConfig, StatusCode, Version,
+    helpers, ConnectionType, RequestHeadType, Response, ServerHeader, ServiceConfig,
+    StatusCode, Version,
 };
 
 const AVERAGE_HEADER_SIZE: usize = 30;
@@ -59,7 +63,8 @@ pub(crate) trait MessageType: Sized {
         conn_type: ConnectionType,
         config: &ServiceConfig,
     ) -> io::Result<()> {
-        let chunked = self.chunked();
+        // HTTP/1.0 does not support chunked transfer encoding
+        let chunked = self.chunked() && version >= Version::HTTP_11;
         let mut skip_len = length != BodySize::Stream;
         let camel_case = self.camel_case();
 
@@ -133,6 +138,14 @@ pub(crate) trait MessageType: Sized {
 
         let mut has_date = false;
 
+        // requests have no status and are not subject to the server header policy
+        let server_header = match self.status() {
+            Some(_) => config.server_header(),
+            None => &ServerHeader::Passthrough,
+        };
+        let suppress_server = *server_header == ServerHeader::Suppress;
+        let mut has_server = false;
+
         let mut buf = dst.chunk_mut().as_mut_ptr();
         let mut remaining = dst.capacity() - dst.len();
 
@@ -144,8 +157,12 @@ pub(crate) trait MessageType: Sized {
         self.write_headers(|key, value| {
             match *key {
                 CONNECTION => return,
-                TRANSFER_ENCODING | CONTENT_LENGTH if skip_len => return,
+                // framing is decided by the encoder based on the body size
+                TRANSFER_ENCODING => return,
+                CONTENT_LENGTH if skip_len => return,
                 DATE => has_date = true,
+                SERVER if suppress_server => return,
+                SERVER => has_server = true,
                 _ => {}
             }
 
@@ -215,6 +232,14 @@ pub(crate) trait MessageType: Sized {
             config.write_date_header(dst, camel_case);
         }
 
+        if let ServerHeader::Default(value) = server_header {
+            if !has_server {
+                dst.put_slice(if camel_case { b"Server: " } else { b"server: " });
+                dst.put_slice(value.as_bytes());
+                dst.put_slice(b"\r\n");
+            }
+        }
+
         // end-of-headers marker
         dst.extend_from_slice(b"\r\n");
 
@@ -299,20 +324,40 @@ impl MessageType for RequestHeadType {
     fn encode_status(&mut self, dst: &mut BytesMut) -> io::Result<()> {
         let head = self.as_ref();
         dst.reserve(256 + head.headers.len() * AVERAGE_HEADER_SIZE);
-        write!(
-            helpers::MutWriter(dst),
-            "{} {} {}",
-            head.method,
-            head.uri.path_and_query().map(|u| u.as_str()).unwrap_or("/"),
-            match head.version {
-                Version::HTTP_09 => "HTTP/0.9",
-                Version::HTTP_10 => "HTTP/1.0",
-                Version::HTTP_11 => "HTTP/1.1",
-                Version::HTTP_2 => "HTTP/2.0",
-                Version::HTTP_3 => "HTTP/3.0",
-                _ => return Err(io::Error::new(io::ErrorKind::Other, "unsupported version")),
-            }
-        )
+
+        let version = match head.version {
+            Version::HTTP_09 => "HTTP/0.9",
+            Version::HTTP_10 => "HTTP/1.0",
+            Version::HTTP_11 => "HTTP/1.1",
+            Version::HTTP_2 => "HTTP/2.0",
+            Version::HTTP_3 => "HTTP/3.0",
+            _ => return Err(io::Error::new(io::ErrorKind::Other, "unsupported version")),
+        };
+
+        let path = head.uri.path_and_query().map(|u| u.as_str()).unwrap_or("/");
+
+        match (
+            head.absolute_form(),
+            head.uri.scheme(),
+            head.uri.authority(),
+        ) {
+            (true, Some(scheme), Some(authority)) => write!(
+                helpers::MutWriter(dst),
+                "{} {}://{}{} {}",
+                head.method,
+                scheme,
+                authority,
+                path,
+                version
+            ),
+            _ => write!(
+                helpers::MutWriter(dst),
+                "{} {} {}",
+                head.method,
+                path,
+                version
+            ),
+        }
         .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
     }
 }
@@ -323,6 +368,16 @@ impl<T: MessageType> MessageEncoder<T> {
         self.te.encode(msg, buf)
     }
 
+    /// Encode chunk for a vectored write, queueing it instead of copying it into `buf`.
+    pub fn encode_chunk_vectored(
+        &mut self,
+        msg: Bytes,
+        buf: &mut BytesMut,
+        queue: &mut WriteQueue,
+    ) -> io::Result<bool> {
+        self.te.encode_vectored(msg, buf, queue)
+    }
+
     /// Encode EOF.
     pub fn encode_eof(&mut self, buf: &mut BytesMut) -> io::Result<()> {
         self.te.encode_eof(buf)
@@ -346,7 +401,7 @@ impl<T: MessageType> MessageEncoder<T> {
                 BodySize::Sized(0) => TransferEncoding::empty(),
                 BodySize::Sized(len) => TransferEncoding::length(len),
                 BodySize::Stream => {
-                    if message.chunked() && !stream {
+                    if message.chunked() && !stream && version >= Version::HTTP_11 {
                         TransferEncoding::chunked()
                     } else {
                         TransferEncoding::eof()
@@ -358,8 +413,17 @@ impl<T: MessageType> MessageEncoder<T> {
             self.te = TransferEncoding::empty();
         }
 
+        // responses to CONNECT and upgrade requests carry the raw tunneled stream, so they must not
+        // have framing headers
+        // see https://datatracker.ietf.org/doc/html/rfc7231#section-4.3.6
+        let header_length = if stream && length == BodySize::Stream {
+            BodySize::None
+        } else {
+            length
+        };
+
         message.encode_status(dst)?;
-        message.encode_headers(dst, version, length, conn_type, config)
+        message.encode_headers(dst, version, header_length, conn_type, config)
     }
 }
 
@@ -459,6 +523,58 @@ impl TransferEncoding {
         }
     }
 
+    /// Encode message for a vectored write. Return `EOF` state of encoder
+    ///
+    /// Behaves like [`encode`](Self::encode), except that `msg` is queued behind the current
+    /// contents of `buf` instead of being copied into it.
+    pub fn encode_vectored(
+        &mut self,
+        mut msg: Bytes,
+        buf: &mut BytesMut,
+        queue: &mut WriteQueue,
+    ) -> io::Result<bool> {
+        match self.kind {
+            TransferEncodingKind::Eof => {
+                let eof = msg.is_empty();
+                queue.push(buf, msg);
+                Ok(eof)
+            }
+            TransferEncodingKind::Chunked(ref mut eof) => {
+                if *eof {
+                    return Ok(true);
+                }
+
+                if msg.is_empty() {
+                    *eof = true;
+                    buf.extend_from_slice(b"0\r\n\r\n");
+                } else {
+                    writeln!(helpers::MutWriter(buf), "{:X}\r", msg.len())
+                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+                    queue.push(buf, msg);
+                    buf.extend_from_slice(b"\r\n");
+                }
+                Ok(*eof)
+            }
+            TransferEncodingKind::Length(ref mut remaining) => {
+                if *remaining > 0 {
+                    if msg.is_empty() {
+                        return Ok(*remaining == 0);
+                    }
+                    let len = cmp::min(*remaining, msg.len() as u64);
+
+                    msg.truncate(len as usize);
+                    queue.push(buf, msg);
+
+                    *remaining -= len as u64;
+                    Ok(*remaining == 0)
+                } else {
+                    Ok(true)
+                }
+            }
+        }
+    }
+
     /// Encode eof. Return `EOF` state of encoder
     #[inline]
     pub fn encode_eof(&mut self, buf: &mut BytesMut) -> io::Result<()> {
@@ -482,6 +598,104 @@ impl TransferEncoding {
     }
 }
 
+/// Encoded data queued for a vectored write, ahead of the data in the write buffer.
+///
+/// Body chunks are queued as they are instead of being copied into the write buffer. Whatever is
+/// in the write buffer at that point is split off and queued first, keeping the output in order.
+#[derive(Debug, Default)]
+pub(crate) struct WriteQueue {
+    bufs: VecDeque<Bytes>,
+    len: usize,
+}
+
+impl WriteQueue {
+    /// Queues the contents of `buf`, followed by `bytes`.
+    fn push(&mut self, buf: &mut BytesMut, bytes: Bytes) {
+        if !buf.is_empty() {
+            self.len += buf.len();
+            self.bufs.push_back(buf.split().freeze());
+        }
+
+        if !bytes.is_empty() {
+            self.len += bytes.len();
+            self.bufs.push_back(bytes);
+        }
+    }
+
+    /// Returns the number of queued bytes.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if nothing is queued.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Fills `dst` with the queued data followed by `tail`, returning the number of slices used.
+    pub(crate) fn io_slices<'a>(&'a self, tail: &'a [u8], dst: &mut [IoSlice<'a>]) -> usize {
+        let bufs = self
+            .bufs
+            .iter()
+            .map(|bytes| bytes.as_ref())
+            .chain(iter::once(tail))
+            .filter(|buf| !buf.is_empty());
+
+        let mut cnt = 0;
+
+        for (slice, buf) in dst.iter_mut().zip(bufs) {
+            *slice = IoSlice::new(buf);
+            cnt += 1;
+        }
+
+        cnt
+    }
+
+    /// Removes `cnt` written bytes from the front of the queue, passing each removed part to
+    /// `written`.
+    ///
+    /// Returns the number of written bytes that were beyond the queued data.
+    pub(crate) fn advance(&mut self, mut cnt: usize, mut written: impl FnMut(&[u8])) -> usize {
+        while cnt > 0 {
+            let front = match self.bufs.front_mut() {
+                Some(front) => front,
+                None => break,
+            };
+
+            let n = cmp::min(cnt, front.len());
+            written(&front[..n]);
+            front.advance(n);
+
+            if front.is_empty() {
+                self.bufs.pop_front();
+            }
+
+            self.len -= n;
+            cnt -= n;
+        }
+
+        cnt
+    }
+
+    /// Moves the queued data, followed by `tail`, into a single buffer.
+    pub(crate) fn flatten(&mut self, tail: BytesMut) -> BytesMut {
+        if self.is_empty() {
+            return tail;
+        }
+
+        let mut buf = BytesMut::with_capacity(self.len + tail.len());
+
+        for bytes in self.bufs.drain(..) {
+            buf.extend_from_slice(&bytes);
+        }
+
+        buf.extend_from_slice(&tail);
+        self.len = 0;
+
+        buf
+    }
+}
+
 /// # Safety
 /// Callers must ensure that the given `len` matches the given `value` length and that `buf` is
 /// valid for writes of at least `len` bytes.
@@ -615,6 +829,26 @@ mod tests {
         assert!(data.contains("date: date\r\n"));
     }
 
+    #[test]
+    fn test_absolute_form() {
+        let mut bytes = BytesMut::new();
+        let mut head = RequestHead::default();
+        head.uri = "http://example.com:8080/path?query".parse().unwrap();
+
+        let mut head = RequestHeadType::Owned(head);
+        head.encode_status(&mut bytes).unwrap();
+        assert_eq!(&bytes.split()[..], b"GET /path?query HTTP/1.1");
+
+        if let RequestHeadType::Owned(ref mut head) = head {
+            head.set_absolute_form(true);
+        }
+        head.encode_status(&mut bytes).unwrap();
+        assert_eq!(
+            &bytes.split()[..],
+            &b"GET http://example.com:8080/path?query HTTP/1.1"[..]
+        );
+    }
+
     #[actix_rt::test]
     async fn test_extra_headers() {
         let mut bytes = BytesMut::with_capacity(2048);
@@ -648,6 +882,63 @@ mod tests {
         assert!(data.contains("date: date\r\n"));
     }
 
+    #[actix_rt::test]
+    async fn test_server_header() {
+        let mut bytes = BytesMut::with_capacity(2048);
+
+        let config = ServiceConfig::default()
+            .with_server_header(ServerHeader::Default(HeaderValue::from_static("actix")));
+
+        let mut res = Response::with_body(StatusCode::OK, ());
+        let _ = res.encode_headers(
+            &mut bytes,
+            Version::HTTP_11,
+            BodySize::Sized(0),
+            ConnectionType::KeepAlive,
+            &config,
+        );
+        let data = String::from_utf8(Vec::from(bytes.split().freeze().as_ref())).unwrap();
+        assert!(data.contains("server: actix\r\n"));
+
+        res.headers_mut()
+            .insert(SERVER, HeaderValue::from_static("custom"));
+        let _ = res.encode_headers(
+            &mut bytes,
+            Version::HTTP_11,
+            BodySize::Sized(0),
+            ConnectionType::KeepAlive,
+            &config,
+        );
+        let data = String::from_utf8(Vec::from(bytes.split().freeze().as_ref())).unwrap();
+        assert!(data.contains("server: custom\r\n"));
+        assert!(!data.contains("server: actix\r\n"));
+
+        let config = ServiceConfig::default().with_server_header(ServerHeader::Suppress);
+        let _ = res.encode_headers(
+            &mut bytes,
+            Version::HTTP_11,
+            BodySize::Sized(0),
+            ConnectionType::KeepAlive,
+            &config,
+        );
+        let data = String::from_utf8(Vec::from(bytes.split().freeze().as_ref())).unwrap();
+        assert!(!data.contains("server:"));
+
+        // requests are not affected
+        let mut head = RequestHeadType::Owned(RequestHead::default());
+        let config = ServiceConfig::default()
+            .with_server_header(ServerHeader::Default(HeaderValue::from_static("actix")));
+        let _ = head.encode_headers(
+            &mut bytes,
+            Version::HTTP_11,
+            BodySize::Sized(0),
+            ConnectionType::KeepAlive,
+            &config,
+        );
+        let data = String::from_utf8(Vec::from(bytes.split().freeze().as_ref())).unwrap();
+        assert!(!data.contains("server:"));
+    }
+
     #[actix_rt::test]
     async fn test_no_content_length() {
         let mut bytes = BytesMut::with_capacity(2048);
@@ -668,4 +959,27 @@ mod tests {
         assert!(!data.contains("content-length: 0\r\n"));
         assert!(!data.contains("transfer-encoding: chunked\r\n"));
     }
+
+    #[actix_rt::test]
+    async fn test_manual_transfer_encoding() {
+        let mut bytes = BytesMut::with_capacity(2048);
+
+        let mut res = Response::with_body(StatusCode::OK, ());
+        res.head_mut().no_chunking(true);
+        res.headers_mut()
+            .insert(CONTENT_LENGTH, HeaderValue::from_static("4"));
+        res.headers_mut()
+            .insert(TRANSFER_ENCODING, HeaderValue::from_static("chunked"));
+
+        let _ = res.encode_headers(
+            &mut bytes,
+            Version::HTTP_11,
+            BodySize::Stream,
+            ConnectionType::KeepAlive,
+            &ServiceConfig::default(),
+        );
+        let data = String::from_utf8(Vec::from(bytes.split().freeze().as_ref())).unwrap();
+        assert!(data.contains("content-length: 4\r\n"));
+        assert!(!data.contains("transfer-encoding"));
+    }
 }
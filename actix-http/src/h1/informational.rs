@@ -0,0 +1,200 @@
+use std::{cell::RefCell, fmt, ptr, rc::Rc, task::Waker};
+
+use bytes::{BufMut as _, BytesMut};
+use http::{StatusCode, Version};
+
+use crate::{helpers, Request, RequestHead, Response, ResponseHead};
+
+/// Sender of informational (`1xx`) responses on an HTTP/1 connection.
+///
+/// The HTTP/1 dispatcher adds a sender to the connection data of each connection. Informational
+/// responses, such as `103 Early Hints`, are written to the client ahead of the final response to
+/// the HTTP/1.1 request currently being handled. HTTP/2 connections do not have a sender.
+#[derive(Clone)]
+pub struct InformationalSender {
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl InformationalSender {
+    /// Queues an informational response to be sent ahead of the final response to `req`.
+    ///
+    /// Only the response head is sent; the body is ignored. Returns false, without sending
+    /// anything, if the status code is not informational, is `101 Switching Protocols`, if `req`
+    /// is not the HTTP/1.1 request currently being handled on the connection, or if its final
+    /// response has already been sent.
+    pub fn send<B>(&self, req: &RequestHead, res: Response<B>) -> bool {
+        let status = res.status();
+
+        if !status.is_informational() || status == StatusCode::SWITCHING_PROTOCOLS {
+            return false;
+        }
+
+        let mut shared = self.shared.borrow_mut();
+
+        if !shared.open || !ptr::eq(shared.current, req) {
+            return false;
+        }
+
+        shared.queue.push(res.drop_body());
+
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+
+        true
+    }
+}
+
+impl fmt::Debug for InformationalSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InformationalSender")
+            .finish_non_exhaustive()
+    }
+}
+
+struct Shared {
+    queue: Vec<Response<()>>,
+
+    /// Head of the request whose service call is in progress; only used for comparison.
+    ///
+    /// Heads are not moved while the service holds the request, and can not be reused for a
+    /// later request before it is dropped, so requests of earlier service calls never match.
+    current: *const RequestHead,
+
+    /// Set while the service call for the current request is in progress.
+    open: bool,
+
+    waker: Option<Waker>,
+}
+
+impl Default for Shared {
+    fn default() -> Self {
+        Shared {
+            queue: Vec::new(),
+            current: ptr::null(),
+            open: false,
+            waker: None,
+        }
+    }
+}
+
+/// Dispatcher side of the informational responses queue of an HTTP/1 connection.
+#[derive(Default)]
+pub(crate) struct Informational {
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl Informational {
+    /// Returns a sender of informational responses for this connection.
+    pub(crate) fn sender(&self) -> InformationalSender {
+        InformationalSender {
+            shared: Rc::clone(&self.shared),
+        }
+    }
+
+    /// Starts accepting informational responses for `req`.
+    pub(crate) fn attach(&self, req: &Request) {
+        let mut shared = self.shared.borrow_mut();
+        shared.current = req.head();
+        shared.open = req.version() == Version::HTTP_11;
+        shared.queue.clear();
+    }
+
+    /// Stops accepting informational responses for the current request.
+    pub(crate) fn close(&self) {
+        let mut shared = self.shared.borrow_mut();
+        shared.current = ptr::null();
+        shared.open = false;
+        shared.queue.clear();
+        shared.waker = None;
+    }
+
+    /// Sets the waker that is woken when an informational response is queued.
+    pub(crate) fn register(&self, waker: &Waker) {
+        let mut shared = self.shared.borrow_mut();
+
+        if shared.open {
+            shared.waker = Some(waker.clone());
+        }
+    }
+
+    /// Writes queued informational responses to `buf`.
+    pub(crate) fn write_to(&self, buf: &mut BytesMut) {
+        let mut shared = self.shared.borrow_mut();
+
+        for res in shared.queue.drain(..) {
+            encode_head(res.head(), buf);
+        }
+    }
+}
+
+fn encode_head(head: &ResponseHead, buf: &mut BytesMut) {
+    helpers::write_status_line(Version::HTTP_11, head.status.as_u16(), buf);
+    buf.put_slice(reason(head).as_bytes());
+    buf.put_slice(b"\r\n");
+
+    for (name, value) in head.headers() {
+        buf.put_slice(name.as_str().as_bytes());
+        buf.put_slice(b": ");
+        buf.put_slice(value.as_bytes());
+        buf.put_slice(b"\r\n");
+    }
+
+    buf.put_slice(b"\r\n");
+}
+
+fn reason(head: &ResponseHead) -> &'static str {
+    match head.reason.or_else(|| head.status.canonical_reason()) {
+        Some(reason) => reason,
+        // not known to the `http` crate
+        None if head.status.as_u16() == 103 => "Early Hints",
+        None => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{header, test::TestRequest};
+
+    #[test]
+    fn send_informational() {
+        let info = Informational::default();
+        let sender = info.sender();
+
+        let req = TestRequest::default().finish();
+        assert!(!sender.send(req.head(), Response::new(StatusCode::CONTINUE)));
+
+        info.attach(&req);
+        assert!(!sender.send(req.head(), Response::ok()));
+        assert!(!sender.send(req.head(), Response::new(StatusCode::SWITCHING_PROTOCOLS)));
+
+        let mut res = Response::new(StatusCode::from_u16(103).unwrap());
+        res.headers_mut().insert(
+            header::LINK,
+            header::HeaderValue::from_static("</style.css>; rel=preload"),
+        );
+        assert!(sender.send(req.head(), res));
+
+        let mut buf = BytesMut::new();
+        info.write_to(&mut buf);
+        assert_eq!(
+            &buf[..],
+            b"HTTP/1.1 103 Early Hints\r\nlink: </style.css>; rel=preload\r\n\r\n"
+        );
+
+        info.close();
+        assert!(!sender.send(req.head(), Response::new(StatusCode::CONTINUE)));
+
+        // earlier requests are ignored
+        let next_req = TestRequest::default().finish();
+        info.attach(&next_req);
+        assert!(!sender.send(req.head(), Response::new(StatusCode::CONTINUE)));
+        assert!(sender.send(next_req.head(), Response::new(StatusCode::CONTINUE)));
+
+        let mut req = TestRequest::default().finish();
+        req.head_mut().version = Version::HTTP_10;
+        info.attach(&req);
+        assert!(!sender.send(req.head(), Response::new(StatusCode::CONTINUE)));
+    }
+}
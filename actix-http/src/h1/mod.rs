@@ -11,21 +11,27 @@ mod dispatcher;
 mod dispatcher_tests;
 mod encoder;
 mod expect;
+mod informational;
 mod payload;
 mod service;
 mod timer;
 mod upgrade;
+mod upgraded;
 mod utils;
 
 pub use self::client::{ClientCodec, ClientPayloadCodec};
 pub use self::codec::Codec;
 pub use self::dispatcher::Dispatcher;
 pub use self::expect::ExpectHandler;
+pub use self::informational::InformationalSender;
 pub use self::payload::Payload;
 pub use self::service::{H1Service, H1ServiceHandler};
 pub use self::upgrade::UpgradeHandler;
+pub use self::upgraded::{OnUpgrade, Upgraded};
 pub use self::utils::SendResponse;
 
+pub(crate) use self::decoder::MAX_HEADERS;
+
 #[derive(Debug)]
 /// Codec message
 pub enum Message<T> {
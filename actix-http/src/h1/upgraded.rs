@@ -0,0 +1,128 @@
+use std::{
+    cmp, fmt,
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use actix_codec::{AsyncRead, AsyncWrite, ReadBuf};
+use bytes::{Buf as _, BytesMut};
+use futures_core::future::LocalBoxFuture;
+
+/// Handler of a connection that is switched to another protocol.
+///
+/// Insert it into the extensions of a `101 Switching Protocols` response to an HTTP/1 request that
+/// asks to upgrade the connection. Once the response head has been written to the client, the
+/// HTTP/1 dispatcher stops handling the connection and runs the handler with the [`Upgraded`]
+/// connection until it finishes. It is ignored for other responses and on HTTP/2 connections.
+pub struct OnUpgrade {
+    handler: Box<dyn FnOnce(Upgraded) -> LocalBoxFuture<'static, ()>>,
+}
+
+impl OnUpgrade {
+    /// Constructs an upgrade handler from an async function.
+    pub fn new<F, Fut>(handler: F) -> Self
+    where
+        F: FnOnce(Upgraded) -> Fut + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        OnUpgrade {
+            handler: Box::new(|io| Box::pin(handler(io))),
+        }
+    }
+
+    /// Runs the handler with the upgraded connection.
+    pub(crate) fn call(self, io: Upgraded) -> LocalBoxFuture<'static, ()> {
+        (self.handler)(io)
+    }
+}
+
+impl fmt::Debug for OnUpgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OnUpgrade").finish_non_exhaustive()
+    }
+}
+
+trait Io: AsyncRead + AsyncWrite + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> Io for T {}
+
+/// Connection handed to an [`OnUpgrade`] handler after a `101 Switching Protocols` response.
+///
+/// Reads first return the bytes that were received after the request head, then read from the
+/// connection. Writes go straight to the connection.
+pub struct Upgraded {
+    io: Box<dyn Io>,
+    read_buf: BytesMut,
+}
+
+impl Upgraded {
+    pub(crate) fn new<T>(io: T, read_buf: BytesMut) -> Self
+    where
+        T: AsyncRead + AsyncWrite + Unpin + 'static,
+    {
+        Upgraded {
+            io: Box::new(io),
+            read_buf,
+        }
+    }
+}
+
+impl fmt::Debug for Upgraded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Upgraded")
+            .field("read_buf", &self.read_buf)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AsyncRead for Upgraded {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        if this.read_buf.is_empty() {
+            return Pin::new(&mut this.io).poll_read(cx, buf);
+        }
+
+        let n = cmp::min(buf.remaining(), this.read_buf.len());
+        buf.put_slice(&this.read_buf[..n]);
+        this.read_buf.advance(n);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for Upgraded {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(cx)
+    }
+}
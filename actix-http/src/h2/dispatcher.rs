@@ -6,6 +6,10 @@ use std::{
     net,
     pin::Pin,
     rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
 };
 
@@ -17,19 +21,21 @@ use bytes::{Bytes, BytesMut};
 use futures_core::ready;
 use h2::{
     server::{Connection, SendResponse},
-    Ping, PingPong,
+    Ping, PingPong, Reason,
 };
 use pin_project_lite::pin_project;
 use tracing::{error, trace, warn};
 
 use crate::{
     body::{BodySize, BoxBody, MessageBody},
-    config::ServiceConfig,
+    config::{ServerHeader, ServiceConfig},
+    h2::PayloadLimit,
     header::{
-        HeaderName, HeaderValue, CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING, UPGRADE,
+        HeaderName, HeaderValue, CONNECTION, CONTENT_LENGTH, DATE, SERVER, TRANSFER_ENCODING,
+        UPGRADE,
     },
     service::HttpFlow,
-    Extensions, OnConnectData, Payload, Request, Response, ResponseHead,
+    Extensions, OnConnectData, Payload, Request, Response, ResponseHead, StatusCode,
 };
 
 const CHUNK_SIZE: usize = 16_384;
@@ -108,9 +114,36 @@ where
 
         loop {
             match Pin::new(&mut this.connection).poll_accept(cx)? {
-                Poll::Ready(Some((req, tx))) => {
+                Poll::Ready(Some((req, mut tx))) => {
                     let (parts, body) = req.into_parts();
-                    let payload = crate::h2::Payload::new(body);
+
+                    if this
+                        .config
+                        .exceeds_payload_limit(parts.headers.get(&CONTENT_LENGTH))
+                    {
+                        trace!("request content-length exceeds payload limit; returning 413");
+
+                        let res = Response::new(StatusCode::PAYLOAD_TOO_LARGE);
+                        let config = this.config.clone();
+
+                        actix_rt::spawn(async move {
+                            // failures here mean the client has already gone away
+                            let _ = handle_response(res, &mut tx, config).await;
+
+                            // the request body is not read, ask the client to stop sending it
+                            tx.send_reset(Reason::CANCEL);
+                        });
+
+                        continue;
+                    }
+
+                    let limit = this.config.max_payload_size().map(|max| PayloadLimit {
+                        max,
+                        exceeded: Arc::new(AtomicBool::new(false)),
+                    });
+                    let exceeded = limit.as_ref().map(|limit| Arc::clone(&limit.exceeded));
+
+                    let payload = crate::h2::Payload::new(body, limit);
                     let pl = Payload::H2 { payload };
                     let mut req = Request::with_payload(pl);
 
@@ -130,13 +163,19 @@ where
                     actix_rt::spawn(async move {
                         // resolve service call and send response.
                         let res = match fut.await {
-                            Ok(res) => handle_response(res.into(), tx, config).await,
+                            Ok(res) => handle_response(res.into(), &mut tx, config).await,
                             Err(err) => {
                                 let res: Response<BoxBody> = err.into();
-                                handle_response(res, tx, config).await
+                                handle_response(res, &mut tx, config).await
                             }
                         };
 
+                        // the rest of an oversized request body is discarded, ask the client to
+                        // stop sending it
+                        if exceeded.map_or(false, |exceeded| exceeded.load(Ordering::Acquire)) {
+                            tx.send_reset(Reason::CANCEL);
+                        }
+
                         // log error.
                         if let Err(err) = res {
                             match err {
@@ -197,7 +236,7 @@ enum DispatchError {
 
 async fn handle_response<B>(
     res: Response<B>,
-    mut tx: SendResponse<Bytes>,
+    tx: &mut SendResponse<Bytes>,
     config: ServiceConfig,
 ) -> Result<(), DispatchError>
 where
@@ -269,6 +308,7 @@ fn prepare_response(
     size: &mut BodySize,
 ) -> http::Response<()> {
     let mut has_date = false;
+    let mut has_server = false;
     let mut skip_len = size != &BodySize::Stream;
 
     let mut res = http::Response::new(());
@@ -315,6 +355,8 @@ fn prepare_response(
 
             &CONTENT_LENGTH if skip_len => continue,
             &DATE => has_date = true,
+            &SERVER if *config.server_header() == ServerHeader::Suppress => continue,
+            &SERVER => has_server = true,
 
             // omit HTTP/1.x only headers according to:
             // https://datatracker.ietf.org/doc/html/rfc7540#section-8.1.2.2
@@ -341,5 +383,11 @@ fn prepare_response(
         );
     }
 
+    if let ServerHeader::Default(value) = config.server_header() {
+        if !has_server {
+            res.headers_mut().insert(SERVER, value.clone());
+        }
+    }
+
     res
 }
@@ -3,6 +3,10 @@
 use std::{
     future::Future,
     pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
 };
 
@@ -28,12 +32,25 @@ pub use self::service::H2Service;
 
 /// HTTP/2 peer stream.
 pub struct Payload {
-    stream: RecvStream,
+    /// Dropped once the payload limit is exceeded, discarding buffered and future data.
+    stream: Option<RecvStream>,
+    limit: Option<PayloadLimit>,
+    received: usize,
+}
+
+/// Maximum payload size, and the flag telling the dispatcher to reset the stream once exceeded.
+pub(crate) struct PayloadLimit {
+    pub(crate) max: usize,
+    pub(crate) exceeded: Arc<AtomicBool>,
 }
 
 impl Payload {
-    pub(crate) fn new(stream: RecvStream) -> Self {
-        Self { stream }
+    pub(crate) fn new(stream: RecvStream, limit: Option<PayloadLimit>) -> Self {
+        Self {
+            stream: Some(stream),
+            limit,
+            received: 0,
+        }
     }
 }
 
@@ -43,11 +60,29 @@ impl Stream for Payload {
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.get_mut();
 
-        match ready!(Pin::new(&mut this.stream).poll_data(cx)) {
+        let stream = match this.stream {
+            Some(ref mut stream) => stream,
+            None => return Poll::Ready(None),
+        };
+
+        match ready!(Pin::new(&mut *stream).poll_data(cx)) {
             Some(Ok(chunk)) => {
                 let len = chunk.len();
+                this.received += len;
+
+                let released = stream.flow_control().release_capacity(len);
+
+                if let Some(ref limit) = this.limit {
+                    if this.received > limit.max {
+                        // dropping the stream releases the capacity of buffered data and makes
+                        // h2 discard further data; the dispatcher resets the stream
+                        this.stream = None;
+                        limit.exceeded.store(true, Ordering::Release);
+                        return Poll::Ready(Some(Err(PayloadError::Overflow)));
+                    }
+                }
 
-                match this.stream.flow_control().release_capacity(len) {
+                match released {
                     Ok(()) => Poll::Ready(Some(Ok(chunk))),
                     Err(err) => Poll::Ready(Some(Err(err.into()))),
                 }
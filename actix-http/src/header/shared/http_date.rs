@@ -1,16 +1,30 @@
 use std::{fmt, io::Write, str::FromStr, time::SystemTime};
 
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
 use http::header::{HeaderValue, InvalidHeaderValue};
 
 use crate::{
-    date::DATE_VALUE_LENGTH, error::ParseError, header::TryIntoHeaderValue, helpers::MutWriter,
+    date::{cached_date, DATE_VALUE_LENGTH},
+    error::ParseError,
+    header::TryIntoHeaderValue,
+    helpers::MutWriter,
 };
 
 /// A timestamp with HTTP-style formatting and parsing.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct HttpDate(SystemTime);
 
+impl HttpDate {
+    /// Returns the current time.
+    ///
+    /// On server worker threads this is the time kept by the server's date service, which is
+    /// refreshed every 500ms and used for `Date` response headers. Converting it to a header value
+    /// reuses the already formatted date. Elsewhere, this falls back to [`SystemTime::now`].
+    pub fn now() -> HttpDate {
+        HttpDate(cached_date().map_or_else(SystemTime::now, |date| date.time))
+    }
+}
+
 impl FromStr for HttpDate {
     type Err = ParseError;
 
@@ -33,6 +47,10 @@ impl TryIntoHeaderValue for HttpDate {
     type Error = InvalidHeaderValue;
 
     fn try_into_value(self) -> Result<HeaderValue, Self::Error> {
+        if let Some(date) = cached_date().filter(|date| date.time == self.0) {
+            return HeaderValue::from_maybe_shared(Bytes::copy_from_slice(&date.bytes));
+        }
+
         let mut buf = BytesMut::with_capacity(DATE_VALUE_LENGTH);
         let mut wrt = MutWriter(&mut buf);
 
@@ -78,4 +96,19 @@ mod tests {
 
         assert!("this-is-no-date".parse::<HttpDate>().is_err());
     }
+
+    #[actix_rt::test]
+    async fn now_uses_date_service() {
+        assert!(cached_date().is_none());
+
+        let service = crate::date::DateService::new();
+        let now = HttpDate::now();
+
+        let mut expected = [0; DATE_VALUE_LENGTH];
+        service.with_date(|date| expected = date.bytes);
+        assert_eq!(now.try_into_value().unwrap().as_bytes(), &expected[..]);
+
+        drop(service);
+        assert!(cached_date().is_none());
+    }
 }
@@ -10,6 +10,7 @@
 //! | `compress-gzip`     | Payload compression support: Deflate, Gzip. |
 //! | `compress-zstd`     | Payload compression support: Zstd.          |
 //! | `trust-dns`         | Use [trust-dns] as the client DNS resolver. |
+//! | `wire-tap`          | Capture of raw HTTP/1 connection traffic.   |
 //!
 //! [h2]: https://crates.io/crates/h2
 //! [OpenSSL]: https://crates.io/crates/openssl
@@ -51,12 +52,13 @@ mod payload;
 mod requests;
 mod responses;
 mod service;
+mod tap;
 pub mod test;
 #[cfg(feature = "ws")]
 pub mod ws;
 
 pub use self::builder::HttpServiceBuilder;
-pub use self::config::ServiceConfig;
+pub use self::config::{ServerHeader, ServiceConfig};
 pub use self::error::Error;
 pub use self::extensions::Extensions;
 pub use self::header::ContentEncoding;
@@ -69,6 +71,8 @@ pub use self::payload::{BoxedPayloadStream, Payload, PayloadStream};
 pub use self::requests::{Request, RequestHead, RequestHeadType};
 pub use self::responses::{Response, ResponseBuilder, ResponseHead};
 pub use self::service::HttpService;
+#[cfg(feature = "wire-tap")]
+pub use self::tap::TapEvent;
 
 /// A major HTTP protocol version.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
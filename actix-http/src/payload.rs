@@ -57,7 +57,7 @@ impl<S> From<crate::h2::Payload> for Payload<S> {
 impl<S> From<::h2::RecvStream> for Payload<S> {
     fn from(stream: ::h2::RecvStream) -> Self {
         Payload::H2 {
-            payload: crate::h2::Payload::new(stream),
+            payload: crate::h2::Payload::new(stream, None),
         }
     }
 }
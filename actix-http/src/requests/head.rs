@@ -75,6 +75,23 @@ impl RequestHead {
         }
     }
 
+    /// Is to send the request target in absolute form, i.e. the full URI.
+    /// Default is `false`
+    #[inline]
+    pub fn absolute_form(&self) -> bool {
+        self.flags.contains(Flags::ABSOLUTE)
+    }
+
+    /// Set `true` to send the full URI as request target, as required by HTTP proxies.
+    #[inline]
+    pub fn set_absolute_form(&mut self, val: bool) {
+        if val {
+            self.flags.insert(Flags::ABSOLUTE);
+        } else {
+            self.flags.remove(Flags::ABSOLUTE);
+        }
+    }
+
     #[inline]
     /// Set connection type of the message
     pub fn set_connection_type(&mut self, ctype: ConnectionType) {
@@ -201,12 +201,22 @@ impl ResponseBuilder {
 
     /// Generate response with a wrapped body.
     ///
+    /// Manually set `Content-Length` or `Transfer-Encoding` headers that conflict with the body
+    /// result in an error response; see [`BodySize::check_framing`].
+    ///
     /// This `ResponseBuilder` will be left in a useless state.
+    ///
+    /// [`BodySize::check_framing`]: crate::body::BodySize::check_framing
     pub fn body<B>(&mut self, body: B) -> Response<EitherBody<B>>
     where
         B: MessageBody + 'static,
     {
-        match self.message_body(body) {
+        let res = self.message_body(body).and_then(|res| {
+            res.body().size().check_framing(res.headers())?;
+            Ok(res)
+        });
+
+        match res {
             Ok(res) => res.map_body(|_, body| EitherBody::left(body)),
             Err(err) => Response::from(err).map_body(|_, body| EitherBody::right(body)),
         }
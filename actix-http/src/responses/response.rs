@@ -9,7 +9,7 @@ use bytes::{Bytes, BytesMut};
 use bytestring::ByteString;
 
 use crate::{
-    body::{BoxBody, EitherBody, MessageBody},
+    body::{self, BodySender, BoxBody, ChannelBody, EitherBody, MessageBody},
     header::{self, HeaderMap, TryIntoHeaderValue},
     responses::BoxedResponseHead,
     Error, Extensions, ResponseBuilder, ResponseHead, StatusCode,
@@ -69,6 +69,18 @@ impl Response<BoxBody> {
     // end shortcuts
 }
 
+impl Response<ChannelBody> {
+    /// Constructs a streaming response whose body is fed from a bounded channel.
+    ///
+    /// The returned sender can be moved to another task to push chunks after the response has
+    /// been returned from the handler. See [`body::channel`] for details on backpressure and
+    /// aborting the stream.
+    pub fn streaming_channel(status: StatusCode) -> (BodySender, Response<ChannelBody>) {
+        let (tx, body) = body::channel(body::DEFAULT_CHANNEL_CAPACITY);
+        (tx, Response::with_body(status, body))
+    }
+}
+
 impl<B> Response<B> {
     /// Constructs a new response with given body.
     #[inline]
@@ -366,6 +378,19 @@ mod tests {
         assert!(dbg.contains("Response"));
     }
 
+    #[actix_rt::test]
+    async fn test_streaming_channel() {
+        let (mut tx, res) = Response::streaming_channel(StatusCode::OK);
+        assert_eq!(res.status(), StatusCode::OK);
+
+        actix_rt::spawn(async move {
+            tx.send(Bytes::from_static(b"foo")).await.unwrap();
+            tx.send(Bytes::from_static(b"bar")).await.unwrap();
+        });
+
+        assert_eq!(to_bytes(res.into_body()).await.unwrap(), &b"foobar"[..]);
+    }
+
     #[actix_rt::test]
     async fn test_into_response() {
         let res = Response::from("test");
@@ -0,0 +1,129 @@
+//! Capture of raw connection traffic, for debugging.
+//!
+//! See [`HttpServiceBuilder::wire_tap`](crate::HttpServiceBuilder::wire_tap) for details. Without
+//! the `wire-tap` feature, the dispatcher hooks in this module compile to nothing.
+
+use std::net;
+
+#[cfg(feature = "wire-tap")]
+use std::{fmt, rc::Rc};
+
+use crate::ServiceConfig;
+
+/// Traffic observed on a connection by a wire tap.
+#[cfg(feature = "wire-tap")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TapEvent<'a> {
+    /// Bytes read from the client.
+    Inbound(&'a [u8]),
+
+    /// Bytes written to the client.
+    Outbound(&'a [u8]),
+
+    /// The connection was closed by either side, or handed over to an upgrade service.
+    Closed,
+}
+
+/// Per-connection receiver of [`TapEvent`]s.
+#[cfg(feature = "wire-tap")]
+pub(crate) type ConnectionTap = Box<dyn FnMut(TapEvent<'_>)>;
+
+/// Creates a [`ConnectionTap`] for each new connection, given its peer address.
+#[cfg(feature = "wire-tap")]
+#[derive(Clone)]
+pub(crate) struct WireTap(Rc<dyn Fn(Option<net::SocketAddr>) -> ConnectionTap>);
+
+#[cfg(feature = "wire-tap")]
+impl WireTap {
+    pub(crate) fn new<F, C>(tap: F) -> Self
+    where
+        F: Fn(Option<net::SocketAddr>) -> C + 'static,
+        C: FnMut(TapEvent<'_>) + 'static,
+    {
+        WireTap(Rc::new(move |peer_addr| Box::new(tap(peer_addr))))
+    }
+
+    pub(crate) fn connect(&self, peer_addr: Option<net::SocketAddr>) -> TapHandle {
+        TapHandle((self.0)(peer_addr))
+    }
+}
+
+#[cfg(feature = "wire-tap")]
+impl fmt::Debug for WireTap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WireTap").finish_non_exhaustive()
+    }
+}
+
+/// Tap of a single connection; reports [`TapEvent::Closed`] when dropped.
+#[cfg(feature = "wire-tap")]
+pub(crate) struct TapHandle(ConnectionTap);
+
+#[cfg(feature = "wire-tap")]
+impl TapHandle {
+    pub(crate) fn inbound(&mut self, bytes: &[u8]) {
+        if !bytes.is_empty() {
+            (self.0)(TapEvent::Inbound(bytes));
+        }
+    }
+
+    pub(crate) fn outbound(&mut self, bytes: &[u8]) {
+        if !bytes.is_empty() {
+            (self.0)(TapEvent::Outbound(bytes));
+        }
+    }
+}
+
+#[cfg(feature = "wire-tap")]
+impl Drop for TapHandle {
+    fn drop(&mut self) {
+        (self.0)(TapEvent::Closed);
+    }
+}
+
+/// Tap of a dispatcher's connection, if any.
+#[cfg(feature = "wire-tap")]
+pub(crate) type Tap = Option<TapHandle>;
+
+/// Tap of a dispatcher's connection, if any.
+#[cfg(not(feature = "wire-tap"))]
+pub(crate) type Tap = ();
+
+/// Creates the tap of a new connection, if the service has a wire tap.
+#[inline]
+pub(crate) fn connect(config: &ServiceConfig, peer_addr: Option<net::SocketAddr>) -> Tap {
+    #[cfg(feature = "wire-tap")]
+    {
+        config.wire_tap().map(|tap| tap.connect(peer_addr))
+    }
+
+    #[cfg(not(feature = "wire-tap"))]
+    {
+        let _ = (config, peer_addr);
+    }
+}
+
+/// Reports bytes read from the client.
+#[inline]
+pub(crate) fn inbound(tap: &mut Tap, bytes: &[u8]) {
+    #[cfg(feature = "wire-tap")]
+    if let Some(tap) = tap {
+        tap.inbound(bytes);
+    }
+
+    #[cfg(not(feature = "wire-tap"))]
+    let _ = (tap, bytes);
+}
+
+/// Reports bytes written to the client.
+#[inline]
+pub(crate) fn outbound(tap: &mut Tap, bytes: &[u8]) {
+    #[cfg(feature = "wire-tap")]
+    if let Some(tap) = tap {
+        tap.outbound(bytes);
+    }
+
+    #[cfg(not(feature = "wire-tap"))]
+    let _ = (tap, bytes);
+}
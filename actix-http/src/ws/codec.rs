@@ -7,7 +7,7 @@ use tracing::error;
 use super::{
     frame::Parser,
     proto::{CloseReason, OpCode},
-    ProtocolError,
+    ProtocolError, Stats,
 };
 
 /// A WebSocket message.
@@ -71,6 +71,7 @@ pub enum Item {
 pub struct Codec {
     flags: Flags,
     max_size: usize,
+    stats: Option<Stats>,
 }
 
 bitflags! {
@@ -78,6 +79,7 @@ bitflags! {
         const SERVER         = 0b0000_0001;
         const CONTINUATION   = 0b0000_0010;
         const W_CONTINUATION = 0b0000_0100;
+        const LENIENT_MASK   = 0b0000_1000;
     }
 }
 
@@ -87,6 +89,7 @@ impl Codec {
         Codec {
             max_size: 65_536,
             flags: Flags::SERVER,
+            stats: None,
         }
     }
 
@@ -99,14 +102,36 @@ impl Codec {
         self
     }
 
-    /// Set decoder to client mode.
+    /// Set codec to client mode.
     ///
-    /// By default decoder works in server mode.
+    /// In client mode, outgoing frames are masked and received frames are expected to be unmasked,
+    /// as required for the client end of a connection. By default codec works in server mode,
+    /// which is the reverse.
     #[must_use = "This returns the a new Codec, without modifying the original."]
     pub fn client_mode(mut self) -> Self {
         self.flags.remove(Flags::SERVER);
         self
     }
+
+    /// Set whether the masking of received frames is enforced.
+    ///
+    /// When enforced, a server rejects unmasked frames and a client rejects masked frames, as
+    /// required by RFC 6455. Disabling enforcement accepts frames either way, which can help
+    /// interoperate with non-compliant peers. Enabled by default.
+    #[must_use = "This returns the a new Codec, without modifying the original."]
+    pub fn enforce_masking(mut self, enforce: bool) -> Self {
+        self.flags.set(Flags::LENIENT_MASK, !enforce);
+        self
+    }
+
+    /// Set a handle counting the frames encoded and decoded by the codec.
+    ///
+    /// Clones of the codec share the handle. See [`Stats`] for the recorded counters.
+    #[must_use = "This returns the a new Codec, without modifying the original."]
+    pub fn with_stats(mut self, stats: Stats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
 }
 
 impl Default for Codec {
@@ -115,10 +140,12 @@ impl Default for Codec {
     }
 }
 
-impl Encoder<Message> for Codec {
-    type Error = ProtocolError;
-
-    fn encode(&mut self, item: Message, dst: &mut BytesMut) -> Result<(), Self::Error> {
+impl Codec {
+    fn encode_message(
+        &mut self,
+        item: Message,
+        dst: &mut BytesMut,
+    ) -> Result<(), ProtocolError> {
         match item {
             Message::Text(txt) => Parser::write_message(
                 dst,
@@ -214,12 +241,47 @@ impl Encoder<Message> for Codec {
     }
 }
 
-impl Decoder for Codec {
-    type Item = Frame;
+impl Encoder<Message> for Codec {
     type Error = ProtocolError;
 
-    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        match Parser::parse(src, self.flags.contains(Flags::SERVER), self.max_size) {
+    fn encode(&mut self, item: Message, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if self.stats.is_none() {
+            return self.encode_message(item, dst);
+        }
+
+        let sent = match item {
+            Message::Text(_) => Some((OpCode::Text, None)),
+            Message::Binary(_) => Some((OpCode::Binary, None)),
+            Message::Ping(_) => Some((OpCode::Ping, None)),
+            Message::Pong(_) => Some((OpCode::Pong, None)),
+            Message::Close(ref reason) => {
+                Some((OpCode::Close, reason.as_ref().map(|reason| reason.code)))
+            }
+            Message::Continuation(Item::FirstText(_)) => Some((OpCode::Text, None)),
+            Message::Continuation(Item::FirstBinary(_)) => Some((OpCode::Binary, None)),
+            Message::Continuation(_) => Some((OpCode::Continue, None)),
+            Message::Nop => None,
+        };
+
+        let start = dst.len();
+        self.encode_message(item, dst)?;
+
+        if let (Some(stats), Some((opcode, close_code))) = (&self.stats, sent) {
+            stats.record_sent(opcode, dst.len() - start, close_code);
+        }
+
+        Ok(())
+    }
+}
+
+impl Codec {
+    fn decode_frame(&mut self, src: &mut BytesMut) -> Result<Option<Frame>, ProtocolError> {
+        match Parser::parse_frame(
+            src,
+            self.flags.contains(Flags::SERVER),
+            !self.flags.contains(Flags::LENIENT_MASK),
+            self.max_size,
+        ) {
             Ok(Some((finished, opcode, payload))) => {
                 // continuation is not supported
                 if !finished {
@@ -299,3 +361,34 @@ impl Decoder for Codec {
         }
     }
 }
+
+impl Decoder for Codec {
+    type Item = Frame;
+    type Error = ProtocolError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let start = src.len();
+        let frame = self.decode_frame(src)?;
+
+        if let (Some(stats), Some(frame)) = (&self.stats, &frame) {
+            let (opcode, close_code) = match frame {
+                Frame::Text(_) | Frame::Continuation(Item::FirstText(_)) => {
+                    (OpCode::Text, None)
+                }
+                Frame::Binary(_) | Frame::Continuation(Item::FirstBinary(_)) => {
+                    (OpCode::Binary, None)
+                }
+                Frame::Continuation(_) => (OpCode::Continue, None),
+                Frame::Ping(_) => (OpCode::Ping, None),
+                Frame::Pong(_) => (OpCode::Pong, None),
+                Frame::Close(reason) => {
+                    (OpCode::Close, reason.as_ref().map(|reason| reason.code))
+                }
+            };
+
+            stats.record_received(opcode, start - src.len(), close_code);
+        }
+
+        Ok(frame)
+    }
+}
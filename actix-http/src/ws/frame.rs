@@ -17,6 +17,7 @@ impl Parser {
     fn parse_metadata(
         src: &[u8],
         server: bool,
+        enforce_masking: bool,
         max_size: usize,
     ) -> Result<Option<(usize, bool, OpCode, usize, Option<[u8; 4]>)>, ProtocolError> {
         let chunk_len = src.len();
@@ -32,9 +33,9 @@ impl Parser {
 
         // check masking
         let masked = second & 0x80 != 0;
-        if !masked && server {
+        if enforce_masking && !masked && server {
             return Err(ProtocolError::UnmaskedFrame);
-        } else if masked && !server {
+        } else if enforce_masking && masked && !server {
             return Err(ProtocolError::MaskedFrame);
         }
 
@@ -74,7 +75,7 @@ impl Parser {
             return Err(ProtocolError::Overflow);
         }
 
-        let mask = if server {
+        let mask = if masked {
             if chunk_len < idx + 4 {
                 return Ok(None);
             }
@@ -92,14 +93,26 @@ impl Parser {
     }
 
     /// Parse the input stream into a frame.
+    ///
+    /// Servers reject unmasked frames and clients reject masked frames.
     pub fn parse(
         src: &mut BytesMut,
         server: bool,
         max_size: usize,
+    ) -> Result<Option<(bool, OpCode, Option<BytesMut>)>, ProtocolError> {
+        Parser::parse_frame(src, server, true, max_size)
+    }
+
+    /// Parse the input stream into a frame, optionally accepting frames regardless of masking.
+    pub(super) fn parse_frame(
+        src: &mut BytesMut,
+        server: bool,
+        enforce_masking: bool,
+        max_size: usize,
     ) -> Result<Option<(bool, OpCode, Option<BytesMut>)>, ProtocolError> {
         // try to parse ws frame metadata
         let (idx, finished, opcode, length, mask) =
-            match Parser::parse_metadata(src, server, max_size)? {
+            match Parser::parse_metadata(src, server, enforce_masking, max_size)? {
                 None => return Ok(None),
                 Some(res) => res,
             };
@@ -140,19 +153,47 @@ impl Parser {
     }
 
     /// Parse the payload of a close frame.
+    ///
+    /// Payloads that are invalid per [RFC 6455 §7.4] are mapped to the close reason to respond
+    /// with: an invalid UTF-8 description to [`CloseCode::Invalid`] and a truncated payload or a
+    /// close code that must not be sent to [`CloseCode::Protocol`].
+    ///
+    /// [RFC 6455 §7.4]: https://datatracker.ietf.org/doc/html/rfc6455#section-7.4
     pub fn parse_close_payload(payload: &[u8]) -> Option<CloseReason> {
-        if payload.len() >= 2 {
-            let raw_code = u16::from_be_bytes(TryFrom::try_from(&payload[..2]).unwrap());
-            let code = CloseCode::from(raw_code);
-            let description = if payload.len() > 2 {
-                Some(String::from_utf8_lossy(&payload[2..]).into())
-            } else {
-                None
-            };
-            Some(CloseReason { code, description })
+        if payload.is_empty() {
+            return None;
+        }
+
+        if payload.len() == 1 {
+            debug!("Received close frame with truncated close code.");
+            return Some(CloseCode::Protocol.into());
+        }
+
+        let raw_code = u16::from_be_bytes(TryFrom::try_from(&payload[..2]).unwrap());
+        if !CloseCode::is_allowed_on_wire(raw_code) {
+            debug!(
+                "Received close frame with reserved close code {}.",
+                raw_code
+            );
+            return Some(CloseCode::Protocol.into());
+        }
+
+        let description = if payload.len() > 2 {
+            match std::str::from_utf8(&payload[2..]) {
+                Ok(description) => Some(description.to_owned()),
+                Err(_) => {
+                    debug!("Received close frame with invalid UTF-8 description.");
+                    return Some(CloseCode::Invalid.into());
+                }
+            }
         } else {
             None
-        }
+        };
+
+        Some(CloseReason {
+            code: CloseCode::from(raw_code),
+            description,
+        })
     }
 
     /// Generate binary representation
@@ -208,7 +249,7 @@ impl Parser {
             Some(reason) => {
                 let mut payload = Into::<u16>::into(reason.code).to_be_bytes().to_vec();
                 if let Some(description) = reason.description {
-                    payload.extend(description.as_bytes());
+                    payload.extend(truncate_description(&description).as_bytes());
                 }
                 payload
             }
@@ -218,6 +259,19 @@ impl Parser {
     }
 }
 
+/// Maximum length of a close description, so that the close frame fits in a control frame.
+const MAX_CLOSE_DESCRIPTION: usize = 123;
+
+/// Truncates a close description to fit in a control frame, keeping it valid UTF-8.
+fn truncate_description(description: &str) -> &str {
+    let mut len = description.len().min(MAX_CLOSE_DESCRIPTION);
+    while !description.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    &description[..len]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -326,6 +380,22 @@ mod tests {
         assert_eq!(frame.payload, Bytes::from(vec![1u8]));
     }
 
+    #[test]
+    fn test_parse_frame_lenient_masking() {
+        let mut buf = BytesMut::from(&[0b1000_0001u8, 0b1000_0001u8][..]);
+        buf.extend(b"0001");
+        buf.extend(b"1");
+        buf.extend(&[0b1000_0001u8, 0b0000_0001u8, 1u8]);
+
+        for _ in 0..2 {
+            let frame = Parser::parse_frame(&mut buf, false, false, 1024)
+                .unwrap()
+                .unwrap();
+            assert_eq!(frame.1, OpCode::Text);
+            assert_eq!(frame.2.unwrap(), Bytes::from(vec![1u8]));
+        }
+    }
+
     #[test]
     fn test_parse_frame_max_size() {
         let mut buf = BytesMut::from(&[0b0000_0001u8, 0b0000_0010u8][..]);
@@ -376,4 +446,52 @@ mod tests {
         Parser::write_close(&mut buf, None, false);
         assert_eq!(&buf[..], &vec![0x88, 0x00][..]);
     }
+
+    #[test]
+    fn test_close_frame_truncation() {
+        let mut buf = BytesMut::new();
+        let description = format!("{}\u{e9}", "a".repeat(122));
+        Parser::write_close(
+            &mut buf,
+            Some((CloseCode::Normal, description).into()),
+            false,
+        );
+
+        // the two byte character would exceed the limit
+        assert_eq!(&buf[..2], &[0x88, 124]);
+        assert_eq!(&buf[4..], "a".repeat(122).as_bytes());
+    }
+
+    #[test]
+    fn test_parse_close_payload() {
+        assert_eq!(Parser::parse_close_payload(b""), None);
+        assert_eq!(
+            Parser::parse_close_payload(b"\x03\xe8bye"),
+            Some((CloseCode::Normal, "bye").into())
+        );
+        assert_eq!(
+            Parser::parse_close_payload(b"\x0f\xa0"),
+            Some(CloseCode::Other(4000).into())
+        );
+
+        // invalid UTF-8
+        assert_eq!(
+            Parser::parse_close_payload(b"\x03\xe8\xff"),
+            Some(CloseCode::Invalid.into())
+        );
+
+        // truncated code and codes that must not be sent
+        for payload in [
+            &b"\x03"[..],
+            b"\x03\xe7",
+            b"\x03\xee",
+            b"\x03\xf7",
+            b"\x07\xd0",
+        ] {
+            assert_eq!(
+                Parser::parse_close_payload(payload),
+                Some(CloseCode::Protocol.into())
+            );
+        }
+    }
 }
@@ -3,7 +3,15 @@
 /// Mask/unmask a frame.
 #[inline]
 pub fn apply_mask(buf: &mut [u8], mask: [u8; 4]) {
-    apply_mask_fast32(buf, mask)
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    {
+        apply_mask_sse2(buf, mask)
+    }
+
+    #[cfg(not(all(target_arch = "x86_64", target_feature = "sse2")))]
+    {
+        apply_mask_fast64(buf, mask)
+    }
 }
 
 /// A safe unoptimized mask application.
@@ -14,33 +22,68 @@ fn apply_mask_fallback(buf: &mut [u8], mask: [u8; 4]) {
     }
 }
 
-/// Faster version of `apply_mask()` which operates on 4-byte blocks.
+/// Faster version of `apply_mask()` which operates on 8-byte blocks.
 #[inline]
-pub fn apply_mask_fast32(buf: &mut [u8], mask: [u8; 4]) {
-    let mask_u32 = u32::from_ne_bytes(mask);
+#[allow(dead_code)] // unused on targets with a SIMD implementation
+fn apply_mask_fast64(buf: &mut [u8], mask: [u8; 4]) {
+    let mask_u64 = u64::from_ne_bytes([
+        mask[0], mask[1], mask[2], mask[3], mask[0], mask[1], mask[2], mask[3],
+    ]);
 
     // SAFETY:
     //
     // buf is a valid slice borrowed mutably from bytes::BytesMut.
     //
     // un aligned prefix and suffix would be mask/unmask per byte.
-    // proper aligned middle slice goes into fast path and operates on 4-byte blocks.
-    let (prefix, words, suffix) = unsafe { buf.align_to_mut::<u32>() };
+    // proper aligned middle slice goes into fast path and operates on 8-byte blocks.
+    let (prefix, words, suffix) = unsafe { buf.align_to_mut::<u64>() };
     apply_mask_fallback(prefix, mask);
+
+    // the mask repeats every 4 bytes so only the prefix length modulo 4 shifts it
     let head = prefix.len() & 3;
-    let mask_u32 = if head > 0 {
+    let mask_u64 = if head > 0 {
         if cfg!(target_endian = "big") {
-            mask_u32.rotate_left(8 * head as u32)
+            mask_u64.rotate_left(8 * head as u32)
         } else {
-            mask_u32.rotate_right(8 * head as u32)
+            mask_u64.rotate_right(8 * head as u32)
         }
     } else {
-        mask_u32
+        mask_u64
     };
+
     for word in words.iter_mut() {
-        *word ^= mask_u32;
+        *word ^= mask_u64;
     }
-    apply_mask_fallback(suffix, mask_u32.to_ne_bytes());
+
+    let mask = mask_u64.to_ne_bytes();
+    apply_mask_fallback(suffix, [mask[0], mask[1], mask[2], mask[3]]);
+}
+
+/// SSE2 version of `apply_mask()` which operates on 16-byte blocks.
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+#[inline]
+fn apply_mask_sse2(buf: &mut [u8], mask: [u8; 4]) {
+    use std::arch::x86_64::{
+        __m128i, _mm_loadu_si128, _mm_set1_epi32, _mm_storeu_si128, _mm_xor_si128,
+    };
+
+    let mut chunks = buf.chunks_exact_mut(16);
+
+    // SAFETY:
+    //
+    // SSE2 is statically enabled for this target. Each chunk is exactly 16 bytes long and the
+    // unaligned load and store intrinsics have no alignment requirements.
+    unsafe {
+        let mask_128 = _mm_set1_epi32(i32::from_ne_bytes(mask));
+
+        for chunk in &mut chunks {
+            let ptr = chunk.as_mut_ptr() as *mut __m128i;
+            _mm_storeu_si128(ptr, _mm_xor_si128(_mm_loadu_si128(ptr), mask_128));
+        }
+    }
+
+    // chunks are a multiple of 4 bytes long so the mask is not shifted for the remainder
+    apply_mask_fallback(chunks.into_remainder(), mask);
 }
 
 #[cfg(test)]
@@ -50,15 +93,14 @@ mod tests {
     #[test]
     fn test_apply_mask() {
         let mask = [0x6d, 0xb6, 0xb2, 0x80];
-        let unmasked = vec![
-            0xf3, 0x00, 0x01, 0x02, 0x03, 0x80, 0x81, 0x82, 0xff, 0xfe, 0x00, 0x17, 0x74, 0xf9,
-            0x12, 0x03,
-        ];
+        let unmasked = (0..80u8)
+            .map(|i| i.wrapping_mul(37) ^ 0xa5)
+            .collect::<Vec<_>>();
 
         for data_len in 0..=unmasked.len() {
             let unmasked = &unmasked[0..data_len];
             // Check masking with different alignment.
-            for off in 0..=3 {
+            for off in 0..=7 {
                 if unmasked.len() < off {
                     continue;
                 }
@@ -66,9 +108,16 @@ mod tests {
                 apply_mask_fallback(&mut masked[off..], mask);
 
                 let mut masked_fast = unmasked.to_vec();
-                apply_mask_fast32(&mut masked_fast[off..], mask);
-
+                apply_mask_fast64(&mut masked_fast[off..], mask);
                 assert_eq!(masked, masked_fast);
+
+                let mut masked_default = unmasked.to_vec();
+                apply_mask(&mut masked_default[off..], mask);
+                assert_eq!(masked, masked_default);
+
+                // masking is its own inverse
+                apply_mask(&mut masked_default[off..], mask);
+                assert_eq!(unmasked, &masked_default[..]);
             }
         }
     }
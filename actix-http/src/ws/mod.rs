@@ -15,12 +15,16 @@ mod codec;
 mod dispatcher;
 mod frame;
 mod mask;
+mod origin;
 mod proto;
+mod stats;
 
 pub use self::codec::{Codec, Frame, Item, Message};
 pub use self::dispatcher::Dispatcher;
 pub use self::frame::Parser;
+pub use self::origin::AllowedOrigins;
 pub use self::proto::{hash_key, CloseCode, CloseReason, OpCode};
+pub use self::stats::Stats;
 
 /// WebSocket protocol errors.
 #[derive(Debug, Display, Error, From)]
@@ -144,6 +148,47 @@ impl From<&HandshakeError> for Response<BoxBody> {
     }
 }
 
+/// WebSocket handshake errors, including origin checks.
+///
+/// Returned by [`verify_handshake_with_origins`] and [`AllowedOrigins::verify`].
+#[derive(Debug, Clone, Copy, PartialEq, Display, Error)]
+#[non_exhaustive]
+pub enum OriginHandshakeError {
+    /// Handshake request is not valid.
+    #[display(fmt = "{}", _0)]
+    Handshake(HandshakeError),
+
+    /// Origin is not allowed to open a WebSocket connection.
+    #[display(fmt = "Origin not allowed.")]
+    OriginNotAllowed,
+}
+
+impl From<HandshakeError> for OriginHandshakeError {
+    fn from(err: HandshakeError) -> Self {
+        OriginHandshakeError::Handshake(err)
+    }
+}
+
+impl From<OriginHandshakeError> for Response<BoxBody> {
+    fn from(err: OriginHandshakeError) -> Self {
+        match err {
+            OriginHandshakeError::Handshake(err) => err.into(),
+
+            OriginHandshakeError::OriginNotAllowed => {
+                let mut res = Response::new(StatusCode::FORBIDDEN);
+                res.head_mut().reason = Some("Origin not allowed");
+                res
+            }
+        }
+    }
+}
+
+impl From<&OriginHandshakeError> for Response<BoxBody> {
+    fn from(err: &OriginHandshakeError) -> Self {
+        (*err).into()
+    }
+}
+
 /// Verify WebSocket handshake request and create handshake response.
 pub fn handshake(req: &RequestHead) -> Result<ResponseBuilder, HandshakeError> {
     verify_handshake(req)?;
@@ -198,6 +243,17 @@ pub fn verify_handshake(req: &RequestHead) -> Result<(), HandshakeError> {
     Ok(())
 }
 
+/// Verify WebSocket handshake request, including that its `Origin` is allowed.
+///
+/// See [`AllowedOrigins`] for why browser facing endpoints should check the origin.
+pub fn verify_handshake_with_origins(
+    req: &RequestHead,
+    origins: &AllowedOrigins,
+) -> Result<(), OriginHandshakeError> {
+    verify_handshake(req)?;
+    origins.verify(req)
+}
+
 /// Create WebSocket handshake response.
 ///
 /// This function returns handshake `Response`, ready to send to peer.
@@ -348,5 +404,10 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
         let resp: Response<BoxBody> = HandshakeError::BadWebsocketKey.into();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp: Response<BoxBody> = OriginHandshakeError::OriginNotAllowed.into();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let resp: Response<BoxBody> =
+            OriginHandshakeError::from(HandshakeError::GetMethodRequired).into();
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
     }
 }
@@ -0,0 +1,140 @@
+use std::{fmt, sync::Arc};
+
+use http::header;
+
+use super::OriginHandshakeError;
+use crate::RequestHead;
+
+/// Origins that are allowed to open WebSocket connections.
+///
+/// Browsers attach cookies to WebSocket handshakes regardless of the page that initiated them, and
+/// WebSockets are not subject to the same-origin policy. Without checking the `Origin` header, a
+/// page on any site can open a connection authenticated as the visiting user, which is known as
+/// cross-site WebSocket hijacking.
+///
+/// Handshakes without an `Origin` header are allowed, since browsers always send one; other
+/// clients are not affected by the attack.
+///
+/// # Examples
+/// ```
+/// use actix_http::ws::AllowedOrigins;
+///
+/// let origins = AllowedOrigins::list(["https://example.com", "https://app.example.com"]);
+/// assert!(origins.is_allowed("https://example.com"));
+/// assert!(!origins.is_allowed("https://evil.example"));
+///
+/// let origins = AllowedOrigins::predicate(|origin| origin.ends_with(".example.com"));
+/// assert!(origins.is_allowed("https://app.example.com"));
+/// ```
+#[derive(Clone)]
+pub struct AllowedOrigins {
+    inner: Inner,
+}
+
+#[derive(Clone)]
+enum Inner {
+    List(Vec<String>),
+    Predicate(Arc<dyn Fn(&str) -> bool + Send + Sync>),
+}
+
+impl AllowedOrigins {
+    /// Allows the given origins, such as `https://example.com`.
+    ///
+    /// Origins are compared case-insensitively and must not have a trailing slash.
+    pub fn list<I, S>(origins: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let origins = origins
+            .into_iter()
+            .map(|origin| origin.into().to_ascii_lowercase())
+            .collect();
+
+        AllowedOrigins {
+            inner: Inner::List(origins),
+        }
+    }
+
+    /// Allows origins for which `predicate` returns true.
+    ///
+    /// The predicate receives the value of the `Origin` header as sent by the client.
+    pub fn predicate<F>(predicate: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        AllowedOrigins {
+            inner: Inner::Predicate(Arc::new(predicate)),
+        }
+    }
+
+    /// Returns true if `origin` is allowed.
+    pub fn is_allowed(&self, origin: &str) -> bool {
+        match self.inner {
+            Inner::List(ref origins) => origins
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(origin)),
+            Inner::Predicate(ref predicate) => predicate(origin),
+        }
+    }
+
+    /// Checks the `Origin` header of a handshake request.
+    pub fn verify(&self, req: &RequestHead) -> Result<(), OriginHandshakeError> {
+        let origin = match req.headers().get(header::ORIGIN) {
+            Some(origin) => origin,
+            None => return Ok(()),
+        };
+
+        match origin.to_str() {
+            Ok(origin) if self.is_allowed(origin) => Ok(()),
+            _ => Err(OriginHandshakeError::OriginNotAllowed),
+        }
+    }
+}
+
+impl fmt::Debug for AllowedOrigins {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.inner {
+            Inner::List(ref origins) => f.debug_tuple("AllowedOrigins").field(origins).finish(),
+            Inner::Predicate(_) => f.write_str("AllowedOrigins(<predicate>)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestRequest;
+
+    #[test]
+    fn verify_origin() {
+        let origins = AllowedOrigins::list(["https://Example.com"]);
+
+        let req = TestRequest::default().finish();
+        assert!(origins.verify(req.head()).is_ok());
+
+        let req = TestRequest::default()
+            .insert_header((header::ORIGIN, "https://example.COM"))
+            .finish();
+        assert!(origins.verify(req.head()).is_ok());
+
+        for origin in ["https://example.com.evil.example", "null"] {
+            let req = TestRequest::default()
+                .insert_header((header::ORIGIN, origin))
+                .finish();
+            assert_eq!(
+                origins.verify(req.head()),
+                Err(OriginHandshakeError::OriginNotAllowed)
+            );
+        }
+
+        let origins = AllowedOrigins::predicate(|origin| origin.starts_with("https://"));
+        let req = TestRequest::default()
+            .insert_header((header::ORIGIN, "http://example.com"))
+            .finish();
+        assert_eq!(
+            origins.verify(req.head()),
+            Err(OriginHandshakeError::OriginNotAllowed)
+        );
+    }
+}
@@ -150,6 +150,18 @@ pub enum CloseCode {
     Other(u16),
 }
 
+impl CloseCode {
+    /// Returns true if `code` may be sent in a close frame, per [RFC 6455 §7.4].
+    ///
+    /// Codes below 1000, codes reserved for signalling a missing or abnormal closure and codes
+    /// reserved for future protocol use are not allowed.
+    ///
+    /// [RFC 6455 §7.4]: https://datatracker.ietf.org/doc/html/rfc6455#section-7.4
+    pub(super) fn is_allowed_on_wire(code: u16) -> bool {
+        matches!(code, 1000..=1003 | 1007..=1014 | 3000..=4999)
+    }
+}
+
 impl From<CloseCode> for u16 {
     fn from(code: CloseCode) -> u16 {
         use self::CloseCode::*;
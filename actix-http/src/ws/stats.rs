@@ -0,0 +1,279 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use super::proto::{CloseCode, OpCode};
+
+/// Number of opcodes that are counted, i.e. all but [`OpCode::Bad`].
+const OPCODES: usize = 6;
+
+/// Handle to the frame counters of WebSocket connections.
+///
+/// Attach a handle to a [`Codec`](super::Codec) with
+/// [`with_stats`](super::Codec::with_stats) to count the frames and bytes it encodes and
+/// decodes, the latency of the last pong (measured from the last ping sent) and the close codes
+/// of the connection. Handles are cheap to clone and can be read from any thread while the
+/// connection is running.
+///
+/// A handle created with [`child`](Self::child) also adds its counts to its parent, which can
+/// aggregate the stats of all connections of a server.
+///
+/// # Examples
+/// ```
+/// use actix_http::ws::{Codec, Message, OpCode, Stats};
+/// use actix_codec::Encoder as _;
+/// use bytes::BytesMut;
+///
+/// let stats = Stats::new();
+/// let mut codec = Codec::new().with_stats(stats.clone());
+///
+/// let mut buf = BytesMut::new();
+/// codec.encode(Message::Text("hello".into()), &mut buf).unwrap();
+///
+/// assert_eq!(stats.frames_sent(OpCode::Text), 1);
+/// assert_eq!(stats.bytes_sent(), buf.len() as u64);
+/// ```
+#[derive(Clone, Default)]
+pub struct Stats {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    frames_sent: [AtomicU64; OPCODES],
+    frames_received: [AtomicU64; OPCODES],
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    timings: Mutex<Timings>,
+    parent: Option<Arc<Inner>>,
+}
+
+#[derive(Default)]
+struct Timings {
+    last_ping: Option<Instant>,
+    last_pong_latency: Option<Duration>,
+    close_code_sent: Option<CloseCode>,
+    close_code_received: Option<CloseCode>,
+}
+
+impl Stats {
+    /// Constructs a handle with all counters at zero.
+    pub fn new() -> Self {
+        Stats::default()
+    }
+
+    /// Constructs a handle whose counts are also added to this one.
+    pub fn child(&self) -> Self {
+        Stats {
+            inner: Arc::new(Inner {
+                parent: Some(Arc::clone(&self.inner)),
+                ..Inner::default()
+            }),
+        }
+    }
+
+    /// Returns the number of frames sent with the given opcode.
+    pub fn frames_sent(&self, opcode: OpCode) -> u64 {
+        index(opcode).map_or(0, |idx| self.inner.frames_sent[idx].load(Ordering::Relaxed))
+    }
+
+    /// Returns the number of frames received with the given opcode.
+    pub fn frames_received(&self, opcode: OpCode) -> u64 {
+        index(opcode).map_or(0, |idx| {
+            self.inner.frames_received[idx].load(Ordering::Relaxed)
+        })
+    }
+
+    /// Returns the number of bytes sent, including frame headers.
+    pub fn bytes_sent(&self) -> u64 {
+        self.inner.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of bytes received, including frame headers.
+    pub fn bytes_received(&self) -> u64 {
+        self.inner.bytes_received.load(Ordering::Relaxed)
+    }
+
+    /// Returns the time between the last ping sent and the pong that answered it.
+    pub fn last_pong_latency(&self) -> Option<Duration> {
+        self.inner.timings.lock().unwrap().last_pong_latency
+    }
+
+    /// Returns the code of the last close frame sent.
+    pub fn close_code_sent(&self) -> Option<CloseCode> {
+        self.inner.timings.lock().unwrap().close_code_sent
+    }
+
+    /// Returns the code of the last close frame received.
+    pub fn close_code_received(&self) -> Option<CloseCode> {
+        self.inner.timings.lock().unwrap().close_code_received
+    }
+
+    pub(super) fn record_sent(&self, opcode: OpCode, bytes: usize, close: Option<CloseCode>) {
+        if opcode == OpCode::Ping {
+            self.inner.timings.lock().unwrap().last_ping = Some(Instant::now());
+        }
+
+        self.inner.each(|inner| {
+            if let Some(idx) = index(opcode) {
+                inner.frames_sent[idx].fetch_add(1, Ordering::Relaxed);
+            }
+            inner.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+
+            if close.is_some() {
+                inner.timings.lock().unwrap().close_code_sent = close;
+            }
+        });
+    }
+
+    pub(super) fn record_received(
+        &self,
+        opcode: OpCode,
+        bytes: usize,
+        close: Option<CloseCode>,
+    ) {
+        // latency is measured against the pings of this connection only
+        let latency = match opcode {
+            OpCode::Pong => self
+                .inner
+                .timings
+                .lock()
+                .unwrap()
+                .last_ping
+                .take()
+                .map(|ping| ping.elapsed()),
+            _ => None,
+        };
+
+        self.inner.each(|inner| {
+            if let Some(idx) = index(opcode) {
+                inner.frames_received[idx].fetch_add(1, Ordering::Relaxed);
+            }
+            inner
+                .bytes_received
+                .fetch_add(bytes as u64, Ordering::Relaxed);
+
+            if latency.is_some() {
+                inner.timings.lock().unwrap().last_pong_latency = latency;
+            }
+            if close.is_some() {
+                inner.timings.lock().unwrap().close_code_received = close;
+            }
+        });
+    }
+}
+
+impl Inner {
+    /// Calls `f` on these counters and those of all ancestors.
+    fn each(&self, mut f: impl FnMut(&Inner)) {
+        let mut inner = Some(self);
+
+        while let Some(counters) = inner {
+            f(counters);
+            inner = counters.parent.as_deref();
+        }
+    }
+}
+
+fn index(opcode: OpCode) -> Option<usize> {
+    match opcode {
+        OpCode::Continue => Some(0),
+        OpCode::Text => Some(1),
+        OpCode::Binary => Some(2),
+        OpCode::Close => Some(3),
+        OpCode::Ping => Some(4),
+        OpCode::Pong => Some(5),
+        OpCode::Bad => None,
+    }
+}
+
+impl fmt::Debug for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stats")
+            .field("bytes_sent", &self.bytes_sent())
+            .field("bytes_received", &self.bytes_received())
+            .field("last_pong_latency", &self.last_pong_latency())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_codec::{Decoder as _, Encoder as _};
+    use bytes::BytesMut;
+
+    use super::*;
+    use crate::ws::{CloseReason, Codec, Frame, Message};
+
+    #[test]
+    fn counts_frames() {
+        let server = Stats::new();
+        let conn = server.child();
+
+        let mut codec = Codec::new().with_stats(conn.clone());
+        let mut client = Codec::new().client_mode();
+
+        let mut out = BytesMut::new();
+        codec.encode(Message::Ping("a".into()), &mut out).unwrap();
+        codec
+            .encode(Message::Text("hello".into()), &mut out)
+            .unwrap();
+        codec
+            .encode(Message::Close(Some(CloseCode::Away.into())), &mut out)
+            .unwrap();
+        codec.encode(Message::Nop, &mut out).unwrap();
+
+        assert_eq!(conn.frames_sent(OpCode::Ping), 1);
+        assert_eq!(conn.frames_sent(OpCode::Text), 1);
+        assert_eq!(conn.frames_sent(OpCode::Bad), 0);
+        assert_eq!(conn.bytes_sent(), out.len() as u64);
+        assert_eq!(conn.close_code_sent(), Some(CloseCode::Away));
+
+        let mut incoming = BytesMut::new();
+        client
+            .encode(Message::Pong("a".into()), &mut incoming)
+            .unwrap();
+        client
+            .encode(Message::Binary("data".into()), &mut incoming)
+            .unwrap();
+        client
+            .encode(
+                Message::Close(Some(CloseReason::from(CloseCode::Normal))),
+                &mut incoming,
+            )
+            .unwrap();
+        let len = incoming.len() as u64;
+
+        assert!(matches!(
+            codec.decode(&mut incoming),
+            Ok(Some(Frame::Pong(_)))
+        ));
+        assert!(matches!(
+            codec.decode(&mut incoming),
+            Ok(Some(Frame::Binary(_)))
+        ));
+        assert!(matches!(
+            codec.decode(&mut incoming),
+            Ok(Some(Frame::Close(_)))
+        ));
+        assert!(matches!(codec.decode(&mut incoming), Ok(None)));
+
+        assert_eq!(conn.frames_received(OpCode::Pong), 1);
+        assert_eq!(conn.frames_received(OpCode::Binary), 1);
+        assert_eq!(conn.bytes_received(), len);
+        assert_eq!(conn.close_code_received(), Some(CloseCode::Normal));
+        assert!(conn.last_pong_latency().is_some());
+
+        // counts are aggregated into the parent
+        assert_eq!(server.frames_sent(OpCode::Text), 1);
+        assert_eq!(server.frames_received(OpCode::Binary), 1);
+        assert_eq!(server.bytes_received(), len);
+        assert_eq!(server.last_pong_latency(), conn.last_pong_latency());
+        assert_eq!(server.child().bytes_sent(), 0);
+    }
+}
@@ -12,7 +12,7 @@ use actix_http::{
 use actix_http_test::test_server;
 use actix_rt::time::sleep;
 use actix_service::fn_service;
-use actix_utils::future::{err, ok, ready};
+use actix_utils::future::{err, ok, ready, Ready};
 use bytes::Bytes;
 use derive_more::{Display, Error};
 use futures_util::{
@@ -196,6 +196,239 @@ async fn chunked_payload() {
     srv.stop().await;
 }
 
+#[actix_rt::test]
+async fn max_payload_size_content_length() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .max_payload_size(16)
+            .h1(|_| -> Ready<Result<Response<BoxBody>, Infallible>> {
+                panic!("oversized request reached service")
+            })
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"POST /test HTTP/1.1\r\ncontent-length: 17\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(
+        data.starts_with("HTTP/1.1 413 Payload Too Large"),
+        "response was not 413: {}",
+        data
+    );
+
+    srv.stop().await;
+}
+
+#[actix_rt::test]
+async fn head_limits() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .max_request_line_length(32)
+            .max_header_size(32)
+            .max_header_count(2)
+            .h1(|_| ok::<_, Infallible>(Response::ok()))
+            .tcp()
+    })
+    .await;
+
+    let requests: [(&[u8], &str); 4] = [
+        (b"GET /test HTTP/1.1\r\nhost: localhost\r\n\r\n", "200 OK"),
+        (
+            b"GET /a-path-that-is-too-long HTTP/1.1\r\n\r\n",
+            "414 URI Too Long",
+        ),
+        (
+            b"GET / HTTP/1.1\r\nx-long: a-value-that-is-far-too-long\r\n\r\n",
+            "431 Request Header Fields Too Large",
+        ),
+        (
+            b"GET / HTTP/1.1\r\na: 1\r\nb: 2\r\nc: 3\r\n\r\n",
+            "431 Request Header Fields Too Large",
+        ),
+    ];
+
+    for (req, status) in requests {
+        let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+        let _ = stream.write_all(req);
+        let _ = stream.shutdown(net::Shutdown::Write);
+        let mut data = String::new();
+        let _ = stream.read_to_string(&mut data);
+        assert!(
+            data.starts_with(&format!("HTTP/1.1 {}", status)),
+            "response was not {}: {}",
+            status,
+            data
+        );
+    }
+
+    srv.stop().await;
+}
+
+#[actix_rt::test]
+async fn max_payload_size_chunked() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .max_payload_size(16)
+            .h1(fn_service(|mut req: Request| async move {
+                let mut pl = req.take_payload();
+                let mut size = 0;
+
+                while let Some(chunk) = pl.next().await {
+                    match chunk {
+                        Ok(chunk) => size += chunk.len(),
+                        Err(err) => {
+                            let res = Response::with_body(
+                                StatusCode::PAYLOAD_TOO_LARGE,
+                                err.to_string(),
+                            );
+                            return Ok::<_, Infallible>(res);
+                        }
+                    }
+                }
+
+                Ok(Response::ok().set_body(format!("size={}", size)))
+            }))
+            .tcp()
+    })
+    .await;
+
+    // within the limit
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(
+        b"POST /test HTTP/1.1\r\nconnection: close\r\ntransfer-encoding: chunked\r\n\r\n\
+        8\r\n01234567\r\n8\r\n01234567\r\n0\r\n\r\n",
+    );
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 200 OK"), "{}", data);
+    assert!(data.ends_with("size=16"), "{}", data);
+
+    // exceeds the limit mid-stream
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(
+        b"POST /test HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n\
+        8\r\n01234567\r\n8\r\n01234567\r\n1\r\n8\r\n",
+    );
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(
+        data.starts_with("HTTP/1.1 413 Payload Too Large"),
+        "response was not 413: {}",
+        data
+    );
+
+    srv.stop().await;
+}
+
+#[cfg(feature = "http2")]
+#[actix_rt::test]
+async fn h2_max_payload_size_resets_stream() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .max_payload_size(16)
+            .h2(|mut req: Request| async move {
+                let mut pl = req.take_payload();
+
+                while let Some(chunk) = pl.next().await {
+                    if let Err(err) = chunk {
+                        let res =
+                            Response::with_body(StatusCode::PAYLOAD_TOO_LARGE, err.to_string());
+                        return Ok::<_, Infallible>(res);
+                    }
+                }
+
+                Ok(Response::ok().set_body(String::new()))
+            })
+            .tcp()
+    })
+    .await;
+
+    let stream = tokio::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut tx, conn) = h2::client::handshake(stream).await.unwrap();
+    actix_rt::spawn(async move {
+        let _ = conn.await;
+    });
+
+    let req = http::Request::post("http://localhost/").body(()).unwrap();
+    let (res, mut body) = tx.send_request(req, false).unwrap();
+
+    // more than the limit, without ending the stream
+    body.send_data(Bytes::from_static(&[b'x'; 32]), false)
+        .unwrap();
+
+    // the reset can overtake the 413 response on the client side
+    let reason = match res.await {
+        Ok(res) => {
+            assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+            futures_util::future::poll_fn(|cx| body.poll_reset(cx))
+                .await
+                .unwrap()
+        }
+        Err(err) => err.reason().unwrap(),
+    };
+    assert_eq!(reason, h2::Reason::CANCEL);
+
+    srv.stop().await;
+}
+
+#[actix_rt::test]
+async fn h1_connect_tunnel() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .h1(fn_service(|mut req: Request| async move {
+                assert_eq!(req.method(), http::Method::CONNECT);
+
+                // echo tunneled bytes back to the client
+                let body = BodyStream::new(req.take_payload());
+                Ok::<_, Infallible>(Response::ok().set_body(body))
+            }))
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(
+        b"CONNECT example.com:443 HTTP/1.1\r\nhost: example.com:443\r\n\r\ntunneled bytes",
+    );
+    let _ = stream.shutdown(net::Shutdown::Write);
+
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 200 OK\r\n"), "{}", data);
+    assert!(!data.contains("transfer-encoding"), "{}", data);
+    assert!(!data.contains("content-length"), "{}", data);
+    assert!(data.ends_with("\r\n\r\ntunneled bytes"), "{}", data);
+
+    srv.stop().await;
+}
+
+#[actix_rt::test]
+async fn small_write_buffer_size() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .write_buffer_size(16)
+            .h1(|_| {
+                let body = futures_util::stream::iter(
+                    (0..64).map(|_| Ok::<_, Infallible>(Bytes::from_static(STR.as_bytes()))),
+                );
+                ok::<_, Infallible>(Response::ok().set_body(BodyStream::new(body)))
+            })
+            .tcp()
+    })
+    .await;
+
+    let response = srv.get("/").send().await.unwrap();
+    assert!(response.status().is_success());
+
+    // body is delivered intact even though the buffer is flushed after every chunk
+    let bytes = srv.load_body(response).await.unwrap();
+    assert_eq!(bytes, STR.repeat(64));
+
+    srv.stop().await;
+}
+
 #[actix_rt::test]
 async fn slow_request_408() {
     let mut srv = test_server(|| {
@@ -858,3 +1091,73 @@ async fn not_modified_spec_h1() {
 
     srv.stop().await;
 }
+
+#[cfg(feature = "wire-tap")]
+#[actix_rt::test]
+async fn h1_wire_tap() {
+    use std::sync::{Arc, Mutex};
+
+    use actix_http::TapEvent;
+
+    #[derive(Default)]
+    struct Capture {
+        inbound: Vec<u8>,
+        outbound: Vec<u8>,
+        closed: bool,
+    }
+
+    let capture = Arc::new(Mutex::new(Capture::default()));
+
+    let srv_capture = Arc::clone(&capture);
+    let mut srv = test_server(move || {
+        let capture = Arc::clone(&srv_capture);
+
+        HttpService::build()
+            .keep_alive(KeepAlive::Disabled)
+            .wire_tap(move |peer_addr| {
+                assert!(peer_addr.is_some());
+                let capture = Arc::clone(&capture);
+
+                move |event: TapEvent<'_>| {
+                    let mut capture = capture.lock().unwrap();
+                    match event {
+                        TapEvent::Inbound(bytes) => capture.inbound.extend_from_slice(bytes),
+                        TapEvent::Outbound(bytes) => capture.outbound.extend_from_slice(bytes),
+                        _ => capture.closed = true,
+                    }
+                }
+            })
+            .h1(|_| ok::<_, Infallible>(Response::with_body(StatusCode::OK, "tapped")))
+            .tcp()
+    })
+    .await;
+
+    let req = b"GET /tap HTTP/1.1\r\nhost: localhost\r\n\r\n";
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream.write_all(req).unwrap();
+    let mut data = Vec::new();
+    stream.read_to_end(&mut data).unwrap();
+
+    for _ in 0..50 {
+        if capture.lock().unwrap().closed {
+            break;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+
+    let (closed, inbound, outbound) = {
+        let capture = capture.lock().unwrap();
+        (
+            capture.closed,
+            capture.inbound.clone(),
+            capture.outbound.clone(),
+        )
+    };
+
+    assert!(closed);
+    assert_eq!(inbound, req);
+    assert_eq!(outbound, data);
+    assert!(data.ends_with(b"\r\n\r\ntapped"));
+
+    srv.stop().await;
+}
@@ -1,12 +1,15 @@
 //! Websocket integration.
 
 use std::{
+    cmp,
     collections::VecDeque,
     convert::TryFrom,
     future::Future,
-    io, mem,
+    io,
+    marker::PhantomData,
+    mem,
     pin::Pin,
-    task::{Context, Poll},
+    task::{Context, Poll, Waker},
 };
 
 use actix::{
@@ -21,7 +24,8 @@ use actix::{
 use actix_codec::{Decoder as _, Encoder as _};
 use actix_http::ws::{hash_key, Codec};
 pub use actix_http::ws::{
-    CloseCode, CloseReason, Frame, HandshakeError, Message, ProtocolError,
+    AllowedOrigins, CloseCode, CloseReason, Frame, HandshakeError, Message,
+    OriginHandshakeError, ProtocolError,
 };
 use actix_web::{
     error::{Error, PayloadError},
@@ -37,6 +41,13 @@ use futures_core::Stream;
 use pin_project_lite::pin_project;
 use tokio::sync::oneshot;
 
+mod broadcast;
+#[cfg(feature = "graphql-ws")]
+pub mod graphql;
+pub mod test;
+
+pub use self::broadcast::{Broadcaster, SessionId, Subscription};
+
 /// Builder for Websocket session response.
 ///
 /// # Examples
@@ -46,7 +57,8 @@ use tokio::sync::oneshot;
 /// WsResponseBuilder::new(WsActor, &req, stream).start()
 /// ```
 ///
-/// Create a Websocket session with a specific max frame size, [`Codec`], and protocols.
+/// Create a Websocket session with a specific max frame size, [`Codec`], protocols and allowed
+/// origins.
 /// ```ignore
 /// const MAX_FRAME_SIZE: usize = 16_384; // 16KiB
 ///
@@ -54,6 +66,7 @@ use tokio::sync::oneshot;
 ///     .codec(Codec::new())
 ///     .protocols(&["A", "B"])
 ///     .frame_size(MAX_FRAME_SIZE)
+///     .origins(ws::AllowedOrigins::list(["https://example.com"]))
 ///     .start()
 /// ```
 pub struct WsResponseBuilder<'a, A, T>
@@ -67,6 +80,7 @@ where
     codec: Option<Codec>,
     protocols: Option<&'a [&'a str]>,
     frame_size: Option<usize>,
+    origins: Option<AllowedOrigins>,
 }
 
 impl<'a, A, T> WsResponseBuilder<'a, A, T>
@@ -85,6 +99,7 @@ where
             codec: None,
             protocols: None,
             frame_size: None,
+            origins: None,
         }
     }
 
@@ -109,11 +124,26 @@ where
         self
     }
 
-    fn handshake_resp(&self) -> Result<HttpResponseBuilder, HandshakeError> {
-        match self.protocols {
-            Some(protocols) => handshake_with_protocols(self.req, protocols),
-            None => handshake(self.req),
+    /// Set the origins allowed to open a session.
+    ///
+    /// Handshakes from other origins are rejected with `403 Forbidden`. See [`AllowedOrigins`]
+    /// for why sessions used by browsers should check the origin.
+    pub fn origins(mut self, origins: AllowedOrigins) -> Self {
+        self.origins = Some(origins);
+        self
+    }
+
+    fn handshake_resp(&self) -> Result<HttpResponseBuilder, OriginHandshakeError> {
+        let res = match self.protocols {
+            Some(protocols) => handshake_with_protocols(self.req, protocols)?,
+            None => handshake(self.req)?,
+        };
+
+        if let Some(ref origins) = self.origins {
+            origins.verify(self.req.head())?;
         }
+
+        Ok(res)
     }
 
     fn set_frame_size(&mut self) {
@@ -148,10 +178,7 @@ where
         S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
     {
         let mb = Mailbox::default();
-        let mut ctx = WebsocketContext {
-            inner: ContextParts::new(mb.sender_producer()),
-            messages: VecDeque::new(),
-        };
+        let mut ctx = WebsocketContext::new(ContextParts::new(mb.sender_producer()));
         ctx.add_stream(WsStream::new(stream, codec.clone()));
 
         let addr = ctx.address();
@@ -374,6 +401,11 @@ where
 {
     inner: ContextParts<A>,
     messages: VecDeque<Option<Message>>,
+    write_capacity: Option<usize>,
+    close_on_overflow: bool,
+    overflowed: bool,
+    peak_queue_depth: usize,
+    write_waker: Option<Waker>,
 }
 
 impl<A> ActorContext for WebsocketContext<A>
@@ -433,6 +465,18 @@ impl<A> WebsocketContext<A>
 where
     A: Actor<Context = Self>,
 {
+    fn new(inner: ContextParts<A>) -> Self {
+        WebsocketContext {
+            inner,
+            messages: VecDeque::new(),
+            write_capacity: None,
+            close_on_overflow: false,
+            overflowed: false,
+            peak_queue_depth: 0,
+            write_waker: None,
+        }
+    }
+
     /// Create a new Websocket context from a request and an actor.
     #[inline]
     pub fn create<S>(actor: A, stream: S) -> impl Stream<Item = Result<Bytes, Error>>
@@ -458,10 +502,7 @@ where
         S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
     {
         let mb = Mailbox::default();
-        let mut ctx = WebsocketContext {
-            inner: ContextParts::new(mb.sender_producer()),
-            messages: VecDeque::new(),
-        };
+        let mut ctx = WebsocketContext::new(ContextParts::new(mb.sender_producer()));
         ctx.add_stream(WsStream::new(stream, Codec::new()));
 
         let addr = ctx.address();
@@ -480,10 +521,7 @@ where
         S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
     {
         let mb = Mailbox::default();
-        let mut ctx = WebsocketContext {
-            inner: ContextParts::new(mb.sender_producer()),
-            messages: VecDeque::new(),
-        };
+        let mut ctx = WebsocketContext::new(ContextParts::new(mb.sender_producer()));
         ctx.add_stream(WsStream::new(stream, codec.clone()));
 
         WebsocketContextFut::new(ctx, actor, mb, codec)
@@ -497,10 +535,7 @@ where
         S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
     {
         let mb = Mailbox::default();
-        let mut ctx = WebsocketContext {
-            inner: ContextParts::new(mb.sender_producer()),
-            messages: VecDeque::new(),
-        };
+        let mut ctx = WebsocketContext::new(ContextParts::new(mb.sender_producer()));
         ctx.add_stream(WsStream::new(stream, Codec::new()));
 
         let act = f(&mut ctx);
@@ -519,9 +554,79 @@ where
     /// be created using `Frame::message()`. If you want to send text or binary
     /// data you should prefer the `text()` or `binary()` convenience functions
     /// that handle the framing for you.
+    ///
+    /// If the outbound queue is full and [`close_on_overflow`](Self::close_on_overflow) is
+    /// enabled, the connection is closed instead.
     #[inline]
     pub fn write_raw(&mut self, msg: Message) {
+        if self.overflowed {
+            return;
+        }
+
+        if self.close_on_overflow && self.is_write_queue_full() {
+            // the peer is not keeping up; drop what it has not received yet and close
+            self.overflowed = true;
+            self.messages.clear();
+            self.messages
+                .push_back(Some(Message::Close(Some(CloseReason {
+                    code: CloseCode::Policy,
+                    description: Some("outbound queue overflow".to_owned()),
+                }))));
+            self.inner.stop();
+            return;
+        }
+
         self.messages.push_back(Some(msg));
+        self.peak_queue_depth = cmp::max(self.peak_queue_depth, self.messages.len());
+    }
+
+    /// Returns a future that writes `msg` once the outbound queue has room.
+    ///
+    /// Use it with [`AsyncContext::wait`] to stop processing other events until the peer has
+    /// caught up, or with [`AsyncContext::spawn`] to keep processing them. Without a
+    /// [write capacity](Self::set_write_capacity), the message is written immediately.
+    ///
+    /// ```ignore
+    /// ctx.wait(ctx.send(ws::Message::Text("update".into())));
+    /// ```
+    pub fn send(&self, msg: Message) -> SendFuture<A> {
+        SendFuture {
+            msg: Some(msg),
+            _actor: PhantomData,
+        }
+    }
+
+    /// Set the capacity of the outbound queue, in messages.
+    ///
+    /// Messages are queued until the connection is ready to write them, which takes longer when
+    /// the peer is slow to read. By default, the queue is unbounded. Once it is full,
+    /// [`send`](Self::send) waits for room and, if [`close_on_overflow`](Self::close_on_overflow)
+    /// is enabled, the other write methods close the connection.
+    pub fn set_write_capacity(&mut self, capacity: usize) {
+        self.write_capacity = Some(capacity);
+    }
+
+    /// Close the connection with [`CloseCode::Policy`] when a message is written while the
+    /// outbound queue is full, instead of queueing it. Disabled by default.
+    ///
+    /// Queued messages are discarded and the actor is stopped. Has no effect unless a
+    /// [write capacity](Self::set_write_capacity) is set.
+    pub fn close_on_overflow(&mut self, close: bool) {
+        self.close_on_overflow = close;
+    }
+
+    /// Number of messages in the outbound queue.
+    pub fn queue_depth(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Largest number of messages that have been in the outbound queue at once.
+    pub fn peak_queue_depth(&self) -> usize {
+        self.peak_queue_depth
+    }
+
+    fn is_write_queue_full(&self) -> bool {
+        matches!(self.write_capacity, Some(capacity) if self.messages.len() >= capacity)
     }
 
     /// Send text frame
@@ -569,6 +674,39 @@ where
     }
 }
 
+/// Future returned by [`WebsocketContext::send`].
+pub struct SendFuture<A> {
+    msg: Option<Message>,
+    _actor: PhantomData<fn(A)>,
+}
+
+impl<A> ActorFuture<A> for SendFuture<A>
+where
+    A: Actor<Context = WebsocketContext<A>>,
+{
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        _: &mut A,
+        ctx: &mut WebsocketContext<A>,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if ctx.is_write_queue_full() && !ctx.overflowed {
+            ctx.write_waker = Some(task.waker().clone());
+            return Poll::Pending;
+        }
+
+        if let Some(msg) = this.msg.take() {
+            ctx.write_raw(msg);
+        }
+
+        Poll::Ready(())
+    }
+}
+
 impl<A> AsyncContextParts<A> for WebsocketContext<A>
 where
     A: Actor<Context = Self>,
@@ -578,6 +716,9 @@ where
     }
 }
 
+/// Size above which encoded messages are passed on to the connection.
+const MAX_CHUNK_SIZE: usize = 65_536;
+
 struct WebsocketContextFut<A>
 where
     A: Actor<Context = WebsocketContext<A>>,
@@ -616,8 +757,13 @@ where
             let _ = Pin::new(&mut this.fut).poll(cx);
         }
 
-        // encode messages
-        while let Some(item) = this.fut.ctx().messages.pop_front() {
+        // encode messages, leaving the rest queued once a chunk is full
+        while this.buf.len() < MAX_CHUNK_SIZE {
+            let item = match this.fut.ctx().messages.pop_front() {
+                Some(item) => item,
+                None => break,
+            };
+
             if let Some(msg) = item {
                 this.encoder.encode(msg, &mut this.buf)?;
             } else {
@@ -626,6 +772,11 @@ where
             }
         }
 
+        // there is room in the queue again
+        if let Some(waker) = this.fut.ctx().write_waker.take() {
+            waker.wake();
+        }
+
         if !this.buf.is_empty() {
             Poll::Ready(Some(Ok(this.buf.split().freeze())))
         } else if this.fut.alive() && !this.closed {
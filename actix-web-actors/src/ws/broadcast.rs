@@ -0,0 +1,308 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+/// Identifier of a session registered with a [`Broadcaster`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SessionId(u64);
+
+/// Registry of WebSocket sessions that fans messages out to all sessions or to topic subscribers.
+///
+/// Each session has a bounded queue. A session whose queue is full when a message is sent is
+/// evicted as a slow consumer, which ends its [`Subscription`] stream. Sessions are also removed
+/// once their `Subscription` is dropped.
+///
+/// `Broadcaster` is cheap to clone and can be shared between workers, usually as app data.
+///
+/// # Examples
+/// ```
+/// use actix::{Actor, AsyncContext, StreamHandler};
+/// use actix_web_actors::ws;
+/// use bytestring::ByteString;
+///
+/// struct ChatSession {
+///     room: ws::Broadcaster<ByteString>,
+/// }
+///
+/// impl Actor for ChatSession {
+///     type Context = ws::WebsocketContext<Self>;
+///
+///     fn started(&mut self, ctx: &mut Self::Context) {
+///         let subscription = self.room.register();
+///         self.room.subscribe(subscription.id(), "general");
+///
+///         // messages for this session are handled by `StreamHandler<ByteString>`
+///         ctx.add_stream(subscription);
+///     }
+/// }
+///
+/// impl StreamHandler<ByteString> for ChatSession {
+///     fn handle(&mut self, msg: ByteString, ctx: &mut Self::Context) {
+///         ctx.text(msg);
+///     }
+/// }
+///
+/// impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for ChatSession {
+///     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, _: &mut Self::Context) {
+///         if let Ok(ws::Message::Text(text)) = msg {
+///             self.room.broadcast_to("general", text);
+///         }
+///     }
+/// }
+/// ```
+pub struct Broadcaster<M> {
+    inner: Arc<Mutex<Inner<M>>>,
+    queue_size: usize,
+}
+
+struct Inner<M> {
+    next_id: u64,
+    sessions: HashMap<SessionId, mpsc::Sender<M>>,
+    topics: HashMap<String, HashSet<SessionId>>,
+}
+
+impl<M> Inner<M> {
+    fn remove(&mut self, id: SessionId) {
+        self.sessions.remove(&id);
+
+        self.topics.retain(|_, members| {
+            members.remove(&id);
+            !members.is_empty()
+        });
+    }
+}
+
+impl<M: Clone> Broadcaster<M> {
+    /// Constructs a broadcaster where each session can have up to `queue_size` undelivered
+    /// messages.
+    ///
+    /// # Panics
+    /// Panics if `queue_size` is 0.
+    pub fn new(queue_size: usize) -> Self {
+        assert!(
+            queue_size > 0,
+            "broadcaster queue size must be greater than 0"
+        );
+
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                next_id: 0,
+                sessions: HashMap::new(),
+                topics: HashMap::new(),
+            })),
+            queue_size,
+        }
+    }
+
+    /// Registers a new session, returning the stream of messages sent to it.
+    pub fn register(&self) -> Subscription<M> {
+        let (tx, rx) = mpsc::channel(self.queue_size);
+
+        let mut inner = self.inner.lock().unwrap();
+        let id = SessionId(inner.next_id);
+        inner.next_id += 1;
+        inner.sessions.insert(id, tx);
+
+        Subscription { id, rx }
+    }
+
+    /// Removes a session and its topic subscriptions, ending its [`Subscription`] stream.
+    pub fn unregister(&self, id: SessionId) {
+        self.inner.lock().unwrap().remove(id);
+    }
+
+    /// Subscribes a session to `topic`.
+    ///
+    /// Returns false if the session is not registered.
+    pub fn subscribe(&self, id: SessionId, topic: impl Into<String>) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+
+        if !inner.sessions.contains_key(&id) {
+            return false;
+        }
+
+        inner.topics.entry(topic.into()).or_default().insert(id);
+        true
+    }
+
+    /// Unsubscribes a session from `topic`.
+    pub fn unsubscribe(&self, id: SessionId, topic: &str) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some(members) = inner.topics.get_mut(topic) {
+            members.remove(&id);
+
+            if members.is_empty() {
+                inner.topics.remove(topic);
+            }
+        }
+    }
+
+    /// Returns the number of registered sessions.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().sessions.len()
+    }
+
+    /// Returns true if no sessions are registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sends `msg` to all sessions, returning the number of sessions it was queued for.
+    pub fn broadcast(&self, msg: M) -> usize {
+        let mut inner = self.inner.lock().unwrap();
+        let ids = inner.sessions.keys().copied().collect::<Vec<_>>();
+        Self::deliver(&mut inner, ids, msg)
+    }
+
+    /// Sends `msg` to the sessions subscribed to `topic`, returning the number of sessions it was
+    /// queued for.
+    pub fn broadcast_to(&self, topic: &str, msg: M) -> usize {
+        let mut inner = self.inner.lock().unwrap();
+
+        let ids = match inner.topics.get(topic) {
+            Some(members) => members.iter().copied().collect::<Vec<_>>(),
+            None => return 0,
+        };
+
+        Self::deliver(&mut inner, ids, msg)
+    }
+
+    /// Sends `msg` to a single session, returning true if it was queued.
+    pub fn send(&self, id: SessionId, msg: M) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        Self::deliver(&mut inner, vec![id], msg) == 1
+    }
+
+    fn deliver(inner: &mut Inner<M>, ids: Vec<SessionId>, msg: M) -> usize {
+        let mut delivered = 0;
+        let mut evicted = Vec::new();
+
+        for id in ids {
+            let tx = match inner.sessions.get(&id) {
+                Some(tx) => tx,
+                None => continue,
+            };
+
+            match tx.try_send(msg.clone()) {
+                Ok(()) => delivered += 1,
+
+                // slow consumer or session that went away
+                Err(TrySendError::Full(_)) | Err(TrySendError::Closed(_)) => evicted.push(id),
+            }
+        }
+
+        for id in evicted {
+            inner.remove(id);
+        }
+
+        delivered
+    }
+}
+
+impl<M> Clone for Broadcaster<M> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+            queue_size: self.queue_size,
+        }
+    }
+}
+
+impl<M> fmt::Debug for Broadcaster<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.lock().unwrap();
+
+        f.debug_struct("Broadcaster")
+            .field("sessions", &inner.sessions.len())
+            .field("topics", &inner.topics.len())
+            .field("queue_size", &self.queue_size)
+            .finish()
+    }
+}
+
+/// Stream of messages sent to a session registered with a [`Broadcaster`].
+///
+/// The stream ends when the session is unregistered or evicted.
+#[derive(Debug)]
+pub struct Subscription<M> {
+    id: SessionId,
+    rx: mpsc::Receiver<M>,
+}
+
+impl<M> Subscription<M> {
+    /// Returns the ID of the session.
+    pub fn id(&self) -> SessionId {
+        self.id
+    }
+}
+
+impl<M> Stream for Subscription<M> {
+    type Item = M;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().rx.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt as _;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn topics() {
+        let broadcaster = Broadcaster::new(8);
+
+        let mut a = broadcaster.register();
+        let mut b = broadcaster.register();
+
+        assert!(broadcaster.subscribe(a.id(), "news"));
+        assert_eq!(broadcaster.len(), 2);
+
+        assert_eq!(broadcaster.broadcast("all"), 2);
+        assert_eq!(broadcaster.broadcast_to("news", "news"), 1);
+        assert_eq!(broadcaster.broadcast_to("sports", "sports"), 0);
+        assert!(broadcaster.send(b.id(), "direct"));
+
+        assert_eq!(a.next().await, Some("all"));
+        assert_eq!(a.next().await, Some("news"));
+        assert_eq!(b.next().await, Some("all"));
+        assert_eq!(b.next().await, Some("direct"));
+
+        broadcaster.unsubscribe(a.id(), "news");
+        assert_eq!(broadcaster.broadcast_to("news", "news"), 0);
+
+        broadcaster.unregister(b.id());
+        assert_eq!(b.next().await, None);
+        assert!(!broadcaster.subscribe(b.id(), "news"));
+        assert_eq!(broadcaster.len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn evict_slow_and_closed() {
+        let broadcaster = Broadcaster::new(1);
+
+        let mut slow = broadcaster.register();
+        let dropped = broadcaster.register();
+        drop(dropped);
+
+        assert_eq!(broadcaster.broadcast(1), 1);
+        assert_eq!(broadcaster.len(), 1);
+
+        // queue of `slow` is full
+        assert_eq!(broadcaster.broadcast(2), 0);
+        assert!(broadcaster.is_empty());
+
+        assert_eq!(slow.next().await, Some(1));
+        assert_eq!(slow.next().await, None);
+    }
+}
@@ -0,0 +1,483 @@
+//! GraphQL over WebSocket, using the `graphql-transport-ws` subprotocol.
+//!
+//! [`Session`] is an actor implementing the protocol state machine: connection initialisation and
+//! acknowledgement, ping/pong, and multiplexing of operations started with `subscribe` and
+//! stopped with `complete`. Executing operations is left to an implementation of
+//! [`Subscriptions`], usually a thin wrapper around a GraphQL library's schema.
+//!
+//! See the [protocol description] for details.
+//!
+//! # Examples
+//! ```
+//! use actix_web::{web, Error, HttpRequest, HttpResponse};
+//! use actix_web_actors::ws::graphql::{self, OperationStream, SubscribePayload, Subscriptions};
+//! use serde_json::json;
+//!
+//! struct Schema;
+//!
+//! impl Subscriptions for Schema {
+//!     fn subscribe(&mut self, _id: &str, payload: SubscribePayload) -> OperationStream {
+//!         // execute `payload.query` and stream its results
+//!         let result = json!({ "data": { "query": payload.query } });
+//!         Box::pin(futures_util::stream::once(async move { Ok(result) }))
+//!     }
+//! }
+//!
+//! async fn index(req: HttpRequest, stream: web::Payload) -> Result<HttpResponse, Error> {
+//!     graphql::start(graphql::Session::new(Schema), &req, stream)
+//! }
+//! ```
+//!
+//! [protocol description]: https://github.com/enisdenjo/graphql-ws/blob/master/PROTOCOL.md
+
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix::{fut::ActorFuture, Actor, ActorContext, AsyncContext, SpawnHandle, StreamHandler};
+use actix_web::{error::PayloadError, web::Bytes, Error, HttpRequest, HttpResponse};
+use futures_core::Stream;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    CloseCode, CloseReason, Message, ProtocolError, WebsocketContext, WsResponseBuilder,
+};
+
+/// Name of the subprotocol, sent in the `Sec-WebSocket-Protocol` header.
+pub const PROTOCOL: &str = "graphql-transport-ws";
+
+const DEFAULT_CONNECTION_INIT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Results of an operation.
+///
+/// Each `Ok` item is sent to the client as the payload of a `next` message, usually an object
+/// with `data` and `errors` fields. An `Err` item is sent as an `error` message, which ends the
+/// operation; it is meant for errors that prevent execution, such as validation errors. The
+/// operation is completed when the stream ends.
+pub type OperationStream = Pin<Box<dyn Stream<Item = Result<Value, Vec<Value>>>>>;
+
+/// Payload of a `subscribe` message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscribePayload {
+    /// Name of the operation to execute, if the document contains several.
+    pub operation_name: Option<String>,
+
+    /// GraphQL document.
+    pub query: String,
+
+    /// Values of the operation's variables.
+    pub variables: Option<Value>,
+
+    /// Protocol extensions.
+    pub extensions: Option<Value>,
+}
+
+/// Executes the operations of a GraphQL over WebSocket session.
+pub trait Subscriptions: Unpin + 'static {
+    /// Handles the `connection_init` message sent by the client when the connection is opened.
+    ///
+    /// Returning `Ok` acknowledges the connection, with an optional payload for the
+    /// `connection_ack` message. Returning `Err` closes the connection as forbidden, with the
+    /// given description.
+    ///
+    /// The default implementation accepts all connections.
+    fn connection_init(&mut self, payload: Option<Value>) -> Result<Option<Value>, String> {
+        let _ = payload;
+        Ok(None)
+    }
+
+    /// Starts executing an operation.
+    ///
+    /// The returned stream is dropped when the client completes the operation or the connection
+    /// is closed.
+    fn subscribe(&mut self, id: &str, payload: SubscribePayload) -> OperationStream;
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    ConnectionInit {
+        payload: Option<Value>,
+    },
+    Ping {
+        #[allow(dead_code)]
+        payload: Option<Value>,
+    },
+    Pong {
+        #[allow(dead_code)]
+        payload: Option<Value>,
+    },
+    Subscribe {
+        id: String,
+        payload: SubscribePayload,
+    },
+    Complete {
+        id: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage<'a> {
+    ConnectionAck {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        payload: Option<Value>,
+    },
+    Pong {},
+    Next {
+        id: &'a str,
+        payload: Value,
+    },
+    Error {
+        id: &'a str,
+        payload: Vec<Value>,
+    },
+    Complete {
+        id: &'a str,
+    },
+}
+
+/// Actor serving a GraphQL over WebSocket session.
+///
+/// Use [`start`] to respond to a handshake request with a session.
+pub struct Session<S> {
+    subscriptions: S,
+    acknowledged: bool,
+    operations: HashMap<String, SpawnHandle>,
+    connection_init_timeout: Duration,
+}
+
+impl<S: Subscriptions> Session<S> {
+    /// Constructs a session executing operations with `subscriptions`.
+    pub fn new(subscriptions: S) -> Self {
+        Session {
+            subscriptions,
+            acknowledged: false,
+            operations: HashMap::new(),
+            connection_init_timeout: DEFAULT_CONNECTION_INIT_TIMEOUT,
+        }
+    }
+
+    /// Sets how long to wait for the `connection_init` message before closing the connection.
+    ///
+    /// By default, the timeout is set to 3 seconds.
+    pub fn connection_init_timeout(mut self, timeout: Duration) -> Self {
+        self.connection_init_timeout = timeout;
+        self
+    }
+
+    fn handle_message(&mut self, msg: ClientMessage, ctx: &mut WebsocketContext<Self>) {
+        match msg {
+            ClientMessage::ConnectionInit { .. } if self.acknowledged => {
+                close(ctx, 4429, "Too many initialisation requests");
+            }
+
+            ClientMessage::ConnectionInit { payload } => {
+                match self.subscriptions.connection_init(payload) {
+                    Ok(payload) => {
+                        self.acknowledged = true;
+                        send(ctx, &ServerMessage::ConnectionAck { payload });
+                    }
+                    Err(reason) => close(ctx, 4403, reason),
+                }
+            }
+
+            ClientMessage::Ping { .. } => send(ctx, &ServerMessage::Pong {}),
+
+            ClientMessage::Pong { .. } => {}
+
+            ClientMessage::Subscribe { .. } if !self.acknowledged => {
+                close(ctx, 4401, "Unauthorized");
+            }
+
+            ClientMessage::Subscribe { id, .. } if self.operations.contains_key(&id) => {
+                close(ctx, 4409, format!("Subscriber for {} already exists", id));
+            }
+
+            ClientMessage::Subscribe { id, payload } => {
+                let stream = self.subscriptions.subscribe(&id, payload);
+                let handle = ctx.spawn(Operation {
+                    id: id.clone(),
+                    stream,
+                });
+                self.operations.insert(id, handle);
+            }
+
+            ClientMessage::Complete { id } => {
+                if let Some(handle) = self.operations.remove(&id) {
+                    ctx.cancel_future(handle);
+                }
+            }
+        }
+    }
+}
+
+impl<S: Subscriptions> Actor for Session<S> {
+    type Context = WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_later(self.connection_init_timeout, |act, ctx| {
+            if !act.acknowledged {
+                close(ctx, 4408, "Connection initialisation timeout");
+            }
+        });
+    }
+}
+
+impl<S: Subscriptions> StreamHandler<Result<Message, ProtocolError>> for Session<S> {
+    fn handle(&mut self, msg: Result<Message, ProtocolError>, ctx: &mut Self::Context) {
+        let text = match msg {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Ping(msg)) => return ctx.pong(&msg),
+            Ok(Message::Pong(_)) | Ok(Message::Nop) => return,
+            Ok(Message::Close(reason)) => {
+                ctx.close(reason);
+                return ctx.stop();
+            }
+            Ok(Message::Binary(_)) | Ok(Message::Continuation(_)) => {
+                return close(ctx, 4400, "Binary messages are not supported");
+            }
+            Err(_) => return ctx.stop(),
+        };
+
+        match serde_json::from_str(&text) {
+            Ok(msg) => self.handle_message(msg, ctx),
+            Err(err) => close(ctx, 4400, err.to_string()),
+        }
+    }
+}
+
+/// Streams the results of an operation to the client.
+struct Operation {
+    id: String,
+    stream: OperationStream,
+}
+
+impl<S: Subscriptions> ActorFuture<Session<S>> for Operation {
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut Session<S>,
+        ctx: &mut WebsocketContext<Session<S>>,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        loop {
+            // only produce results as fast as they can be written
+            if ctx.is_write_queue_full() && !ctx.overflowed {
+                ctx.write_waker = Some(task.waker().clone());
+                return Poll::Pending;
+            }
+
+            match this.stream.as_mut().poll_next(task) {
+                Poll::Ready(Some(Ok(payload))) => {
+                    let id = &this.id;
+                    send(ctx, &ServerMessage::Next { id, payload });
+                }
+
+                Poll::Ready(Some(Err(payload))) => {
+                    let id = &this.id;
+                    send(ctx, &ServerMessage::Error { id, payload });
+                    act.operations.remove(id);
+                    return Poll::Ready(());
+                }
+
+                Poll::Ready(None) => {
+                    let id = &this.id;
+                    send(ctx, &ServerMessage::Complete { id });
+                    act.operations.remove(id);
+                    return Poll::Ready(());
+                }
+
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+fn send<S: Subscriptions>(ctx: &mut WebsocketContext<Session<S>>, msg: &ServerMessage<'_>) {
+    // server messages only contain strings and JSON values, which always serialize
+    if let Ok(text) = serde_json::to_string(msg) {
+        ctx.text(text);
+    }
+}
+
+fn close<S: Subscriptions>(
+    ctx: &mut WebsocketContext<Session<S>>,
+    code: u16,
+    description: impl Into<String>,
+) {
+    ctx.close(Some(CloseReason {
+        code: CloseCode::Other(code),
+        description: Some(description.into()),
+    }));
+    ctx.stop();
+}
+
+/// Performs a WebSocket handshake negotiating the `graphql-transport-ws` subprotocol and starts
+/// `session` to serve the connection.
+pub fn start<S, T>(
+    session: Session<S>,
+    req: &HttpRequest,
+    stream: T,
+) -> Result<HttpResponse, Error>
+where
+    S: Subscriptions,
+    T: Stream<Item = Result<Bytes, PayloadError>> + 'static,
+{
+    WsResponseBuilder::new(session, req, stream)
+        .protocols(&[PROTOCOL])
+        .start()
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::header, test::TestRequest, web, App};
+    use futures_util::stream;
+    use serde_json::json;
+
+    use super::*;
+    use crate::ws::{test::call_ws, Frame};
+
+    struct Echo;
+
+    impl Subscriptions for Echo {
+        fn connection_init(&mut self, payload: Option<Value>) -> Result<Option<Value>, String> {
+            match payload {
+                Some(payload) if payload["token"] == "secret" => {
+                    Ok(Some(json!({ "ok": true })))
+                }
+                _ => Err("Forbidden".to_owned()),
+            }
+        }
+
+        fn subscribe(&mut self, _: &str, payload: SubscribePayload) -> OperationStream {
+            match payload.query.as_str() {
+                "invalid" => Box::pin(stream::iter(vec![Err(vec![
+                    json!({ "message": "invalid" }),
+                ])])),
+                "pending" => Box::pin(stream::pending()),
+                query => {
+                    let count = query.parse().unwrap();
+                    Box::pin(stream::iter((0..count).map(|n| Ok(json!({ "data": n })))))
+                }
+            }
+        }
+    }
+
+    async fn index(req: HttpRequest, stream: web::Payload) -> Result<HttpResponse, Error> {
+        start(Session::new(Echo), &req, stream)
+    }
+
+    async fn connect() -> crate::ws::test::TestWsClient {
+        let app =
+            actix_web::test::init_service(App::new().route("/", web::get().to(index))).await;
+        let req = TestRequest::get().insert_header((header::SEC_WEBSOCKET_PROTOCOL, PROTOCOL));
+        call_ws(&app, req).await
+    }
+
+    fn text(msg: Value) -> Message {
+        Message::Text(msg.to_string().into())
+    }
+
+    fn subscribe(id: &str, query: &str) -> Message {
+        text(json!({ "type": "subscribe", "id": id, "payload": { "query": query } }))
+    }
+
+    async fn recv_json(client: &mut crate::ws::test::TestWsClient) -> Value {
+        match client.recv().await.unwrap().unwrap() {
+            Frame::Text(text) => serde_json::from_slice(&text).unwrap(),
+            frame => panic!("unexpected frame: {:?}", frame),
+        }
+    }
+
+    async fn recv_close(client: &mut crate::ws::test::TestWsClient) -> u16 {
+        match client.recv().await.unwrap().unwrap() {
+            Frame::Close(Some(reason)) => reason.code.into(),
+            frame => panic!("unexpected frame: {:?}", frame),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn operations() {
+        let mut client = connect().await;
+
+        let init = json!({ "type": "connection_init", "payload": { "token": "secret" } });
+        client.send(text(init)).unwrap();
+        assert_eq!(
+            recv_json(&mut client).await,
+            json!({ "type": "connection_ack", "payload": { "ok": true } })
+        );
+
+        client.send(text(json!({ "type": "ping" }))).unwrap();
+        assert_eq!(recv_json(&mut client).await, json!({ "type": "pong" }));
+
+        client.send(subscribe("1", "2")).unwrap();
+        for n in 0..2 {
+            assert_eq!(
+                recv_json(&mut client).await,
+                json!({ "type": "next", "id": "1", "payload": { "data": n } })
+            );
+        }
+        assert_eq!(
+            recv_json(&mut client).await,
+            json!({ "type": "complete", "id": "1" })
+        );
+
+        client.send(subscribe("2", "invalid")).unwrap();
+        assert_eq!(
+            recv_json(&mut client).await,
+            json!({ "type": "error", "id": "2", "payload": [{ "message": "invalid" }] })
+        );
+
+        // ids of finished operations can be reused
+        client.send(subscribe("1", "pending")).unwrap();
+        client
+            .send(text(json!({ "type": "complete", "id": "1" })))
+            .unwrap();
+        client.send(subscribe("1", "1")).unwrap();
+        assert_eq!(
+            recv_json(&mut client).await,
+            json!({ "type": "next", "id": "1", "payload": { "data": 0 } })
+        );
+        assert_eq!(
+            recv_json(&mut client).await,
+            json!({ "type": "complete", "id": "1" })
+        );
+
+        client.send(subscribe("3", "pending")).unwrap();
+        client.send(subscribe("3", "pending")).unwrap();
+        assert_eq!(recv_close(&mut client).await, 4409);
+    }
+
+    #[actix_rt::test]
+    async fn protocol_violations() {
+        let mut client = connect().await;
+        client.send(subscribe("1", "1")).unwrap();
+        assert_eq!(recv_close(&mut client).await, 4401);
+
+        let mut client = connect().await;
+        client
+            .send(text(json!({ "type": "connection_init" })))
+            .unwrap();
+        assert_eq!(recv_close(&mut client).await, 4403);
+
+        let mut client = connect().await;
+        let init = json!({ "type": "connection_init", "payload": { "token": "secret" } });
+        client.send(text(init.clone())).unwrap();
+        recv_json(&mut client).await;
+        client.send(text(init)).unwrap();
+        assert_eq!(recv_close(&mut client).await, 4429);
+
+        let mut client = connect().await;
+        client.send(text(json!({ "type": "unknown" }))).unwrap();
+        assert_eq!(recv_close(&mut client).await, 4400);
+    }
+}
@@ -0,0 +1,225 @@
+//! Helpers for testing WebSocket handlers without binding a socket.
+//!
+//! # Examples
+//! ```
+//! use actix::{Actor, StreamHandler};
+//! use actix_web::{test, web, App, Error, HttpRequest, HttpResponse};
+//! use actix_web_actors::ws;
+//!
+//! struct Echo;
+//!
+//! impl Actor for Echo {
+//!     type Context = ws::WebsocketContext<Self>;
+//! }
+//!
+//! impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for Echo {
+//!     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+//!         if let Ok(ws::Message::Text(text)) = msg {
+//!             ctx.text(text);
+//!         }
+//!     }
+//! }
+//!
+//! async fn index(req: HttpRequest, stream: web::Payload) -> Result<HttpResponse, Error> {
+//!     ws::start(Echo, &req, stream)
+//! }
+//!
+//! # actix_rt::System::new().block_on(async {
+//! let app = test::init_service(App::new().route("/ws", web::get().to(index))).await;
+//!
+//! let req = test::TestRequest::get().uri("/ws");
+//! let mut client = ws::test::call_ws(&app, req).await;
+//!
+//! client.send(ws::Message::Text("hello".into())).unwrap();
+//! let frame = client.recv().await.unwrap().unwrap();
+//! assert_eq!(frame, ws::Frame::Text("hello".into()));
+//! # })
+//! ```
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use actix_codec::{Decoder as _, Encoder as _};
+use actix_http::ws::{hash_key, Codec};
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{Service, ServiceResponse},
+    error::PayloadError,
+    http::{header, StatusCode},
+    test::TestRequest,
+    Error,
+};
+use bytes::{Bytes, BytesMut};
+use futures_core::Stream;
+use tokio::sync::mpsc;
+
+use super::{Frame, Message, ProtocolError};
+
+const TEST_KEY: &str = "dGhlIHNhbXBsZSBub25jZQ==";
+
+/// Client side of an in-process WebSocket session started with [`call_ws`].
+///
+/// Messages sent with [`send`](Self::send) are fed to the handler as request payload and frames
+/// written by the handler are decoded from the response body. Dropping the client ends the request
+/// payload, which handlers see as the client disconnecting.
+pub struct TestWsClient {
+    tx: Option<mpsc::UnboundedSender<Bytes>>,
+    body: BoxBody,
+    codec: Codec,
+    buf: BytesMut,
+    eof: bool,
+}
+
+impl TestWsClient {
+    /// Encodes `msg` as a client frame and sends it to the handler.
+    ///
+    /// Sending a [`Message::Close`] also ends the request payload.
+    ///
+    /// # Errors
+    /// Returns an error if the message can not be encoded or if the session was closed.
+    pub fn send(&mut self, msg: Message) -> Result<(), ProtocolError> {
+        let is_close = matches!(msg, Message::Close(_));
+
+        let mut buf = BytesMut::new();
+        self.codec.encode(msg, &mut buf)?;
+
+        let tx = self.tx.as_ref().ok_or(ProtocolError::Io(closed_error()))?;
+        tx.send(buf.freeze())
+            .map_err(|_| ProtocolError::Io(closed_error()))?;
+
+        if is_close {
+            self.tx = None;
+        }
+
+        Ok(())
+    }
+
+    /// Receives the next frame written by the handler.
+    ///
+    /// Returns `None` once the handler has finished the response.
+    pub fn recv(&mut self) -> impl Future<Output = Option<Result<Frame, ProtocolError>>> + '_ {
+        Recv { client: self }
+    }
+}
+
+impl Stream for TestWsClient {
+    type Item = Result<Frame, ProtocolError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(frame) = this.codec.decode(&mut this.buf)? {
+                return Poll::Ready(Some(Ok(frame)));
+            }
+
+            if this.eof {
+                return Poll::Ready(None);
+            }
+
+            match Pin::new(&mut this.body).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => this.buf.extend_from_slice(&chunk),
+                Poll::Ready(Some(Err(err))) => {
+                    return Poll::Ready(Some(Err(ProtocolError::Io(std::io::Error::new(
+                        std::io::ErrorKind::Other,
+                        err.to_string(),
+                    )))))
+                }
+                Poll::Ready(None) => this.eof = true,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl fmt::Debug for TestWsClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestWsClient")
+            .field("open", &self.tx.is_some())
+            .field("eof", &self.eof)
+            .finish()
+    }
+}
+
+struct Recv<'a> {
+    client: &'a mut TestWsClient,
+}
+
+impl Future for Recv<'_> {
+    type Output = Option<Result<Frame, ProtocolError>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.get_mut().client).poll_next(cx)
+    }
+}
+
+/// Request payload fed by a [`TestWsClient`].
+struct ClientPayload {
+    rx: mpsc::UnboundedReceiver<Bytes>,
+}
+
+impl Stream for ClientPayload {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().rx.poll_recv(cx).map(|chunk| chunk.map(Ok))
+    }
+}
+
+fn closed_error() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::BrokenPipe,
+        "WebSocket session is closed",
+    )
+}
+
+/// Performs a WebSocket handshake against `app` and returns a client for the session.
+///
+/// The handshake headers are added to `req`; the method and path should already be set.
+///
+/// # Panics
+/// Panics if the service call fails or the handler does not accept the handshake.
+pub async fn call_ws<S, B>(app: &S, req: TestRequest) -> TestWsClient
+where
+    S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody + 'static,
+{
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    let req = req
+        .insert_header((header::UPGRADE, "websocket"))
+        .insert_header((header::CONNECTION, "upgrade"))
+        .insert_header((header::SEC_WEBSOCKET_VERSION, "13"))
+        .insert_header((header::SEC_WEBSOCKET_KEY, TEST_KEY))
+        .set_payload_stream(ClientPayload { rx })
+        .to_request();
+
+    let res = app.call(req).await.expect("test service call failed");
+
+    assert_eq!(
+        res.status(),
+        StatusCode::SWITCHING_PROTOCOLS,
+        "WebSocket handshake was not accepted"
+    );
+
+    let accept = hash_key(TEST_KEY.as_bytes());
+    assert_eq!(
+        res.headers()
+            .get(header::SEC_WEBSOCKET_ACCEPT)
+            .map(|val| val.as_bytes()),
+        Some(&accept[..]),
+        "WebSocket handshake response has wrong Sec-WebSocket-Accept header"
+    );
+
+    TestWsClient {
+        tx: Some(tx),
+        body: res.into_body().boxed(),
+        codec: Codec::new().client_mode(),
+        buf: BytesMut::new(),
+        eof: false,
+    }
+}
@@ -1,6 +1,6 @@
 use actix::prelude::*;
 use actix_http::ws::Codec;
-use actix_web::{web, App, HttpRequest};
+use actix_web::{http::StatusCode, web, App, HttpRequest};
 use actix_web_actors::ws;
 use bytes::Bytes;
 use futures_util::{SinkExt, StreamExt};
@@ -174,6 +174,41 @@ async fn builder_full() {
     common_test_code(srv, MAX_FRAME_SIZE).await;
 }
 
+#[actix_rt::test]
+async fn builder_with_origins() {
+    let srv = actix_test::start(|| {
+        App::new().service(web::resource("/").to(
+            |req: HttpRequest, stream: web::Payload| async move {
+                ws::WsResponseBuilder::new(Ws, &req, stream)
+                    .origins(ws::AllowedOrigins::list(["https://example.com"]))
+                    .start()
+            },
+        ))
+    });
+
+    let res = awc::Client::new()
+        .ws(srv.url("/"))
+        .origin("https://evil.example")
+        .connect()
+        .await;
+    assert!(matches!(
+        res,
+        Err(awc::error::WsClientError::InvalidResponseStatus(
+            StatusCode::FORBIDDEN
+        ))
+    ));
+
+    let (_, mut framed) = awc::Client::new()
+        .ws(srv.url("/"))
+        .origin("https://example.com")
+        .connect()
+        .await
+        .unwrap();
+    framed.send(ws::Message::Text("text".into())).await.unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Text(Bytes::from_static(b"text")));
+}
+
 #[actix_rt::test]
 async fn simple_start() {
     let srv = actix_test::start(|| {
@@ -184,3 +219,130 @@ async fn simple_start() {
 
     common_test_code(srv, DEFAULT_FRAME_SIZE).await;
 }
+
+#[actix_rt::test]
+async fn in_process_test_client() {
+    let app = actix_web::test::init_service(App::new().service(web::resource("/").to(
+        |req: HttpRequest, stream: web::Payload| async move { ws::start(Ws, &req, stream) },
+    )))
+    .await;
+
+    let req = actix_web::test::TestRequest::get();
+    let mut client = ws::test::call_ws(&app, req).await;
+
+    client.send(ws::Message::Text("text".into())).unwrap();
+    let item = client.recv().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Text(Bytes::from_static(b"text")));
+
+    client.send(ws::Message::Ping("ping".into())).unwrap();
+    let item = client.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Pong(Bytes::from_static(b"ping")));
+
+    client
+        .send(ws::Message::Close(Some(ws::CloseCode::Normal.into())))
+        .unwrap();
+    let item = client.recv().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
+
+    // session has ended
+    assert!(client.recv().await.is_none());
+    assert!(client.send(ws::Message::Text("text".into())).is_err());
+}
+
+#[actix_rt::test]
+#[should_panic(expected = "WebSocket handshake was not accepted")]
+async fn in_process_test_client_rejected() {
+    let app = actix_web::test::init_service(
+        App::new().route("/", web::get().to(actix_web::HttpResponse::Ok)),
+    )
+    .await;
+
+    ws::test::call_ws(&app, actix_web::test::TestRequest::get()).await;
+}
+
+/// Writes a burst of messages when started, limited by the outbound queue.
+struct Burst {
+    wait_for_room: bool,
+}
+
+impl Actor for Burst {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.set_write_capacity(1);
+
+        if self.wait_for_room {
+            send_numbers(0, ctx);
+        } else {
+            ctx.close_on_overflow(true);
+
+            for i in 0..3 {
+                ctx.text(i.to_string());
+            }
+        }
+    }
+}
+
+/// Sends numbers one after another, each once there is room in the queue.
+fn send_numbers(i: usize, ctx: &mut ws::WebsocketContext<Burst>) {
+    if i == 3 {
+        ctx.text(format!("peak {}", ctx.peak_queue_depth()));
+        return;
+    }
+
+    let send = ctx.send(ws::Message::Text(i.to_string().into()));
+    ctx.wait(send.map(move |_, _, ctx| send_numbers(i + 1, ctx)));
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for Burst {
+    fn handle(&mut self, _: Result<ws::Message, ws::ProtocolError>, _: &mut Self::Context) {}
+}
+
+#[actix_rt::test]
+async fn write_backpressure() {
+    let app = actix_web::test::init_service(App::new().service(web::resource("/").to(
+        |req: HttpRequest, stream: web::Payload| async move {
+            ws::start(
+                Burst {
+                    wait_for_room: true,
+                },
+                &req,
+                stream,
+            )
+        },
+    )))
+    .await;
+
+    let mut client = ws::test::call_ws(&app, actix_web::test::TestRequest::get()).await;
+
+    for expected in ["0", "1", "2", "peak 1"] {
+        let item = client.recv().await.unwrap().unwrap();
+        assert_eq!(item, ws::Frame::Text(Bytes::from(expected)));
+    }
+}
+
+#[actix_rt::test]
+async fn close_on_overflow() {
+    let app = actix_web::test::init_service(App::new().service(web::resource("/").to(
+        |req: HttpRequest, stream: web::Payload| async move {
+            ws::start(
+                Burst {
+                    wait_for_room: false,
+                },
+                &req,
+                stream,
+            )
+        },
+    )))
+    .await;
+
+    let mut client = ws::test::call_ws(&app, actix_web::test::TestRequest::get()).await;
+
+    let item = client.recv().await.unwrap().unwrap();
+    assert_eq!(
+        item,
+        ws::Frame::Close(Some(
+            (ws::CloseCode::Policy, "outbound queue overflow").into()
+        ))
+    );
+}
@@ -45,6 +45,19 @@
 //! }
 //! ```
 //!
+//! # Error Types
+//! Implements `ResponseError` for custom error types. See [derive@ResponseError] macro docs.
+//!
+//! ```
+//! # use actix_web_codegen::ResponseError;
+//! #[derive(Debug, ResponseError)]
+//! #[response_error(status = 404)]
+//! struct UserNotFound;
+//! # impl std::fmt::Display for UserNotFound {
+//! #     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str("user not found") }
+//! # }
+//! ```
+//!
 //! # Multiple Path Handlers
 //! There are no macros to generate multi-path handlers. Let us know in [this issue].
 //!
@@ -68,6 +81,7 @@
 use proc_macro::TokenStream;
 use quote::quote;
 
+mod response_error;
 mod route;
 
 /// Creates resource handler, allowing multiple HTTP method guards.
@@ -193,3 +207,49 @@ pub fn test(_: TokenStream, item: TokenStream) -> TokenStream {
     output.extend(item);
     output
 }
+
+/// Derives `actix_web::ResponseError` for a struct or enum.
+///
+/// The error is rendered by its `Display` implementation, which must be provided separately
+/// (for example with `derive_more::Display` or `thiserror`), with the configured status code.
+///
+/// # Attributes
+/// - `#[response_error(status = 404)]`: Sets the response status code. On an enum, the attribute
+///   can be set on the type to change the default and on each variant to override it.
+///
+/// Without any attributes, the status code is 500 Internal Server Error.
+///
+/// # Examples
+/// ```
+/// use std::fmt;
+///
+/// use actix_web::{http::StatusCode, ResponseError};
+///
+/// #[derive(Debug, ResponseError)]
+/// enum UserError {
+///     #[response_error(status = 404)]
+///     NotFound { id: u64 },
+///
+///     #[response_error(status = 400)]
+///     InvalidName(String),
+///
+///     Database,
+/// }
+///
+/// impl fmt::Display for UserError {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         match self {
+///             UserError::NotFound { id } => write!(f, "user {} not found", id),
+///             UserError::InvalidName(name) => write!(f, "invalid user name: {}", name),
+///             UserError::Database => f.write_str("internal error"),
+///         }
+///     }
+/// }
+///
+/// assert_eq!(UserError::NotFound { id: 1 }.status_code(), StatusCode::NOT_FOUND);
+/// assert_eq!(UserError::Database.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+/// ```
+#[proc_macro_derive(ResponseError, attributes(response_error))]
+pub fn response_error(input: TokenStream) -> TokenStream {
+    response_error::derive(input)
+}
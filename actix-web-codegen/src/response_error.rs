@@ -0,0 +1,127 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Fields, Lit, Meta, NestedMeta};
+
+const ATTR_NAME: &str = "response_error";
+
+/// Parses the status code from `#[response_error(status = 404)]` attributes, if any.
+fn parse_status(attrs: &[Attribute]) -> syn::Result<Option<u16>> {
+    let mut status = None;
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident(ATTR_NAME)) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(syn::Error::new_spanned(
+                meta,
+                "expected attribute arguments in parentheses: #[response_error(status = 404)]",
+            )),
+        };
+
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("status") => {
+                    let code = match &nv.lit {
+                        Lit::Int(lit) => lit.base10_parse::<u16>()?,
+                        lit => {
+                            return Err(syn::Error::new_spanned(
+                                lit,
+                                "status must be an integer literal, e.g. `status = 404`",
+                            ))
+                        }
+                    };
+
+                    if !(100..1000).contains(&code) {
+                        return Err(syn::Error::new_spanned(
+                            nv.lit,
+                            "status must be between 100 and 999",
+                        ));
+                    }
+
+                    if status.replace(code).is_some() {
+                        return Err(syn::Error::new_spanned(nv.path, "duplicate status"));
+                    }
+                }
+
+                other => {
+                    return Err(syn::Error::new_spanned(
+                        other,
+                        "unknown attribute argument, expected `status = <code>`",
+                    ))
+                }
+            }
+        }
+    }
+
+    Ok(status)
+}
+
+fn status_code(code: u16) -> TokenStream2 {
+    quote! {
+        ::actix_web::http::StatusCode::from_u16(#code).unwrap()
+    }
+}
+
+pub(crate) fn derive(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+
+    match expand(input) {
+        Ok(stream) => stream.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let default = parse_status(&input.attrs)?.unwrap_or(500);
+
+    let body = match &input.data {
+        Data::Enum(data) => {
+            let arms = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let ident = &variant.ident;
+                    let code = status_code(parse_status(&variant.attrs)?.unwrap_or(default));
+
+                    let pattern = match variant.fields {
+                        Fields::Named(_) => quote! { Self::#ident { .. } },
+                        Fields::Unnamed(_) => quote! { Self::#ident(..) },
+                        Fields::Unit => quote! { Self::#ident },
+                    };
+
+                    Ok(quote! { #pattern => #code, })
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+
+            if arms.is_empty() {
+                quote! { match *self {} }
+            } else {
+                quote! {
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            }
+        }
+
+        Data::Struct(_) => status_code(default),
+
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "ResponseError can not be derived for unions",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::actix_web::ResponseError for #name #ty_generics #where_clause {
+            fn status_code(&self) -> ::actix_web::http::StatusCode {
+                #body
+            }
+        }
+    })
+}
@@ -268,3 +268,66 @@ async fn test_wrap() {
     let body = String::from_utf8(body.to_vec()).unwrap();
     assert!(body.contains("wrong number of parameters"));
 }
+
+#[derive(Debug, actix_web_codegen::ResponseError)]
+#[response_error(status = 400)]
+enum AppError {
+    #[response_error(status = 404)]
+    NotFound {
+        id: u32,
+    },
+    Invalid(&'static str),
+    #[response_error(status = 503)]
+    Unavailable,
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::NotFound { id } => write!(f, "item {} not found", id),
+            AppError::Invalid(reason) => write!(f, "invalid request: {}", reason),
+            AppError::Unavailable => f.write_str("try again later"),
+        }
+    }
+}
+
+#[derive(Debug, actix_web_codegen::ResponseError)]
+struct Opaque;
+
+impl std::fmt::Display for Opaque {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("opaque")
+    }
+}
+
+#[get("/error/{kind}")]
+async fn error_handler(kind: web::Path<String>) -> Result<HttpResponse, AppError> {
+    match kind.as_str() {
+        "not-found" => Err(AppError::NotFound { id: 7 }),
+        "invalid" => Err(AppError::Invalid("bad")),
+        _ => Err(AppError::Unavailable),
+    }
+}
+
+#[actix_rt::test]
+async fn test_response_error_derive() {
+    use actix_web::ResponseError as _;
+
+    assert_eq!(Opaque.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(
+        AppError::Invalid("x").status_code(),
+        StatusCode::BAD_REQUEST
+    );
+
+    let srv = actix_test::start(|| App::new().service(error_handler));
+
+    let mut res = srv.get("/error/not-found").send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert_eq!(res.body().await.unwrap(), "item 7 not found");
+
+    let res = srv.get("/error/invalid").send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = srv.get("/error/other").send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
@@ -0,0 +1,479 @@
+//! Minimal ACME ([RFC 8555]) client for ordering certificates with HTTP-01 challenges.
+//!
+//! [RFC 8555]: https://datatracker.ietf.org/doc/html/rfc8555
+
+use std::{collections::HashMap, fmt, sync::RwLock, time::Duration};
+
+use bytes::Bytes;
+use rcgen::{Certificate, CertificateParams, DistinguishedName};
+use ring::{
+    digest,
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, KeyPair as _, ECDSA_P256_SHA256_FIXED_SIGNING},
+};
+use serde_json::{json, Value};
+
+use crate::http::header;
+
+/// Largest response body read from the ACME server.
+const MAX_RESPONSE_SIZE: usize = 1_048_576;
+
+/// Number of times pending authorizations and orders are polled before giving up.
+const MAX_POLLS: usize = 30;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+const BAD_NONCE: &str = "urn:ietf:params:acme:error:badNonce";
+
+/// Error that occurred while talking to the ACME server.
+#[derive(Debug)]
+pub(super) struct AcmeError(String);
+
+impl AcmeError {
+    pub(super) fn new(msg: impl Into<String>) -> Self {
+        AcmeError(msg.into())
+    }
+}
+
+impl fmt::Display for AcmeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Certificate chain, as PEM, and the PKCS #8 DER encoded key it was issued for.
+pub(super) struct Issued {
+    pub(super) chain_pem: Bytes,
+    pub(super) key_der: Vec<u8>,
+}
+
+struct Directory {
+    new_nonce: String,
+    new_account: String,
+    new_order: String,
+}
+
+struct AcmeResponse {
+    location: Option<String>,
+    body: Bytes,
+}
+
+impl AcmeResponse {
+    fn json(&self) -> Result<Value, AcmeError> {
+        serde_json::from_slice(&self.body)
+            .map_err(|err| AcmeError::new(format!("invalid ACME response: {}", err)))
+    }
+}
+
+/// ACME account session.
+pub(super) struct AcmeClient {
+    http: awc::Client,
+    directory: Directory,
+    key: EcdsaKeyPair,
+    rng: SystemRandom,
+    kid: Option<String>,
+    nonce: Option<String>,
+}
+
+impl AcmeClient {
+    /// Fetches the directory, and creates or looks up the account for the given key.
+    pub(super) async fn connect(
+        directory_url: &str,
+        account_key: &[u8],
+        contact: &[String],
+    ) -> Result<Self, AcmeError> {
+        let http = awc::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .finish();
+
+        let mut res = http
+            .get(directory_url)
+            .send()
+            .await
+            .map_err(|err| AcmeError::new(format!("failed to fetch directory: {}", err)))?;
+
+        let directory: Value = res
+            .json()
+            .limit(MAX_RESPONSE_SIZE)
+            .await
+            .map_err(|err| AcmeError::new(format!("invalid directory: {}", err)))?;
+
+        let url = |name: &str| {
+            directory[name]
+                .as_str()
+                .map(ToOwned::to_owned)
+                .ok_or_else(|| AcmeError::new(format!("directory has no `{}` URL", name)))
+        };
+
+        let directory = Directory {
+            new_nonce: url("newNonce")?,
+            new_account: url("newAccount")?,
+            new_order: url("newOrder")?,
+        };
+
+        let key = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, account_key)
+            .map_err(|err| AcmeError::new(format!("invalid account key: {}", err)))?;
+
+        let mut client = AcmeClient {
+            http,
+            directory,
+            key,
+            rng: SystemRandom::new(),
+            kid: None,
+            nonce: None,
+        };
+
+        let payload = json!({
+            "termsOfServiceAgreed": true,
+            "contact": contact,
+        });
+
+        let url = client.directory.new_account.clone();
+        let res = client.post(&url, Some(&payload)).await?;
+
+        client.kid = Some(
+            res.location
+                .ok_or_else(|| AcmeError::new("account response has no location"))?,
+        );
+
+        Ok(client)
+    }
+
+    /// Orders a certificate for `domains`, publishing HTTP-01 key authorizations in `challenges`
+    /// while they are being validated.
+    pub(super) async fn order(
+        &mut self,
+        domains: &[String],
+        challenges: &RwLock<HashMap<String, String>>,
+    ) -> Result<Issued, AcmeError> {
+        let identifiers = domains
+            .iter()
+            .map(|domain| json!({ "type": "dns", "value": domain }))
+            .collect::<Vec<_>>();
+
+        let url = self.directory.new_order.clone();
+        let res = self
+            .post(&url, Some(&json!({ "identifiers": identifiers })))
+            .await?;
+
+        let order_url = res
+            .location
+            .clone()
+            .ok_or_else(|| AcmeError::new("order response has no location"))?;
+        let order = res.json()?;
+
+        for authz_url in string_array(&order["authorizations"]) {
+            let authz = self.post(&authz_url, None).await?.json()?;
+
+            if authz["status"] == "valid" {
+                continue;
+            }
+
+            let challenge = authz["challenges"]
+                .as_array()
+                .and_then(|challenges| challenges.iter().find(|ch| ch["type"] == "http-01"))
+                .ok_or_else(|| AcmeError::new("no HTTP-01 challenge offered"))?;
+
+            let token = challenge["token"]
+                .as_str()
+                .ok_or_else(|| AcmeError::new("challenge has no token"))?
+                .to_owned();
+            let challenge_url = challenge["url"]
+                .as_str()
+                .ok_or_else(|| AcmeError::new("challenge has no URL"))?
+                .to_owned();
+
+            let key_authorization = format!("{}.{}", token, self.thumbprint());
+            challenges
+                .write()
+                .unwrap()
+                .insert(token.clone(), key_authorization);
+
+            let validated = match self.post(&challenge_url, Some(&json!({}))).await {
+                Ok(_) => self.poll(&authz_url).await,
+                Err(err) => Err(err),
+            };
+
+            challenges.write().unwrap().remove(&token);
+            validated?;
+        }
+
+        let finalize_url = order["finalize"]
+            .as_str()
+            .ok_or_else(|| AcmeError::new("order has no finalize URL"))?
+            .to_owned();
+
+        let mut params = CertificateParams::new(domains.to_vec());
+        params.distinguished_name = DistinguishedName::new();
+        let cert = Certificate::from_params(params)
+            .map_err(|err| AcmeError::new(format!("failed to generate key: {}", err)))?;
+        let csr = cert
+            .serialize_request_der()
+            .map_err(|err| AcmeError::new(format!("failed to generate CSR: {}", err)))?;
+
+        self.post(&finalize_url, Some(&json!({ "csr": b64(&csr) })))
+            .await?;
+
+        let order = self.poll(&order_url).await?;
+
+        let cert_url = order["certificate"]
+            .as_str()
+            .ok_or_else(|| AcmeError::new("order has no certificate URL"))?
+            .to_owned();
+
+        let chain_pem = self.post(&cert_url, None).await?.body;
+
+        Ok(Issued {
+            chain_pem,
+            key_der: cert.serialize_private_key_der(),
+        })
+    }
+
+    /// Polls an authorization or order until it is no longer being processed.
+    async fn poll(&mut self, url: &str) -> Result<Value, AcmeError> {
+        for _ in 0..MAX_POLLS {
+            let res = self.post(url, None).await?.json()?;
+
+            match res["status"].as_str() {
+                Some("valid") => return Ok(res),
+                Some("pending") | Some("processing") | Some("ready") => {
+                    actix_rt::time::sleep(POLL_INTERVAL).await
+                }
+                _ => return Err(AcmeError::new(format!("validation failed: {}", res))),
+            }
+        }
+
+        Err(AcmeError::new(format!("timed out waiting for {}", url)))
+    }
+
+    /// Sends a signed request; a `None` payload sends a POST-as-GET request.
+    async fn post(
+        &mut self,
+        url: &str,
+        payload: Option<&Value>,
+    ) -> Result<AcmeResponse, AcmeError> {
+        let mut retried = false;
+
+        loop {
+            let nonce = match self.nonce.take() {
+                Some(nonce) => nonce,
+                None => self.new_nonce().await?,
+            };
+
+            let body = self.sign(url, &nonce, payload)?;
+
+            let mut res = self
+                .http
+                .post(url)
+                .insert_header((header::CONTENT_TYPE, "application/jose+json"))
+                .send_body(body)
+                .await
+                .map_err(|err| AcmeError::new(format!("request to {} failed: {}", url, err)))?;
+
+            self.nonce = header_str(res.headers(), "replay-nonce");
+            let location = header_str(res.headers(), header::LOCATION.as_str());
+
+            let body =
+                res.body().limit(MAX_RESPONSE_SIZE).await.map_err(|err| {
+                    AcmeError::new(format!("request to {} failed: {}", url, err))
+                })?;
+
+            if res.status().is_success() {
+                return Ok(AcmeResponse { location, body });
+            }
+
+            let problem = serde_json::from_slice::<Value>(&body).unwrap_or_default();
+
+            // nonces can expire between requests; retry once with a fresh one
+            if problem["type"] == BAD_NONCE && !retried {
+                retried = true;
+                continue;
+            }
+
+            return Err(AcmeError::new(format!(
+                "request to {} failed with {}: {}",
+                url,
+                res.status(),
+                String::from_utf8_lossy(&body)
+            )));
+        }
+    }
+
+    async fn new_nonce(&self) -> Result<String, AcmeError> {
+        let res = self
+            .http
+            .head(&self.directory.new_nonce)
+            .send()
+            .await
+            .map_err(|err| AcmeError::new(format!("failed to get nonce: {}", err)))?;
+
+        header_str(res.headers(), "replay-nonce")
+            .ok_or_else(|| AcmeError::new("nonce response has no Replay-Nonce header"))
+    }
+
+    /// Builds a JWS in flattened JSON serialization.
+    fn sign(
+        &self,
+        url: &str,
+        nonce: &str,
+        payload: Option<&Value>,
+    ) -> Result<String, AcmeError> {
+        let mut protected = json!({
+            "alg": "ES256",
+            "nonce": nonce,
+            "url": url,
+        });
+
+        // the account URL identifies the key once the account exists
+        match self.kid {
+            Some(ref kid) => protected["kid"] = Value::from(kid.as_str()),
+            None => protected["jwk"] = self.jwk(),
+        }
+
+        let protected = b64(protected.to_string().as_bytes());
+        let payload =
+            payload.map_or_else(String::new, |payload| b64(payload.to_string().as_bytes()));
+
+        let signature = self
+            .key
+            .sign(&self.rng, format!("{}.{}", protected, payload).as_bytes())
+            .map_err(|_| AcmeError::new("failed to sign request"))?;
+
+        Ok(json!({
+            "protected": protected,
+            "payload": payload,
+            "signature": b64(signature.as_ref()),
+        })
+        .to_string())
+    }
+
+    fn jwk(&self) -> Value {
+        let (x, y) = self.public_coordinates();
+        json!({ "crv": "P-256", "kty": "EC", "x": x, "y": y })
+    }
+
+    /// Returns the JWK thumbprint of the account key, as defined in RFC 7638.
+    fn thumbprint(&self) -> String {
+        let (x, y) = self.public_coordinates();
+
+        // members in lexicographic order, without whitespace
+        let jwk = format!(r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#, x, y);
+
+        b64(digest::digest(&digest::SHA256, jwk.as_bytes()).as_ref())
+    }
+
+    fn public_coordinates(&self) -> (String, String) {
+        // uncompressed point: 0x04 || x || y
+        let point = self.key.public_key().as_ref();
+        (b64(&point[1..33]), b64(&point[33..65]))
+    }
+}
+
+/// Generates a new PKCS #8 DER encoded account key.
+pub(super) fn generate_account_key() -> Result<Vec<u8>, AcmeError> {
+    EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &SystemRandom::new())
+        .map(|doc| doc.as_ref().to_vec())
+        .map_err(|_| AcmeError::new("failed to generate account key"))
+}
+
+fn b64(data: &[u8]) -> String {
+    base64::encode_config(data, base64::URL_SAFE_NO_PAD)
+}
+
+fn header_str(headers: &crate::http::header::HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|val| val.to_str().ok())
+        .map(ToOwned::to_owned)
+}
+
+fn string_array(val: &Value) -> Vec<String> {
+    val.as_array()
+        .map(|vals| {
+            vals.iter()
+                .filter_map(|val| val.as_str().map(ToOwned::to_owned))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_FIXED};
+
+    use super::*;
+
+    fn client() -> AcmeClient {
+        let key = generate_account_key().unwrap();
+
+        AcmeClient {
+            http: awc::Client::default(),
+            directory: Directory {
+                new_nonce: String::new(),
+                new_account: String::new(),
+                new_order: String::new(),
+            },
+            key: EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &key).unwrap(),
+            rng: SystemRandom::new(),
+            kid: None,
+            nonce: None,
+        }
+    }
+
+    fn decode(data: &Value) -> Vec<u8> {
+        base64::decode_config(data.as_str().unwrap(), base64::URL_SAFE_NO_PAD).unwrap()
+    }
+
+    #[test]
+    fn signed_requests() {
+        let mut client = client();
+
+        let jws: Value = serde_json::from_str(
+            &client
+                .sign("https://acme/new-acct", "abc", Some(&json!({ "a": 1 })))
+                .unwrap(),
+        )
+        .unwrap();
+
+        let protected: Value = serde_json::from_slice(&decode(&jws["protected"])).unwrap();
+        assert_eq!(protected["alg"], "ES256");
+        assert_eq!(protected["nonce"], "abc");
+        assert_eq!(protected["url"], "https://acme/new-acct");
+        assert_eq!(protected["jwk"]["kty"], "EC");
+        assert_eq!(decode(&jws["payload"]), br#"{"a":1}"#);
+
+        let signed = format!(
+            "{}.{}",
+            jws["protected"].as_str().unwrap(),
+            jws["payload"].as_str().unwrap()
+        );
+        UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, client.key.public_key().as_ref())
+            .verify(signed.as_bytes(), &decode(&jws["signature"]))
+            .unwrap();
+
+        // accounts are referred to by URL once created; POST-as-GET has an empty payload
+        client.kid = Some("https://acme/acct/1".to_owned());
+        let jws: Value =
+            serde_json::from_str(&client.sign("https://acme/order", "def", None).unwrap())
+                .unwrap();
+        let protected: Value = serde_json::from_slice(&decode(&jws["protected"])).unwrap();
+        assert_eq!(protected["kid"], "https://acme/acct/1");
+        assert!(protected.get("jwk").is_none());
+        assert_eq!(jws["payload"], "");
+    }
+
+    #[test]
+    fn thumbprint() {
+        let client = client();
+        let jwk = client.jwk();
+
+        let expected = format!(
+            r#"{{"crv":"P-256","kty":"EC","x":{},"y":{}}}"#,
+            jwk["x"], jwk["y"]
+        );
+        let expected = b64(digest::digest(&digest::SHA256, expected.as_bytes()).as_ref());
+
+        assert_eq!(client.thumbprint(), expected);
+        assert_eq!(decode(&jwk["x"]).len(), 32);
+    }
+}
@@ -0,0 +1,617 @@
+//! Automatic certificate management using ACME, as offered by Let's Encrypt.
+//!
+//! [`Acme`] obtains a certificate for a set of domains from an ACME certificate authority, proving
+//! control of the domains with HTTP-01 challenges served on the plaintext listener. Certificates
+//! are renewed in the background before they expire and are swapped into the TLS acceptor
+//! through a [`CertResolver`], so new connections use them without a restart.
+//!
+//! The HTTP-01 challenge requires the server to be reachable on port 80 of each domain.
+//!
+//! # Examples
+//! ```no_run
+//! use actix_web::{acme::Acme, middleware::RedirectHttps, web, App, HttpResponse, HttpServer};
+//!
+//! #[actix_web::main]
+//! async fn main() -> std::io::Result<()> {
+//!     let acme = Acme::new(["example.com", "www.example.com"])
+//!         .contact("mailto:admin@example.com")
+//!         .cache_dir("/var/lib/example/acme");
+//!
+//!     let tls_config = acme.server_config();
+//!     actix_web::rt::spawn(acme.clone().run());
+//!
+//!     HttpServer::new(move || {
+//!         App::new()
+//!             // challenge requests are exempt from the redirect
+//!             .wrap(RedirectHttps::new())
+//!             .service(acme.challenge_service())
+//!             .route("/", web::get().to(HttpResponse::Ok))
+//!     })
+//!     .bind(("0.0.0.0", 80))?
+//!     .bind_rustls(("0.0.0.0", 443), tls_config)?
+//!     .run()
+//!     .await
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    convert::TryFrom as _,
+    fmt, fs,
+    io::{self, Write as _},
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use actix_utils::future::ready;
+use tls_rustls::{Certificate, PrivateKey, ServerConfig};
+
+use crate::{tls::CertResolver, web, HttpResponse, Resource};
+
+mod client;
+
+use self::client::{AcmeClient, AcmeError};
+
+/// Directory URL of the Let's Encrypt production environment.
+pub const LETS_ENCRYPT_PRODUCTION: &str = "https://acme-v02.api.letsencrypt.org/directory";
+
+/// Directory URL of the Let's Encrypt staging environment, which has more generous rate limits
+/// but issues certificates that are not trusted by browsers.
+pub const LETS_ENCRYPT_STAGING: &str = "https://acme-staging-v02.api.letsencrypt.org/directory";
+
+/// Time to wait before retrying after failing to obtain a certificate.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Longest time to sleep before checking whether the certificate is due for renewal again.
+const MAX_SLEEP: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// ACME certificate manager.
+///
+/// `Acme` is a cheap handle to shared state; configure it before cloning it into the
+/// `HttpServer::new` closure. Certificates are only obtained and renewed while the future
+/// returned by [`run`](Self::run) is polled.
+///
+/// By submitting requests to the certificate authority, you agree to its terms of service.
+#[derive(Clone)]
+pub struct Acme {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    domains: Vec<String>,
+    contact: Vec<String>,
+    directory: String,
+    cache_dir: Option<PathBuf>,
+    renew_before: Duration,
+    resolver: CertResolver,
+
+    /// Key authorizations of pending challenges, by token.
+    challenges: RwLock<HashMap<String, String>>,
+
+    /// Account key, once generated or loaded.
+    account_key: Mutex<Option<Vec<u8>>>,
+}
+
+impl Acme {
+    /// Constructs a manager for a certificate covering `domains`, using Let's Encrypt.
+    ///
+    /// # Panics
+    /// Panics if `domains` is empty.
+    pub fn new<I>(domains: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let domains = domains.into_iter().map(Into::into).collect::<Vec<_>>();
+        assert!(
+            !domains.is_empty(),
+            "ACME certificate needs at least one domain"
+        );
+
+        Acme {
+            inner: Arc::new(Inner {
+                domains,
+                contact: Vec::new(),
+                directory: LETS_ENCRYPT_PRODUCTION.to_owned(),
+                cache_dir: None,
+                renew_before: Duration::from_secs(30 * 24 * 60 * 60),
+                resolver: CertResolver::empty(),
+                challenges: RwLock::new(HashMap::new()),
+                account_key: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Adds a contact URL, such as `mailto:admin@example.com`, to the account.
+    ///
+    /// The certificate authority may use it to warn about expiring certificates.
+    ///
+    /// # Panics
+    /// Panics if called after the manager has been cloned.
+    pub fn contact(mut self, contact: impl Into<String>) -> Self {
+        self.inner_mut().contact.push(contact.into());
+        self
+    }
+
+    /// Sets the directory URL of the certificate authority. Defaults to the Let's Encrypt
+    /// production environment.
+    ///
+    /// # Panics
+    /// Panics if called after the manager has been cloned.
+    pub fn directory(mut self, url: impl Into<String>) -> Self {
+        self.inner_mut().directory = url.into();
+        self
+    }
+
+    /// Sets a directory to store the account key and certificate in, so they are reused after
+    /// restarts.
+    ///
+    /// Without a cache, a new account and certificate are requested on every start, which quickly
+    /// runs into the rate limits of the certificate authority.
+    ///
+    /// # Panics
+    /// Panics if called after the manager has been cloned.
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.inner_mut().cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets how long before expiry certificates are renewed. Defaults to 30 days.
+    ///
+    /// # Panics
+    /// Panics if called after the manager has been cloned.
+    pub fn renew_before(mut self, duration: Duration) -> Self {
+        self.inner_mut().renew_before = duration;
+        self
+    }
+
+    /// Sets the resolver that obtained certificates are added to, to use them alongside other
+    /// certificates.
+    ///
+    /// The certificate is added for each of the domains.
+    ///
+    /// # Panics
+    /// Panics if called after the manager has been cloned.
+    pub fn resolver(mut self, resolver: CertResolver) -> Self {
+        self.inner_mut().resolver = resolver;
+        self
+    }
+
+    /// Returns the resolver that obtained certificates are added to.
+    pub fn cert_resolver(&self) -> &CertResolver {
+        &self.inner.resolver
+    }
+
+    /// Builds a server config for [`HttpServer::bind_rustls`](crate::HttpServer::bind_rustls)
+    /// that uses the managed certificate.
+    ///
+    /// Handshakes fail until a certificate has been obtained.
+    pub fn server_config(&self) -> ServerConfig {
+        self.inner.resolver.server_config()
+    }
+
+    /// Returns a resource that answers HTTP-01 challenges at `/.well-known/acme-challenge/`.
+    ///
+    /// Register it on the app that serves plaintext requests on port 80.
+    pub fn challenge_service(&self) -> Resource {
+        let inner = Arc::clone(&self.inner);
+
+        web::resource("/.well-known/acme-challenge/{token}").route(web::get().to(
+            move |token: web::Path<String>| {
+                let res = match inner.challenges.read().unwrap().get(&*token) {
+                    Some(key_authorization) => HttpResponse::Ok()
+                        .content_type(mime::TEXT_PLAIN)
+                        .body(key_authorization.clone()),
+                    None => HttpResponse::NotFound().finish(),
+                };
+
+                ready(res)
+            },
+        ))
+    }
+
+    /// Obtains a certificate and keeps renewing it before it expires.
+    ///
+    /// A cached certificate is used if there is one. Failures are logged and retried hourly. The
+    /// returned future never completes; spawn it on the Actix runtime.
+    pub async fn run(self) {
+        let inner = &self.inner;
+        let mut expires = inner.load_cached_cert();
+
+        loop {
+            if let Some(expires) = expires {
+                let renew_at = expires
+                    .checked_sub(inner.renew_before)
+                    .unwrap_or(UNIX_EPOCH);
+
+                if let Ok(wait) = renew_at.duration_since(SystemTime::now()) {
+                    actix_rt::time::sleep(wait.min(MAX_SLEEP)).await;
+                    continue;
+                }
+            }
+
+            match inner.obtain().await {
+                Ok(new_expires) => {
+                    log::info!("obtained certificate for {:?}", inner.domains);
+                    expires = Some(new_expires);
+                }
+                Err(err) => {
+                    log::error!(
+                        "failed to obtain certificate for {:?}: {}",
+                        inner.domains,
+                        err
+                    );
+                    actix_rt::time::sleep(RETRY_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Arc::get_mut(&mut self.inner).expect("Acme must be configured before it is cloned.")
+    }
+}
+
+impl fmt::Debug for Acme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Acme")
+            .field("domains", &self.inner.domains)
+            .field("directory", &self.inner.directory)
+            .field("cache_dir", &self.inner.cache_dir)
+            .finish()
+    }
+}
+
+impl Inner {
+    async fn obtain(&self) -> Result<SystemTime, AcmeError> {
+        let account_key = self.account_key()?;
+
+        let mut client =
+            AcmeClient::connect(&self.directory, &account_key, &self.contact).await?;
+        let issued = client.order(&self.domains, &self.challenges).await?;
+
+        let expires = self.install(&issued.chain_pem, issued.key_der.clone())?;
+
+        // the certificate is in use already; failing here would re-order it on every retry
+        let cached = self
+            .cache_path("crt")
+            .map_or(Ok(()), |path| write_cache(path, &issued.chain_pem, false))
+            .and_then(|_| {
+                self.cache_path("key").map_or(Ok(()), |path| {
+                    write_cache(path, pem("PRIVATE KEY", &issued.key_der).as_bytes(), true)
+                })
+            });
+
+        if let Err(err) = cached {
+            log::warn!(
+                "failed to cache certificate for {:?}: {}",
+                self.domains,
+                err
+            );
+        }
+
+        Ok(expires)
+    }
+
+    /// Adds the certificate to the resolver, returning its expiry time.
+    fn install(&self, chain_pem: &[u8], key_der: Vec<u8>) -> Result<SystemTime, AcmeError> {
+        let chain = rustls_pemfile::certs(&mut &*chain_pem)
+            .map_err(|err| AcmeError::new(format!("invalid certificate chain: {}", err)))?;
+
+        let expires = chain
+            .first()
+            .and_then(|cert| not_after(cert))
+            .ok_or_else(|| AcmeError::new("invalid certificate"))?;
+
+        let chain = chain.into_iter().map(Certificate).collect::<Vec<_>>();
+
+        for domain in &self.domains {
+            self.resolver
+                .set_cert(domain, chain.clone(), PrivateKey(key_der.clone()))
+                .map_err(|err| AcmeError::new(format!("invalid certificate: {}", err)))?;
+        }
+
+        Ok(expires)
+    }
+
+    /// Installs the cached certificate, if any, returning its expiry time.
+    fn load_cached_cert(&self) -> Option<SystemTime> {
+        let chain_pem = fs::read(self.cache_path("crt")?).ok()?;
+        let key_pem = fs::read(self.cache_path("key")?).ok()?;
+        let key_der = rustls_pemfile::pkcs8_private_keys(&mut &*key_pem)
+            .ok()?
+            .pop()?;
+
+        match self.install(&chain_pem, key_der) {
+            Ok(expires) => Some(expires),
+            Err(err) => {
+                log::warn!("ignoring cached certificate: {}", err);
+                None
+            }
+        }
+    }
+
+    /// Returns the account key, loading it from the cache or generating it on first use.
+    fn account_key(&self) -> Result<Vec<u8>, AcmeError> {
+        let mut account_key = self.account_key.lock().unwrap();
+
+        if let Some(ref key) = *account_key {
+            return Ok(key.clone());
+        }
+
+        let path = self.cache_dir.as_ref().map(|dir| dir.join("account.key"));
+
+        let cached = path
+            .as_ref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|pem| rustls_pemfile::pkcs8_private_keys(&mut &*pem).ok()?.pop());
+
+        let key = match cached {
+            Some(key) => key,
+            None => {
+                let key = client::generate_account_key()?;
+
+                if let Some(path) = path {
+                    write_cache(path, pem("PRIVATE KEY", &key).as_bytes(), true)?;
+                }
+
+                key
+            }
+        };
+
+        *account_key = Some(key.clone());
+        Ok(key)
+    }
+
+    fn cache_path(&self, ext: &str) -> Option<PathBuf> {
+        let dir = self.cache_dir.as_ref()?;
+        Some(dir.join(format!("{}.{}", self.domains[0], ext)))
+    }
+}
+
+/// Writes a cache file; `private` files are only readable by their owner on unix.
+fn write_cache(path: PathBuf, data: &[u8], private: bool) -> Result<(), AcmeError> {
+    let write = |path: &PathBuf| -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut opts = fs::OpenOptions::new();
+        opts.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        if private {
+            use std::os::unix::fs::OpenOptionsExt as _;
+            opts.mode(0o600);
+        }
+
+        let mut file = opts.open(path)?;
+
+        // the mode only applies to new files
+        #[cfg(unix)]
+        if private {
+            use std::os::unix::fs::PermissionsExt as _;
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+
+        #[cfg(not(unix))]
+        let _ = private;
+
+        file.write_all(data)
+    };
+
+    write(&path)
+        .map_err(|err| AcmeError::new(format!("failed to write {}: {}", path.display(), err)))
+}
+
+/// Encodes DER data as PEM.
+fn pem(label: &str, der: &[u8]) -> String {
+    let encoded = base64::encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap());
+        pem.push('\n');
+    }
+
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+/// Reads the end of the validity period of a DER encoded X.509 certificate.
+fn not_after(cert: &[u8]) -> Option<SystemTime> {
+    let (_, cert, _) = der_read(cert)?;
+    let (_, mut tbs, _) = der_read(cert)?;
+
+    // skip the explicitly tagged version, if present
+    let (tag, _, rest) = der_read(tbs)?;
+    if tag == 0xa0 {
+        tbs = rest;
+    }
+
+    // serial number, signature algorithm and issuer
+    for _ in 0..3 {
+        tbs = der_read(tbs)?.2;
+    }
+
+    let (_, validity, _) = der_read(tbs)?;
+    let (_, _, validity) = der_read(validity)?;
+    let (tag, time, _) = der_read(validity)?;
+
+    parse_time(tag, std::str::from_utf8(time).ok()?)
+}
+
+/// Splits a DER value into its tag, contents and the remaining input.
+fn der_read(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, input) = input.split_first()?;
+    let (&len, mut input) = input.split_first()?;
+
+    let len = if len < 0x80 {
+        usize::from(len)
+    } else {
+        // long form: the low bits give the number of length bytes
+        let n = usize::from(len & 0x7f);
+        if n == 0 || n > 4 || input.len() < n {
+            return None;
+        }
+
+        let (len_bytes, rest) = input.split_at(n);
+        input = rest;
+        len_bytes
+            .iter()
+            .fold(0, |len, &byte| (len << 8) | usize::from(byte))
+    };
+
+    if input.len() < len {
+        return None;
+    }
+
+    let (contents, rest) = input.split_at(len);
+    Some((tag, contents, rest))
+}
+
+/// Parses an ASN.1 `UTCTime` (tag 0x17) or `GeneralizedTime` (tag 0x18) in UTC.
+fn parse_time(tag: u8, time: &str) -> Option<SystemTime> {
+    let time = time.strip_suffix('Z')?;
+
+    let (year, rest) = match tag {
+        0x17 => {
+            let year: i64 = time.get(..2)?.parse().ok()?;
+            (
+                if year < 50 { 2000 + year } else { 1900 + year },
+                &time[2..],
+            )
+        }
+        0x18 => (time.get(..4)?.parse().ok()?, &time[4..]),
+        _ => return None,
+    };
+
+    if rest.len() != 10 || !rest.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let field = |idx: usize| rest[idx..idx + 2].parse::<i64>().unwrap();
+    let (month, day) = (field(0), field(2));
+    let (hour, min, sec) = (field(4), field(6), field(8));
+
+    // days since the Unix epoch of a proleptic Gregorian date
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let secs = days * 86_400 + hour * 3600 + min * 60 + sec;
+    Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use rcgen::{date_time_ymd, CertificateParams};
+
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        App,
+    };
+
+    #[test]
+    fn certificate_expiry() {
+        let mut params = CertificateParams::new(vec!["example.com".to_owned()]);
+        params.not_after = date_time_ymd(2031, 3, 14);
+        let cert = rcgen::Certificate::from_params(params).unwrap();
+
+        let expected = UNIX_EPOCH + Duration::from_secs(1_931_212_800);
+        assert_eq!(not_after(&cert.serialize_der().unwrap()), Some(expected));
+
+        // UTCTime is used for dates before 2050
+        assert_eq!(
+            parse_time(0x17, "991231235959Z"),
+            Some(UNIX_EPOCH + Duration::from_secs(946_684_799))
+        );
+        assert_eq!(
+            parse_time(0x18, "20500101000000Z"),
+            Some(UNIX_EPOCH + Duration::from_secs(2_524_608_000))
+        );
+        assert_eq!(parse_time(0x17, "9912312359Z"), None);
+        assert_eq!(not_after(b"\x30\x05\x30"), None);
+    }
+
+    #[actix_rt::test]
+    async fn answers_challenges() {
+        let acme = Acme::new(["example.com"]);
+        acme.inner
+            .challenges
+            .write()
+            .unwrap()
+            .insert("token".to_owned(), "token.thumbprint".to_owned());
+
+        let app = test::init_service(App::new().service(acme.challenge_service())).await;
+
+        let req = TestRequest::get()
+            .uri("/.well-known/acme-challenge/token")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(test::read_body(res).await, "token.thumbprint");
+
+        let req = TestRequest::get()
+            .uri("/.well-known/acme-challenge/other")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn installs_cached_certificate() {
+        let dir = std::env::temp_dir().join(format!("actix-acme-{}", rand::random::<u64>()));
+
+        let mut params = CertificateParams::new(vec!["example.com".to_owned()]);
+        params.not_after = date_time_ymd(2031, 3, 14);
+        let cert = rcgen::Certificate::from_params(params).unwrap();
+
+        write_cache(
+            dir.join("example.com.crt"),
+            pem("CERTIFICATE", &cert.serialize_der().unwrap()).as_bytes(),
+            false,
+        )
+        .unwrap();
+        write_cache(
+            dir.join("example.com.key"),
+            pem("PRIVATE KEY", &cert.serialize_private_key_der()).as_bytes(),
+            true,
+        )
+        .unwrap();
+
+        let acme = Acme::new(["example.com"]).cache_dir(&dir);
+        let expires = acme.inner.load_cached_cert().unwrap();
+        assert_eq!(
+            expires.duration_since(UNIX_EPOCH).unwrap(),
+            Duration::from_secs(1_931_212_800)
+        );
+        assert!(format!("{:?}", acme.cert_resolver()).contains("example.com"));
+
+        // account keys are generated once and cached
+        let key = acme.inner.account_key().unwrap();
+        let cached = fs::read(dir.join("account.key")).unwrap();
+        assert_eq!(
+            rustls_pemfile::pkcs8_private_keys(&mut &*cached).unwrap(),
+            vec![key]
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+
+            let mode =
+                |name| fs::metadata(dir.join(name)).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode("example.com.key"), 0o600);
+            assert_eq!(mode("account.key"), 0o600);
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
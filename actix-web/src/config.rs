@@ -1,5 +1,6 @@
 use std::net::SocketAddr;
 use std::rc::Rc;
+use std::sync::Arc;
 
 use actix_http::Extensions;
 use actix_router::ResourceDef;
@@ -8,6 +9,7 @@ use actix_service::{boxed, IntoServiceFactory, ServiceFactory};
 use crate::data::Data;
 use crate::error::Error;
 use crate::guard::Guard;
+use crate::info::TrustedProxies;
 use crate::resource::Resource;
 use crate::rmap::ResourceMap;
 use crate::route::Route;
@@ -113,11 +115,29 @@ pub struct AppConfig {
     secure: bool,
     host: String,
     addr: SocketAddr,
+    trusted_proxies: Option<Arc<TrustedProxies>>,
+    server_name: Option<Arc<str>>,
 }
 
 impl AppConfig {
     pub(crate) fn new(secure: bool, host: String, addr: SocketAddr) -> Self {
-        AppConfig { secure, host, addr }
+        AppConfig {
+            secure,
+            host,
+            addr,
+            trusted_proxies: None,
+            server_name: None,
+        }
+    }
+
+    pub(crate) fn with_trusted_proxies(mut self, proxies: Option<Arc<TrustedProxies>>) -> Self {
+        self.trusted_proxies = proxies;
+        self
+    }
+
+    pub(crate) fn with_server_name(mut self, name: Option<String>) -> Self {
+        self.server_name = name.map(Into::into);
+        self
     }
 
     /// Needed in actix-test crate. Semver exempt.
@@ -147,6 +167,20 @@ impl AppConfig {
         self.addr
     }
 
+    /// Returns the networks of reverse proxies whose forwarding headers are trusted, if set.
+    ///
+    /// See [`HttpServer::trusted_proxies`](crate::HttpServer::trusted_proxies).
+    pub fn trusted_proxies(&self) -> Option<&TrustedProxies> {
+        self.trusted_proxies.as_deref()
+    }
+
+    /// Returns the name of this server, if set.
+    ///
+    /// See [`HttpServer::server_name`](crate::HttpServer::server_name).
+    pub fn server_name(&self) -> Option<&str> {
+        self.server_name.as_deref()
+    }
+
     #[cfg(test)]
     pub(crate) fn set_host(&mut self, host: &str) {
         self.host = host.to_owned();
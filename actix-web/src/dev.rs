@@ -20,7 +20,8 @@ pub use actix_http::encoding::Decoder as Decompress;
 pub use crate::config::{AppConfig, AppService};
 #[doc(hidden)]
 pub use crate::handler::Handler;
-pub use crate::info::{ConnectionInfo, PeerAddr};
+pub use crate::info::{ConnectionInfo, InvalidNetwork, PeerAddr, TrustedProxies};
+pub use crate::proxy_protocol::{ProxyHeader, ProxyTlsInfo};
 pub use crate::rmap::ResourceMap;
 pub use crate::service::{HttpServiceFactory, ServiceRequest, ServiceResponse, WebService};
 
@@ -31,6 +31,13 @@ impl Error {
     pub fn error_response(&self) -> HttpResponse {
         self.cause.error_response()
     }
+
+    /// Shortcut for creating a machine-readable `HttpResponse`.
+    ///
+    /// See [`ResponseError::problem_response`].
+    pub fn problem_response(&self) -> HttpResponse {
+        self.cause.problem_response()
+    }
 }
 
 impl fmt::Display for Error {
@@ -9,22 +9,33 @@ pub use actix_http::error::{
     ContentTypeError, DispatchError, HttpError, ParseError, PayloadError,
 };
 
+use std::borrow::Cow;
+
+use bytes::Bytes;
 use derive_more::{Display, Error, From};
 use serde_json::error::Error as JsonError;
 use serde_urlencoded::de::Error as FormDeError;
 use serde_urlencoded::ser::Error as FormError;
 use url::ParseError as UrlParseError;
 
-use crate::http::StatusCode;
+use crate::{
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    HttpResponse,
+};
 
 #[allow(clippy::module_inception)]
 mod error;
 mod internal;
 mod macros;
+mod problem;
 mod response_error;
 
 pub use self::error::Error;
 pub use self::internal::*;
+pub use self::problem::ProblemDetails;
 pub use self::response_error::ResponseError;
 pub(crate) use macros::{downcast_dyn, downcast_get_type_id};
 
@@ -166,6 +177,47 @@ impl ResponseError for JsonPayloadError {
     }
 }
 
+/// A set of errors that can occur during parsing Protocol Buffers payloads.
+#[cfg(feature = "protobuf")]
+#[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum ProtoBufPayloadError {
+    /// Payload size is bigger than allowed. (default: 256kB)
+    #[display(fmt = "Protobuf payload has exceeded limit ({} bytes).", limit)]
+    Overflow { limit: usize },
+
+    /// Content type error
+    #[display(fmt = "Content type error")]
+    ContentType,
+
+    /// Decode error
+    #[display(fmt = "Protobuf decode error: {}", _0)]
+    Deserialize(prost::DecodeError),
+
+    /// Payload error
+    #[display(fmt = "Error that occur during reading payload: {}", _0)]
+    Payload(PayloadError),
+}
+
+#[cfg(feature = "protobuf")]
+impl From<PayloadError> for ProtoBufPayloadError {
+    fn from(err: PayloadError) -> Self {
+        Self::Payload(err)
+    }
+}
+
+#[cfg(feature = "protobuf")]
+impl ResponseError for ProtoBufPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Overflow { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Payload(err) => err.status_code(),
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
 /// A set of errors that can occur during parsing request paths
 #[derive(Debug, Display, Error)]
 #[non_exhaustive]
@@ -227,10 +279,154 @@ impl ResponseError for ReadlinesError {
     }
 }
 
+/// A set of errors that can occur when accepting a chunk of a resumable upload.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum UploadRangeError {
+    /// Chunks must be sent with `PUT` or `PATCH`.
+    #[display(fmt = "Uploads must use PUT or PATCH")]
+    Method,
+
+    /// `Content-Range`, `Upload-Offset` or `Upload-Length` header is malformed.
+    #[display(fmt = "Invalid upload range")]
+    Invalid,
+
+    /// Chunk does not start at the offset the upload can be resumed from.
+    #[display(fmt = "Upload must resume at offset {}, not {}", expected, offset)]
+    OffsetMismatch {
+        /// Number of bytes received so far.
+        expected: u64,
+        /// First byte of the chunk.
+        offset: u64,
+    },
+
+    /// Chunk extends beyond, or disagrees with, the total length of the upload.
+    #[display(fmt = "Upload range exceeds upload length")]
+    Length,
+}
+
+/// Returns `409 Conflict` for offset mismatches, with the expected offset in an `Upload-Offset`
+/// header, and `405 Method Not Allowed`, `400 Bad Request` or `416 Range Not Satisfiable` for the
+/// other errors.
+impl ResponseError for UploadRangeError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            UploadRangeError::Method => StatusCode::METHOD_NOT_ALLOWED,
+            UploadRangeError::Invalid => StatusCode::BAD_REQUEST,
+            UploadRangeError::OffsetMismatch { .. } => StatusCode::CONFLICT,
+            UploadRangeError::Length => StatusCode::RANGE_NOT_SATISFIABLE,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut res = HttpResponse::build(self.status_code());
+
+        if let UploadRangeError::OffsetMismatch { expected, .. } = *self {
+            res.insert_header((
+                header::HeaderName::from_static("upload-offset"),
+                HeaderValue::from(expected),
+            ));
+        }
+
+        res.content_type(mime::TEXT_PLAIN_UTF_8)
+            .body(self.to_string())
+    }
+}
+
+/// Error returned when a request does not carry acceptable credentials.
+///
+/// Responds with `401 Unauthorized` and a `WWW-Authenticate` header that challenges the client to
+/// authenticate using the `Basic` or `Bearer` scheme. `Basic` challenges always include a realm,
+/// which is `Restricted` unless set with [`realm`](Self::realm).
+#[derive(Debug, Clone, Display, Error)]
+#[display(fmt = "Authentication required")]
+pub struct AuthenticationError {
+    scheme: &'static str,
+    realm: Option<Cow<'static, str>>,
+}
+
+impl AuthenticationError {
+    /// Constructs an error that challenges the client to use the `Basic` scheme.
+    pub fn basic() -> Self {
+        AuthenticationError {
+            scheme: "Basic",
+            realm: None,
+        }
+    }
+
+    /// Constructs an error that challenges the client to use the `Bearer` scheme.
+    pub fn bearer() -> Self {
+        AuthenticationError {
+            scheme: "Bearer",
+            realm: None,
+        }
+    }
+
+    /// Sets the protection space the client should authenticate for.
+    pub fn realm(mut self, realm: impl Into<Cow<'static, str>>) -> Self {
+        self.realm = Some(realm.into());
+        self
+    }
+
+    /// Returns the `WWW-Authenticate` challenge sent with the error.
+    pub fn challenge(&self) -> String {
+        let realm = match self.realm {
+            Some(ref realm) => realm,
+            None if self.scheme == "Basic" => "Restricted",
+            None => return self.scheme.to_owned(),
+        };
+
+        let mut challenge = format!("{} realm=\"", self.scheme);
+
+        // realm is sent as a quoted-string; control characters can not be represented
+        for ch in realm.chars().filter(|ch| !ch.is_control()) {
+            if ch == '"' || ch == '\\' {
+                challenge.push('\\');
+            }
+            challenge.push(ch);
+        }
+
+        challenge.push('"');
+        challenge
+    }
+}
+
+impl ResponseError for AuthenticationError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNAUTHORIZED
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        // challenge contains no control characters so it is always a valid header value
+        let challenge = HeaderValue::from_maybe_shared(Bytes::from(self.challenge())).unwrap();
+
+        HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, challenge))
+            .content_type(mime::TEXT_PLAIN_UTF_8)
+            .body(self.to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_authentication_error() {
+        let resp = AuthenticationError::basic().error_response();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            resp.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Basic realm=\"Restricted\""
+        );
+
+        let err = AuthenticationError::bearer();
+        assert_eq!(err.challenge(), "Bearer");
+
+        let err = AuthenticationError::bearer().realm("the \"api\"\n");
+        assert_eq!(err.challenge(), "Bearer realm=\"the \\\"api\\\"\"");
+    }
+
     #[test]
     fn test_urlencoded_error() {
         let resp = UrlencodedError::Overflow { size: 0, limit: 0 }.error_response();
@@ -0,0 +1,179 @@
+//! RFC 7807 problem details.
+
+use serde::{ser::SerializeMap as _, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::{
+    body::BoxBody,
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    HttpResponse,
+};
+
+/// Media type of problem details bodies.
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Machine-readable details of an error, rendered as an [RFC 7807] `application/problem+json`
+/// body.
+///
+/// This is the body produced by [`ResponseError::problem_response`](super::ResponseError::problem_response).
+/// Implementors can use it to add a problem type or extension members to their responses.
+///
+/// # Examples
+/// ```
+/// use actix_web::{error::ProblemDetails, http::StatusCode};
+///
+/// let res = ProblemDetails::new(StatusCode::FORBIDDEN)
+///     .problem_type("https://example.com/probs/out-of-credit")
+///     .detail("Your current balance is 30, but that costs 50.")
+///     .extension("balance", 30)
+///     .into_response();
+///
+/// assert_eq!(res.status(), StatusCode::FORBIDDEN);
+/// assert_eq!(res.headers().get("content-type").unwrap(), "application/problem+json");
+/// ```
+///
+/// [RFC 7807]: https://datatracker.ietf.org/doc/html/rfc7807
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProblemDetails {
+    problem_type: String,
+    title: String,
+    status: u16,
+    detail: Option<String>,
+    instance: Option<String>,
+    extensions: Map<String, Value>,
+}
+
+impl ProblemDetails {
+    /// Constructs problem details for `status`, titled with its canonical reason.
+    ///
+    /// The problem type is `about:blank`, meaning the problem has no semantics beyond its status.
+    pub fn new(status: StatusCode) -> Self {
+        ProblemDetails {
+            problem_type: "about:blank".to_owned(),
+            title: status.canonical_reason().unwrap_or_default().to_owned(),
+            status: status.as_u16(),
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// Sets a URI identifying the problem type.
+    pub fn problem_type(mut self, problem_type: impl Into<String>) -> Self {
+        self.problem_type = problem_type.into();
+        self
+    }
+
+    /// Sets a short summary of the problem type.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Sets an explanation specific to this occurrence of the problem.
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Sets a URI identifying this occurrence of the problem.
+    pub fn instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Adds an extension member.
+    ///
+    /// # Panics
+    /// Panics if `value` cannot be serialized to JSON.
+    pub fn extension(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        let value =
+            serde_json::to_value(value).expect("problem extension must serialize to JSON");
+        self.extensions.insert(name.into(), value);
+        self
+    }
+
+    /// Returns the status code of the problem.
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// Creates an `application/problem+json` response with the problem's status code.
+    pub fn into_response(self) -> HttpResponse<BoxBody> {
+        let res = HttpResponse::new(self.status());
+        self.render(res)
+    }
+
+    /// Replaces the body and content type of `res` with the problem details, keeping its status
+    /// code and other headers.
+    pub(crate) fn render(self, mut res: HttpResponse<BoxBody>) -> HttpResponse<BoxBody> {
+        res.headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+
+        // problem details only contain strings, numbers and JSON values, which always serialize
+        let body = serde_json::to_string(&self).unwrap_or_default();
+        res.set_body(BoxBody::new(body))
+    }
+}
+
+impl Serialize for ProblemDetails {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+
+        map.serialize_entry("type", &self.problem_type)?;
+        map.serialize_entry("title", &self.title)?;
+        map.serialize_entry("status", &self.status)?;
+
+        if let Some(ref detail) = self.detail {
+            map.serialize_entry("detail", detail)?;
+        }
+
+        if let Some(ref instance) = self.instance {
+            map.serialize_entry("instance", instance)?;
+        }
+
+        for (name, value) in &self.extensions {
+            map.serialize_entry(name, value)?;
+        }
+
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::to_bytes;
+
+    #[actix_rt::test]
+    async fn problem_json() {
+        let res = ProblemDetails::new(StatusCode::NOT_FOUND)
+            .detail("no such user")
+            .instance("/users/1")
+            .extension("id", 1)
+            .into_response();
+
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            PROBLEM_JSON
+        );
+
+        let body = to_bytes(res.into_body()).await.unwrap();
+        let body = serde_json::from_slice::<Value>(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "type": "about:blank",
+                "title": "Not Found",
+                "status": 404,
+                "detail": "no such user",
+                "instance": "/users/1",
+                "id": 1,
+            })
+        );
+    }
+}
@@ -11,7 +11,7 @@ use bytes::BytesMut;
 
 use crate::{
     body::BoxBody,
-    error::{downcast_dyn, downcast_get_type_id},
+    error::{downcast_dyn, downcast_get_type_id, ProblemDetails},
     helpers,
     http::{
         header::{self, TryIntoHeaderValue},
@@ -47,6 +47,26 @@ pub trait ResponseError: fmt::Debug + fmt::Display {
         res.set_body(BoxBody::new(buf))
     }
 
+    /// Creates a machine-readable response for error, with an [RFC 7807] `application/problem+json`
+    /// body.
+    ///
+    /// By default, the response is the [error response](Self::error_response) with its body
+    /// replaced by [problem details](ProblemDetails) containing the status code, its canonical
+    /// reason as the title, and `Self`'s `Display` impl as the detail. Other headers of the error
+    /// response are kept.
+    ///
+    /// Problem responses are sent in place of error responses by the
+    /// [`ProblemJson`](crate::middleware::ProblemJson) middleware, to clients that accept them.
+    ///
+    /// [RFC 7807]: https://datatracker.ietf.org/doc/html/rfc7807
+    fn problem_response(&self) -> HttpResponse<BoxBody> {
+        let res = self.error_response();
+
+        ProblemDetails::new(res.status())
+            .detail(self.to_string())
+            .render(res)
+    }
+
     downcast_get_type_id!();
 }
 
@@ -118,6 +138,8 @@ impl ResponseError for actix_http::error::PayloadError {
 
 impl ResponseError for actix_http::ws::ProtocolError {}
 
+impl ResponseError for actix_http::error::FramingError {}
+
 impl ResponseError for actix_http::error::ContentTypeError {
     fn status_code(&self) -> StatusCode {
         StatusCode::BAD_REQUEST
@@ -130,6 +152,12 @@ impl ResponseError for actix_http::ws::HandshakeError {
     }
 }
 
+impl ResponseError for actix_http::ws::OriginHandshakeError {
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        Response::from(self).map_into_boxed_body().into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
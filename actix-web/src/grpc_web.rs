@@ -0,0 +1,768 @@
+//! gRPC-Web bridge.
+//!
+//! See [`GrpcWeb`] for details.
+
+use std::{
+    convert::Infallible,
+    fmt,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use actix_service::{IntoServiceFactory, Service, ServiceFactory};
+use bytes::{Buf as _, BufMut as _, Bytes, BytesMut};
+use futures_core::{future::LocalBoxFuture, stream::LocalBoxStream, Stream};
+use futures_util::{stream, StreamExt as _};
+
+use crate::{
+    body::{BodySize, MessageBody},
+    dev::{AppService, HttpServiceFactory, ResourceDef, ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, HeaderMap, HeaderName, HeaderValue},
+        Method, StatusCode,
+    },
+    Error, HttpMessage as _, HttpResponse,
+};
+
+/// Content type of gRPC-Web requests with Protocol Buffers messages.
+const GRPC_WEB: &str = "application/grpc-web";
+
+/// Explicit form of [`GRPC_WEB`], used for responses.
+const GRPC_WEB_PROTO: &str = "application/grpc-web+proto";
+
+/// Length of the flags byte and big-endian length preceding each frame.
+const FRAME_HEADER_LEN: usize = 5;
+
+/// Frame flag marking a message as compressed.
+const COMPRESSED_FLAG: u8 = 0x01;
+
+/// Frame flag marking the frame holding the trailers.
+const TRAILERS_FLAG: u8 = 0x80;
+
+const DEFAULT_LIMIT: usize = 4_194_304; // 4MiB
+
+/// gRPC status codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Code {
+    /// The call completed successfully.
+    Ok = 0,
+    /// The call was cancelled, typically by the caller.
+    Cancelled = 1,
+    /// Unknown error.
+    Unknown = 2,
+    /// The client specified an invalid argument.
+    InvalidArgument = 3,
+    /// The deadline expired before the call could complete.
+    DeadlineExceeded = 4,
+    /// A requested entity was not found.
+    NotFound = 5,
+    /// An entity that the client attempted to create already exists.
+    AlreadyExists = 6,
+    /// The caller does not have permission to execute the call.
+    PermissionDenied = 7,
+    /// Some resource has been exhausted, such as the request size limit.
+    ResourceExhausted = 8,
+    /// The system is not in a state required for the call.
+    FailedPrecondition = 9,
+    /// The call was aborted, typically due to a concurrency issue.
+    Aborted = 10,
+    /// The call was attempted past the valid range.
+    OutOfRange = 11,
+    /// The call is not implemented or not supported.
+    Unimplemented = 12,
+    /// Internal error.
+    Internal = 13,
+    /// The service is currently unavailable.
+    Unavailable = 14,
+    /// Unrecoverable data loss or corruption.
+    DataLoss = 15,
+    /// The request does not have valid authentication credentials.
+    Unauthenticated = 16,
+}
+
+/// Outcome of a gRPC call, sent to the client in the trailers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+    code: Code,
+    message: String,
+}
+
+impl Status {
+    /// Constructs a status with the given code and message.
+    pub fn new(code: Code, message: impl Into<String>) -> Self {
+        Status {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// Constructs a status for a successful call.
+    pub fn ok() -> Self {
+        Status::new(Code::Ok, "")
+    }
+
+    /// Returns the status code.
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    /// Returns the status message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "gRPC status {:?}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for Status {}
+
+/// A gRPC call, decoded from a gRPC-Web request.
+#[derive(Debug)]
+pub struct GrpcRequest {
+    path: String,
+    metadata: HeaderMap,
+    messages: Vec<Bytes>,
+}
+
+impl GrpcRequest {
+    /// Returns the path of the call, in the `/package.Service/Method` form.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the name of the called method, i.e. the last segment of the path.
+    pub fn method(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or_default()
+    }
+
+    /// Returns the request metadata, i.e. the headers of the HTTP request.
+    pub fn metadata(&self) -> &HeaderMap {
+        &self.metadata
+    }
+
+    /// Returns the encoded request messages.
+    pub fn messages(&self) -> &[Bytes] {
+        &self.messages
+    }
+
+    /// Returns the request message of a unary or server-streaming call.
+    ///
+    /// Fails with [`Code::InvalidArgument`] unless the request holds exactly one message.
+    pub fn into_message(self) -> Result<Bytes, Status> {
+        let mut messages = self.messages.into_iter();
+
+        match (messages.next(), messages.next()) {
+            (Some(msg), None) => Ok(msg),
+            _ => Err(Status::new(
+                Code::InvalidArgument,
+                "expected exactly one request message",
+            )),
+        }
+    }
+
+    /// Returns the encoded request messages.
+    pub fn into_messages(self) -> Vec<Bytes> {
+        self.messages
+    }
+}
+
+/// Response to a gRPC call, encoded by [`GrpcWeb`] into a gRPC-Web response.
+pub struct GrpcResponse {
+    metadata: HeaderMap,
+    messages: LocalBoxStream<'static, Result<Bytes, Status>>,
+    trailers: HeaderMap,
+}
+
+impl GrpcResponse {
+    /// Constructs the response of a unary call, holding a single encoded message.
+    pub fn new(message: impl Into<Bytes>) -> Self {
+        GrpcResponse::streaming(stream::once(futures_util::future::ready(
+            Ok(message.into()),
+        )))
+    }
+
+    /// Constructs the response of a streaming call, sending messages as they are produced.
+    ///
+    /// If the stream yields an error, the response ends with that status; otherwise it ends with
+    /// an [`Ok`](Code::Ok) status once the stream completes.
+    pub fn streaming<S>(messages: S) -> Self
+    where
+        S: Stream<Item = Result<Bytes, Status>> + 'static,
+    {
+        GrpcResponse {
+            metadata: HeaderMap::new(),
+            messages: messages.boxed_local(),
+            trailers: HeaderMap::new(),
+        }
+    }
+
+    /// Returns a mutable reference to the response metadata, sent as HTTP headers.
+    pub fn metadata_mut(&mut self) -> &mut HeaderMap {
+        &mut self.metadata
+    }
+
+    /// Returns a mutable reference to the custom trailers, sent after the status in the body.
+    pub fn trailers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.trailers
+    }
+}
+
+impl From<Status> for GrpcResponse {
+    /// Constructs a response without messages, ending with `status`.
+    fn from(status: Status) -> Self {
+        GrpcResponse::streaming(stream::once(futures_util::future::ready(Err(status))))
+    }
+}
+
+impl fmt::Debug for GrpcResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GrpcResponse")
+            .field("metadata", &self.metadata)
+            .field("trailers", &self.trailers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Bridge serving a gRPC service to browser clients over [gRPC-Web].
+///
+/// Registers a resource matching all methods of a gRPC service, i.e. all paths starting with
+/// `/{service name}/`. Requests must use the `POST` method and the `application/grpc-web` or
+/// `application/grpc-web+proto` content type; base64-encoded (`-text`) requests are not
+/// supported.
+///
+/// The request messages are unframed and passed, with the request headers as metadata, to a
+/// [`Service`] handling [`GrpcRequest`]s, such as a service wrapping a generated gRPC server.
+/// The messages of its [`GrpcResponse`] are framed back into the response body, followed by a
+/// trailers frame holding the `grpc-status` and `grpc-message` of the call and any custom
+/// trailers, since browsers do not expose HTTP trailers.
+///
+/// Failures, including malformed or oversized requests, are reported to the client as a gRPC
+/// [`Status`] in a `200 OK` response, as gRPC clients expect. Compressed messages are rejected
+/// with [`Code::Unimplemented`].
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     dev::fn_service,
+///     grpc_web::{Code, GrpcRequest, GrpcResponse, GrpcWeb, Status},
+///     App,
+/// };
+///
+/// let greeter = fn_service(|req: GrpcRequest| async move {
+///     match req.method() {
+///         // echo the encoded request message
+///         "SayHello" => Ok(GrpcResponse::new(req.into_message()?)),
+///         _ => Err(Status::new(Code::Unimplemented, "unknown method")),
+///     }
+/// });
+///
+/// let app = App::new().service(GrpcWeb::new("helloworld.Greeter", greeter));
+/// ```
+///
+/// [gRPC-Web]: https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-WEB.md
+pub struct GrpcWeb<T> {
+    service_name: String,
+    factory: Rc<T>,
+    limit: usize,
+}
+
+impl<T> GrpcWeb<T>
+where
+    T: ServiceFactory<GrpcRequest, Config = (), Response = GrpcResponse, Error = Status>,
+{
+    /// Constructs a bridge for the gRPC service with the given fully-qualified name (e.g.
+    /// `helloworld.Greeter`), handling calls with services created by `factory`.
+    pub fn new<F>(service_name: impl Into<String>, factory: F) -> Self
+    where
+        F: IntoServiceFactory<T, GrpcRequest>,
+    {
+        GrpcWeb {
+            service_name: service_name.into(),
+            factory: Rc::new(factory.into_factory()),
+            limit: DEFAULT_LIMIT,
+        }
+    }
+
+    /// Set maximum accepted request body size. By default this limit is 4MiB.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+impl<T> fmt::Debug for GrpcWeb<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GrpcWeb")
+            .field("service_name", &self.service_name)
+            .field("limit", &self.limit)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> HttpServiceFactory for GrpcWeb<T>
+where
+    T: ServiceFactory<GrpcRequest, Config = (), Response = GrpcResponse, Error = Status>
+        + 'static,
+    T::InitError: fmt::Debug,
+{
+    fn register(self, config: &mut AppService) {
+        let path = format!("/{}", self.service_name.trim_matches('/'));
+
+        let rdef = if config.is_root() {
+            ResourceDef::root_prefix(&path)
+        } else {
+            ResourceDef::prefix(&path)
+        };
+
+        config.register_service(rdef, None, self, None)
+    }
+}
+
+impl<T> ServiceFactory<ServiceRequest> for GrpcWeb<T>
+where
+    T: ServiceFactory<GrpcRequest, Config = (), Response = GrpcResponse, Error = Status>
+        + 'static,
+    T::InitError: fmt::Debug,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Config = ();
+    type Service = GrpcWebService<T::Service>;
+    type InitError = ();
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let factory = Rc::clone(&self.factory);
+        let limit = self.limit;
+
+        Box::pin(async move {
+            match factory.new_service(()).await {
+                Ok(service) => Ok(GrpcWebService {
+                    service: Rc::new(service),
+                    limit,
+                }),
+                Err(err) => {
+                    log::error!("Can not construct gRPC service: {:?}", err);
+                    Err(())
+                }
+            }
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct GrpcWebService<S> {
+    service: Rc<S>,
+    limit: usize,
+}
+
+impl<S> Service<ServiceRequest> for GrpcWebService<S>
+where
+    S: Service<GrpcRequest, Response = GrpcResponse, Error = Status> + 'static,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service
+            .poll_ready(cx)
+            .map_err(crate::error::ErrorServiceUnavailable)
+    }
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        if req.method() != Method::POST {
+            let res = HttpResponse::MethodNotAllowed()
+                .insert_header((header::ALLOW, "POST"))
+                .finish();
+            return Box::pin(async move { Ok(req.into_response(res)) });
+        }
+
+        let is_grpc_web = matches!(
+            req.mime_type(),
+            Ok(Some(mime)) if mime.essence_str() == GRPC_WEB || mime.essence_str() == GRPC_WEB_PROTO
+        );
+
+        if !is_grpc_web {
+            let res = HttpResponse::UnsupportedMediaType().finish();
+            return Box::pin(async move { Ok(req.into_response(res)) });
+        }
+
+        let service = Rc::clone(&self.service);
+        let limit = self.limit;
+        let mut payload = req.take_payload();
+
+        Box::pin(async move {
+            let mut body = BytesMut::new();
+
+            while let Some(chunk) = payload.next().await {
+                let chunk = chunk?;
+
+                if body.len() + chunk.len() > limit {
+                    let status = Status::new(
+                        Code::ResourceExhausted,
+                        format!("request body is larger than {} bytes", limit),
+                    );
+                    return Ok(req.into_response(respond(status.into())));
+                }
+
+                body.extend_from_slice(&chunk);
+            }
+
+            let messages = match decode_frames(body.freeze()) {
+                Ok(messages) => messages,
+                Err(status) => return Ok(req.into_response(respond(status.into()))),
+            };
+
+            let grpc_req = GrpcRequest {
+                path: req.path().to_owned(),
+                metadata: req.headers().clone(),
+                messages,
+            };
+
+            let res = match service.call(grpc_req).await {
+                Ok(res) => res,
+                Err(status) => status.into(),
+            };
+
+            Ok(req.into_response(respond(res)))
+        })
+    }
+}
+
+/// Builds the gRPC-Web response for a gRPC response.
+fn respond(res: GrpcResponse) -> HttpResponse {
+    let GrpcResponse {
+        metadata,
+        messages,
+        trailers,
+    } = res;
+
+    let mut res = HttpResponse::with_body(
+        StatusCode::OK,
+        GrpcWebBody {
+            messages,
+            trailers: Some(trailers),
+        },
+    );
+
+    let headers = res.headers_mut();
+    for (name, value) in metadata {
+        headers.append(name, value);
+    }
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(GRPC_WEB_PROTO),
+    );
+
+    res.map_into_boxed_body()
+}
+
+/// Splits a gRPC-Web request body into its messages.
+fn decode_frames(mut body: Bytes) -> Result<Vec<Bytes>, Status> {
+    let mut messages = Vec::new();
+
+    while !body.is_empty() {
+        if body.len() < FRAME_HEADER_LEN {
+            return Err(Status::new(Code::Internal, "truncated frame header"));
+        }
+
+        let flags = body.get_u8();
+        let len = body.get_u32() as usize;
+
+        if len > body.len() {
+            return Err(Status::new(Code::Internal, "truncated frame"));
+        }
+
+        let message = body.split_to(len);
+
+        if flags & TRAILERS_FLAG != 0 {
+            return Err(Status::new(
+                Code::Internal,
+                "unexpected trailers in request",
+            ));
+        }
+
+        if flags & COMPRESSED_FLAG != 0 {
+            return Err(Status::new(
+                Code::Unimplemented,
+                "compressed messages are not supported",
+            ));
+        }
+
+        messages.push(message);
+    }
+
+    Ok(messages)
+}
+
+/// Encodes a frame with the given flags and payload.
+fn encode_frame(flags: u8, payload: &[u8]) -> Bytes {
+    let mut buf = BytesMut::with_capacity(FRAME_HEADER_LEN + payload.len());
+    buf.put_u8(flags);
+    buf.put_u32(payload.len() as u32);
+    buf.put_slice(payload);
+    buf.freeze()
+}
+
+/// Encodes the trailers frame ending a response.
+fn encode_trailers(status: &Status, trailers: &HeaderMap) -> Bytes {
+    let mut buf = Vec::with_capacity(64);
+
+    let mut push = |name: &[u8], value: &[u8]| {
+        buf.extend_from_slice(name);
+        buf.push(b':');
+        buf.extend_from_slice(value);
+        buf.extend_from_slice(b"\r\n");
+    };
+
+    let mut code = itoa::Buffer::new();
+    push(b"grpc-status", code.format(status.code as u8).as_bytes());
+
+    if !status.message.is_empty() {
+        push(b"grpc-message", &percent_encode(&status.message));
+    }
+
+    for (name, value) in trailers {
+        if !is_reserved_trailer(name) {
+            push(name.as_str().as_bytes(), value.as_bytes());
+        }
+    }
+
+    encode_frame(TRAILERS_FLAG, &buf)
+}
+
+fn is_reserved_trailer(name: &HeaderName) -> bool {
+    name == "grpc-status" || name == "grpc-message"
+}
+
+/// Percent-encodes a status message as required by the gRPC protocol.
+fn percent_encode(message: &str) -> Vec<u8> {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut buf = Vec::with_capacity(message.len());
+
+    for &byte in message.as_bytes() {
+        if (0x20..0x7f).contains(&byte) && byte != b'%' {
+            buf.push(byte);
+        } else {
+            buf.extend_from_slice(&[
+                b'%',
+                HEX[(byte >> 4) as usize],
+                HEX[(byte & 0xf) as usize],
+            ]);
+        }
+    }
+
+    buf
+}
+
+/// Body framing the messages of a [`GrpcResponse`], followed by its trailers.
+#[doc(hidden)]
+pub struct GrpcWebBody {
+    messages: LocalBoxStream<'static, Result<Bytes, Status>>,
+    trailers: Option<HeaderMap>,
+}
+
+impl MessageBody for GrpcWebBody {
+    type Error = Infallible;
+
+    #[inline]
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.get_mut();
+
+        let trailers = match this.trailers.as_ref() {
+            Some(trailers) => trailers,
+            None => return Poll::Ready(None),
+        };
+
+        let status = match futures_core::ready!(this.messages.poll_next_unpin(cx)) {
+            Some(Ok(message)) => return Poll::Ready(Some(Ok(encode_frame(0, &message)))),
+            Some(Err(status)) => status,
+            None => Status::ok(),
+        };
+
+        let frame = encode_trailers(&status, trailers);
+        this.trailers = None;
+
+        Poll::Ready(Some(Ok(frame)))
+    }
+}
+
+impl fmt::Debug for GrpcWebBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GrpcWebBody")
+            .field("trailers", &self.trailers)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_service::fn_service;
+
+    use super::*;
+    use crate::{
+        body,
+        test::{call_service, init_service, TestRequest},
+        App,
+    };
+
+    fn greeter() -> GrpcWeb<
+        impl ServiceFactory<
+            GrpcRequest,
+            Config = (),
+            Response = GrpcResponse,
+            Error = Status,
+            InitError = (),
+        >,
+    > {
+        GrpcWeb::new(
+            "helloworld.Greeter",
+            fn_service(|req: GrpcRequest| async move {
+                match req.method() {
+                    "SayHello" => {
+                        let mut res = GrpcResponse::new(req.into_message()?);
+                        res.metadata_mut().insert(
+                            HeaderName::from_static("x-greeting"),
+                            HeaderValue::from_static("hi"),
+                        );
+                        res.trailers_mut().insert(
+                            HeaderName::from_static("x-served-by"),
+                            HeaderValue::from_static("test"),
+                        );
+                        Ok(res)
+                    }
+                    "SayHellos" => {
+                        let messages = req.into_messages();
+                        Ok(GrpcResponse::streaming(stream::iter(
+                            messages.into_iter().map(Ok),
+                        )))
+                    }
+                    _ => Err(Status::new(Code::Unimplemented, "unknown method: ½")),
+                }
+            }),
+        )
+    }
+
+    fn grpc_request(method: &str, body: Vec<u8>) -> TestRequest {
+        TestRequest::post()
+            .uri(&format!("/helloworld.Greeter/{}", method))
+            .insert_header((header::CONTENT_TYPE, "application/grpc-web+proto"))
+            .set_payload(body)
+    }
+
+    #[test]
+    fn frames() {
+        let mut body = encode_frame(0, b"abc").to_vec();
+        body.extend_from_slice(&encode_frame(0, b""));
+        assert_eq!(&body[..FRAME_HEADER_LEN], &[0, 0, 0, 0, 3]);
+
+        let messages = decode_frames(Bytes::from(body)).unwrap();
+        assert_eq!(messages, vec![Bytes::from_static(b"abc"), Bytes::new()]);
+
+        let status = decode_frames(Bytes::from_static(&[0, 0, 0, 0, 3, b'a'])).unwrap_err();
+        assert_eq!(status.code(), Code::Internal);
+
+        let status = decode_frames(encode_frame(COMPRESSED_FLAG, b"abc")).unwrap_err();
+        assert_eq!(status.code(), Code::Unimplemented);
+
+        let trailers =
+            encode_trailers(&Status::new(Code::NotFound, "100% gone"), &HeaderMap::new());
+        assert_eq!(
+            &trailers[FRAME_HEADER_LEN..],
+            b"grpc-status:5\r\ngrpc-message:100%25 gone\r\n"
+        );
+        assert_eq!(trailers[0], TRAILERS_FLAG);
+    }
+
+    #[actix_rt::test]
+    async fn unary_call() {
+        let srv = init_service(App::new().service(greeter())).await;
+
+        let req = grpc_request("SayHello", encode_frame(0, b"world").to_vec()).to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/grpc-web+proto"
+        );
+        assert_eq!(res.headers().get("x-greeting").unwrap(), "hi");
+
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        let mut expected = encode_frame(0, b"world").to_vec();
+        expected.extend_from_slice(&encode_frame(
+            TRAILERS_FLAG,
+            b"grpc-status:0\r\nx-served-by:test\r\n",
+        ));
+        assert_eq!(body, expected);
+    }
+
+    #[actix_rt::test]
+    async fn streaming_call_and_errors() {
+        let srv = init_service(App::new().service(greeter().limit(16))).await;
+
+        let mut payload = encode_frame(0, b"a").to_vec();
+        payload.extend_from_slice(&encode_frame(0, b"b"));
+        let req = grpc_request("SayHellos", payload).to_request();
+        let body = body::to_bytes(call_service(&srv, req).await.into_body())
+            .await
+            .unwrap();
+        let mut expected = encode_frame(0, b"a").to_vec();
+        expected.extend_from_slice(&encode_frame(0, b"b"));
+        expected.extend_from_slice(&encode_frame(TRAILERS_FLAG, b"grpc-status:0\r\n"));
+        assert_eq!(body, expected);
+
+        let req = grpc_request("Unknown", Vec::new()).to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(
+            body,
+            encode_frame(
+                TRAILERS_FLAG,
+                b"grpc-status:12\r\ngrpc-message:unknown method: %C2%BD\r\n"
+            )
+        );
+
+        let req = grpc_request("SayHello", encode_frame(0, &[0; 32]).to_vec()).to_request();
+        let body = body::to_bytes(call_service(&srv, req).await.into_body())
+            .await
+            .unwrap();
+        assert!(body.starts_with(&[TRAILERS_FLAG]));
+        assert!(body.ends_with(
+            b"grpc-status:8\r\ngrpc-message:request body is larger than 16 bytes\r\n"
+        ));
+
+        let req = TestRequest::get()
+            .uri("/helloworld.Greeter/SayHello")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let req = grpc_request("SayHello", Vec::new())
+            .insert_header((header::CONTENT_TYPE, "application/grpc-web-text"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let req = grpc_request("SayHello", Vec::new())
+            .uri("/other.Service/SayHello")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
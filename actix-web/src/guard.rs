@@ -52,9 +52,13 @@ use std::{
     rc::Rc,
 };
 
-use actix_http::{header, uri::Uri, Extensions, Method as HttpMethod, RequestHead};
+use actix_http::{uri::Uri, Extensions, Method as HttpMethod, RequestHead};
 
-use crate::{http::header::Header, service::ServiceRequest, HttpMessage as _};
+use crate::{
+    http::header::{self, Header},
+    service::ServiceRequest,
+    HttpMessage as _,
+};
 
 /// Provides access to request parts that are useful during routing.
 #[derive(Debug)]
@@ -280,7 +284,7 @@ pub fn Method(method: HttpMethod) -> impl Guard {
 }
 
 /// HTTP method guard.
-struct MethodGuard(HttpMethod);
+pub(crate) struct MethodGuard(pub(crate) HttpMethod);
 
 impl Guard for MethodGuard {
     fn check(&self, ctx: &GuardContext<'_>) -> bool {
@@ -350,6 +354,69 @@ impl Guard for HeaderGuard {
     }
 }
 
+/// Creates a guard that matches if request contains a header with the given name, whatever its value.
+///
+/// # Examples
+/// The handler below will be called when the request contains an `x-request-id` header.
+/// ```
+/// use actix_web::{guard, web, HttpResponse};
+///
+/// web::route()
+///     .guard(guard::HeaderPresent("x-request-id"))
+///     .to(|| HttpResponse::Ok());
+/// ```
+#[allow(non_snake_case)]
+pub fn HeaderPresent(name: &'static str) -> impl Guard {
+    HeaderPresentGuard(header::HeaderName::try_from(name).unwrap())
+}
+
+struct HeaderPresentGuard(header::HeaderName);
+
+impl Guard for HeaderPresentGuard {
+    fn check(&self, ctx: &GuardContext<'_>) -> bool {
+        ctx.head().headers.contains_key(&self.0)
+    }
+}
+
+/// Creates a guard that matches if the request's `Content-Type` has the given media type.
+///
+/// Parameters such as `charset` are ignored when comparing, so `ContentType(mime::APPLICATION_JSON)`
+/// matches both `application/json` and `application/json; charset=utf-8`. Requests without a valid
+/// `Content-Type` header do not match.
+///
+/// # Examples
+/// The same path can be served by different handlers depending on the payload format.
+/// ```
+/// use actix_web::{guard, web};
+///
+/// web::resource("/upload")
+///     .route(
+///         web::post()
+///             .guard(guard::ContentType(mime::APPLICATION_JSON))
+///             .to(|| async { "json" }),
+///     )
+///     .route(
+///         web::post()
+///             .guard(guard::ContentType(mime::MULTIPART_FORM_DATA))
+///             .to(|| async { "multipart" }),
+///     );
+/// ```
+#[allow(non_snake_case)]
+pub fn ContentType(mime: mime::Mime) -> impl Guard {
+    ContentTypeGuard(mime)
+}
+
+struct ContentTypeGuard(mime::Mime);
+
+impl Guard for ContentTypeGuard {
+    fn check(&self, ctx: &GuardContext<'_>) -> bool {
+        match ctx.header::<header::ContentType>() {
+            Some(header::ContentType(mime)) => mime.essence_str() == self.0.essence_str(),
+            None => false,
+        }
+    }
+}
+
 /// Creates a guard that matches requests targetting a specific host.
 ///
 /// # Matching Host
@@ -494,6 +561,39 @@ mod tests {
         assert!(!hdr.check(&req.guard_ctx()));
     }
 
+    #[test]
+    fn header_present() {
+        let req = TestRequest::default()
+            .insert_header((header::TRANSFER_ENCODING, "chunked"))
+            .to_srv_request();
+
+        assert!(HeaderPresent("transfer-encoding").check(&req.guard_ctx()));
+        assert!(!HeaderPresent("content-type").check(&req.guard_ctx()));
+    }
+
+    #[test]
+    fn content_type_match() {
+        let req = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/json; charset=utf-8"))
+            .to_srv_request();
+
+        assert!(ContentType(mime::APPLICATION_JSON).check(&req.guard_ctx()));
+        assert!(!ContentType(mime::TEXT_PLAIN).check(&req.guard_ctx()));
+
+        let req = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "Application/JSON"))
+            .to_srv_request();
+        assert!(ContentType(mime::APPLICATION_JSON).check(&req.guard_ctx()));
+
+        let req = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "not a mime"))
+            .to_srv_request();
+        assert!(!ContentType(mime::APPLICATION_JSON).check(&req.guard_ctx()));
+
+        let req = TestRequest::default().to_srv_request();
+        assert!(!ContentType(mime::APPLICATION_JSON).check(&req.guard_ctx()));
+    }
+
     #[test]
     fn host_from_header() {
         let req = TestRequest::default()
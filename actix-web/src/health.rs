@@ -0,0 +1,300 @@
+//! Liveness and readiness endpoints.
+//!
+//! See [`Health`] for details.
+
+use std::{fmt, future::Future, rc::Rc, time::Duration};
+
+use actix_rt::time::{timeout, Instant};
+use futures_core::future::LocalBoxFuture;
+use futures_util::future::join_all;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    dev::{AppService, HttpServiceFactory},
+    http::StatusCode,
+    web, HttpResponse, Resource,
+};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+type CheckFn = Rc<dyn Fn() -> LocalBoxFuture<'static, Result<(), String>>>;
+
+struct Check {
+    name: String,
+    timeout: Option<Duration>,
+    check: CheckFn,
+}
+
+/// Liveness and readiness endpoints for orchestrators and load balancers.
+///
+/// Registers two resources:
+/// - `GET /healthz` reports the process as alive. It always responds with `200 OK`, since a
+///   process that can answer the request does not need to be restarted.
+/// - `GET /readyz` runs all registered readiness checks concurrently and responds with `200 OK`
+///   if they all succeed, or `503 Service Unavailable` otherwise, so that traffic is only routed
+///   to instances whose dependencies are reachable.
+///
+/// Both respond with a JSON body. The readiness body lists the outcome and duration of each check;
+/// a check that does not complete within its timeout fails.
+///
+/// ```json
+/// {
+///   "status": "error",
+///   "checks": {
+///     "database": { "status": "ok", "duration_ms": 3 },
+///     "cache": { "status": "error", "error": "timed out after 5000ms", "duration_ms": 5000 }
+///   }
+/// }
+/// ```
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use actix_web::{health::Health, App};
+///
+/// # async fn ping_database() -> Result<(), std::io::Error> { Ok(()) }
+/// # async fn ping_cache() -> Result<(), std::io::Error> { Ok(()) }
+/// let app = App::new().service(
+///     Health::new()
+///         .check("database", ping_database)
+///         .check_with_timeout("cache", Duration::from_millis(200), ping_cache),
+/// );
+/// ```
+pub struct Health {
+    liveness_path: String,
+    readiness_path: String,
+    timeout: Duration,
+    checks: Vec<Check>,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Health {
+            liveness_path: "/healthz".to_owned(),
+            readiness_path: "/readyz".to_owned(),
+            timeout: DEFAULT_TIMEOUT,
+            checks: Vec::new(),
+        }
+    }
+}
+
+impl Health {
+    /// Constructs health endpoints without readiness checks.
+    pub fn new() -> Self {
+        Health::default()
+    }
+
+    /// Sets the path of the liveness endpoint. Defaults to `/healthz`.
+    pub fn liveness_path(mut self, path: impl Into<String>) -> Self {
+        self.liveness_path = path.into();
+        self
+    }
+
+    /// Sets the path of the readiness endpoint. Defaults to `/readyz`.
+    pub fn readiness_path(mut self, path: impl Into<String>) -> Self {
+        self.readiness_path = path.into();
+        self
+    }
+
+    /// Sets the timeout of checks registered with [`check`](Self::check). Defaults to 5 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Registers a readiness check, such as pinging a database.
+    ///
+    /// The check is run on every readiness request and fails if it returns an error or does not
+    /// complete within the [default timeout](Self::timeout).
+    pub fn check<F, Fut, E>(self, name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = Result<(), E>> + 'static,
+        E: fmt::Display,
+    {
+        self.add_check(name.into(), None, check)
+    }
+
+    /// Registers a readiness check with its own timeout.
+    pub fn check_with_timeout<F, Fut, E>(
+        self,
+        name: impl Into<String>,
+        timeout: Duration,
+        check: F,
+    ) -> Self
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = Result<(), E>> + 'static,
+        E: fmt::Display,
+    {
+        self.add_check(name.into(), Some(timeout), check)
+    }
+
+    fn add_check<F, Fut, E>(mut self, name: String, timeout: Option<Duration>, check: F) -> Self
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = Result<(), E>> + 'static,
+        E: fmt::Display,
+    {
+        let check: CheckFn = Rc::new(move || {
+            let fut = check();
+            Box::pin(async move { fut.await.map_err(|err| err.to_string()) })
+        });
+
+        self.checks.push(Check {
+            name,
+            timeout,
+            check,
+        });
+        self
+    }
+}
+
+impl HttpServiceFactory for Health {
+    fn register(self, config: &mut AppService) {
+        let liveness = Resource::new(self.liveness_path.as_str())
+            .route(web::get().to(liveness))
+            .route(web::head().to(liveness));
+
+        let default_timeout = self.timeout;
+        let checks = Rc::<[Check]>::from(self.checks);
+        let readiness_handler = move || readiness(Rc::clone(&checks), default_timeout);
+
+        let readiness = Resource::new(self.readiness_path.as_str())
+            .route(web::get().to(readiness_handler.clone()))
+            .route(web::head().to(readiness_handler));
+
+        liveness.register(config);
+        readiness.register(config);
+    }
+}
+
+async fn liveness() -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "status": "ok" }))
+}
+
+async fn readiness(checks: Rc<[Check]>, default_timeout: Duration) -> HttpResponse {
+    let results = join_all(
+        checks
+            .iter()
+            .map(|check| run_check(check, check.timeout.unwrap_or(default_timeout))),
+    )
+    .await;
+
+    let ready = results.iter().all(|(_, result)| result["status"] == "ok");
+
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    let checks = results.into_iter().collect::<Map<String, Value>>();
+
+    HttpResponse::build(status).json(json!({
+        "status": if ready { "ok" } else { "error" },
+        "checks": checks,
+    }))
+}
+
+async fn run_check(check: &Check, limit: Duration) -> (String, Value) {
+    let start = Instant::now();
+    let result = timeout(limit, (check.check)()).await;
+    let duration_ms = start.elapsed().as_millis() as u64;
+
+    let result = match result {
+        Ok(Ok(())) => json!({ "status": "ok", "duration_ms": duration_ms }),
+        Ok(Err(err)) => json!({ "status": "error", "error": err, "duration_ms": duration_ms }),
+        Err(_) => json!({
+            "status": "error",
+            "error": format!("timed out after {}ms", limit.as_millis()),
+            "duration_ms": duration_ms,
+        }),
+    };
+
+    (check.name.clone(), result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, io};
+
+    use super::*;
+    use crate::{
+        test::{self, TestRequest},
+        App,
+    };
+
+    #[actix_rt::test]
+    async fn liveness_and_readiness() {
+        let healthy = Rc::new(Cell::new(true));
+
+        let app = test::init_service(
+            App::new().service(
+                Health::new()
+                    .check("always", || async { Ok::<_, io::Error>(()) })
+                    .check("toggle", {
+                        let healthy = Rc::clone(&healthy);
+                        move || {
+                            let healthy = healthy.get();
+                            async move {
+                                if healthy {
+                                    Ok(())
+                                } else {
+                                    Err("connection refused")
+                                }
+                            }
+                        }
+                    }),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/healthz").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::get().uri("/readyz").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["checks"]["toggle"]["status"], "ok");
+
+        healthy.set(false);
+
+        let req = TestRequest::get().uri("/readyz").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body["status"], "error");
+        assert_eq!(body["checks"]["always"]["status"], "ok");
+        assert_eq!(body["checks"]["toggle"]["error"], "connection refused");
+
+        // liveness does not depend on checks
+        let req = TestRequest::get().uri("/healthz").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn check_timeout() {
+        let app = test::init_service(App::new().service(
+            Health::new().readiness_path("/ready").check_with_timeout(
+                "slow",
+                Duration::from_millis(10),
+                || async {
+                    actix_rt::time::sleep(Duration::from_secs(10)).await;
+                    Ok::<_, io::Error>(())
+                },
+            ),
+        ))
+        .await;
+
+        let req = TestRequest::get().uri("/ready").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body["checks"]["slow"]["error"], "timed out after 10ms");
+    }
+}
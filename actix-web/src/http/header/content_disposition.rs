@@ -14,7 +14,7 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 use std::fmt::{self, Write};
 
-use super::{ExtendedValue, Header, TryIntoHeaderValue, Writer};
+use super::{Charset, ExtendedValue, Header, TryIntoHeaderValue, Writer};
 use crate::http::header;
 
 /// Split at the index of the first `needle` if it exists or at the end.
@@ -398,6 +398,44 @@ impl ContentDisposition {
         Ok(cd)
     }
 
+    /// Constructs a `Content-Disposition: attachment` header for downloading a file as `filename`.
+    ///
+    /// Non-ASCII file names are encoded as a UTF-8 `filename*` parameter
+    /// ([RFC 5987](https://datatracker.ietf.org/doc/html/rfc5987)) alongside an ASCII-only
+    /// `filename` fallback for user agents that do not understand the extended form.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::http::header::ContentDisposition;
+    ///
+    /// let cd = ContentDisposition::attachment("report.pdf");
+    /// assert_eq!(cd.to_string(), r#"attachment; filename="report.pdf""#);
+    ///
+    /// let cd = ContentDisposition::attachment("résumé.pdf");
+    /// assert_eq!(
+    ///     cd.to_string(),
+    ///     r#"attachment; filename="r_sum_.pdf"; filename*=UTF-8''r%C3%A9sum%C3%A9.pdf"#,
+    /// );
+    /// ```
+    pub fn attachment(filename: impl AsRef<str>) -> Self {
+        let filename = filename.as_ref();
+
+        let mut parameters = vec![DispositionParam::Filename(ascii_fallback(filename))];
+
+        if !filename.is_ascii() {
+            parameters.push(DispositionParam::FilenameExt(ExtendedValue {
+                charset: Charset::Ext(String::from("UTF-8")),
+                language_tag: None,
+                value: filename.as_bytes().to_vec(),
+            }));
+        }
+
+        ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters,
+        }
+    }
+
     /// Returns `true` if type is [`Inline`](DispositionType::Inline).
     pub fn is_inline(&self) -> bool {
         matches!(self.disposition, DispositionType::Inline)
@@ -453,6 +491,20 @@ impl ContentDisposition {
     }
 }
 
+/// Replaces characters that cannot appear in a plain `filename` parameter with underscores.
+fn ascii_fallback(filename: &str) -> String {
+    filename
+        .chars()
+        .map(|c| {
+            if c.is_ascii() && !c.is_ascii_control() {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
 impl TryIntoHeaderValue for ContentDisposition {
     type Error = header::InvalidHeaderValue;
 
@@ -985,4 +1037,27 @@ mod tests {
         assert_eq!(cd.get_unknown_ext("dummy"), None);
         assert_eq!(cd.get_unknown("duMMy"), Some("3"));
     }
+
+    #[test]
+    fn test_attachment() {
+        let cd = ContentDisposition::attachment("sample.png");
+        assert!(cd.is_attachment());
+        assert_eq!(cd.get_filename(), Some("sample.png"));
+        assert_eq!(cd.get_filename_ext(), None);
+
+        let cd = ContentDisposition::attachment("😀 \"a\".svg");
+        assert_eq!(cd.get_filename(), Some("_ \"a\".svg"));
+        assert_eq!(
+            cd.get_filename_ext().map(|ev| ev.value.as_ref()),
+            Some("😀 \"a\".svg".as_bytes())
+        );
+        assert_eq!(
+            cd.to_string(),
+            "attachment; filename=\"_ \\\"a\\\".svg\"; filename*=UTF-8''%F0%9F%98%80%20%22a%22.svg"
+        );
+
+        let cd = ContentDisposition::attachment("line\nbreak.txt");
+        assert_eq!(cd.get_filename(), Some("line_break.txt"));
+        assert!(cd.get_filename_ext().is_none());
+    }
 }
@@ -66,6 +66,22 @@ pub enum IfRange {
     Date(HttpDate),
 }
 
+impl IfRange {
+    /// Returns true if the requested range should be served for a representation with the given
+    /// validators; otherwise, the entire representation should be sent.
+    ///
+    /// As required by [RFC 7233 §3.2], entity-tags are compared with the strong comparison
+    /// function, so weak tags never match, and dates must equal the last modification time.
+    ///
+    /// [RFC 7233 §3.2]: https://datatracker.ietf.org/doc/html/rfc7233#section-3.2
+    pub fn matches(&self, etag: Option<&EntityTag>, last_modified: Option<HttpDate>) -> bool {
+        match self {
+            IfRange::EntityTag(tag) => etag.map_or(false, |etag| tag.strong_eq(etag)),
+            IfRange::Date(date) => last_modified == Some(*date),
+        }
+    }
+}
+
 impl Header for IfRange {
     fn name() -> HeaderName {
         header::IF_RANGE
@@ -116,4 +132,24 @@ mod test_parse_and_format {
     crate::http::header::common_header_test!(test1, vec![b"Sat, 29 Oct 1994 19:43:31 GMT"]);
     crate::http::header::common_header_test!(test2, vec![b"\"abc\""]);
     crate::http::header::common_header_test!(test3, vec![b"this-is-invalid"], None::<IfRange>);
+
+    #[test]
+    fn test_matches() {
+        let strong = EntityTag::new_strong("abc".to_owned());
+        let weak = EntityTag::new_weak("abc".to_owned());
+
+        let if_range = IfRange::EntityTag(strong.clone());
+        assert!(if_range.matches(Some(&strong), None));
+        assert!(!if_range.matches(Some(&weak), None));
+        assert!(!if_range.matches(None, None));
+
+        let date: HttpDate = "Sat, 29 Oct 1994 19:43:31 GMT".parse().unwrap();
+        let if_range = IfRange::Date(date);
+        assert!(if_range.matches(None, Some(date)));
+        assert!(!if_range.matches(
+            Some(&strong),
+            Some(HttpDate::from(std::time::SystemTime::now()))
+        ));
+        assert!(!IfRange::EntityTag(weak.clone()).matches(Some(&weak), None));
+    }
 }
@@ -22,6 +22,7 @@ mod accept_charset;
 mod accept_encoding;
 mod accept_language;
 mod allow;
+mod authorization;
 mod cache_control;
 mod content_disposition;
 mod content_language;
@@ -51,6 +52,7 @@ pub use self::accept_charset::AcceptCharset;
 pub use self::accept_encoding::AcceptEncoding;
 pub use self::accept_language::AcceptLanguage;
 pub use self::allow::Allow;
+pub use self::authorization::{Authorization, Basic, Bearer, Scheme};
 pub use self::cache_control::{CacheControl, CacheDirective};
 pub use self::content_disposition::{ContentDisposition, DispositionParam, DispositionType};
 pub use self::content_language::ContentLanguage;
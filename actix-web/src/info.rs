@@ -1,11 +1,14 @@
-use std::{convert::Infallible, net::SocketAddr};
+use std::{
+    convert::Infallible,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+};
 
 use actix_utils::future::{err, ok, Ready};
 use derive_more::{Display, Error};
 use once_cell::sync::Lazy;
 
 use crate::{
-    dev::{AppConfig, Payload, RequestHead},
+    dev::{AppConfig, Payload, ProxyHeader, RequestHead},
     http::{
         header::{self, HeaderName},
         uri::{Authority, Scheme},
@@ -60,6 +63,14 @@ fn first_header_value<'a>(req: &'a RequestHead, name: &'_ HeaderName) -> Option<
 /// If the older, related headers are also present (eg. `X-Forwarded-For`), then `Forwarded`
 /// is preferred.
 ///
+/// By default, these headers are honored regardless of who sent them. Configure the addresses of
+/// your reverse proxies with [`HttpServer::trusted_proxies`](crate::HttpServer::trusted_proxies) so
+/// that the headers are ignored on requests that did not come through one of them.
+///
+/// On listeners that accept the [PROXY protocol](crate::HttpServer::listen_proxy_protocol), the
+/// peer address is the client address sent by the proxy and the scheme is `https` if the proxy
+/// reported a TLS connection.
+///
 /// [rfc7239]: https://datatracker.ietf.org/doc/html/rfc7239
 /// [rfc7239-62]: https://datatracker.ietf.org/doc/html/rfc7239#section-6.2
 /// [rfc7239-63]: https://datatracker.ietf.org/doc/html/rfc7239#section-6.3
@@ -72,15 +83,31 @@ pub struct ConnectionInfo {
 }
 
 impl ConnectionInfo {
-    pub(crate) fn new(req: &RequestHead, cfg: &AppConfig) -> ConnectionInfo {
+    pub(crate) fn new(
+        req: &RequestHead,
+        cfg: &AppConfig,
+        proxy: Option<&ProxyHeader>,
+    ) -> ConnectionInfo {
         let mut host = None;
         let mut scheme = None;
-        let mut realip_remote_addr = None;
+        let mut forwarded_for = Vec::new();
+
+        let trusted = cfg.trusted_proxies();
+
+        // forwarding headers are only honored when sent by a trusted proxy, or by anyone if no
+        // trusted proxies are configured
+        let from_trusted_proxy = match trusted {
+            Some(trusted) => req
+                .peer_addr
+                .map_or(false, |addr| trusted.contains(addr.ip())),
+            None => true,
+        };
 
         for (name, val) in req
             .headers
             .get_all(&header::FORWARDED)
             .into_iter()
+            .filter(|_| from_trusted_proxy)
             .filter_map(|hdr| hdr.to_str().ok())
             // "for=1.2.3.4, for=5.6.7.8; scheme=https"
             .flat_map(|val| val.split(';'))
@@ -105,9 +132,13 @@ impl ConnectionInfo {
             // --- https://datatracker.ietf.org/doc/html/rfc7239#section-5.2
 
             match name.trim().to_lowercase().as_str() {
-                "for" => realip_remote_addr.get_or_insert_with(|| unquote(val)),
-                "proto" => scheme.get_or_insert_with(|| unquote(val)),
-                "host" => host.get_or_insert_with(|| unquote(val)),
+                "for" => forwarded_for.push(unquote(val)),
+                "proto" => {
+                    scheme.get_or_insert_with(|| unquote(val));
+                }
+                "host" => {
+                    host.get_or_insert_with(|| unquote(val));
+                }
                 "by" => {
                     // TODO: implement https://datatracker.ietf.org/doc/html/rfc7239#section-5.1
                     continue;
@@ -117,22 +148,45 @@ impl ConnectionInfo {
         }
 
         let scheme = scheme
-            .or_else(|| first_header_value(req, &*X_FORWARDED_PROTO))
+            .or_else(|| {
+                first_header_value(req, &X_FORWARDED_PROTO).filter(|_| from_trusted_proxy)
+            })
             .or_else(|| req.uri.scheme().map(Scheme::as_str))
+            .or_else(|| Some("https").filter(|_| proxy.and_then(ProxyHeader::tls).is_some()))
             .or_else(|| Some("https").filter(|_| cfg.secure()))
             .unwrap_or("http")
             .to_owned();
 
         let host = host
-            .or_else(|| first_header_value(req, &*X_FORWARDED_HOST))
+            .or_else(|| {
+                first_header_value(req, &X_FORWARDED_HOST).filter(|_| from_trusted_proxy)
+            })
             .or_else(|| req.headers.get(&header::HOST)?.to_str().ok())
             .or_else(|| req.uri.authority().map(Authority::as_str))
             .unwrap_or_else(|| cfg.host())
             .to_owned();
 
-        let realip_remote_addr = realip_remote_addr
-            .or_else(|| first_header_value(req, &*X_FORWARDED_FOR))
-            .map(str::to_owned);
+        if forwarded_for.is_empty() && from_trusted_proxy {
+            forwarded_for = req
+                .headers
+                .get_all(&*X_FORWARDED_FOR)
+                .filter_map(|hdr| hdr.to_str().ok())
+                .flat_map(|val| val.split(','))
+                .map(str::trim)
+                .filter(|val| !val.is_empty())
+                .collect();
+        }
+
+        let realip_remote_addr = match trusted {
+            // the client is the last hop that was not added by one of our own proxies
+            Some(trusted) => forwarded_for
+                .iter()
+                .rev()
+                .find(|val| !parse_forwarded_ip(val).map_or(false, |ip| trusted.contains(ip)))
+                .or_else(|| forwarded_for.first()),
+            None => forwarded_for.first(),
+        }
+        .map(|val| (*val).to_owned());
 
         let peer_addr = req.peer_addr.map(|addr| addr.ip().to_string());
 
@@ -151,10 +205,14 @@ impl ConnectionInfo {
     /// - `X-Forwarded-For` header
     /// - peer address of opened socket (same as [`remote_addr`](Self::remote_addr))
     ///
+    /// When [trusted proxies](crate::HttpServer::trusted_proxies) are configured, the address is
+    /// the last one in the forwarding chain that does not belong to a trusted proxy.
+    ///
     /// # Security
     /// Do not use this function for security purposes unless you can be sure that the `Forwarded`
-    /// and `X-Forwarded-For` headers cannot be spoofed by the client. If you are running without a
-    /// proxy then [obtaining the peer address](Self::peer_addr) would be more appropriate.
+    /// and `X-Forwarded-For` headers cannot be spoofed by the client, for example by configuring
+    /// [trusted proxies](crate::HttpServer::trusted_proxies). If you are running without a proxy
+    /// then [obtaining the peer address](Self::peer_addr) would be more appropriate.
     #[inline]
     pub fn realip_remote_addr(&self) -> Option<&str> {
         self.realip_remote_addr
@@ -210,6 +268,104 @@ impl FromRequest for ConnectionInfo {
     }
 }
 
+/// Parses the IP address from a `Forwarded: for=` or `X-Forwarded-For` value.
+///
+/// Values can be bare addresses, have a port, or be bracketed IPv6 addresses (with or without
+/// a port). Obfuscated identifiers like `_hidden` or `unknown` return `None`.
+fn parse_forwarded_ip(val: &str) -> Option<IpAddr> {
+    if let Ok(ip) = val.parse() {
+        return Some(ip);
+    }
+
+    if let Ok(addr) = val.parse::<SocketAddr>() {
+        return Some(addr.ip());
+    }
+
+    val.strip_prefix('[')?.strip_suffix(']')?.parse().ok()
+}
+
+/// List of IP networks, in CIDR notation, that reverse proxies in front of the server connect from.
+///
+/// See [`HttpServer::trusted_proxies`](crate::HttpServer::trusted_proxies).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies {
+    networks: Vec<(IpAddr, u8)>,
+}
+
+impl TrustedProxies {
+    /// Parses a list of networks such as `10.0.0.0/8`, `192.168.1.1` or `fd00::/8`.
+    ///
+    /// Addresses without a prefix length match only that address.
+    pub fn new<I, T>(networks: I) -> Result<Self, InvalidNetwork>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let networks = networks
+            .into_iter()
+            .map(|network| {
+                let network = network.as_ref().trim();
+                parse_network(network).ok_or_else(|| InvalidNetwork(network.to_owned()))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { networks })
+    }
+
+    /// Returns true if `ip` is in one of the networks.
+    ///
+    /// IPv4-mapped IPv6 addresses are matched against IPv4 networks.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(ip) => match ip.segments() {
+                [0, 0, 0, 0, 0, 0xffff, hi, lo] => {
+                    IpAddr::V4(Ipv4Addr::from((u32::from(hi) << 16) | u32::from(lo)))
+                }
+                _ => IpAddr::V6(ip),
+            },
+            ip => ip,
+        };
+
+        self.networks
+            .iter()
+            .any(|&(network, prefix)| match (network, ip) {
+                (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                    let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+                    u32::from(network) & mask == u32::from(ip) & mask
+                }
+                (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                    let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+                    u128::from(network) & mask == u128::from(ip) & mask
+                }
+                _ => false,
+            })
+    }
+}
+
+fn parse_network(network: &str) -> Option<(IpAddr, u8)> {
+    let mut parts = network.splitn(2, '/');
+    let ip = parts.next()?.parse::<IpAddr>().ok()?;
+
+    let max_prefix = if ip.is_ipv4() { 32 } else { 128 };
+    let prefix = match parts.next() {
+        Some(prefix) => prefix
+            .parse::<u8>()
+            .ok()
+            .filter(|&prefix| prefix <= max_prefix)?,
+        None => max_prefix,
+    };
+
+    Some((ip, prefix))
+}
+
+/// Error returned when a trusted proxy network can not be parsed.
+#[derive(Debug, Display, Error)]
+#[display(
+    fmt = "Invalid network \"{}\", expected an IP address or CIDR block",
+    _0
+)]
+pub struct InvalidNetwork(#[error(not(source))] String);
+
 /// Extractor for peer's socket address.
 ///
 /// Also see [`HttpRequest::peer_addr`] and [`ConnectionInfo::peer_addr`].
@@ -468,4 +624,97 @@ mod tests {
         let conn_info = ConnectionInfo::extract(&req).await.unwrap();
         assert_eq!(conn_info.realip_remote_addr().unwrap(), "127.0.0.1");
     }
+
+    #[test]
+    fn trusted_proxies_parse() {
+        let proxies = TrustedProxies::new(["10.0.0.0/8", "192.168.1.1", "fd00::/8"]).unwrap();
+
+        assert!(proxies.contains("10.1.2.3".parse().unwrap()));
+        assert!(proxies.contains("192.168.1.1".parse().unwrap()));
+        assert!(!proxies.contains("192.168.1.2".parse().unwrap()));
+        assert!(proxies.contains("fd12::1".parse().unwrap()));
+        assert!(!proxies.contains("fe80::1".parse().unwrap()));
+
+        // IPv4-mapped IPv6 addresses match IPv4 networks
+        assert!(proxies.contains("::ffff:10.0.0.1".parse().unwrap()));
+
+        assert!(TrustedProxies::new(["0.0.0.0/0"])
+            .unwrap()
+            .contains("203.0.113.7".parse().unwrap()));
+
+        assert!(TrustedProxies::new(["10.0.0.0/33"]).is_err());
+        assert!(TrustedProxies::new(["example.com"]).is_err());
+    }
+
+    #[test]
+    fn untrusted_peer_ignores_forwarded_headers() {
+        let req = TestRequest::default()
+            .trusted_proxies(TrustedProxies::new(["10.0.0.0/8"]).unwrap())
+            .peer_addr("203.0.113.7:4000".parse().unwrap())
+            .insert_header((header::HOST, "rust-lang.org"))
+            .insert_header((
+                header::FORWARDED,
+                "for=192.0.2.60; proto=https; host=spoofed.com",
+            ))
+            .insert_header((X_FORWARDED_FOR, "192.0.2.61"))
+            .insert_header((X_FORWARDED_PROTO, "https"))
+            .insert_header((X_FORWARDED_HOST, "spoofed.com"))
+            .to_http_request();
+
+        let info = req.connection_info();
+        assert_eq!(info.scheme(), "http");
+        assert_eq!(info.host(), "rust-lang.org");
+        assert_eq!(info.realip_remote_addr(), Some("203.0.113.7"));
+    }
+
+    #[test]
+    fn trusted_peer_uses_forwarded_headers() {
+        let proxies = || TrustedProxies::new(["10.0.0.0/8"]).unwrap();
+
+        let req = TestRequest::default()
+            .trusted_proxies(proxies())
+            .peer_addr("10.0.0.2:4000".parse().unwrap())
+            .insert_header((
+                header::FORWARDED,
+                "for=192.0.2.60; proto=https; host=rust-lang.org",
+            ))
+            .to_http_request();
+
+        let info = req.connection_info();
+        assert_eq!(info.scheme(), "https");
+        assert_eq!(info.host(), "rust-lang.org");
+        assert_eq!(info.realip_remote_addr(), Some("192.0.2.60"));
+
+        // addresses prepended by the client are skipped
+        let req = TestRequest::default()
+            .trusted_proxies(proxies())
+            .peer_addr("10.0.0.2:4000".parse().unwrap())
+            .insert_header((X_FORWARDED_FOR, "1.1.1.1, 192.0.2.60, 10.0.0.3"))
+            .to_http_request();
+        assert_eq!(
+            req.connection_info().realip_remote_addr(),
+            Some("192.0.2.60")
+        );
+
+        let req = TestRequest::default()
+            .trusted_proxies(proxies())
+            .peer_addr("10.0.0.2:4000".parse().unwrap())
+            .insert_header((
+                header::FORWARDED,
+                r#"for="[2001:db8:cafe::17]:4711", for="10.0.0.3:80""#,
+            ))
+            .to_http_request();
+        assert_eq!(
+            req.connection_info().realip_remote_addr(),
+            Some("[2001:db8:cafe::17]:4711")
+        );
+
+        // only trusted proxies in the chain, so the original client is used
+        let req = TestRequest::default()
+            .trusted_proxies(proxies())
+            .peer_addr("10.0.0.2:4000".parse().unwrap())
+            .insert_header((X_FORWARDED_FOR, "10.0.0.4, 10.0.0.3"))
+            .to_http_request();
+        assert_eq!(req.connection_info().realip_remote_addr(), Some("10.0.0.4"));
+    }
 }
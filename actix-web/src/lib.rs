@@ -65,7 +65,11 @@
 //! - `compress-zstd` - zstd content encoding compression support (enabled by default)
 //! - `openssl` - HTTPS support via `openssl` crate, supports `HTTP/2`
 //! - `rustls` - HTTPS support via `rustls` crate, supports `HTTP/2`
+//! - `acme` - automatic certificate management for `rustls` servers using ACME (e.g. Let's Encrypt)
 //! - `secure-cookies` - secure cookies support
+//! - `tracing` - request spans and W3C trace context propagation via the `tracing` crate
+//! - `protobuf` - Protocol Buffers extractor and responder via the `prost` crate
+//! - `config-toml` - loading `HttpServerConfig` from TOML files
 
 #![deny(rust_2018_idioms, nonstandard_style)]
 #![warn(future_incompatible)]
@@ -73,6 +77,8 @@
 #![doc(html_favicon_url = "https://actix.rs/favicon.ico")]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(feature = "acme")]
+pub mod acme;
 mod app;
 mod app_service;
 mod config;
@@ -80,12 +86,16 @@ mod data;
 pub mod dev;
 pub mod error;
 mod extract;
+pub mod grpc_web;
 pub mod guard;
 mod handler;
+pub mod health;
 mod helpers;
 pub mod http;
 mod info;
 pub mod middleware;
+mod proxy_protocol;
+mod reload;
 mod request;
 mod request_data;
 mod resource;
@@ -95,9 +105,13 @@ mod route;
 pub mod rt;
 mod scope;
 mod server;
+mod server_config;
 mod service;
 pub mod test;
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+pub mod tls;
 pub(crate) mod types;
+mod vhost;
 pub mod web;
 
 pub use crate::app::App;
@@ -106,13 +120,16 @@ pub use crate::error::Result;
 pub use crate::error::{Error, ResponseError};
 pub use crate::extract::FromRequest;
 pub use crate::handler::Handler;
+pub use crate::reload::AppReloader;
 pub use crate::request::HttpRequest;
 pub use crate::resource::Resource;
 pub use crate::response::{CustomizeResponder, HttpResponse, HttpResponseBuilder, Responder};
 pub use crate::route::Route;
 pub use crate::scope::Scope;
 pub use crate::server::HttpServer;
+pub use crate::server_config::{HttpServerConfig, HttpServerConfigError};
 pub use crate::types::Either;
+pub use crate::vhost::VirtualHosts;
 
 pub use actix_http::{body, HttpMessage};
 
@@ -141,5 +158,6 @@ codegen_reexport!(delete);
 codegen_reexport!(trace);
 codegen_reexport!(connect);
 codegen_reexport!(options);
+codegen_reexport!(ResponseError);
 
 pub(crate) type BoxError = Box<dyn std::error::Error>;
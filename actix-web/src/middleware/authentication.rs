@@ -0,0 +1,303 @@
+//! For middleware documentation, see [`HttpAuthentication`].
+
+use std::{borrow::Cow, fmt, future::Future, marker::PhantomData, rc::Rc};
+
+use actix_service::{Service, Transform};
+use actix_utils::future::{ready, Ready};
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    body::EitherBody,
+    error::AuthenticationError,
+    service::{ServiceRequest, ServiceResponse},
+    web::{BasicAuth, BearerAuth},
+    Error, FromRequest,
+};
+
+/// Middleware for authenticating requests using an async validator function.
+///
+/// Credentials are extracted from the `Authorization` header using [`BasicAuth`] or
+/// [`BearerAuth`] and passed to the validator along with the request. Requests without valid
+/// credentials for the scheme are rejected with `401 Unauthorized` and a `WWW-Authenticate`
+/// challenge, without calling the validator or the inner service.
+///
+/// The validator returns the request to let it through, or an error and the request to reject it.
+/// Return an [`AuthenticationError`] to challenge the client to authenticate again, or another
+/// error, such as `403 Forbidden`, when the credentials are valid but not sufficient.
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     dev::ServiceRequest, error::AuthenticationError, middleware::HttpAuthentication, web,
+///     App, Error, HttpResponse,
+/// };
+///
+/// async fn validator(
+///     req: ServiceRequest,
+///     auth: web::BearerAuth,
+/// ) -> Result<ServiceRequest, (Error, ServiceRequest)> {
+///     if auth.token() == "mF_9.B5f-4.1JqM" {
+///         Ok(req)
+///     } else {
+///         Err((AuthenticationError::bearer().realm("api").into(), req))
+///     }
+/// }
+///
+/// let app = App::new().service(
+///     web::scope("/api")
+///         .wrap(HttpAuthentication::bearer(validator).realm("api"))
+///         .route("/", web::get().to(HttpResponse::Ok)),
+/// );
+/// ```
+pub struct HttpAuthentication<C, F> {
+    inner: Rc<Inner<F>>,
+    _credentials: PhantomData<fn() -> C>,
+}
+
+struct Inner<F> {
+    validator: F,
+    realm: Option<Cow<'static, str>>,
+}
+
+impl<F, Fut> HttpAuthentication<BasicAuth, F>
+where
+    F: Fn(ServiceRequest, BasicAuth) -> Fut,
+    Fut: Future<Output = Result<ServiceRequest, (Error, ServiceRequest)>>,
+{
+    /// Constructs middleware that validates credentials of the `Basic` scheme.
+    pub fn basic(validator: F) -> Self {
+        HttpAuthentication::with_validator(validator)
+    }
+}
+
+impl<F, Fut> HttpAuthentication<BearerAuth, F>
+where
+    F: Fn(ServiceRequest, BearerAuth) -> Fut,
+    Fut: Future<Output = Result<ServiceRequest, (Error, ServiceRequest)>>,
+{
+    /// Constructs middleware that validates credentials of the `Bearer` scheme.
+    pub fn bearer(validator: F) -> Self {
+        HttpAuthentication::with_validator(validator)
+    }
+}
+
+impl<C, F> HttpAuthentication<C, F> {
+    fn with_validator(validator: F) -> Self {
+        HttpAuthentication {
+            inner: Rc::new(Inner {
+                validator,
+                realm: None,
+            }),
+            _credentials: PhantomData,
+        }
+    }
+
+    /// Sets the realm sent in challenges when credentials are missing.
+    ///
+    /// Overrides the realm set with [`AuthConfig`](crate::web::AuthConfig).
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn realm(mut self, realm: impl Into<Cow<'static, str>>) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("HttpAuthentication must be configured before it is cloned.")
+            .realm = Some(realm.into());
+        self
+    }
+}
+
+impl<C, F> Clone for HttpAuthentication<C, F> {
+    fn clone(&self) -> Self {
+        HttpAuthentication {
+            inner: Rc::clone(&self.inner),
+            _credentials: PhantomData,
+        }
+    }
+}
+
+impl<C, F> fmt::Debug for HttpAuthentication<C, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpAuthentication")
+            .field("realm", &self.inner.realm)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, B, C, F, Fut> Transform<S, ServiceRequest> for HttpAuthentication<C, F>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+    C: FromRequest<Error = AuthenticationError> + 'static,
+    F: Fn(ServiceRequest, C) -> Fut + 'static,
+    Fut: Future<Output = Result<ServiceRequest, (Error, ServiceRequest)>> + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = HttpAuthenticationMiddleware<S, C, F>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(HttpAuthenticationMiddleware {
+            service: Rc::new(service),
+            inner: Rc::clone(&self.inner),
+            _credentials: PhantomData,
+        }))
+    }
+}
+
+#[doc(hidden)]
+pub struct HttpAuthenticationMiddleware<S, C, F> {
+    service: Rc<S>,
+    inner: Rc<Inner<F>>,
+    _credentials: PhantomData<fn() -> C>,
+}
+
+impl<S, B, C, F, Fut> Service<ServiceRequest> for HttpAuthenticationMiddleware<S, C, F>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+    C: FromRequest<Error = AuthenticationError> + 'static,
+    F: Fn(ServiceRequest, C) -> Fut + 'static,
+    Fut: Future<Output = Result<ServiceRequest, (Error, ServiceRequest)>> + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let inner = Rc::clone(&self.inner);
+
+        let credentials = {
+            let (http_req, payload) = req.parts_mut();
+            C::from_request(http_req, payload)
+        };
+
+        Box::pin(async move {
+            let credentials = match credentials.await {
+                Ok(credentials) => credentials,
+                Err(err) => {
+                    let err = match inner.realm {
+                        Some(ref realm) => err.realm(realm.clone()),
+                        None => err,
+                    };
+
+                    return Ok(req.error_response(err).map_into_right_body());
+                }
+            };
+
+            match (inner.validator)(req, credentials).await {
+                Ok(req) => service
+                    .call(req)
+                    .await
+                    .map(ServiceResponse::map_into_left_body),
+
+                Err((err, req)) => Ok(req.error_response(err).map_into_right_body()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::ErrorForbidden,
+        http::{header, StatusCode},
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    async fn validator(
+        req: ServiceRequest,
+        auth: BasicAuth,
+    ) -> Result<ServiceRequest, (Error, ServiceRequest)> {
+        match (auth.user_id(), auth.password()) {
+            ("Aladdin", Some("open sesame")) => Ok(req),
+            ("Aladdin", _) => Err((AuthenticationError::basic().into(), req)),
+            _ => Err((ErrorForbidden("not allowed"), req)),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn basic_validator() {
+        let app = test::init_service(
+            App::new()
+                .wrap(HttpAuthentication::basic(validator).realm("Lamp"))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = |auth: Option<&str>| {
+            let mut req = TestRequest::default();
+            if let Some(auth) = auth {
+                req = req.insert_header((header::AUTHORIZATION, auth));
+            }
+            req.to_request()
+        };
+
+        let res =
+            test::call_service(&app, req(Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="))).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // missing credentials are challenged using the configured realm
+        let res = test::call_service(&app, req(None)).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Basic realm=\"Lamp\""
+        );
+
+        let res = test::call_service(&app, req(Some("Bearer mF_9.B5f-4.1JqM"))).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        // errors from the validator are used as-is
+        let res = test::call_service(&app, req(Some("Basic QWxhZGRpbjo="))).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Basic realm=\"Restricted\""
+        );
+
+        let res = test::call_service(&app, req(Some("Basic dXNlcjpwYXNz"))).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert!(res.headers().get(header::WWW_AUTHENTICATE).is_none());
+    }
+
+    #[actix_rt::test]
+    async fn bearer_validator() {
+        let app = test::init_service(
+            App::new()
+                .wrap(HttpAuthentication::bearer(
+                    |req: ServiceRequest, auth: BearerAuth| async move {
+                        if auth.token() == "secret" {
+                            Ok(req)
+                        } else {
+                            Err((AuthenticationError::bearer().into(), req))
+                        }
+                    },
+                ))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer secret"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer other"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Bearer"
+        );
+    }
+}
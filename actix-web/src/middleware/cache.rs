@@ -0,0 +1,713 @@
+//! For middleware documentation, see [`ResponseCache`].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use actix_service::{Service, Transform};
+use actix_utils::future::{ready, Ready};
+use bytes::Bytes;
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    body::{self, BodySize, BoxBody, EitherBody, MessageBody},
+    error::ErrorInternalServerError,
+    http::{
+        header::{self, CacheDirective, HeaderMap, HeaderName, HeaderValue},
+        Method, StatusCode,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpResponse,
+};
+
+/// Default size above which response bodies are not cached.
+const DEFAULT_MAX_BODY_SIZE: u64 = 1024 * 1024;
+
+/// Identifies a cached resource; the values of its `Vary` headers select among its responses.
+///
+/// Includes the scheme and host so that applications serving several hosts, e.g. with
+/// [`VirtualHosts`](crate::VirtualHosts), do not share responses between them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    method: Method,
+    scheme: String,
+    host: String,
+    path: String,
+    query: String,
+}
+
+impl Key {
+    fn new(req: &ServiceRequest) -> Self {
+        let info = req.connection_info();
+
+        Key {
+            method: req.method().clone(),
+            scheme: info.scheme().to_owned(),
+            host: info.host().to_ascii_lowercase(),
+            path: req.path().to_owned(),
+            query: req.query_string().to_owned(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    stored: Instant,
+    ttl: Duration,
+}
+
+#[derive(Debug)]
+struct Resource {
+    last_used: u64,
+    vary: Vec<HeaderName>,
+    variants: Vec<(Vec<Option<HeaderValue>>, CachedResponse)>,
+}
+
+#[derive(Debug)]
+struct Entries {
+    capacity: usize,
+    len: usize,
+    tick: u64,
+    resources: HashMap<Key, Resource>,
+    recency: BTreeMap<u64, Key>,
+}
+
+impl Entries {
+    fn touch(&mut self, key: &Key) {
+        self.tick += 1;
+
+        if let Some(resource) = self.resources.get_mut(key) {
+            self.recency.remove(&resource.last_used);
+            resource.last_used = self.tick;
+            self.recency.insert(self.tick, key.clone());
+        }
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(resource) = self.resources.remove(key) {
+            self.recency.remove(&resource.last_used);
+            self.len -= resource.variants.len();
+        }
+    }
+}
+
+/// Storage for [`ResponseCache`], shared between all clones and worker threads.
+///
+/// Holds up to a fixed number of responses, evicting those of the least recently used resources
+/// first. Keep a clone of the store to [purge](Self::purge) responses when the underlying data
+/// changes.
+#[derive(Debug, Clone)]
+pub struct CacheStore {
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl CacheStore {
+    /// Constructs an empty store holding up to `capacity` responses.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "response cache capacity must be non-zero");
+
+        CacheStore {
+            entries: Arc::new(Mutex::new(Entries {
+                capacity,
+                len: 0,
+                tick: 0,
+                resources: HashMap::new(),
+                recency: BTreeMap::new(),
+            })),
+        }
+    }
+
+    /// Removes all cached responses for `path`, regardless of host and query string.
+    pub fn purge(&self, path: &str) {
+        let mut entries = self.entries.lock().unwrap();
+
+        let keys = entries
+            .resources
+            .keys()
+            .filter(|key| key.path == path)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for key in &keys {
+            entries.remove(key);
+        }
+    }
+
+    /// Removes all cached responses.
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.resources.clear();
+        entries.recency.clear();
+        entries.len = 0;
+    }
+
+    /// Returns the number of cached responses, including expired ones not yet removed.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len
+    }
+
+    /// Returns true if no responses are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a fresh response for `key` matching the request headers, and its age.
+    fn lookup(
+        &self,
+        key: &Key,
+        req_headers: &HeaderMap,
+        now: Instant,
+    ) -> Option<(StatusCode, HeaderMap, Bytes, Duration)> {
+        let mut entries = self.entries.lock().unwrap();
+        let entries = &mut *entries;
+
+        let resource = entries.resources.get_mut(key)?;
+        let values = vary_values(&resource.vary, req_headers);
+        let idx = resource.variants.iter().position(|(v, _)| *v == values)?;
+
+        let cached = &resource.variants[idx].1;
+        let age = now.saturating_duration_since(cached.stored);
+
+        if age >= cached.ttl {
+            resource.variants.swap_remove(idx);
+            entries.len -= 1;
+
+            if resource.variants.is_empty() {
+                entries.remove(key);
+            }
+
+            return None;
+        }
+
+        let res = (
+            cached.status,
+            cached.headers.clone(),
+            cached.body.clone(),
+            age,
+        );
+        entries.touch(key);
+        Some(res)
+    }
+
+    fn insert(
+        &self,
+        key: Key,
+        vary: Vec<HeaderName>,
+        req_headers: &HeaderMap,
+        response: CachedResponse,
+    ) {
+        let mut entries = self.entries.lock().unwrap();
+        let entries = &mut *entries;
+
+        let values = vary_values(&vary, req_headers);
+
+        match entries.resources.get_mut(&key) {
+            Some(resource) if resource.vary == vary => {
+                match resource.variants.iter_mut().find(|(v, _)| *v == values) {
+                    Some((_, cached)) => *cached = response,
+                    None => {
+                        resource.variants.push((values, response));
+                        entries.len += 1;
+                    }
+                }
+            }
+
+            _ => {
+                // a changed Vary header makes existing variants unreachable
+                entries.remove(&key);
+                entries.resources.insert(
+                    key.clone(),
+                    Resource {
+                        last_used: 0,
+                        vary,
+                        variants: vec![(values, response)],
+                    },
+                );
+                entries.len += 1;
+            }
+        }
+
+        entries.touch(&key);
+
+        while entries.len > entries.capacity {
+            let oldest = match entries.recency.values().next() {
+                Some(oldest) => oldest.clone(),
+                None => break,
+            };
+
+            entries.remove(&oldest);
+        }
+    }
+}
+
+fn vary_values(vary: &[HeaderName], req_headers: &HeaderMap) -> Vec<Option<HeaderValue>> {
+    vary.iter()
+        .map(|name| req_headers.get(name).cloned())
+        .collect()
+}
+
+/// Middleware for caching responses in memory.
+///
+/// Successful responses to `GET` requests are stored, keyed by path and query string, and served
+/// with an `Age` header without calling the inner service until they expire. Responses with
+/// different values of the request headers listed in their `Vary` header are cached separately.
+///
+/// Caching follows the response's `Cache-Control` header: responses are stored for their
+/// `s-maxage` or `max-age`, and not at all if marked `no-store`, `no-cache` or `private`.
+/// Responses without an explicit lifetime are only stored if a
+/// [default lifetime](Self::default_max_age) is set. Responses setting cookies, with `Vary: *`
+/// or with bodies that are streamed or larger than [`max_body_size`](Self::max_body_size) are
+/// never stored.
+///
+/// Requests with an `Authorization` header or `Cache-Control: no-store` bypass the cache, and
+/// those with `Cache-Control: no-cache` are always passed to the inner service, refreshing the
+/// cached response.
+///
+/// Responses are held in a [`CacheStore`]. Construct it outside the `HttpServer::new` closure to
+/// share it between workers and to purge responses when their data changes.
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     middleware::{CacheStore, ResponseCache},
+///     web, App, HttpResponse, HttpServer,
+/// };
+///
+/// # fn run() -> std::io::Result<actix_web::dev::Server> {
+/// let store = CacheStore::new(1000);
+///
+/// let srv = HttpServer::new(move || {
+///     let store = store.clone();
+///
+///     App::new().service(
+///         web::resource("/articles")
+///             .wrap(ResponseCache::with_store(store.clone()))
+///             .route(web::get().to(|| async {
+///                 HttpResponse::Ok()
+///                     .insert_header(("cache-control", "max-age=60"))
+///                     .body("articles")
+///             }))
+///             .route(web::post().to(move || {
+///                 // articles changed, so the cached list is stale
+///                 store.purge("/articles");
+///                 async { HttpResponse::Created() }
+///             })),
+///     )
+/// })
+/// .bind(("127.0.0.1", 8080))?
+/// .run();
+/// # Ok(srv)
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ResponseCache {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    store: CacheStore,
+    default_max_age: Option<Duration>,
+    max_body_size: u64,
+}
+
+impl ResponseCache {
+    /// Constructs a response cache holding up to `capacity` responses in a new [`CacheStore`].
+    ///
+    /// Note that each call creates separate storage; to share it between workers, use
+    /// [`with_store`](Self::with_store) with a single, cloned store.
+    pub fn new(capacity: usize) -> Self {
+        ResponseCache::with_store(CacheStore::new(capacity))
+    }
+
+    /// Constructs a response cache using the given store.
+    pub fn with_store(store: CacheStore) -> Self {
+        ResponseCache {
+            inner: Rc::new(Inner {
+                store,
+                default_max_age: None,
+                max_body_size: DEFAULT_MAX_BODY_SIZE,
+            }),
+        }
+    }
+
+    /// Sets how long responses without a `max-age` or `s-maxage` directive are cached.
+    ///
+    /// By default, such responses are not cached.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn default_max_age(mut self, max_age: Duration) -> Self {
+        self.inner_mut().default_max_age = Some(max_age);
+        self
+    }
+
+    /// Sets the size, in bytes, above which response bodies are not cached.
+    ///
+    /// Defaults to 1 MiB.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn max_body_size(mut self, max_body_size: u64) -> Self {
+        self.inner_mut().max_body_size = max_body_size;
+        self
+    }
+
+    /// Returns the store holding cached responses.
+    pub fn store(&self) -> &CacheStore {
+        &self.inner.store
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner)
+            .expect("ResponseCache must be configured before it is cloned.")
+    }
+}
+
+impl fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseCache")
+            .field("store", &self.inner.store)
+            .field("default_max_age", &self.inner.default_max_age)
+            .field("max_body_size", &self.inner.max_body_size)
+            .finish()
+    }
+}
+
+impl Inner {
+    /// Returns how long a response may be cached, or `None` if it must not be.
+    fn ttl<B: MessageBody>(&self, res: &ServiceResponse<B>) -> Option<Duration> {
+        if res.status() != StatusCode::OK || res.headers().contains_key(header::SET_COOKIE) {
+            return None;
+        }
+
+        match res.response().body().size() {
+            BodySize::Sized(size) if size <= self.max_body_size => {}
+            _ => return None,
+        }
+
+        let mut max_age = None;
+        let mut s_maxage = None;
+
+        for directive in cache_directives(res.headers()) {
+            match directive {
+                CacheDirective::NoStore | CacheDirective::NoCache | CacheDirective::Private => {
+                    return None
+                }
+                CacheDirective::MaxAge(secs) => max_age = Some(secs),
+                CacheDirective::SMaxAge(secs) => s_maxage = Some(secs),
+                _ => {}
+            }
+        }
+
+        let ttl = match s_maxage.or(max_age) {
+            Some(secs) => Duration::from_secs(u64::from(secs)),
+            None => self.default_max_age?,
+        };
+
+        (!ttl.is_zero()).then(|| ttl)
+    }
+}
+
+/// Returns the header names in the response's `Vary` header, or `None` if it varies on anything.
+fn vary_names(headers: &HeaderMap) -> Option<Vec<HeaderName>> {
+    let mut names = Vec::new();
+
+    for value in headers.get_all(header::VARY) {
+        for name in value.to_str().ok()?.split(',') {
+            let name = name.trim();
+
+            if name == "*" {
+                return None;
+            }
+
+            if !name.is_empty() {
+                names.push(HeaderName::from_bytes(name.as_bytes()).ok()?);
+            }
+        }
+    }
+
+    names.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    names.dedup();
+    Some(names)
+}
+
+/// Returns the directives of the `Cache-Control` headers, ignoring them if malformed.
+fn cache_directives(headers: &HeaderMap) -> Vec<CacheDirective> {
+    header::from_comma_delimited(headers.get_all(header::CACHE_CONTROL)).unwrap_or_default()
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ResponseCache
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = ResponseCacheMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ResponseCacheMiddleware {
+            service: Rc::new(service),
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+#[doc(hidden)]
+pub struct ResponseCacheMiddleware<S> {
+    service: Rc<S>,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for ResponseCacheMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        if req.method() != Method::GET
+            || req.headers().contains_key(header::AUTHORIZATION)
+            || cache_directives(req.headers()).contains(&CacheDirective::NoStore)
+        {
+            return Box::pin(async move {
+                service
+                    .call(req)
+                    .await
+                    .map(ServiceResponse::map_into_left_body)
+            });
+        }
+
+        let key = Key::new(&req);
+
+        if !cache_directives(req.headers()).contains(&CacheDirective::NoCache) {
+            let cached = self.inner.store.lookup(&key, req.headers(), Instant::now());
+
+            if let Some((status, headers, body, age)) = cached {
+                let mut res = HttpResponse::with_body(status, BoxBody::new(body));
+                *res.headers_mut() = headers;
+                res.headers_mut()
+                    .insert(header::AGE, HeaderValue::from(age.as_secs()));
+
+                return Box::pin(ready(Ok(req.into_response(res).map_into_right_body())));
+            }
+        }
+
+        let inner = Rc::clone(&self.inner);
+
+        Box::pin(async move {
+            let res = service.call(req).await?;
+
+            let (ttl, vary) = match (inner.ttl(&res), vary_names(res.headers())) {
+                (Some(ttl), Some(vary)) => (ttl, vary),
+                _ => return Ok(res.map_into_left_body()),
+            };
+
+            let (req, res) = res.into_parts();
+            let (res, body) = res.into_parts();
+            let body = body::to_bytes(body)
+                .await
+                .map_err(|err| ErrorInternalServerError(err.into()))?;
+
+            inner.store.insert(
+                key,
+                vary,
+                req.headers(),
+                CachedResponse {
+                    status: res.status(),
+                    headers: res.headers().clone(),
+                    body: body.clone(),
+                    stored: Instant::now(),
+                    ttl,
+                },
+            );
+
+            let res = res.set_body(BoxBody::new(body));
+            Ok(ServiceResponse::new(req, res).map_into_right_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    fn cached(ttl: u64) -> CachedResponse {
+        CachedResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::new(),
+            stored: Instant::now(),
+            ttl: Duration::from_secs(ttl),
+        }
+    }
+
+    fn key(path: &str) -> Key {
+        Key {
+            method: Method::GET,
+            scheme: "http".to_owned(),
+            host: "localhost:8080".to_owned(),
+            path: path.to_owned(),
+            query: String::new(),
+        }
+    }
+
+    #[test]
+    fn store_expiry_and_eviction() {
+        let store = CacheStore::new(2);
+        let headers = HeaderMap::new();
+        let now = Instant::now();
+
+        store.insert(key("/a"), vec![], &headers, cached(10));
+        store.insert(key("/b"), vec![], &headers, cached(10));
+        assert!(store.lookup(&key("/a"), &headers, now).is_some());
+
+        // "/b" is the least recently used
+        store.insert(key("/c"), vec![], &headers, cached(10));
+        assert_eq!(store.len(), 2);
+        assert!(store.lookup(&key("/b"), &headers, now).is_none());
+        assert!(store.lookup(&key("/a"), &headers, now).is_some());
+
+        let later = now + Duration::from_secs(11);
+        assert!(store.lookup(&key("/a"), &headers, later).is_none());
+        assert_eq!(store.len(), 1);
+
+        store.purge("/c");
+        assert!(store.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn serves_cached_responses() {
+        let calls = Rc::new(Cell::new(0));
+        let store = CacheStore::new(10);
+
+        let srv = {
+            let calls = Rc::clone(&calls);
+
+            test::init_service(
+                App::new()
+                    .wrap(ResponseCache::with_store(store.clone()))
+                    .default_service(web::to(move |req: crate::HttpRequest| {
+                        calls.set(calls.get() + 1);
+                        let cache_control = match req.path() {
+                            "/private" => "private, max-age=60",
+                            _ => "max-age=60",
+                        };
+
+                        ready(
+                            HttpResponse::Ok()
+                                .insert_header((header::CACHE_CONTROL, cache_control))
+                                .insert_header((header::VARY, "accept-language"))
+                                .body("cached"),
+                        )
+                    })),
+            )
+            .await
+        };
+
+        let get = |path: &str, lang: &str| {
+            TestRequest::with_uri(path)
+                .insert_header((header::ACCEPT_LANGUAGE, lang))
+                .to_request()
+        };
+
+        let res = test::call_service(&srv, get("/", "en")).await;
+        assert!(!res.headers().contains_key(header::AGE));
+        assert_eq!(test::read_body(res).await, "cached");
+
+        let res = test::call_service(&srv, get("/", "en")).await;
+        assert_eq!(res.headers().get(header::AGE).unwrap(), "0");
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "accept-language");
+        assert_eq!(test::read_body(res).await, "cached");
+        assert_eq!(calls.get(), 1);
+
+        test::call_service(&srv, get("/", "fr")).await;
+        assert_eq!(calls.get(), 2);
+
+        test::call_service(&srv, get("/private", "en")).await;
+        test::call_service(&srv, get("/private", "en")).await;
+        assert_eq!(calls.get(), 4);
+
+        let req = TestRequest::with_uri("/")
+            .insert_header((header::ACCEPT_LANGUAGE, "en"))
+            .insert_header((header::CACHE_CONTROL, "no-cache"))
+            .to_request();
+        test::call_service(&srv, req).await;
+        assert_eq!(calls.get(), 5);
+
+        store.purge("/");
+        test::call_service(&srv, get("/", "en")).await;
+        assert_eq!(calls.get(), 6);
+    }
+
+    #[actix_rt::test]
+    async fn separates_hosts() {
+        let srv = test::init_service(App::new().wrap(ResponseCache::new(10)).default_service(
+            web::to(|req: crate::HttpRequest| {
+                let host = req.connection_info().host().to_owned();
+
+                ready(
+                    HttpResponse::Ok()
+                        .insert_header((header::CACHE_CONTROL, "max-age=60"))
+                        .body(host),
+                )
+            }),
+        ))
+        .await;
+
+        let get = |host: &str| {
+            TestRequest::with_uri("/")
+                .insert_header((header::HOST, host))
+                .to_request()
+        };
+
+        for host in ["a.example", "b.example", "a.example", "b.example"] {
+            let res = test::call_service(&srv, get(host)).await;
+            assert_eq!(test::read_body(res).await, host);
+        }
+    }
+
+    #[test]
+    fn parses_vary() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(vary_names(&headers), Some(vec![]));
+
+        headers.append(
+            header::VARY,
+            HeaderValue::from_static("Accept-Encoding, origin"),
+        );
+        headers.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+        assert_eq!(
+            vary_names(&headers),
+            Some(vec![header::ACCEPT_ENCODING, header::ORIGIN])
+        );
+
+        headers.append(header::VARY, HeaderValue::from_static("*"));
+        assert_eq!(vary_names(&headers), None);
+    }
+}
@@ -0,0 +1,508 @@
+//! For middleware documentation, see [`Csrf`].
+
+use std::{collections::HashSet, fmt, ops::Deref, rc::Rc};
+
+use actix_service::{Service, Transform};
+use actix_utils::future::{ready, Ready};
+use bytes::BytesMut;
+use cookie::{Cookie, SameSite};
+use futures_core::future::LocalBoxFuture;
+use futures_util::StreamExt as _;
+use regex::RegexSet;
+
+use crate::{
+    body::EitherBody,
+    error::{ErrorForbidden, UrlencodedError},
+    http::{
+        header::{self, HeaderName},
+        Method,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpMessage as _,
+};
+
+/// Length of generated tokens, in bytes, before encoding.
+const TOKEN_LEN: usize = 32;
+
+/// Largest URL-encoded form body that is read to find the token field.
+const MAX_FORM_SIZE: usize = 262_144;
+
+/// Middleware for protecting against cross-site request forgery using double-submit cookies.
+///
+/// Each client is issued a random token in a cookie (`csrf-token` by default). Requests with
+/// unsafe methods, that is all but `GET`, `HEAD`, `OPTIONS` and `TRACE`, must send the same token
+/// back in a header (`X-CSRF-Token` by default) or, for URL-encoded forms, in a form field
+/// (`csrf_token` by default). Other sites can trigger requests that carry the cookie but can not
+/// read it, so they can not supply a matching token. Requests without a matching token are
+/// rejected with `403 Forbidden`, without calling the inner service.
+///
+/// The token is stored in the request extensions as a [`CsrfToken`], and can be extracted in
+/// handlers using [`ReqData<CsrfToken>`](crate::web::ReqData) to embed it into forms. The cookie is
+/// not `HttpOnly` so that scripts can read it and send it in the header.
+///
+/// By default the cookie is `Secure` and `SameSite=Lax`, which keeps the cookie on top-level
+/// navigations from other sites so that the token stays stable. Setting `SameSite=None` also
+/// forces the `Secure` attribute, which browsers require for such cookies.
+///
+/// Routes that can not send a token, such as webhooks, can be opted out using
+/// [`exclude`](Self::exclude) and [`exclude_regex`](Self::exclude_regex).
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     middleware::{Csrf, CsrfToken},
+///     web, App, HttpResponse,
+/// };
+///
+/// async fn form(token: web::ReqData<CsrfToken>) -> HttpResponse {
+///     HttpResponse::Ok().content_type("text/html").body(format!(
+///         r#"<form method="post"><input type="hidden" name="csrf_token" value="{}"></form>"#,
+///         token.as_str()
+///     ))
+/// }
+///
+/// let app = App::new()
+///     .wrap(Csrf::new().exclude("/webhook"))
+///     .route("/", web::get().to(form))
+///     .route("/", web::post().to(HttpResponse::Ok))
+///     .route("/webhook", web::post().to(HttpResponse::Ok));
+/// ```
+#[derive(Clone)]
+pub struct Csrf {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    cookie_name: String,
+    header_name: HeaderName,
+    field_name: String,
+    secure: bool,
+    same_site: SameSite,
+    exclude: HashSet<String>,
+    exclude_regex: RegexSet,
+}
+
+impl Default for Csrf {
+    fn default() -> Self {
+        Csrf {
+            inner: Rc::new(Inner {
+                cookie_name: "csrf-token".to_owned(),
+                header_name: HeaderName::from_static("x-csrf-token"),
+                field_name: "csrf_token".to_owned(),
+                secure: true,
+                same_site: SameSite::Lax,
+                exclude: HashSet::new(),
+                exclude_regex: RegexSet::empty(),
+            }),
+        }
+    }
+}
+
+impl Csrf {
+    /// Constructs a `Csrf` middleware with the default cookie, header and field names.
+    pub fn new() -> Self {
+        Csrf::default()
+    }
+
+    /// Sets the name of the token cookie.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.inner_mut().cookie_name = name.into();
+        self
+    }
+
+    /// Sets the request header the token is read from.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn header_name(mut self, header_name: HeaderName) -> Self {
+        self.inner_mut().header_name = header_name;
+        self
+    }
+
+    /// Sets the URL-encoded form field the token is read from.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn field_name(mut self, name: impl Into<String>) -> Self {
+        self.inner_mut().field_name = name.into();
+        self
+    }
+
+    /// Sets whether the token cookie has the `Secure` attribute. Defaults to `true`.
+    ///
+    /// Only disable this for local development over plain HTTP.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.inner_mut().secure = secure;
+        self
+    }
+
+    /// Sets the `SameSite` attribute of the token cookie. Defaults to `Lax`.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.inner_mut().same_site = same_site;
+        self
+    }
+
+    /// Does not check tokens for requests to the specified path.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn exclude<T: Into<String>>(mut self, path: T) -> Self {
+        self.inner_mut().exclude.insert(path.into());
+        self
+    }
+
+    /// Does not check tokens for requests to paths that match regex.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned, or if the regex is invalid.
+    pub fn exclude_regex<T: Into<String>>(mut self, path: T) -> Self {
+        let inner = self.inner_mut();
+        let mut patterns = inner.exclude_regex.patterns().to_vec();
+        patterns.push(path.into());
+        inner.exclude_regex = RegexSet::new(patterns).unwrap();
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner)
+            .expect("Csrf must be configured before it is cloned or used.")
+    }
+}
+
+impl fmt::Debug for Csrf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Csrf")
+            .field("cookie_name", &self.inner.cookie_name)
+            .field("header_name", &self.inner.header_name)
+            .field("field_name", &self.inner.field_name)
+            .finish()
+    }
+}
+
+impl Inner {
+    fn cookie(&self, token: &str) -> Cookie<'static> {
+        Cookie::build(self.cookie_name.clone(), token.to_owned())
+            .path("/")
+            .secure(self.secure || self.same_site == SameSite::None)
+            .same_site(self.same_site)
+            .finish()
+    }
+
+    fn is_excluded(&self, path: &str) -> bool {
+        self.exclude.contains(path) || self.exclude_regex.is_match(path)
+    }
+}
+
+/// The CSRF token issued to a client by the [`Csrf`] middleware.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CsrfToken(String);
+
+impl CsrfToken {
+    /// Returns the token as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Consumes the value, returning the token.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl Deref for CsrfToken {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for CsrfToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Csrf
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = CsrfMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CsrfMiddleware {
+            service: Rc::new(service),
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+#[doc(hidden)]
+pub struct CsrfMiddleware<S> {
+    service: Rc<S>,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for CsrfMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let inner = Rc::clone(&self.inner);
+
+        Box::pin(async move {
+            let cookie_token = req
+                .cookie(&inner.cookie_name)
+                .map(|cookie| cookie.value().to_owned())
+                .filter(|token| is_valid_token(token));
+
+            // issue a new token if the client does not have one yet
+            let (token, new_cookie) = match cookie_token {
+                Some(token) => (token, None),
+                None => {
+                    let token = generate_token();
+                    let cookie = inner.cookie(&token);
+                    (token, Some(cookie))
+                }
+            };
+
+            req.extensions_mut().insert(CsrfToken(token.clone()));
+
+            let checked = !is_safe_method(req.method()) && !inner.is_excluded(req.path());
+
+            let mut res = if checked && !has_matching_token(&mut req, &inner, &token).await? {
+                req.error_response(ErrorForbidden("CSRF token missing or invalid"))
+                    .map_into_right_body()
+            } else {
+                service.call(req).await?.map_into_left_body()
+            };
+
+            if let Some(cookie) = new_cookie {
+                res.response_mut().add_cookie(&cookie)?;
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+/// Returns true if the request carries `token` in the token header or form field.
+///
+/// The form body, once read, is put back so that handlers can still extract it.
+async fn has_matching_token(
+    req: &mut ServiceRequest,
+    inner: &Inner,
+    token: &str,
+) -> Result<bool, Error> {
+    if let Some(val) = req.headers().get(&inner.header_name) {
+        return Ok(constant_time_eq(val.as_bytes(), token.as_bytes()));
+    }
+
+    let is_form = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|val| val.to_str().ok())
+        .map_or(false, |ct| {
+            ct.split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+        });
+
+    if !is_form {
+        return Ok(false);
+    }
+
+    let mut payload = req.parts_mut().1.take();
+    let mut body = BytesMut::new();
+
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+
+        if body.len() + chunk.len() > MAX_FORM_SIZE {
+            return Err(UrlencodedError::Overflow {
+                size: body.len() + chunk.len(),
+                limit: MAX_FORM_SIZE,
+            }
+            .into());
+        }
+
+        body.extend_from_slice(&chunk);
+    }
+
+    let body = body.freeze();
+
+    let matches = url::form_urlencoded::parse(&body)
+        .find(|(name, _)| *name == inner.field_name)
+        .map_or(false, |(_, val)| {
+            constant_time_eq(val.as_bytes(), token.as_bytes())
+        });
+
+    let (_, mut h1_payload) = actix_http::h1::Payload::create(true);
+    h1_payload.unread_data(body);
+    req.set_payload(h1_payload.into());
+
+    Ok(matches)
+}
+
+fn is_safe_method(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    )
+}
+
+/// Generates a random token encoded as URL-safe base64.
+fn generate_token() -> String {
+    let bytes: [u8; TOKEN_LEN] = rand::random();
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+/// Returns true if `token` looks like a token generated by [`generate_token`].
+fn is_valid_token(token: &str) -> bool {
+    token.len() == (TOKEN_LEN * 4 + 2) / 3
+        && token
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+/// Compares byte strings in time independent of where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    #[actix_rt::test]
+    async fn issues_and_checks_tokens() {
+        let app = test::init_service(
+            App::new()
+                .wrap(Csrf::new())
+                .route(
+                    "/",
+                    web::get().to(|token: web::ReqData<CsrfToken>| async move {
+                        token.into_inner().into_inner()
+                    }),
+                )
+                .route("/", web::post().to(|body: String| async move { body })),
+        )
+        .await;
+
+        let res = test::call_service(&app, TestRequest::get().to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let cookie = res
+            .response()
+            .cookies()
+            .find(|cookie| cookie.name() == "csrf-token")
+            .unwrap()
+            .into_owned();
+        assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+        assert_eq!(cookie.secure(), Some(true));
+        assert_eq!(cookie.http_only(), None);
+
+        let token = test::read_body(res).await;
+        assert_eq!(token, cookie.value());
+
+        // clients that already have a token are not issued a new one
+        let req = TestRequest::get().cookie(cookie.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.response().cookies().count(), 0);
+
+        let req = TestRequest::post().cookie(cookie.clone()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let req = TestRequest::post()
+            .cookie(cookie.clone())
+            .insert_header(("x-csrf-token", cookie.value()))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::post()
+            .cookie(cookie.clone())
+            .insert_header(("x-csrf-token", generate_token()))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        // form field is checked and the body is still available to the handler
+        let body = format!("name=x&csrf_token={}", cookie.value());
+        let req = TestRequest::post()
+            .cookie(cookie.clone())
+            .insert_header((header::CONTENT_TYPE, "application/x-www-form-urlencoded"))
+            .set_payload(body.clone())
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(test::read_body(res).await, body);
+
+        // a token without the matching cookie is rejected
+        let req = TestRequest::post()
+            .insert_header(("x-csrf-token", cookie.value()))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[actix_rt::test]
+    async fn excluded_paths() {
+        let app = test::init_service(
+            App::new()
+                .wrap(
+                    Csrf::new()
+                        .exclude("/webhook")
+                        .exclude_regex("^/api/")
+                        .same_site(SameSite::None)
+                        .secure(false),
+                )
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        for path in ["/webhook", "/api/items"] {
+            let req = TestRequest::post().uri(path).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+
+        let req = TestRequest::post().uri("/other").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        // `SameSite=None` cookies are always secure
+        let cookie = res.response().cookies().next().unwrap();
+        assert_eq!(cookie.secure(), Some(true));
+    }
+}
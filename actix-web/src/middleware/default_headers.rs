@@ -54,6 +54,13 @@ impl DefaultHeaders {
         DefaultHeaders::default()
     }
 
+    /// Constructs a `DefaultHeaders` middleware that adds the given headers.
+    pub(crate) fn from_headers(headers: HeaderMap) -> DefaultHeaders {
+        DefaultHeaders {
+            inner: Rc::new(Inner { headers }),
+        }
+    }
+
     /// Adds a header to the default set.
     ///
     /// # Panics
@@ -35,6 +35,16 @@ type ErrorHandler<B> = dyn Fn(ServiceResponse<B>) -> Result<ErrorHandlerResponse
 /// Register handlers with the `ErrorHandlers::handler()` method to register a custom error handler
 /// for a given status code. Handlers can modify existing responses or create completely new ones.
 ///
+/// To catch all error responses that have no status-specific handler, such as to emit a
+/// consistent JSON error envelope, register a fallback with [`default_handler`]. Fallbacks can
+/// also be registered for client errors (4xx) and server errors (5xx) independently with
+/// [`default_handler_client`] and [`default_handler_server`]. A handler registered for a specific
+/// status code always takes precedence over the defaults.
+///
+/// [`default_handler`]: ErrorHandlers::default_handler
+/// [`default_handler_client`]: ErrorHandlers::default_handler_client
+/// [`default_handler_server`]: ErrorHandlers::default_handler_server
+///
 /// # Examples
 /// ```
 /// use actix_web::http::{header, StatusCode};
@@ -53,16 +63,74 @@ type ErrorHandler<B> = dyn Fn(ServiceResponse<B>) -> Result<ErrorHandlerResponse
 ///     .wrap(ErrorHandlers::new().handler(StatusCode::INTERNAL_SERVER_ERROR, add_error_header))
 ///     .service(web::resource("/").route(web::get().to(HttpResponse::InternalServerError)));
 /// ```
+///
+/// Using a default handler to rewrite the body of every error response:
+/// ```
+/// use actix_web::{
+///     dev::ServiceResponse,
+///     http::header,
+///     middleware::{ErrorHandlerResponse, ErrorHandlers},
+///     web, App, HttpResponse, Result,
+/// };
+///
+/// fn json_error<B>(res: ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>> {
+///     let status = res.status();
+///     let (req, res) = res.into_parts();
+///
+///     let res = res
+///         .set_body(format!(r#"{{"error":{}}}"#, status.as_u16()))
+///         .map_into_boxed_body();
+///
+///     let mut res = ServiceResponse::new(req, res).map_into_right_body();
+///     res.headers_mut().insert(
+///         header::CONTENT_TYPE,
+///         header::HeaderValue::from_static("application/json"),
+///     );
+///
+///     Ok(ErrorHandlerResponse::Response(res))
+/// }
+///
+/// let app = App::new()
+///     .wrap(ErrorHandlers::new().default_handler(json_error))
+///     .service(web::resource("/").route(web::get().to(HttpResponse::Ok)));
+/// ```
 pub struct ErrorHandlers<B> {
     handlers: Handlers<B>,
 }
 
-type Handlers<B> = Rc<AHashMap<StatusCode, Box<ErrorHandler<B>>>>;
+type Handlers<B> = Rc<HandlerMap<B>>;
+
+struct HandlerMap<B> {
+    by_status: AHashMap<StatusCode, Box<ErrorHandler<B>>>,
+    default_client: Option<Rc<ErrorHandler<B>>>,
+    default_server: Option<Rc<ErrorHandler<B>>>,
+}
+
+impl<B> HandlerMap<B> {
+    /// Returns the handler for the given status code, falling back to the default handlers.
+    fn get(&self, status: &StatusCode) -> Option<&ErrorHandler<B>> {
+        if let Some(handler) = self.by_status.get(status) {
+            return Some(handler.as_ref());
+        }
+
+        if status.is_client_error() {
+            self.default_client.as_deref()
+        } else if status.is_server_error() {
+            self.default_server.as_deref()
+        } else {
+            None
+        }
+    }
+}
 
 impl<B> Default for ErrorHandlers<B> {
     fn default() -> Self {
         ErrorHandlers {
-            handlers: Default::default(),
+            handlers: Rc::new(HandlerMap {
+                by_status: AHashMap::default(),
+                default_client: None,
+                default_server: None,
+            }),
         }
     }
 }
@@ -80,9 +148,46 @@ impl<B> ErrorHandlers<B> {
     {
         Rc::get_mut(&mut self.handlers)
             .unwrap()
+            .by_status
             .insert(status, Box::new(handler));
         self
     }
+
+    /// Register fallback error handler for all client (4xx) and server (5xx) error responses
+    /// without a status-specific handler.
+    ///
+    /// Replaces any handlers set with [`default_handler_client`](Self::default_handler_client)
+    /// or [`default_handler_server`](Self::default_handler_server).
+    pub fn default_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>> + 'static,
+    {
+        let handler: Rc<ErrorHandler<B>> = Rc::new(handler);
+        let handlers = Rc::get_mut(&mut self.handlers).unwrap();
+        handlers.default_client = Some(Rc::clone(&handler));
+        handlers.default_server = Some(handler);
+        self
+    }
+
+    /// Register fallback error handler for client error (4xx) responses without a status-specific
+    /// handler.
+    pub fn default_handler_client<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>> + 'static,
+    {
+        Rc::get_mut(&mut self.handlers).unwrap().default_client = Some(Rc::new(handler));
+        self
+    }
+
+    /// Register fallback error handler for server error (5xx) responses without a status-specific
+    /// handler.
+    pub fn default_handler_server<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>> + 'static,
+    {
+        Rc::get_mut(&mut self.handlers).unwrap().default_server = Some(Rc::new(handler));
+        self
+    }
 }
 
 impl<S, B> Transform<S, ServiceRequest> for ErrorHandlers<B>
@@ -298,4 +403,48 @@ mod tests {
             "error in error handler"
         );
     }
+
+    #[actix_rt::test]
+    async fn default_handlers() {
+        fn tag_handler<B>(
+            tag: &'static str,
+        ) -> impl Fn(ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>> {
+            move |mut res| {
+                res.response_mut()
+                    .headers_mut()
+                    .insert(CONTENT_TYPE, HeaderValue::from_static(tag));
+
+                Ok(ErrorHandlerResponse::Response(res.map_into_left_body()))
+            }
+        }
+
+        let make_mw = |status| async move {
+            ErrorHandlers::new()
+                .default_handler(tag_handler("default"))
+                .default_handler_server(tag_handler("server"))
+                .handler(StatusCode::NOT_FOUND, tag_handler("not found"))
+                .new_transform(test::status_service(status).into_service())
+                .await
+                .unwrap()
+        };
+
+        for (status, tag) in [
+            (StatusCode::NOT_FOUND, Some("not found")),
+            (StatusCode::BAD_REQUEST, Some("default")),
+            (StatusCode::INTERNAL_SERVER_ERROR, Some("server")),
+            (StatusCode::OK, None),
+            (StatusCode::SEE_OTHER, None),
+        ] {
+            let mw = make_mw(status).await;
+            let res = test::call_service(&mw, TestRequest::default().to_srv_request()).await;
+            assert_eq!(
+                res.headers()
+                    .get(CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok()),
+                tag,
+                "{}",
+                status
+            );
+        }
+    }
 }
@@ -0,0 +1,250 @@
+//! For middleware documentation, see [`from_fn`].
+
+use std::{future::Future, marker::PhantomData, rc::Rc};
+
+use actix_service::{
+    boxed::{self, RcService},
+    Service, Transform,
+};
+use actix_utils::future::{ready, Ready};
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    body::MessageBody,
+    service::{ServiceRequest, ServiceResponse},
+    Error,
+};
+
+/// Wraps an async function to be used as a middleware.
+///
+/// The wrapped function receives the incoming request and a [`Next`] handle to the rest of the
+/// service chain. Code before `next.call(req).await` runs before routing and the handler; code
+/// after it runs once the response (or error) has been produced. Because all of the state lives
+/// inside one future, data can be carried from the "before" part to the "after" part with plain
+/// local variables.
+///
+/// Like other middleware, layers are composed in registration order: the last `.wrap()`ed layer
+/// sees the request first and the response last.
+///
+/// # Examples
+/// ```
+/// use std::time::Instant;
+///
+/// use actix_web::{
+///     body::MessageBody,
+///     dev::{ServiceRequest, ServiceResponse},
+///     http::header::{HeaderName, HeaderValue},
+///     middleware::{from_fn, Next},
+///     App, Error,
+/// };
+///
+/// async fn timing(
+///     req: ServiceRequest,
+///     next: Next<impl MessageBody>,
+/// ) -> Result<ServiceResponse<impl MessageBody>, Error> {
+///     // before: runs ahead of routing and the handler
+///     let start = Instant::now();
+///
+///     let mut res = next.call(req).await?;
+///
+///     // after: runs once the response has been produced
+///     let elapsed = format!("{}", start.elapsed().as_micros());
+///     res.headers_mut().insert(
+///         HeaderName::from_static("x-elapsed-micros"),
+///         HeaderValue::from_str(&elapsed).unwrap(),
+///     );
+///
+///     Ok(res)
+/// }
+///
+/// let app = App::new().wrap(from_fn(timing));
+/// ```
+pub fn from_fn<F>(mw_fn: F) -> MiddlewareFn<F> {
+    MiddlewareFn {
+        mw_fn: Rc::new(mw_fn),
+    }
+}
+
+/// Middleware transform for [`from_fn`].
+pub struct MiddlewareFn<F> {
+    mw_fn: Rc<F>,
+}
+
+impl<S, F, Fut, B, B2> Transform<S, ServiceRequest> for MiddlewareFn<F>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    F: Fn(ServiceRequest, Next<B>) -> Fut + 'static,
+    Fut: Future<Output = Result<ServiceResponse<B2>, Error>>,
+    B2: MessageBody,
+{
+    type Response = ServiceResponse<B2>;
+    type Error = Error;
+    type Transform = MiddlewareFnService<F, B>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MiddlewareFnService {
+            service: boxed::rc_service(service),
+            mw_fn: Rc::clone(&self.mw_fn),
+            _phantom: PhantomData,
+        }))
+    }
+}
+
+/// Middleware service for [`from_fn`].
+pub struct MiddlewareFnService<F, B> {
+    service: RcService<ServiceRequest, ServiceResponse<B>, Error>,
+    mw_fn: Rc<F>,
+    _phantom: PhantomData<B>,
+}
+
+impl<F, Fut, B, B2> Service<ServiceRequest> for MiddlewareFnService<F, B>
+where
+    F: Fn(ServiceRequest, Next<B>) -> Fut,
+    Fut: Future<Output = Result<ServiceResponse<B2>, Error>>,
+    B2: MessageBody,
+{
+    type Response = ServiceResponse<B2>;
+    type Error = Error;
+    type Future = Fut;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        (self.mw_fn)(
+            req,
+            Next::<B> {
+                service: Rc::clone(&self.service),
+            },
+        )
+    }
+}
+
+/// Handle to the remainder of the service chain, passed to [`from_fn`] middleware.
+pub struct Next<B> {
+    service: RcService<ServiceRequest, ServiceResponse<B>, Error>,
+}
+
+impl<B> Next<B> {
+    /// Calls the inner service (further middleware, routing and the handler) with the request.
+    pub fn call(
+        &self,
+        req: ServiceRequest,
+    ) -> LocalBoxFuture<'static, Result<ServiceResponse<B>, Error>> {
+        self.service.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::header::{self, HeaderValue},
+        middleware::{Compat, Logger},
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    async fn noop<B>(req: ServiceRequest, next: Next<B>) -> Result<ServiceResponse<B>, Error> {
+        next.call(req).await
+    }
+
+    async fn add_res_header<B>(
+        req: ServiceRequest,
+        next: Next<B>,
+    ) -> Result<ServiceResponse<B>, Error> {
+        let mut res = next.call(req).await?;
+        res.headers_mut()
+            .insert(header::WARNING, HeaderValue::from_static("42"));
+        Ok(res)
+    }
+
+    async fn mutate_body_type(
+        req: ServiceRequest,
+        next: Next<impl MessageBody + 'static>,
+    ) -> Result<ServiceResponse<impl MessageBody>, Error> {
+        let res = next.call(req).await?;
+        Ok(res.map_into_left_body::<()>())
+    }
+
+    #[actix_rt::test]
+    async fn compat_compat() {
+        let _ = App::new().wrap(Compat::new(from_fn(noop)));
+        let _ = App::new().wrap(Compat::new(from_fn(mutate_body_type)));
+    }
+
+    #[actix_rt::test]
+    async fn permits_different_in_and_out_body_types() {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(mutate_body_type))
+                .wrap(from_fn(add_res_header))
+                .wrap(Logger::default())
+                .wrap(from_fn(noop))
+                .default_service(web::to(HttpResponse::NotFound)),
+        )
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.headers().contains_key(header::WARNING));
+    }
+
+    #[actix_rt::test]
+    async fn runs_in_registration_order() {
+        async fn push<B>(
+            tag: &'static str,
+            req: ServiceRequest,
+            next: Next<B>,
+        ) -> Result<ServiceResponse<B>, Error> {
+            let mut res = next.call(req).await?;
+            res.headers_mut()
+                .append(header::VIA, HeaderValue::from_static(tag));
+            Ok(res)
+        }
+
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(|req: ServiceRequest, next: Next<_>| {
+                    push("inner", req, next)
+                }))
+                .wrap(from_fn(|req: ServiceRequest, next: Next<_>| {
+                    push("outer", req, next)
+                }))
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = test::call_service(&app, req).await;
+
+        let via = res
+            .headers()
+            .get_all(header::VIA)
+            .map(|v| v.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(via, ["inner", "outer"]);
+    }
+
+    #[actix_rt::test]
+    async fn observes_handler_errors() {
+        async fn recover<B>(
+            req: ServiceRequest,
+            next: Next<B>,
+        ) -> Result<ServiceResponse<B>, Error> {
+            let res = next.call(req).await?;
+            assert!(res.response().error().is_some());
+            Ok(res)
+        }
+
+        let app = test::init_service(App::new().wrap(from_fn(recover)).default_service(
+            web::to(|| async { Err::<HttpResponse, _>(crate::error::ErrorBadRequest("nope")) }),
+        ))
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), crate::http::StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,472 @@
+//! For middleware documentation, see [`LoadShed`].
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use actix_service::{Service, Transform};
+use actix_utils::future::{ready, Ready};
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    body::EitherBody,
+    http::header::{self, HeaderValue},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpResponse,
+};
+
+/// Maximum number of latency samples kept.
+const MAX_SAMPLES: usize = 1024;
+
+/// Minimum number of latency samples before the latency threshold is applied.
+const MIN_SAMPLES: usize = 10;
+
+/// Time after which the cached latency percentile is recomputed.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Number of new samples after which the cached latency percentile is recomputed.
+const REFRESH_SAMPLES: usize = 16;
+
+/// How [`LoadShed`] rejects requests once a threshold is crossed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ShedStrategy {
+    /// Rejects every request arriving while a threshold is crossed.
+    RejectAll,
+
+    /// Rejects a random share of requests that grows with the load.
+    ///
+    /// With load at twice a threshold, half of the requests are rejected; at four times, three
+    /// quarters. This degrades more smoothly than [`RejectAll`](Self::RejectAll), but lets the
+    /// in-flight count exceed its limit.
+    Probabilistic,
+}
+
+/// Middleware for rejecting requests early when the service is overloaded.
+///
+/// Two measures of load can be limited:
+/// - the number of requests being handled at once, with [`max_in_flight`](Self::max_in_flight);
+/// - the 99th percentile latency of recently handled requests, with
+///   [`max_latency`](Self::max_latency). Latency is measured until the response is produced, so
+///   streaming bodies do not count.
+///
+/// When either is over its threshold, new requests are rejected according to the
+/// [strategy](Self::strategy) with `503 Service Unavailable`, before reaching the wrapped
+/// service. Rejecting requests that would only wait in a queue keeps latency low for the requests
+/// that are served, rather than letting all of them time out.
+///
+/// Load is tracked separately by each worker, and shared between clones of the middleware.
+/// Latency samples older than the [latency window](Self::latency_window) are discarded, so a
+/// service that sheds all of its requests recovers once the window has passed.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use actix_web::{middleware::{LoadShed, ShedStrategy}, web, App, HttpResponse};
+///
+/// let app = App::new()
+///     .wrap(
+///         LoadShed::new()
+///             .max_in_flight(512)
+///             .max_latency(Duration::from_millis(250))
+///             .strategy(ShedStrategy::Probabilistic)
+///             .retry_after(Duration::from_secs(5)),
+///     )
+///     .route("/", web::get().to(HttpResponse::Ok));
+/// ```
+#[derive(Debug, Clone)]
+pub struct LoadShed {
+    inner: Rc<Inner>,
+    state: Rc<State>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    max_in_flight: Option<usize>,
+    max_latency: Option<Duration>,
+    latency_window: Duration,
+    strategy: ShedStrategy,
+    retry_after: Option<Duration>,
+}
+
+impl Default for LoadShed {
+    fn default() -> Self {
+        LoadShed {
+            inner: Rc::new(Inner {
+                max_in_flight: None,
+                max_latency: None,
+                latency_window: Duration::from_secs(10),
+                strategy: ShedStrategy::RejectAll,
+                retry_after: None,
+            }),
+            state: Rc::new(State::default()),
+        }
+    }
+}
+
+impl LoadShed {
+    /// Constructs a middleware without thresholds, which never rejects requests.
+    pub fn new() -> Self {
+        LoadShed::default()
+    }
+
+    /// Sets the maximum number of requests handled at once.
+    ///
+    /// # Panics
+    /// Panics if `max` is zero, or if called after the middleware has been cloned.
+    pub fn max_in_flight(mut self, max: usize) -> Self {
+        assert!(max > 0, "in-flight limit must allow at least one request");
+        self.inner_mut().max_in_flight = Some(max);
+        self
+    }
+
+    /// Sets the maximum 99th percentile latency of recently handled requests.
+    ///
+    /// # Panics
+    /// Panics if `max` is zero, or if called after the middleware has been cloned.
+    pub fn max_latency(mut self, max: Duration) -> Self {
+        assert!(!max.is_zero(), "latency limit must be non-zero");
+        self.inner_mut().max_latency = Some(max);
+        self
+    }
+
+    /// Sets how long latency samples are kept. Defaults to 10 seconds.
+    ///
+    /// At most the latest 1024 samples are kept regardless of the window.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn latency_window(mut self, window: Duration) -> Self {
+        self.inner_mut().latency_window = window;
+        self
+    }
+
+    /// Sets how requests are rejected. Defaults to [`ShedStrategy::RejectAll`].
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn strategy(mut self, strategy: ShedStrategy) -> Self {
+        self.inner_mut().strategy = strategy;
+        self
+    }
+
+    /// Sets a `Retry-After` header on responses to rejected requests.
+    ///
+    /// The delay is sent in whole seconds, rounded up.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn retry_after(mut self, delay: Duration) -> Self {
+        self.inner_mut().retry_after = Some(delay);
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("LoadShed must be configured before cloning")
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for LoadShed
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = LoadShedMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(LoadShedMiddleware {
+            service,
+            inner: Rc::clone(&self.inner),
+            state: Rc::clone(&self.state),
+        }))
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    in_flight: Cell<usize>,
+    latencies: RefCell<Latencies>,
+}
+
+#[derive(Debug, Default)]
+struct Latencies {
+    /// Completion time and latency of recent requests, oldest first.
+    samples: VecDeque<(Instant, Duration)>,
+    p99: Option<Duration>,
+    refreshed: Option<Instant>,
+    /// Number of samples recorded since the last refresh.
+    pending: usize,
+}
+
+impl Latencies {
+    fn record(&mut self, now: Instant, latency: Duration) {
+        if self.samples.len() == MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back((now, latency));
+        self.pending += 1;
+    }
+
+    /// Returns the 99th percentile latency of samples within `window`.
+    fn p99(&mut self, now: Instant, window: Duration) -> Option<Duration> {
+        let fresh = self.pending < REFRESH_SAMPLES
+            && matches!(self.refreshed, Some(at) if now - at < REFRESH_INTERVAL);
+
+        if !fresh {
+            while matches!(self.samples.front(), Some((at, _)) if now - *at > window) {
+                self.samples.pop_front();
+            }
+
+            self.p99 = if self.samples.len() < MIN_SAMPLES {
+                None
+            } else {
+                let mut sorted = self.samples.iter().map(|(_, d)| *d).collect::<Vec<_>>();
+                sorted.sort_unstable();
+                Some(sorted[(sorted.len() - 1) * 99 / 100])
+            };
+
+            self.refreshed = Some(now);
+            self.pending = 0;
+        }
+
+        self.p99
+    }
+}
+
+/// Decrements the in-flight count when the request completes or is cancelled.
+struct InFlight(Rc<State>);
+
+impl InFlight {
+    fn new(state: Rc<State>) -> Self {
+        state.in_flight.set(state.in_flight.get() + 1);
+        InFlight(state)
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.in_flight.set(self.0.in_flight.get() - 1);
+    }
+}
+
+#[doc(hidden)]
+pub struct LoadShedMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+    state: Rc<State>,
+}
+
+impl<S> LoadShedMiddleware<S> {
+    /// Returns the largest ratio of observed load to its threshold; above 1 means overloaded.
+    fn load(&self, now: Instant) -> f64 {
+        let mut load = 0.0_f64;
+
+        if let Some(max) = self.inner.max_in_flight {
+            // count the request being decided on
+            let in_flight = self.state.in_flight.get() + 1;
+            load = load.max(in_flight as f64 / max as f64);
+        }
+
+        if let Some(max) = self.inner.max_latency {
+            let p99 = self
+                .state
+                .latencies
+                .borrow_mut()
+                .p99(now, self.inner.latency_window);
+
+            if let Some(p99) = p99 {
+                load = load.max(p99.as_secs_f64() / max.as_secs_f64());
+            }
+        }
+
+        load
+    }
+
+    fn should_shed(&self, now: Instant) -> bool {
+        let load = self.load(now);
+
+        if load <= 1.0 {
+            return false;
+        }
+
+        match self.inner.strategy {
+            ShedStrategy::RejectAll => true,
+            ShedStrategy::Probabilistic => rand::random::<f64>() < 1.0 - 1.0 / load,
+        }
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for LoadShedMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let start = Instant::now();
+
+        if self.should_shed(start) {
+            let mut res = HttpResponse::ServiceUnavailable();
+
+            if let Some(delay) = self.inner.retry_after {
+                let secs = super::retry_after_secs(delay);
+                res.insert_header((header::RETRY_AFTER, HeaderValue::from(secs)));
+            }
+
+            let res = req.into_response(res.finish()).map_into_right_body();
+            return Box::pin(ready(Ok(res)));
+        }
+
+        let guard = InFlight::new(Rc::clone(&self.state));
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await;
+
+            let now = Instant::now();
+            guard.0.latencies.borrow_mut().record(now, now - start);
+            drop(guard);
+
+            res.map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_service::IntoService;
+
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    #[test]
+    fn latency_percentile() {
+        let mut latencies = Latencies::default();
+        let start = Instant::now();
+        let window = Duration::from_secs(10);
+
+        for ms in 1..=9 {
+            latencies.record(start, Duration::from_millis(ms));
+        }
+        assert_eq!(latencies.p99(start, window), None);
+
+        latencies.record(start, Duration::from_millis(500));
+        let later = start + REFRESH_INTERVAL;
+        assert_eq!(latencies.p99(later, window), Some(Duration::from_millis(9)));
+
+        // cached until the refresh interval has passed or enough samples were recorded
+        for _ in 0..5 {
+            latencies.record(later, Duration::from_millis(500));
+        }
+        assert_eq!(latencies.p99(later, window), Some(Duration::from_millis(9)));
+        assert_eq!(
+            latencies.p99(later + REFRESH_INTERVAL, window),
+            Some(Duration::from_millis(500))
+        );
+
+        for _ in 0..REFRESH_SAMPLES {
+            latencies.record(later, Duration::from_millis(1));
+        }
+        assert_eq!(
+            latencies.p99(later + REFRESH_INTERVAL, window),
+            Some(Duration::from_millis(500))
+        );
+
+        // samples expire after the window
+        assert_eq!(latencies.p99(start + window * 2, window), None);
+        assert!(latencies.samples.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn sheds_excess_in_flight_requests() {
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let rx = RefCell::new(Some(rx));
+
+        let srv = move |req: ServiceRequest| {
+            let rx = rx.borrow_mut().take();
+            async move {
+                if let Some(rx) = rx {
+                    let _ = rx.await;
+                }
+                Ok(req.into_response(HttpResponse::Ok().finish()))
+            }
+        };
+
+        let mw = LoadShed::new()
+            .max_in_flight(1)
+            .retry_after(Duration::from_millis(100))
+            .new_transform(srv.into_service())
+            .await
+            .unwrap();
+
+        let blocked = mw.call(TestRequest::default().to_srv_request());
+
+        let res = mw
+            .call(TestRequest::default().to_srv_request())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "1");
+
+        tx.send(()).unwrap();
+        assert_eq!(blocked.await.unwrap().status(), StatusCode::OK);
+
+        let res = mw
+            .call(TestRequest::default().to_srv_request())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    async fn slow() -> HttpResponse {
+        actix_rt::time::sleep(Duration::from_millis(10)).await;
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_rt::test]
+    async fn sheds_on_high_latency() {
+        let app = test::init_service(
+            App::new()
+                .wrap(
+                    LoadShed::new()
+                        .max_latency(Duration::from_millis(5))
+                        .latency_window(Duration::from_millis(300)),
+                )
+                .route("/", web::get().to(slow)),
+        )
+        .await;
+
+        for _ in 0..MIN_SAMPLES {
+            let res = test::call_service(&app, TestRequest::default().to_request()).await;
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+
+        actix_rt::time::sleep(REFRESH_INTERVAL).await;
+        let res = test::call_service(&app, TestRequest::default().to_request()).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // recovers once the slow samples have left the window
+        actix_rt::time::sleep(Duration::from_millis(350)).await;
+        let res = test::call_service(&app, TestRequest::default().to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
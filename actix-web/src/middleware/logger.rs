@@ -68,6 +68,7 @@ use crate::{
 /// `%T` | Time taken to serve the request, in seconds to 6 decimal places
 /// `%D` | Time taken to serve the request, in milliseconds
 /// `%U` | Request URL
+/// `%v` | [Server name](crate::HttpServer::server_name)
 /// `%{r}a` | "Real IP" remote address **\***
 /// `%{FOO}i` |  `request.headers["FOO"]`
 /// `%{FOO}o` | `response.headers["FOO"]`
@@ -399,7 +400,7 @@ impl Format {
     /// Returns `None` if the format string syntax is incorrect.
     pub fn new(s: &str) -> Format {
         log::trace!("Access log format: {}", s);
-        let fmt = Regex::new(r"%(\{([A-Za-z0-9\-_]+)\}([aioe]|xi)|[%atPrUsbTD]?)").unwrap();
+        let fmt = Regex::new(r"%(\{([A-Za-z0-9\-_]+)\}([aioe]|xi)|[%atPrUsbTDv]?)").unwrap();
 
         let mut idx = 0;
         let mut results = Vec::new();
@@ -442,6 +443,7 @@ impl Format {
                     "U" => FormatText::UrlPath,
                     "T" => FormatText::Time,
                     "D" => FormatText::TimeMillis,
+                    "v" => FormatText::ServerName,
                     _ => FormatText::Str(m.as_str().to_owned()),
                 });
             }
@@ -471,6 +473,7 @@ enum FormatText {
     RemoteAddr,
     RealIpRemoteAddr,
     UrlPath,
+    ServerName,
     RequestHeader(HeaderName),
     ResponseHeader(HeaderName),
     EnvironHeader(String),
@@ -568,6 +571,10 @@ impl FormatText {
                 };
             }
             FormatText::UrlPath => *self = FormatText::Str(req.path().to_string()),
+            FormatText::ServerName => {
+                let name = req.app_config().server_name().unwrap_or("-");
+                *self = FormatText::Str(name.to_owned());
+            }
             FormatText::RequestTime => *self = FormatText::Str(now.format(&Rfc3339).unwrap()),
             FormatText::RequestHeader(ref name) => {
                 let s = if let Some(val) = req.headers().get(name) {
@@ -831,6 +838,30 @@ mod tests {
         assert!(s.contains("192.0.2.60"));
     }
 
+    #[actix_rt::test]
+    async fn test_server_name_format() {
+        let render = |req: ServiceRequest| {
+            let mut format = Format::new("%v");
+
+            let now = OffsetDateTime::now_utc();
+            for unit in &mut format.0 {
+                unit.render_request(now, &req);
+            }
+
+            let render = |fmt: &mut fmt::Formatter<'_>| {
+                for unit in &format.0 {
+                    unit.render(fmt, 1024, now)?;
+                }
+                Ok(())
+            };
+            format!("{}", FormatDisplay(&render))
+        };
+
+        let req = TestRequest::default().server_name("my-app/1.0");
+        assert_eq!(render(req.to_srv_request()), "my-app/1.0");
+        assert_eq!(render(TestRequest::default().to_srv_request()), "-");
+    }
+
     #[actix_rt::test]
     async fn test_custom_closure_log() {
         let mut logger = Logger::new("test %{CUSTOM}xi")
@@ -0,0 +1,627 @@
+//! For middleware documentation, see [`Metrics`].
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use actix_http::ws;
+use actix_utils::future::{ready, Ready};
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use crate::{
+    dev::{Service, Transform},
+    http::{Method, StatusCode},
+    service::{ServiceRequest, ServiceResponse},
+    web, Error, HttpResponse, Resource,
+};
+
+/// Default latency histogram buckets, in seconds.
+const DEFAULT_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Endpoint label used for requests that did not match any resource pattern.
+const UNMATCHED_ENDPOINT: &str = "<unmatched>";
+
+/// Middleware for collecting request metrics, exported in the Prometheus text format.
+///
+/// The following metrics are recorded:
+/// - `http_requests_total`: counter of completed requests;
+/// - `http_requests_in_flight`: gauge of requests currently being processed;
+/// - `http_request_duration_seconds`: histogram of the time taken to produce a response.
+///
+/// WebSocket connections can also be tracked, see [`websocket_stats`](Self::websocket_stats).
+///
+/// Requests are labeled by `endpoint` (the matched resource pattern, such as `/user/{id}`, to keep
+/// the number of series bounded), `method`, and `status`. Requests which do not match a resource
+/// are labeled with the endpoint `<unmatched>`, and requests with non-standard methods with the
+/// method `OTHER`.
+///
+/// Durations are measured until the response head is ready; time spent streaming the body to the
+/// client is not included.
+///
+/// A `Metrics` instance is a cheap handle to shared, thread-safe storage. Construct it once,
+/// outside the `HttpServer::new` closure, and clone it into each worker's `App` so that all
+/// workers record into the same registry. The collected metrics can be served with
+/// [`exporter`](Self::exporter) or rendered manually with [`render`](Self::render).
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware::Metrics, web, App, HttpResponse, HttpServer};
+///
+/// # fn run() -> std::io::Result<actix_web::dev::Server> {
+/// let metrics = Metrics::new();
+///
+/// let srv = HttpServer::new(move || {
+///     App::new()
+///         .wrap(metrics.clone())
+///         .service(metrics.exporter("/metrics"))
+///         .route("/user/{id}", web::get().to(HttpResponse::Ok))
+/// })
+/// .bind(("127.0.0.1", 8080))?
+/// .run();
+/// # Ok(srv)
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    buckets: Vec<f64>,
+    in_flight: AtomicI64,
+    series: Mutex<BTreeMap<SeriesKey, Series>>,
+
+    /// Aggregate of the WebSocket connections tracked with `websocket_stats`.
+    websocket: ws::Stats,
+    websocket_connections: AtomicU64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct SeriesKey {
+    endpoint: String,
+    method: &'static str,
+    status: u16,
+}
+
+#[derive(Debug)]
+struct Series {
+    count: u64,
+    sum: f64,
+
+    /// Non-cumulative observation counts, one per configured bucket.
+    buckets: Vec<u64>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            inner: Arc::new(Inner {
+                buckets: DEFAULT_BUCKETS.to_vec(),
+                in_flight: AtomicI64::new(0),
+                series: Mutex::new(BTreeMap::new()),
+                websocket: ws::Stats::new(),
+                websocket_connections: AtomicU64::new(0),
+            }),
+        }
+    }
+}
+
+impl Metrics {
+    /// Constructs a new, empty metrics registry with default latency buckets.
+    pub fn new() -> Self {
+        Metrics::default()
+    }
+
+    /// Sets the upper bounds, in seconds, of the latency histogram buckets.
+    ///
+    /// The `+Inf` bucket is always added.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned, or if the bounds are not finite and
+    /// strictly increasing.
+    pub fn buckets(mut self, buckets: impl Into<Vec<f64>>) -> Self {
+        let buckets = buckets.into();
+
+        assert!(
+            buckets.iter().all(|b| b.is_finite()) && buckets.windows(2).all(|w| w[0] < w[1]),
+            "histogram buckets must be finite and strictly increasing"
+        );
+
+        Arc::get_mut(&mut self.inner)
+            .expect("Metrics must be configured before it is cloned.")
+            .buckets = buckets;
+
+        self
+    }
+
+    /// Renders all collected metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let inner = &self.inner;
+        let series = inner.series.lock().unwrap();
+
+        let mut out = String::new();
+
+        out.push_str("# HELP http_requests_total Total number of HTTP requests.\n");
+        out.push_str("# TYPE http_requests_total counter\n");
+        for (key, series) in series.iter() {
+            let _ = writeln!(
+                out,
+                "http_requests_total{{{}}} {}",
+                key.labels(),
+                series.count
+            );
+        }
+
+        out.push_str(
+            "# HELP http_requests_in_flight Number of HTTP requests being processed.\n",
+        );
+        out.push_str("# TYPE http_requests_in_flight gauge\n");
+        let _ = writeln!(
+            out,
+            "http_requests_in_flight {}",
+            inner.in_flight.load(Ordering::Relaxed)
+        );
+
+        out.push_str(
+            "# HELP http_request_duration_seconds Time taken to produce HTTP responses.\n",
+        );
+        out.push_str("# TYPE http_request_duration_seconds histogram\n");
+        for (key, series) in series.iter() {
+            let labels = key.labels();
+            let mut cumulative = 0;
+
+            for (bound, count) in inner.buckets.iter().zip(&series.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, cumulative
+                );
+            }
+
+            let _ = writeln!(
+                out,
+                "http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, series.count
+            );
+            let _ = writeln!(
+                out,
+                "http_request_duration_seconds_sum{{{}}} {}",
+                labels, series.sum
+            );
+            let _ = writeln!(
+                out,
+                "http_request_duration_seconds_count{{{}}} {}",
+                labels, series.count
+            );
+        }
+
+        if inner.websocket_connections.load(Ordering::Relaxed) > 0 {
+            render_websocket(&mut out, inner);
+        }
+
+        out
+    }
+
+    /// Returns a handle counting the frames of a WebSocket connection, whose counts are added to
+    /// the rendered metrics.
+    ///
+    /// Attach a new handle to the codec of each connection with [`Codec::with_stats`]. The
+    /// following metrics are then recorded, across all connections:
+    /// - `websocket_connections_total`: counter of tracked connections;
+    /// - `websocket_frames_sent_total` and `websocket_frames_received_total`: counters of frames,
+    ///   labeled by `opcode`;
+    /// - `websocket_bytes_sent_total` and `websocket_bytes_received_total`: counters of bytes;
+    /// - `websocket_last_pong_latency_seconds`: gauge of the latency of the last pong received.
+    ///
+    /// The returned handle also exposes the counters of its own connection.
+    ///
+    /// ```
+    /// use actix_http::ws::Codec;
+    /// use actix_web::middleware::Metrics;
+    ///
+    /// let metrics = Metrics::new();
+    ///
+    /// // when accepting a connection
+    /// let codec = Codec::new().with_stats(metrics.websocket_stats());
+    /// # drop(codec);
+    /// ```
+    ///
+    /// [`Codec::with_stats`]: actix_http::ws::Codec::with_stats
+    pub fn websocket_stats(&self) -> ws::Stats {
+        self.inner
+            .websocket_connections
+            .fetch_add(1, Ordering::Relaxed);
+        self.inner.websocket.child()
+    }
+
+    /// Returns a resource that serves the collected metrics on `GET` requests to `path`.
+    pub fn exporter(&self, path: &str) -> Resource {
+        let metrics = self.clone();
+
+        web::resource(path).route(web::get().to(move || {
+            ready(
+                HttpResponse::Ok()
+                    .content_type("text/plain; version=0.0.4; charset=utf-8")
+                    .body(metrics.render()),
+            )
+        }))
+    }
+}
+
+/// Opcodes of counted WebSocket frames, with their label values.
+const WEBSOCKET_OPCODES: &[(ws::OpCode, &str)] = &[
+    (ws::OpCode::Continue, "continuation"),
+    (ws::OpCode::Text, "text"),
+    (ws::OpCode::Binary, "binary"),
+    (ws::OpCode::Close, "close"),
+    (ws::OpCode::Ping, "ping"),
+    (ws::OpCode::Pong, "pong"),
+];
+
+fn render_websocket(out: &mut String, inner: &Inner) {
+    let stats = &inner.websocket;
+
+    out.push_str("# HELP websocket_connections_total Total number of WebSocket connections.\n");
+    out.push_str("# TYPE websocket_connections_total counter\n");
+    let _ = writeln!(
+        out,
+        "websocket_connections_total {}",
+        inner.websocket_connections.load(Ordering::Relaxed)
+    );
+
+    out.push_str("# HELP websocket_frames_sent_total Total number of WebSocket frames sent.\n");
+    out.push_str("# TYPE websocket_frames_sent_total counter\n");
+    for (opcode, label) in WEBSOCKET_OPCODES {
+        let _ = writeln!(
+            out,
+            "websocket_frames_sent_total{{opcode=\"{}\"}} {}",
+            label,
+            stats.frames_sent(*opcode)
+        );
+    }
+
+    out.push_str(
+        "# HELP websocket_frames_received_total Total number of WebSocket frames received.\n",
+    );
+    out.push_str("# TYPE websocket_frames_received_total counter\n");
+    for (opcode, label) in WEBSOCKET_OPCODES {
+        let _ = writeln!(
+            out,
+            "websocket_frames_received_total{{opcode=\"{}\"}} {}",
+            label,
+            stats.frames_received(*opcode)
+        );
+    }
+
+    out.push_str("# HELP websocket_bytes_sent_total Total number of WebSocket bytes sent.\n");
+    out.push_str("# TYPE websocket_bytes_sent_total counter\n");
+    let _ = writeln!(out, "websocket_bytes_sent_total {}", stats.bytes_sent());
+
+    out.push_str(
+        "# HELP websocket_bytes_received_total Total number of WebSocket bytes received.\n",
+    );
+    out.push_str("# TYPE websocket_bytes_received_total counter\n");
+    let _ = writeln!(
+        out,
+        "websocket_bytes_received_total {}",
+        stats.bytes_received()
+    );
+
+    if let Some(latency) = stats.last_pong_latency() {
+        out.push_str(
+            "# HELP websocket_last_pong_latency_seconds Latency of the last WebSocket pong received.\n",
+        );
+        out.push_str("# TYPE websocket_last_pong_latency_seconds gauge\n");
+        let _ = writeln!(
+            out,
+            "websocket_last_pong_latency_seconds {}",
+            latency.as_secs_f64()
+        );
+    }
+}
+
+impl Inner {
+    fn record(&self, endpoint: String, method: &Method, status: StatusCode, elapsed: f64) {
+        let key = SeriesKey {
+            endpoint,
+            method: method_label(method),
+            status: status.as_u16(),
+        };
+
+        let mut series = self.series.lock().unwrap();
+        let series = series.entry(key).or_insert_with(|| Series {
+            count: 0,
+            sum: 0.0,
+            buckets: vec![0; self.buckets.len()],
+        });
+
+        series.count += 1;
+        series.sum += elapsed;
+
+        if let Some(idx) = self.buckets.iter().position(|bound| elapsed <= *bound) {
+            series.buckets[idx] += 1;
+        }
+    }
+}
+
+impl SeriesKey {
+    fn labels(&self) -> String {
+        format!(
+            "endpoint=\"{}\",method=\"{}\",status=\"{}\"",
+            escape_label(&self.endpoint),
+            self.method,
+            self.status
+        )
+    }
+}
+
+/// Returns the label of a method; non-standard methods share one so clients cannot create series.
+fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::HEAD => "HEAD",
+        Method::OPTIONS => "OPTIONS",
+        Method::CONNECT => "CONNECT",
+        Method::PATCH => "PATCH",
+        Method::TRACE => "TRACE",
+        _ => "OTHER",
+    }
+}
+
+/// Escapes a label value for the Prometheus text format.
+fn escape_label(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for ch in value.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            ch => escaped.push(ch),
+        }
+    }
+
+    escaped
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Metrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = MetricsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MetricsMiddleware {
+            service,
+            inner: Arc::clone(&self.inner),
+        }))
+    }
+}
+
+#[doc(hidden)]
+pub struct MetricsMiddleware<S> {
+    service: S,
+    inner: Arc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for MetricsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = MetricsFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let method = req.method().clone();
+
+        MetricsFuture {
+            in_flight: InFlight::new(Arc::clone(&self.inner)),
+            fut: self.service.call(req),
+            method,
+            start: Instant::now(),
+            _body: PhantomData,
+        }
+    }
+}
+
+/// Tracks a request in the in-flight gauge until dropped.
+struct InFlight(Arc<Inner>);
+
+impl InFlight {
+    fn new(inner: Arc<Inner>) -> Self {
+        inner.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlight(inner)
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pin_project! {
+    #[doc(hidden)]
+    pub struct MetricsFuture<S: Service<ServiceRequest>, B> {
+        #[pin]
+        fut: S::Future,
+        in_flight: InFlight,
+        method: Method,
+        start: Instant,
+        _body: PhantomData<B>,
+    }
+}
+
+impl<S, B> Future for MetricsFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = <S::Future as Future>::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.fut.poll(cx));
+
+        let elapsed = this.start.elapsed().as_secs_f64();
+
+        let (endpoint, status) = match res {
+            Ok(ref res) => (res.request().match_pattern(), res.status()),
+            Err(ref err) => (None, err.as_response_error().status_code()),
+        };
+
+        let endpoint = endpoint.unwrap_or_else(|| UNMATCHED_ENDPOINT.to_owned());
+        this.in_flight
+            .0
+            .record(endpoint, this.method, status, elapsed);
+
+        Poll::Ready(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test::{self, TestRequest},
+        App,
+    };
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!(escape_label(r#"/a"b\c"#), r#"/a\"b\\c"#);
+        assert_eq!(escape_label("a\nb"), "a\\nb");
+    }
+
+    #[actix_rt::test]
+    async fn records_and_exports() {
+        let metrics = Metrics::new().buckets([0.5, 60.0]);
+
+        let app = test::init_service(
+            App::new()
+                .wrap(metrics.clone())
+                .service(metrics.exporter("/metrics"))
+                .route("/user/{id}", web::get().to(HttpResponse::Ok))
+                .route("/fail", web::post().to(HttpResponse::BadRequest)),
+        )
+        .await;
+
+        for path in ["/user/1", "/user/2", "/nope"] {
+            let req = TestRequest::get().uri(path).to_request();
+            test::call_service(&app, req).await;
+        }
+
+        let req = TestRequest::post().uri("/fail").to_request();
+        test::call_service(&app, req).await;
+
+        for method in ["PURGE", "X-RANDOM"] {
+            let req = TestRequest::default()
+                .method(Method::from_bytes(method.as_bytes()).unwrap())
+                .uri("/nope")
+                .to_request();
+            test::call_service(&app, req).await;
+        }
+
+        let req = TestRequest::get().uri("/metrics").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let body = test::read_body(res).await;
+        let body = std::str::from_utf8(&body).unwrap();
+
+        assert!(body.contains(
+            "http_requests_total{endpoint=\"/user/{id}\",method=\"GET\",status=\"200\"} 2\n"
+        ));
+        assert!(body.contains(
+            "http_requests_total{endpoint=\"<unmatched>\",method=\"GET\",status=\"404\"} 1\n"
+        ));
+        assert!(body.contains(
+            "http_requests_total{endpoint=\"/fail\",method=\"POST\",status=\"400\"} 1\n"
+        ));
+        assert!(body.contains(
+            "http_requests_total{endpoint=\"<unmatched>\",method=\"OTHER\",status=\"404\"} 2\n"
+        ));
+
+        // the exporter request itself is still in flight while rendering
+        assert!(body.contains("http_requests_in_flight 1\n"));
+
+        assert!(body.contains(
+            "http_request_duration_seconds_bucket\
+            {endpoint=\"/user/{id}\",method=\"GET\",status=\"200\",le=\"60\"} 2\n"
+        ));
+        assert!(body.contains(
+            "http_request_duration_seconds_bucket\
+            {endpoint=\"/user/{id}\",method=\"GET\",status=\"200\",le=\"+Inf\"} 2\n"
+        ));
+        assert!(body.contains(
+            "http_request_duration_seconds_count\
+            {endpoint=\"/user/{id}\",method=\"GET\",status=\"200\"} 2\n"
+        ));
+
+        assert_eq!(metrics.inner.in_flight.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn records_websocket_frames() {
+        use actix_codec::Encoder as _;
+        use bytes::BytesMut;
+
+        let metrics = Metrics::new();
+        assert!(!metrics.render().contains("websocket_"));
+
+        let stats = metrics.websocket_stats();
+        let mut codec = ws::Codec::new().with_stats(stats.clone());
+        let mut buf = BytesMut::new();
+        codec
+            .encode(ws::Message::Text("a".into()), &mut buf)
+            .unwrap();
+        codec
+            .encode(ws::Message::Text("b".into()), &mut buf)
+            .unwrap();
+
+        let mut codec = ws::Codec::new().with_stats(metrics.websocket_stats());
+        codec
+            .encode(ws::Message::Ping("".into()), &mut buf)
+            .unwrap();
+
+        assert_eq!(stats.frames_sent(ws::OpCode::Text), 2);
+
+        let body = metrics.render();
+        assert!(body.contains("websocket_connections_total 2\n"));
+        assert!(body.contains("websocket_frames_sent_total{opcode=\"text\"} 2\n"));
+        assert!(body.contains("websocket_frames_sent_total{opcode=\"ping\"} 1\n"));
+        assert!(body.contains("websocket_frames_received_total{opcode=\"text\"} 0\n"));
+        assert!(body.contains(&format!("websocket_bytes_sent_total {}\n", buf.len())));
+        assert!(!body.contains("websocket_last_pong_latency_seconds"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn unordered_buckets() {
+        let _ = Metrics::new().buckets([1.0, 0.5]);
+    }
+}
@@ -1,22 +1,54 @@
 //! A collection of common middleware.
 
+mod authentication;
+mod cache;
 mod compat;
 mod condition;
 mod default_headers;
 mod err_handlers;
+mod from_fn;
+mod load_shed;
 mod logger;
+mod metrics;
 #[cfg(test)]
 mod noop;
 mod normalize;
+mod problem_json;
+mod rate_limit;
+mod redirect_https;
+mod request_id;
+mod security_headers;
+mod throttle;
+mod timeout;
 
+pub use self::authentication::HttpAuthentication;
+pub use self::cache::{CacheStore, ResponseCache};
 pub use self::compat::Compat;
 pub use self::condition::Condition;
 pub use self::default_headers::DefaultHeaders;
 pub use self::err_handlers::{ErrorHandlerResponse, ErrorHandlers};
+pub use self::from_fn::{from_fn, MiddlewareFn, MiddlewareFnService, Next};
+pub use self::load_shed::{LoadShed, ShedStrategy};
 pub use self::logger::Logger;
+pub use self::metrics::Metrics;
 #[cfg(test)]
 pub(crate) use self::noop::Noop;
-pub use self::normalize::{NormalizePath, TrailingSlash};
+pub use self::normalize::{NormalizePath, NormalizePathRedirect, TrailingSlash};
+pub use self::problem_json::ProblemJson;
+pub use self::rate_limit::{
+    MemoryBackend, Quota, RateLimitBackend, RateLimitDecision, RateLimiter,
+};
+pub use self::redirect_https::RedirectHttps;
+pub use self::request_id::{RequestId, RequestIdValue};
+pub use self::security_headers::SecurityHeaders;
+pub use self::throttle::{Throttle, ThrottledBody};
+pub use self::timeout::Timeout;
+
+#[cfg(feature = "cookies")]
+mod csrf;
+
+#[cfg(feature = "cookies")]
+pub use self::csrf::{Csrf, CsrfToken};
 
 #[cfg(feature = "__compress")]
 mod compress;
@@ -24,12 +56,39 @@ mod compress;
 #[cfg(feature = "__compress")]
 pub use self::compress::Compress;
 
+#[cfg(feature = "tracing")]
+mod trace;
+
+#[cfg(feature = "tracing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
+pub use self::trace::{TraceContext, Tracing};
+
+#[cfg(all(feature = "tracing", feature = "awc"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "tracing", feature = "awc"))))]
+pub use self::trace::TracePropagation;
+
+/// Returns the `Retry-After` value for `delay` in seconds.
+///
+/// Rounds up so clients never retry too early.
+pub(crate) fn retry_after_secs(delay: std::time::Duration) -> u64 {
+    delay.as_secs() + u64::from(delay.subsec_nanos() > 0)
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use crate::{http::StatusCode, App};
 
     use super::*;
 
+    #[test]
+    fn retry_after_rounds_up() {
+        assert_eq!(retry_after_secs(Duration::ZERO), 0);
+        assert_eq!(retry_after_secs(Duration::from_secs(2)), 2);
+        assert_eq!(retry_after_secs(Duration::from_millis(2001)), 3);
+    }
+
     #[test]
     fn common_combinations() {
         // ensure there's no reason that the built-in middleware cannot compose
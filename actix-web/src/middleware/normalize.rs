@@ -1,14 +1,25 @@
 //! For middleware documentation, see [`NormalizePath`].
 
+use std::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
 use actix_http::uri::{PathAndQuery, Uri};
 use actix_service::{Service, Transform};
-use actix_utils::future::{ready, Ready};
+use actix_utils::future::{ok, ready, Either, Ready};
 use bytes::Bytes;
+use futures_core::ready;
+use pin_project_lite::pin_project;
 use regex::Regex;
 
 use crate::{
+    body::MessageBody,
+    http::{header, StatusCode},
     service::{ServiceRequest, ServiceResponse},
-    Error,
+    Error, HttpResponse,
 };
 
 /// Determines the behavior of the [`NormalizePath`] middleware.
@@ -47,6 +58,12 @@ impl Default for TrailingSlash {
 ///   slashes as-is, depending on which [`TrailingSlash`] variant is supplied
 ///   to [`new`](NormalizePath::new()).
 ///
+/// # Redirects
+/// By default, the request is rewritten in place and passed on to the wrapped service. Calling
+/// [`use_redirects`](NormalizePath::use_redirects()) instead makes the middleware respond with a
+/// redirect to the normalized path (keeping any query string) whenever normalization would change
+/// the path, so that clients and search engines learn the canonical URL.
+///
 /// # Default Behavior
 /// The default constructor chooses to strip trailing slashes from the end of paths with them
 /// ([`TrailingSlash::Trim`]). The implication is that route definitions should be defined without
@@ -111,6 +128,39 @@ impl NormalizePath {
     pub fn trim() -> Self {
         Self::new(TrailingSlash::Trim)
     }
+
+    /// Respond with a `308 Permanent Redirect` to the normalized path instead of rewriting the
+    /// request in place.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{middleware::NormalizePath, App};
+    ///
+    /// let app = App::new().wrap(NormalizePath::trim().use_redirects());
+    /// ```
+    pub fn use_redirects(self) -> NormalizePathRedirect {
+        self.use_redirects_with_status(StatusCode::PERMANENT_REDIRECT)
+    }
+
+    /// Respond with a redirect to the normalized path, using the given status code, instead of
+    /// rewriting the request in place.
+    ///
+    /// `308 Permanent Redirect` preserves the request method and body; `301 Moved Permanently` is
+    /// more widely understood by older clients but allows them to change the method to `GET`.
+    ///
+    /// # Panics
+    /// Panics if `status` is not a redirection (3xx) status code.
+    pub fn use_redirects_with_status(self, status: StatusCode) -> NormalizePathRedirect {
+        assert!(
+            status.is_redirection(),
+            "NormalizePath redirect status must be 3xx"
+        );
+
+        NormalizePathRedirect {
+            trailing_slash_behavior: self.0,
+            status,
+        }
+    }
 }
 
 impl<S, B> Transform<S, ServiceRequest> for NormalizePath
@@ -153,56 +203,173 @@ where
     fn call(&self, mut req: ServiceRequest) -> Self::Future {
         let head = req.head_mut();
 
-        let original_path = head.uri.path();
-
-        // An empty path here means that the URI has no valid path. We skip normalization in this
-        // case, because adding a path can make the URI invalid
-        if !original_path.is_empty() {
-            // Either adds a string to the end (duplicates will be removed anyways) or trims all
-            // slashes from the end
-            let path = match self.trailing_slash_behavior {
-                TrailingSlash::Always => format!("{}/", original_path),
-                TrailingSlash::MergeOnly => original_path.to_string(),
-                TrailingSlash::Trim => original_path.trim_end_matches('/').to_string(),
-            };
-
-            // normalize multiple /'s to one /
-            let path = self.merge_slash.replace_all(&path, "/");
-
-            // Ensure root paths are still resolvable. If resulting path is blank after previous
-            // step it means the path was one or more slashes. Reduce to single slash.
-            let path = if path.is_empty() { "/" } else { path.as_ref() };
-
-            // Check whether the path has been changed
-            //
-            // This check was previously implemented as string length comparison
-            //
-            // That approach fails when a trailing slash is added,
-            // and a duplicate slash is removed,
-            // since the length of the strings remains the same
-            //
-            // For example, the path "/v1//s" will be normalized to "/v1/s/"
-            // Both of the paths have the same length,
-            // so the change can not be deduced from the length comparison
-            if path != original_path {
-                let mut parts = head.uri.clone().into_parts();
-                let query = parts.path_and_query.as_ref().and_then(|pq| pq.query());
-
-                let path = match query {
-                    Some(q) => Bytes::from(format!("{}?{}", path, q)),
-                    None => Bytes::copy_from_slice(path.as_bytes()),
-                };
-                parts.path_and_query = Some(PathAndQuery::from_maybe_shared(path).unwrap());
-
-                let uri = Uri::from_parts(parts).unwrap();
-                req.match_info_mut().get_mut().update(&uri);
-                req.head_mut().uri = uri;
-            }
+        if let Some(path) =
+            normalized_path(&self.merge_slash, self.trailing_slash_behavior, &head.uri)
+        {
+            let mut parts = head.uri.clone().into_parts();
+            parts.path_and_query = Some(PathAndQuery::from_maybe_shared(path).unwrap());
+
+            let uri = Uri::from_parts(parts).unwrap();
+            req.match_info_mut().get_mut().update(&uri);
+            req.head_mut().uri = uri;
         }
+
         self.service.call(req)
     }
 }
 
+/// Middleware for redirecting requests to their normalized path.
+///
+/// Constructed with [`NormalizePath::use_redirects`] or
+/// [`NormalizePath::use_redirects_with_status`]. Requests whose path is already normalized are
+/// passed on to the wrapped service; since redirects have their own body, response bodies are
+/// [boxed](crate::body::BoxBody).
+#[derive(Debug, Clone, Copy)]
+pub struct NormalizePathRedirect {
+    trailing_slash_behavior: TrailingSlash,
+    status: StatusCode,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for NormalizePathRedirect
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Transform = NormalizePathRedirectMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(NormalizePathRedirectMiddleware {
+            service,
+            merge_slash: Regex::new("//+").unwrap(),
+            trailing_slash_behavior: self.trailing_slash_behavior,
+            status: self.status,
+        }))
+    }
+}
+
+#[doc(hidden)]
+pub struct NormalizePathRedirectMiddleware<S> {
+    service: S,
+    merge_slash: Regex,
+    trailing_slash_behavior: TrailingSlash,
+    status: StatusCode,
+}
+
+impl<S, B> Service<ServiceRequest> for NormalizePathRedirectMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    #[allow(clippy::type_complexity)]
+    type Future = Either<NormalizePathFuture<S, B>, Ready<Result<Self::Response, Self::Error>>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        match normalized_path(
+            &self.merge_slash,
+            self.trailing_slash_behavior,
+            &req.head().uri,
+        ) {
+            Some(path) => {
+                let res = HttpResponse::build(self.status)
+                    .insert_header((header::LOCATION, path))
+                    .finish();
+
+                Either::right(ok(req.into_response(res)))
+            }
+
+            None => Either::left(NormalizePathFuture {
+                fut: self.service.call(req),
+                _phantom: PhantomData,
+            }),
+        }
+    }
+}
+
+pin_project! {
+    #[doc(hidden)]
+    pub struct NormalizePathFuture<S, B>
+    where
+        S: Service<ServiceRequest>,
+    {
+        #[pin]
+        fut: S::Future,
+        _phantom: PhantomData<B>,
+    }
+}
+
+impl<S, B> Future for NormalizePathFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody + 'static,
+{
+    type Output = Result<ServiceResponse, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let res = ready!(self.project().fut.poll(cx))?;
+        Poll::Ready(Ok(res.map_into_boxed_body()))
+    }
+}
+
+/// Returns the normalized path and query of `uri`, if normalization changes its path.
+fn normalized_path(
+    merge_slash: &Regex,
+    trailing_slash_behavior: TrailingSlash,
+    uri: &Uri,
+) -> Option<Bytes> {
+    let original_path = uri.path();
+
+    // An empty path here means that the URI has no valid path. We skip normalization in this
+    // case, because adding a path can make the URI invalid
+    if original_path.is_empty() {
+        return None;
+    }
+
+    // Either adds a string to the end (duplicates will be removed anyways) or trims all
+    // slashes from the end
+    let path = match trailing_slash_behavior {
+        TrailingSlash::Always => format!("{}/", original_path),
+        TrailingSlash::MergeOnly => original_path.to_string(),
+        TrailingSlash::Trim => original_path.trim_end_matches('/').to_string(),
+    };
+
+    // normalize multiple /'s to one /
+    let path = merge_slash.replace_all(&path, "/");
+
+    // Ensure root paths are still resolvable. If resulting path is blank after previous
+    // step it means the path was one or more slashes. Reduce to single slash.
+    let path = if path.is_empty() { "/" } else { path.as_ref() };
+
+    // Check whether the path has been changed
+    //
+    // This check was previously implemented as string length comparison
+    //
+    // That approach fails when a trailing slash is added,
+    // and a duplicate slash is removed,
+    // since the length of the strings remains the same
+    //
+    // For example, the path "/v1//s" will be normalized to "/v1/s/"
+    // Both of the paths have the same length,
+    // so the change can not be deduced from the length comparison
+    if path == original_path {
+        return None;
+    }
+
+    Some(match uri.query() {
+        Some(q) => Bytes::from(format!("{}?{}", path, q)),
+        None => Bytes::copy_from_slice(path.as_bytes()),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use actix_http::StatusCode;
@@ -256,7 +423,7 @@ mod tests {
     async fn trim_trailing_slashes() {
         let app = init_service(
             App::new()
-                .wrap(NormalizePath(TrailingSlash::Trim))
+                .wrap(NormalizePath::new(TrailingSlash::Trim))
                 .service(web::resource("/").to(HttpResponse::Ok))
                 .service(web::resource("/v1/something").to(HttpResponse::Ok))
                 .service(
@@ -292,11 +459,13 @@ mod tests {
     #[actix_rt::test]
     async fn trim_root_trailing_slashes_with_query() {
         let app = init_service(
-            App::new().wrap(NormalizePath(TrailingSlash::Trim)).service(
-                web::resource("/")
-                    .guard(fn_guard(|ctx| ctx.head().uri.query() == Some("query=test")))
-                    .to(HttpResponse::Ok),
-            ),
+            App::new()
+                .wrap(NormalizePath::new(TrailingSlash::Trim))
+                .service(
+                    web::resource("/")
+                        .guard(fn_guard(|ctx| ctx.head().uri.query() == Some("query=test")))
+                        .to(HttpResponse::Ok),
+                ),
         )
         .await;
 
@@ -313,7 +482,7 @@ mod tests {
     async fn ensure_trailing_slash() {
         let app = init_service(
             App::new()
-                .wrap(NormalizePath(TrailingSlash::Always))
+                .wrap(NormalizePath::new(TrailingSlash::Always))
                 .service(web::resource("/").to(HttpResponse::Ok))
                 .service(web::resource("/v1/something/").to(HttpResponse::Ok))
                 .service(
@@ -350,7 +519,7 @@ mod tests {
     async fn ensure_root_trailing_slash_with_query() {
         let app = init_service(
             App::new()
-                .wrap(NormalizePath(TrailingSlash::Always))
+                .wrap(NormalizePath::new(TrailingSlash::Always))
                 .service(
                     web::resource("/")
                         .guard(fn_guard(|ctx| ctx.head().uri.query() == Some("query=test")))
@@ -372,7 +541,7 @@ mod tests {
     async fn keep_trailing_slash_unchanged() {
         let app = init_service(
             App::new()
-                .wrap(NormalizePath(TrailingSlash::MergeOnly))
+                .wrap(NormalizePath::new(TrailingSlash::MergeOnly))
                 .service(web::resource("/").to(HttpResponse::Ok))
                 .service(web::resource("/v1/something").to(HttpResponse::Ok))
                 .service(web::resource("/v1/").to(HttpResponse::Ok))
@@ -409,6 +578,76 @@ mod tests {
         }
     }
 
+    #[actix_rt::test]
+    async fn redirect_to_normalized_path() {
+        let app = init_service(
+            App::new()
+                .wrap(NormalizePath::trim().use_redirects())
+                .service(web::resource("/").to(HttpResponse::Ok))
+                .service(web::resource("/v1/something").to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let tests = vec![
+            ("//", "/"),
+            ("/v1/something/", "/v1/something"),
+            ("//v1//something", "/v1/something"),
+            ("/v1/something//?query=test", "/v1/something?query=test"),
+        ];
+
+        for (uri, location) in tests {
+            let req = TestRequest::with_uri(uri).to_request();
+            let res = call_service(&app, req).await;
+            assert_eq!(
+                res.status(),
+                StatusCode::PERMANENT_REDIRECT,
+                "Failed uri: {}",
+                uri
+            );
+            assert_eq!(
+                res.headers().get(header::LOCATION).unwrap(),
+                location,
+                "Failed uri: {}",
+                uri
+            );
+        }
+
+        // already normalized paths are passed through
+        for uri in ["/", "/v1/something", "/v1/something?query=test"] {
+            let req = TestRequest::with_uri(uri).to_request();
+            let res = call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::OK, "Failed uri: {}", uri);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn redirect_with_custom_status() {
+        let app = init_service(
+            App::new()
+                .wrap(
+                    NormalizePath::new(TrailingSlash::Always)
+                        .use_redirects_with_status(StatusCode::MOVED_PERMANENTLY),
+                )
+                .service(web::resource("/v1/").to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/v1").to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(res.headers().get(header::LOCATION).unwrap(), "/v1/");
+
+        let req = TestRequest::with_uri("/v1/").to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[test]
+    #[should_panic]
+    fn redirect_status_must_be_3xx() {
+        NormalizePath::trim().use_redirects_with_status(StatusCode::OK);
+    }
+
     #[actix_rt::test]
     async fn no_path() {
         let app = init_service(
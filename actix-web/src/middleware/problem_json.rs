@@ -0,0 +1,307 @@
+//! For middleware documentation, see [`ProblemJson`].
+
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use actix_service::{Service, Transform};
+use actix_utils::future::{ready, Ready};
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use crate::{
+    body::{BoxBody, EitherBody},
+    http::header::{Accept, Header as _},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpResponse, ResponseError,
+};
+
+/// Middleware for rendering error responses as [RFC 7807] problem details.
+///
+/// Responses caused by an [`Error`] are replaced with the error's
+/// [problem response](crate::ResponseError::problem_response), an `application/problem+json` body
+/// carrying the status code, title and detail, when the client accepts it. Errors returned by
+/// inner services, rather than rendered as responses, are wrapped so that they render as problem
+/// details too. Other responses are passed through unchanged.
+///
+/// A client accepts problem details when its most preferred media type in the `Accept` header is
+/// JSON (`application/json` or a `+json` type such as `application/problem+json`), `application/*`
+/// or `*/*`, or when it does not send an `Accept` header. Browsers, which prefer HTML, keep
+/// receiving the plain text error responses.
+///
+/// # Examples
+/// ```
+/// use actix_web::{error, middleware::ProblemJson, web, App};
+///
+/// let app = App::new()
+///     .wrap(ProblemJson::new())
+///     .route("/", web::get().to(|| async {
+///         Err::<&str, _>(error::ErrorNotFound("no such user"))
+///     }));
+/// ```
+///
+/// [RFC 7807]: https://datatracker.ietf.org/doc/html/rfc7807
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ProblemJson;
+
+impl ProblemJson {
+    /// Constructs a `ProblemJson` middleware.
+    pub fn new() -> Self {
+        ProblemJson
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ProblemJson
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = ProblemJsonMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ProblemJsonMiddleware { service }))
+    }
+}
+
+#[doc(hidden)]
+pub struct ProblemJsonMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for ProblemJsonMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = ProblemJsonFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        ProblemJsonFuture {
+            accepts_problem: accepts_problem(&req),
+            fut: self.service.call(req),
+            _body: PhantomData,
+        }
+    }
+}
+
+pin_project! {
+    #[doc(hidden)]
+    pub struct ProblemJsonFuture<S: Service<ServiceRequest>, B> {
+        #[pin]
+        fut: S::Future,
+        accepts_problem: bool,
+        _body: PhantomData<B>,
+    }
+}
+
+impl<S, B> Future for ProblemJsonFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = Result<ServiceResponse<EitherBody<B>>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.fut.poll(cx));
+
+        if !*this.accepts_problem {
+            return Poll::Ready(res.map(ServiceResponse::map_into_left_body));
+        }
+
+        let res = match res {
+            Ok(res) => match res.response().error().map(Error::problem_response) {
+                Some(problem) => res.map_body(|head, _| render(head, problem)),
+                None => res.map_into_left_body(),
+            },
+
+            // the request is gone, so the error is rendered wherever it ends up being handled
+            Err(err) => return Poll::Ready(Err(Problem(err).into())),
+        };
+
+        Poll::Ready(Ok(res))
+    }
+}
+
+/// Error whose response is the problem response of the wrapped error.
+struct Problem(Error);
+
+impl fmt::Debug for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl ResponseError for Problem {
+    fn status_code(&self) -> crate::http::StatusCode {
+        self.0.as_response_error().status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        self.0.problem_response()
+    }
+
+    fn problem_response(&self) -> HttpResponse {
+        self.0.problem_response()
+    }
+}
+
+/// Replaces a response with `problem`, keeping the response's headers that `problem` does not set.
+fn render<B>(head: &mut actix_http::ResponseHead, problem: HttpResponse) -> EitherBody<B> {
+    let (problem, body) = problem.into_parts();
+
+    head.status = problem.status();
+
+    for name in problem.headers().keys() {
+        head.headers.remove(name);
+    }
+
+    for (name, value) in problem.headers() {
+        head.headers.append(name.clone(), value.clone());
+    }
+
+    EitherBody::right(BoxBody::new(body))
+}
+
+/// Returns true if the client prefers problem details to a plain text error response.
+fn accepts_problem(req: &ServiceRequest) -> bool {
+    let accept = match Accept::parse(req) {
+        Ok(accept) => accept,
+        // treat a malformed header as missing
+        Err(_) => return true,
+    };
+
+    let mime = accept.preference();
+
+    match (mime.type_(), mime.subtype()) {
+        (mime::STAR, _) => true,
+        (mime::APPLICATION, mime::STAR) | (mime::APPLICATION, mime::JSON) => true,
+        (mime::APPLICATION, _) => mime.suffix() == Some(mime::JSON),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        error,
+        http::{
+            header::{self, HeaderValue},
+            StatusCode,
+        },
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    #[actix_rt::test]
+    async fn renders_problem_details() {
+        let app = test::init_service(
+            App::new()
+                .wrap(ProblemJson::new())
+                .route(
+                    "/",
+                    web::get().to(|| async {
+                        Err::<HttpResponse, _>(error::ErrorForbidden("missing scope"))
+                    }),
+                )
+                .route("/ok", web::get().to(|| async { "ok" })),
+        )
+        .await;
+
+        for accept in [
+            None,
+            Some("application/json"),
+            Some("application/problem+json"),
+        ] {
+            let mut req = TestRequest::get();
+            if let Some(accept) = accept {
+                req = req.insert_header((header::ACCEPT, accept));
+            }
+
+            let res = test::call_service(&app, req.to_request()).await;
+            assert_eq!(res.status(), StatusCode::FORBIDDEN);
+            assert_eq!(
+                res.headers().get(header::CONTENT_TYPE).unwrap(),
+                "application/problem+json"
+            );
+            assert!(res.response().error().is_some());
+
+            let body: Value = test::read_body_json(res).await;
+            assert_eq!(
+                body,
+                json!({
+                    "type": "about:blank",
+                    "title": "Forbidden",
+                    "status": 403,
+                    "detail": "missing scope",
+                })
+            );
+        }
+
+        let req = TestRequest::get()
+            .insert_header((header::ACCEPT, "text/html,*/*;q=0.8"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(test::read_body(res).await, "missing scope");
+
+        let req = TestRequest::get().uri("/ok").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(test::read_body(res).await, "ok");
+    }
+
+    #[actix_rt::test]
+    async fn renders_service_errors() {
+        let srv = |req: ServiceRequest| async move {
+            let _ = req;
+            Err::<ServiceResponse, _>(error::ErrorUnauthorized("token expired"))
+        };
+
+        let mw = ProblemJson::new()
+            .new_transform(actix_service::fn_service(srv))
+            .await
+            .unwrap();
+
+        let req = TestRequest::default().to_srv_request();
+        let err = mw.call(req).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let res = err.error_response();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("application/problem+json")
+        );
+
+        let body = crate::body::to_bytes(res.into_body()).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["detail"], "token expired");
+    }
+}
@@ -0,0 +1,546 @@
+//! For middleware documentation, see [`RateLimiter`].
+
+use std::{
+    cmp,
+    collections::HashMap,
+    fmt,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use actix_service::{Service, Transform};
+use actix_utils::future::{ready, Ready};
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    body::EitherBody,
+    http::header::{self, HeaderName, HeaderValue},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpResponse,
+};
+
+/// Minimum number of tracked keys above which the in-memory backend drops idle buckets.
+const MEMORY_PRUNE_THRESHOLD: usize = 10_000;
+
+/// A rate limit, expressed as a number of requests allowed per period.
+///
+/// Limits are enforced with a token bucket: each key may make up to `burst` requests at once,
+/// after which requests are allowed again at a steady rate of `burst` per `period`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    burst: u32,
+    period: Duration,
+}
+
+impl Quota {
+    /// Constructs a quota allowing `burst` requests per `period`.
+    ///
+    /// # Panics
+    /// Panics if `burst` or `period` is zero.
+    pub fn new(burst: u32, period: Duration) -> Self {
+        assert!(
+            burst > 0,
+            "rate limit quota must allow at least one request"
+        );
+        assert!(!period.is_zero(), "rate limit period must be non-zero");
+        Quota { burst, period }
+    }
+
+    /// Constructs a quota allowing `burst` requests per second.
+    pub fn per_second(burst: u32) -> Self {
+        Quota::new(burst, Duration::from_secs(1))
+    }
+
+    /// Constructs a quota allowing `burst` requests per minute.
+    pub fn per_minute(burst: u32) -> Self {
+        Quota::new(burst, Duration::from_secs(60))
+    }
+
+    /// Returns the maximum number of requests that can be made at once.
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// Returns the period over which the full `burst` is replenished.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Returns the time it takes to replenish a single request.
+    pub fn replenish_interval(&self) -> Duration {
+        self.period / self.burst
+    }
+}
+
+/// Outcome of a rate limit check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitDecision {
+    /// The request is allowed; `remaining` requests may be made before the limit is reached.
+    Allowed { remaining: u32 },
+
+    /// The request is rejected; a request will be allowed again after `retry_after`.
+    Limited { retry_after: Duration },
+}
+
+/// Storage for [`RateLimiter`] buckets.
+///
+/// Implement this trait to share limits between processes, such as with a Redis-backed store.
+/// Backends are usually cloned into each worker, so they should be cheap handles to shared state.
+pub trait RateLimitBackend {
+    /// Attempts to take one request from the bucket for `key` under the given quota.
+    ///
+    /// Returning an error fails the request with that error.
+    fn acquire(
+        &self,
+        key: String,
+        quota: Quota,
+    ) -> LocalBoxFuture<'static, Result<RateLimitDecision, Error>>;
+}
+
+/// In-memory [`RateLimitBackend`], shared between all clones and worker threads.
+///
+/// Limits are local to the process; use a shared backend if running multiple instances. Limiters
+/// using the same backend also share buckets for equal keys, so use a separate backend for each
+/// distinct quota.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBackend {
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+#[derive(Debug)]
+struct Buckets {
+    map: HashMap<String, Bucket>,
+
+    /// Number of tracked keys at which idle buckets are next dropped.
+    prune_at: usize,
+}
+
+impl Default for Buckets {
+    fn default() -> Self {
+        Buckets {
+            map: HashMap::new(),
+            prune_at: MEMORY_PRUNE_THRESHOLD,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl MemoryBackend {
+    /// Constructs an empty in-memory backend.
+    pub fn new() -> Self {
+        MemoryBackend::default()
+    }
+
+    fn acquire_at(&self, key: String, quota: Quota, now: Instant) -> RateLimitDecision {
+        let interval = quota.replenish_interval().as_secs_f64();
+        let burst = f64::from(quota.burst);
+
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.map.len() >= buckets.prune_at {
+            // drop buckets that would be full by now; they are equivalent to missing ones
+            buckets.map.retain(|_, bucket| {
+                bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() / interval
+                    < burst
+            });
+
+            // keys still in use are not pruned again until as many new ones are tracked, so the
+            // cost of pruning stays proportional to the number of inserted keys
+            buckets.prune_at = cmp::max(MEMORY_PRUNE_THRESHOLD, buckets.map.len() * 2);
+        }
+
+        let bucket = buckets.map.entry(key).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed / interval).min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+
+            RateLimitDecision::Allowed {
+                remaining: bucket.tokens as u32,
+            }
+        } else {
+            RateLimitDecision::Limited {
+                retry_after: Duration::from_secs_f64((1.0 - bucket.tokens) * interval),
+            }
+        }
+    }
+}
+
+impl RateLimitBackend for MemoryBackend {
+    fn acquire(
+        &self,
+        key: String,
+        quota: Quota,
+    ) -> LocalBoxFuture<'static, Result<RateLimitDecision, Error>> {
+        let decision = self.acquire_at(key, quota, Instant::now());
+        Box::pin(ready(Ok(decision)))
+    }
+}
+
+type KeyFn = dyn Fn(&ServiceRequest) -> Option<String>;
+
+/// Middleware for limiting the rate of requests per client.
+///
+/// Requests are grouped by a key, the peer IP address by default, and each key gets its own token
+/// bucket as described by the [`Quota`]. Requests over the limit are rejected with
+/// `429 Too Many Requests` and a `Retry-After` header, without calling the inner service. Requests
+/// for which no key can be determined are not limited.
+///
+/// Buckets are stored in a [`RateLimitBackend`]; [`MemoryBackend`] is used by [`new`](Self::new).
+/// Construct the backend outside the `HttpServer::new` closure and pass a clone to each worker's
+/// limiter so that all workers share the same limits.
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     middleware::{MemoryBackend, Quota, RateLimiter},
+///     web, App, HttpResponse, HttpServer,
+/// };
+///
+/// # fn run() -> std::io::Result<actix_web::dev::Server> {
+/// let backend = MemoryBackend::new();
+///
+/// let srv = HttpServer::new(move || {
+///     App::new()
+///         .service(
+///             web::resource("/login")
+///                 .wrap(RateLimiter::with_backend(backend.clone(), Quota::per_minute(10)))
+///                 .route(web::post().to(HttpResponse::Ok)),
+///         )
+///         .route("/", web::get().to(HttpResponse::Ok))
+/// })
+/// .bind(("127.0.0.1", 8080))?
+/// .run();
+/// # Ok(srv)
+/// # }
+/// ```
+pub struct RateLimiter<Bk = MemoryBackend> {
+    inner: Rc<Inner<Bk>>,
+}
+
+struct Inner<Bk> {
+    backend: Bk,
+    quota: Quota,
+    key_fn: Box<KeyFn>,
+}
+
+impl RateLimiter {
+    /// Constructs a rate limiter using the given quota and a new [`MemoryBackend`].
+    ///
+    /// Note that each call creates separate storage; to share limits between workers, use
+    /// [`with_backend`](Self::with_backend) with a single, cloned backend.
+    pub fn new(quota: Quota) -> Self {
+        RateLimiter::with_backend(MemoryBackend::new(), quota)
+    }
+}
+
+impl<Bk> RateLimiter<Bk>
+where
+    Bk: RateLimitBackend,
+{
+    /// Constructs a rate limiter using the given backend and quota.
+    pub fn with_backend(backend: Bk, quota: Quota) -> Self {
+        RateLimiter {
+            inner: Rc::new(Inner {
+                backend,
+                quota,
+                key_fn: Box::new(|req| req.peer_addr().map(|addr| addr.ip().to_string())),
+            }),
+        }
+    }
+
+    /// Groups requests by the value of the given request header.
+    ///
+    /// Requests without the header are not limited.
+    pub fn key_by_header(self, header_name: HeaderName) -> Self {
+        self.key_fn(move |req| {
+            req.headers()
+                .get(&header_name)
+                .and_then(|val| val.to_str().ok())
+                .map(ToOwned::to_owned)
+        })
+    }
+
+    /// Groups requests by the "real IP" of the [connection info](crate::dev::ConnectionInfo).
+    ///
+    /// This value is read from the `Forwarded` and `X-Forwarded-For` headers when present, so it
+    /// can be spoofed by clients. Only use it when the application is behind a proxy that sets
+    /// these headers.
+    pub fn key_by_realip(self) -> Self {
+        self.key_fn(|req| {
+            req.connection_info()
+                .realip_remote_addr()
+                .map(ToOwned::to_owned)
+        })
+    }
+
+    /// Groups requests using a custom key function.
+    ///
+    /// Requests for which the function returns `None` are not limited.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn key_fn<F>(mut self, key_fn: F) -> Self
+    where
+        F: Fn(&ServiceRequest) -> Option<String> + 'static,
+    {
+        Rc::get_mut(&mut self.inner)
+            .expect("RateLimiter must be configured before it is cloned.")
+            .key_fn = Box::new(key_fn);
+        self
+    }
+}
+
+impl<Bk> Clone for RateLimiter<Bk> {
+    fn clone(&self) -> Self {
+        RateLimiter {
+            inner: Rc::clone(&self.inner),
+        }
+    }
+}
+
+impl<Bk> fmt::Debug for RateLimiter<Bk> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("quota", &self.inner.quota)
+            .finish()
+    }
+}
+
+impl<S, B, Bk> Transform<S, ServiceRequest> for RateLimiter<Bk>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+    Bk: RateLimitBackend + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimiterMiddleware<S, Bk>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimiterMiddleware {
+            service: Rc::new(service),
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+#[doc(hidden)]
+pub struct RateLimiterMiddleware<S, Bk> {
+    service: Rc<S>,
+    inner: Rc<Inner<Bk>>,
+}
+
+impl<S, B, Bk> Service<ServiceRequest> for RateLimiterMiddleware<S, Bk>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+    Bk: RateLimitBackend + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        let key = match (self.inner.key_fn)(&req) {
+            Some(key) => key,
+            None => {
+                return Box::pin(async move {
+                    service
+                        .call(req)
+                        .await
+                        .map(ServiceResponse::map_into_left_body)
+                })
+            }
+        };
+
+        let acquire = self.inner.backend.acquire(key, self.inner.quota);
+
+        Box::pin(async move {
+            match acquire.await? {
+                RateLimitDecision::Allowed { .. } => service
+                    .call(req)
+                    .await
+                    .map(ServiceResponse::map_into_left_body),
+
+                RateLimitDecision::Limited { retry_after } => {
+                    let secs = super::retry_after_secs(retry_after);
+
+                    let res = HttpResponse::TooManyRequests()
+                        .insert_header((header::RETRY_AFTER, HeaderValue::from(secs)))
+                        .finish();
+
+                    Ok(req.into_response(res).map_into_right_body())
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    #[test]
+    fn memory_backend_token_bucket() {
+        let backend = MemoryBackend::new();
+        let quota = Quota::new(2, Duration::from_secs(10));
+        let start = Instant::now();
+
+        let acquire = |key: &str, after: u64| {
+            backend.acquire_at(key.to_owned(), quota, start + Duration::from_secs(after))
+        };
+
+        assert_eq!(acquire("a", 0), RateLimitDecision::Allowed { remaining: 1 });
+        assert_eq!(acquire("a", 0), RateLimitDecision::Allowed { remaining: 0 });
+        assert_eq!(
+            acquire("a", 1),
+            RateLimitDecision::Limited {
+                retry_after: Duration::from_secs(4)
+            }
+        );
+
+        // other keys have their own bucket
+        assert_eq!(acquire("b", 1), RateLimitDecision::Allowed { remaining: 1 });
+
+        // one request is replenished every 5 seconds
+        assert_eq!(acquire("a", 5), RateLimitDecision::Allowed { remaining: 0 });
+
+        // bucket is never filled beyond the burst size
+        assert_eq!(
+            acquire("a", 100),
+            RateLimitDecision::Allowed { remaining: 1 }
+        );
+    }
+
+    #[test]
+    fn memory_backend_prunes_idle_buckets() {
+        let backend = MemoryBackend::new();
+        let quota = Quota::new(1, Duration::from_secs(10));
+        let start = Instant::now();
+
+        // buckets that are still refilling are kept
+        for i in 0..MEMORY_PRUNE_THRESHOLD {
+            backend.acquire_at(i.to_string(), quota, start);
+        }
+        backend.acquire_at("new".to_owned(), quota, start);
+
+        let buckets = backend.buckets.lock().unwrap();
+        assert_eq!(buckets.map.len(), MEMORY_PRUNE_THRESHOLD + 1);
+        assert_eq!(buckets.prune_at, MEMORY_PRUNE_THRESHOLD * 2);
+        drop(buckets);
+
+        // full buckets are dropped, and the threshold is lowered again
+        let later = start + Duration::from_secs(60);
+        for i in 0..MEMORY_PRUNE_THRESHOLD - 1 {
+            backend.acquire_at(format!("later-{}", i), quota, start);
+        }
+        backend.acquire_at("last".to_owned(), quota, later);
+
+        let buckets = backend.buckets.lock().unwrap();
+        assert_eq!(buckets.map.len(), 1);
+        assert_eq!(buckets.prune_at, MEMORY_PRUNE_THRESHOLD);
+    }
+
+    #[actix_rt::test]
+    async fn limits_by_header() {
+        let app = test::init_service(
+            App::new()
+                .wrap(
+                    RateLimiter::new(Quota::per_minute(1))
+                        .key_by_header(HeaderName::from_static("x-api-key")),
+                )
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = |key: Option<&str>| {
+            let mut req = TestRequest::default();
+            if let Some(key) = key {
+                req = req.insert_header(("x-api-key", key));
+            }
+            req.to_request()
+        };
+
+        let res = test::call_service(&app, req(Some("a"))).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = test::call_service(&app, req(Some("a"))).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after = res.headers().get(header::RETRY_AFTER).unwrap();
+        let retry_after = retry_after.to_str().unwrap().parse::<u64>().unwrap();
+        assert!((1..=60).contains(&retry_after));
+
+        let res = test::call_service(&app, req(Some("b"))).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // requests without a key are not limited
+        for _ in 0..3 {
+            let res = test::call_service(&app, req(None)).await;
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn backend_errors_are_propagated() {
+        struct Failing;
+
+        impl RateLimitBackend for Failing {
+            fn acquire(
+                &self,
+                _key: String,
+                _quota: Quota,
+            ) -> LocalBoxFuture<'static, Result<RateLimitDecision, Error>> {
+                Box::pin(ready(Err(crate::error::ErrorServiceUnavailable(
+                    "store unavailable",
+                ))))
+            }
+        }
+
+        let app = test::init_service(
+            App::new()
+                .wrap(
+                    RateLimiter::with_backend(Failing, Quota::per_second(1))
+                        .key_fn(|_| Some("k".to_owned())),
+                )
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let res = app.call(TestRequest::default().to_request()).await;
+        let err = res.map(|_| ()).unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_burst() {
+        let _ = Quota::per_second(0);
+    }
+}
@@ -0,0 +1,316 @@
+//! For middleware documentation, see [`RedirectHttps`].
+
+use std::{
+    collections::HashSet,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use actix_service::{Service, Transform};
+use actix_utils::future::{ready, Either, Ready};
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use crate::{
+    body::EitherBody,
+    http::{header, StatusCode},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpResponse,
+};
+
+/// Middleware for redirecting plaintext requests to HTTPS.
+///
+/// Requests that were not received over TLS are answered with a redirect to the same host, path
+/// and query on the `https` scheme, without calling the inner service. The scheme is determined
+/// from the [connection info](crate::dev::ConnectionInfo), so requests forwarded by a TLS
+/// terminating proxy are not redirected.
+///
+/// `308 Permanent Redirect` is used by default; unlike `301 Moved Permanently`, it tells clients to
+/// repeat the request using the same method and body.
+///
+/// Requests for ACME HTTP-01 challenges, under `/.well-known/acme-challenge/`, are never redirected
+/// so that certificates can be obtained before HTTPS is available.
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware::RedirectHttps, web, App, HttpResponse, HttpServer};
+///
+/// # fn run() -> std::io::Result<actix_web::dev::Server> {
+/// let srv = HttpServer::new(|| {
+///     App::new()
+///         .wrap(RedirectHttps::new().to_port(8443))
+///         .route("/", web::get().to(HttpResponse::Ok))
+/// })
+/// .bind(("0.0.0.0", 8080))?
+/// // .bind_rustls(("0.0.0.0", 8443), tls_config)?
+/// .run();
+/// # Ok(srv)
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RedirectHttps {
+    inner: Rc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    port: Option<u16>,
+    status: StatusCode,
+    exclude: HashSet<String>,
+    exclude_prefix: Vec<String>,
+}
+
+impl Default for RedirectHttps {
+    fn default() -> Self {
+        RedirectHttps {
+            inner: Rc::new(Inner {
+                port: None,
+                status: StatusCode::PERMANENT_REDIRECT,
+                exclude: HashSet::new(),
+                exclude_prefix: vec!["/.well-known/acme-challenge/".to_owned()],
+            }),
+        }
+    }
+}
+
+impl RedirectHttps {
+    /// Constructs a `RedirectHttps` middleware redirecting to the default HTTPS port.
+    pub fn new() -> Self {
+        RedirectHttps::default()
+    }
+
+    /// Sets the port that redirects point to. Defaults to 443.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn to_port(mut self, port: u16) -> Self {
+        self.inner_mut().port = Some(port).filter(|&port| port != 443);
+        self
+    }
+
+    /// Sets the redirect status code. Defaults to `308 Permanent Redirect`.
+    ///
+    /// # Panics
+    /// Panics if the status code is not a redirection, or if called after the middleware has been
+    /// cloned.
+    pub fn status(mut self, status: StatusCode) -> Self {
+        assert!(
+            status.is_redirection(),
+            "HTTPS redirect status must be a redirection"
+        );
+        self.inner_mut().status = status;
+        self
+    }
+
+    /// Does not redirect requests to the specified path.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn exclude<T: Into<String>>(mut self, path: T) -> Self {
+        self.inner_mut().exclude.insert(path.into());
+        self
+    }
+
+    /// Does not redirect requests to paths starting with `prefix`.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn exclude_prefix<T: Into<String>>(mut self, prefix: T) -> Self {
+        self.inner_mut().exclude_prefix.push(prefix.into());
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner)
+            .expect("RedirectHttps must be configured before it is cloned or used.")
+    }
+}
+
+impl Inner {
+    fn is_excluded(&self, path: &str) -> bool {
+        self.exclude.contains(path)
+            || self
+                .exclude_prefix
+                .iter()
+                .any(|prefix| path.starts_with(prefix.as_str()))
+    }
+
+    fn location(&self, req: &ServiceRequest) -> String {
+        let conn_info = req.connection_info();
+        let host = strip_port(conn_info.host());
+
+        let path = req
+            .uri()
+            .path_and_query()
+            .map_or("/", |path_and_query| path_and_query.as_str());
+
+        match self.port {
+            Some(port) => format!("https://{}:{}{}", host, port, path),
+            None => format!("https://{}{}", host, path),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RedirectHttps
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RedirectHttpsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RedirectHttpsMiddleware {
+            service,
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+#[doc(hidden)]
+pub struct RedirectHttpsMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for RedirectHttpsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Either<RedirectHttpsFuture<S, B>, Ready<Result<Self::Response, Self::Error>>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let is_https = req.connection_info().scheme() == "https";
+
+        if is_https || self.inner.is_excluded(req.path()) {
+            return Either::left(RedirectHttpsFuture {
+                fut: self.service.call(req),
+                _body: PhantomData,
+            });
+        }
+
+        let res = HttpResponse::build(self.inner.status)
+            .insert_header((header::LOCATION, self.inner.location(&req)))
+            .finish();
+
+        Either::right(ready(Ok(req.into_response(res).map_into_right_body())))
+    }
+}
+
+pin_project! {
+    #[doc(hidden)]
+    pub struct RedirectHttpsFuture<S: Service<ServiceRequest>, B> {
+        #[pin]
+        fut: S::Future,
+        _body: PhantomData<B>,
+    }
+}
+
+impl<S, B> Future for RedirectHttpsFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = Result<ServiceResponse<EitherBody<B>>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let res = ready!(self.project().fut.poll(cx))?;
+        Poll::Ready(Ok(res.map_into_left_body()))
+    }
+}
+
+/// Removes the port, if any, from a `Host` header value.
+fn strip_port(host: &str) -> &str {
+    // IPv6 literals are enclosed in brackets and contain colons themselves
+    if host.starts_with('[') {
+        return match host.find(']') {
+            Some(end) => &host[..=end],
+            None => host,
+        };
+    }
+
+    match host.rsplit_once(':') {
+        Some((host, port)) if port.bytes().all(|b| b.is_ascii_digit()) => host,
+        _ => host,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    #[actix_rt::test]
+    async fn redirects_plaintext_requests() {
+        let app = test::init_service(
+            App::new()
+                .wrap(RedirectHttps::new().to_port(8443).exclude("/health"))
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/login?next=%2F")
+            .insert_header((header::HOST, "example.com:8080"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            res.headers().get(header::LOCATION).unwrap(),
+            "https://example.com:8443/login?next=%2F"
+        );
+
+        for path in ["/health", "/.well-known/acme-challenge/token"] {
+            let req = TestRequest::get().uri(path).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+
+        // requests forwarded by a TLS terminating proxy are not redirected
+        let req = TestRequest::get()
+            .insert_header(("x-forwarded-proto", "https"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn default_port_and_status() {
+        let app = test::init_service(
+            App::new()
+                .wrap(RedirectHttps::new().status(StatusCode::MOVED_PERMANENTLY))
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header((header::HOST, "[::1]:80"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(
+            res.headers().get(header::LOCATION).unwrap(),
+            "https://[::1]/"
+        );
+    }
+
+    #[test]
+    fn strips_host_port() {
+        assert_eq!(strip_port("example.com"), "example.com");
+        assert_eq!(strip_port("example.com:80"), "example.com");
+        assert_eq!(strip_port("[::1]"), "[::1]");
+        assert_eq!(strip_port("[::1]:8080"), "[::1]");
+    }
+}
@@ -0,0 +1,405 @@
+//! For middleware documentation, see [`RequestId`].
+
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    ops::Deref,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use actix_utils::future::{ready, Ready};
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use crate::{
+    dev::{Service, Transform},
+    http::header::{HeaderName, HeaderValue},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpMessage as _,
+};
+
+/// Longest incoming request ID that is accepted as-is.
+const MAX_INCOMING_LEN: usize = 128;
+
+/// Middleware for assigning an ID to each request.
+///
+/// If the request carries an ID header (`X-Request-Id` by default) with a reasonable value, that
+/// ID is reused so that it can be correlated with the upstream service. Otherwise, a new random
+/// (version 4) UUID is generated. Incoming values are only reused if they consist of at most 128
+/// visible ASCII characters.
+///
+/// The ID is:
+/// - stored in the request extensions as a [`RequestIdValue`], and can be extracted in handlers
+///   using [`ReqData<RequestIdValue>`](crate::web::ReqData);
+/// - set on the request headers, so that inner middleware and handlers can read it too;
+/// - echoed on the response headers, unless the response already has that header set.
+///
+/// To include the ID in access logs, use the `%{x-request-id}o` format with [`Logger`]. The
+/// response header is used since the `Logger` usually wraps this middleware and therefore renders
+/// request details before the ID is assigned.
+///
+/// [`Logger`]: crate::middleware::Logger
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     middleware::{Logger, RequestId, RequestIdValue},
+///     web, App,
+/// };
+///
+/// async fn index(id: web::ReqData<RequestIdValue>) -> String {
+///     format!("request ID: {}", id.as_str())
+/// }
+///
+/// let app = App::new()
+///     .wrap(RequestId::new())
+///     .wrap(Logger::new("%a \"%r\" %s %{x-request-id}o"))
+///     .route("/", web::get().to(index));
+/// ```
+#[derive(Clone)]
+pub struct RequestId {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    header_name: HeaderName,
+    trust_incoming: bool,
+    generator: Box<dyn Fn() -> String>,
+}
+
+impl Default for RequestId {
+    fn default() -> Self {
+        RequestId {
+            inner: Rc::new(Inner {
+                header_name: HeaderName::from_static("x-request-id"),
+                trust_incoming: true,
+                generator: Box::new(uuid_v4),
+            }),
+        }
+    }
+}
+
+impl RequestId {
+    /// Constructs a `RequestId` middleware using the `X-Request-Id` header and UUID v4 IDs.
+    pub fn new() -> Self {
+        RequestId::default()
+    }
+
+    /// Sets the header used to read incoming IDs and echo the ID on responses.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn header(mut self, header_name: HeaderName) -> Self {
+        self.inner_mut().header_name = header_name;
+        self
+    }
+
+    /// Sets whether IDs supplied by the client are reused. Defaults to `true`.
+    ///
+    /// Disable this when the service is exposed directly to untrusted clients and IDs must be
+    /// unique.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn trust_incoming(mut self, trust: bool) -> Self {
+        self.inner_mut().trust_incoming = trust;
+        self
+    }
+
+    /// Sets the function used to generate new IDs, such as a ULID generator.
+    ///
+    /// Generated IDs must be valid header values; requests panic otherwise.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn generator<F>(mut self, generator: F) -> Self
+    where
+        F: Fn() -> String + 'static,
+    {
+        self.inner_mut().generator = Box::new(generator);
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner)
+            .expect("RequestId must be configured before it is cloned or used.")
+    }
+}
+
+impl fmt::Debug for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestId")
+            .field("header_name", &self.inner.header_name)
+            .field("trust_incoming", &self.inner.trust_incoming)
+            .finish()
+    }
+}
+
+/// The ID assigned to a request by the [`RequestId`] middleware.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestIdValue(String);
+
+impl RequestIdValue {
+    /// Returns the ID as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Consumes the value, returning the ID.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl Deref for RequestIdValue {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RequestIdValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestId
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestIdMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdMiddleware {
+            service,
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+#[doc(hidden)]
+pub struct RequestIdMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = RequestIdFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let inner = &self.inner;
+
+        let incoming = req
+            .headers()
+            .get(&inner.header_name)
+            .filter(|_| inner.trust_incoming)
+            .and_then(|val| val.to_str().ok())
+            .filter(|val| is_acceptable_id(val))
+            .map(ToOwned::to_owned);
+
+        let id = incoming.unwrap_or_else(|| (inner.generator)());
+        let value = HeaderValue::from_str(&id)
+            .expect("generated request ID is not a valid header value");
+
+        req.headers_mut()
+            .insert(inner.header_name.clone(), value.clone());
+        req.extensions_mut().insert(RequestIdValue(id));
+
+        RequestIdFuture {
+            fut: self.service.call(req),
+            header_name: inner.header_name.clone(),
+            value,
+            _body: PhantomData,
+        }
+    }
+}
+
+pin_project! {
+    #[doc(hidden)]
+    pub struct RequestIdFuture<S: Service<ServiceRequest>, B> {
+        #[pin]
+        fut: S::Future,
+        header_name: HeaderName,
+        value: HeaderValue,
+        _body: PhantomData<B>,
+    }
+}
+
+impl<S, B> Future for RequestIdFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = <S::Future as Future>::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut res = ready!(this.fut.poll(cx))?;
+
+        if !res.headers().contains_key(&*this.header_name) {
+            res.headers_mut()
+                .insert(this.header_name.clone(), this.value.clone());
+        }
+
+        Poll::Ready(Ok(res))
+    }
+}
+
+/// Returns true if an incoming ID is short and consists only of visible ASCII characters.
+fn is_acceptable_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_INCOMING_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// Generates a random (version 4) UUID in its hyphenated form.
+fn uuid_v4() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+
+    // set version (4) and variant (RFC 4122) bits
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let mut id = String::with_capacity(36);
+
+    for (idx, byte) in bytes.iter().enumerate() {
+        if matches!(idx, 4 | 6 | 8 | 10) {
+            id.push('-');
+        }
+
+        id.push_str(&format!("{:02x}", byte));
+    }
+
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_service::IntoService;
+
+    use super::*;
+    use crate::{
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    #[test]
+    fn uuid_format() {
+        let id = uuid_v4();
+
+        assert_eq!(id.len(), 36);
+        assert_eq!(id.as_bytes()[14], b'4');
+        assert!(matches!(id.as_bytes()[19], b'8' | b'9' | b'a' | b'b'));
+        assert_eq!(id.matches('-').count(), 4);
+        assert_ne!(id, uuid_v4());
+    }
+
+    #[actix_rt::test]
+    async fn generates_and_echoes_id() {
+        let app = test::init_service(App::new().wrap(RequestId::new()).route(
+            "/",
+            web::get().to(|id: web::ReqData<RequestIdValue>| async move { id.into_inner().0 }),
+        ))
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = test::call_service(&app, req).await;
+
+        let header = res.headers().get("x-request-id").unwrap().clone();
+        assert_eq!(header.len(), 36);
+        assert_eq!(test::read_body(res).await, header.as_bytes());
+    }
+
+    #[actix_rt::test]
+    async fn reuses_incoming_id() {
+        let srv = |req: ServiceRequest| {
+            assert_eq!(
+                req.extensions().get::<RequestIdValue>().unwrap().as_str(),
+                "abc-123"
+            );
+            ready(Ok(req.into_response(HttpResponse::Ok().finish())))
+        };
+
+        let mw = RequestId::new()
+            .new_transform(srv.into_service())
+            .await
+            .unwrap();
+
+        let req = TestRequest::default()
+            .insert_header(("x-request-id", "abc-123"))
+            .to_srv_request();
+        let res = test::call_service(&mw, req).await;
+        assert_eq!(res.headers().get("x-request-id").unwrap(), "abc-123");
+    }
+
+    #[actix_rt::test]
+    async fn replaces_untrusted_or_invalid_id() {
+        let mw = RequestId::new()
+            .generator(|| "generated".to_owned())
+            .new_transform(test::ok_service())
+            .await
+            .unwrap();
+
+        let long_id = "a".repeat(MAX_INCOMING_LEN + 1);
+
+        for incoming in ["", "has space", long_id.as_str()] {
+            let req = TestRequest::default()
+                .insert_header(("x-request-id", incoming))
+                .to_srv_request();
+            let res = test::call_service(&mw, req).await;
+            assert_eq!(res.headers().get("x-request-id").unwrap(), "generated");
+        }
+
+        let mw = RequestId::new()
+            .trust_incoming(false)
+            .generator(|| "generated".to_owned())
+            .new_transform(test::ok_service())
+            .await
+            .unwrap();
+
+        let req = TestRequest::default()
+            .insert_header(("x-request-id", "abc-123"))
+            .to_srv_request();
+        let res = test::call_service(&mw, req).await;
+        assert_eq!(res.headers().get("x-request-id").unwrap(), "generated");
+    }
+
+    #[actix_rt::test]
+    async fn custom_header_and_existing_response_header() {
+        let srv = |req: ServiceRequest| {
+            assert_eq!(req.headers().get("x-correlation-id").unwrap(), "generated");
+            ready(Ok(req.into_response(
+                HttpResponse::Ok()
+                    .insert_header(("x-correlation-id", "from-handler"))
+                    .finish(),
+            )))
+        };
+
+        let mw = RequestId::new()
+            .header(HeaderName::from_static("x-correlation-id"))
+            .generator(|| "generated".to_owned())
+            .new_transform(srv.into_service())
+            .await
+            .unwrap();
+
+        let req = TestRequest::default().to_srv_request();
+        let res = test::call_service(&mw, req).await;
+        assert_eq!(
+            res.headers().get("x-correlation-id").unwrap(),
+            "from-handler"
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! For middleware documentation, see [`SecurityHeaders`].
+
+use std::time::Duration;
+
+use actix_utils::future::Ready;
+
+use super::{default_headers::DefaultHeadersMiddleware, DefaultHeaders};
+use crate::{
+    dev::{Service, Transform},
+    http::header::{self, HeaderMap, HeaderName, HeaderValue, TryIntoHeaderPair},
+    service::{ServiceRequest, ServiceResponse},
+    Error,
+};
+
+/// Middleware for setting security related response headers.
+///
+/// The following headers are set by default:
+/// - `Strict-Transport-Security: max-age=31536000; includeSubDomains`
+/// - `X-Content-Type-Options: nosniff`
+/// - `X-Frame-Options: DENY`
+/// - `Referrer-Policy: strict-origin-when-cross-origin`
+///
+/// A `Content-Security-Policy` is not set by default since it depends on the resources used by the
+/// application; set one using [`content_security_policy`](Self::content_security_policy).
+///
+/// Like with [`DefaultHeaders`], headers that are already set in a response are *not*
+/// overwritten, so handlers can relax them for specific responses.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{http::header, middleware::SecurityHeaders, web, App, HttpResponse};
+///
+/// let app = App::new()
+///     .wrap(
+///         SecurityHeaders::new()
+///             .strict_transport_security(Duration::from_secs(63_072_000), true)
+///             .content_security_policy("default-src 'self'")
+///             .remove(header::X_FRAME_OPTIONS),
+///     )
+///     .route("/", web::get().to(HttpResponse::Ok));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SecurityHeaders {
+    headers: HeaderMap,
+}
+
+impl Default for SecurityHeaders {
+    fn default() -> Self {
+        SecurityHeaders {
+            headers: HeaderMap::new(),
+        }
+        .strict_transport_security(Duration::from_secs(31_536_000), true)
+        .add((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .frame_options("DENY")
+        .referrer_policy("strict-origin-when-cross-origin")
+    }
+}
+
+impl SecurityHeaders {
+    /// Constructs a `SecurityHeaders` middleware with the default set of headers.
+    pub fn new() -> Self {
+        SecurityHeaders::default()
+    }
+
+    /// Sets the `Strict-Transport-Security` header, telling browsers to only use HTTPS for the
+    /// given duration.
+    ///
+    /// Browsers ignore this header on plain HTTP responses.
+    pub fn strict_transport_security(
+        self,
+        max_age: Duration,
+        include_subdomains: bool,
+    ) -> Self {
+        let mut value = format!("max-age={}", max_age.as_secs());
+
+        if include_subdomains {
+            value.push_str("; includeSubDomains");
+        }
+
+        self.add((header::STRICT_TRANSPORT_SECURITY, value))
+    }
+
+    /// Sets the `Content-Security-Policy` header.
+    ///
+    /// # Panics
+    /// Panics when the policy is not a valid header value.
+    pub fn content_security_policy(self, policy: &str) -> Self {
+        self.add((header::CONTENT_SECURITY_POLICY, policy))
+    }
+
+    /// Sets the `X-Frame-Options` header, such as `DENY` or `SAMEORIGIN`.
+    ///
+    /// # Panics
+    /// Panics when the value is not a valid header value.
+    pub fn frame_options(self, value: &str) -> Self {
+        self.add((header::X_FRAME_OPTIONS, value))
+    }
+
+    /// Sets the `Referrer-Policy` header, such as `no-referrer` or `same-origin`.
+    ///
+    /// # Panics
+    /// Panics when the value is not a valid header value.
+    pub fn referrer_policy(self, value: &str) -> Self {
+        self.add((header::REFERRER_POLICY, value))
+    }
+
+    /// Sets a header, replacing the value set for it so far.
+    ///
+    /// # Panics
+    /// Panics when resolved header name or value is invalid.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(mut self, header: impl TryIntoHeaderPair) -> Self {
+        let (key, value) = match header.try_into_pair() {
+            Ok(pair) => pair,
+            Err(err) => panic!("Invalid header: {}", err.into()),
+        };
+
+        self.headers.insert(key, value);
+
+        self
+    }
+
+    /// Stops setting a header, such as one of the defaults.
+    pub fn remove(mut self, name: HeaderName) -> Self {
+        self.headers.remove(name);
+        self
+    }
+
+    /// Returns the value set for a header, if any.
+    pub fn get(&self, name: &HeaderName) -> Option<&HeaderValue> {
+        self.headers.get(name)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for SecurityHeaders
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = DefaultHeadersMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        DefaultHeaders::from_headers(self.headers.clone()).new_transform(service)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    #[actix_rt::test]
+    async fn sets_security_headers() {
+        let app = test::init_service(
+            App::new()
+                .wrap(
+                    SecurityHeaders::new()
+                        .strict_transport_security(Duration::from_secs(60), false)
+                        .content_security_policy("default-src 'self'")
+                        .remove(header::X_FRAME_OPTIONS),
+                )
+                .route("/", web::get().to(HttpResponse::Ok))
+                .route(
+                    "/embed",
+                    web::get().to(|| {
+                        HttpResponse::Ok()
+                            .insert_header((header::REFERRER_POLICY, "no-referrer"))
+                            .finish()
+                    }),
+                ),
+        )
+        .await;
+
+        let res = test::call_service(&app, TestRequest::default().to_request()).await;
+        let headers = res.headers();
+        assert_eq!(
+            headers.get(header::STRICT_TRANSPORT_SECURITY).unwrap(),
+            "max-age=60"
+        );
+        assert_eq!(
+            headers.get(header::CONTENT_SECURITY_POLICY).unwrap(),
+            "default-src 'self'"
+        );
+        assert_eq!(
+            headers.get(header::X_CONTENT_TYPE_OPTIONS).unwrap(),
+            "nosniff"
+        );
+        assert_eq!(
+            headers.get(header::REFERRER_POLICY).unwrap(),
+            "strict-origin-when-cross-origin"
+        );
+        assert!(headers.get(header::X_FRAME_OPTIONS).is_none());
+
+        // headers set by handlers are kept
+        let req = TestRequest::default().uri("/embed").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(
+            res.headers().get(header::REFERRER_POLICY).unwrap(),
+            "no-referrer"
+        );
+    }
+
+    #[test]
+    fn default_headers() {
+        let mw = SecurityHeaders::new();
+        assert_eq!(
+            mw.get(&header::STRICT_TRANSPORT_SECURITY).unwrap(),
+            "max-age=31536000; includeSubDomains"
+        );
+        assert_eq!(mw.get(&header::X_FRAME_OPTIONS).unwrap(), "DENY");
+        assert!(mw.get(&header::CONTENT_SECURITY_POLICY).is_none());
+    }
+}
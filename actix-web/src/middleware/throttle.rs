@@ -0,0 +1,403 @@
+//! For middleware documentation, see [`Throttle`].
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    cmp,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_rt::time::{sleep_until, Instant, Sleep};
+use actix_service::{Service, Transform};
+use actix_utils::future::{ready, Ready};
+use bytes::Bytes;
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use crate::{
+    body::{BodySize, MessageBody},
+    dev::Extensions,
+    service::{ServiceRequest, ServiceResponse},
+    Error,
+};
+
+/// Middleware for limiting the bandwidth used to send response bodies.
+///
+/// Each response body is wrapped in a [`ThrottledBody`], which sends at most `rate` bytes per
+/// second on average, so that large downloads do not starve other clients on constrained links.
+///
+/// By default, each response is throttled on its own, so concurrent responses on an HTTP/2
+/// connection, or responses on separate connections, each get the full `rate`. To bound the
+/// bandwidth of each connection instead, register [`Throttle::on_connect`] with
+/// [`HttpServer::on_connect`](crate::HttpServer::on_connect); the responses of a connection then
+/// share one bucket.
+///
+/// Throttling uses a token bucket: up to [`burst`](Self::burst) bytes can be sent at once after
+/// the bucket has been idle, which defaults to one second worth of `rate`. Response heads and
+/// body sizes are left untouched.
+///
+/// To throttle a single response instead, wrap its body in a [`ThrottledBody`].
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware::Throttle, web, App, HttpResponse, HttpServer};
+///
+/// # fn run() -> std::io::Result<actix_web::dev::Server> {
+/// // limit downloads to 1MiB/s per connection, with bursts of up to 64KiB
+/// let srv = HttpServer::new(|| {
+///     App::new().service(
+///         web::scope("/downloads")
+///             .wrap(Throttle::new(1024 * 1024).burst(64 * 1024))
+///             .route("/{file}", web::get().to(HttpResponse::Ok)),
+///     )
+/// })
+/// .on_connect(Throttle::on_connect)
+/// .bind("127.0.0.1:8080")?
+/// .run();
+/// # Ok(srv)
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Throttle {
+    rate: u64,
+    burst: u64,
+}
+
+impl Throttle {
+    /// Constructs a middleware sending response bodies at up to `rate` bytes per second.
+    ///
+    /// # Panics
+    /// Panics if `rate` is zero.
+    pub fn new(rate: u64) -> Self {
+        assert!(rate > 0, "throttling rate must be positive");
+        Throttle { rate, burst: rate }
+    }
+
+    /// Sets the maximum number of bytes sent at once.
+    ///
+    /// Defaults to `rate`, i.e. one second worth of data.
+    ///
+    /// # Panics
+    /// Panics if `burst` is zero.
+    pub fn burst(mut self, burst: u64) -> Self {
+        assert!(burst > 0, "throttling burst must be positive");
+        self.burst = burst;
+        self
+    }
+
+    /// Connection callback sharing one bucket between the responses of each connection.
+    ///
+    /// Pass it to [`HttpServer::on_connect`](crate::HttpServer::on_connect).
+    pub fn on_connect(_: &dyn Any, data: &mut Extensions) {
+        data.insert(ConnectionBucket(Rc::new(RefCell::new(TokenBucket {
+            // filled up to the burst of the middleware on first use
+            tokens: f64::INFINITY,
+            refilled: Instant::now(),
+        }))));
+    }
+}
+
+/// Token bucket shared by the responses of a connection.
+struct ConnectionBucket(Rc<RefCell<TokenBucket>>);
+
+struct TokenBucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Throttle
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Response = ServiceResponse<ThrottledBody<B>>;
+    type Error = Error;
+    type Transform = ThrottleMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ThrottleMiddleware {
+            service,
+            throttle: *self,
+        }))
+    }
+}
+
+#[doc(hidden)]
+pub struct ThrottleMiddleware<S> {
+    service: S,
+    throttle: Throttle,
+}
+
+impl<S, B> Service<ServiceRequest> for ThrottleMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Response = ServiceResponse<ThrottledBody<B>>;
+    type Error = Error;
+    type Future = ThrottleFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let bucket = req
+            .conn_data::<ConnectionBucket>()
+            .map(|bucket| Rc::clone(&bucket.0));
+
+        ThrottleFuture {
+            fut: self.service.call(req),
+            throttle: self.throttle,
+            bucket,
+            _body: PhantomData,
+        }
+    }
+}
+
+pin_project! {
+    #[doc(hidden)]
+    pub struct ThrottleFuture<S: Service<ServiceRequest>, B> {
+        #[pin]
+        fut: S::Future,
+        throttle: Throttle,
+        bucket: Option<Rc<RefCell<TokenBucket>>>,
+        _body: PhantomData<B>,
+    }
+}
+
+impl<S, B> Future for ThrottleFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Output = Result<ServiceResponse<ThrottledBody<B>>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.fut.poll(cx))?;
+
+        let Throttle { rate, burst } = *this.throttle;
+        let bucket = this.bucket.take();
+
+        Poll::Ready(Ok(res.map_body(|_, body| match bucket {
+            Some(bucket) => ThrottledBody::with_bucket(body, rate, burst, bucket),
+            None => ThrottledBody::new(body, rate).burst(burst),
+        })))
+    }
+}
+
+pin_project! {
+    /// A body sent at a limited rate, using a token bucket.
+    ///
+    /// Chunks larger than the bucket are split. See [`Throttle`] for details.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{middleware::ThrottledBody, HttpResponse};
+    ///
+    /// async fn download() -> HttpResponse {
+    ///     let data = vec![0; 1024 * 1024];
+    ///
+    ///     // send at 256KiB/s
+    ///     HttpResponse::Ok().body(ThrottledBody::new(data, 256 * 1024))
+    /// }
+    /// ```
+    pub struct ThrottledBody<B> {
+        #[pin]
+        body: B,
+        rate: u64,
+        burst: u64,
+        bucket: Rc<RefCell<TokenBucket>>,
+        pending: Option<Bytes>,
+        sleep: Option<Pin<Box<Sleep>>>,
+    }
+}
+
+impl<B: MessageBody> ThrottledBody<B> {
+    /// Constructs a body sending `body` at up to `rate` bytes per second.
+    ///
+    /// # Panics
+    /// Panics if `rate` is zero.
+    pub fn new(body: B, rate: u64) -> Self {
+        assert!(rate > 0, "throttling rate must be positive");
+
+        let bucket = Rc::new(RefCell::new(TokenBucket {
+            tokens: rate as f64,
+            refilled: Instant::now(),
+        }));
+
+        ThrottledBody::with_bucket(body, rate, rate, bucket)
+    }
+
+    fn with_bucket(body: B, rate: u64, burst: u64, bucket: Rc<RefCell<TokenBucket>>) -> Self {
+        ThrottledBody {
+            body,
+            rate,
+            burst,
+            bucket,
+            pending: None,
+            sleep: None,
+        }
+    }
+
+    /// Sets the maximum number of bytes sent at once.
+    ///
+    /// Defaults to `rate`, i.e. one second worth of data.
+    ///
+    /// # Panics
+    /// Panics if `burst` is zero.
+    pub fn burst(mut self, burst: u64) -> Self {
+        assert!(burst > 0, "throttling burst must be positive");
+        self.burst = burst;
+        self.bucket.borrow_mut().tokens = burst as f64;
+        self
+    }
+}
+
+impl<B: MessageBody> MessageBody for ThrottledBody<B> {
+    type Error = B::Error;
+
+    #[inline]
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let mut this = self.project();
+
+        loop {
+            let chunk = match this.pending.take() {
+                Some(chunk) => chunk,
+                None => match ready!(this.body.as_mut().poll_next(cx)) {
+                    Some(Ok(chunk)) if chunk.is_empty() => continue,
+                    Some(Ok(chunk)) => chunk,
+                    res => return Poll::Ready(res),
+                },
+            };
+
+            if let Some(sleep) = this.sleep.as_mut() {
+                if sleep.as_mut().poll(cx).is_pending() {
+                    *this.pending = Some(chunk);
+                    return Poll::Pending;
+                }
+
+                *this.sleep = None;
+            }
+
+            let now = Instant::now();
+            let mut bucket = this.bucket.borrow_mut();
+            let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+            bucket.tokens =
+                (bucket.tokens + elapsed * *this.rate as f64).min(*this.burst as f64);
+            bucket.refilled = now;
+
+            let len = cmp::min(chunk.len() as u64, *this.burst);
+
+            if bucket.tokens < len as f64 {
+                let wait = (len as f64 - bucket.tokens) / *this.rate as f64;
+                *this.sleep = Some(Box::pin(sleep_until(now + Duration::from_secs_f64(wait))));
+                *this.pending = Some(chunk);
+                continue;
+            }
+
+            bucket.tokens -= len as f64;
+            drop(bucket);
+
+            let mut chunk = chunk;
+            if (len as usize) < chunk.len() {
+                *this.pending = Some(chunk.split_off(len as usize));
+            }
+
+            return Poll::Ready(Some(Ok(chunk)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body,
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    /// Collects the chunks of a body, with the time at which each was produced.
+    async fn chunks<B: MessageBody>(body: B) -> Vec<(usize, Instant)> {
+        let mut body = Box::pin(body);
+        let mut chunks = Vec::new();
+
+        while let Some(chunk) =
+            futures_util::future::poll_fn(|cx| body.as_mut().poll_next(cx)).await
+        {
+            chunks.push((chunk.ok().unwrap().len(), Instant::now()));
+        }
+
+        chunks
+    }
+
+    #[actix_rt::test]
+    async fn limits_rate() {
+        let start = Instant::now();
+        let body = ThrottledBody::new(vec![0u8; 3000], 10_000).burst(1000);
+        assert_eq!(body.size(), BodySize::Sized(3000));
+
+        let chunks = chunks(body).await;
+        assert_eq!(
+            chunks.iter().map(|(len, _)| *len).collect::<Vec<_>>(),
+            [1000, 1000, 1000]
+        );
+
+        // the first chunk is sent right away, then one per 100ms
+        assert!(chunks[0].1 - start < Duration::from_millis(50));
+        assert!(chunks[2].1 - start >= Duration::from_millis(190));
+    }
+
+    #[actix_rt::test]
+    async fn shares_connection_bucket() {
+        let mut data = Extensions::new();
+        Throttle::on_connect(&(), &mut data);
+        let bucket = &data.get::<ConnectionBucket>().unwrap().0;
+
+        let start = Instant::now();
+        let body = |bucket: &Rc<RefCell<TokenBucket>>| {
+            ThrottledBody::with_bucket(vec![0u8; 1000], 10_000, 1000, Rc::clone(bucket))
+        };
+
+        // the burst is spent by the first response, so the second one has to wait for a refill
+        let first = chunks(body(bucket)).await;
+        let second = chunks(body(bucket)).await;
+
+        assert!(first[0].1 - start < Duration::from_millis(50));
+        assert!(second[0].1 - start >= Duration::from_millis(90));
+    }
+
+    async fn index() -> HttpResponse {
+        HttpResponse::Ok().body("hello")
+    }
+
+    #[actix_rt::test]
+    async fn middleware() {
+        let srv = test::init_service(
+            App::new()
+                .wrap(Throttle::new(1_000_000))
+                .route("/", web::get().to(index)),
+        )
+        .await;
+
+        let res = test::call_service(&srv, TestRequest::default().to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.response().body().size(), BodySize::Sized(5));
+        assert_eq!(body::to_bytes(res.into_body()).await.ok().unwrap(), "hello");
+    }
+}
@@ -0,0 +1,336 @@
+//! For middleware documentation, see [`Timeout`].
+
+use std::{
+    cell::Cell,
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_rt::time::{sleep_until, Instant, Sleep};
+use actix_service::{Service, Transform};
+use actix_utils::future::{ready, Ready};
+use pin_project_lite::pin_project;
+
+use crate::{
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpMessage as _, HttpResponse, ResponseError,
+};
+
+/// Middleware for limiting the time spent handling a request.
+///
+/// If the wrapped service does not produce a response before the deadline, its future is dropped,
+/// cancelling any work it was awaiting, and the request fails with `503 Service Unavailable`. Use
+/// [`status`](Self::status) to respond with `504 Gateway Timeout` instead, and
+/// [`retry_after`](Self::retry_after) to tell clients when to try again. The deadline only covers
+/// producing the response; streaming its body is not limited.
+///
+/// # Overriding Timeouts
+/// A `Timeout` wrapping a scope or resource inside another `Timeout` does not start a second
+/// timer; it replaces the deadline of the outer one for the requests it handles. This allows a
+/// longer timeout for known slow routes, such as uploads, as well as a shorter one. The response
+/// sent on expiry is still configured by the outer middleware.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use actix_web::{http::StatusCode, middleware::Timeout, web, App, HttpResponse};
+///
+/// let app = App::new()
+///     .wrap(
+///         Timeout::new(Duration::from_secs(5))
+///             .status(StatusCode::GATEWAY_TIMEOUT)
+///             .retry_after(Duration::from_secs(30)),
+///     )
+///     .service(
+///         web::scope("/reports")
+///             .wrap(Timeout::new(Duration::from_secs(60)))
+///             .route("", web::get().to(HttpResponse::Ok)),
+///     );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Timeout {
+    inner: Rc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    timeout: Duration,
+    status: StatusCode,
+    retry_after: Option<Duration>,
+}
+
+impl Timeout {
+    /// Constructs a middleware failing requests not handled within `timeout`.
+    pub fn new(timeout: Duration) -> Self {
+        Timeout {
+            inner: Rc::new(Inner {
+                timeout,
+                status: StatusCode::SERVICE_UNAVAILABLE,
+                retry_after: None,
+            }),
+        }
+    }
+
+    /// Sets the status code of responses to timed out requests.
+    ///
+    /// Defaults to `503 Service Unavailable`.
+    ///
+    /// # Panics
+    /// Panics if `status` is not a server error (5xx) status code, or if called after the
+    /// middleware has been cloned.
+    pub fn status(mut self, status: StatusCode) -> Self {
+        assert!(
+            status.is_server_error(),
+            "timeout status must be a server error"
+        );
+        self.inner_mut().status = status;
+        self
+    }
+
+    /// Sets a `Retry-After` header on responses to timed out requests.
+    ///
+    /// The delay is sent in whole seconds, rounded up.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has been cloned.
+    pub fn retry_after(mut self, delay: Duration) -> Self {
+        self.inner_mut().retry_after = Some(delay);
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Timeout must be configured before cloning")
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Timeout
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = TimeoutMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(TimeoutMiddleware {
+            service,
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+/// Deadline of the outermost [`Timeout`], shared with the ones it wraps.
+#[derive(Clone)]
+struct Deadline(Rc<Cell<Instant>>);
+
+#[doc(hidden)]
+pub struct TimeoutMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for TimeoutMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = TimeoutFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let deadline = Instant::now() + self.inner.timeout;
+
+        let outer = req.extensions().get::<Deadline>().cloned();
+
+        let timer = match outer {
+            Some(Deadline(outer)) => {
+                outer.set(deadline);
+                None
+            }
+
+            None => {
+                let shared = Rc::new(Cell::new(deadline));
+                req.extensions_mut().insert(Deadline(Rc::clone(&shared)));
+                Some((shared, Rc::clone(&self.inner)))
+            }
+        };
+
+        TimeoutFuture {
+            sleep: timer.as_ref().map(|_| sleep_until(deadline)),
+            timer,
+            fut: self.service.call(req),
+            _body: PhantomData,
+        }
+    }
+}
+
+pin_project! {
+    #[doc(hidden)]
+    pub struct TimeoutFuture<S: Service<ServiceRequest>, B> {
+        #[pin]
+        fut: S::Future,
+        #[pin]
+        sleep: Option<Sleep>,
+        timer: Option<(Rc<Cell<Instant>>, Rc<Inner>)>,
+        _body: PhantomData<B>,
+    }
+}
+
+impl<S, B> Future for TimeoutFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = Result<ServiceResponse<B>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        if let Poll::Ready(res) = this.fut.poll(cx) {
+            return Poll::Ready(res);
+        }
+
+        let (mut sleep, (deadline, inner)) =
+            match (this.sleep.as_pin_mut(), this.timer.as_ref()) {
+                (Some(sleep), Some(timer)) => (sleep, timer),
+                // an outer timeout is responsible for the deadline
+                _ => return Poll::Pending,
+            };
+
+        // wrapped timeouts may have moved the deadline since the last poll
+        if sleep.deadline() != deadline.get() {
+            sleep.as_mut().reset(deadline.get());
+        }
+
+        match sleep.poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(TimedOut {
+                status: inner.status,
+                retry_after: inner.retry_after,
+            }
+            .into())),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Error returned for requests not handled before their deadline.
+#[derive(Debug)]
+struct TimedOut {
+    status: StatusCode,
+    retry_after: Option<Duration>,
+}
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Request timed out")
+    }
+}
+
+impl ResponseError for TimedOut {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut res = HttpResponse::build(self.status);
+
+        if let Some(delay) = self.retry_after {
+            let secs = super::retry_after_secs(delay);
+            res.insert_header((header::RETRY_AFTER, HeaderValue::from(secs)));
+        }
+
+        res.content_type(mime::TEXT_PLAIN_UTF_8)
+            .body(self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::MessageBody,
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    async fn wait(ms: u64) -> HttpResponse {
+        actix_rt::time::sleep(Duration::from_millis(ms)).await;
+        HttpResponse::Ok().finish()
+    }
+
+    async fn status<S, B>(app: &S, path: &str) -> StatusCode
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = Error>,
+        B: MessageBody,
+    {
+        let req = TestRequest::get().uri(path).to_request();
+        match app.call(req).await {
+            Ok(res) => res.status(),
+            Err(err) => err.error_response().status(),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn times_out_slow_handlers() {
+        let app = test::init_service(
+            App::new()
+                .wrap(
+                    Timeout::new(Duration::from_millis(20))
+                        .status(StatusCode::GATEWAY_TIMEOUT)
+                        .retry_after(Duration::from_millis(1500)),
+                )
+                .route("/fast", web::get().to(HttpResponse::Ok))
+                .route("/slow", web::get().to(|| wait(100))),
+        )
+        .await;
+
+        assert_eq!(status(&app, "/fast").await, StatusCode::OK);
+
+        let req = TestRequest::get().uri("/slow").to_request();
+        let res = app.call(req).await.unwrap_err().error_response();
+        assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "2");
+
+        let body = crate::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "Request timed out");
+    }
+
+    #[actix_rt::test]
+    async fn scope_overrides_deadline() {
+        let app = test::init_service(
+            App::new()
+                .wrap(Timeout::new(Duration::from_millis(20)))
+                .route("/slow", web::get().to(|| wait(100)))
+                .service(
+                    web::scope("/longer")
+                        .wrap(Timeout::new(Duration::from_secs(10)))
+                        .route("", web::get().to(|| wait(100))),
+                )
+                .service(
+                    web::scope("/shorter")
+                        .wrap(Timeout::new(Duration::from_millis(1)))
+                        .route("", web::get().to(|| wait(10))),
+                ),
+        )
+        .await;
+
+        assert_eq!(status(&app, "/slow").await, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(status(&app, "/longer").await, StatusCode::OK);
+        assert_eq!(
+            status(&app, "/shorter").await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+}
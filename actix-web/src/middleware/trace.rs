@@ -0,0 +1,581 @@
+//! For middleware documentation, see [`Tracing`].
+
+use std::{
+    cell::Cell,
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use actix_utils::future::{ready, Ready};
+use futures_core::ready;
+use pin_project_lite::pin_project;
+use tracing::{field::Empty, Span};
+
+use crate::{
+    dev::{Service, Transform},
+    error::ParseError,
+    http::header::{Header, HeaderName, HeaderValue, InvalidHeaderValue, TryIntoHeaderValue},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpMessage,
+};
+
+/// Name of the W3C trace context header.
+const TRACEPARENT: &str = "traceparent";
+
+thread_local! {
+    /// Context of the request whose service future is being polled on this thread.
+    static CURRENT: Cell<Option<TraceContext>> = Cell::new(None);
+}
+
+/// Makes a trace context current until dropped, then restores the previous one.
+struct CurrentGuard {
+    prev: Option<TraceContext>,
+}
+
+impl CurrentGuard {
+    fn enter(ctx: TraceContext) -> Self {
+        CurrentGuard {
+            prev: CURRENT.with(|current| current.replace(Some(ctx))),
+        }
+    }
+}
+
+impl Drop for CurrentGuard {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.set(self.prev));
+    }
+}
+
+/// Middleware for creating a [`tracing`] span for each request.
+///
+/// The span is named `HTTP request` and is entered whenever the request is being processed by
+/// inner services, so events emitted by handlers are attributed to it. It records the following
+/// fields:
+/// - `http.method` and `http.target`: the request method and path;
+/// - `http.route`: the matched resource pattern, once routing has completed;
+/// - `http.status_code`: the response status;
+/// - `http.duration_ms`: the time taken to produce the response;
+/// - `trace_id`, `span_id` and `parent_span_id`: the W3C [trace context](TraceContext).
+///
+/// If the request carries a valid `traceparent` header, the request joins that trace; otherwise a
+/// new trace is started. The resulting [`TraceContext`] is stored in the request extensions, where
+/// handlers can pick it up with [`ReqData<TraceContext>`](crate::web::ReqData), and is returned by
+/// [`TraceContext::current`] while the request is processed. It is propagated to outbound `awc`
+/// requests by wrapping the client in `TracePropagation` (with the `awc` feature), or by calling
+/// [`TraceContext::child`] and sending it as a header.
+///
+/// An `ERROR` level event is emitted for server error (5xx) responses and for errors returned by
+/// the inner service. Errors raised by the HTTP dispatcher itself, outside of any request, are
+/// already emitted as `tracing` events by `actix-http`.
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     middleware::{TraceContext, Tracing},
+///     web, App, HttpResponse,
+/// };
+///
+/// async fn index(trace: web::ReqData<TraceContext>) -> HttpResponse {
+///     // pass the trace on to the next service, e.g. with `awc`:
+///     // client.get(url).insert_header(trace.child())
+///     let _outbound = trace.child();
+///
+///     HttpResponse::Ok().finish()
+/// }
+///
+/// let app = App::new()
+///     .wrap(Tracing::default())
+///     .route("/", web::get().to(index));
+/// ```
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct Tracing;
+
+impl Tracing {
+    /// Constructs a new `Tracing` middleware.
+    pub fn new() -> Self {
+        Tracing
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Tracing
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = TracingMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(TracingMiddleware { service }))
+    }
+}
+
+#[doc(hidden)]
+pub struct TracingMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for TracingMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = TracingFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let parent = req.get_header::<TraceContext>();
+
+        let ctx = match parent {
+            Some(ref parent) => parent.child(),
+            None => TraceContext::new_root(),
+        };
+
+        let span = tracing::info_span!(
+            "HTTP request",
+            http.method = %req.method(),
+            http.target = %req.path(),
+            http.route = Empty,
+            http.status_code = Empty,
+            http.duration_ms = Empty,
+            trace_id = %format_args!("{:032x}", ctx.trace_id),
+            span_id = %format_args!("{:016x}", ctx.span_id),
+            parent_span_id = Empty,
+        );
+
+        if let Some(parent) = parent {
+            span.record(
+                "parent_span_id",
+                tracing::field::display(format_args!("{:016x}", parent.span_id)),
+            );
+        }
+
+        req.extensions_mut().insert(ctx);
+
+        let fut = {
+            let _enter = span.enter();
+            let _current = CurrentGuard::enter(ctx);
+            self.service.call(req)
+        };
+
+        TracingFuture {
+            fut,
+            span,
+            ctx,
+            start: Instant::now(),
+            _body: PhantomData,
+        }
+    }
+}
+
+pin_project! {
+    #[doc(hidden)]
+    pub struct TracingFuture<S: Service<ServiceRequest>, B> {
+        #[pin]
+        fut: S::Future,
+        span: Span,
+        ctx: TraceContext,
+        start: Instant,
+        _body: PhantomData<B>,
+    }
+}
+
+impl<S, B> Future for TracingFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = <S::Future as Future>::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _enter = this.span.enter();
+
+        let res = {
+            let _current = CurrentGuard::enter(*this.ctx);
+            ready!(this.fut.poll(cx))
+        };
+
+        let span = &*this.span;
+        span.record("http.duration_ms", this.start.elapsed().as_millis() as u64);
+
+        match res {
+            Ok(ref res) => {
+                if let Some(route) = res.request().match_pattern() {
+                    span.record("http.route", route.as_str());
+                }
+
+                span.record("http.status_code", res.status().as_u16());
+
+                if res.status().is_server_error() {
+                    match res.response().error() {
+                        Some(err) => tracing::error!(error = %err, "request failed"),
+                        None => tracing::error!("request failed"),
+                    }
+                }
+            }
+
+            Err(ref err) => {
+                let status = err.as_response_error().status_code();
+                span.record("http.status_code", status.as_u16());
+                tracing::error!(error = %err, "request failed");
+            }
+        }
+
+        Poll::Ready(res)
+    }
+}
+
+/// W3C trace context, carried in the `traceparent` header, defined
+/// in [Trace Context §3.2](https://www.w3.org/TR/trace-context/#traceparent-header).
+///
+/// Only version `00` is produced; headers from future versions are accepted as long as their
+/// leading fields are valid.
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware::TraceContext, test::TestRequest, HttpMessage as _};
+///
+/// let req = TestRequest::default()
+///     .insert_header(("traceparent", "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"))
+///     .to_http_request();
+///
+/// let ctx = req.get_header::<TraceContext>().unwrap();
+/// assert_eq!(ctx.trace_id(), 0x4bf92f3577b34da6a3ce929d0e0e4736);
+/// assert!(ctx.is_sampled());
+///
+/// let child = ctx.child();
+/// assert_eq!(child.trace_id(), ctx.trace_id());
+/// assert_ne!(child.span_id(), ctx.span_id());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceContext {
+    trace_id: u128,
+    span_id: u64,
+    flags: u8,
+}
+
+impl TraceContext {
+    /// Flag indicating that the caller may have recorded trace data.
+    const FLAG_SAMPLED: u8 = 0x01;
+
+    /// Starts a new, sampled trace with random trace and span IDs.
+    pub fn new_root() -> Self {
+        TraceContext {
+            trace_id: non_zero(rand::random),
+            span_id: non_zero(rand::random),
+            flags: Self::FLAG_SAMPLED,
+        }
+    }
+
+    /// Returns the context of the request that a [`Tracing`] middleware is processing on the
+    /// current thread.
+    ///
+    /// It is set while the inner services of the middleware are called and their futures polled,
+    /// so it is not available in tasks spawned by a handler.
+    pub fn current() -> Option<Self> {
+        CURRENT.with(Cell::get)
+    }
+
+    /// Creates the context for a child span in the same trace, with a new random span ID.
+    pub fn child(&self) -> Self {
+        TraceContext {
+            span_id: non_zero(rand::random),
+            ..*self
+        }
+    }
+
+    /// Returns the trace ID.
+    pub fn trace_id(&self) -> u128 {
+        self.trace_id
+    }
+
+    /// Returns the ID of the span this context describes.
+    pub fn span_id(&self) -> u64 {
+        self.span_id
+    }
+
+    /// Returns true if the `sampled` flag is set.
+    pub fn is_sampled(&self) -> bool {
+        self.flags & Self::FLAG_SAMPLED != 0
+    }
+
+    fn parse_str(value: &str) -> Option<Self> {
+        let mut parts = value.trim().split('-');
+
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let span_id = parts.next()?;
+        let flags = parts.next()?;
+
+        if version.len() != 2 || version == "ff" || !is_lower_hex(version) {
+            return None;
+        }
+
+        // version 00 has exactly four fields; later versions may append more
+        if version == "00" && parts.next().is_some() {
+            return None;
+        }
+
+        if trace_id.len() != 32 || span_id.len() != 16 || flags.len() != 2 {
+            return None;
+        }
+
+        if !is_lower_hex(trace_id) || !is_lower_hex(span_id) || !is_lower_hex(flags) {
+            return None;
+        }
+
+        let ctx = TraceContext {
+            trace_id: u128::from_str_radix(trace_id, 16).ok()?,
+            span_id: u64::from_str_radix(span_id, 16).ok()?,
+            flags: u8::from_str_radix(flags, 16).ok()?,
+        };
+
+        // all-zero IDs are invalid
+        if ctx.trace_id == 0 || ctx.span_id == 0 {
+            return None;
+        }
+
+        Some(ctx)
+    }
+}
+
+/// Returns a non-zero random value from the given source.
+fn non_zero<T: Default + PartialEq>(mut random: impl FnMut() -> T) -> T {
+    loop {
+        let val = random();
+
+        if val != T::default() {
+            return val;
+        }
+    }
+}
+
+fn is_lower_hex(val: &str) -> bool {
+    val.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+impl fmt::Display for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "00-{:032x}-{:016x}-{:02x}",
+            self.trace_id, self.span_id, self.flags
+        )
+    }
+}
+
+impl Header for TraceContext {
+    fn name() -> HeaderName {
+        HeaderName::from_static(TRACEPARENT)
+    }
+
+    fn parse<M: HttpMessage>(msg: &M) -> Result<Self, ParseError> {
+        msg.headers()
+            .get(TRACEPARENT)
+            .and_then(|val| val.to_str().ok())
+            .and_then(TraceContext::parse_str)
+            .ok_or(ParseError::Header)
+    }
+}
+
+impl TryIntoHeaderValue for TraceContext {
+    type Error = InvalidHeaderValue;
+
+    fn try_into_value(self) -> Result<HeaderValue, Self::Error> {
+        HeaderValue::from_str(&self.to_string())
+    }
+}
+
+/// Client middleware for propagating the [current](TraceContext::current) trace to outbound `awc`
+/// requests.
+///
+/// Requests sent while a [`Tracing`] middleware is processing a request get a `traceparent`
+/// header for a child of that request's context. Requests that already have a `traceparent`
+/// header, or that are sent outside of a traced request, are sent unchanged.
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     middleware::{TracePropagation, Tracing},
+///     web, App, HttpResponse,
+/// };
+///
+/// async fn index(client: web::Data<awc::Client>) -> HttpResponse {
+///     // the outbound request joins the trace of the inbound one
+///     let _req = client.get("http://localhost:8081/");
+///
+///     HttpResponse::Ok().finish()
+/// }
+///
+/// let app = App::new()
+///     .app_data(web::Data::new(
+///         awc::Client::builder().wrap(TracePropagation).finish(),
+///     ))
+///     .wrap(Tracing::default())
+///     .route("/", web::get().to(index));
+/// ```
+#[cfg(feature = "awc")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracePropagation;
+
+#[cfg(feature = "awc")]
+impl<S> awc::middleware::Transform<S, awc::ConnectRequest> for TracePropagation
+where
+    S: Service<awc::ConnectRequest>,
+{
+    type Transform = TracePropagationService<S>;
+
+    fn new_transform(self, service: S) -> Self::Transform {
+        TracePropagationService { service }
+    }
+}
+
+#[cfg(feature = "awc")]
+#[doc(hidden)]
+pub struct TracePropagationService<S> {
+    service: S,
+}
+
+#[cfg(feature = "awc")]
+impl<S> Service<awc::ConnectRequest> for TracePropagationService<S>
+where
+    S: Service<awc::ConnectRequest>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: awc::ConnectRequest) -> Self::Future {
+        use actix_http::RequestHeadType;
+
+        use crate::http::header::HeaderMap;
+
+        if let (awc::ConnectRequest::Client(head, _, _), Some(ctx)) =
+            (&mut req, TraceContext::current())
+        {
+            let name = TraceContext::name();
+
+            let has_traceparent = head.as_ref().headers.contains_key(&name)
+                || head
+                    .extra_headers()
+                    .map_or(false, |extra| extra.contains_key(&name));
+
+            if !has_traceparent {
+                // formatted from hex digits only, so always a valid header value
+                if let Ok(value) = ctx.child().try_into_value() {
+                    match head {
+                        RequestHeadType::Owned(head) => {
+                            head.headers.insert(name, value);
+                        }
+                        RequestHeadType::Rc(_, extra) => {
+                            extra.get_or_insert_with(HeaderMap::new).insert(name, value);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.service.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    #[test]
+    fn trace_context_parse() {
+        let ctx =
+            TraceContext::parse_str("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+                .unwrap();
+        assert_eq!(ctx.trace_id(), 0x4bf92f3577b34da6a3ce929d0e0e4736);
+        assert_eq!(ctx.span_id(), 0x00f067aa0ba902b7);
+        assert!(ctx.is_sampled());
+        assert_eq!(
+            ctx.to_string(),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        );
+
+        // future versions may carry extra fields
+        assert!(TraceContext::parse_str(
+            "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-extra"
+        )
+        .is_some());
+
+        for invalid in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+        ] {
+            assert!(TraceContext::parse_str(invalid).is_none(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn trace_context_root_and_child() {
+        let root = TraceContext::new_root();
+        assert!(root.is_sampled());
+
+        let child = root.child();
+        assert_eq!(child.trace_id(), root.trace_id());
+        assert_ne!(child.span_id(), root.span_id());
+
+        let val = child.try_into_value().unwrap();
+        assert_eq!(TraceContext::parse_str(val.to_str().unwrap()), Some(child));
+    }
+
+    #[actix_rt::test]
+    async fn propagates_incoming_context() {
+        async fn echo(ctx: web::ReqData<TraceContext>) -> HttpResponse {
+            assert_eq!(TraceContext::current(), Some(*ctx));
+            HttpResponse::Ok().body(ctx.to_string())
+        }
+
+        let app = test::init_service(
+            App::new()
+                .wrap(Tracing::new())
+                .route("/", web::get().to(echo)),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .insert_header((
+                TRACEPARENT,
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            ))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+
+        let body = test::read_body(res).await;
+        let ctx = TraceContext::parse_str(std::str::from_utf8(&body).unwrap()).unwrap();
+        assert_eq!(ctx.trace_id(), 0x4bf92f3577b34da6a3ce929d0e0e4736);
+        assert_ne!(ctx.span_id(), 0x00f067aa0ba902b7);
+
+        // invalid or missing headers start a new trace
+        let req = TestRequest::default()
+            .insert_header((TRACEPARENT, "garbage"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.status().is_success());
+    }
+}
@@ -0,0 +1,505 @@
+//! PROXY protocol (v1 and v2) support for server listeners.
+//!
+//! See the [specification](https://www.haproxy.org/download/2.6/doc/proxy-protocol.txt).
+
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    str,
+    task::{Context, Poll},
+};
+
+use actix_codec::{AsyncRead, AsyncWrite, ReadBuf};
+use futures_util::future::poll_fn;
+
+/// Signature that starts every v2 header.
+const V2_SIGNATURE: &[u8; 12] = b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Longest v1 header allowed by the specification, including the trailing CRLF.
+const V1_MAX_LEN: usize = 107;
+
+const PP2_TYPE_AUTHORITY: u8 = 0x02;
+const PP2_TYPE_SSL: u8 = 0x20;
+const PP2_SUBTYPE_SSL_VERSION: u8 = 0x21;
+const PP2_SUBTYPE_SSL_CN: u8 = 0x22;
+const PP2_SUBTYPE_SSL_CIPHER: u8 = 0x23;
+const PP2_CLIENT_SSL: u8 = 0x01;
+
+/// Connection details sent by a load balancer in a PROXY protocol header.
+///
+/// Available as connection data on listeners created with
+/// [`HttpServer::bind_proxy_protocol`](crate::HttpServer::bind_proxy_protocol):
+///
+/// ```
+/// use actix_web::{dev::ProxyHeader, HttpRequest};
+///
+/// async fn handler(req: HttpRequest) -> String {
+///     let header = req.conn_data::<ProxyHeader>();
+///     format!("{:?}", header.and_then(|header| header.source()))
+/// }
+/// ```
+///
+/// The client address in the header is also used as the request's
+/// [peer address](crate::HttpRequest::peer_addr).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyHeader {
+    source: Option<SocketAddr>,
+    destination: Option<SocketAddr>,
+    authority: Option<String>,
+    tls: Option<ProxyTlsInfo>,
+}
+
+impl ProxyHeader {
+    /// Returns the address of the client that connected to the proxy.
+    ///
+    /// Returns `None` for health checks made by the proxy itself and for unsupported address
+    /// families.
+    pub fn source(&self) -> Option<SocketAddr> {
+        self.source
+    }
+
+    /// Returns the address the client connected to on the proxy.
+    pub fn destination(&self) -> Option<SocketAddr> {
+        self.destination
+    }
+
+    /// Returns the host name requested by the client (usually from TLS SNI), if sent by the proxy.
+    ///
+    /// Only available with v2 headers.
+    pub fn authority(&self) -> Option<&str> {
+        self.authority.as_deref()
+    }
+
+    /// Returns details of the TLS connection between the client and the proxy, if any.
+    ///
+    /// Only available with v2 headers.
+    pub fn tls(&self) -> Option<&ProxyTlsInfo> {
+        self.tls.as_ref()
+    }
+}
+
+/// TLS details of the client connection, sent by a proxy that terminates TLS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyTlsInfo {
+    version: Option<String>,
+    cipher: Option<String>,
+    common_name: Option<String>,
+    client_cert_verified: bool,
+}
+
+impl ProxyTlsInfo {
+    /// Returns the TLS version, for example `TLSv1.3`.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Returns the name of the negotiated cipher.
+    pub fn cipher(&self) -> Option<&str> {
+        self.cipher.as_deref()
+    }
+
+    /// Returns the common name of the client certificate's subject.
+    pub fn common_name(&self) -> Option<&str> {
+        self.common_name.as_deref()
+    }
+
+    /// Returns true if the client presented a certificate that the proxy verified.
+    pub fn client_cert_verified(&self) -> bool {
+        self.client_cert_verified
+    }
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Reads exactly `buf.len()` bytes, without reading past them.
+async fn read_exact<Io: AsyncRead + Unpin>(io: &mut Io, buf: &mut [u8]) -> io::Result<()> {
+    let mut filled = 0;
+
+    while filled < buf.len() {
+        let n = poll_fn(|cx| {
+            let mut read_buf = ReadBuf::new(&mut buf[filled..]);
+            match Pin::new(&mut *io).poll_read(cx, &mut read_buf) {
+                Poll::Ready(Ok(())) => Poll::Ready(Ok(read_buf.filled().len())),
+                Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+                Poll::Pending => Poll::Pending,
+            }
+        })
+        .await?;
+
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        filled += n;
+    }
+
+    Ok(())
+}
+
+/// Reads a v1 or v2 PROXY protocol header from the start of a connection.
+pub(crate) async fn read_header<Io: AsyncRead + Unpin>(io: &mut Io) -> io::Result<ProxyHeader> {
+    // the shortest v1 header ("PROXY UNKNOWN\r\n") is longer than the v2 signature
+    let mut start = [0; 12];
+    read_exact(io, &mut start).await?;
+
+    if &start == V2_SIGNATURE {
+        let mut head = [0; 4];
+        read_exact(io, &mut head).await?;
+
+        let len = u16::from_be_bytes([head[2], head[3]]) as usize;
+        let mut body = vec![0; len];
+        read_exact(io, &mut body).await?;
+
+        parse_v2(head[0], head[1], &body)
+    } else if start.starts_with(b"PROXY ") {
+        let mut line = start.to_vec();
+
+        // read byte by byte so that no request data is consumed
+        while !line.ends_with(b"\r\n") {
+            if line.len() >= V1_MAX_LEN {
+                return Err(invalid("PROXY protocol v1 header is too long"));
+            }
+
+            let mut byte = [0];
+            read_exact(io, &mut byte).await?;
+            line.push(byte[0]);
+        }
+
+        parse_v1(&line[..line.len() - 2])
+    } else {
+        Err(invalid("missing PROXY protocol header"))
+    }
+}
+
+fn parse_v1(line: &[u8]) -> io::Result<ProxyHeader> {
+    let line = str::from_utf8(line).map_err(|_| invalid("invalid PROXY protocol v1 header"))?;
+    let mut parts = line.split(' ').skip(1);
+
+    let unknown = ProxyHeader {
+        source: None,
+        destination: None,
+        authority: None,
+        tls: None,
+    };
+
+    match parts.next() {
+        Some("TCP4") | Some("TCP6") => {}
+        Some("UNKNOWN") => return Ok(unknown),
+        _ => return Err(invalid("invalid PROXY protocol v1 header")),
+    }
+
+    let mut next = || {
+        parts
+            .next()
+            .ok_or_else(|| invalid("invalid PROXY protocol v1 header"))
+    };
+
+    let src_ip = next()?.parse::<IpAddr>();
+    let dst_ip = next()?.parse::<IpAddr>();
+    let src_port = next()?.parse::<u16>();
+    let dst_port = next()?.parse::<u16>();
+
+    match (src_ip, dst_ip, src_port, dst_port) {
+        (Ok(src_ip), Ok(dst_ip), Ok(src_port), Ok(dst_port)) => Ok(ProxyHeader {
+            source: Some(SocketAddr::new(src_ip, src_port)),
+            destination: Some(SocketAddr::new(dst_ip, dst_port)),
+            ..unknown
+        }),
+        _ => Err(invalid("invalid address in PROXY protocol v1 header")),
+    }
+}
+
+fn parse_v2(ver_cmd: u8, family: u8, body: &[u8]) -> io::Result<ProxyHeader> {
+    if ver_cmd >> 4 != 2 {
+        return Err(invalid("unsupported PROXY protocol version"));
+    }
+
+    let is_local = match ver_cmd & 0x0F {
+        0x0 => true,
+        0x1 => false,
+        _ => return Err(invalid("unsupported PROXY protocol command")),
+    };
+
+    let (addrs, tlvs) = match family >> 4 {
+        // AF_INET
+        0x1 if body.len() >= 12 => {
+            let ip = |at: usize| {
+                IpAddr::V4(Ipv4Addr::new(
+                    body[at],
+                    body[at + 1],
+                    body[at + 2],
+                    body[at + 3],
+                ))
+            };
+            let port = |at: usize| u16::from_be_bytes([body[at], body[at + 1]]);
+
+            let addrs = (
+                SocketAddr::new(ip(0), port(8)),
+                SocketAddr::new(ip(4), port(10)),
+            );
+
+            (Some(addrs), &body[12..])
+        }
+
+        // AF_INET6
+        0x2 if body.len() >= 36 => {
+            let ip = |at: usize| {
+                let mut octets = [0; 16];
+                octets.copy_from_slice(&body[at..at + 16]);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            };
+            let port = |at: usize| u16::from_be_bytes([body[at], body[at + 1]]);
+
+            let addrs = (
+                SocketAddr::new(ip(0), port(32)),
+                SocketAddr::new(ip(16), port(34)),
+            );
+
+            (Some(addrs), &body[36..])
+        }
+
+        // AF_UNIX
+        0x3 if body.len() >= 216 => (None, &body[216..]),
+
+        // AF_UNSPEC
+        0x0 => (None, body),
+
+        _ => return Err(invalid("invalid PROXY protocol v2 address block")),
+    };
+
+    let mut header = ProxyHeader {
+        source: None,
+        destination: None,
+        authority: None,
+        tls: None,
+    };
+
+    // addresses of LOCAL connections (health checks from the proxy) must be ignored
+    if let (Some((source, destination)), false) = (addrs, is_local) {
+        header.source = Some(source);
+        header.destination = Some(destination);
+    }
+
+    for (kind, value) in tlvs_iter(tlvs)? {
+        match kind {
+            PP2_TYPE_AUTHORITY => header.authority = Some(lossy_string(value)),
+            PP2_TYPE_SSL if value.len() >= 5 => {
+                let client = value[0];
+                let verify = u32::from_be_bytes([value[1], value[2], value[3], value[4]]);
+
+                if client & PP2_CLIENT_SSL == 0 {
+                    continue;
+                }
+
+                let mut tls = ProxyTlsInfo {
+                    version: None,
+                    cipher: None,
+                    common_name: None,
+                    client_cert_verified: client & !PP2_CLIENT_SSL != 0 && verify == 0,
+                };
+
+                for (sub_kind, sub_value) in tlvs_iter(&value[5..])? {
+                    match sub_kind {
+                        PP2_SUBTYPE_SSL_VERSION => tls.version = Some(lossy_string(sub_value)),
+                        PP2_SUBTYPE_SSL_CIPHER => tls.cipher = Some(lossy_string(sub_value)),
+                        PP2_SUBTYPE_SSL_CN => tls.common_name = Some(lossy_string(sub_value)),
+                        _ => {}
+                    }
+                }
+
+                header.tls = Some(tls);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(header)
+}
+
+/// Splits a block of type-length-value fields.
+fn tlvs_iter(mut data: &[u8]) -> io::Result<Vec<(u8, &[u8])>> {
+    let mut tlvs = Vec::new();
+
+    while !data.is_empty() {
+        if data.len() < 3 {
+            return Err(invalid("truncated PROXY protocol v2 TLV"));
+        }
+
+        let len = u16::from_be_bytes([data[1], data[2]]) as usize;
+        let value = data
+            .get(3..3 + len)
+            .ok_or_else(|| invalid("truncated PROXY protocol v2 TLV"))?;
+
+        tlvs.push((data[0], value));
+        data = &data[3 + len..];
+    }
+
+    Ok(tlvs)
+}
+
+fn lossy_string(val: &[u8]) -> String {
+    String::from_utf8_lossy(val).into_owned()
+}
+
+/// Stream wrapper carrying the PROXY protocol header read from the start of a connection.
+pub(crate) struct ProxiedStream<Io> {
+    pub(crate) io: Io,
+    pub(crate) header: ProxyHeader,
+}
+
+impl<Io: AsyncRead + Unpin> AsyncRead for ProxiedStream<Io> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_read(cx, buf)
+    }
+}
+
+impl<Io: AsyncWrite + Unpin> AsyncWrite for ProxiedStream<Io> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read(data: &[u8]) -> io::Result<(ProxyHeader, Vec<u8>)> {
+        let mut io = data;
+        let header = read_header(&mut io).await?;
+        Ok((header, io.to_vec()))
+    }
+
+    #[actix_rt::test]
+    async fn v1_header() {
+        let (header, rest) =
+            read(b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 443\r\nGET / HTTP/1.1\r\n")
+                .await
+                .unwrap();
+
+        assert_eq!(header.source(), Some("192.0.2.1:56324".parse().unwrap()));
+        assert_eq!(
+            header.destination(),
+            Some("198.51.100.1:443".parse().unwrap())
+        );
+        assert_eq!(header.tls(), None);
+        assert_eq!(rest, b"GET / HTTP/1.1\r\n");
+
+        let (header, _) = read(b"PROXY TCP6 2001:db8::1 2001:db8::2 1 2\r\n")
+            .await
+            .unwrap();
+        assert_eq!(header.source(), Some("[2001:db8::1]:1".parse().unwrap()));
+
+        let (header, _) = read(b"PROXY UNKNOWN ffff::1 ffff::2 1 2\r\n")
+            .await
+            .unwrap();
+        assert_eq!(header.source(), None);
+
+        assert!(read(b"PROXY TCP4 nope 198.51.100.1 1 2\r\n").await.is_err());
+        assert!(read(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n")
+            .await
+            .is_err());
+        assert!(read(&[b"PROXY TCP4 ".as_ref(), &[b'1'; 200]].concat())
+            .await
+            .is_err());
+    }
+
+    #[actix_rt::test]
+    async fn v2_header() {
+        let mut data = V2_SIGNATURE.to_vec();
+        data.extend_from_slice(&[0x21, 0x11]);
+
+        let mut body = vec![192, 0, 2, 1, 198, 51, 100, 1];
+        body.extend_from_slice(&56324u16.to_be_bytes());
+        body.extend_from_slice(&443u16.to_be_bytes());
+
+        // authority TLV
+        body.extend_from_slice(&[PP2_TYPE_AUTHORITY, 0, 11]);
+        body.extend_from_slice(b"example.com");
+
+        // SSL TLV with version and cipher sub-TLVs, client certificate verified
+        let mut ssl = vec![PP2_CLIENT_SSL | 0x02, 0, 0, 0, 0];
+        ssl.extend_from_slice(&[PP2_SUBTYPE_SSL_VERSION, 0, 7]);
+        ssl.extend_from_slice(b"TLSv1.3");
+        ssl.extend_from_slice(&[PP2_SUBTYPE_SSL_CIPHER, 0, 22]);
+        ssl.extend_from_slice(b"TLS_AES_128_GCM_SHA256");
+        body.extend_from_slice(&[PP2_TYPE_SSL, 0, ssl.len() as u8]);
+        body.extend_from_slice(&ssl);
+
+        data.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        data.extend_from_slice(&body);
+        data.extend_from_slice(b"GET /");
+
+        let (header, rest) = read(&data).await.unwrap();
+        assert_eq!(header.source(), Some("192.0.2.1:56324".parse().unwrap()));
+        assert_eq!(
+            header.destination(),
+            Some("198.51.100.1:443".parse().unwrap())
+        );
+        assert_eq!(header.authority(), Some("example.com"));
+
+        let tls = header.tls().unwrap();
+        assert_eq!(tls.version(), Some("TLSv1.3"));
+        assert_eq!(tls.cipher(), Some("TLS_AES_128_GCM_SHA256"));
+        assert_eq!(tls.common_name(), None);
+        assert!(tls.client_cert_verified());
+
+        assert_eq!(rest, b"GET /");
+    }
+
+    #[actix_rt::test]
+    async fn v2_local_and_invalid() {
+        // LOCAL command with addresses, which must be ignored
+        let mut data = V2_SIGNATURE.to_vec();
+        data.extend_from_slice(&[0x20, 0x11, 0, 12]);
+        data.extend_from_slice(&[127, 0, 0, 1, 127, 0, 0, 1, 0, 1, 0, 2]);
+
+        let (header, _) = read(&data).await.unwrap();
+        assert_eq!(header.source(), None);
+
+        // wrong version
+        let mut data = V2_SIGNATURE.to_vec();
+        data.extend_from_slice(&[0x11, 0x00, 0, 0]);
+        assert!(read(&data).await.is_err());
+
+        // truncated TLV
+        let mut data = V2_SIGNATURE.to_vec();
+        data.extend_from_slice(&[0x21, 0x00, 0, 3, PP2_TYPE_AUTHORITY, 0, 10]);
+        assert!(read(&data).await.is_err());
+
+        // truncated body
+        let mut data = V2_SIGNATURE.to_vec();
+        data.extend_from_slice(&[0x21, 0x11, 0, 12, 1, 2, 3]);
+        assert!(read(&data).await.is_err());
+    }
+}
@@ -0,0 +1,334 @@
+//! For reloading documentation, see [`AppReloader`].
+
+use std::{
+    cell::RefCell,
+    fmt,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    task::{Context, Poll},
+};
+
+use actix_http::Request;
+use actix_service::{
+    boxed::{self, BoxService, BoxServiceFactory},
+    IntoServiceFactory, Service, ServiceFactory, ServiceFactoryExt as _,
+};
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    body::MessageBody,
+    config::AppConfig,
+    dev::{ServiceRequest, ServiceResponse},
+    App, Error,
+};
+
+type AppFactory = BoxServiceFactory<AppConfig, Request, ServiceResponse, Error, ()>;
+type AppService = BoxService<Request, ServiceResponse, Error>;
+type AppFactoryFn = Arc<dyn Fn() -> AppFactory + Send + Sync>;
+
+/// Handle for replacing the application of a running server.
+///
+/// Serve the application returned by [`app`](Self::app) from the `HttpServer::new` closure; a
+/// call to [`reload`](Self::reload) then swaps the application of every worker for a new one,
+/// without restarting the server or dropping connections. This allows changing routes, for
+/// example when feature flags change or plugins are loaded.
+///
+/// Each worker builds the new application when it handles its next request, and keeps serving
+/// requests with the old one until it is ready, so a reload never delays requests. Requests that
+/// are in flight when the swap happens complete on the old application, which is dropped once
+/// they have all finished. If the new application fails to build, an error is logged and the
+/// worker keeps the old one.
+///
+/// Since applications are rebuilt by the workers, data shared between applications, such as
+/// database pools, must be created outside the factories and cloned into them.
+///
+/// # Examples
+/// ```no_run
+/// use actix_web::{web, App, AppReloader, HttpResponse, HttpServer};
+///
+/// #[actix_web::main]
+/// async fn main() -> std::io::Result<()> {
+///     let reloader = AppReloader::new(|| App::new().route("/", web::get().to(HttpResponse::Ok)));
+///
+///     let app = reloader.clone();
+///     let srv = HttpServer::new(move || app.app()).bind("127.0.0.1:8080")?.run();
+///
+///     // later, e.g. when a feature flag is enabled
+///     reloader.reload(|| {
+///         App::new()
+///             .route("/", web::get().to(HttpResponse::Ok))
+///             .route("/beta", web::get().to(HttpResponse::Ok))
+///     });
+///
+///     srv.await
+/// }
+/// ```
+#[derive(Clone)]
+pub struct AppReloader {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    /// Generation of the latest factory, readable without locking.
+    generation: AtomicU64,
+    factory: RwLock<(u64, AppFactoryFn)>,
+}
+
+impl AppReloader {
+    /// Constructs a handle serving applications created by `factory`.
+    pub fn new<F, T, B>(factory: F) -> Self
+    where
+        F: Fn() -> App<T> + Send + Sync + 'static,
+        T: ServiceFactory<
+                ServiceRequest,
+                Config = (),
+                Response = ServiceResponse<B>,
+                Error = Error,
+                InitError = (),
+            > + 'static,
+        B: MessageBody + 'static,
+    {
+        AppReloader {
+            shared: Arc::new(Shared {
+                generation: AtomicU64::new(0),
+                factory: RwLock::new((0, boxed_factory(factory))),
+            }),
+        }
+    }
+
+    /// Replaces the application factory, swapping the application of every worker.
+    pub fn reload<F, T, B>(&self, factory: F)
+    where
+        F: Fn() -> App<T> + Send + Sync + 'static,
+        T: ServiceFactory<
+                ServiceRequest,
+                Config = (),
+                Response = ServiceResponse<B>,
+                Error = Error,
+                InitError = (),
+            > + 'static,
+        B: MessageBody + 'static,
+    {
+        let mut current = self.shared.factory.write().unwrap();
+        let generation = current.0 + 1;
+        *current = (generation, boxed_factory(factory));
+        self.shared.generation.store(generation, Ordering::Release);
+    }
+
+    /// Returns the number of times the application factory has been replaced.
+    pub fn generation(&self) -> u64 {
+        self.shared.generation.load(Ordering::Acquire)
+    }
+
+    /// Returns the application to serve, to be returned from the `HttpServer::new` closure.
+    pub fn app(&self) -> ReloadableApp {
+        ReloadableApp {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    fn current(shared: &Shared) -> (u64, AppFactoryFn) {
+        let current = shared.factory.read().unwrap();
+        (current.0, Arc::clone(&current.1))
+    }
+}
+
+impl fmt::Debug for AppReloader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AppReloader")
+            .field("generation", &self.generation())
+            .finish_non_exhaustive()
+    }
+}
+
+fn boxed_factory<F, T, B>(factory: F) -> AppFactoryFn
+where
+    F: Fn() -> App<T> + Send + Sync + 'static,
+    T: ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<B>,
+            Error = Error,
+            InitError = (),
+        > + 'static,
+    B: MessageBody + 'static,
+{
+    Arc::new(move || {
+        boxed::factory(
+            factory()
+                .into_factory()
+                .map(ServiceResponse::map_into_boxed_body),
+        )
+    })
+}
+
+#[doc(hidden)]
+pub struct ReloadableApp {
+    shared: Arc<Shared>,
+}
+
+impl ServiceFactory<Request> for ReloadableApp {
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Config = AppConfig;
+    type Service = ReloadableAppService;
+    type InitError = ();
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, config: AppConfig) -> Self::Future {
+        let shared = Arc::clone(&self.shared);
+        let (generation, factory) = AppReloader::current(&shared);
+        let fut = factory().new_service(config.clone());
+
+        Box::pin(async move {
+            let service = fut.await?;
+
+            Ok(ReloadableAppService {
+                shared,
+                config,
+                local: Rc::new(RefCell::new(Local {
+                    service: Rc::new(service),
+                    generation,
+                    target: generation,
+                })),
+            })
+        })
+    }
+}
+
+/// Application of a worker.
+struct Local {
+    service: Rc<AppService>,
+
+    /// Generation of the factory that built `service`.
+    generation: u64,
+
+    /// Generation of the latest factory the worker started building.
+    target: u64,
+}
+
+#[doc(hidden)]
+pub struct ReloadableAppService {
+    shared: Arc<Shared>,
+    config: AppConfig,
+    local: Rc<RefCell<Local>>,
+}
+
+impl ReloadableAppService {
+    /// Starts building the application from a newer factory, if there is one.
+    fn check_reload(&self) {
+        if self.shared.generation.load(Ordering::Acquire) == self.local.borrow().target {
+            return;
+        }
+
+        let (generation, factory) = AppReloader::current(&self.shared);
+        self.local.borrow_mut().target = generation;
+
+        let fut = factory().new_service(self.config.clone());
+        let local = Rc::clone(&self.local);
+
+        actix_rt::spawn(async move {
+            match fut.await {
+                Ok(service) => {
+                    let mut local = local.borrow_mut();
+
+                    // a build for a later generation may have completed first
+                    if generation > local.generation {
+                        local.service = Rc::new(service);
+                        local.generation = generation;
+                    }
+                }
+                Err(_) => {
+                    log::error!("Can not construct reloaded application, keeping current one")
+                }
+            }
+        });
+    }
+}
+
+impl Service<Request> for ReloadableAppService {
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.check_reload();
+        self.local.borrow().service.poll_ready(cx)
+    }
+
+    fn call(&self, req: Request) -> Self::Future {
+        self.check_reload();
+
+        // in-flight requests keep their application alive until they complete
+        let service = Rc::clone(&self.local.borrow().service);
+        Box::pin(async move { service.call(req).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix_rt::time::sleep;
+
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        test::{call_service, init_service, read_body, TestRequest},
+        web, HttpResponse,
+    };
+
+    async fn v1() -> &'static str {
+        "v1"
+    }
+
+    async fn v2() -> &'static str {
+        "v2"
+    }
+
+    async fn slow() -> &'static str {
+        sleep(Duration::from_millis(50)).await;
+        "slow"
+    }
+
+    #[actix_rt::test]
+    async fn swaps_app() {
+        let reloader = AppReloader::new(|| {
+            App::new()
+                .route("/", web::get().to(v1))
+                .route("/slow", web::get().to(slow))
+        });
+        let srv = init_service(reloader.app()).await;
+
+        let res = call_service(&srv, TestRequest::default().to_request()).await;
+        assert_eq!(read_body(res).await, "v1");
+
+        // in-flight request started on the old application
+        let in_flight = srv.call(TestRequest::with_uri("/slow").to_request());
+
+        reloader.reload(|| {
+            App::new()
+                .route("/", web::get().to(v2))
+                .route("/new", web::get().to(HttpResponse::Ok))
+        });
+        assert_eq!(reloader.generation(), 1);
+
+        // the first request after a reload triggers the rebuild
+        let res = call_service(&srv, TestRequest::default().to_request()).await;
+        assert_eq!(read_body(res).await, "v1");
+        actix_rt::task::yield_now().await;
+
+        let res = call_service(&srv, TestRequest::default().to_request()).await;
+        assert_eq!(read_body(res).await, "v2");
+
+        let res = call_service(&srv, TestRequest::with_uri("/new").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // the old application finishes the in-flight request
+        let res = in_flight.await.unwrap();
+        assert_eq!(read_body(res).await, "slow");
+    }
+}
@@ -5,7 +5,7 @@ use std::{
     str,
 };
 
-use actix_http::{Message, RequestHead};
+use actix_http::{h1::InformationalSender, Message, RequestHead};
 use actix_router::{Path, Url};
 use actix_utils::future::{ok, Ready};
 #[cfg(feature = "cookies")]
@@ -15,12 +15,12 @@ use smallvec::SmallVec;
 use crate::{
     app_service::AppInitServiceState,
     config::AppConfig,
-    dev::{Extensions, Payload},
+    dev::{Extensions, Payload, ProxyHeader},
     error::UrlGenerationError,
     http::{header::HeaderMap, Method, Uri, Version},
     info::ConnectionInfo,
     rmap::ResourceMap,
-    Error, FromRequest, HttpMessage,
+    Error, FromRequest, HttpMessage, HttpResponse,
 };
 
 #[cfg(feature = "cookies")]
@@ -181,6 +181,36 @@ impl HttpRequest {
             .and_then(|container| container.get::<T>())
     }
 
+    /// Sends an informational (`1xx`) response, such as `103 Early Hints`, ahead of the final
+    /// response.
+    ///
+    /// Only the response head is sent. Returns false if the response was not sent because its
+    /// status is not informational or is `101 Switching Protocols`, because the final response has
+    /// already been sent, or because the connection does not support it. Informational responses
+    /// are currently only sent to HTTP/1.1 clients.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{http::{header, StatusCode}, HttpRequest, HttpResponse};
+    ///
+    /// async fn index(req: HttpRequest) -> HttpResponse {
+    ///     req.send_informational(
+    ///         HttpResponse::build(StatusCode::from_u16(103).unwrap())
+    ///             .insert_header((header::LINK, "</style.css>; rel=preload; as=style"))
+    ///             .finish(),
+    ///     );
+    ///
+    ///     // ... render the page ...
+    ///     HttpResponse::Ok().finish()
+    /// }
+    /// ```
+    pub fn send_informational(&self, res: HttpResponse) -> bool {
+        match self.conn_data::<InformationalSender>() {
+            Some(sender) => sender.send(self.head(), res.into()),
+            None => false,
+        }
+    }
+
     /// Generates URL for a named resource.
     ///
     /// This substitutes in sequence all URL parameters that appear in the resource itself and in
@@ -191,6 +221,10 @@ impl HttpRequest {
     /// beforehand without worrying about double encoding. Any other character that is not valid in
     /// a URL path context is escaped using percent-encoding.
     ///
+    /// For resources that are not [external](crate::App::external_resource), the scheme and host
+    /// of the generated URL are taken from [`connection_info`](Self::connection_info), so they
+    /// honor `Forwarded` and `X-Forwarded-*` headers set by a reverse proxy.
+    ///
     /// # Examples
     /// ```
     /// # use actix_web::{web, App, HttpRequest, HttpResponse};
@@ -253,7 +287,8 @@ impl HttpRequest {
     #[inline]
     pub fn connection_info(&self) -> Ref<'_, ConnectionInfo> {
         if !self.extensions().contains::<ConnectionInfo>() {
-            let info = ConnectionInfo::new(self.head(), &*self.app_config());
+            let proxy = self.conn_data::<ProxyHeader>();
+            let info = ConnectionInfo::new(self.head(), self.app_config(), proxy);
             self.extensions_mut().insert(info);
         }
 
@@ -591,6 +626,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_url_for_forwarded() {
+        let mut rdef = ResourceDef::new("/user/{id}");
+        rdef.set_name("user_detail");
+
+        let mut rmap = ResourceMap::new(ResourceDef::prefix(""));
+        rmap.add(&mut rdef, None);
+
+        let req = TestRequest::default()
+            .insert_header((header::HOST, "10.0.0.1:8080"))
+            .insert_header(("x-forwarded-proto", "https"))
+            .insert_header(("x-forwarded-host", "www.rust-lang.org"))
+            .rmap(rmap.clone())
+            .to_http_request();
+        let url = req.url_for("user_detail", ["42"]);
+        assert_eq!(
+            url.ok().unwrap().as_str(),
+            "https://www.rust-lang.org/user/42"
+        );
+
+        let req = TestRequest::default()
+            .insert_header((header::HOST, "10.0.0.1:8080"))
+            .insert_header((header::FORWARDED, "proto=https; host=rust-lang.org"))
+            .rmap(rmap)
+            .to_http_request();
+        let url = req.url_for("user_detail", ["42"]);
+        assert_eq!(url.ok().unwrap().as_str(), "https://rust-lang.org/user/42");
+    }
+
     #[test]
     fn test_url_for_static() {
         let mut rdef = ResourceDef::new("/index.html");
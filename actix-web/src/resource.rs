@@ -15,6 +15,7 @@ use crate::{
     dev::{ensure_leading_slash, AppService, ResourceDef},
     guard::Guard,
     handler::Handler,
+    http::header,
     route::{Route, RouteService},
     service::{
         BoxedHttpService, BoxedHttpServiceFactory, HttpServiceFactory, ServiceRequest,
@@ -40,8 +41,9 @@ use crate::{
 ///         .route(web::get().to(|| HttpResponse::Ok())));
 /// ```
 ///
-/// If no matching route could be found, *405* response code get returned. Default behavior could be
-/// overridden with `default_resource()` method.
+/// If no matching route could be found, *405* response code get returned, with an `Allow` header
+/// listing the methods of the method guards of the resource's routes. Default behavior could be
+/// overridden with `default_service()` method.
 pub struct Resource<T = ResourceEndpoint> {
     endpoint: T,
     rdef: Patterns,
@@ -49,7 +51,7 @@ pub struct Resource<T = ResourceEndpoint> {
     routes: Vec<Route>,
     app_data: Option<Extensions>,
     guards: Vec<Box<dyn Guard>>,
-    default: BoxedHttpServiceFactory,
+    default: Option<BoxedHttpServiceFactory>,
     factory_ref: Rc<RefCell<Option<ResourceFactory>>>,
 }
 
@@ -65,9 +67,7 @@ impl Resource {
             factory_ref: fref,
             guards: Vec::new(),
             app_data: None,
-            default: boxed::factory(fn_service(|req: ServiceRequest| async {
-                Ok(req.into_response(HttpResponse::MethodNotAllowed()))
-            })),
+            default: None,
         }
     }
 }
@@ -314,7 +314,9 @@ where
     /// You can use a [`Route`] as default service.
     ///
     /// If a default service is not registered, an empty `405 Method Not Allowed` response will be
-    /// sent to the client instead. Unlike [`Scope`](crate::Scope)s, a [`Resource`] does **not**
+    /// sent to the client instead. Its `Allow` header lists the methods of the method guards of
+    /// this resource's routes, such as [`web::get`](crate::web::get) or [`guard::Get`], and is
+    /// omitted if there are none. Unlike [`Scope`](crate::Scope)s, a [`Resource`] does **not**
     /// inherit its parent's default service.
     pub fn default_service<F, U>(mut self, f: F) -> Self
     where
@@ -328,10 +330,9 @@ where
         U::InitError: fmt::Debug,
     {
         // create and configure default resource
-        self.default = boxed::factory(
-            f.into_factory()
-                .map_init_err(|e| log::error!("Can not construct default service: {:?}", e)),
-        );
+        self.default = Some(boxed::factory(f.into_factory().map_init_err(|e| {
+            log::error!("Can not construct default service: {:?}", e)
+        })));
 
         self
     }
@@ -365,10 +366,12 @@ where
             rdef.set_name(name);
         }
 
-        *self.factory_ref.borrow_mut() = Some(ResourceFactory {
-            routes: self.routes,
-            default: self.default,
-        });
+        let routes = self.routes;
+        let default = self
+            .default
+            .unwrap_or_else(|| method_not_allowed_service(&routes));
+
+        *self.factory_ref.borrow_mut() = Some(ResourceFactory { routes, default });
 
         let resource_data = self.app_data.map(Rc::new);
 
@@ -387,6 +390,33 @@ where
     }
 }
 
+/// Constructs the default `405 Method Not Allowed` service for a resource with the given routes.
+///
+/// The `Allow` header lists the methods of the routes' method guards and is omitted if there are
+/// none.
+fn method_not_allowed_service(routes: &[Route]) -> BoxedHttpServiceFactory {
+    let mut methods = Vec::new();
+
+    for method in routes.iter().flat_map(Route::methods) {
+        if !methods.contains(method) {
+            methods.push(method.clone());
+        }
+    }
+
+    let allow = (!methods.is_empty()).then(|| header::Allow(methods));
+
+    boxed::factory(fn_service(move |req: ServiceRequest| {
+        let mut res = HttpResponse::MethodNotAllowed();
+
+        if let Some(ref allow) = allow {
+            res.insert_header(allow.clone());
+        }
+
+        let res = res.finish();
+        async { Ok(req.into_response(res)) }
+    }))
+}
+
 pub struct ResourceFactory {
     routes: Vec<Route>,
     default: BoxedHttpServiceFactory,
@@ -644,6 +674,67 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[actix_rt::test]
+    async fn test_method_not_allowed_allow_header() {
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/test")
+                        .route(web::get().to(HttpResponse::Ok))
+                        .route(web::post().to(HttpResponse::Ok))
+                        .route(
+                            web::get()
+                                .guard(guard::Header("x-v2", "1"))
+                                .to(HttpResponse::Ok),
+                        )
+                        .route(web::route().guard(guard::Put()).to(HttpResponse::Ok))
+                        // nested method guards are not listed
+                        .route(
+                            web::route()
+                                .guard(guard::Not(guard::Delete()))
+                                .guard(guard::Any(guard::Patch()))
+                                .to(HttpResponse::Ok),
+                        ),
+                )
+                .service(
+                    web::resource("/header").route(
+                        web::route()
+                            .guard(guard::Header("x-v2", "1"))
+                            .to(HttpResponse::Ok),
+                    ),
+                )
+                .service(
+                    web::resource("/custom")
+                        .route(web::get().to(HttpResponse::Ok))
+                        .default_service(web::to(HttpResponse::BadRequest)),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/test")
+            .method(Method::DELETE)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(
+            resp.headers().get(header::ALLOW).unwrap(),
+            HeaderValue::from_static("GET, POST, PUT")
+        );
+
+        // no method guards, so no methods to list
+        let req = TestRequest::with_uri("/header").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert!(resp.headers().get(header::ALLOW).is_none());
+
+        let req = TestRequest::with_uri("/custom")
+            .method(Method::DELETE)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert!(resp.headers().get(header::ALLOW).is_none());
+    }
+
     #[actix_rt::test]
     async fn test_resource_guards() {
         let srv = init_service(
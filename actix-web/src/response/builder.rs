@@ -215,6 +215,23 @@ impl HttpResponseBuilder {
         self
     }
 
+    /// Set a `Content-Disposition` header that prompts the user agent to download the response
+    /// as `filename`.
+    ///
+    /// See [`ContentDisposition::attachment`](header::ContentDisposition::attachment) for how
+    /// non-ASCII file names are encoded.
+    ///
+    /// ```
+    /// use actix_web::HttpResponse;
+    ///
+    /// HttpResponse::Ok()
+    ///     .attachment("données.csv")
+    ///     .body("a,b,c");
+    /// ```
+    pub fn attachment(&mut self, filename: impl AsRef<str>) -> &mut Self {
+        self.insert_header(header::ContentDisposition::attachment(filename))
+    }
+
     /// Add a cookie to the response.
     ///
     /// To send a "removal" cookie, call [`.make_removal()`](cookie::Cookie::make_removal) on the
@@ -284,14 +301,22 @@ impl HttpResponseBuilder {
     /// Set a body and build the `HttpResponse`.
     ///
     /// Unlike [`message_body`](Self::message_body), errors are converted into error
-    /// responses immediately.
+    /// responses immediately. This includes manually set `Content-Length` or `Transfer-Encoding`
+    /// headers that conflict with the body; see [`BodySize::check_framing`].
+    ///
+    /// [`BodySize::check_framing`]: crate::body::BodySize::check_framing
     ///
     /// `HttpResponseBuilder` can not be used after this call.
     pub fn body<B>(&mut self, body: B) -> HttpResponse<BoxBody>
     where
         B: MessageBody + 'static,
     {
-        match self.message_body(body) {
+        let res = self.message_body(body).and_then(|res| {
+            res.body().size().check_framing(res.headers())?;
+            Ok(res)
+        });
+
+        match res {
             Ok(res) => res.map_into_boxed_body(),
             Err(err) => HttpResponse::from_error(err),
         }
@@ -422,6 +447,15 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[test]
+    fn test_attachment() {
+        let resp = HttpResponse::Ok().attachment("файл.txt").finish();
+        assert_eq!(
+            resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "attachment; filename=\"____.txt\"; filename*=UTF-8''%D1%84%D0%B0%D0%B9%D0%BB.txt"
+        );
+    }
+
     #[test]
     fn test_upgrade() {
         let resp = HttpResponseBuilder::new(StatusCode::OK)
@@ -532,4 +566,36 @@ mod tests {
         assert!(headers.contains(&HeaderValue::from_static("application/octet-stream")));
         assert!(headers.contains(&HeaderValue::from_static("application/json")));
     }
+
+    #[actix_rt::test]
+    async fn response_builder_conflicting_framing() {
+        let res = HttpResponse::Ok()
+            .insert_header((header::CONTENT_LENGTH, "4"))
+            .body("test");
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = HttpResponse::Ok()
+            .insert_header((header::CONTENT_LENGTH, "3"))
+            .body("test");
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let res = HttpResponse::Ok()
+            .insert_header((header::TRANSFER_ENCODING, "chunked"))
+            .no_chunking(4)
+            .streaming(futures_util::stream::once(async {
+                Ok::<_, Error>(Bytes::from_static(b"test"))
+            }));
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let res = HttpResponse::Ok()
+            .insert_header((header::CONTENT_LENGTH, "many"))
+            .finish();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        // allowed for responses to `HEAD` requests
+        let res = HttpResponse::Ok()
+            .insert_header((header::CONTENT_LENGTH, "4"))
+            .finish();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
 }
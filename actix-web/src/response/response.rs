@@ -4,8 +4,8 @@ use std::{
 };
 
 use actix_http::{
-    body::{BoxBody, EitherBody, MessageBody},
-    header::HeaderMap,
+    body::{BodyTransform, BoxBody, EitherBody, MessageBody, TransformBody},
+    header::{HeaderMap, CONTENT_LENGTH},
     Extensions, Response, ResponseHead, StatusCode,
 };
 
@@ -254,7 +254,10 @@ impl<B> HttpResponse<B> {
 
     /// Map the current body type to another using a closure, returning a new response.
     ///
-    /// Closure receives the response head and the current body type.
+    /// Closure receives the response head and the current body type. Headers describing the body
+    /// are left as they are; to rewrite the body's content, see [`transform_body`].
+    ///
+    /// [`transform_body`]: Self::transform_body
     pub fn map_body<F, B2>(self, f: F) -> HttpResponse<B2>
     where
         F: FnOnce(&mut ResponseHead, B) -> B2,
@@ -265,6 +268,28 @@ impl<B> HttpResponse<B> {
         }
     }
 
+    /// Rewrites the content of the body with a [`BodyTransform`], returning a new response.
+    ///
+    /// The size of the new body is recomputed by the transform, and a `Content-Length` header is
+    /// removed if the size changes, so that the response is framed correctly. Validators such as
+    /// `ETag` describe the original content; transforms that change it should remove them.
+    pub fn transform_body<T>(self, transform: T) -> HttpResponse<TransformBody<B, T>>
+    where
+        B: MessageBody,
+        T: BodyTransform,
+    {
+        self.map_body(|head, body| {
+            let original = body.size();
+            let body = TransformBody::new(body, transform);
+
+            if body.size() != original {
+                head.headers_mut().remove(CONTENT_LENGTH);
+            }
+
+            body
+        })
+    }
+
     /// Map the current body type `B` to `EitherBody::Left(B)`.
     ///
     /// Useful for middleware which can generate their own responses.
@@ -1,19 +1,28 @@
-use std::{mem, rc::Rc};
+use std::{any::Any, future::Future, mem, rc::Rc};
 
 use actix_http::Method;
 use actix_service::{
+    apply_fn,
     boxed::{self, BoxService},
-    fn_service, Service, ServiceFactory, ServiceFactoryExt,
+    fn_service, Service, ServiceExt as _, ServiceFactory, ServiceFactoryExt, Transform,
 };
+use actix_utils::future::ready;
 use futures_core::future::LocalBoxFuture;
 
 use crate::{
-    guard::{self, Guard},
+    body::MessageBody,
+    guard::{self, Guard, MethodGuard},
     handler::{handler_service, Handler},
     service::{BoxedHttpServiceFactory, ServiceRequest, ServiceResponse},
     Error, FromRequest, HttpResponse, Responder,
 };
 
+type BoxedRouteService = BoxService<ServiceRequest, ServiceResponse, Error>;
+
+/// Wraps the handler service of a route in a middleware.
+type RouteWrap =
+    Box<dyn Fn(BoxedRouteService) -> LocalBoxFuture<'static, Result<BoxedRouteService, ()>>>;
+
 /// A request handler with [guards](guard).
 ///
 /// Route uses a builder-like pattern for configuration. If handler is not set, a `404 Not Found`
@@ -21,6 +30,8 @@ use crate::{
 pub struct Route {
     service: BoxedHttpServiceFactory,
     guards: Rc<Vec<Box<dyn Guard>>>,
+    methods: Vec<Method>,
+    wraps: Rc<Vec<RouteWrap>>,
 }
 
 impl Route {
@@ -32,12 +43,19 @@ impl Route {
                 Ok(req.into_response(HttpResponse::NotFound()))
             })),
             guards: Rc::new(Vec::new()),
+            methods: Vec::new(),
+            wraps: Rc::new(Vec::new()),
         }
     }
 
     pub(crate) fn take_guards(&mut self) -> Vec<Box<dyn Guard>> {
         mem::take(Rc::get_mut(&mut self.guards).unwrap())
     }
+
+    /// Methods of the method guards added directly to this route.
+    pub(crate) fn methods(&self) -> &[Method] {
+        &self.methods
+    }
 }
 
 impl ServiceFactory<ServiceRequest> for Route {
@@ -51,16 +69,22 @@ impl ServiceFactory<ServiceRequest> for Route {
     fn new_service(&self, _: ()) -> Self::Future {
         let fut = self.service.new_service(());
         let guards = self.guards.clone();
+        let wraps = self.wraps.clone();
 
         Box::pin(async move {
-            let service = fut.await?;
+            let mut service = fut.await?;
+
+            for wrap in wraps.iter() {
+                service = wrap(service).await?;
+            }
+
             Ok(RouteService { service, guards })
         })
     }
 }
 
 pub struct RouteService {
-    service: BoxService<ServiceRequest, ServiceResponse, Error>,
+    service: BoxedRouteService,
     guards: Rc<Vec<Box<dyn Guard>>>,
 }
 
@@ -106,6 +130,7 @@ impl Route {
     /// # }
     /// ```
     pub fn method(mut self, method: Method) -> Self {
+        self.methods.push(method.clone());
         Rc::get_mut(&mut self.guards)
             .unwrap()
             .push(Box::new(guard::Method(method)));
@@ -127,6 +152,11 @@ impl Route {
     /// # }
     /// ```
     pub fn guard<F: Guard + 'static>(mut self, f: F) -> Self {
+        // method guards nested in other guards, e.g. `guard::Not`, do not allow their method
+        if let Some(MethodGuard(method)) = (&f as &dyn Any).downcast_ref() {
+            self.methods.push(method.clone());
+        }
+
         Rc::get_mut(&mut self.guards).unwrap().push(Box::new(f));
         self
     }
@@ -235,6 +265,85 @@ impl Route {
         self.service = boxed::factory(service_factory.map_err(Into::into));
         self
     }
+
+    /// Registers a route middleware.
+    ///
+    /// `mw` is a middleware component (type), that can modify the request and response handled by
+    /// this `Route` only. Middleware wraps the handler whether it is registered before or after
+    /// [`to`](Self::to) or [`service`](Self::service); middleware registered later runs first.
+    ///
+    /// Route middleware runs inside the middleware of its resource, scopes and app. See
+    /// [`App::wrap`](crate::App::wrap) for more details.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{middleware::DefaultHeaders, web, App, HttpResponse};
+    ///
+    /// let app = App::new().service(
+    ///     web::resource("/items")
+    ///         .route(web::get().to(HttpResponse::Ok))
+    ///         .route(
+    ///             web::delete()
+    ///                 .to(HttpResponse::NoContent)
+    ///                 .wrap(DefaultHeaders::new().add(("x-audited", "true"))),
+    ///         ),
+    /// );
+    /// ```
+    #[doc(alias = "middleware")]
+    #[doc(alias = "use")] // nodejs terminology
+    pub fn wrap<M, B>(mut self, mw: M) -> Self
+    where
+        M: Transform<
+                BoxService<ServiceRequest, ServiceResponse, Error>,
+                ServiceRequest,
+                Response = ServiceResponse<B>,
+                Error = Error,
+                InitError = (),
+            > + 'static,
+        B: MessageBody + 'static,
+    {
+        self.push_wrap(Box::new(move |service| {
+            let fut = mw.new_transform(service);
+
+            Box::pin(async move {
+                let service = fut.await?;
+                Ok(boxed::service(
+                    service.map(ServiceResponse::map_into_boxed_body),
+                ))
+            })
+        }));
+        self
+    }
+
+    /// Registers a route function middleware.
+    ///
+    /// `mw` is a closure that runs during inbound and/or outbound processing in the request
+    /// life-cycle (request -> response), modifying request/response as necessary, for requests
+    /// handled by this `Route` only. As with [`wrap`](Self::wrap), it can be registered before or
+    /// after the handler.
+    ///
+    /// See [`App::wrap_fn`](crate::App::wrap_fn) for examples and more details.
+    #[doc(alias = "middleware")]
+    #[doc(alias = "use")] // nodejs terminology
+    pub fn wrap_fn<F, R, B>(mut self, mw: F) -> Self
+    where
+        F: Fn(ServiceRequest, &BoxService<ServiceRequest, ServiceResponse, Error>) -> R
+            + Clone
+            + 'static,
+        R: Future<Output = Result<ServiceResponse<B>, Error>> + 'static,
+        B: MessageBody + 'static,
+    {
+        self.push_wrap(Box::new(move |service| {
+            let service =
+                apply_fn(service, mw.clone()).map(ServiceResponse::map_into_boxed_body);
+            Box::pin(ready(Ok(boxed::service(service))))
+        }));
+        self
+    }
+
+    fn push_wrap(&mut self, wrap: RouteWrap) {
+        Rc::get_mut(&mut self.wraps).unwrap().push(wrap);
+    }
 }
 
 #[cfg(test)]
@@ -323,6 +432,113 @@ mod tests {
         assert_eq!(body, Bytes::from_static(b"{\"name\":\"test\"}"));
     }
 
+    #[actix_rt::test]
+    async fn route_middleware() {
+        fn trace(mut res: ServiceResponse, layer: &'static str) -> ServiceResponse {
+            res.headers_mut().append(
+                header::HeaderName::from_static("x-trace"),
+                header::HeaderValue::from_static(layer),
+            );
+            res
+        }
+
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::scope("/admin")
+                        .wrap_fn(|req, srv| {
+                            let fut = srv.call(req);
+                            async { Ok(trace(fut.await?, "scope")) }
+                        })
+                        .service(
+                            web::resource("/users")
+                                .wrap_fn(|req, srv| {
+                                    let fut = srv.call(req);
+                                    async { Ok(trace(fut.await?, "resource")) }
+                                })
+                                .route(web::get().to(HttpResponse::Ok).wrap_fn(|req, srv| {
+                                    let fut = srv.call(req);
+                                    async { Ok(trace(fut.await?, "route")) }
+                                }))
+                                .route(web::post().to(HttpResponse::Created)),
+                        ),
+                )
+                .route("/public", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let traces = |res: &ServiceResponse| {
+            res.headers()
+                .get_all("x-trace")
+                .map(|value| value.to_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let req = TestRequest::get().uri("/admin/users").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(traces(&res), ["route", "resource", "scope"]);
+
+        let req = TestRequest::post().uri("/admin/users").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(traces(&res), ["resource", "scope"]);
+
+        let req = TestRequest::get().uri("/public").to_request();
+        let res = call_service(&srv, req).await;
+        assert!(traces(&res).is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn route_middleware_before_handler() {
+        let srv = init_service(
+            App::new()
+                .route(
+                    "/admin",
+                    web::get()
+                        .wrap_fn(|req, srv| {
+                            let authorized = req.headers().contains_key(header::AUTHORIZATION);
+                            let fut = srv.call(req);
+
+                            async move {
+                                if authorized {
+                                    fut.await
+                                } else {
+                                    Err(error::ErrorUnauthorized("no credentials"))
+                                }
+                            }
+                        })
+                        .to(HttpResponse::Ok),
+                )
+                .route(
+                    "/service",
+                    web::get()
+                        .wrap(crate::middleware::DefaultHeaders::new().add(("x-wrapped", "1")))
+                        .service(fn_service(|req: ServiceRequest| async {
+                            Ok::<_, Infallible>(req.into_response(HttpResponse::Ok()))
+                        })),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/admin").to_request();
+        let err = srv.call(req).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let req = TestRequest::get()
+            .uri("/admin")
+            .insert_header((header::AUTHORIZATION, "Bearer token"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::get().uri("/service").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.headers().get("x-wrapped").unwrap(), "1");
+    }
+
     #[actix_rt::test]
     async fn test_service_handler() {
         struct HelloWorld;
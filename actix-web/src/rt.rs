@@ -66,7 +66,109 @@
 // - Re-export but hide the runtime macros because they won't work directly but are required for
 //   `#[actix_web::main]` and `#[actix_web::test]` to work.
 
-pub use actix_rt::{net, pin, signal, spawn, task, time, Runtime, System, SystemRunner};
+pub use actix_rt::{net, pin, signal, spawn, task, Runtime, System, SystemRunner};
+
+pub mod time {
+    //! Utilities for tracking time (Tokio re-exports), plus a stream of interval ticks.
+    //!
+    //! These use the timer of the runtime Actix Web runs on, so they can be used in handlers,
+    //! streaming response bodies and WebSocket heartbeats without an actor framework.
+
+    use std::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use futures_core::Stream;
+
+    pub use actix_rt::time::*;
+
+    /// A [`Stream`] yielding the instant of each tick of an [`Interval`].
+    ///
+    /// This makes intervals usable with stream combinators, such as for producing a streaming
+    /// response body, or interleaving periodic pings with WebSocket messages.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use actix_web::{rt::time::{interval, IntervalStream}, web::Bytes, Error, HttpResponse};
+    /// use futures_util::StreamExt as _;
+    ///
+    /// async fn events() -> HttpResponse {
+    ///     // sends a server-sent event comment every 15 seconds to keep the connection open
+    ///     let keep_alive = IntervalStream::new(interval(Duration::from_secs(15)))
+    ///         .map(|_| Ok::<_, Error>(Bytes::from_static(b":\n\n")));
+    ///
+    ///     HttpResponse::Ok()
+    ///         .content_type("text/event-stream")
+    ///         .streaming(keep_alive)
+    /// }
+    /// ```
+    #[derive(Debug)]
+    pub struct IntervalStream {
+        interval: Interval,
+    }
+
+    impl IntervalStream {
+        /// Wraps an interval in a stream.
+        pub fn new(interval: Interval) -> Self {
+            IntervalStream { interval }
+        }
+
+        /// Returns the wrapped interval.
+        pub fn into_inner(self) -> Interval {
+            self.interval
+        }
+    }
+
+    impl Stream for IntervalStream {
+        type Item = Instant;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Instant>> {
+            self.interval.poll_tick(cx).map(Some)
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+
+    impl AsRef<Interval> for IntervalStream {
+        fn as_ref(&self) -> &Interval {
+            &self.interval
+        }
+    }
+
+    impl AsMut<Interval> for IntervalStream {
+        fn as_mut(&mut self) -> &mut Interval {
+            &mut self.interval
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::time::Duration;
+
+        use futures_util::StreamExt as _;
+
+        use super::*;
+
+        #[actix_rt::test]
+        async fn interval_stream_ticks() {
+            let start = Instant::now();
+            let ticks = IntervalStream::new(interval(Duration::from_millis(10)))
+                .take(3)
+                .collect::<Vec<_>>()
+                .await;
+
+            assert_eq!(ticks.len(), 3);
+            // the first tick completes immediately
+            assert!(ticks[0] - start < Duration::from_millis(10));
+            assert!(ticks[2] - ticks[0] >= Duration::from_millis(20));
+        }
+    }
+}
 
 #[cfg(feature = "macros")]
 #[doc(hidden)]
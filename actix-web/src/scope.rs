@@ -951,6 +951,58 @@ mod tests {
         );
     }
 
+    #[actix_rt::test]
+    async fn test_nested_scope_middleware_and_guard() {
+        let srv = init_service(
+            App::new().service(
+                web::scope("/app")
+                    .wrap(
+                        DefaultHeaders::new()
+                            .add((header::CONTENT_TYPE, HeaderValue::from_static("0001"))),
+                    )
+                    .service(
+                        web::scope("/admin")
+                            .guard(guard::Header("x-admin", "1"))
+                            .wrap(DefaultHeaders::new().add(("x-scope", "admin")))
+                            .route("/panel", web::get().to(HttpResponse::Ok)),
+                    )
+                    .route("/panel", web::get().to(HttpResponse::Accepted)),
+            ),
+        )
+        .await;
+
+        // outer and inner middleware both apply to the nested scope
+        let req = TestRequest::with_uri("/app/admin/panel")
+            .insert_header(("x-admin", "1"))
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("0001")
+        );
+        assert_eq!(
+            resp.headers().get("x-scope").unwrap(),
+            HeaderValue::from_static("admin")
+        );
+
+        // inner scope guard fails; outer middleware still applies
+        let req = TestRequest::with_uri("/app/admin/panel").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("0001")
+        );
+        assert!(resp.headers().get("x-scope").is_none());
+
+        // sibling resource in outer scope is unaffected by inner middleware
+        let req = TestRequest::with_uri("/app/panel").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::ACCEPTED);
+        assert!(resp.headers().get("x-scope").is_none());
+    }
+
     #[actix_rt::test]
     async fn test_middleware_body_type() {
         // Compile test that Scope accepts any body type; test for `EitherBody`
@@ -7,7 +7,10 @@ use std::{
     time::Duration,
 };
 
-use actix_http::{body::MessageBody, Extensions, HttpService, KeepAlive, Request, Response};
+use actix_http::{
+    body::MessageBody, header::HeaderValue, Extensions, HttpService, KeepAlive, Request,
+    Response, ServerHeader,
+};
 use actix_server::{Server, ServerBuilder};
 use actix_service::{
     map_config, IntoServiceFactory, Service, ServiceFactory, ServiceFactoryExt as _,
@@ -18,7 +21,9 @@ use actix_tls::accept::openssl::reexports::{AlpnError, SslAcceptor, SslAcceptorB
 #[cfg(feature = "rustls")]
 use actix_tls::accept::rustls::reexports::ServerConfig as RustlsServerConfig;
 
-use crate::{config::AppConfig, Error};
+use crate::{
+    config::AppConfig, info::TrustedProxies, Error, HttpServerConfig, HttpServerConfigError,
+};
 
 struct Socket {
     scheme: &'static str,
@@ -30,6 +35,34 @@ struct Config {
     keep_alive: KeepAlive,
     client_request_timeout: Duration,
     client_disconnect_timeout: Duration,
+    trusted_proxies: Option<Arc<TrustedProxies>>,
+    tcp_nodelay: Option<bool>,
+    server_name: Option<String>,
+    suppress_server_header: bool,
+}
+
+impl Config {
+    fn server_header(&self) -> ServerHeader {
+        if self.suppress_server_header {
+            return ServerHeader::Suppress;
+        }
+
+        match self.server_name {
+            Some(ref name) => HeaderValue::from_str(name)
+                .map_or(ServerHeader::Passthrough, ServerHeader::Default),
+            None => ServerHeader::Passthrough,
+        }
+    }
+}
+
+/// Options applied to TCP listeners created by the `bind*` methods.
+#[derive(Debug, Clone, Copy)]
+struct ListenerConfig {
+    backlog: u32,
+    reuse_address: bool,
+    reuse_port: bool,
+    send_buffer_size: Option<usize>,
+    recv_buffer_size: Option<usize>,
 }
 
 /// An HTTP Server.
@@ -61,7 +94,7 @@ where
 {
     pub(super) factory: F,
     config: Arc<Mutex<Config>>,
-    backlog: u32,
+    listener_config: ListenerConfig,
     sockets: Vec<Socket>,
     builder: ServerBuilder,
     #[allow(clippy::type_complexity)]
@@ -92,8 +125,18 @@ where
                 keep_alive: KeepAlive::default(),
                 client_request_timeout: Duration::from_secs(5),
                 client_disconnect_timeout: Duration::from_secs(1),
+                trusted_proxies: None,
+                tcp_nodelay: None,
+                server_name: None,
+                suppress_server_header: false,
             })),
-            backlog: 1024,
+            listener_config: ListenerConfig {
+                backlog: 1024,
+                reuse_address: true,
+                reuse_port: false,
+                send_buffer_size: None,
+                recv_buffer_size: None,
+            },
             sockets: Vec::new(),
             builder: ServerBuilder::default(),
             on_connect_fn: None,
@@ -118,7 +161,7 @@ where
         HttpServer {
             factory: self.factory,
             config: self.config,
-            backlog: self.backlog,
+            listener_config: self.listener_config,
             sockets: self.sockets,
             builder: self.builder,
             on_connect_fn: Some(Arc::new(f)),
@@ -145,11 +188,74 @@ where
     ///
     /// This method should be called before `bind()` method call.
     pub fn backlog(mut self, backlog: u32) -> Self {
-        self.backlog = backlog;
+        self.listener_config.backlog = backlog;
         self.builder = self.builder.backlog(backlog);
         self
     }
 
+    /// Sets the `SO_REUSEADDR` option on listeners.
+    ///
+    /// Allows binding to an address with connections left over from a previous server in the
+    /// `TIME_WAIT` state.
+    ///
+    /// By default, `SO_REUSEADDR` is enabled.
+    ///
+    /// This method should be called before `bind()` method call.
+    pub fn reuse_address(mut self, reuse: bool) -> Self {
+        self.listener_config.reuse_address = reuse;
+        self
+    }
+
+    /// Sets the `SO_REUSEPORT` option on listeners.
+    ///
+    /// Allows other sockets, such as a second server process during a zero-downtime restart, to
+    /// bind to the same address. The kernel then distributes incoming connections between them.
+    ///
+    /// By default, `SO_REUSEPORT` is disabled.
+    ///
+    /// This method should be called before `bind()` method call.
+    #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+    pub fn reuse_port(mut self, reuse: bool) -> Self {
+        self.listener_config.reuse_port = reuse;
+        self
+    }
+
+    /// Sets the size, in bytes, of the send buffer (`SO_SNDBUF`) of listeners.
+    ///
+    /// Connections accepted from a listener inherit its buffer sizes on most platforms.
+    ///
+    /// By default, the OS default size is used.
+    ///
+    /// This method should be called before `bind()` method call.
+    pub fn send_buffer_size(mut self, size: usize) -> Self {
+        self.listener_config.send_buffer_size = Some(size);
+        self
+    }
+
+    /// Sets the size, in bytes, of the receive buffer (`SO_RCVBUF`) of listeners.
+    ///
+    /// Connections accepted from a listener inherit its buffer sizes on most platforms.
+    ///
+    /// By default, the OS default size is used.
+    ///
+    /// This method should be called before `bind()` method call.
+    pub fn recv_buffer_size(mut self, size: usize) -> Self {
+        self.listener_config.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Sets the `TCP_NODELAY` option on accepted TCP connections.
+    ///
+    /// Enabling it disables Nagle's algorithm, so small writes are sent immediately instead of
+    /// being held back to be combined with later ones. This lowers latency for small responses.
+    ///
+    /// Applies to plain, TLS and PROXY protocol TCP listeners, including ones passed to
+    /// `listen()`. By default, the OS default is used, which has Nagle's algorithm enabled.
+    pub fn tcp_nodelay(self, nodelay: bool) -> Self {
+        self.config.lock().unwrap().tcp_nodelay = Some(nodelay);
+        self
+    }
+
     /// Sets the maximum per-worker number of concurrent connections.
     ///
     /// All socket listeners will stop accepting connections when this limit is reached for
@@ -243,6 +349,75 @@ where
         self
     }
 
+    /// Sets the name of this server.
+    ///
+    /// The name is sent in the `Server` header of responses that do not set one, including error
+    /// responses generated before a request reaches the app, and can be logged using the `%v`
+    /// [`Logger`](crate::middleware::Logger) format.
+    ///
+    /// By default, no name is set and a `Server` header is only sent if the response sets one.
+    ///
+    /// # Panics
+    /// Panics if `name` is not a valid header value.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use actix_web::{web, App, HttpServer};
+    ///
+    /// # fn run() -> std::io::Result<actix_web::dev::Server> {
+    /// let srv = HttpServer::new(|| App::new().route("/", web::get().to(|| async { "Hello" })))
+    ///     .server_name("my-app/1.0")
+    ///     .bind("127.0.0.1:8080")?
+    ///     .run();
+    /// # Ok(srv)
+    /// # }
+    /// ```
+    pub fn server_name<T: Into<String>>(self, name: T) -> Self {
+        let name = name.into();
+        assert!(
+            HeaderValue::from_str(&name).is_ok(),
+            "server name must be a valid header value"
+        );
+
+        self.config.lock().unwrap().server_name = Some(name);
+        self
+    }
+
+    /// Never sends a `Server` header, even if a response sets one.
+    ///
+    /// A [server name](Self::server_name) is still available to the app and to logs.
+    pub fn suppress_server_header(self) -> Self {
+        self.config.lock().unwrap().suppress_server_header = true;
+        self
+    }
+
+    /// Sets the networks that reverse proxies in front of this server connect from.
+    ///
+    /// By default, `Forwarded` and `X-Forwarded-*` headers are honored on every request, which
+    /// lets clients spoof their address, host and scheme when the server is directly reachable.
+    /// Once trusted proxies are set, these headers are ignored unless the request came from one of
+    /// the networks, and the [real IP](crate::dev::ConnectionInfo::realip_remote_addr) is the last
+    /// address in the forwarding chain that does not belong to a trusted proxy.
+    ///
+    /// ```no_run
+    /// use actix_web::{dev::TrustedProxies, web, App, HttpServer};
+    ///
+    /// #[actix_rt::main]
+    /// async fn main() -> std::io::Result<()> {
+    ///     let proxies = TrustedProxies::new(["10.0.0.0/8", "127.0.0.1", "::1"]).unwrap();
+    ///
+    ///     HttpServer::new(|| App::new().route("/", web::get().to(|| async { "Hello" })))
+    ///         .trusted_proxies(proxies)
+    ///         .bind("127.0.0.1:8080")?
+    ///         .run()
+    ///         .await
+    /// }
+    /// ```
+    pub fn trusted_proxies(self, proxies: TrustedProxies) -> Self {
+        self.config.lock().unwrap().trusted_proxies = Some(Arc::new(proxies));
+        self
+    }
+
     /// Stop Actix `System` after server shutdown.
     pub fn system_exit(mut self) -> Self {
         self.builder = self.builder.system_exit();
@@ -301,16 +476,26 @@ where
                 .listen(format!("actix-web-service-{}", addr), lst, move || {
                     let c = cfg.lock().unwrap();
                     let host = c.host.clone().unwrap_or_else(|| format!("{}", addr));
+                    let trusted_proxies = c.trusted_proxies.clone();
+                    let server_name = c.server_name.clone();
+
+                    let tcp_nodelay = c.tcp_nodelay;
 
                     let mut svc = HttpService::build()
                         .keep_alive(c.keep_alive)
                         .client_request_timeout(c.client_request_timeout)
                         .client_disconnect_timeout(c.client_disconnect_timeout)
+                        .server_header(c.server_header())
                         .local_addr(addr);
 
-                    if let Some(handler) = on_connect_fn.clone() {
+                    if on_connect_fn.is_some() || tcp_nodelay.is_some() {
+                        let handler = on_connect_fn.clone();
                         svc = svc.on_connect_ext(move |io: &_, ext: _| {
-                            (handler)(io as &dyn Any, ext)
+                            set_tcp_nodelay(io as &dyn Any, tcp_nodelay);
+
+                            if let Some(ref handler) = handler {
+                                (handler)(io as &dyn Any, ext)
+                            }
                         })
                     };
 
@@ -320,6 +505,8 @@ where
 
                     svc.finish(map_config(fac, move |_| {
                         AppConfig::new(false, host.clone(), addr)
+                            .with_trusted_proxies(trusted_proxies.clone())
+                            .with_server_name(server_name.clone())
                     }))
                     .tcp()
                 })?;
@@ -359,18 +546,27 @@ where
                 .listen(format!("actix-web-service-{}", addr), lst, move || {
                     let c = cfg.lock().unwrap();
                     let host = c.host.clone().unwrap_or_else(|| format!("{}", addr));
+                    let trusted_proxies = c.trusted_proxies.clone();
+                    let server_name = c.server_name.clone();
 
                     let svc = HttpService::build()
                         .keep_alive(c.keep_alive)
                         .client_request_timeout(c.client_request_timeout)
                         .client_disconnect_timeout(c.client_disconnect_timeout)
+                        .server_header(c.server_header())
                         .local_addr(addr);
 
-                    let svc = if let Some(handler) = on_connect_fn.clone() {
-                        svc.on_connect_ext(move |io: &_, ext: _| (handler)(io as &dyn Any, ext))
-                    } else {
-                        svc
-                    };
+                    // client certificates are always made available to handlers
+                    let handler = on_connect_fn.clone();
+                    let tcp_nodelay = c.tcp_nodelay;
+                    let svc = svc.on_connect_ext(move |io: &_, ext: _| {
+                        set_tcp_nodelay(io as &dyn Any, tcp_nodelay);
+                        crate::tls::ClientCert::on_connect(io as &dyn Any, ext);
+
+                        if let Some(ref handler) = handler {
+                            (handler)(io as &dyn Any, ext)
+                        }
+                    });
 
                     let fac = factory()
                         .into_factory()
@@ -378,6 +574,8 @@ where
 
                     svc.finish(map_config(fac, move |_| {
                         AppConfig::new(true, host.clone(), addr)
+                            .with_trusted_proxies(trusted_proxies.clone())
+                            .with_server_name(server_name.clone())
                     }))
                     .openssl(acceptor.clone())
                 })?;
@@ -418,17 +616,26 @@ where
                 .listen(format!("actix-web-service-{}", addr), lst, move || {
                     let c = cfg.lock().unwrap();
                     let host = c.host.clone().unwrap_or_else(|| format!("{}", addr));
+                    let trusted_proxies = c.trusted_proxies.clone();
+                    let server_name = c.server_name.clone();
 
                     let svc = HttpService::build()
                         .keep_alive(c.keep_alive)
                         .client_request_timeout(c.client_request_timeout)
-                        .client_disconnect_timeout(c.client_disconnect_timeout);
+                        .client_disconnect_timeout(c.client_disconnect_timeout)
+                        .server_header(c.server_header());
 
-                    let svc = if let Some(handler) = on_connect_fn.clone() {
-                        svc.on_connect_ext(move |io: &_, ext: _| (handler)(io as &dyn Any, ext))
-                    } else {
-                        svc
-                    };
+                    // client certificates are always made available to handlers
+                    let handler = on_connect_fn.clone();
+                    let tcp_nodelay = c.tcp_nodelay;
+                    let svc = svc.on_connect_ext(move |io: &_, ext: _| {
+                        set_tcp_nodelay(io as &dyn Any, tcp_nodelay);
+                        crate::tls::ClientCert::on_connect(io as &dyn Any, ext);
+
+                        if let Some(ref handler) = handler {
+                            (handler)(io as &dyn Any, ext)
+                        }
+                    });
 
                     let fac = factory()
                         .into_factory()
@@ -436,6 +643,8 @@ where
 
                     svc.finish(map_config(fac, move |_| {
                         AppConfig::new(true, host.clone(), addr)
+                            .with_trusted_proxies(trusted_proxies.clone())
+                            .with_server_name(server_name.clone())
                     }))
                     .rustls(config.clone())
                 })?;
@@ -456,13 +665,126 @@ where
         Ok(self)
     }
 
+    /// Start listening for incoming connections that are prefixed with a PROXY protocol header.
+    ///
+    /// Use this for listeners behind a load balancer (HAProxy, AWS NLB, etc.) that sends a
+    /// v1 or v2 PROXY protocol header. The client address from the header is reported as the
+    /// request's [peer address](crate::HttpRequest::peer_addr) and the full header is available
+    /// as [`ProxyHeader`](crate::dev::ProxyHeader) connection data. Connections that do not start
+    /// with a valid header within the [client request timeout](Self::client_request_timeout)
+    /// are closed.
+    ///
+    /// Only enable this on listeners that can not be reached directly by clients, otherwise they
+    /// can spoof their address.
+    pub fn listen_proxy_protocol(mut self, lst: net::TcpListener) -> io::Result<Self> {
+        use actix_http::{error::DispatchError, Protocol};
+        use actix_rt::net::TcpStream;
+        use actix_service::fn_service;
+
+        use crate::proxy_protocol::{self, ProxiedStream};
+
+        let cfg = self.config.clone();
+        let factory = self.factory.clone();
+        let addr = lst.local_addr()?;
+        self.sockets.push(Socket {
+            addr,
+            scheme: "http",
+        });
+        let on_connect_fn = self.on_connect_fn.clone();
+
+        self.builder =
+            self.builder
+                .listen(format!("actix-web-service-{}", addr), lst, move || {
+                    let c = cfg.lock().unwrap();
+                    let host = c.host.clone().unwrap_or_else(|| format!("{}", addr));
+                    let trusted_proxies = c.trusted_proxies.clone();
+                    let server_name = c.server_name.clone();
+                    let header_timeout = c.client_request_timeout;
+                    let tcp_nodelay = c.tcp_nodelay;
+
+                    let accept = fn_service(move |mut io: TcpStream| async move {
+                        let read = proxy_protocol::read_header(&mut io);
+
+                        let header = if header_timeout == Duration::ZERO {
+                            read.await
+                        } else {
+                            actix_rt::time::timeout(header_timeout, read)
+                                .await
+                                .map_err(|_| DispatchError::SlowRequestTimeout)?
+                        }
+                        .map_err(DispatchError::Io)?;
+
+                        let peer_addr = header.source().or_else(|| io.peer_addr().ok());
+
+                        Ok((ProxiedStream { io, header }, Protocol::Http1, peer_addr))
+                    });
+
+                    let on_connect_fn = on_connect_fn.clone();
+                    let svc = HttpService::build()
+                        .keep_alive(c.keep_alive)
+                        .client_request_timeout(c.client_request_timeout)
+                        .client_disconnect_timeout(c.client_disconnect_timeout)
+                        .server_header(c.server_header())
+                        .local_addr(addr)
+                        .on_connect_ext(move |io: &ProxiedStream<TcpStream>, ext: _| {
+                            set_tcp_nodelay(&io.io as &dyn Any, tcp_nodelay);
+                            ext.insert(io.header.clone());
+
+                            if let Some(handler) = &on_connect_fn {
+                                (handler)(&io.io as &dyn Any, ext)
+                            }
+                        });
+
+                    let fac = factory()
+                        .into_factory()
+                        .map_err(|err| err.into().error_response());
+
+                    accept.and_then(svc.finish(map_config(fac, move |_| {
+                        AppConfig::new(false, host.clone(), addr)
+                            .with_trusted_proxies(trusted_proxies.clone())
+                            .with_server_name(server_name.clone())
+                    })))
+                })?;
+        Ok(self)
+    }
+
+    /// Binds to the given address and accepts connections that are prefixed with a PROXY protocol
+    /// header.
+    ///
+    /// See [`listen_proxy_protocol`](Self::listen_proxy_protocol) for details.
+    ///
+    /// ```no_run
+    /// use actix_web::{web, App, HttpRequest, HttpServer};
+    ///
+    /// async fn index(req: HttpRequest) -> String {
+    ///     format!("client: {:?}", req.peer_addr())
+    /// }
+    ///
+    /// #[actix_rt::main]
+    /// async fn main() -> std::io::Result<()> {
+    ///     HttpServer::new(|| App::new().route("/", web::get().to(index)))
+    ///         .bind_proxy_protocol("0.0.0.0:8080")?
+    ///         .run()
+    ///         .await
+    /// }
+    /// ```
+    pub fn bind_proxy_protocol<A: net::ToSocketAddrs>(mut self, addr: A) -> io::Result<Self> {
+        let sockets = self.bind2(addr)?;
+
+        for lst in sockets {
+            self = self.listen_proxy_protocol(lst)?;
+        }
+
+        Ok(self)
+    }
+
     fn bind2<A: net::ToSocketAddrs>(&self, addr: A) -> io::Result<Vec<net::TcpListener>> {
         let mut err = None;
         let mut success = false;
         let mut sockets = Vec::new();
 
         for addr in addr.to_socket_addrs()? {
-            match create_tcp_listener(addr, self.backlog) {
+            match create_tcp_listener(addr, &self.listener_config) {
                 Ok(lst) => {
                     success = true;
                     sockets.push(lst);
@@ -543,13 +865,16 @@ where
                 false,
                 c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
                 socket_addr,
-            );
+            )
+            .with_trusted_proxies(c.trusted_proxies.clone())
+            .with_server_name(c.server_name.clone());
 
             fn_service(|io: UnixStream| async { Ok((io, Protocol::Http1, None)) }).and_then({
                 let mut svc = HttpService::build()
                     .keep_alive(c.keep_alive)
                     .client_request_timeout(c.client_request_timeout)
-                    .client_disconnect_timeout(c.client_disconnect_timeout);
+                    .client_disconnect_timeout(c.client_disconnect_timeout)
+                    .server_header(c.server_header());
 
                 if let Some(handler) = on_connect_fn.clone() {
                     svc = svc
@@ -595,7 +920,9 @@ where
                     false,
                     c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
                     socket_addr,
-                );
+                )
+                .with_trusted_proxies(c.trusted_proxies.clone())
+                .with_server_name(c.server_name.clone());
 
                 let fac = factory()
                     .into_factory()
@@ -606,6 +933,7 @@ where
                         .keep_alive(c.keep_alive)
                         .client_request_timeout(c.client_request_timeout)
                         .client_disconnect_timeout(c.client_disconnect_timeout)
+                        .server_header(c.server_header())
                         .finish(map_config(fac, move |_| config.clone())),
                 )
             },
@@ -613,6 +941,66 @@ where
 
         Ok(self)
     }
+
+    /// Applies the settings of a [`HttpServerConfig`], then binds its addresses.
+    ///
+    /// The configuration is validated, and its TLS files loaded, before any address is bound, so
+    /// a misconfigured deployment fails at startup with a descriptive error. Settings left unset
+    /// keep their current value.
+    ///
+    /// See [`HttpServerConfig`] for an example.
+    pub fn apply_config(
+        mut self,
+        config: &HttpServerConfig,
+    ) -> Result<Self, HttpServerConfigError> {
+        config.validate()?;
+
+        #[cfg(feature = "rustls")]
+        let tls = config.rustls_server_config()?;
+
+        if let Some(num) = config.workers {
+            self = self.workers(num);
+        }
+        if let Some(backlog) = config.backlog {
+            self = self.backlog(backlog);
+        }
+        if let Some(num) = config.max_connections {
+            self = self.max_connections(num);
+        }
+        if let Some(num) = config.max_connection_rate {
+            self = self.max_connection_rate(num);
+        }
+        if let Some(dur) = config.keep_alive {
+            self = self.keep_alive(dur);
+        }
+        if let Some(dur) = config.client_request_timeout {
+            self = self.client_request_timeout(dur);
+        }
+        if let Some(dur) = config.client_disconnect_timeout {
+            self = self.client_disconnect_timeout(dur);
+        }
+        if let Some(dur) = config.shutdown_timeout {
+            self = self.shutdown_timeout(dur.as_secs());
+        }
+
+        for addr in &config.bind {
+            #[cfg(feature = "rustls")]
+            let res = match tls {
+                Some(ref tls) => self.bind_rustls(addr.as_str(), tls.clone()),
+                None => self.bind(addr.as_str()),
+            };
+
+            #[cfg(not(feature = "rustls"))]
+            let res = self.bind(addr.as_str());
+
+            self = res.map_err(|source| HttpServerConfigError::Bind {
+                addr: addr.clone(),
+                source,
+            })?;
+        }
+
+        Ok(self)
+    }
 }
 
 impl<F, I, S, B> HttpServer<F, I, S, B>
@@ -652,18 +1040,63 @@ where
     }
 }
 
-fn create_tcp_listener(addr: net::SocketAddr, backlog: u32) -> io::Result<net::TcpListener> {
+fn create_tcp_listener(
+    addr: net::SocketAddr,
+    config: &ListenerConfig,
+) -> io::Result<net::TcpListener> {
     use socket2::{Domain, Protocol, Socket, Type};
     let domain = Domain::for_address(addr);
     let socket = Socket::new(domain, Type::STREAM, Some(Protocol::TCP))?;
-    socket.set_reuse_address(true)?;
+    socket.set_reuse_address(config.reuse_address)?;
+
+    #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+    if config.reuse_port {
+        socket.set_reuse_port(true)?;
+    }
+
+    // buffer sizes must be set before listening for the receive window to be scaled accordingly
+    if let Some(size) = config.send_buffer_size {
+        socket.set_send_buffer_size(size)?;
+    }
+    if let Some(size) = config.recv_buffer_size {
+        socket.set_recv_buffer_size(size)?;
+    }
+
     socket.bind(&addr.into())?;
     // clamp backlog to max u32 that fits in i32 range
-    let backlog = cmp::min(backlog, i32::MAX as u32) as i32;
+    let backlog = cmp::min(config.backlog, i32::MAX as u32) as i32;
     socket.listen(backlog)?;
     Ok(net::TcpListener::from(socket))
 }
 
+/// Applies the `TCP_NODELAY` setting to an accepted plain or TLS TCP connection.
+fn set_tcp_nodelay(io: &dyn Any, nodelay: Option<bool>) {
+    use actix_rt::net::TcpStream;
+
+    let nodelay = match nodelay {
+        Some(nodelay) => nodelay,
+        None => return,
+    };
+
+    let tcp = io.downcast_ref::<TcpStream>();
+
+    #[cfg(feature = "openssl")]
+    let tcp = tcp.or_else(|| {
+        io.downcast_ref::<actix_tls::accept::openssl::TlsStream<TcpStream>>()
+            .map(|stream| stream.get_ref())
+    });
+
+    #[cfg(feature = "rustls")]
+    let tcp = tcp.or_else(|| {
+        io.downcast_ref::<actix_tls::accept::rustls::TlsStream<TcpStream>>()
+            .map(|stream| stream.get_ref().0)
+    });
+
+    if let Some(Err(err)) = tcp.map(|tcp| tcp.set_nodelay(nodelay)) {
+        log::debug!("failed to set TCP_NODELAY on connection: {}", err);
+    }
+}
+
 /// Configure `SslAcceptorBuilder` with custom server flags.
 #[cfg(feature = "openssl")]
 fn openssl_acceptor(mut builder: SslAcceptorBuilder) -> io::Result<SslAcceptor> {
@@ -684,3 +1117,37 @@ fn openssl_acceptor(mut builder: SslAcceptorBuilder) -> io::Result<SslAcceptor>
 
     Ok(builder.build())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{web, App, HttpResponse};
+
+    #[test]
+    fn listener_socket_options() {
+        let srv = HttpServer::new(|| App::new().route("/", web::to(HttpResponse::Ok)))
+            .reuse_address(true)
+            .send_buffer_size(64 * 1024)
+            .recv_buffer_size(64 * 1024);
+
+        let listeners = srv.bind2("127.0.0.1:0").unwrap();
+        let sock = socket2::SockRef::from(&listeners[0]);
+
+        assert!(sock.reuse_address().unwrap());
+        // the kernel may round buffer sizes up, e.g. Linux doubles them for bookkeeping
+        assert!(sock.send_buffer_size().unwrap() >= 64 * 1024);
+        assert!(sock.recv_buffer_size().unwrap() >= 64 * 1024);
+    }
+
+    #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+    #[test]
+    fn listener_reuse_port() {
+        let srv = HttpServer::new(|| App::new().route("/", web::to(HttpResponse::Ok)))
+            .reuse_port(true);
+
+        let listeners = srv.bind2("127.0.0.1:0").unwrap();
+        let sock = socket2::SockRef::from(&listeners[0]);
+
+        assert!(sock.reuse_port().unwrap());
+    }
+}
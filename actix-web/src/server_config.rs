@@ -0,0 +1,642 @@
+//! Server configuration loaded from the environment or a file.
+
+use std::{
+    convert::TryFrom as _, env, fmt, io, net::ToSocketAddrs as _, path::PathBuf, time::Duration,
+};
+
+#[cfg(feature = "rustls")]
+use actix_tls::accept::rustls::reexports::ServerConfig as RustlsServerConfig;
+
+/// Prefix of the environment variables read by [`HttpServerConfig::from_env`].
+const DEFAULT_ENV_PREFIX: &str = "ACTIX_";
+
+/// Deployment settings of an [`HttpServer`](crate::HttpServer).
+///
+/// Settings can be loaded from environment variables, with [`from_env`](Self::from_env), or
+/// from a TOML file when the `config-toml` feature is enabled, with
+/// [`from_toml_file`](Self::from_toml_file). They are applied to a server, and its addresses
+/// bound, in one call with [`HttpServer::apply_config`](crate::HttpServer::apply_config).
+/// Settings left unset keep the server defaults.
+///
+/// | Setting                     | Variable                            | Unit                  |
+/// |-----------------------------|-------------------------------------|-----------------------|
+/// | `bind`                      | `ACTIX_BIND`                        | comma-separated list  |
+/// | `workers`                   | `ACTIX_WORKERS`                     |                       |
+/// | `backlog`                   | `ACTIX_BACKLOG`                     |                       |
+/// | `max_connections`           | `ACTIX_MAX_CONNECTIONS`             | per worker            |
+/// | `max_connection_rate`       | `ACTIX_MAX_CONNECTION_RATE`         | per worker            |
+/// | `keep_alive`                | `ACTIX_KEEP_ALIVE`                  | seconds, 0 disables   |
+/// | `client_request_timeout`    | `ACTIX_CLIENT_REQUEST_TIMEOUT`      | milliseconds          |
+/// | `client_disconnect_timeout` | `ACTIX_CLIENT_DISCONNECT_TIMEOUT`   | milliseconds          |
+/// | `shutdown_timeout`          | `ACTIX_SHUTDOWN_TIMEOUT`            | seconds               |
+/// | `tls_cert`                  | `ACTIX_TLS_CERT`                    | path to PEM chain     |
+/// | `tls_key`                   | `ACTIX_TLS_KEY`                     | path to PEM key       |
+///
+/// When a certificate and key are configured, all addresses are bound with TLS, which requires
+/// the `rustls` feature.
+///
+/// # Examples
+/// ```no_run
+/// use actix_web::{web, App, HttpServer, HttpServerConfig};
+///
+/// #[actix_web::main]
+/// async fn main() -> std::io::Result<()> {
+///     // e.g. ACTIX_BIND=0.0.0.0:8080 ACTIX_WORKERS=4
+///     let config = HttpServerConfig::from_env()?;
+///
+///     HttpServer::new(|| App::new().route("/", web::get().to(|| async { "Hello!" })))
+///         .apply_config(&config)?
+///         .run()
+///         .await
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HttpServerConfig {
+    /// Addresses to bind, in `host:port` form.
+    pub bind: Vec<String>,
+
+    /// Number of workers.
+    pub workers: Option<usize>,
+
+    /// Maximum number of pending connections of each listener.
+    pub backlog: Option<u32>,
+
+    /// Maximum number of concurrent connections of each worker.
+    pub max_connections: Option<usize>,
+
+    /// Maximum number of concurrent TLS handshakes of each worker.
+    pub max_connection_rate: Option<usize>,
+
+    /// Keep-alive duration; a zero duration disables keep-alive.
+    pub keep_alive: Option<Duration>,
+
+    /// Time allowed for clients to send the request head.
+    pub client_request_timeout: Option<Duration>,
+
+    /// Time allowed for connections to close after the response is sent.
+    pub client_disconnect_timeout: Option<Duration>,
+
+    /// Time allowed for workers to finish in-flight requests on shutdown.
+    pub shutdown_timeout: Option<Duration>,
+
+    /// Path to the PEM-encoded TLS certificate chain.
+    pub tls_cert: Option<PathBuf>,
+
+    /// Path to the PEM-encoded (PKCS #8 or RSA) TLS private key.
+    pub tls_key: Option<PathBuf>,
+}
+
+impl HttpServerConfig {
+    /// Loads settings from the `ACTIX_*` environment variables.
+    pub fn from_env() -> Result<Self, HttpServerConfigError> {
+        Self::from_env_prefixed(DEFAULT_ENV_PREFIX)
+    }
+
+    /// Loads settings from environment variables starting with `prefix`, e.g. `MYAPP_BIND` for a
+    /// `MYAPP_` prefix.
+    pub fn from_env_prefixed(prefix: &str) -> Result<Self, HttpServerConfigError> {
+        Self::from_vars(prefix, |name| env::var_os(name))
+    }
+
+    fn from_vars<F>(prefix: &str, var: F) -> Result<Self, HttpServerConfigError>
+    where
+        F: Fn(&str) -> Option<std::ffi::OsString>,
+    {
+        let get = |key: &str| -> Result<Option<String>, HttpServerConfigError> {
+            let name = format!("{}{}", prefix, key);
+
+            match var(&name) {
+                None => Ok(None),
+                Some(val) => val
+                    .into_string()
+                    .map(Some)
+                    .map_err(|_| HttpServerConfigError::invalid(name, "not valid unicode")),
+            }
+        };
+
+        let parse = |key: &str| -> Result<Option<(String, u64)>, HttpServerConfigError> {
+            let name = format!("{}{}", prefix, key);
+
+            get(key)?
+                .map(|val| parse_number(&name, &val).map(|val| (name.clone(), val)))
+                .transpose()
+        };
+
+        let mut config = HttpServerConfig::default();
+
+        if let Some(bind) = get("BIND")? {
+            config.bind = bind
+                .split(',')
+                .map(str::trim)
+                .filter(|addr| !addr.is_empty())
+                .map(str::to_owned)
+                .collect();
+        }
+
+        config.set_numbers(|key| parse(&key.to_ascii_uppercase()))?;
+        config.tls_cert = get("TLS_CERT")?.map(PathBuf::from);
+        config.tls_key = get("TLS_KEY")?.map(PathBuf::from);
+
+        Ok(config)
+    }
+
+    /// Sets the numeric settings from the values returned by `get` for their names.
+    ///
+    /// Values are returned along with the full name of their variable or field, which errors for
+    /// out of range values report.
+    fn set_numbers<F>(&mut self, mut get: F) -> Result<(), HttpServerConfigError>
+    where
+        F: FnMut(&str) -> Result<Option<(String, u64)>, HttpServerConfigError>,
+    {
+        let mut duration = |key: &str, unit: fn(u64) -> Duration| {
+            get(key).map(|val| val.map(|(_, val)| unit(val)))
+        };
+
+        self.keep_alive = duration("keep_alive", Duration::from_secs)?;
+        self.client_request_timeout =
+            duration("client_request_timeout", Duration::from_millis)?;
+        self.client_disconnect_timeout =
+            duration("client_disconnect_timeout", Duration::from_millis)?;
+        self.shutdown_timeout = duration("shutdown_timeout", Duration::from_secs)?;
+
+        let mut count = |key: &str| -> Result<Option<(String, usize)>, HttpServerConfigError> {
+            get(key)?
+                .map(|(name, val)| match usize::try_from(val) {
+                    Ok(val) => Ok((name, val)),
+                    Err(_) => Err(HttpServerConfigError::invalid(name, "value is too large")),
+                })
+                .transpose()
+        };
+
+        self.workers = count("workers")?.map(|(_, val)| val);
+        self.max_connections = count("max_connections")?.map(|(_, val)| val);
+        self.max_connection_rate = count("max_connection_rate")?.map(|(_, val)| val);
+        self.backlog = count("backlog")?
+            .map(|(name, val)| {
+                u32::try_from(val)
+                    .map_err(|_| HttpServerConfigError::invalid(name, "value is too large"))
+            })
+            .transpose()?;
+
+        Ok(())
+    }
+
+    /// Checks that the settings can be applied, without binding anything.
+    ///
+    /// Bind addresses are resolved, and TLS files must exist.
+    pub fn validate(&self) -> Result<(), HttpServerConfigError> {
+        if self.bind.is_empty() {
+            return Err(HttpServerConfigError::invalid(
+                "bind",
+                "at least one address is required",
+            ));
+        }
+
+        for addr in &self.bind {
+            match addr.to_socket_addrs().map(|mut addrs| addrs.next()) {
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    return Err(HttpServerConfigError::invalid(
+                        "bind",
+                        format!("`{}` does not resolve to any address", addr),
+                    ))
+                }
+                Err(err) => {
+                    return Err(HttpServerConfigError::invalid(
+                        "bind",
+                        format!("`{}`: {}", addr, err),
+                    ))
+                }
+            }
+        }
+
+        let positive = [
+            ("workers", self.workers),
+            ("max_connections", self.max_connections),
+            ("max_connection_rate", self.max_connection_rate),
+        ];
+
+        for (key, val) in positive.iter() {
+            if *val == Some(0) {
+                return Err(HttpServerConfigError::invalid(*key, "must be positive"));
+            }
+        }
+
+        match (&self.tls_cert, &self.tls_key) {
+            (None, None) => {}
+            (Some(cert), Some(key)) => {
+                if cfg!(not(feature = "rustls")) {
+                    return Err(HttpServerConfigError::invalid(
+                        "tls_cert",
+                        "TLS requires the `rustls` feature",
+                    ));
+                }
+
+                for path in [cert, key].iter() {
+                    if !path.is_file() {
+                        return Err(HttpServerConfigError::Io {
+                            path: path.to_path_buf(),
+                            source: io::ErrorKind::NotFound.into(),
+                        });
+                    }
+                }
+            }
+            (Some(_), None) => {
+                return Err(HttpServerConfigError::invalid(
+                    "tls_key",
+                    "required when `tls_cert` is set",
+                ))
+            }
+            (None, Some(_)) => {
+                return Err(HttpServerConfigError::invalid(
+                    "tls_cert",
+                    "required when `tls_key` is set",
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Loads the configured certificate and key into a Rustls server config.
+    #[cfg(feature = "rustls")]
+    pub(crate) fn rustls_server_config(
+        &self,
+    ) -> Result<Option<RustlsServerConfig>, HttpServerConfigError> {
+        use tls_rustls::{Certificate, PrivateKey};
+
+        let (cert_path, key_path) = match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => (cert, key),
+            _ => return Ok(None),
+        };
+
+        let cert_pem = read(cert_path)?;
+        let chain = rustls_pemfile::certs(&mut &*cert_pem)
+            .map_err(|source| HttpServerConfigError::Io {
+                path: cert_path.clone(),
+                source,
+            })?
+            .into_iter()
+            .map(Certificate)
+            .collect::<Vec<_>>();
+
+        if chain.is_empty() {
+            return Err(HttpServerConfigError::invalid(
+                "tls_cert",
+                "no certificate found",
+            ));
+        }
+
+        let key_pem = read(key_path)?;
+        let key = rustls_pemfile::pkcs8_private_keys(&mut &*key_pem)
+            .ok()
+            .and_then(|mut keys| keys.pop())
+            .or_else(|| {
+                rustls_pemfile::rsa_private_keys(&mut &*key_pem)
+                    .ok()
+                    .and_then(|mut keys| keys.pop())
+            })
+            .ok_or_else(|| HttpServerConfigError::invalid("tls_key", "no private key found"))?;
+
+        RustlsServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(chain, PrivateKey(key))
+            .map(Some)
+            .map_err(|err| HttpServerConfigError::invalid("tls_cert", err.to_string()))
+    }
+}
+
+#[cfg(feature = "config-toml")]
+impl HttpServerConfig {
+    /// Loads settings from a TOML file.
+    ///
+    /// Durations use the units of the environment variables; TLS paths are relative to the
+    /// working directory.
+    ///
+    /// ```toml
+    /// bind = ["0.0.0.0:8080", "[::]:8080"]
+    /// workers = 4
+    /// keep_alive = 75
+    /// tls_cert = "/etc/app/cert.pem"
+    /// tls_key = "/etc/app/key.pem"
+    /// ```
+    pub fn from_toml_file(
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<Self, HttpServerConfigError> {
+        let contents = read(path.as_ref())?;
+        let contents = String::from_utf8(contents)
+            .map_err(|_| HttpServerConfigError::invalid("file", "not valid UTF-8"))?;
+        contents.parse()
+    }
+}
+
+/// Parses settings from a TOML document; see [`HttpServerConfig::from_toml_file`].
+#[cfg(feature = "config-toml")]
+impl std::str::FromStr for HttpServerConfig {
+    type Err = HttpServerConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use toml::Value;
+
+        let mut table = match s.parse::<Value>().map_err(HttpServerConfigError::Toml)? {
+            Value::Table(table) => table,
+            _ => unreachable!("TOML documents are tables"),
+        };
+
+        let bind = match table.remove("bind") {
+            None => Vec::new(),
+            Some(Value::String(addr)) => vec![addr],
+            Some(Value::Array(addrs)) => addrs
+                .into_iter()
+                .map(|addr| match addr {
+                    Value::String(addr) => Ok(addr),
+                    _ => Err(HttpServerConfigError::invalid("bind", "expected strings")),
+                })
+                .collect::<Result<_, _>>()?,
+            Some(_) => {
+                return Err(HttpServerConfigError::invalid(
+                    "bind",
+                    "expected a string or an array of strings",
+                ))
+            }
+        };
+
+        let mut config = HttpServerConfig {
+            bind,
+            ..HttpServerConfig::default()
+        };
+
+        config.set_numbers(|key| match table.remove(key) {
+            None => Ok(None),
+            Some(Value::Integer(val)) => u64::try_from(val)
+                .map(|val| Some((key.to_owned(), val)))
+                .map_err(|_| HttpServerConfigError::invalid(key, "must not be negative")),
+            Some(_) => Err(HttpServerConfigError::invalid(key, "expected an integer")),
+        })?;
+
+        let mut path = |key: &str| match table.remove(key) {
+            None => Ok(None),
+            Some(Value::String(path)) => Ok(Some(PathBuf::from(path))),
+            Some(_) => Err(HttpServerConfigError::invalid(key, "expected a string")),
+        };
+
+        config.tls_cert = path("tls_cert")?;
+        config.tls_key = path("tls_key")?;
+
+        if let Some(key) = table.keys().next() {
+            return Err(HttpServerConfigError::invalid(
+                key.as_str(),
+                "unknown setting",
+            ));
+        }
+
+        Ok(config)
+    }
+}
+
+fn parse_number(key: &str, val: &str) -> Result<u64, HttpServerConfigError> {
+    val.trim()
+        .parse()
+        .map_err(|_| HttpServerConfigError::invalid(key, format!("`{}` is not a number", val)))
+}
+
+#[cfg(any(feature = "rustls", feature = "config-toml"))]
+fn read(path: &std::path::Path) -> Result<Vec<u8>, HttpServerConfigError> {
+    std::fs::read(path).map_err(|source| HttpServerConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Errors loading, validating or applying an [`HttpServerConfig`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum HttpServerConfigError {
+    /// A configuration or TLS file could not be read.
+    Io {
+        /// Path of the file.
+        path: PathBuf,
+        /// Underlying error.
+        source: io::Error,
+    },
+
+    /// The configuration file is not valid TOML.
+    #[cfg(feature = "config-toml")]
+    Toml(toml::de::Error),
+
+    /// A setting has an invalid value.
+    Invalid {
+        /// Name of the setting or environment variable.
+        key: String,
+        /// Why the value is invalid.
+        reason: String,
+    },
+
+    /// An address could not be bound.
+    Bind {
+        /// The address.
+        addr: String,
+        /// Underlying error.
+        source: io::Error,
+    },
+}
+
+impl HttpServerConfigError {
+    fn invalid(key: impl Into<String>, reason: impl Into<String>) -> Self {
+        HttpServerConfigError::Invalid {
+            key: key.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for HttpServerConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpServerConfigError::Io { path, source } => {
+                write!(f, "Failed to read {}: {}", path.display(), source)
+            }
+            #[cfg(feature = "config-toml")]
+            HttpServerConfigError::Toml(err) => {
+                write!(f, "Invalid configuration file: {}", err)
+            }
+            HttpServerConfigError::Invalid { key, reason } => {
+                write!(f, "Invalid setting `{}`: {}", key, reason)
+            }
+            HttpServerConfigError::Bind { addr, source } => {
+                write!(f, "Failed to bind {}: {}", addr, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HttpServerConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HttpServerConfigError::Io { source, .. }
+            | HttpServerConfigError::Bind { source, .. } => Some(source),
+            #[cfg(feature = "config-toml")]
+            HttpServerConfigError::Toml(err) => Some(err),
+            HttpServerConfigError::Invalid { .. } => None,
+        }
+    }
+}
+
+impl From<HttpServerConfigError> for io::Error {
+    fn from(err: HttpServerConfigError) -> Self {
+        let kind = match &err {
+            HttpServerConfigError::Io { source, .. }
+            | HttpServerConfigError::Bind { source, .. } => source.kind(),
+            _ => io::ErrorKind::InvalidInput,
+        };
+
+        io::Error::new(kind, err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, ffi::OsString};
+
+    use super::*;
+    use crate::{App, HttpServer};
+
+    fn from_vars(vars: &[(&str, &str)]) -> Result<HttpServerConfig, HttpServerConfigError> {
+        let vars = vars
+            .iter()
+            .map(|&(name, val)| (name.to_owned(), OsString::from(val)))
+            .collect::<HashMap<_, _>>();
+
+        HttpServerConfig::from_vars("APP_", |name| vars.get(name).cloned())
+    }
+
+    fn invalid_key(err: HttpServerConfigError) -> String {
+        match err {
+            HttpServerConfigError::Invalid { key, .. } => key,
+            err => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn env() {
+        let config = from_vars(&[
+            ("APP_BIND", "127.0.0.1:8080, [::1]:8080"),
+            ("APP_WORKERS", "4"),
+            ("APP_KEEP_ALIVE", "0"),
+            ("APP_CLIENT_REQUEST_TIMEOUT", "2500"),
+            ("APP_TLS_CERT", "cert.pem"),
+            ("ACTIX_WORKERS", "8"),
+        ])
+        .unwrap();
+
+        assert_eq!(config.bind, vec!["127.0.0.1:8080", "[::1]:8080"]);
+        assert_eq!(config.workers, Some(4));
+        assert_eq!(config.keep_alive, Some(Duration::ZERO));
+        assert_eq!(
+            config.client_request_timeout,
+            Some(Duration::from_millis(2500))
+        );
+        assert_eq!(config.backlog, None);
+        assert_eq!(config.tls_cert, Some(PathBuf::from("cert.pem")));
+
+        let err = from_vars(&[("APP_WORKERS", "four")]).unwrap_err();
+        assert_eq!(invalid_key(err), "APP_WORKERS");
+
+        // range errors report the variable name too
+        let err = from_vars(&[("APP_BACKLOG", "5000000000")]).unwrap_err();
+        assert_eq!(invalid_key(err), "APP_BACKLOG");
+    }
+
+    #[test]
+    fn validation() {
+        let mut config = HttpServerConfig::default();
+        assert_eq!(invalid_key(config.validate().unwrap_err()), "bind");
+
+        config.bind = vec!["127.0.0.1".to_owned()];
+        assert_eq!(invalid_key(config.validate().unwrap_err()), "bind");
+
+        config.bind = vec!["127.0.0.1:0".to_owned()];
+        config.validate().unwrap();
+
+        config.workers = Some(0);
+        assert_eq!(invalid_key(config.validate().unwrap_err()), "workers");
+
+        config.workers = None;
+        config.tls_cert = Some(PathBuf::from("cert.pem"));
+        assert_eq!(invalid_key(config.validate().unwrap_err()), "tls_key");
+    }
+
+    #[test]
+    fn apply_config() {
+        let config = from_vars(&[("APP_BIND", "127.0.0.1:0"), ("APP_WORKERS", "1")]).unwrap();
+        let srv = HttpServer::new(App::new).apply_config(&config).unwrap();
+        assert_eq!(srv.addrs().len(), 1);
+
+        let err = match HttpServer::new(App::new).apply_config(&HttpServerConfig::default()) {
+            Ok(_) => panic!("empty config should not apply"),
+            Err(err) => err,
+        };
+        assert_eq!(io::Error::from(err).kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(feature = "rustls")]
+    #[test]
+    fn apply_tls_config() {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+        let dir = env::temp_dir().join(format!("actix-server-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("cert.pem"), cert.serialize_pem().unwrap()).unwrap();
+        std::fs::write(dir.join("key.pem"), cert.serialize_private_key_pem()).unwrap();
+
+        let mut config = HttpServerConfig {
+            bind: vec!["127.0.0.1:0".to_owned()],
+            tls_cert: Some(dir.join("cert.pem")),
+            tls_key: Some(dir.join("missing.pem")),
+            ..HttpServerConfig::default()
+        };
+        assert!(matches!(
+            config.validate().unwrap_err(),
+            HttpServerConfigError::Io { .. }
+        ));
+
+        config.tls_key = Some(dir.join("key.pem"));
+        let srv = HttpServer::new(App::new).apply_config(&config).unwrap();
+        assert_eq!(srv.addrs_with_scheme()[0].1, "https");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "config-toml")]
+    #[test]
+    fn toml() {
+        let config = r#"
+            bind = "127.0.0.1:8080"
+            workers = 2
+            shutdown_timeout = 10
+            tls_key = "key.pem"
+        "#
+        .parse::<HttpServerConfig>()
+        .unwrap();
+
+        assert_eq!(config.bind, vec!["127.0.0.1:8080"]);
+        assert_eq!(config.workers, Some(2));
+        assert_eq!(config.shutdown_timeout, Some(Duration::from_secs(10)));
+        assert_eq!(config.tls_key, Some(PathBuf::from("key.pem")));
+
+        let err = "workers = -1".parse::<HttpServerConfig>().unwrap_err();
+        assert_eq!(invalid_key(err), "workers");
+
+        let err = "backlog = 5000000000"
+            .parse::<HttpServerConfig>()
+            .unwrap_err();
+        assert_eq!(invalid_key(err), "backlog");
+
+        let err = "port = 8080".parse::<HttpServerConfig>().unwrap_err();
+        assert_eq!(invalid_key(err), "port");
+
+        let err = "bind = ".parse::<HttpServerConfig>().unwrap_err();
+        assert!(matches!(err, HttpServerConfigError::Toml(_)));
+    }
+}
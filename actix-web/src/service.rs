@@ -5,7 +5,7 @@ use std::{
 };
 
 use actix_http::{
-    body::{BoxBody, EitherBody, MessageBody},
+    body::{BodyTransform, BoxBody, EitherBody, MessageBody, TransformBody},
     header::HeaderMap,
     BoxedPayloadStream, Extensions, HttpMessage, Method, Payload, RequestHead, Response,
     ResponseHead, StatusCode, Uri, Version,
@@ -431,7 +431,10 @@ impl<B> ServiceResponse<B> {
 
     /// Map the current body type to another using a closure. Returns a new response.
     ///
-    /// Closure receives the response head and the current body type.
+    /// Closure receives the response head and the current body type. Headers describing the body
+    /// are left as they are; to rewrite the body's content, see [`transform_body`].
+    ///
+    /// [`transform_body`]: Self::transform_body
     #[inline]
     pub fn map_body<F, B2>(self, f: F) -> ServiceResponse<B2>
     where
@@ -445,6 +448,41 @@ impl<B> ServiceResponse<B> {
         }
     }
 
+    /// Rewrites the content of the body with a [`BodyTransform`]. Returns a new response.
+    ///
+    /// This allows middleware to post-process response bodies as they are streamed, such as
+    /// rewriting HTML or compressing. See [`HttpResponse::transform_body`] for how headers are
+    /// kept consistent with the new body.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{dev::Service as _, web::Bytes, App};
+    ///
+    /// let app = App::new().wrap_fn(|req, srv| {
+    ///     let fut = srv.call(req);
+    ///
+    ///     async {
+    ///         let res = fut.await?;
+    ///         Ok(res.transform_body(|chunk: Bytes| {
+    ///             Bytes::from(chunk.to_ascii_uppercase())
+    ///         }))
+    ///     }
+    /// });
+    /// ```
+    #[inline]
+    pub fn transform_body<T>(self, transform: T) -> ServiceResponse<TransformBody<B, T>>
+    where
+        B: MessageBody,
+        T: BodyTransform,
+    {
+        let response = self.response.transform_body(transform);
+
+        ServiceResponse {
+            response,
+            request: self.request,
+        }
+    }
+
     #[inline]
     pub fn map_into_left_body<R>(self) -> ServiceResponse<EitherBody<B, R>> {
         self.map_body(|_, body| EitherBody::left(body))
@@ -813,6 +851,35 @@ mod tests {
         assert_eq!(resp.status(), http::StatusCode::OK);
     }
 
+    #[actix_rt::test]
+    async fn transform_body_in_middleware() {
+        async fn index() -> HttpResponse {
+            HttpResponse::Ok()
+                .insert_header((http::header::CONTENT_LENGTH, "5"))
+                .body("hello")
+        }
+
+        let srv = init_service(
+            App::new()
+                .wrap_fn(|req, srv| {
+                    let fut = srv.call(req);
+
+                    async {
+                        let res = fut.await?;
+                        Ok(res.transform_body(|chunk: web::Bytes| {
+                            web::Bytes::from(chunk.to_ascii_uppercase())
+                        }))
+                    }
+                })
+                .route("/", web::get().to(index)),
+        )
+        .await;
+
+        let res = test::call_service(&srv, TestRequest::default().to_request()).await;
+        assert!(!res.headers().contains_key(http::header::CONTENT_LENGTH));
+        assert_eq!(test::read_body(res).await, "HELLO");
+    }
+
     #[actix_rt::test]
     #[should_panic(expected = "called `Option::unwrap()` on a `None` value")]
     async fn cloning_request_panics() {
@@ -9,6 +9,7 @@
 //!
 //! # Calling Test Service
 //! - [`TestRequest`]
+//! - [`TestMultipart`]
 //! - [`call_service`]
 //! - [`call_and_read_body`]
 //! - [`call_and_read_body_json`]
@@ -21,10 +22,12 @@
 
 pub use actix_http::test::TestBuffer;
 
+mod test_multipart;
 mod test_request;
 mod test_services;
 mod test_utils;
 
+pub use self::test_multipart::TestMultipart;
 pub use self::test_request::TestRequest;
 #[allow(deprecated)]
 pub use self::test_services::{default_service, ok_service, simple_service, status_service};
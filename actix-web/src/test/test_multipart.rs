@@ -0,0 +1,145 @@
+use bytes::{BufMut as _, Bytes, BytesMut};
+use mime::Mime;
+
+/// Boundary used for all test multipart bodies.
+const BOUNDARY: &str = "------------------------actix-web-test-boundary";
+
+/// Builder for `multipart/form-data` request bodies.
+///
+/// Use with [`TestRequest::set_multipart`](super::TestRequest::set_multipart), which also sets
+/// the `Content-Type` header, including the boundary.
+///
+/// # Examples
+/// ```
+/// use actix_web::test::{TestMultipart, TestRequest};
+///
+/// let form = TestMultipart::new()
+///     .text("title", "Holiday")
+///     .file("photo", "beach.jpg", mime::IMAGE_JPEG, &b"<image>"[..]);
+///
+/// let req = TestRequest::post().set_multipart(form).to_request();
+/// ```
+#[derive(Debug, Clone)]
+pub struct TestMultipart {
+    body: BytesMut,
+}
+
+impl TestMultipart {
+    /// Constructs an empty form.
+    pub fn new() -> Self {
+        Self {
+            body: BytesMut::new(),
+        }
+    }
+
+    /// Adds a text field.
+    pub fn text(self, name: &str, value: impl AsRef<str>) -> Self {
+        self.field(
+            name,
+            None,
+            None,
+            Bytes::copy_from_slice(value.as_ref().as_bytes()),
+        )
+    }
+
+    /// Adds a file field.
+    pub fn file(
+        self,
+        name: &str,
+        filename: &str,
+        content_type: Mime,
+        data: impl Into<Bytes>,
+    ) -> Self {
+        self.field(name, Some(filename), Some(content_type), data.into())
+    }
+
+    fn field(
+        mut self,
+        name: &str,
+        filename: Option<&str>,
+        content_type: Option<Mime>,
+        data: Bytes,
+    ) -> Self {
+        let body = &mut self.body;
+
+        body.put_slice(b"--");
+        body.put_slice(BOUNDARY.as_bytes());
+        body.put_slice(b"\r\nContent-Disposition: form-data; name=\"");
+        body.put_slice(escape(name).as_bytes());
+        body.put_u8(b'"');
+
+        if let Some(filename) = filename {
+            body.put_slice(b"; filename=\"");
+            body.put_slice(escape(filename).as_bytes());
+            body.put_u8(b'"');
+        }
+
+        body.put_slice(b"\r\n");
+
+        if let Some(content_type) = content_type {
+            body.put_slice(b"Content-Type: ");
+            body.put_slice(content_type.as_ref().as_bytes());
+            body.put_slice(b"\r\n");
+        }
+
+        body.put_slice(b"\r\n");
+        body.put_slice(&data);
+        body.put_slice(b"\r\n");
+
+        self
+    }
+
+    /// Returns the `Content-Type` header value and the encoded body.
+    pub(crate) fn into_parts(mut self) -> (String, Bytes) {
+        self.body.put_slice(b"--");
+        self.body.put_slice(BOUNDARY.as_bytes());
+        self.body.put_slice(b"--\r\n");
+
+        let content_type = format!("multipart/form-data; boundary={}", BOUNDARY);
+        (content_type, self.body.freeze())
+    }
+}
+
+impl Default for TestMultipart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Escapes quotes and line breaks in a `Content-Disposition` parameter.
+fn escape(val: &str) -> String {
+    val.replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_fields() {
+        let (content_type, body) = TestMultipart::new()
+            .text("name", "value")
+            .file("upload", "a \"b\".txt", mime::TEXT_PLAIN, "file contents")
+            .into_parts();
+
+        assert_eq!(
+            content_type,
+            "multipart/form-data; boundary=------------------------actix-web-test-boundary"
+        );
+
+        let expected = "--------------------------actix-web-test-boundary\r\n\
+            Content-Disposition: form-data; name=\"name\"\r\n\
+            \r\n\
+            value\r\n\
+            --------------------------actix-web-test-boundary\r\n\
+            Content-Disposition: form-data; name=\"upload\"; filename=\"a %22b%22.txt\"\r\n\
+            Content-Type: text/plain\r\n\
+            \r\n\
+            file contents\r\n\
+            --------------------------actix-web-test-boundary--\r\n";
+
+        assert_eq!(body, expected);
+    }
+}
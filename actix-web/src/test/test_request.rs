@@ -1,18 +1,21 @@
-use std::{borrow::Cow, net::SocketAddr, rc::Rc};
+use std::{borrow::Cow, net::SocketAddr, rc::Rc, sync::Arc};
 
-use actix_http::{test::TestRequest as HttpTestRequest, Request};
+use actix_http::{
+    error::PayloadError, test::TestRequest as HttpTestRequest, BoxedPayloadStream, Request,
+};
+use futures_core::Stream;
 use serde::Serialize;
 
 use crate::{
     app_service::AppInitServiceState,
     config::AppConfig,
     data::Data,
-    dev::{Extensions, Path, Payload, ResourceDef, Service, Url},
-    http::header::ContentType,
+    dev::{Extensions, Path, Payload, ResourceDef, Service, TrustedProxies, Url},
+    http::header::{self, ContentType, HeaderValue},
     http::{header::TryIntoHeaderPair, Method, Uri, Version},
     rmap::ResourceMap,
     service::{ServiceRequest, ServiceResponse},
-    test,
+    test::{self, TestMultipart},
     web::Bytes,
     HttpRequest, HttpResponse,
 };
@@ -63,6 +66,8 @@ pub struct TestRequest {
     path: Path<Url>,
     peer_addr: Option<SocketAddr>,
     app_data: Extensions,
+    payload: Option<Payload>,
+    payload_len: Option<usize>,
     #[cfg(feature = "cookies")]
     cookies: CookieJar,
 }
@@ -76,6 +81,8 @@ impl Default for TestRequest {
             path: Path::new(Url::new(Uri::default())),
             peer_addr: None,
             app_data: Extensions::new(),
+            payload: None,
+            payload_len: None,
             #[cfg(feature = "cookies")]
             cookies: CookieJar::new(),
         }
@@ -175,32 +182,97 @@ impl TestRequest {
         self
     }
 
+    /// Set trusted proxies.
+    ///
+    /// See [`HttpServer::trusted_proxies`](crate::HttpServer::trusted_proxies).
+    pub fn trusted_proxies(mut self, proxies: TrustedProxies) -> Self {
+        self.config = self.config.with_trusted_proxies(Some(Arc::new(proxies)));
+        self
+    }
+
+    /// Set server name.
+    ///
+    /// See [`HttpServer::server_name`](crate::HttpServer::server_name).
+    pub fn server_name(mut self, name: impl Into<String>) -> Self {
+        self.config = self.config.with_server_name(Some(name.into()));
+        self
+    }
+
     /// Set request payload.
+    ///
+    /// No `Content-Length` header is set; add one with [`insert_header`](Self::insert_header) if
+    /// needed.
     pub fn set_payload(mut self, data: impl Into<Bytes>) -> Self {
+        self.payload = None;
+        self.payload_len = None;
         self.req.set_payload(data);
         self
     }
 
+    /// Sets a complete payload along with its `Content-Length`, unless that header is already set.
+    fn set_sized_payload(mut self, data: Bytes) -> Self {
+        let len = data.len();
+        self = self.set_payload(data);
+        self.payload_len = Some(len);
+        self
+    }
+
+    /// Set a streaming request payload.
+    ///
+    /// No `Content-Length` header is set, as with a chunked request.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{test::TestRequest, web::Bytes};
+    /// use futures_util::stream;
+    ///
+    /// let chunks = stream::iter(vec![
+    ///     Ok::<_, actix_web::error::PayloadError>(Bytes::from_static(b"hello ")),
+    ///     Ok(Bytes::from_static(b"world")),
+    /// ]);
+    ///
+    /// let req = TestRequest::post().set_payload_stream(chunks).to_request();
+    /// ```
+    pub fn set_payload_stream<S>(mut self, stream: S) -> Self
+    where
+        S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
+    {
+        let stream: BoxedPayloadStream = Box::pin(stream);
+        self.payload = Some(stream.into());
+        self.payload_len = None;
+        self
+    }
+
     /// Serialize `data` to a URL encoded form and set it as the request payload.
     ///
-    /// The `Content-Type` header is set to `application/x-www-form-urlencoded`.
+    /// The `Content-Type` header is set to `application/x-www-form-urlencoded` and the
+    /// `Content-Length` header to the payload length, unless it is already set.
     pub fn set_form(mut self, data: impl Serialize) -> Self {
         let bytes = serde_urlencoded::to_string(&data)
             .expect("Failed to serialize test data as a urlencoded form");
-        self.req.set_payload(bytes);
         self.req.insert_header(ContentType::form_url_encoded());
-        self
+        self.set_sized_payload(bytes.into())
     }
 
     /// Serialize `data` to JSON and set it as the request payload.
     ///
-    /// The `Content-Type` header is set to `application/json`.
+    /// The `Content-Type` header is set to `application/json` and the `Content-Length` header to
+    /// the payload length, unless it is already set.
     pub fn set_json(mut self, data: impl Serialize) -> Self {
         let bytes =
             serde_json::to_string(&data).expect("Failed to serialize test data to json");
-        self.req.set_payload(bytes);
         self.req.insert_header(ContentType::json());
-        self
+        self.set_sized_payload(bytes.into())
+    }
+
+    /// Encode `form` as `multipart/form-data` and set it as the request payload.
+    ///
+    /// The `Content-Type` header is set to `multipart/form-data` with the form's boundary and the
+    /// `Content-Length` header to the payload length, unless it is already set.
+    pub fn set_multipart(mut self, form: TestMultipart) -> Self {
+        let (content_type, body) = form.into_parts();
+        self.req.insert_header((header::CONTENT_TYPE, content_type));
+        self.set_sized_payload(body)
     }
 
     /// Set application data. This is equivalent of `App::data()` method
@@ -226,12 +298,22 @@ impl TestRequest {
 
     fn finish(&mut self) -> Request {
         // mut used when cookie feature is enabled
-        #[allow(unused_mut)]
         let mut req = self.req.finish();
 
+        if let Some(payload) = self.payload.take() {
+            *req.payload() = payload;
+        }
+
+        if let Some(len) = self.payload_len {
+            if !req.head().headers.contains_key(header::CONTENT_LENGTH) {
+                req.headers_mut()
+                    .insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+            }
+        }
+
         #[cfg(feature = "cookies")]
         {
-            use actix_http::header::{HeaderValue, COOKIE};
+            use actix_http::header::COOKIE;
 
             let cookie: String = self
                 .cookies
@@ -342,7 +424,9 @@ mod tests {
     use std::time::SystemTime;
 
     use super::*;
-    use crate::{http::header, test::init_service, web, App, Error, HttpResponse, Responder};
+    use crate::{
+        http::header, test::init_service, web, App, Error, FromRequest, HttpResponse, Responder,
+    };
 
     #[actix_rt::test]
     async fn test_basics() {
@@ -433,4 +517,67 @@ mod tests {
         let res = app.call(req).await.unwrap();
         assert!(res.status().is_success());
     }
+
+    #[actix_rt::test]
+    async fn test_payload_content_length() {
+        let req = TestRequest::post().set_json([1, 2, 3]).to_http_request();
+        assert_eq!(req.headers().get(header::CONTENT_LENGTH).unwrap(), "7");
+        assert_eq!(
+            req.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+
+        let req = TestRequest::post().set_form([("a", "b")]).to_http_request();
+        assert_eq!(req.headers().get(header::CONTENT_LENGTH).unwrap(), "3");
+
+        // explicitly set header is kept
+        let req = TestRequest::post()
+            .insert_header((header::CONTENT_LENGTH, "xxx"))
+            .set_json("abc")
+            .to_http_request();
+        assert_eq!(req.headers().get(header::CONTENT_LENGTH).unwrap(), "xxx");
+
+        let req = TestRequest::post().set_payload("abc").to_http_request();
+        assert!(!req.headers().contains_key(header::CONTENT_LENGTH));
+    }
+
+    #[actix_rt::test]
+    async fn test_payload_stream() {
+        use futures_util::stream;
+
+        let chunks = stream::iter(vec![
+            Ok(Bytes::from_static(b"hello ")),
+            Ok(Bytes::from_static(b"world")),
+        ]);
+
+        let app = init_service(
+            App::new().route("/", web::post().to(|body: Bytes| async move { body })),
+        )
+        .await;
+
+        let req = TestRequest::post().set_payload_stream(chunks).to_request();
+        assert!(!req.head().headers.contains_key(header::CONTENT_LENGTH));
+
+        let res = test::call_and_read_body(&app, req).await;
+        assert_eq!(res, "hello world");
+    }
+
+    #[actix_rt::test]
+    async fn test_multipart() {
+        let (req, mut payload) = TestRequest::post()
+            .set_multipart(TestMultipart::new().text("a", "b"))
+            .to_http_parts();
+
+        let content_type = req.headers().get(header::CONTENT_TYPE).unwrap();
+        assert!(content_type
+            .to_str()
+            .unwrap()
+            .starts_with("multipart/form-data; boundary="));
+
+        let body = Bytes::from_request(&req, &mut payload).await.unwrap();
+        assert_eq!(
+            req.headers().get(header::CONTENT_LENGTH).unwrap(),
+            &body.len().to_string()
+        );
+    }
 }
@@ -0,0 +1,129 @@
+//! TLS helpers for servers.
+//!
+//! # Certificate Selection And Reloading
+//! With the `rustls` feature, [`CertResolver`] selects the server certificate for each TLS
+//! handshake based on the host name the client asked for (SNI). Certificates can be added,
+//! replaced and removed while the server is running.
+//!
+//! # Client Certificates
+//! Connections accepted by [`HttpServer::bind_rustls`](crate::HttpServer::bind_rustls) with a
+//! server config that requests client certificates, e.g. one built with
+//! [`server_config_request_client_cert`](CertResolver::server_config_request_client_cert), or by
+//! [`HttpServer::bind_openssl`](crate::HttpServer::bind_openssl) with an acceptor that verifies
+//! them, make the verified certificate chain of the client available to handlers through the
+//! [`ClientCert`] extractor.
+//!
+//! ```
+//! use actix_web::{tls::ClientCert, Responder};
+//!
+//! async fn whoami(cert: Option<ClientCert>) -> impl Responder {
+//!     match cert {
+//!         Some(cert) => format!("client certificate is {} bytes", cert.leaf().len()),
+//!         None => "no client certificate".to_owned(),
+//!     }
+//! }
+//! ```
+
+use std::{
+    any::Any,
+    future::{ready, Ready},
+};
+
+use crate::{
+    dev::{Extensions, Payload},
+    error::ErrorUnauthorized,
+    rt::net::TcpStream,
+    Error, FromRequest, HttpRequest,
+};
+
+#[cfg(feature = "rustls")]
+mod resolver;
+
+#[cfg(feature = "rustls")]
+pub use self::resolver::{certified_key, CertResolver};
+
+/// Verified certificate chain presented by the client of a TLS connection.
+///
+/// Only available for connections accepted by [`HttpServer`](crate::HttpServer) with rustls using
+/// a server config that requests client certificates, or with OpenSSL using an acceptor that
+/// verifies them. Extracting `ClientCert` fails with `401 Unauthorized` when the client did not
+/// present one; use `Option<ClientCert>` where the certificate is optional.
+#[derive(Debug, Clone)]
+pub struct ClientCert {
+    chain: Vec<Vec<u8>>,
+}
+
+impl ClientCert {
+    /// Returns the DER encoding of the client's own (end-entity) certificate.
+    pub fn leaf(&self) -> &[u8] {
+        &self.chain[0]
+    }
+
+    /// Returns the DER encoded chain, starting with the client's own certificate.
+    pub fn chain(&self) -> &[Vec<u8>] {
+        &self.chain
+    }
+
+    /// Stores the client certificate of a TLS connection in its connection data.
+    pub(crate) fn on_connect(io: &dyn Any, data: &mut Extensions) {
+        let chain = None;
+
+        #[cfg(feature = "rustls")]
+        let chain = chain.or_else(|| rustls_chain(io));
+
+        #[cfg(feature = "openssl")]
+        let chain = chain.or_else(|| openssl_chain(io));
+
+        if let Some(chain) = chain {
+            data.insert(ClientCert { chain });
+        }
+    }
+}
+
+#[cfg(feature = "rustls")]
+fn rustls_chain(io: &dyn Any) -> Option<Vec<Vec<u8>>> {
+    use actix_tls::accept::rustls::TlsStream;
+
+    let chain = io
+        .downcast_ref::<TlsStream<TcpStream>>()?
+        .get_ref()
+        .1
+        .peer_certificates()?;
+
+    (!chain.is_empty()).then(|| chain.iter().map(|cert| cert.0.clone()).collect())
+}
+
+#[cfg(feature = "openssl")]
+fn openssl_chain(io: &dyn Any) -> Option<Vec<Vec<u8>>> {
+    use actix_tls::accept::openssl::TlsStream;
+
+    let ssl = io.downcast_ref::<TlsStream<TcpStream>>()?.ssl();
+
+    // skip certificates that failed verification but were let through by a callback
+    // (0 is X509_V_OK)
+    if ssl.verify_result().as_raw() != 0 {
+        return None;
+    }
+
+    // on the server side, the peer chain does not include the client's own certificate
+    let leaf = ssl.peer_certificate()?;
+    let intermediates = ssl.peer_cert_chain().into_iter().flatten();
+
+    std::iter::once(&*leaf)
+        .chain(intermediates)
+        .map(|cert| cert.to_der().ok())
+        .collect()
+}
+
+impl FromRequest for ClientCert {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.conn_data::<ClientCert>()
+                .cloned()
+                .ok_or_else(|| ErrorUnauthorized("client certificate required")),
+        )
+    }
+}
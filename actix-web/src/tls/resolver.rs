@@ -0,0 +1,320 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+use tls_rustls::{
+    server::{
+        AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient, ClientHello,
+        ResolvesServerCert,
+    },
+    sign::{self, CertifiedKey},
+    Certificate, Error as TlsError, PrivateKey, RootCertStore, ServerConfig,
+};
+
+type SniFn = dyn Fn(&str) -> Option<Arc<CertifiedKey>> + Send + Sync;
+
+/// Server certificate resolver with SNI-based selection and hot reloading.
+///
+/// Certificates can be added, replaced and removed while the server is running, so renewed
+/// certificates are picked up by new connections without a restart.
+///
+/// Certificates are looked up, in order:
+/// 1. by the exact host name sent by the client;
+/// 1. by a wildcard entry (`*.example.com`) matching the host name;
+/// 1. using the callback set with [`sni_fn`](Self::sni_fn);
+/// 1. falling back to the default certificate, which is also used for clients that do not send
+///    a host name.
+///
+/// `CertResolver` is a cheap handle to shared state; clones observe all updates. Use
+/// [`server_config`](Self::server_config) to build a `ServerConfig` for
+/// [`HttpServer::bind_rustls`](crate::HttpServer::bind_rustls).
+///
+/// # Examples
+/// ```no_run
+/// # extern crate tls_rustls as rustls;
+/// use actix_web::{tls::CertResolver, web, App, HttpServer};
+/// use rustls::{Certificate, PrivateKey};
+///
+/// # fn load(_: &str) -> (Vec<Certificate>, PrivateKey) { unimplemented!() }
+/// #[actix_web::main]
+/// async fn main() -> std::io::Result<()> {
+///     let (chain, key) = load("default");
+///     let resolver = CertResolver::new(chain, key).unwrap();
+///
+///     let (chain, key) = load("api.example.com");
+///     resolver.set_cert("api.example.com", chain, key).unwrap();
+///
+///     // later, e.g. after renewal, swap the certificate in place
+///     let reloader = resolver.clone();
+///     # let _ = reloader;
+///
+///     HttpServer::new(|| App::new().route("/", web::get().to(|| async { "Hello!" })))
+///         .bind_rustls("0.0.0.0:8443", resolver.server_config())?
+///         .run()
+///         .await
+/// }
+/// ```
+#[derive(Clone)]
+pub struct CertResolver {
+    inner: Arc<RwLock<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    default: Option<Arc<CertifiedKey>>,
+    by_name: HashMap<String, Arc<CertifiedKey>>,
+    sni_fn: Option<Arc<SniFn>>,
+}
+
+impl CertResolver {
+    /// Constructs a resolver using the given certificate chain and key by default.
+    pub fn new(cert_chain: Vec<Certificate>, key: PrivateKey) -> Result<Self, TlsError> {
+        let resolver = Self::empty();
+        resolver.set_default(cert_chain, key)?;
+        Ok(resolver)
+    }
+
+    /// Constructs a resolver without any certificates.
+    ///
+    /// Handshakes fail until a matching certificate is added.
+    pub fn empty() -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Inner::default())),
+        }
+    }
+
+    /// Sets a callback used to obtain certificates for host names without a configured entry.
+    ///
+    /// The callback receives the lowercase host name and runs during the TLS handshake, so it
+    /// should not block. Use [`certified_key`] to build its return value.
+    pub fn sni_fn<F>(self, sni_fn: F) -> Self
+    where
+        F: Fn(&str) -> Option<Arc<CertifiedKey>> + Send + Sync + 'static,
+    {
+        self.write().sni_fn = Some(Arc::new(sni_fn));
+        self
+    }
+
+    /// Replaces the default certificate.
+    pub fn set_default(
+        &self,
+        cert_chain: Vec<Certificate>,
+        key: PrivateKey,
+    ) -> Result<(), TlsError> {
+        let key = certified_key(cert_chain, key)?;
+        self.write().default = Some(key);
+        Ok(())
+    }
+
+    /// Adds or replaces the certificate for `host_name`.
+    ///
+    /// Host names are case-insensitive and may be a wildcard such as `*.example.com`, which
+    /// matches exactly one additional label.
+    pub fn set_cert(
+        &self,
+        host_name: &str,
+        cert_chain: Vec<Certificate>,
+        key: PrivateKey,
+    ) -> Result<(), TlsError> {
+        let key = certified_key(cert_chain, key)?;
+        self.write()
+            .by_name
+            .insert(host_name.to_ascii_lowercase(), key);
+        Ok(())
+    }
+
+    /// Removes the certificate for `host_name`, returning true if there was one.
+    pub fn remove_cert(&self, host_name: &str) -> bool {
+        self.write()
+            .by_name
+            .remove(&host_name.to_ascii_lowercase())
+            .is_some()
+    }
+
+    /// Builds a server config with safe defaults, no client authentication and this resolver.
+    pub fn server_config(&self) -> ServerConfig {
+        ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_cert_resolver(Arc::new(self.clone()))
+    }
+
+    /// Builds a server config that asks clients for a certificate signed by one of `roots`.
+    ///
+    /// Clients without a certificate are still accepted; a certificate that fails verification
+    /// aborts the handshake.
+    pub fn server_config_request_client_cert(&self, roots: RootCertStore) -> ServerConfig {
+        ServerConfig::builder()
+            .with_safe_defaults()
+            .with_client_cert_verifier(AllowAnyAnonymousOrAuthenticatedClient::new(roots))
+            .with_cert_resolver(Arc::new(self.clone()))
+    }
+
+    /// Builds a server config that only accepts clients with a certificate signed by one of
+    /// `roots`.
+    pub fn server_config_require_client_cert(&self, roots: RootCertStore) -> ServerConfig {
+        ServerConfig::builder()
+            .with_safe_defaults()
+            .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots))
+            .with_cert_resolver(Arc::new(self.clone()))
+    }
+
+    fn lookup(&self, host_name: Option<&str>) -> Option<Arc<CertifiedKey>> {
+        let host_name = match host_name {
+            Some(host_name) => host_name.to_ascii_lowercase(),
+            None => return self.inner.read().unwrap().default.clone(),
+        };
+
+        let (sni_fn, default) = {
+            let inner = self.inner.read().unwrap();
+
+            let wildcard = || {
+                let (_, parent) = host_name.split_once('.')?;
+                inner.by_name.get(&format!("*.{}", parent)).cloned()
+            };
+
+            if let Some(key) = inner.by_name.get(&host_name).cloned().or_else(wildcard) {
+                return Some(key);
+            }
+
+            (inner.sni_fn.clone(), inner.default.clone())
+        };
+
+        // the lock is released so that the callback can add certificates to this resolver
+        sni_fn.and_then(|sni_fn| sni_fn(&host_name)).or(default)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Inner> {
+        self.inner.write().unwrap()
+    }
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        self.lookup(client_hello.server_name())
+    }
+}
+
+impl fmt::Debug for CertResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.read().unwrap();
+
+        f.debug_struct("CertResolver")
+            .field("has_default", &inner.default.is_some())
+            .field("host_names", &inner.by_name.keys().collect::<Vec<_>>())
+            .field("has_sni_fn", &inner.sni_fn.is_some())
+            .finish()
+    }
+}
+
+/// Combines a certificate chain and private key into a `CertifiedKey`.
+///
+/// Fails if the private key type is not supported.
+pub fn certified_key(
+    cert_chain: Vec<Certificate>,
+    key: PrivateKey,
+) -> Result<Arc<CertifiedKey>, TlsError> {
+    let key = sign::any_supported_type(&key)
+        .map_err(|_| TlsError::General("unsupported private key type".to_owned()))?;
+
+    Ok(Arc::new(CertifiedKey::new(cert_chain, key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(host_name: &str) -> (Vec<Certificate>, PrivateKey) {
+        let cert = rcgen::generate_simple_self_signed(vec![host_name.to_owned()]).unwrap();
+        let chain = vec![Certificate(cert.serialize_der().unwrap())];
+        let key = PrivateKey(cert.serialize_private_key_der());
+        (chain, key)
+    }
+
+    fn cert_of(key: Option<Arc<CertifiedKey>>) -> Option<Vec<u8>> {
+        key.map(|key| key.cert[0].0.clone())
+    }
+
+    #[test]
+    fn sni_selection() {
+        let default = generate("default");
+        let api = generate("api.example.com");
+        let wildcard = generate("*.example.com");
+
+        let resolver = CertResolver::new(default.0.clone(), default.1).unwrap();
+        resolver
+            .set_cert("API.example.com", api.0.clone(), api.1)
+            .unwrap();
+        resolver
+            .set_cert("*.example.com", wildcard.0.clone(), wildcard.1)
+            .unwrap();
+
+        let default = Some(default.0[0].0.clone());
+        let api = Some(api.0[0].0.clone());
+        let wildcard = Some(wildcard.0[0].0.clone());
+
+        assert_eq!(cert_of(resolver.lookup(None)), default);
+        assert_eq!(cert_of(resolver.lookup(Some("api.example.com"))), api);
+        assert_eq!(cert_of(resolver.lookup(Some("www.example.com"))), wildcard);
+        assert_eq!(cert_of(resolver.lookup(Some("a.b.example.com"))), default);
+        assert_eq!(cert_of(resolver.lookup(Some("example.org"))), default);
+    }
+
+    #[test]
+    fn sni_fn_and_reload() {
+        let from_fn = generate("dynamic.local");
+        let dynamic = certified_key(from_fn.0.clone(), from_fn.1).unwrap();
+
+        let resolver = CertResolver::empty().sni_fn(move |host_name| {
+            (host_name == "dynamic.local").then(|| Arc::clone(&dynamic))
+        });
+
+        assert!(resolver.lookup(None).is_none());
+        assert_eq!(
+            cert_of(resolver.lookup(Some("Dynamic.Local"))),
+            Some(from_fn.0[0].0.clone())
+        );
+
+        // updates through a clone are visible to the original handle
+        let reloader = resolver.clone();
+        let first = generate("static.local");
+        let second = generate("static.local");
+
+        reloader
+            .set_cert("static.local", first.0.clone(), first.1)
+            .unwrap();
+        assert_eq!(
+            cert_of(resolver.lookup(Some("static.local"))),
+            Some(first.0[0].0.clone())
+        );
+
+        reloader
+            .set_cert("static.local", second.0.clone(), second.1)
+            .unwrap();
+        assert_eq!(
+            cert_of(resolver.lookup(Some("static.local"))),
+            Some(second.0[0].0.clone())
+        );
+
+        assert!(reloader.remove_cert("static.local"));
+        assert!(resolver.lookup(Some("static.local")).is_none());
+    }
+
+    #[test]
+    fn sni_fn_can_set_cert() {
+        let resolver = CertResolver::empty();
+        let cache = resolver.clone();
+
+        let resolver = resolver.sni_fn(move |host_name| {
+            let (chain, key) = generate(host_name);
+            cache.set_cert(host_name, chain, key).unwrap();
+            cache.lookup(Some(host_name))
+        });
+
+        let issued = cert_of(resolver.lookup(Some("issued.local")));
+        assert!(issued.is_some());
+        assert_eq!(cert_of(resolver.lookup(Some("issued.local"))), issued);
+    }
+}
@@ -1,19 +1,32 @@
 //! Common extractors and responders.
 
+mod auth;
 mod either;
 mod form;
 mod header;
 mod json;
 mod path;
 mod payload;
+#[cfg(feature = "protobuf")]
+mod protobuf;
 mod query;
 mod readlines;
+mod streaming_json;
+mod upgrade;
+mod upload;
 
+pub use self::auth::{AuthConfig, BasicAuth, BearerAuth};
 pub use self::either::Either;
 pub use self::form::{Form, FormConfig, UrlEncoded};
 pub use self::header::Header;
 pub use self::json::{Json, JsonBody, JsonConfig};
 pub use self::path::{Path, PathConfig};
-pub use self::payload::{Payload, PayloadConfig};
+pub use self::payload::{JsonStream, LimitedPayload, Payload, PayloadConfig, PayloadLines};
+#[cfg(feature = "protobuf")]
+#[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+pub use self::protobuf::{ProtoBuf, ProtoBufConfig};
 pub use self::query::{Query, QueryConfig};
 pub use self::readlines::Readlines;
+pub use self::streaming_json::StreamingJson;
+pub use self::upgrade::{Upgrade, Upgraded};
+pub use self::upload::{UploadOffset, UploadProgress, UploadRange};
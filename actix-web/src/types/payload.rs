@@ -3,6 +3,7 @@
 use std::{
     borrow::Cow,
     future::Future,
+    marker::PhantomData,
     pin::Pin,
     str,
     task::{Context, Poll},
@@ -13,11 +14,15 @@ use actix_utils::future::{ready, Either, Ready};
 use bytes::{Bytes, BytesMut};
 use encoding_rs::{Encoding, UTF_8};
 use futures_core::{ready, stream::Stream};
+use futures_util::StreamExt as _;
 use mime::Mime;
+use serde::de::DeserializeOwned;
 
 use crate::{
-    dev, error::ErrorBadRequest, http::header, web, Error, FromRequest, HttpMessage,
-    HttpRequest,
+    dev,
+    error::{ErrorBadRequest, JsonPayloadError, ReadlinesError},
+    http::header,
+    web, Error, FromRequest, HttpMessage, HttpRequest,
 };
 
 /// Extract a request's raw payload stream.
@@ -43,6 +48,23 @@ use crate::{
 ///     Ok(format!("Request Body Bytes:\n{:?}", bytes))
 /// }
 /// ```
+///
+/// # `CONNECT` Tunnels
+/// For `CONNECT` requests, the payload is the raw byte stream that the client sends after the
+/// request head. A streaming response to such a request is sent to the client as-is, without
+/// `Content-Length` or chunked framing. Together they expose the tunneled connection to handlers,
+/// for example to build a forward proxy.
+///
+/// ```
+/// use actix_web::{guard, web, App, HttpResponse};
+///
+/// // echoes everything sent through the tunnel back to the client
+/// async fn tunnel(body: web::Payload) -> HttpResponse {
+///     HttpResponse::Ok().streaming(body)
+/// }
+///
+/// let app = App::new().default_service(web::route().guard(guard::Connect()).to(tunnel));
+/// ```
 pub struct Payload(dev::Payload);
 
 impl Payload {
@@ -51,6 +73,102 @@ impl Payload {
     pub fn into_inner(self) -> dev::Payload {
         self.0
     }
+
+    /// Collects the payload into a [`Bytes`] instance.
+    ///
+    /// Returns [`PayloadError::Overflow`] once more than `limit` bytes are received.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{post, web};
+    ///
+    /// #[post("/")]
+    /// async fn index(body: web::Payload) -> actix_web::Result<String> {
+    ///     let bytes = body.to_bytes(1024).await?;
+    ///     Ok(format!("Received {} bytes", bytes.len()))
+    /// }
+    /// ```
+    pub async fn to_bytes(mut self, limit: usize) -> Result<Bytes, PayloadError> {
+        let mut buf = BytesMut::new();
+
+        while let Some(chunk) = self.next().await {
+            let chunk = chunk?;
+
+            if buf.len() + chunk.len() > limit {
+                return Err(PayloadError::Overflow);
+            }
+
+            buf.extend_from_slice(&chunk);
+        }
+
+        Ok(buf.freeze())
+    }
+
+    /// Returns a stream of the UTF-8 lines of the payload.
+    ///
+    /// Lines are split on `\n` and yielded without the `\n` or `\r\n` line ending. By default,
+    /// lines of up to 256kB are accepted; see [`PayloadLines::limit`].
+    ///
+    /// # Examples
+    /// ```
+    /// use futures_util::stream::StreamExt as _;
+    /// use actix_web::{post, web};
+    ///
+    /// #[post("/")]
+    /// async fn index(body: web::Payload) -> actix_web::Result<String> {
+    ///     let mut lines = body.lines();
+    ///     let mut count = 0;
+    ///
+    ///     while let Some(line) = lines.next().await {
+    ///         line?;
+    ///         count += 1;
+    ///     }
+    ///
+    ///     Ok(format!("Received {} lines", count))
+    /// }
+    /// ```
+    pub fn lines(self) -> PayloadLines {
+        PayloadLines {
+            lines: LineSplitter::new(self, DEFAULT_CONFIG_LIMIT),
+        }
+    }
+
+    /// Returns a stream of the values in a newline-delimited JSON (ND-JSON) payload.
+    ///
+    /// Each non-blank line is deserialized into a `T`. By default, lines of up to 2MB are
+    /// accepted; see [`JsonStream::limit`].
+    ///
+    /// # Examples
+    /// ```
+    /// use futures_util::stream::StreamExt as _;
+    /// use actix_web::{post, web};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Event {
+    ///     kind: String,
+    /// }
+    ///
+    /// #[post("/events")]
+    /// async fn ingest(body: web::Payload) -> actix_web::Result<String> {
+    ///     let mut events = body.json_stream::<Event>();
+    ///     let mut count = 0;
+    ///
+    ///     while let Some(event) = events.next().await {
+    ///         let event = event?;
+    ///         log::info!("received {} event", event.kind);
+    ///         count += 1;
+    ///     }
+    ///
+    ///     Ok(format!("Ingested {} events", count))
+    /// }
+    /// ```
+    pub fn json_stream<T: DeserializeOwned>(self) -> JsonStream<T> {
+        JsonStream {
+            lines: LineSplitter::new(self, DEFAULT_JSON_LINE_LIMIT),
+            _phantom: PhantomData,
+        }
+    }
 }
 
 impl Stream for Payload {
@@ -73,6 +191,256 @@ impl FromRequest for Payload {
     }
 }
 
+/// Extract a request's raw payload stream, limited in size by [`PayloadConfig`].
+///
+/// Works like the [`Payload`] extractor, except that requests with a `Content-Length` over the
+/// configured limit are rejected with `413 Payload Too Large` and the stream fails with
+/// [`PayloadError::Overflow`] once more bytes than the limit are received. The mime type
+/// condition of the config is also checked.
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, web, App};
+///
+/// #[post("/upload")]
+/// async fn upload(body: web::LimitedPayload) -> actix_web::Result<String> {
+///     let bytes = body.into_inner().to_bytes(usize::MAX).await?;
+///     Ok(format!("Received {} bytes", bytes.len()))
+/// }
+///
+/// let app = App::new()
+///     .app_data(web::PayloadConfig::new(4096))
+///     .service(upload);
+/// ```
+pub struct LimitedPayload(Payload);
+
+impl LimitedPayload {
+    /// Unwrap to the payload stream, which still enforces the size limit.
+    #[inline]
+    pub fn into_inner(self) -> Payload {
+        self.0
+    }
+}
+
+impl Stream for LimitedPayload {
+    type Item = Result<Bytes, PayloadError>;
+
+    #[inline]
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.0).poll_next(cx)
+    }
+}
+
+impl FromRequest for LimitedPayload {
+    type Error = Error;
+    type Future = Ready<Result<LimitedPayload, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+        let cfg = PayloadConfig::from_req(req);
+
+        if let Err(err) = cfg.check_mimetype(req) {
+            return ready(Err(err));
+        }
+
+        let length = req
+            .headers()
+            .get(&header::CONTENT_LENGTH)
+            .and_then(|len| len.to_str().ok())
+            .and_then(|len| len.parse::<usize>().ok());
+
+        if matches!(length, Some(len) if len > cfg.limit) {
+            return ready(Err(PayloadError::Overflow.into()));
+        }
+
+        let stream: actix_http::BoxedPayloadStream = Box::pin(LimitedStream {
+            stream: payload.take(),
+            remaining: cfg.limit,
+        });
+
+        ready(Ok(LimitedPayload(Payload(stream.into()))))
+    }
+}
+
+/// Payload stream that fails once more than `remaining` bytes are received.
+struct LimitedStream {
+    stream: dev::Payload,
+    remaining: usize,
+}
+
+impl Stream for LimitedStream {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        match ready!(Pin::new(&mut this.stream).poll_next(cx)) {
+            Some(Ok(chunk)) if chunk.len() > this.remaining => {
+                this.stream = dev::Payload::None;
+                Poll::Ready(Some(Err(PayloadError::Overflow)))
+            }
+            Some(Ok(chunk)) => {
+                this.remaining -= chunk.len();
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            res => Poll::Ready(res),
+        }
+    }
+}
+
+/// Stream of the lines of a payload, returned by [`Payload::lines`].
+pub struct PayloadLines {
+    lines: LineSplitter,
+}
+
+impl PayloadLines {
+    /// Set maximum accepted line length in bytes. The default limit is 256kB.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.lines.limit = limit;
+        self
+    }
+}
+
+impl Stream for PayloadLines {
+    type Item = Result<String, ReadlinesError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let line = match ready!(self.lines.poll_line(cx)) {
+            Some(Ok(line)) => line,
+            Some(Err(PayloadError::Overflow)) => {
+                return Poll::Ready(Some(Err(ReadlinesError::LimitOverflow)))
+            }
+            Some(Err(err)) => return Poll::Ready(Some(Err(err.into()))),
+            None => return Poll::Ready(None),
+        };
+
+        let line = str::from_utf8(&line)
+            .map(str::to_owned)
+            .map_err(|_| ReadlinesError::EncodingError);
+
+        Poll::Ready(Some(line))
+    }
+}
+
+/// Stream of the values in a newline-delimited JSON payload, returned by
+/// [`Payload::json_stream`].
+pub struct JsonStream<T> {
+    lines: LineSplitter,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> JsonStream<T> {
+    /// Set maximum accepted line length in bytes. The default limit is 2MB.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.lines.limit = limit;
+        self
+    }
+}
+
+impl<T: DeserializeOwned> Stream for JsonStream<T> {
+    type Item = Result<T, JsonPayloadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let line = match ready!(self.lines.poll_line(cx)) {
+                Some(Ok(line)) => line,
+                Some(Err(PayloadError::Overflow)) => {
+                    let limit = self.lines.limit;
+                    return Poll::Ready(Some(Err(JsonPayloadError::Overflow { limit })));
+                }
+                Some(Err(err)) => return Poll::Ready(Some(Err(err.into()))),
+                None => return Poll::Ready(None),
+            };
+
+            // blank lines do not hold a value
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+
+            let value = serde_json::from_slice(&line).map_err(JsonPayloadError::Deserialize);
+            return Poll::Ready(Some(value));
+        }
+    }
+}
+
+/// Splits a payload into lines of at most `limit` bytes.
+struct LineSplitter {
+    stream: Payload,
+    buf: BytesMut,
+
+    /// Number of bytes at the start of `buf` known not to contain a line feed.
+    scanned: usize,
+
+    limit: usize,
+    eof: bool,
+}
+
+impl LineSplitter {
+    fn new(stream: Payload, limit: usize) -> Self {
+        Self {
+            stream,
+            buf: BytesMut::new(),
+            scanned: 0,
+            limit,
+            eof: false,
+        }
+    }
+
+    /// Polls for the next line, without its line ending.
+    ///
+    /// Lines longer than `limit` are reported as `PayloadError::Overflow`.
+    fn poll_line(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, PayloadError>>> {
+        loop {
+            if let Some(pos) = self.buf[self.scanned..].iter().position(|&b| b == b'\n') {
+                let end = self.scanned + pos;
+                self.scanned = 0;
+
+                let mut line = self.buf.split_to(end + 1);
+                line.truncate(end);
+                if line.ends_with(b"\r") {
+                    line.truncate(end - 1);
+                }
+
+                if line.len() > self.limit {
+                    return Poll::Ready(Some(Err(self.fail(PayloadError::Overflow))));
+                }
+
+                return Poll::Ready(Some(Ok(line.freeze())));
+            }
+
+            self.scanned = self.buf.len();
+
+            if self.buf.len() > self.limit {
+                return Poll::Ready(Some(Err(self.fail(PayloadError::Overflow))));
+            }
+
+            if self.eof {
+                self.scanned = 0;
+
+                return Poll::Ready(if self.buf.is_empty() {
+                    None
+                } else {
+                    Some(Ok(self.buf.split().freeze()))
+                });
+            }
+
+            match ready!(Pin::new(&mut self.stream).poll_next(cx)) {
+                Some(Ok(chunk)) => self.buf.extend_from_slice(&chunk),
+                Some(Err(err)) => return Poll::Ready(Some(Err(self.fail(err)))),
+                None => self.eof = true,
+            }
+        }
+    }
+
+    /// Ends the stream after an error.
+    fn fail(&mut self, err: PayloadError) -> PayloadError {
+        self.buf.clear();
+        self.scanned = 0;
+        self.eof = true;
+        err
+    }
+}
+
 /// Extract binary data from a request's payload.
 ///
 /// Collects request payload stream into a [Bytes] instance.
@@ -195,7 +563,7 @@ fn bytes_to_string(body: Bytes, encoding: &'static Encoding) -> Result<String, E
 
 /// Configuration for request payloads.
 ///
-/// Applies to the built-in [`Bytes`] and [`String`] extractors.
+/// Applies to the built-in [`Bytes`], [`String`] and [`LimitedPayload`] extractors.
 /// Note that the [`Payload`] extractor does not automatically check
 /// conformance with this configuration to allow more flexibility when
 /// building extractors on top of [`Payload`].
@@ -263,6 +631,8 @@ impl PayloadConfig {
 
 const DEFAULT_CONFIG_LIMIT: usize = 262_144; // 2^18 bytes (~256kB)
 
+const DEFAULT_JSON_LINE_LIMIT: usize = 2_097_152; // 2^21 bytes (~2MB)
+
 /// Allow shared refs used as defaults.
 const DEFAULT_CONFIG: PayloadConfig = PayloadConfig {
     limit: DEFAULT_CONFIG_LIMIT,
@@ -535,4 +905,117 @@ mod tests {
             _ => unreachable!("error"),
         }
     }
+
+    fn chunked_payload(chunks: &[&'static [u8]]) -> Payload {
+        let chunks = chunks
+            .iter()
+            .map(|chunk| Ok(Bytes::from_static(chunk)))
+            .collect::<Vec<_>>();
+
+        let stream: actix_http::BoxedPayloadStream =
+            Box::pin(futures_util::stream::iter(chunks));
+        Payload(stream.into())
+    }
+
+    #[actix_rt::test]
+    async fn test_payload_to_bytes() {
+        let body = chunked_payload(&[b"hello", b" ", b"world"]);
+        assert_eq!(body.to_bytes(11).await.unwrap(), "hello world");
+
+        let body = chunked_payload(&[b"hello", b" ", b"world"]);
+        assert!(matches!(
+            body.to_bytes(10).await,
+            Err(PayloadError::Overflow)
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_payload_lines() {
+        let body = chunked_payload(&[b"foo\r\nba", b"r\n", b"", b"\nbaz"]);
+        let lines = body.lines().collect::<Vec<_>>().await;
+        let lines = lines.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(lines, ["foo", "bar", "", "baz"]);
+
+        let body = chunked_payload(&[b"foo\n", b"barbaz", b"qux\n"]);
+        let mut lines = body.lines().limit(8);
+        assert_eq!(lines.next().await.unwrap().unwrap(), "foo");
+        assert!(matches!(
+            lines.next().await.unwrap(),
+            Err(ReadlinesError::LimitOverflow)
+        ));
+        assert!(lines.next().await.is_none());
+
+        let body = chunked_payload(&[b"\xff\n"]);
+        let mut lines = body.lines();
+        assert!(matches!(
+            lines.next().await.unwrap(),
+            Err(ReadlinesError::EncodingError)
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_payload_json_stream() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Event {
+            id: u32,
+        }
+
+        let body = chunked_payload(&[b"{\"id\": 1}\n\n  \r\n{\"i", b"d\": 2}", b"\nnull\n"]);
+        let mut events = body.json_stream::<Event>();
+        assert_eq!(events.next().await.unwrap().unwrap(), Event { id: 1 });
+        assert_eq!(events.next().await.unwrap().unwrap(), Event { id: 2 });
+        assert!(matches!(
+            events.next().await.unwrap(),
+            Err(JsonPayloadError::Deserialize(_))
+        ));
+        assert!(events.next().await.is_none());
+
+        let body = chunked_payload(&[b"{\"id\": 1}\n", b"{\"id\": 1000000}\n"]);
+        let mut events = body.json_stream::<Event>().limit(10);
+        assert_eq!(events.next().await.unwrap().unwrap(), Event { id: 1 });
+        assert!(matches!(
+            events.next().await.unwrap(),
+            Err(JsonPayloadError::Overflow { limit: 10 })
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_limited_payload() {
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_LENGTH, "8"))
+            .app_data(PayloadConfig::new(4))
+            .to_http_parts();
+        let err = LimitedPayload::from_request(&req, &mut pl)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        let (req, mut pl) = TestRequest::default()
+            .set_payload(Bytes::from_static(b"11111111"))
+            .app_data(PayloadConfig::new(8))
+            .to_http_parts();
+        let body = LimitedPayload::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(
+            body.into_inner().to_bytes(usize::MAX).await.unwrap(),
+            "11111111"
+        );
+
+        let mut pl = chunked_payload(&[b"1111", b"1111", b"1"]).into_inner();
+        let req = TestRequest::default()
+            .app_data(PayloadConfig::new(8))
+            .to_http_request();
+        let body = LimitedPayload::from_request(&req, &mut pl).await.unwrap();
+        let res = body.collect::<Vec<_>>().await;
+        assert_eq!(res.len(), 3);
+        assert!(matches!(res[2], Err(PayloadError::Overflow)));
+
+        let (req, mut pl) = TestRequest::default()
+            .app_data(PayloadConfig::default().mimetype(mime::APPLICATION_JSON))
+            .to_http_parts();
+        assert!(LimitedPayload::from_request(&req, &mut pl).await.is_err());
+    }
 }
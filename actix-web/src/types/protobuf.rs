@@ -0,0 +1,372 @@
+//! For Protocol Buffers helper documentation, see [`ProtoBuf`].
+
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    ops,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures_core::ready;
+use prost::Message;
+
+use actix_http::Payload;
+
+use super::payload::HttpMessageBody;
+use crate::{
+    error::{Error, PayloadError, ProtoBufPayloadError},
+    extract::FromRequest,
+    http::{
+        header::{HeaderValue, CONTENT_TYPE},
+        StatusCode,
+    },
+    request::HttpRequest,
+    web, HttpMessage, HttpResponse, Responder,
+};
+
+/// Content type of Protocol Buffers payloads and responses.
+const PROTOBUF: &str = "application/protobuf";
+
+/// Legacy content type of Protocol Buffers payloads, also accepted by the extractor.
+const X_PROTOBUF: &str = "application/x-protobuf";
+
+/// Protocol Buffers extractor and responder.
+///
+/// `ProtoBuf` mirrors [`Json`](super::Json) for messages implementing [`prost::Message`], such as
+/// those generated by `prost-build`.
+///
+/// # Extractor
+/// Decodes a message from a request payload with an `application/protobuf` (or
+/// `application/x-protobuf`) content type. Use [`ProtoBufConfig`] to configure extraction
+/// options.
+///
+/// ```
+/// use actix_web::{post, web};
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// struct Info {
+///     #[prost(string, tag = "1")]
+///     username: String,
+/// }
+///
+/// #[post("/")]
+/// async fn index(info: web::ProtoBuf<Info>) -> String {
+///     format!("Welcome {}!", info.username)
+/// }
+/// ```
+///
+/// # Responder
+/// Encodes the message as the response body, with the `application/protobuf` content type.
+///
+/// ```
+/// use actix_web::{get, web};
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// struct Info {
+///     #[prost(string, tag = "1")]
+///     name: String,
+/// }
+///
+/// #[get("/{name}")]
+/// async fn index(name: web::Path<String>) -> web::ProtoBuf<Info> {
+///     web::ProtoBuf(Info {
+///         name: name.into_inner(),
+///     })
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ProtoBuf<T>(pub T);
+
+impl<T> ProtoBuf<T> {
+    /// Unwrap into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for ProtoBuf<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for ProtoBuf<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// Creates response with OK status code, correct content type header, and encoded payload.
+impl<T: Message> Responder for ProtoBuf<T> {
+    type Body = Vec<u8>;
+
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse<Self::Body> {
+        let mut res = HttpResponse::with_body(StatusCode::OK, self.0.encode_to_vec());
+        res.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(PROTOBUF));
+        res
+    }
+}
+
+/// See [here](#extractor) for example of usage as an extractor.
+impl<T: Message + Default> FromRequest for ProtoBuf<T> {
+    type Error = Error;
+    type Future = ProtoBufExtractFut<T>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = ProtoBufConfig::from_req(req);
+
+        let is_protobuf = matches!(
+            req.mime_type(),
+            Ok(Some(mime)) if mime.essence_str() == PROTOBUF || mime.essence_str() == X_PROTOBUF
+        );
+
+        let body = if is_protobuf {
+            Ok(HttpMessageBody::new(req, payload).limit(config.limit))
+        } else {
+            Err(ProtoBufPayloadError::ContentType)
+        };
+
+        ProtoBufExtractFut {
+            req: Some(req.clone()),
+            body: Some(body),
+            limit: config.limit,
+            err_handler: config.err_handler.clone(),
+            _message: PhantomData,
+        }
+    }
+}
+
+type ProtoBufErrorHandler =
+    Option<Arc<dyn Fn(ProtoBufPayloadError, &HttpRequest) -> Error + Send + Sync>>;
+
+#[doc(hidden)]
+pub struct ProtoBufExtractFut<T> {
+    req: Option<HttpRequest>,
+    body: Option<Result<HttpMessageBody, ProtoBufPayloadError>>,
+    limit: usize,
+    err_handler: ProtoBufErrorHandler,
+    _message: PhantomData<fn() -> T>,
+}
+
+impl<T: Message + Default> Future for ProtoBufExtractFut<T> {
+    type Output = Result<ProtoBuf<T>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        let res = match this.body.as_mut().unwrap() {
+            Ok(body) => match ready!(Pin::new(body).poll(cx)) {
+                Ok(bytes) => T::decode(bytes).map_err(ProtoBufPayloadError::Deserialize),
+                Err(PayloadError::Overflow) => {
+                    Err(ProtoBufPayloadError::Overflow { limit: this.limit })
+                }
+                Err(err) => Err(err.into()),
+            },
+            Err(_) => Err(this.body.take().unwrap().err().unwrap()),
+        };
+
+        let req = this.req.take().unwrap();
+
+        Poll::Ready(match res {
+            Ok(msg) => Ok(ProtoBuf(msg)),
+            Err(err) => {
+                log::debug!(
+                    "Failed to decode ProtoBuf from payload. Request path: {}",
+                    req.path()
+                );
+
+                match this.err_handler.as_ref() {
+                    Some(err_handler) => Err((*err_handler)(err, &req)),
+                    None => Err(err.into()),
+                }
+            }
+        })
+    }
+}
+
+/// `ProtoBuf` extractor configuration.
+///
+/// # Examples
+/// ```
+/// use actix_web::{error, post, web, App, HttpResponse};
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// struct Info {
+///     #[prost(string, tag = "1")]
+///     name: String,
+/// }
+///
+/// #[post("/")]
+/// async fn index(info: web::ProtoBuf<Info>) -> String {
+///     format!("Welcome {}!", info.name)
+/// }
+///
+/// // custom `ProtoBuf` extractor configuration
+/// let protobuf_cfg = web::ProtoBufConfig::default()
+///     // limit request payload size to 4kB
+///     .limit(4096)
+///     // use custom error handler
+///     .error_handler(|err, _req| {
+///         error::InternalError::from_response(err, HttpResponse::Conflict().into()).into()
+///     });
+///
+/// App::new()
+///     .app_data(protobuf_cfg)
+///     .service(index);
+/// ```
+#[derive(Clone)]
+pub struct ProtoBufConfig {
+    limit: usize,
+    err_handler: ProtoBufErrorHandler,
+}
+
+impl ProtoBufConfig {
+    /// Set maximum accepted payload size. By default this limit is 256kB.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Set custom error handler.
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(ProtoBufPayloadError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.err_handler = Some(Arc::new(f));
+        self
+    }
+
+    /// Extract payload config from app data. Check both `T` and `Data<T>`, in that order, and fall
+    /// back to the default payload config.
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+const DEFAULT_LIMIT: usize = 262_144; // 256kB
+
+/// Allow shared refs used as default.
+const DEFAULT_CONFIG: ProtoBufConfig = ProtoBufConfig {
+    limit: DEFAULT_LIMIT,
+    err_handler: None,
+};
+
+impl Default for ProtoBufConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG.clone()
+    }
+}
+
+impl fmt::Debug for ProtoBufConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProtoBufConfig")
+            .field("limit", &self.limit)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::{
+        body,
+        http::header::{self, CONTENT_LENGTH},
+        test::TestRequest,
+    };
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct MyObject {
+        #[prost(string, tag = "1")]
+        name: String,
+    }
+
+    fn encoded() -> Bytes {
+        Bytes::from(
+            MyObject {
+                name: "test".to_owned(),
+            }
+            .encode_to_vec(),
+        )
+    }
+
+    #[actix_rt::test]
+    async fn test_responder() {
+        let req = TestRequest::default().to_http_request();
+
+        let res = ProtoBuf(MyObject {
+            name: "test".to_owned(),
+        })
+        .respond_to(&req);
+
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/protobuf"
+        );
+        assert_eq!(body::to_bytes(res.into_body()).await.unwrap(), encoded());
+    }
+
+    #[actix_rt::test]
+    async fn test_extract() {
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/x-protobuf"))
+            .set_payload(encoded())
+            .to_http_parts();
+
+        let msg = ProtoBuf::<MyObject>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(msg.name, "test");
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .set_payload(encoded())
+            .to_http_parts();
+
+        let err = ProtoBuf::<MyObject>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::BAD_REQUEST
+        );
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/protobuf"))
+            .insert_header((CONTENT_LENGTH, "16"))
+            .set_payload(Bytes::from_static(&[0xff; 16]))
+            .app_data(ProtoBufConfig::default().limit(10))
+            .to_http_parts();
+
+        let err = ProtoBuf::<MyObject>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/protobuf"))
+            .set_payload(Bytes::from_static(&[0xff; 4]))
+            .to_http_parts();
+
+        let err = ProtoBuf::<MyObject>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! For streaming JSON responder documentation, see [`StreamingJson`].
+
+use std::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_core::{ready, Stream};
+use pin_project_lite::pin_project;
+use serde::Serialize;
+
+use crate::{
+    body::{BodySize, MessageBody},
+    error::JsonPayloadError,
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    HttpRequest, HttpResponse, Responder,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Framing {
+    /// One value per line.
+    Lines,
+
+    /// Values in a JSON array; `started` is true once the opening bracket has been sent.
+    Array { started: bool },
+}
+
+pin_project! {
+    /// Responder serializing the items of a stream as JSON as they are produced.
+    ///
+    /// Items are sent as soon as they are serialized, using chunked encoding, so large results
+    /// never need to be buffered in full. Two framings are supported:
+    /// - [newline-delimited JSON](Self::ndjson), one value per line, with the
+    ///   `application/x-ndjson` content type;
+    /// - a [JSON array](Self::array), with the `application/json` content type, for clients that
+    ///   expect a single JSON document.
+    ///
+    /// If an item fails to serialize, the response is aborted; since the status has already been
+    /// sent, the client sees a truncated body.
+    ///
+    /// To read newline-delimited JSON request payloads, see [`Payload::json_stream`].
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{get, web};
+    /// use futures_util::stream;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Row {
+    ///     id: u32,
+    /// }
+    ///
+    /// #[get("/rows")]
+    /// async fn rows() -> web::StreamingJson<impl futures_core::Stream<Item = Row>> {
+    ///     web::StreamingJson::array(stream::iter((0..1000).map(|id| Row { id })))
+    /// }
+    /// ```
+    ///
+    /// [`Payload::json_stream`]: crate::web::Payload::json_stream
+    pub struct StreamingJson<S> {
+        #[pin]
+        stream: S,
+        framing: Framing,
+        done: bool,
+    }
+}
+
+impl<S> StreamingJson<S>
+where
+    S: Stream,
+    S::Item: Serialize,
+{
+    /// Constructs a responder sending each item on its own line (newline-delimited JSON).
+    pub fn ndjson(stream: S) -> Self {
+        StreamingJson {
+            stream,
+            framing: Framing::Lines,
+            done: false,
+        }
+    }
+
+    /// Constructs a responder sending the items as the elements of a JSON array.
+    pub fn array(stream: S) -> Self {
+        StreamingJson {
+            stream,
+            framing: Framing::Array { started: false },
+            done: false,
+        }
+    }
+}
+
+impl<S> MessageBody for StreamingJson<S>
+where
+    S: Stream,
+    S::Item: Serialize,
+{
+    type Error = JsonPayloadError;
+
+    #[inline]
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.project();
+
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        let item = match ready!(this.stream.poll_next(cx)) {
+            Some(item) => item,
+            None => {
+                *this.done = true;
+
+                return Poll::Ready(match *this.framing {
+                    Framing::Lines => None,
+                    Framing::Array { started: false } => Some(Ok(Bytes::from_static(b"[]"))),
+                    Framing::Array { started: true } => Some(Ok(Bytes::from_static(b"]"))),
+                });
+            }
+        };
+
+        let mut buf = Vec::with_capacity(64);
+
+        if let Framing::Array { ref mut started } = this.framing {
+            buf.push(if *started { b',' } else { b'[' });
+            *started = true;
+        }
+
+        if let Err(err) = serde_json::to_writer(&mut buf, &item) {
+            *this.done = true;
+            return Poll::Ready(Some(Err(JsonPayloadError::Serialize(err))));
+        }
+
+        if *this.framing == Framing::Lines {
+            buf.push(b'\n');
+        }
+
+        Poll::Ready(Some(Ok(Bytes::from(buf))))
+    }
+}
+
+impl<S> Responder for StreamingJson<S>
+where
+    S: Stream + 'static,
+    S::Item: Serialize,
+{
+    type Body = Self;
+
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse<Self::Body> {
+        let content_type = match self.framing {
+            Framing::Lines => HeaderValue::from_static("application/x-ndjson"),
+            Framing::Array { .. } => HeaderValue::from_static("application/json"),
+        };
+
+        let mut res = HttpResponse::with_body(StatusCode::OK, self);
+        res.headers_mut().insert(header::CONTENT_TYPE, content_type);
+        res
+    }
+}
+
+impl<S> fmt::Debug for StreamingJson<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamingJson")
+            .field("framing", &self.framing)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::stream;
+    use serde::Serialize;
+
+    use super::*;
+    use crate::{body, test::TestRequest};
+
+    #[derive(Serialize)]
+    struct Row {
+        id: u32,
+    }
+
+    fn rows(n: u32) -> impl Stream<Item = Row> {
+        stream::iter((0..n).map(|id| Row { id }))
+    }
+
+    #[actix_rt::test]
+    async fn ndjson() {
+        let req = TestRequest::default().to_http_request();
+
+        let res = StreamingJson::ndjson(rows(2)).respond_to(&req);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/x-ndjson"
+        );
+        assert_eq!(res.body().size(), BodySize::Stream);
+
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "{\"id\":0}\n{\"id\":1}\n");
+
+        let body = body::to_bytes(StreamingJson::ndjson(rows(0)))
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn array() {
+        let req = TestRequest::default().to_http_request();
+
+        let res = StreamingJson::array(rows(3)).respond_to(&req);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        let values: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(body, "[{\"id\":0},{\"id\":1},{\"id\":2}]");
+
+        let body = body::to_bytes(StreamingJson::array(rows(0))).await.unwrap();
+        assert_eq!(body, "[]");
+    }
+}
@@ -0,0 +1,140 @@
+//! For protocol upgrade extractor documentation, see [`Upgrade`].
+
+use std::{fmt, future::Future};
+
+use actix_http::h1::OnUpgrade;
+use actix_utils::future::{ready, Ready};
+
+use crate::{
+    dev, error::ErrorBadRequest, http::header, Error, FromRequest, HttpRequest, HttpResponse,
+};
+
+pub use actix_http::h1::Upgraded;
+
+/// Extractor for requests that ask to switch to another protocol using the `Upgrade` header.
+///
+/// [`respond`](Self::respond) switches the connection with a `101 Switching Protocols` response
+/// and hands it to a handler as an [`Upgraded`] I/O object, so that custom protocols can be
+/// implemented without an upgrade service for the whole server. WebSocket handshakes are better
+/// served by the `actix-web-actors` crate, which also validates the WebSocket headers.
+///
+/// Requests without `Connection: upgrade` and `Upgrade` headers are rejected with
+/// `400 Bad Request`.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, web, HttpResponse};
+/// use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+///
+/// #[get("/echo")]
+/// async fn echo(upgrade: web::Upgrade) -> HttpResponse {
+///     if !upgrade.protocols().any(|proto| proto == "echo") {
+///         return HttpResponse::BadRequest().finish();
+///     }
+///
+///     upgrade.respond("echo", |mut io| async move {
+///         let mut buf = [0; 1024];
+///
+///         loop {
+///             match io.read(&mut buf).await {
+///                 Ok(0) | Err(_) => break,
+///                 Ok(n) => {
+///                     if io.write_all(&buf[..n]).await.is_err() {
+///                         break;
+///                     }
+///                 }
+///             }
+///         }
+///     })
+/// }
+/// ```
+pub struct Upgrade {
+    protocols: String,
+    payload: dev::Payload,
+}
+
+impl Upgrade {
+    /// Returns the protocols offered by the client, in order of preference.
+    pub fn protocols(&self) -> impl Iterator<Item = &str> {
+        self.protocols
+            .split(',')
+            .map(str::trim)
+            .filter(|proto| !proto.is_empty())
+    }
+
+    /// Switches the connection to `protocol` and runs `handler` with the upgraded connection.
+    ///
+    /// The returned `101 Switching Protocols` response must be returned from the request handler.
+    /// Once its head is written, the HTTP/1 dispatcher hands the connection to `handler` and the
+    /// connection is closed when the handler finishes. The handler does not run if the response
+    /// is replaced before it is sent or if the request was made over HTTP/2.
+    pub fn respond<F, Fut>(self, protocol: &str, handler: F) -> HttpResponse
+    where
+        F: FnOnce(Upgraded) -> Fut + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        let payload = self.payload;
+
+        let mut res = HttpResponse::SwitchingProtocols()
+            .upgrade(protocol)
+            .finish();
+        res.extensions_mut().insert(OnUpgrade::new(move |io| {
+            // the dispatcher declines the upgrade if the request payload is dropped before the
+            // response is sent
+            drop(payload);
+            handler(io)
+        }));
+
+        res
+    }
+}
+
+impl fmt::Debug for Upgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Upgrade")
+            .field("protocols", &self.protocols)
+            .finish()
+    }
+}
+
+impl FromRequest for Upgrade {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+        let protocols = req
+            .headers()
+            .get(header::UPGRADE)
+            .and_then(|val| val.to_str().ok())
+            .filter(|_| req.head().upgrade());
+
+        ready(match protocols {
+            Some(protocols) => Ok(Upgrade {
+                protocols: protocols.to_owned(),
+                payload: payload.take(),
+            }),
+            None => Err(ErrorBadRequest("expected a protocol upgrade request")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestRequest;
+
+    #[actix_rt::test]
+    async fn rejects_non_upgrade_requests() {
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::UPGRADE, "echo"))
+            .to_http_parts();
+        assert!(Upgrade::from_request(&req, &mut pl).await.is_err());
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONNECTION, "keep-alive, Upgrade"))
+            .insert_header((header::UPGRADE, "echo, irc/1.0"))
+            .to_http_parts();
+        let upgrade = Upgrade::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(upgrade.protocols().collect::<Vec<_>>(), ["echo", "irc/1.0"]);
+    }
+}
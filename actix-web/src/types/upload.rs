@@ -0,0 +1,441 @@
+//! For resumable upload helper documentation, see [`UploadRange`].
+
+use actix_utils::future::{ready, Ready};
+
+use crate::{
+    body::BoxBody,
+    dev::Payload,
+    error::UploadRangeError,
+    extract::FromRequest,
+    http::{
+        header::{self, ContentRange, ContentRangeSpec, Header as _, HeaderValue},
+        Method, StatusCode,
+    },
+    HttpRequest, HttpResponse, Responder,
+};
+
+/// tus header carrying the offset of a chunk, or the number of bytes received.
+const UPLOAD_OFFSET: &str = "upload-offset";
+
+/// tus header carrying the total length of an upload.
+const UPLOAD_LENGTH: &str = "upload-length";
+
+/// How the start of an upload chunk must relate to the number of bytes already received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UploadOffset {
+    /// Chunks must start exactly where the received bytes end.
+    ///
+    /// This is what tus clients expect.
+    Exact,
+
+    /// Chunks may also start within the received bytes, such as when a client re-sends a chunk
+    /// whose response it never got. The part that was already received should be discarded.
+    AllowOverlap,
+}
+
+/// Position of a chunk within a resumable upload.
+///
+/// Extracted from `PUT` and `PATCH` requests, from either of:
+/// - a `Content-Range` header, such as `bytes 1000-1999/5000`, where the total may be unknown
+///   (`bytes 1000-1999/*`), or the range omitted by a client asking how much was received
+///   (`bytes */5000`);
+/// - the `Upload-Offset` and, optionally, `Upload-Length` headers of the [tus] protocol.
+///
+/// Requests with neither start a new upload at offset 0. Other methods and malformed headers are
+/// rejected with an [`UploadRangeError`].
+///
+/// Handlers [validate](Self::validate) the range against the number of bytes stored so far,
+/// [check](Self::check_chunk) it against the body they received, append the chunk and respond
+/// with the new [`UploadProgress`].
+///
+/// # Examples
+/// ```
+/// use actix_web::{put, web, Error};
+///
+/// # fn received() -> u64 { 0 }
+/// # async fn store(_skip: u64, _chunk: web::Bytes) -> u64 { 0 }
+/// #[put("/uploads/{id}")]
+/// async fn upload(range: web::UploadRange, chunk: web::Bytes) -> Result<web::UploadProgress, Error> {
+///     let skip = range.validate(received(), web::UploadOffset::AllowOverlap)?;
+///     range.check_chunk(chunk.len() as u64)?;
+///     let received = store(skip, chunk).await;
+///
+///     let mut progress = web::UploadProgress::new(received);
+///     if let Some(total) = range.total() {
+///         progress = progress.total(total);
+///     }
+///     Ok(progress)
+/// }
+/// ```
+///
+/// [tus]: https://tus.io/protocols/resumable-upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadRange {
+    offset: Option<u64>,
+    len: Option<u64>,
+    total: Option<u64>,
+}
+
+impl UploadRange {
+    /// Returns the offset of the first byte of the chunk.
+    ///
+    /// Returns `None` if the request only asks for the upload's progress.
+    pub fn offset(&self) -> Option<u64> {
+        self.offset
+    }
+
+    /// Returns the length of the chunk, if specified by a `Content-Range` header.
+    pub fn chunk_len(&self) -> Option<u64> {
+        self.len
+    }
+
+    /// Returns true if the request only asks for the upload's progress without sending bytes.
+    pub fn is_progress_query(&self) -> bool {
+        self.offset.is_none()
+    }
+
+    /// Returns the total length of the upload, if known.
+    pub fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    /// Checks that the chunk can be appended to an upload of which `received` bytes are stored.
+    ///
+    /// Returns the number of bytes at the start of the chunk that were already received and must
+    /// be discarded, which is only non-zero with [`UploadOffset::AllowOverlap`].
+    ///
+    /// # Errors
+    /// Fails with [`UploadRangeError::OffsetMismatch`] if the chunk would leave a gap, or overlaps
+    /// received bytes under [`UploadOffset::Exact`], and with [`UploadRangeError::Length`] if the
+    /// upload is already longer than its total length.
+    pub fn validate(
+        &self,
+        received: u64,
+        policy: UploadOffset,
+    ) -> Result<u64, UploadRangeError> {
+        if matches!(self.total, Some(total) if received > total) {
+            return Err(UploadRangeError::Length);
+        }
+
+        let offset = match self.offset {
+            Some(offset) => offset,
+            None => return Ok(0),
+        };
+
+        let mismatch = UploadRangeError::OffsetMismatch {
+            expected: received,
+            offset,
+        };
+
+        if offset > received || (offset < received && policy == UploadOffset::Exact) {
+            return Err(mismatch);
+        }
+
+        let skip = received - offset;
+
+        Ok(match self.len {
+            Some(len) => skip.min(len),
+            None => skip,
+        })
+    }
+
+    /// Checks that a received body of `len` bytes is the chunk described by the range.
+    ///
+    /// # Errors
+    /// Fails with [`UploadRangeError::Invalid`] if `len` differs from the chunk length of a
+    /// `Content-Range` header, and with [`UploadRangeError::Length`] if the chunk would extend
+    /// beyond the total length of the upload.
+    pub fn check_chunk(&self, len: u64) -> Result<(), UploadRangeError> {
+        if matches!(self.len, Some(declared) if declared != len) {
+            return Err(UploadRangeError::Invalid);
+        }
+
+        let end = match self.offset {
+            Some(offset) => offset.checked_add(len).ok_or(UploadRangeError::Invalid)?,
+            None => return Ok(()),
+        };
+
+        match self.total {
+            Some(total) if end > total => Err(UploadRangeError::Length),
+            _ => Ok(()),
+        }
+    }
+
+    fn from_headers(req: &HttpRequest) -> Result<Self, UploadRangeError> {
+        if req.method() != Method::PUT && req.method() != Method::PATCH {
+            return Err(UploadRangeError::Method);
+        }
+
+        if req.headers().contains_key(header::CONTENT_RANGE) {
+            let spec = ContentRange::parse(req)
+                .map_err(|_| UploadRangeError::Invalid)?
+                .0;
+
+            return match spec {
+                ContentRangeSpec::Bytes {
+                    range,
+                    instance_length,
+                } => {
+                    if matches!(
+                        (range, instance_length),
+                        (Some((_, last)), Some(total)) if last >= total
+                    ) {
+                        return Err(UploadRangeError::Length);
+                    }
+
+                    let len = match range {
+                        Some((first, last)) => Some(
+                            last.checked_sub(first)
+                                .and_then(|len| len.checked_add(1))
+                                .ok_or(UploadRangeError::Invalid)?,
+                        ),
+                        None => None,
+                    };
+
+                    // the body is the chunk, so a declared body length must match the range
+                    if let (Some(len), Some(content_len)) =
+                        (len, parse_u64(req, header::CONTENT_LENGTH.as_str())?)
+                    {
+                        if len != content_len {
+                            return Err(UploadRangeError::Invalid);
+                        }
+                    }
+
+                    Ok(UploadRange {
+                        offset: range.map(|(first, _)| first),
+                        len,
+                        total: instance_length,
+                    })
+                }
+
+                ContentRangeSpec::Unregistered { .. } => Err(UploadRangeError::Invalid),
+            };
+        }
+
+        Ok(UploadRange {
+            offset: Some(parse_u64(req, UPLOAD_OFFSET)?.unwrap_or(0)),
+            len: None,
+            total: parse_u64(req, UPLOAD_LENGTH)?,
+        })
+    }
+}
+
+impl FromRequest for UploadRange {
+    type Error = UploadRangeError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(UploadRange::from_headers(req))
+    }
+}
+
+/// Progress of a resumable upload, responding with `308 Permanent Redirect` while incomplete.
+///
+/// The number of received bytes is sent in a `Range` header, such as `bytes=0-999` after 1000
+/// bytes (omitted if nothing was received), and in a tus `Upload-Offset` header. Once the total
+/// length is reached, the response is `200 OK` instead; handlers that create a resource may
+/// prefer to respond differently at that point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadProgress {
+    received: u64,
+    total: Option<u64>,
+}
+
+impl UploadProgress {
+    /// Constructs the progress of an upload of which `received` bytes are stored.
+    pub fn new(received: u64) -> Self {
+        UploadProgress {
+            received,
+            total: None,
+        }
+    }
+
+    /// Sets the total length of the upload, sent in an `Upload-Length` header.
+    pub fn total(mut self, total: u64) -> Self {
+        self.total = Some(total);
+        self
+    }
+
+    /// Returns true if all bytes of the upload were received.
+    pub fn is_complete(&self) -> bool {
+        matches!(self.total, Some(total) if self.received >= total)
+    }
+}
+
+impl Responder for UploadProgress {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse<Self::Body> {
+        let mut res = if self.is_complete() {
+            HttpResponse::Ok()
+        } else {
+            HttpResponse::build(StatusCode::PERMANENT_REDIRECT)
+        };
+
+        if self.received > 0 {
+            res.insert_header((header::RANGE, format!("bytes=0-{}", self.received - 1)));
+        }
+
+        res.insert_header((UPLOAD_OFFSET, HeaderValue::from(self.received)));
+
+        if let Some(total) = self.total {
+            res.insert_header((UPLOAD_LENGTH, HeaderValue::from(total)));
+        }
+
+        res.finish()
+    }
+}
+
+fn parse_u64(req: &HttpRequest, name: &str) -> Result<Option<u64>, UploadRangeError> {
+    match req.headers().get(name) {
+        Some(value) => value
+            .to_str()
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .map(Some)
+            .ok_or(UploadRangeError::Invalid),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestRequest;
+
+    fn parse(req: TestRequest) -> Result<UploadRange, UploadRangeError> {
+        UploadRange::from_headers(&req.to_http_request())
+    }
+
+    #[test]
+    fn parse_content_range() {
+        let req =
+            TestRequest::put().insert_header((header::CONTENT_RANGE, "bytes 100-199/500"));
+        let range = parse(req).unwrap();
+        assert_eq!(range.offset(), Some(100));
+        assert_eq!(range.chunk_len(), Some(100));
+        assert_eq!(range.total(), Some(500));
+
+        let req = TestRequest::put().insert_header((header::CONTENT_RANGE, "bytes */500"));
+        let range = parse(req).unwrap();
+        assert!(range.is_progress_query());
+        assert_eq!(range.total(), Some(500));
+
+        let req =
+            TestRequest::put().insert_header((header::CONTENT_RANGE, "bytes 400-599/500"));
+        assert!(matches!(parse(req), Err(UploadRangeError::Length)));
+
+        let req = TestRequest::put().insert_header((header::CONTENT_RANGE, "lines 1-2/3"));
+        assert!(matches!(parse(req), Err(UploadRangeError::Invalid)));
+
+        let req = TestRequest::put()
+            .insert_header((header::CONTENT_RANGE, format!("bytes 0-{}/*", u64::MAX)));
+        assert!(matches!(parse(req), Err(UploadRangeError::Invalid)));
+
+        let req = TestRequest::put()
+            .insert_header((header::CONTENT_RANGE, "bytes 100-199/500"))
+            .insert_header((header::CONTENT_LENGTH, "50"));
+        assert!(matches!(parse(req), Err(UploadRangeError::Invalid)));
+
+        let req = TestRequest::get().insert_header((header::CONTENT_RANGE, "bytes 0-1/2"));
+        assert!(matches!(parse(req), Err(UploadRangeError::Method)));
+    }
+
+    #[test]
+    fn parse_tus_headers() {
+        let req = TestRequest::default()
+            .method(Method::PATCH)
+            .insert_header(("upload-offset", "300"))
+            .insert_header(("upload-length", "500"));
+        let range = parse(req).unwrap();
+        assert_eq!(range.offset(), Some(300));
+        assert_eq!(range.chunk_len(), None);
+        assert_eq!(range.total(), Some(500));
+
+        let req = TestRequest::put();
+        assert_eq!(parse(req).unwrap().offset(), Some(0));
+
+        let req = TestRequest::put().insert_header(("upload-offset", "-1"));
+        assert!(matches!(parse(req), Err(UploadRangeError::Invalid)));
+    }
+
+    #[test]
+    fn validate_offset() {
+        let req =
+            TestRequest::put().insert_header((header::CONTENT_RANGE, "bytes 100-199/500"));
+        let range = parse(req).unwrap();
+
+        assert_eq!(range.validate(100, UploadOffset::Exact).unwrap(), 0);
+        assert_eq!(range.validate(150, UploadOffset::AllowOverlap).unwrap(), 50);
+        assert_eq!(
+            range.validate(300, UploadOffset::AllowOverlap).unwrap(),
+            100
+        );
+
+        assert!(matches!(
+            range.validate(150, UploadOffset::Exact),
+            Err(UploadRangeError::OffsetMismatch {
+                expected: 150,
+                offset: 100
+            })
+        ));
+        assert!(matches!(
+            range.validate(50, UploadOffset::AllowOverlap),
+            Err(UploadRangeError::OffsetMismatch { .. })
+        ));
+        assert!(matches!(
+            range.validate(600, UploadOffset::AllowOverlap),
+            Err(UploadRangeError::Length)
+        ));
+    }
+
+    #[test]
+    fn check_chunk_len() {
+        let req =
+            TestRequest::put().insert_header((header::CONTENT_RANGE, "bytes 100-199/500"));
+        let range = parse(req).unwrap();
+        assert!(range.check_chunk(100).is_ok());
+        assert!(matches!(
+            range.check_chunk(99),
+            Err(UploadRangeError::Invalid)
+        ));
+
+        let req = TestRequest::put()
+            .insert_header(("upload-offset", "300"))
+            .insert_header(("upload-length", "500"));
+        let range = parse(req).unwrap();
+        assert!(range.check_chunk(200).is_ok());
+        assert!(matches!(
+            range.check_chunk(201),
+            Err(UploadRangeError::Length)
+        ));
+
+        let req = TestRequest::put().insert_header(("upload-offset", u64::MAX.to_string()));
+        let range = parse(req).unwrap();
+        assert!(matches!(
+            range.check_chunk(1),
+            Err(UploadRangeError::Invalid)
+        ));
+    }
+
+    #[test]
+    fn progress_response() {
+        let req = TestRequest::default().to_http_request();
+
+        let res = UploadProgress::new(0).respond_to(&req);
+        assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+        assert!(res.headers().get(header::RANGE).is_none());
+        assert_eq!(res.headers().get("upload-offset").unwrap(), "0");
+
+        let res = UploadProgress::new(1000).total(5000).respond_to(&req);
+        assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(res.headers().get(header::RANGE).unwrap(), "bytes=0-999");
+        assert_eq!(res.headers().get("upload-offset").unwrap(), "1000");
+        assert_eq!(res.headers().get("upload-length").unwrap(), "5000");
+
+        let res = UploadProgress::new(5000).total(5000).respond_to(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,346 @@
+use std::convert::TryFrom as _;
+
+use actix_http::{
+    body::{BoxBody, MessageBody},
+    HttpMessage as _, Request, Response,
+};
+use actix_service::{
+    boxed::{self, BoxService, BoxServiceFactory},
+    IntoServiceFactory, Service, ServiceFactory, ServiceFactoryExt as _,
+};
+use actix_utils::future::ready;
+use futures_core::future::LocalBoxFuture;
+use futures_util::future::join_all;
+
+use crate::{
+    config::AppConfig,
+    http::{header, uri::Authority, StatusCode},
+    service::ServiceResponse,
+    Error,
+};
+
+type BoxedAppFactory = BoxServiceFactory<AppConfig, Request, Response<BoxBody>, Error, ()>;
+type BoxedApp = BoxService<Request, Response<BoxBody>, Error>;
+
+/// Routes requests to one of several applications based on their `Host` header.
+///
+/// Each application is paired with a host pattern. A pattern is either a host name, such as
+/// `www.example.com`, or a wildcard, such as `*.example.com`, that matches all subdomains of
+/// `example.com` but not `example.com` itself. Patterns are compared case-insensitively, ignoring
+/// the port, and checked in the order they were registered. Requests matching no pattern are
+/// handled by the [default application](Self::default_app), or answered with `404 Not Found` if
+/// there is none.
+///
+/// The host is selected before any path matching, so each application has its own routes, data
+/// and middleware stack. Like an [`App`](crate::App), `VirtualHosts` is constructed by the
+/// [`HttpServer`](crate::HttpServer) factory closure, once per worker.
+///
+/// # Examples
+/// ```no_run
+/// use actix_web::{middleware, web, App, HttpResponse, HttpServer, VirtualHosts};
+///
+/// #[actix_web::main]
+/// async fn main() -> std::io::Result<()> {
+///     HttpServer::new(|| {
+///         VirtualHosts::new()
+///             .host(
+///                 "api.example.com",
+///                 App::new()
+///                     .wrap(middleware::Logger::default())
+///                     .route("/users", web::get().to(HttpResponse::Ok)),
+///             )
+///             .host(
+///                 "*.example.com",
+///                 App::new().route("/", web::get().to(HttpResponse::Ok)),
+///             )
+///             .default_app(App::new().default_service(web::to(HttpResponse::NotFound)))
+///     })
+///     .bind(("127.0.0.1", 8080))?
+///     .run()
+///     .await
+/// }
+/// ```
+pub struct VirtualHosts {
+    hosts: Vec<(HostPattern, BoxedAppFactory)>,
+    default: Option<BoxedAppFactory>,
+}
+
+impl VirtualHosts {
+    /// Constructs a router without any hosts.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        VirtualHosts {
+            hosts: Vec::new(),
+            default: None,
+        }
+    }
+
+    /// Serves requests for hosts matching `pattern` with `app`.
+    ///
+    /// # Panics
+    /// Panics if `pattern` is empty.
+    pub fn host<F, U, B>(mut self, pattern: &str, app: F) -> Self
+    where
+        F: IntoServiceFactory<U, Request>,
+        U: ServiceFactory<
+                Request,
+                Config = AppConfig,
+                Response = ServiceResponse<B>,
+                Error = Error,
+                InitError = (),
+            > + 'static,
+        U::Service: 'static,
+        U::Future: 'static,
+        <U::Service as Service<Request>>::Future: 'static,
+        B: MessageBody + 'static,
+    {
+        self.hosts.push((HostPattern::new(pattern), boxed_app(app)));
+        self
+    }
+
+    /// Serves requests not matching any host pattern with `app`.
+    pub fn default_app<F, U, B>(mut self, app: F) -> Self
+    where
+        F: IntoServiceFactory<U, Request>,
+        U: ServiceFactory<
+                Request,
+                Config = AppConfig,
+                Response = ServiceResponse<B>,
+                Error = Error,
+                InitError = (),
+            > + 'static,
+        U::Service: 'static,
+        U::Future: 'static,
+        <U::Service as Service<Request>>::Future: 'static,
+        B: MessageBody + 'static,
+    {
+        self.default = Some(boxed_app(app));
+        self
+    }
+}
+
+fn boxed_app<F, U, B>(app: F) -> BoxedAppFactory
+where
+    F: IntoServiceFactory<U, Request>,
+    U: ServiceFactory<
+            Request,
+            Config = AppConfig,
+            Response = ServiceResponse<B>,
+            Error = Error,
+            InitError = (),
+        > + 'static,
+    U::Service: 'static,
+    U::Future: 'static,
+    <U::Service as Service<Request>>::Future: 'static,
+    B: MessageBody + 'static,
+{
+    boxed::factory(
+        app.into_factory()
+            .map(|res: ServiceResponse<B>| res.map_into_boxed_body().into()),
+    )
+}
+
+impl ServiceFactory<Request> for VirtualHosts {
+    type Response = Response<BoxBody>;
+    type Error = Error;
+    type Config = AppConfig;
+    type Service = VirtualHostsService;
+    type InitError = ();
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, config: AppConfig) -> Self::Future {
+        let patterns = self
+            .hosts
+            .iter()
+            .map(|(pattern, _)| pattern.clone())
+            .collect::<Vec<_>>();
+
+        let hosts_fut = join_all(
+            self.hosts
+                .iter()
+                .map(|(_, factory)| factory.new_service(config.clone())),
+        );
+
+        let default_fut = self
+            .default
+            .as_ref()
+            .map(|factory| factory.new_service(config));
+
+        Box::pin(async move {
+            let services = hosts_fut.await.into_iter().collect::<Result<Vec<_>, _>>()?;
+
+            let default = match default_fut {
+                Some(fut) => Some(fut.await?),
+                None => None,
+            };
+
+            Ok(VirtualHostsService {
+                hosts: patterns.into_iter().zip(services).collect(),
+                default,
+            })
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct VirtualHostsService {
+    hosts: Vec<(HostPattern, BoxedApp)>,
+    default: Option<BoxedApp>,
+}
+
+impl VirtualHostsService {
+    fn select(&self, req: &Request) -> Option<&BoxedApp> {
+        let host = match request_host(req) {
+            Some(host) => host,
+            None => return self.default.as_ref(),
+        };
+
+        self.hosts
+            .iter()
+            .find(|(pattern, _)| pattern.matches(&host))
+            .map(|(_, app)| app)
+            .or(self.default.as_ref())
+    }
+}
+
+impl Service<Request> for VirtualHostsService {
+    type Response = Response<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::always_ready!();
+
+    fn call(&self, req: Request) -> Self::Future {
+        match self.select(&req) {
+            Some(app) => app.call(req),
+            None => Box::pin(ready(Ok(Response::new(StatusCode::NOT_FOUND)))),
+        }
+    }
+}
+
+/// Returns the host of a request, without port, from its `Host` header or, for HTTP/2 requests,
+/// its URI.
+fn request_host(req: &Request) -> Option<String> {
+    let authority = match req.headers().get(header::HOST) {
+        Some(host) => Authority::try_from(host.as_bytes()).ok()?,
+        None => req.uri().authority()?.clone(),
+    };
+
+    let host = authority.host();
+
+    // tolerate fully qualified host names
+    Some(host.strip_suffix('.').unwrap_or(host).to_owned())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum HostPattern {
+    /// Matches a single host.
+    Exact(String),
+
+    /// Matches subdomains of a host; stored with the leading dot, e.g. `.example.com`.
+    Subdomains(String),
+}
+
+impl HostPattern {
+    fn new(pattern: &str) -> Self {
+        assert!(!pattern.is_empty(), "host pattern must not be empty");
+
+        let pattern = pattern.to_ascii_lowercase();
+
+        match pattern.strip_prefix('*') {
+            Some(suffix) if suffix.starts_with('.') => {
+                HostPattern::Subdomains(suffix.to_owned())
+            }
+            _ => HostPattern::Exact(pattern),
+        }
+    }
+
+    fn matches(&self, host: &str) -> bool {
+        match self {
+            HostPattern::Exact(pattern) => pattern.eq_ignore_ascii_case(host),
+            HostPattern::Subdomains(suffix) => {
+                host.len() > suffix.len()
+                    && host.is_char_boundary(host.len() - suffix.len())
+                    && host[host.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::{app_service::AppEntry, body, test::TestRequest, web, App, HttpResponse};
+
+    fn app(name: &'static str) -> App<AppEntry> {
+        App::new().route("/", web::get().to(move || HttpResponse::Ok().body(name)))
+    }
+
+    async fn call(srv: &VirtualHostsService, host: &str) -> (StatusCode, Bytes) {
+        let req = TestRequest::get()
+            .insert_header((header::HOST, host))
+            .to_request();
+        let res = srv.call(req).await.unwrap();
+        let status = res.status();
+        (status, body::to_bytes(res.into_body()).await.unwrap())
+    }
+
+    #[test]
+    fn host_patterns() {
+        let exact = HostPattern::new("Example.com");
+        assert!(exact.matches("example.com"));
+        assert!(exact.matches("EXAMPLE.COM"));
+        assert!(!exact.matches("www.example.com"));
+
+        let wildcard = HostPattern::new("*.example.com");
+        assert!(wildcard.matches("www.example.com"));
+        assert!(wildcard.matches("a.b.example.com"));
+        assert!(!wildcard.matches("example.com"));
+        assert!(!wildcard.matches("badexample.com"));
+    }
+
+    #[actix_rt::test]
+    async fn routes_by_host() {
+        let srv = VirtualHosts::new()
+            .host("api.example.com", app("api"))
+            .host("*.example.com", app("www"))
+            .new_service(AppConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            call(&srv, "API.example.com:8080").await,
+            (StatusCode::OK, Bytes::from_static(b"api"))
+        );
+        assert_eq!(
+            call(&srv, "www.example.com.").await,
+            (StatusCode::OK, Bytes::from_static(b"www"))
+        );
+        assert_eq!(call(&srv, "example.com").await.0, StatusCode::NOT_FOUND);
+
+        let req = TestRequest::get().to_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn default_app() {
+        let srv = VirtualHosts::new()
+            .host("api.example.com", app("api"))
+            .default_app(app("default"))
+            .new_service(AppConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            call(&srv, "example.com").await,
+            (StatusCode::OK, Bytes::from_static(b"default"))
+        );
+
+        let req = TestRequest::get().to_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,199 @@
+#![cfg(any(feature = "openssl", feature = "rustls"))]
+
+extern crate tls_openssl as openssl;
+#[cfg(feature = "rustls")]
+extern crate tls_rustls as rustls;
+
+use std::net::TcpListener;
+
+#[cfg(feature = "rustls")]
+use actix_web::tls::CertResolver;
+use actix_web::{tls::ClientCert, web, App, HttpServer};
+use openssl::{
+    pkey::PKey,
+    ssl::{SslAcceptor, SslConnector, SslMethod, SslVerifyMode},
+    x509::X509,
+};
+use rcgen::{BasicConstraints, Certificate as RcgenCert, CertificateParams, DnType, IsCa};
+#[cfg(feature = "rustls")]
+use rustls::{Certificate, PrivateKey, RootCertStore};
+
+struct Pki {
+    ca: RcgenCert,
+    client: RcgenCert,
+}
+
+fn pki() -> Pki {
+    let mut params = CertificateParams::new(vec![]);
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    // OpenSSL takes certificates with the same subject and issuer for self-signed ones
+    params
+        .distinguished_name
+        .push(DnType::CommonName, "actix test CA");
+    let ca = RcgenCert::from_params(params).unwrap();
+
+    let client = rcgen::generate_simple_self_signed(vec!["client.local".to_owned()]).unwrap();
+
+    Pki { ca, client }
+}
+
+#[cfg(feature = "rustls")]
+fn server_resolver() -> CertResolver {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+    let chain = vec![Certificate(cert.serialize_der().unwrap())];
+    let key = PrivateKey(cert.serialize_private_key_der());
+    CertResolver::new(chain, key).unwrap()
+}
+
+fn client(pki: Option<&Pki>) -> awc::Client {
+    let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+    builder.set_verify(SslVerifyMode::NONE);
+
+    if let Some(pki) = pki {
+        let cert_pem = pki.client.serialize_pem_with_signer(&pki.ca).unwrap();
+        let key_pem = pki.client.serialize_private_key_pem();
+
+        builder
+            .set_certificate(&X509::from_pem(cert_pem.as_bytes()).unwrap())
+            .unwrap();
+        builder
+            .set_private_key(&PKey::private_key_from_pem(key_pem.as_bytes()).unwrap())
+            .unwrap();
+    }
+
+    awc::Client::builder()
+        .connector(awc::Connector::new().openssl(builder.build()))
+        .finish()
+}
+
+async fn cert_len(cert: Option<ClientCert>) -> String {
+    match cert {
+        Some(cert) => format!("{}", cert.chain().len()),
+        None => "none".to_owned(),
+    }
+}
+
+#[cfg(feature = "rustls")]
+#[actix_rt::test]
+async fn client_cert_is_exposed_to_handlers() {
+    let pki = pki();
+
+    let mut roots = RootCertStore::empty();
+    roots
+        .add(&Certificate(pki.ca.serialize_der().unwrap()))
+        .unwrap();
+
+    let config = server_resolver().server_config_request_client_cert(roots);
+
+    let lst = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = lst.local_addr().unwrap();
+
+    let srv = HttpServer::new(|| App::new().route("/", web::get().to(cert_len)))
+        .workers(1)
+        .disable_signals()
+        .listen_rustls(lst, config)
+        .unwrap()
+        .run();
+
+    let handle = srv.handle();
+    actix_rt::spawn(srv);
+
+    let url = format!("https://localhost:{}/", addr.port());
+
+    let mut res = client(Some(&pki)).get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.body().await.unwrap(), "1");
+
+    let mut res = client(None).get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.body().await.unwrap(), "none");
+
+    handle.stop(true).await;
+}
+
+#[cfg(feature = "rustls")]
+#[actix_rt::test]
+async fn required_client_cert() {
+    let pki = pki();
+
+    let mut roots = RootCertStore::empty();
+    roots
+        .add(&Certificate(pki.ca.serialize_der().unwrap()))
+        .unwrap();
+
+    let config = server_resolver().server_config_require_client_cert(roots);
+
+    let lst = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = lst.local_addr().unwrap();
+
+    let srv = HttpServer::new(|| {
+        App::new().route(
+            "/",
+            web::get().to(|cert: ClientCert| async move { format!("{}", cert.leaf().len()) }),
+        )
+    })
+    .workers(1)
+    .disable_signals()
+    .listen_rustls(lst, config)
+    .unwrap()
+    .run();
+
+    let handle = srv.handle();
+    actix_rt::spawn(srv);
+
+    let url = format!("https://localhost:{}/", addr.port());
+
+    let res = client(Some(&pki)).get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+
+    assert!(client(None).get(&url).send().await.is_err());
+
+    handle.stop(true).await;
+}
+
+#[cfg(feature = "openssl")]
+#[actix_rt::test]
+async fn openssl_client_cert() {
+    let pki = pki();
+
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+    let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+    builder
+        .set_certificate(&X509::from_der(&cert.serialize_der().unwrap()).unwrap())
+        .unwrap();
+    builder
+        .set_private_key(
+            &PKey::private_key_from_der(&cert.serialize_private_key_der()).unwrap(),
+        )
+        .unwrap();
+    builder
+        .cert_store_mut()
+        .add_cert(X509::from_der(&pki.ca.serialize_der().unwrap()).unwrap())
+        .unwrap();
+    builder.set_verify(SslVerifyMode::PEER);
+
+    let lst = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = lst.local_addr().unwrap();
+
+    let srv = HttpServer::new(|| App::new().route("/", web::get().to(cert_len)))
+        .workers(1)
+        .disable_signals()
+        .listen_openssl(lst, builder)
+        .unwrap()
+        .run();
+
+    let handle = srv.handle();
+    actix_rt::spawn(srv);
+
+    let url = format!("https://localhost:{}/", addr.port());
+
+    let mut res = client(Some(&pki)).get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.body().await.unwrap(), "1");
+
+    let mut res = client(None).get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.body().await.unwrap(), "none");
+
+    handle.stop(true).await;
+}
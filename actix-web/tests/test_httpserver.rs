@@ -133,3 +133,115 @@ async fn test_start_ssl() {
 
     srv.stop(false).await;
 }
+
+#[actix_rt::test]
+async fn test_proxy_protocol() {
+    use std::io::{Read as _, Write as _};
+
+    use actix_web::{dev::ProxyHeader, HttpRequest};
+
+    let lst = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = lst.local_addr().unwrap();
+
+    let srv = actix_web::HttpServer::new(|| {
+        actix_web::App::new().default_service(actix_web::web::to(|req: HttpRequest| {
+            let header = req.conn_data::<ProxyHeader>().cloned();
+            async move {
+                format!(
+                    "{:?} {:?}",
+                    req.peer_addr(),
+                    header.and_then(|header| header.destination())
+                )
+            }
+        }))
+    })
+    .workers(1)
+    .disable_signals()
+    .listen_proxy_protocol(lst)
+    .unwrap()
+    .run();
+
+    let handle = srv.handle();
+    actix_rt::spawn(srv);
+
+    // raw client connections are blocking so must not run on the server's runtime
+    actix_rt::task::spawn_blocking(move || {
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(
+                b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 443\r\n\
+                GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            )
+            .unwrap();
+
+        let mut res = String::new();
+        stream.read_to_string(&mut res).unwrap();
+        assert!(res.starts_with("HTTP/1.1 200 OK"));
+        assert!(res.ends_with("Some(192.0.2.1:56324) Some(198.51.100.1:443)"));
+
+        // connections without a header are rejected
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+
+        let mut res = String::new();
+        let _ = stream.read_to_string(&mut res);
+        assert!(res.is_empty());
+    })
+    .await
+    .unwrap();
+
+    handle.stop(true).await;
+}
+
+#[actix_rt::test]
+async fn test_server_name() {
+    use actix_web::http::header;
+
+    let start = |suppress: bool| {
+        let lst = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = lst.local_addr().unwrap();
+
+        let srv = actix_web::HttpServer::new(|| {
+            actix_web::App::new()
+                .route("/", actix_web::web::get().to(actix_web::HttpResponse::Ok))
+                .route(
+                    "/custom",
+                    actix_web::web::get().to(|| async {
+                        actix_web::HttpResponse::Ok()
+                            .insert_header((header::SERVER, "custom"))
+                            .finish()
+                    }),
+                )
+        })
+        .workers(1)
+        .disable_signals()
+        .server_name("my-app/1.0");
+
+        let srv = if suppress {
+            srv.suppress_server_header()
+        } else {
+            srv
+        };
+
+        let srv = srv.listen(lst).unwrap().run();
+        let handle = srv.handle();
+        actix_rt::spawn(srv);
+        (format!("http://{}", addr), handle)
+    };
+
+    let client = awc::Client::new();
+
+    let (url, handle) = start(false);
+    let res = client.get(url.clone()).send().await.unwrap();
+    assert_eq!(res.headers().get(header::SERVER).unwrap(), "my-app/1.0");
+    let res = client.get(format!("{}/custom", url)).send().await.unwrap();
+    assert_eq!(res.headers().get(header::SERVER).unwrap(), "custom");
+    handle.stop(true).await;
+
+    let (url, handle) = start(true);
+    let res = client.get(format!("{}/custom", url)).send().await.unwrap();
+    assert!(res.headers().get(header::SERVER).is_none());
+    handle.stop(true).await;
+}
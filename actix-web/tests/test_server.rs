@@ -13,9 +13,9 @@ use std::{
 
 use actix_web::{
     cookie::Cookie,
-    http::{header, StatusCode},
+    http::{header, StatusCode, Version},
     middleware::{Compress, NormalizePath, TrailingSlash},
-    web, App, Error, HttpResponse,
+    web, App, Error, HttpRequest, HttpResponse,
 };
 use bytes::Bytes;
 use futures_core::ready;
@@ -855,6 +855,122 @@ async fn test_slow_request() {
     srv.stop().await;
 }
 
+#[actix_rt::test]
+async fn test_protocol_upgrade() {
+    use std::net;
+
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    let srv = actix_test::start(|| {
+        App::new().route(
+            "/",
+            web::get().to(|upgrade: web::Upgrade| async move {
+                assert_eq!(upgrade.protocols().collect::<Vec<_>>(), ["shout"]);
+
+                upgrade.respond("shout", |mut io| async move {
+                    let mut buf = Vec::new();
+                    io.read_to_end(&mut buf).await.unwrap();
+                    io.write_all(&buf.to_ascii_uppercase()).await.unwrap();
+                })
+            }),
+        )
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream
+        .write_all(b"GET / HTTP/1.1\r\nconnection: upgrade\r\nupgrade: shout\r\n\r\nhello");
+    let _ = stream.shutdown(net::Shutdown::Write);
+
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(
+        data.starts_with("HTTP/1.1 101 Switching Protocols\r\n"),
+        "{}",
+        data
+    );
+    assert!(data.contains("upgrade: shout\r\n"), "{}", data);
+    assert!(data.ends_with("\r\n\r\nHELLO"), "{}", data);
+
+    srv.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_send_informational() {
+    use std::net;
+
+    let srv = actix_test::start(|| {
+        App::new().route(
+            "/",
+            web::get().to(|req: HttpRequest| async move {
+                let early_hints = StatusCode::from_u16(103).unwrap();
+
+                // sent while the handler is pending
+                actix_rt::time::sleep(Duration::from_millis(10)).await;
+                let sent = req.send_informational(
+                    HttpResponse::build(early_hints)
+                        .insert_header((header::LINK, "</style.css>; rel=preload"))
+                        .finish(),
+                );
+                assert_eq!(sent, req.version() == Version::HTTP_11);
+                assert!(!req.send_informational(HttpResponse::Ok().finish()));
+
+                actix_rt::time::sleep(Duration::from_millis(10)).await;
+                HttpResponse::Ok().body("done")
+            }),
+        )
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\nconnection: close\r\n\r\n");
+
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(
+        data.starts_with(
+            "HTTP/1.1 103 Early Hints\r\nlink: </style.css>; rel=preload\r\n\r\nHTTP/1.1 200 OK\r\n"
+        ),
+        "{}",
+        data
+    );
+    assert!(data.ends_with("\r\n\r\ndone"), "{}", data);
+
+    // informational responses are not sent to HTTP/1.0 clients
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET / HTTP/1.0\r\n\r\n");
+
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(!data.contains("103"), "{}", data);
+    assert!(data.ends_with("\r\n\r\ndone"), "{}", data);
+
+    srv.stop().await;
+}
+
+#[actix_rt::test]
+async fn test_protocol_upgrade_declined() {
+    use std::net;
+
+    let srv = actix_test::start(|| {
+        App::new()
+            .route("/", web::get().to(HttpResponse::Ok))
+            .route("/next", web::get().to(|| async { "next" }))
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(
+        b"GET / HTTP/1.1\r\nconnection: upgrade\r\nupgrade: shout\r\n\r\n\
+        GET /next HTTP/1.1\r\nconnection: close\r\n\r\n",
+    );
+
+    // the connection is kept alive and the pipelined request is served
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 200 OK\r\n"), "{}", data);
+    assert!(data.ends_with("\r\n\r\nnext"), "{}", data);
+
+    srv.stop().await;
+}
+
 #[actix_rt::test]
 async fn test_normalize() {
     let srv = actix_test::start_with(actix_test::config().h1(), || {
@@ -944,3 +1060,68 @@ async fn test_accept_encoding_no_match() {
 
     srv.stop().await;
 }
+
+#[cfg(all(feature = "tracing", feature = "awc"))]
+#[actix_rt::test]
+async fn test_trace_propagation() {
+    use actix_web::middleware::{TracePropagation, Tracing};
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    fn echo_traceparent(req: &HttpRequest) -> HttpResponse {
+        let traceparent = req
+            .headers()
+            .get("traceparent")
+            .map(|val| val.to_str().unwrap().to_owned())
+            .unwrap_or_default();
+
+        HttpResponse::Ok().body(traceparent)
+    }
+
+    let upstream = actix_test::start(|| {
+        App::new().default_service(web::to(
+            |req: HttpRequest| async move { echo_traceparent(&req) },
+        ))
+    });
+    let upstream_url = upstream.url("/");
+
+    let srv = actix_test::start(move || {
+        let upstream_url = upstream_url.clone();
+
+        App::new()
+            .wrap(Tracing::new())
+            .default_service(web::to(move || {
+                let client = awc::Client::builder().wrap(TracePropagation).finish();
+                let req = client.get(&upstream_url);
+
+                async move {
+                    let mut res = req.send().await.unwrap();
+                    HttpResponse::Ok().body(res.body().await.unwrap())
+                }
+            }))
+    });
+
+    // the outbound request carries a child of the inbound context
+    let mut res = srv
+        .get("/")
+        .insert_header(("traceparent", TRACEPARENT))
+        .send()
+        .await
+        .unwrap();
+    let body = res.body().await.unwrap();
+    let outbound = std::str::from_utf8(&body).unwrap();
+    assert!(
+        outbound.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"),
+        "{}",
+        outbound
+    );
+    assert_ne!(outbound, TRACEPARENT);
+
+    // requests sent outside of a traced request are unchanged
+    let client = awc::Client::builder().wrap(TracePropagation).finish();
+    let mut res = client.get(upstream.url("/")).send().await.unwrap();
+    assert!(res.body().await.unwrap().is_empty());
+
+    srv.stop().await;
+    upstream.stop().await;
+}
@@ -0,0 +1,15 @@
+//! Exponential backoff shared by retrying middleware and reconnecting clients.
+
+use std::{cmp, time::Duration};
+
+/// Returns `base` doubled `exponent` times, capped at `max`, with up to half of it subtracted as
+/// random jitter.
+pub(crate) fn jittered_delay(base: Duration, max: Duration, exponent: u32) -> Duration {
+    let factor = 1u32.checked_shl(exponent).unwrap_or(u32::MAX);
+
+    let delay = base.checked_mul(factor).unwrap_or(max);
+    let delay = cmp::min(delay, max);
+
+    // subtract up to half of the delay as jitter
+    delay - delay.mul_f64(rand::random::<f64>() / 2.0)
+}
@@ -1,4 +1,6 @@
-use std::{net::IpAddr, time::Duration};
+use std::{net::IpAddr, rc::Rc, time::Duration};
+
+use super::proxy::ProxyConfig;
 
 const DEFAULT_H2_CONN_WINDOW: u32 = 1024 * 1024 * 2; // 2MB
 const DEFAULT_H2_STREAM_WINDOW: u32 = 1024 * 1024; // 1MB
@@ -12,9 +14,12 @@ pub(crate) struct ConnectorConfig {
     pub(crate) conn_keep_alive: Duration,
     pub(crate) disconnect_timeout: Option<Duration>,
     pub(crate) limit: usize,
+    pub(crate) limit_per_host: Option<usize>,
     pub(crate) conn_window_size: u32,
     pub(crate) stream_window_size: u32,
     pub(crate) local_address: Option<IpAddr>,
+    pub(crate) proxy: Option<Rc<ProxyConfig>>,
+    pub(crate) happy_eyeballs_delay: Duration,
 }
 
 impl Default for ConnectorConfig {
@@ -26,9 +31,12 @@ impl Default for ConnectorConfig {
             conn_keep_alive: Duration::from_secs(15),
             disconnect_timeout: Some(Duration::from_millis(3000)),
             limit: 100,
+            limit_per_host: None,
             conn_window_size: DEFAULT_H2_CONN_WINDOW,
             stream_window_size: DEFAULT_H2_STREAM_WINDOW,
             local_address: None,
+            proxy: None,
+            happy_eyeballs_delay: Duration::from_millis(250),
         }
     }
 }
@@ -18,6 +18,7 @@ use crate::BoxError;
 
 use super::error::SendRequestError;
 use super::pool::Acquired;
+use super::proxy::ForwardProxy;
 use super::{h1proto, h2proto};
 
 /// Trait alias for types impl [tokio::io::AsyncRead] and [tokio::io::AsyncWrite].
@@ -30,6 +31,9 @@ pub struct H1Connection<Io: ConnectionIo> {
     io: Option<Io>,
     created: time::Instant,
     acquired: Acquired<Io>,
+
+    /// HTTP proxy that this connection is made to, for forwarding requests.
+    forward_proxy: Option<ForwardProxy>,
 }
 
 impl<Io: ConnectionIo> H1Connection<Io> {
@@ -224,9 +228,19 @@ impl<Io: ConnectionIo> ConnectionType<Io> {
             io: Some(io),
             created,
             acquired,
+            forward_proxy: None,
         })
     }
 
+    /// Marks an HTTP/1 connection as being made to an HTTP proxy that forwards its requests.
+    pub(super) fn forward_proxy(mut self, forward_proxy: Option<ForwardProxy>) -> Self {
+        if let Self::H1(ref mut conn) = self {
+            conn.forward_proxy = forward_proxy;
+        }
+
+        self
+    }
+
     pub(super) fn from_h2(
         io: H2ConnectionInner,
         created: time::Instant,
@@ -259,7 +273,13 @@ where
         Box::pin(async move {
             match self {
                 Connection::Tcp(ConnectionType::H1(conn)) => {
-                    h1proto::send_request(conn, head.into(), body).await
+                    let mut head = head.into();
+
+                    if let Some(ref forward_proxy) = conn.forward_proxy {
+                        forward_proxy.prepare(&mut head);
+                    }
+
+                    h1proto::send_request(conn, head, body).await
                 }
                 Connection::Tls(ConnectionType::H1(conn)) => {
                     h1proto::send_request(conn, head.into(), body).await
@@ -284,8 +304,14 @@ where
     > {
         Box::pin(async move {
             match self {
-                Connection::Tcp(ConnectionType::H1(ref _conn)) => {
-                    let (head, framed) = h1proto::open_tunnel(self, head.into()).await?;
+                Connection::Tcp(ConnectionType::H1(ref conn)) => {
+                    let mut head = head.into();
+
+                    if let Some(ref forward_proxy) = conn.forward_proxy {
+                        forward_proxy.prepare(&mut head);
+                    }
+
+                    let (head, framed) = h1proto::open_tunnel(self, head).await?;
                     Ok((head, framed))
                 }
                 Connection::Tls(ConnectionType::H1(ref _conn)) => {
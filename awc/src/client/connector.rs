@@ -16,7 +16,7 @@ use actix_rt::{
 use actix_service::Service;
 use actix_tls::connect::{
     ConnectError as TcpConnectError, ConnectInfo, Connection as TcpConnection,
-    Connector as TcpConnector, Resolver,
+    Connector as TcpConnector, Resolve, Resolver,
 };
 use futures_core::{future::LocalBoxFuture, ready};
 use http::Uri;
@@ -26,7 +26,9 @@ use super::{
     config::ConnectorConfig,
     connection::{Connection, ConnectionIo},
     error::ConnectError,
+    happy_eyeballs,
     pool::ConnectionPool,
+    proxy::{self, ForwardProxy, Proxy, ProxyConfig},
     Connect,
 };
 
@@ -64,6 +66,9 @@ pub struct Connector<T> {
     connector: T,
     config: ConnectorConfig,
 
+    /// Resolver of the default connector, used for racing connection attempts.
+    resolver: Option<Resolver>,
+
     #[allow(dead_code)] // only dead when no TLS feature is enabled
     tls: OurTlsConnector,
 }
@@ -77,9 +82,12 @@ impl Connector<()> {
                 Error = actix_tls::connect::ConnectError,
             > + Clone,
     > {
+        let resolver = resolver::resolver();
+
         Connector {
-            connector: TcpConnector::new(resolver::resolver()).service(),
+            connector: TcpConnector::new(resolver.clone()).service(),
             config: ConnectorConfig::default(),
+            resolver: Some(resolver),
             tls: Self::build_ssl(vec![b"h2".to_vec(), b"http/1.1".to_vec()]),
         }
     }
@@ -129,6 +137,29 @@ impl Connector<()> {
 }
 
 impl<S> Connector<S> {
+    /// Use a custom DNS resolver, such as a configured [`DnsResolver`](super::DnsResolver).
+    ///
+    /// Replaces any [custom connector](Self::connector).
+    pub fn resolver(
+        self,
+        resolver: impl Resolve + 'static,
+    ) -> Connector<
+        impl Service<
+                ConnectInfo<Uri>,
+                Response = TcpConnection<Uri, TcpStream>,
+                Error = actix_tls::connect::ConnectError,
+            > + Clone,
+    > {
+        let resolver = Resolver::custom(resolver);
+
+        Connector {
+            connector: TcpConnector::new(resolver.clone()).service(),
+            config: self.config,
+            resolver: Some(resolver),
+            tls: self.tls,
+        }
+    }
+
     /// Use custom connector.
     pub fn connector<S1, Io1>(self, connector: S1) -> Connector<S1>
     where
@@ -142,9 +173,18 @@ impl<S> Connector<S> {
         Connector {
             connector,
             config: self.config,
+            resolver: None,
             tls: self.tls,
         }
     }
+
+    /// Use a connector that sends all requests to the Unix domain socket at `path`.
+    ///
+    /// See [`UdsConnector`](crate::UdsConnector).
+    #[cfg(unix)]
+    pub fn uds(self, path: impl AsRef<std::path::Path>) -> Connector<super::UdsConnector> {
+        self.connector(super::UdsConnector::new(path))
+    }
 }
 
 impl<S, IO> Connector<S>
@@ -255,6 +295,18 @@ where
         self
     }
 
+    /// Set number of simultaneous connections per host.
+    ///
+    /// Hosts are identified by the authority (host and port) of the request URI. Requests beyond
+    /// the limit wait, in order, for a connection to the same host to be released. This limit
+    /// applies in addition to the total limit set with [`limit`](Self::limit).
+    ///
+    /// If limit is 0, the connector has no per-host limit. By default, there is no per-host limit.
+    pub fn limit_per_host(mut self, limit: usize) -> Self {
+        self.config.limit_per_host = (limit > 0).then(|| limit);
+        self
+    }
+
     /// Set keep-alive period for opened connection.
     ///
     /// Keep-alive period is the period between connection usage. If
@@ -295,15 +347,62 @@ where
         self
     }
 
+    /// Set how long to wait for a connection attempt before racing it with the next address.
+    ///
+    /// When a host resolves to several addresses, they are tried alternating between IPv6 and
+    /// IPv4, as described by [RFC 8305]. A new attempt starts whenever the previous one fails or
+    /// has not succeeded within this delay, and the first connection established is used. This
+    /// avoids waiting for the full connect timeout on networks where one address family is
+    /// broken.
+    ///
+    /// Only applies to the default connector and ones using a custom [resolver](Self::resolver),
+    /// not to [custom connectors](Connector::connector).
+    ///
+    /// By default, the delay is 250 milliseconds.
+    ///
+    /// [RFC 8305]: https://datatracker.ietf.org/doc/html/rfc8305
+    pub fn happy_eyeballs_delay(mut self, delay: Duration) -> Self {
+        self.config.happy_eyeballs_delay = delay;
+        self
+    }
+
+    /// Route connections through the given proxy.
+    ///
+    /// See [`Proxy`] for how connections are tunneled and how to exclude hosts.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.config.proxy = Some(Rc::new(ProxyConfig::all(proxy)));
+        self
+    }
+
+    /// Route connections through the proxies set in the environment.
+    ///
+    /// The `HTTP_PROXY` and `HTTPS_PROXY` variables set the proxy for `http` and `https` URIs
+    /// respectively, falling back to `ALL_PROXY`, and `NO_PROXY` holds a comma-separated list of
+    /// hosts that are connected to directly. Lowercase variable names take precedence. Variables
+    /// are read when this method is called; invalid proxy URIs are logged and ignored.
+    pub fn proxy_from_env(mut self) -> Self {
+        self.config.proxy = Some(Rc::new(ProxyConfig::from_env()));
+        self
+    }
+
     /// Finish configuration process and create connector service.
     ///
     /// The `Connector` builder always concludes by calling `finish()` last in its combinator chain.
     pub fn finish(self) -> ConnectorService<S, IO> {
         let local_address = self.config.local_address;
         let timeout = self.config.timeout;
+        let proxy = self.config.proxy.clone();
 
-        let tcp_service_inner =
-            TcpConnectorInnerService::new(self.connector, timeout, local_address);
+        let delay = self.config.happy_eyeballs_delay;
+        let happy_eyeballs = self.resolver.map(|resolver| (resolver, delay));
+
+        let tcp_service_inner = TcpConnectorInnerService::new(
+            self.connector,
+            timeout,
+            local_address,
+            proxy,
+            happy_eyeballs,
+        );
 
         #[allow(clippy::redundant_clone)]
         let tcp_service = TcpConnectorService {
@@ -451,11 +550,17 @@ where
 
         let tcp_pool = ConnectionPool::new(tcp_service, tcp_config);
 
+        let proxy = self.config.proxy.clone();
+
         let tls_config = self.config;
         let tls_pool =
             tls_service.map(move |tls_service| ConnectionPool::new(tls_service, tls_config));
 
-        ConnectorServicePriv { tcp_pool, tls_pool }
+        ConnectorServicePriv {
+            tcp_pool,
+            tls_pool,
+            proxy,
+        }
     }
 }
 
@@ -615,14 +720,24 @@ pub struct TcpConnectorInnerService<S: Clone> {
     service: S,
     timeout: Duration,
     local_address: Option<std::net::IpAddr>,
+    proxy: Option<Rc<ProxyConfig>>,
+    happy_eyeballs: Option<(Resolver, Duration)>,
 }
 
 impl<S: Clone> TcpConnectorInnerService<S> {
-    fn new(service: S, timeout: Duration, local_address: Option<std::net::IpAddr>) -> Self {
+    fn new(
+        service: S,
+        timeout: Duration,
+        local_address: Option<std::net::IpAddr>,
+        proxy: Option<Rc<ProxyConfig>>,
+        happy_eyeballs: Option<(Resolver, Duration)>,
+    ) -> Self {
         Self {
             service,
             timeout,
             local_address,
+            proxy,
+            happy_eyeballs,
         }
     }
 }
@@ -632,48 +747,109 @@ where
     S: Service<ConnectInfo<Uri>, Response = TcpConnection<Uri, Io>, Error = TcpConnectError>
         + Clone
         + 'static,
+    Io: ActixStream + 'static,
 {
     type Response = S::Response;
     type Error = ConnectError;
-    type Future = TcpConnectorInnerFuture<S::Future>;
+    type Future = TcpConnectorInnerFuture<S::Future, Io>;
 
     actix_service::forward_ready!(service);
 
     fn call(&self, req: Connect) -> Self::Future {
+        let timeout = sleep(self.timeout);
+
+        let proxy = self
+            .proxy
+            .as_ref()
+            .and_then(|proxy| proxy.proxy_for(&req.uri))
+            .cloned();
+
+        if let Some(proxy) = proxy {
+            let service = self.service.clone();
+            let local_address = self.local_address;
+            let resolver = self
+                .happy_eyeballs
+                .as_ref()
+                .map(|(resolver, _)| resolver.clone())
+                .unwrap_or_default();
+
+            let fut = Box::pin(async move {
+                let mut proxy_req = ConnectInfo::new(proxy.uri().clone());
+
+                if let Some(local_addr) = local_address {
+                    proxy_req = proxy_req.set_local_addr(local_addr);
+                }
+
+                let (mut io, _) = service.call(proxy_req).await?.into_parts();
+                proxy::tunnel(&proxy, &mut io, &req.uri, req.addr, resolver).await?;
+
+                Ok(TcpConnection::new(req.uri, io))
+            });
+
+            return TcpConnectorInnerFuture::Boxed { fut, timeout };
+        }
+
+        if let (Some((resolver, delay)), None) = (&self.happy_eyeballs, req.addr) {
+            let fut = Box::pin(happy_eyeballs::connect(
+                self.service.clone(),
+                resolver.clone(),
+                req.uri,
+                self.local_address,
+                *delay,
+            ));
+
+            return TcpConnectorInnerFuture::Boxed { fut, timeout };
+        }
+
         let mut req = ConnectInfo::new(req.uri).set_addr(req.addr);
 
         if let Some(local_addr) = self.local_address {
             req = req.set_local_addr(local_addr);
         }
 
-        TcpConnectorInnerFuture {
+        TcpConnectorInnerFuture::Direct {
             fut: self.service.call(req),
-            timeout: sleep(self.timeout),
+            timeout,
         }
     }
 }
 
 pin_project! {
     #[project = TcpConnectorInnerFutureProj]
-    pub struct TcpConnectorInnerFuture<Fut> {
-        #[pin]
-        fut: Fut,
-        #[pin]
-        timeout: Sleep,
+    pub enum TcpConnectorInnerFuture<Fut, Io> {
+        Direct {
+            #[pin]
+            fut: Fut,
+            #[pin]
+            timeout: Sleep,
+        },
+        // proxied or raced connections
+        Boxed {
+            fut: LocalBoxFuture<'static, Result<TcpConnection<Uri, Io>, ConnectError>>,
+            #[pin]
+            timeout: Sleep,
+        },
     }
 }
 
-impl<Fut, Io> Future for TcpConnectorInnerFuture<Fut>
+impl<Fut, Io> Future for TcpConnectorInnerFuture<Fut, Io>
 where
     Fut: Future<Output = Result<TcpConnection<Uri, Io>, TcpConnectError>>,
 {
     type Output = Result<TcpConnection<Uri, Io>, ConnectError>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        let this = self.project();
-        match this.fut.poll(cx) {
-            Poll::Ready(res) => Poll::Ready(res.map_err(ConnectError::from)),
-            Poll::Pending => this.timeout.poll(cx).map(|_| Err(ConnectError::Timeout)),
+        match self.project() {
+            TcpConnectorInnerFutureProj::Direct { fut, timeout } => match fut.poll(cx) {
+                Poll::Ready(res) => Poll::Ready(res.map_err(ConnectError::from)),
+                Poll::Pending => timeout.poll(cx).map(|_| Err(ConnectError::Timeout)),
+            },
+            TcpConnectorInnerFutureProj::Boxed { fut, timeout } => {
+                match fut.as_mut().poll(cx) {
+                    Poll::Ready(res) => Poll::Ready(res),
+                    Poll::Pending => timeout.poll(cx).map(|_| Err(ConnectError::Timeout)),
+                }
+            }
         }
     }
 }
@@ -705,6 +881,7 @@ where
 {
     tcp_pool: ConnectionPool<S1, Io1>,
     tls_pool: Option<ConnectionPool<S2, Io2>>,
+    proxy: Option<Rc<ProxyConfig>>,
 }
 
 impl<S1, S2, Io1, Io2> Service<Connect> for ConnectorServicePriv<S1, S2, Io1, Io2>
@@ -735,6 +912,10 @@ where
                 },
             },
             _ => ConnectorServiceFuture::Tcp {
+                forward: self
+                    .proxy
+                    .as_ref()
+                    .and_then(|proxy| proxy.forward_for(&req.uri)),
                 fut: self.tcp_pool.call(req),
             },
         }
@@ -756,7 +937,8 @@ pin_project! {
     {
         Tcp {
             #[pin]
-            fut: <ConnectionPool<S1, Io1> as Service<Connect>>::Future
+            fut: <ConnectionPool<S1, Io1> as Service<Connect>>::Future,
+            forward: Option<ForwardProxy>,
         },
         Tls {
             #[pin]
@@ -777,7 +959,9 @@ where
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         match self.project() {
-            ConnectorServiceFutureProj::Tcp { fut } => fut.poll(cx).map_ok(Connection::Tcp),
+            ConnectorServiceFutureProj::Tcp { fut, forward } => fut
+                .poll(cx)
+                .map_ok(|conn| Connection::Tcp(conn.forward_proxy(forward.take()))),
             ConnectorServiceFutureProj::Tls { fut } => fut.poll(cx).map_ok(Connection::Tls),
             ConnectorServiceFutureProj::SslIsNotSupported => {
                 Poll::Ready(Err(ConnectError::SslIsNotSupported))
@@ -786,84 +970,20 @@ where
     }
 }
 
-#[cfg(not(feature = "trust-dns"))]
-mod resolver {
-    use super::*;
-
-    pub(super) fn resolver() -> Resolver {
-        Resolver::default()
-    }
-}
-
-#[cfg(feature = "trust-dns")]
 mod resolver {
-    use std::{cell::RefCell, net::SocketAddr};
-
-    use actix_tls::connect::Resolve;
-    use futures_core::future::LocalBoxFuture;
-    use trust_dns_resolver::{
-        config::{ResolverConfig, ResolverOpts},
-        system_conf::read_system_conf,
-        TokioAsyncResolver,
-    };
-
     use super::*;
 
     pub(super) fn resolver() -> Resolver {
-        // new type for impl Resolve trait for TokioAsyncResolver.
-        struct TrustDnsResolver(TokioAsyncResolver);
-
-        impl Resolve for TrustDnsResolver {
-            fn lookup<'a>(
-                &'a self,
-                host: &'a str,
-                port: u16,
-            ) -> LocalBoxFuture<'a, Result<Vec<SocketAddr>, Box<dyn std::error::Error>>>
-            {
-                Box::pin(async move {
-                    let res = self
-                        .0
-                        .lookup_ip(host)
-                        .await?
-                        .iter()
-                        .map(|ip| SocketAddr::new(ip, port))
-                        .collect();
-                    Ok(res)
-                })
-            }
+        // trust-dns lookups are made by `DnsResolver`, which reuses the resolver of each thread
+        #[cfg(feature = "trust-dns")]
+        {
+            Resolver::custom(super::super::DnsResolver::new())
         }
 
-        // resolver struct is cached in thread local so new clients can reuse the existing instance
-        thread_local! {
-            static TRUST_DNS_RESOLVER: RefCell<Option<Resolver>> = RefCell::new(None);
+        #[cfg(not(feature = "trust-dns"))]
+        {
+            Resolver::default()
         }
-
-        // get from thread local or construct a new trust-dns resolver.
-        TRUST_DNS_RESOLVER.with(|local| {
-            let resolver = local.borrow().as_ref().map(Clone::clone);
-
-            match resolver {
-                Some(resolver) => resolver,
-
-                None => {
-                    let (cfg, opts) = match read_system_conf() {
-                        Ok((cfg, opts)) => (cfg, opts),
-                        Err(e) => {
-                            log::error!("TRust-DNS can not load system config: {}", e);
-                            (ResolverConfig::default(), ResolverOpts::default())
-                        }
-                    };
-
-                    let resolver = TokioAsyncResolver::tokio(cfg, opts).unwrap();
-
-                    // box trust dns resolver and put it in thread local.
-                    let resolver = Resolver::custom(TrustDnsResolver(resolver));
-                    *local.borrow_mut() = Some(resolver.clone());
-
-                    resolver
-                }
-            }
-        })
     }
 }
 
@@ -892,6 +1012,7 @@ mod tests {
         let connector = Connector {
             connector: TcpConnector::new(resolver::resolver()).service(),
             config: ConnectorConfig::default(),
+            resolver: None,
             tls: OurTlsConnector::None,
         };
 
@@ -0,0 +1,200 @@
+//! Dual-stack connection racing, as described by [RFC 8305] ("Happy Eyeballs").
+//!
+//! [RFC 8305]: https://datatracker.ietf.org/doc/html/rfc8305
+
+use std::{
+    future::Future as _,
+    net::{IpAddr, SocketAddr},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_rt::time::{sleep, Instant};
+use actix_service::Service;
+use actix_tls::connect::{
+    ConnectError as TcpConnectError, ConnectInfo, Connection as TcpConnection, Resolver,
+};
+use futures_util::{
+    future::poll_fn,
+    stream::{FuturesUnordered, StreamExt as _},
+};
+use http::Uri;
+
+use super::error::ConnectError;
+
+/// Resolves the host of `uri` and connects to its addresses, starting a new attempt each time
+/// `delay` passes or an attempt fails, until one succeeds.
+pub(super) async fn connect<S, Io>(
+    service: S,
+    resolver: Resolver,
+    uri: Uri,
+    local_address: Option<IpAddr>,
+    delay: Duration,
+) -> Result<TcpConnection<Uri, Io>, ConnectError>
+where
+    S: Service<ConnectInfo<Uri>, Response = TcpConnection<Uri, Io>, Error = TcpConnectError>,
+{
+    let mut info = resolver
+        .service()
+        .call(ConnectInfo::new(uri.clone()))
+        .await?;
+    let mut addrs = interleave(info.take_addrs().collect())
+        .into_iter()
+        .peekable();
+
+    let attempt = |addr| {
+        let mut req = ConnectInfo::with_addr(uri.clone(), addr);
+
+        if let Some(local_addr) = local_address {
+            req = req.set_local_addr(local_addr);
+        }
+
+        service.call(req)
+    };
+
+    let mut attempts = FuturesUnordered::new();
+    let mut last_err = None;
+    let mut start_next = true;
+    let mut next_attempt = Box::pin(sleep(delay));
+
+    poll_fn(|cx: &mut Context<'_>| loop {
+        // an attempt starts right away when the previous one failed, otherwise after the delay
+        if addrs.peek().is_some() && (start_next || next_attempt.as_mut().poll(cx).is_ready()) {
+            attempts.push(attempt(addrs.next().unwrap()));
+            next_attempt.as_mut().reset(Instant::now() + delay);
+            start_next = false;
+            continue;
+        }
+
+        match attempts.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(conn))) => return Poll::Ready(Ok(conn)),
+            Poll::Ready(Some(Err(err))) => {
+                last_err = Some(err);
+                start_next = true;
+            }
+            Poll::Ready(None) => {
+                let err = last_err.take().unwrap_or(TcpConnectError::NoRecords);
+                return Poll::Ready(Err(err.into()));
+            }
+            Poll::Pending => return Poll::Pending,
+        }
+    })
+    .await
+}
+
+/// Orders addresses so that address families alternate, starting with the family of the first.
+fn interleave(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let first_is_ipv6 = match addrs.first() {
+        Some(addr) => addr.is_ipv6(),
+        None => return addrs,
+    };
+
+    let (first, second): (Vec<_>, Vec<_>) = addrs
+        .into_iter()
+        .partition(|addr| addr.is_ipv6() == first_is_ipv6);
+
+    let mut res = Vec::with_capacity(first.len() + second.len());
+    let mut first = first.into_iter();
+    let mut second = second.into_iter();
+
+    loop {
+        match (first.next(), second.next()) {
+            (None, None) => break,
+            (a, b) => res.extend(a.into_iter().chain(b)),
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        error::Error as StdError,
+        io,
+        net::{Ipv4Addr, Ipv6Addr},
+    };
+
+    use actix_rt::time::timeout;
+    use actix_service::fn_service;
+    use actix_tls::connect::Resolve;
+    use futures_core::future::LocalBoxFuture;
+
+    use super::*;
+
+    const V4: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+    const V6: IpAddr = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+
+    struct DualStack;
+
+    impl Resolve for DualStack {
+        fn lookup<'a>(
+            &'a self,
+            _host: &'a str,
+            port: u16,
+        ) -> LocalBoxFuture<'a, Result<Vec<SocketAddr>, Box<dyn StdError>>> {
+            Box::pin(
+                async move { Ok(vec![SocketAddr::new(V6, port), SocketAddr::new(V4, port)]) },
+            )
+        }
+    }
+
+    /// Connects to the IPv4 address; IPv6 attempts fail or never complete.
+    async fn race(ipv6_fails: bool, delay: Duration) -> Result<IpAddr, ConnectError> {
+        let service = fn_service(move |req: ConnectInfo<Uri>| {
+            let addr = req.addrs().next().unwrap();
+
+            async move {
+                if addr.is_ipv4() {
+                    Ok(TcpConnection::new(req.request().clone(), addr.ip()))
+                } else if ipv6_fails {
+                    Err(TcpConnectError::Io(io::ErrorKind::ConnectionRefused.into()))
+                } else {
+                    futures_util::future::pending().await
+                }
+            }
+        });
+
+        let uri = Uri::from_static("http://dual-stack.example");
+        let conn = connect(service, Resolver::custom(DualStack), uri, None, delay).await?;
+        Ok(*conn.io_ref())
+    }
+
+    #[actix_rt::test]
+    async fn races_after_delay() {
+        let res = timeout(
+            Duration::from_secs(1),
+            race(false, Duration::from_millis(10)),
+        )
+        .await;
+        assert_eq!(res.unwrap().unwrap(), V4);
+    }
+
+    #[actix_rt::test]
+    async fn failed_attempt_starts_next() {
+        let res = timeout(Duration::from_secs(1), race(true, Duration::from_secs(60))).await;
+        assert_eq!(res.unwrap().unwrap(), V4);
+    }
+
+    #[test]
+    fn interleaves_families() {
+        let v4 = |n| SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 0, 2, n)), 80);
+        let v6 = |n| {
+            SocketAddr::new(
+                IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, n)),
+                80,
+            )
+        };
+
+        assert_eq!(
+            interleave(vec![v6(1), v6(2), v6(3), v4(1)]),
+            vec![v6(1), v4(1), v6(2), v6(3)]
+        );
+        assert_eq!(
+            interleave(vec![v4(1), v4(2), v6(1), v6(2)]),
+            vec![v4(1), v6(1), v4(2), v6(2)]
+        );
+        assert_eq!(interleave(vec![v4(1)]), vec![v4(1)]);
+        assert!(interleave(Vec::new()).is_empty());
+    }
+}
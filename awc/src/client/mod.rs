@@ -17,11 +17,20 @@ mod connector;
 mod error;
 mod h1proto;
 mod h2proto;
+mod happy_eyeballs;
 mod pool;
+mod proxy;
+mod resolver;
+#[cfg(unix)]
+mod uds;
 
 pub use self::connection::{Connection, ConnectionIo};
 pub use self::connector::{Connector, ConnectorService};
 pub use self::error::{ConnectError, FreezeRequestError, InvalidUrl, SendRequestError};
+pub use self::proxy::Proxy;
+pub use self::resolver::{DnsResolver, IpPreference};
+#[cfg(unix)]
+pub use self::uds::UdsConnector;
 
 #[derive(Clone)]
 pub struct Connect {
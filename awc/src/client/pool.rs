@@ -30,6 +30,9 @@ use super::error::ConnectError;
 use super::h2proto::handshake;
 use super::Connect;
 
+/// Number of tracked hosts above which idle per-host permit entries are dropped.
+const HOST_PERMITS_PRUNE_THRESHOLD: usize = 256;
+
 #[derive(Hash, Eq, PartialEq, Clone, Debug)]
 pub struct Key {
     authority: Authority,
@@ -63,11 +66,13 @@ where
     fn new(config: ConnectorConfig) -> Self {
         let permits = Arc::new(Semaphore::new(config.limit));
         let available = RefCell::new(AHashMap::default());
+        let host_permits = RefCell::new(AHashMap::default());
 
         Self(Rc::new(ConnectionPoolInnerPriv {
             config,
             available,
             permits,
+            host_permits,
         }))
     }
 
@@ -110,6 +115,10 @@ where
         // remove and drop all Io types.
         if Rc::strong_count(&self.0) == 1 {
             self.permits.close();
+            self.host_permits
+                .borrow()
+                .values()
+                .for_each(|permits| permits.close());
             std::mem::take(&mut *self.available.borrow_mut())
                 .into_iter()
                 .for_each(|(_, conns)| {
@@ -126,6 +135,31 @@ where
     config: ConnectorConfig,
     available: RefCell<AHashMap<Key, VecDeque<PooledConnection<Io>>>>,
     permits: Arc<Semaphore>,
+    /// per-host permits; only populated when a per-host limit is configured.
+    host_permits: RefCell<AHashMap<Key, Arc<Semaphore>>>,
+}
+
+impl<Io> ConnectionPoolInnerPriv<Io>
+where
+    Io: AsyncWrite + Unpin + 'static,
+{
+    /// Returns the semaphore limiting connections to the host of `key`, if a limit is configured.
+    fn host_permits(&self, key: &Key) -> Option<Arc<Semaphore>> {
+        let limit = self.config.limit_per_host?;
+
+        let mut host_permits = self.host_permits.borrow_mut();
+
+        // forget hosts with no connections in use to keep the map from growing unbounded
+        if host_permits.len() > HOST_PERMITS_PRUNE_THRESHOLD {
+            host_permits.retain(|_, permits| Arc::strong_count(permits) > 1);
+        }
+
+        let permits = host_permits
+            .entry(key.clone())
+            .or_insert_with(|| Arc::new(Semaphore::new(limit)));
+
+        Some(Arc::clone(permits))
+    }
 }
 
 impl<S, Io> ConnectionPool<S, Io>
@@ -171,13 +205,27 @@ where
                 return Err(ConnectError::Unresolved);
             };
 
-            // acquire an owned permit and carry it with connection
-            let permit = inner.permits.clone().acquire_owned().await.map_err(|_| {
+            let permit_err = |_| {
                 ConnectError::Io(io::Error::new(
                     io::ErrorKind::Other,
                     "failed to acquire semaphore on client connection pool",
                 ))
-            })?;
+            };
+
+            // acquire the per-host permit first so that queued requests for one busy host do not
+            // hold on to permits needed by other hosts
+            let host_permit = match inner.host_permits(&key) {
+                Some(permits) => Some(permits.acquire_owned().await.map_err(permit_err)?),
+                None => None,
+            };
+
+            // acquire an owned permit and carry it with connection
+            let permit = inner
+                .permits
+                .clone()
+                .acquire_owned()
+                .await
+                .map_err(permit_err)?;
 
             let conn = {
                 let mut conn = None;
@@ -224,7 +272,12 @@ where
 
             // construct acquired. It's used to put Io type back to pool/ close the Io type.
             // permit is carried with the whole lifecycle of Acquired.
-            let acquired = Acquired { key, inner, permit };
+            let acquired = Acquired {
+                key,
+                inner,
+                permit,
+                host_permit,
+            };
 
             // match the connection and spawn new one if did not get anything.
             match conn {
@@ -342,6 +395,8 @@ where
     inner: ConnectionPoolInner<Io>,
     /// permit for limit concurrent in-flight connection for a Client object.
     permit: OwnedSemaphorePermit,
+    /// permit for limit concurrent in-flight connection to the host of `key`.
+    host_permit: Option<OwnedSemaphorePermit>,
 }
 
 impl<Io: ConnectionIo> Acquired<Io> {
@@ -366,6 +421,7 @@ impl<Io: ConnectionIo> Acquired<Io> {
             });
 
         let _ = &self.permit;
+        let _ = &self.host_permit;
     }
 }
 
@@ -485,6 +541,53 @@ mod test {
         assert!(now.elapsed() >= Duration::from_millis(100));
     }
 
+    #[actix_rt::test]
+    async fn test_pool_limit_per_host() {
+        let connector = TestPoolConnector {
+            generated: Rc::new(Cell::new(0)),
+        };
+
+        let config = ConnectorConfig {
+            limit_per_host: Some(1),
+            ..Default::default()
+        };
+
+        let pool = super::ConnectionPool::new(connector, config);
+
+        let req = Connect {
+            uri: Uri::from_static("http://localhost"),
+            addr: None,
+        };
+
+        let conn = pool.call(req.clone()).await.unwrap();
+
+        // other hosts are not affected by the limit
+        let other_req = Connect {
+            uri: Uri::from_static("http://127.0.0.1"),
+            addr: None,
+        };
+        let other_conn = pool.call(other_req).await.unwrap();
+
+        let waiting = Rc::new(Cell::new(true));
+
+        let waiting_clone = waiting.clone();
+        actix_rt::spawn(async move {
+            actix_rt::time::sleep(Duration::from_millis(100)).await;
+            waiting_clone.set(false);
+            drop(conn);
+        });
+
+        assert!(waiting.get());
+
+        let now = Instant::now();
+        let conn = pool.call(req).await.unwrap();
+
+        release(conn);
+        release(other_conn);
+        assert!(!waiting.get());
+        assert!(now.elapsed() >= Duration::from_millis(100));
+    }
+
     #[actix_rt::test]
     async fn test_pool_keep_alive() {
         let generated = Rc::new(Cell::new(0));
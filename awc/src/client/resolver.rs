@@ -0,0 +1,353 @@
+//! Configurable DNS resolution for the client connector.
+
+use std::{
+    error::Error as StdError,
+    fmt,
+    net::{IpAddr, SocketAddr},
+    rc::Rc,
+};
+
+use actix_tls::connect::Resolve;
+use ahash::AHashMap;
+use futures_core::future::LocalBoxFuture;
+
+/// Which address families are used, and which are tried first, when a host has both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IpPreference {
+    /// Keeps the order returned by the lookup.
+    System,
+
+    /// Tries IPv6 addresses before IPv4 addresses.
+    Ipv6First,
+
+    /// Tries IPv4 addresses before IPv6 addresses.
+    Ipv4First,
+
+    /// Only connects to IPv6 addresses.
+    Ipv6Only,
+
+    /// Only connects to IPv4 addresses.
+    Ipv4Only,
+}
+
+impl Default for IpPreference {
+    fn default() -> Self {
+        IpPreference::System
+    }
+}
+
+impl IpPreference {
+    fn apply(self, addrs: &mut Vec<SocketAddr>) {
+        match self {
+            IpPreference::System => {}
+            // sorts are stable, so the lookup order is kept within each family
+            IpPreference::Ipv6First => addrs.sort_by_key(SocketAddr::is_ipv4),
+            IpPreference::Ipv4First => addrs.sort_by_key(SocketAddr::is_ipv6),
+            IpPreference::Ipv6Only => addrs.retain(SocketAddr::is_ipv6),
+            IpPreference::Ipv4Only => addrs.retain(SocketAddr::is_ipv4),
+        }
+    }
+}
+
+enum Lookup {
+    System,
+
+    #[cfg(feature = "trust-dns")]
+    TrustDns(Rc<trust_dns_resolver::TokioAsyncResolver>),
+
+    Custom(Rc<dyn Resolve>),
+}
+
+/// DNS resolver for the client [`Connector`](super::Connector).
+///
+/// By default, host names are resolved using the system configuration. With the `trust-dns` crate
+/// feature, lookups are made asynchronously by [`trust-dns-resolver`], which also allows querying
+/// [specific name servers](Self::nameservers); otherwise, the system resolver is run on a thread
+/// pool. Any other [`Resolve`] implementation can be plugged in with [`custom`](Self::custom).
+///
+/// Lookups can be [bypassed](Self::override_host) for specific hosts, which is useful for pointing
+/// a client at a test server, and resolved addresses can be filtered or reordered by
+/// [address family](Self::ip_preference).
+///
+/// # Examples
+/// ```
+/// use std::net::{IpAddr, Ipv4Addr};
+/// use awc::{Client, Connector, DnsResolver, IpPreference};
+///
+/// let resolver = DnsResolver::new()
+///     .override_host("api.example.com", [IpAddr::V4(Ipv4Addr::LOCALHOST)])
+///     .ip_preference(IpPreference::Ipv4First);
+///
+/// let client = Client::builder()
+///     .connector(Connector::new().resolver(resolver))
+///     .finish();
+/// ```
+///
+/// [`trust-dns-resolver`]: https://docs.rs/trust-dns-resolver
+#[derive(Clone)]
+pub struct DnsResolver {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    lookup: Lookup,
+    overrides: AHashMap<String, Vec<IpAddr>>,
+    ip_preference: IpPreference,
+}
+
+impl Default for DnsResolver {
+    fn default() -> Self {
+        DnsResolver {
+            inner: Rc::new(Inner {
+                lookup: Lookup::System,
+                overrides: AHashMap::default(),
+                ip_preference: IpPreference::default(),
+            }),
+        }
+    }
+}
+
+impl DnsResolver {
+    /// Constructs a resolver using the system configuration.
+    pub fn new() -> Self {
+        DnsResolver::default()
+    }
+
+    /// Resolves host names with the given name servers instead of the system configuration.
+    ///
+    /// Name servers are queried over UDP, falling back to TCP for large responses.
+    ///
+    /// # Panics
+    /// Panics if called after the resolver has been cloned.
+    #[cfg(feature = "trust-dns")]
+    pub fn nameservers(mut self, nameservers: impl IntoIterator<Item = SocketAddr>) -> Self {
+        use trust_dns_resolver::{
+            config::{NameServerConfigGroup, ResolverConfig, ResolverOpts},
+            TokioAsyncResolver,
+        };
+
+        let mut group = NameServerConfigGroup::new();
+        for addr in nameservers {
+            group.merge(NameServerConfigGroup::from_ips_clear(
+                &[addr.ip()],
+                addr.port(),
+                true,
+            ));
+        }
+
+        let config = ResolverConfig::from_parts(None, Vec::new(), group);
+        let resolver = TokioAsyncResolver::tokio(config, ResolverOpts::default())
+            .expect("resolver configuration should be valid");
+
+        self.inner_mut().lookup = Lookup::TrustDns(Rc::new(resolver));
+        self
+    }
+
+    /// Resolves host names with a custom [`Resolve`] implementation.
+    ///
+    /// Host overrides and the IP preference still apply to its results.
+    ///
+    /// # Panics
+    /// Panics if called after the resolver has been cloned.
+    pub fn custom(mut self, resolver: impl Resolve + 'static) -> Self {
+        self.inner_mut().lookup = Lookup::Custom(Rc::new(resolver));
+        self
+    }
+
+    /// Resolves `host` to the given addresses without a lookup.
+    ///
+    /// Host names are matched case-insensitively. Connections to IP address hosts are never
+    /// resolved and cannot be overridden.
+    ///
+    /// # Panics
+    /// Panics if called after the resolver has been cloned.
+    pub fn override_host(
+        mut self,
+        host: impl Into<String>,
+        addrs: impl IntoIterator<Item = IpAddr>,
+    ) -> Self {
+        let host = host.into().to_ascii_lowercase();
+        let addrs = addrs.into_iter().collect();
+        self.inner_mut().overrides.insert(host, addrs);
+        self
+    }
+
+    /// Sets which address families are used, and which are tried first.
+    ///
+    /// Defaults to [`IpPreference::System`].
+    ///
+    /// # Panics
+    /// Panics if called after the resolver has been cloned.
+    pub fn ip_preference(mut self, preference: IpPreference) -> Self {
+        self.inner_mut().ip_preference = preference;
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("DnsResolver must be configured before cloning")
+    }
+
+    async fn lookup_addrs(
+        &self,
+        host: &str,
+        port: u16,
+    ) -> Result<Vec<SocketAddr>, Box<dyn StdError>> {
+        let inner = &self.inner;
+
+        if let Some(ips) = inner.overrides.get(&host.to_ascii_lowercase()) {
+            return Ok(ips.iter().map(|&ip| SocketAddr::new(ip, port)).collect());
+        }
+
+        match inner.lookup {
+            Lookup::System => system_lookup(host, port).await,
+
+            #[cfg(feature = "trust-dns")]
+            Lookup::TrustDns(ref resolver) => trust_dns_lookup(resolver, host, port).await,
+
+            Lookup::Custom(ref resolver) => resolver.lookup(host, port).await,
+        }
+    }
+}
+
+impl Resolve for DnsResolver {
+    fn lookup<'a>(
+        &'a self,
+        host: &'a str,
+        port: u16,
+    ) -> LocalBoxFuture<'a, Result<Vec<SocketAddr>, Box<dyn StdError>>> {
+        Box::pin(async move {
+            let mut addrs = self.lookup_addrs(host, port).await?;
+            self.inner.ip_preference.apply(&mut addrs);
+            Ok(addrs)
+        })
+    }
+}
+
+impl fmt::Debug for DnsResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DnsResolver")
+            .field("overrides", &self.inner.overrides)
+            .field("ip_preference", &self.inner.ip_preference)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(not(feature = "trust-dns"))]
+async fn system_lookup(host: &str, port: u16) -> Result<Vec<SocketAddr>, Box<dyn StdError>> {
+    // the system resolver blocks, so it runs on tokio's blocking thread pool
+    Ok(tokio::net::lookup_host((host, port)).await?.collect())
+}
+
+#[cfg(feature = "trust-dns")]
+async fn system_lookup(host: &str, port: u16) -> Result<Vec<SocketAddr>, Box<dyn StdError>> {
+    let resolver = system_resolver();
+    trust_dns_lookup(&resolver, host, port).await
+}
+
+#[cfg(feature = "trust-dns")]
+async fn trust_dns_lookup(
+    resolver: &trust_dns_resolver::TokioAsyncResolver,
+    host: &str,
+    port: u16,
+) -> Result<Vec<SocketAddr>, Box<dyn StdError>> {
+    Ok(resolver
+        .lookup_ip(host)
+        .await?
+        .iter()
+        .map(|ip| SocketAddr::new(ip, port))
+        .collect())
+}
+
+/// Returns a trust-dns resolver using the system configuration.
+#[cfg(feature = "trust-dns")]
+pub(crate) fn system_resolver() -> trust_dns_resolver::TokioAsyncResolver {
+    use std::cell::RefCell;
+
+    use trust_dns_resolver::{
+        config::{ResolverConfig, ResolverOpts},
+        system_conf::read_system_conf,
+        TokioAsyncResolver,
+    };
+
+    // resolver is cached in thread local so new clients can reuse the existing instance
+    thread_local! {
+        static TRUST_DNS_RESOLVER: RefCell<Option<TokioAsyncResolver>> = RefCell::new(None);
+    }
+
+    // get from thread local or construct a new trust-dns resolver.
+    TRUST_DNS_RESOLVER.with(|local| {
+        let resolver = local.borrow().as_ref().map(Clone::clone);
+
+        match resolver {
+            Some(resolver) => resolver,
+
+            None => {
+                let (cfg, opts) = match read_system_conf() {
+                    Ok((cfg, opts)) => (cfg, opts),
+                    Err(e) => {
+                        log::error!("TRust-DNS can not load system config: {}", e);
+                        (ResolverConfig::default(), ResolverOpts::default())
+                    }
+                };
+
+                let resolver = TokioAsyncResolver::tokio(cfg, opts).unwrap();
+                *local.borrow_mut() = Some(resolver.clone());
+
+                resolver
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    struct StaticResolver(Vec<IpAddr>);
+
+    impl Resolve for StaticResolver {
+        fn lookup<'a>(
+            &'a self,
+            _host: &'a str,
+            port: u16,
+        ) -> LocalBoxFuture<'a, Result<Vec<SocketAddr>, Box<dyn StdError>>> {
+            let addrs = self.0.iter().map(|&ip| SocketAddr::new(ip, port)).collect();
+            Box::pin(async move { Ok(addrs) })
+        }
+    }
+
+    const V4: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+    const V6: IpAddr = IpAddr::V6(Ipv6Addr::LOCALHOST);
+
+    fn ips(addrs: Vec<SocketAddr>) -> Vec<IpAddr> {
+        addrs.into_iter().map(|addr| addr.ip()).collect()
+    }
+
+    #[actix_rt::test]
+    async fn overrides_and_preference() {
+        let resolver = DnsResolver::new()
+            .custom(StaticResolver(vec![V4, V6]))
+            .override_host("Test.Example", [V6]);
+
+        let addrs = resolver.lookup("test.example", 8080).await.unwrap();
+        assert_eq!(addrs, vec![SocketAddr::new(V6, 8080)]);
+
+        let addrs = resolver.lookup("other.example", 80).await.unwrap();
+        assert_eq!(ips(addrs), vec![V4, V6]);
+
+        let resolver = DnsResolver::new()
+            .custom(StaticResolver(vec![V4, V6]))
+            .ip_preference(IpPreference::Ipv6First);
+        let addrs = resolver.lookup("other.example", 80).await.unwrap();
+        assert_eq!(ips(addrs), vec![V6, V4]);
+
+        let resolver = DnsResolver::new()
+            .custom(StaticResolver(vec![V4, V6]))
+            .ip_preference(IpPreference::Ipv4Only);
+        let addrs = resolver.lookup("other.example", 80).await.unwrap();
+        assert_eq!(ips(addrs), vec![V4]);
+    }
+}
@@ -0,0 +1,80 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use actix_rt::net::UnixStream;
+use actix_service::Service;
+use actix_tls::connect::{
+    ConnectError as TcpConnectError, ConnectInfo, Connection as TcpConnection,
+};
+use futures_core::future::LocalBoxFuture;
+use http::Uri;
+
+/// Connector service that opens connections to a Unix domain socket.
+///
+/// All requests are sent to the socket at the configured path, regardless of the host in the
+/// request URI. The host is still used for the `Host` header, so URIs like
+/// `http://localhost/path` are typical. WebSocket connections made with [`Client::ws`] are
+/// routed through the socket too.
+///
+/// Use with [`Connector::connector`] or the shorthand [`Connector::uds`].
+///
+/// [`Client::ws`]: crate::Client::ws
+/// [`Connector::connector`]: crate::Connector::connector
+/// [`Connector::uds`]: crate::Connector::uds
+///
+/// # Examples
+/// ```
+/// use awc::{Client, Connector};
+///
+/// let client = Client::builder()
+///     .connector(Connector::new().uds("/run/app.sock"))
+///     .finish();
+///
+/// // requests are sent over the socket
+/// let req = client.get("http://localhost/health");
+/// ```
+#[derive(Clone)]
+pub struct UdsConnector {
+    path: Rc<PathBuf>,
+}
+
+impl UdsConnector {
+    /// Constructs a connector for the Unix domain socket at `path`.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: Rc::new(path.as_ref().to_owned()),
+        }
+    }
+}
+
+impl fmt::Debug for UdsConnector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UdsConnector")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl Service<ConnectInfo<Uri>> for UdsConnector {
+    type Response = TcpConnection<Uri, UnixStream>;
+    type Error = TcpConnectError;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::always_ready!();
+
+    fn call(&self, req: ConnectInfo<Uri>) -> Self::Future {
+        let path = Rc::clone(&self.path);
+        let uri = req.request().clone();
+
+        Box::pin(async move {
+            let io = UnixStream::connect(&*path)
+                .await
+                .map_err(TcpConnectError::Io)?;
+
+            Ok(TcpConnection::new(uri, io))
+        })
+    }
+}
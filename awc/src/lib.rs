@@ -116,6 +116,7 @@ pub use actix_http::body;
 pub use cookie;
 
 mod any_body;
+mod backoff;
 mod builder;
 mod client;
 mod connect;
@@ -138,7 +139,9 @@ pub mod http {
 }
 
 pub use self::builder::ClientBuilder;
-pub use self::client::{Client, Connector};
+#[cfg(unix)]
+pub use self::client::UdsConnector;
+pub use self::client::{Client, Connector, DnsResolver, IpPreference, Proxy};
 pub use self::connect::{BoxConnectorService, BoxedSocket, ConnectRequest, ConnectResponse};
 pub use self::frozen::{FrozenClientRequest, FrozenSendBuilder};
 pub use self::request::ClientRequest;
@@ -0,0 +1,213 @@
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_http::{
+    error::HttpError,
+    header::{self, HeaderMap, HeaderValue},
+    RequestHeadType,
+};
+use actix_service::Service;
+use actix_utils::future::Either;
+
+use super::Transform;
+use crate::{
+    connect::{ConnectRequest, ConnectResponse},
+    error::SendRequestError,
+};
+
+/// Client middleware for adding a bearer token `Authorization` header to outbound requests.
+///
+/// The token is obtained from a function on every request, which allows tokens to be refreshed or
+/// rotated without rebuilding the client. Requests that already have an `Authorization` header are
+/// sent unchanged. Requests fail with [`SendRequestError::Http`] if the token is not a valid
+/// header value, e.g. when it contains a newline.
+///
+/// For a fixed token, [`ClientBuilder::bearer_auth`](crate::ClientBuilder::bearer_auth) can be
+/// used instead.
+///
+/// # Examples
+/// ```
+/// use std::{cell::RefCell, rc::Rc};
+/// use awc::{middleware::BearerToken, Client};
+///
+/// let token = Rc::new(RefCell::new(String::from("initial-token")));
+///
+/// let client = Client::builder()
+///     .wrap(BearerToken::from_fn({
+///         let token = Rc::clone(&token);
+///         move || token.borrow().clone()
+///     }))
+///     .finish();
+///
+/// // later, after refreshing the token
+/// *token.borrow_mut() = String::from("refreshed-token");
+/// ```
+pub struct BearerToken {
+    token_fn: Rc<dyn Fn() -> String>,
+}
+
+impl BearerToken {
+    /// Constructs a `BearerToken` middleware that always sends the given token.
+    pub fn new(token: impl Into<String>) -> Self {
+        let token = token.into();
+        Self::from_fn(move || token.clone())
+    }
+
+    /// Constructs a `BearerToken` middleware that calls `token_fn` to get the token for each
+    /// request.
+    pub fn from_fn<F>(token_fn: F) -> Self
+    where
+        F: Fn() -> String + 'static,
+    {
+        Self {
+            token_fn: Rc::new(token_fn),
+        }
+    }
+}
+
+impl<S> Transform<S, ConnectRequest> for BearerToken
+where
+    S: Service<ConnectRequest, Response = ConnectResponse, Error = SendRequestError>,
+{
+    type Transform = BearerTokenService<S>;
+
+    fn new_transform(self, service: S) -> Self::Transform {
+        BearerTokenService {
+            token_fn: self.token_fn,
+            connector: service,
+        }
+    }
+}
+
+pub struct BearerTokenService<S> {
+    token_fn: Rc<dyn Fn() -> String>,
+    connector: S,
+}
+
+impl<S> Service<ConnectRequest> for BearerTokenService<S>
+where
+    S: Service<ConnectRequest, Response = ConnectResponse, Error = SendRequestError>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<S::Future, Ready<Result<ConnectResponse, SendRequestError>>>;
+
+    actix_service::forward_ready!(connector);
+
+    fn call(&self, req: ConnectRequest) -> Self::Future {
+        let req = match req {
+            ConnectRequest::Client(mut head, body, addr) => {
+                if !has_authorization(&head) {
+                    let value =
+                        match HeaderValue::from_str(&format!("Bearer {}", (self.token_fn)())) {
+                            Ok(value) => value,
+                            Err(err) => {
+                                let err = SendRequestError::Http(HttpError::from(err));
+                                return Either::right(ready(Err(err)));
+                            }
+                        };
+
+                    match head {
+                        RequestHeadType::Owned(ref mut head) => {
+                            head.headers.insert(header::AUTHORIZATION, value);
+                        }
+                        RequestHeadType::Rc(_, ref mut extra) => {
+                            extra
+                                .get_or_insert_with(HeaderMap::new)
+                                .insert(header::AUTHORIZATION, value);
+                        }
+                    }
+                }
+
+                ConnectRequest::Client(head, body, addr)
+            }
+            req => req,
+        };
+
+        Either::left(self.connector.call(req))
+    }
+}
+
+fn has_authorization(head: &RequestHeadType) -> bool {
+    head.as_ref().headers.contains_key(header::AUTHORIZATION)
+        || head
+            .extra_headers()
+            .map_or(false, |extra| extra.contains_key(header::AUTHORIZATION))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use actix_web::{web, App, HttpRequest, HttpResponse};
+
+    use super::*;
+    use crate::ClientBuilder;
+
+    fn echo_auth_server() -> actix_test::TestServer {
+        actix_test::start(|| {
+            App::new().default_service(web::to(|req: HttpRequest| {
+                let auth = req
+                    .headers()
+                    .get(header::AUTHORIZATION)
+                    .map(|val| val.to_str().unwrap().to_owned())
+                    .unwrap_or_default();
+
+                actix_utils::future::ok::<_, actix_web::Error>(HttpResponse::Ok().body(auth))
+            }))
+        })
+    }
+
+    #[actix_rt::test]
+    async fn adds_token_from_fn() {
+        let srv = echo_auth_server();
+
+        let counter = Rc::new(Cell::new(0));
+        let client = ClientBuilder::new()
+            .wrap(BearerToken::from_fn({
+                let counter = Rc::clone(&counter);
+                move || {
+                    counter.set(counter.get() + 1);
+                    format!("token-{}", counter.get())
+                }
+            }))
+            .finish();
+
+        let mut res = client.get(srv.url("/")).send().await.unwrap();
+        assert_eq!(res.body().await.unwrap(), "Bearer token-1");
+
+        let mut res = client.get(srv.url("/")).send().await.unwrap();
+        assert_eq!(res.body().await.unwrap(), "Bearer token-2");
+    }
+
+    #[actix_rt::test]
+    async fn keeps_existing_authorization() {
+        let srv = echo_auth_server();
+
+        let client = ClientBuilder::new()
+            .wrap(BearerToken::new("token"))
+            .finish();
+
+        let mut res = client
+            .get(srv.url("/"))
+            .insert_header((header::AUTHORIZATION, "Basic abc"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.body().await.unwrap(), "Basic abc");
+    }
+
+    #[actix_rt::test]
+    async fn invalid_token_is_an_error() {
+        let srv = echo_auth_server();
+
+        let client = ClientBuilder::new()
+            .wrap(BearerToken::new("token\r\nx-injected: 1"))
+            .finish();
+
+        let err = client.get(srv.url("/")).send().await.unwrap_err();
+        assert!(matches!(err, SendRequestError::Http(_)));
+    }
+}
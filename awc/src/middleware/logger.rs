@@ -0,0 +1,133 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use actix_http::{Method, Uri};
+use actix_service::Service;
+use futures_core::ready;
+
+use super::Transform;
+use crate::{
+    client::SendRequestError,
+    connect::{ConnectRequest, ConnectResponse},
+};
+
+/// Client middleware for logging outbound requests and their responses.
+///
+/// Uses the `log` crate: completed requests are logged at `info` level with the method, URI,
+/// response status and time taken until the response head was received, and failed requests are
+/// logged at `warn` level along with the error.
+///
+/// # Examples
+/// ```
+/// use awc::{middleware::Logger, Client};
+///
+/// let client = Client::builder().wrap(Logger::new()).finish();
+/// ```
+#[derive(Debug, Default)]
+pub struct Logger {
+    _priv: (),
+}
+
+impl Logger {
+    /// Constructs a `Logger` middleware.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<S> Transform<S, ConnectRequest> for Logger
+where
+    S: Service<ConnectRequest, Response = ConnectResponse, Error = SendRequestError>,
+{
+    type Transform = LoggerService<S>;
+
+    fn new_transform(self, service: S) -> Self::Transform {
+        LoggerService { connector: service }
+    }
+}
+
+pub struct LoggerService<S> {
+    connector: S,
+}
+
+impl<S> Service<ConnectRequest> for LoggerService<S>
+where
+    S: Service<ConnectRequest, Response = ConnectResponse, Error = SendRequestError>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = LoggerFuture<S::Future>;
+
+    actix_service::forward_ready!(connector);
+
+    fn call(&self, req: ConnectRequest) -> Self::Future {
+        let head = match req {
+            ConnectRequest::Client(ref head, ..) => head.as_ref(),
+            ConnectRequest::Tunnel(ref head, ..) => head,
+        };
+
+        let method = head.method.clone();
+        let uri = head.uri.clone();
+
+        LoggerFuture {
+            fut: self.connector.call(req),
+            method,
+            uri,
+            start: Instant::now(),
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    pub struct LoggerFuture<Fut> {
+        #[pin]
+        fut: Fut,
+        method: Method,
+        uri: Uri,
+        start: Instant,
+    }
+}
+
+impl<Fut> Future for LoggerFuture<Fut>
+where
+    Fut: Future<Output = Result<ConnectResponse, SendRequestError>>,
+{
+    type Output = Fut::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.fut.poll(cx));
+
+        let elapsed = this.start.elapsed();
+
+        match res {
+            Ok(ConnectResponse::Client(ref res)) => log::info!(
+                "{} {} {} {:.6}s",
+                this.method,
+                this.uri,
+                res.status().as_u16(),
+                elapsed.as_secs_f64()
+            ),
+            Ok(ConnectResponse::Tunnel(ref head, _)) => log::info!(
+                "{} {} {} {:.6}s (tunnel)",
+                this.method,
+                this.uri,
+                head.status.as_u16(),
+                elapsed.as_secs_f64()
+            ),
+            Err(ref err) => log::warn!(
+                "{} {} failed after {:.6}s: {}",
+                this.method,
+                this.uri,
+                elapsed.as_secs_f64(),
+                err
+            ),
+        }
+
+        Poll::Ready(res)
+    }
+}
@@ -1,6 +1,12 @@
+mod bearer;
+mod logger;
 mod redirect;
+mod retry;
 
+pub use self::bearer::BearerToken;
+pub use self::logger::Logger;
 pub use self::redirect::Redirect;
+pub use self::retry::Retry;
 
 use std::marker::PhantomData;
 
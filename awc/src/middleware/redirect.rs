@@ -20,8 +20,17 @@ use crate::{
     ClientResponse,
 };
 
+/// Client middleware for following redirect responses.
+///
+/// Responses with status 301, 302 and 303 are followed using `GET` (or `HEAD` for `HEAD` requests)
+/// without a body. Responses with status 307 and 308 are followed using the original method and
+/// body; streaming bodies cannot be replayed and are sent as empty bodies instead.
+///
+/// When a redirect leads to a different origin (scheme, host and port), the `Authorization`,
+/// `Proxy-Authorization` and `Cookie` headers are removed from the redirected request.
 pub struct Redirect {
     max_redirect_times: u8,
+    same_origin_only: bool,
 }
 
 impl Default for Redirect {
@@ -34,13 +43,26 @@ impl Redirect {
     pub fn new() -> Self {
         Self {
             max_redirect_times: 10,
+            same_origin_only: false,
         }
     }
 
+    /// Set max number of redirects that are followed for a single request.
+    ///
+    /// Max redirects is set to 10 by default.
     pub fn max_redirect_times(mut self, times: u8) -> Self {
         self.max_redirect_times = times;
         self
     }
+
+    /// Only follow redirects to the same origin (scheme, host and port) as the current request.
+    ///
+    /// Redirect responses pointing to another origin are returned to the caller as-is. Disabled
+    /// by default.
+    pub fn same_origin_only(mut self, enabled: bool) -> Self {
+        self.same_origin_only = enabled;
+        self
+    }
 }
 
 impl<S> Transform<S, ConnectRequest> for Redirect
@@ -52,6 +74,7 @@ where
     fn new_transform(self, service: S) -> Self::Transform {
         RedirectService {
             max_redirect_times: self.max_redirect_times,
+            same_origin_only: self.same_origin_only,
             connector: Rc::new(service),
         }
     }
@@ -59,6 +82,7 @@ where
 
 pub struct RedirectService<S> {
     max_redirect_times: u8,
+    same_origin_only: bool,
     connector: Rc<S>,
 }
 
@@ -81,6 +105,7 @@ where
             ConnectRequest::Client(head, body, addr) => {
                 let connector = self.connector.clone();
                 let max_redirect_times = self.max_redirect_times;
+                let same_origin_only = self.same_origin_only;
 
                 // backup the uri and method for reuse schema and authority.
                 let (uri, method, headers) = match head {
@@ -102,6 +127,7 @@ where
                 RedirectServiceFuture::Client {
                     fut,
                     max_redirect_times,
+                    same_origin_only,
                     uri: Some(uri),
                     method: Some(method),
                     headers: Some(headers),
@@ -126,6 +152,7 @@ pin_project_lite::pin_project! {
             #[pin]
             fut: S::Future,
             max_redirect_times: u8,
+            same_origin_only: bool,
             uri: Option<Uri>,
             method: Option<Method>,
             headers: Option<header::HeaderMap>,
@@ -148,6 +175,7 @@ where
             RedirectServiceProj::Client {
                 fut,
                 max_redirect_times,
+                same_origin_only,
                 uri,
                 method,
                 headers,
@@ -172,9 +200,14 @@ where
                         // rebuild uri from the location header value.
                         let next_uri = build_next_uri(&res, &prev_uri)?;
 
+                        if *same_origin_only && !is_same_origin(&prev_uri, &next_uri) {
+                            return Poll::Ready(Ok(ConnectResponse::Client(res)));
+                        }
+
                         // take ownership of states that could be reused
                         let addr = addr.take();
                         let connector = connector.take();
+                        let same_origin_only = *same_origin_only;
 
                         // reset method
                         let method = if reuse_body {
@@ -227,6 +260,7 @@ where
                         self.set(RedirectServiceFuture::Client {
                             fut,
                             max_redirect_times,
+                            same_origin_only,
                             uri: Some(next_uri),
                             method: Some(method),
                             headers: Some(headers),
@@ -276,11 +310,14 @@ fn build_next_uri(res: &ClientResponse, prev_uri: &Uri) -> Result<Uri, SendReque
     Ok(uri)
 }
 
+fn is_same_origin(prev_uri: &Uri, next_uri: &Uri) -> bool {
+    next_uri.host() == prev_uri.host()
+        && next_uri.port() == prev_uri.port()
+        && next_uri.scheme() == prev_uri.scheme()
+}
+
 fn remove_sensitive_headers(headers: &mut header::HeaderMap, prev_uri: &Uri, next_uri: &Uri) {
-    if next_uri.host() != prev_uri.host()
-        || next_uri.port() != prev_uri.port()
-        || next_uri.scheme() != prev_uri.scheme()
-    {
+    if !is_same_origin(prev_uri, next_uri) {
         headers.remove(header::COOKIE);
         headers.remove(header::AUTHORIZATION);
         headers.remove(header::PROXY_AUTHORIZATION);
@@ -588,6 +625,46 @@ mod tests {
         assert_eq!(res.status().as_u16(), 200);
     }
 
+    #[actix_rt::test]
+    async fn test_redirect_same_origin_only() {
+        let srv2 = actix_test::start(|| {
+            App::new().service(web::resource("/").route(web::to(HttpResponse::Ok)))
+        });
+        let srv2_port: u16 = srv2.addr().port();
+
+        let srv1 = actix_test::start(move || {
+            async fn cross(req: HttpRequest) -> HttpResponse {
+                let port = *req.app_data::<u16>().unwrap();
+                HttpResponse::Found()
+                    .append_header(("location", format!("http://localhost:{}/", port)))
+                    .finish()
+            }
+
+            async fn same() -> HttpResponse {
+                HttpResponse::Found()
+                    .append_header(("location", "/test"))
+                    .finish()
+            }
+
+            App::new()
+                .app_data(srv2_port)
+                .service(web::resource("/cross").route(web::to(cross)))
+                .service(web::resource("/same").route(web::to(same)))
+                .service(web::resource("/test").route(web::to(HttpResponse::Ok)))
+        });
+
+        let client = ClientBuilder::new()
+            .disable_redirects()
+            .wrap(Redirect::new().same_origin_only(true))
+            .finish();
+
+        let res = client.get(srv1.url("/same")).send().await.unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+
+        let res = client.get(srv1.url("/cross")).send().await.unwrap();
+        assert_eq!(res.status().as_u16(), 302);
+    }
+
     #[actix_rt::test]
     async fn test_remove_sensitive_headers() {
         fn gen_headers() -> header::HeaderMap {
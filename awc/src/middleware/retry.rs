@@ -0,0 +1,293 @@
+use std::{rc::Rc, time::Duration};
+
+use actix_http::{Method, RequestHeadType, StatusCode};
+use actix_service::Service;
+use futures_core::future::LocalBoxFuture;
+
+use super::Transform;
+use crate::{
+    any_body::AnyBody,
+    backoff,
+    client::SendRequestError,
+    connect::{ConnectRequest, ConnectResponse},
+};
+
+/// Client middleware for retrying failed requests with exponential backoff.
+///
+/// Requests are retried when connecting to the host or sending the request fails, or when the
+/// response has one of the retryable status codes (502, 503 and 504 by default).
+///
+/// Only requests with idempotent methods (`GET`, `HEAD`, `PUT`, `DELETE`, `OPTIONS` and `TRACE`)
+/// and in-memory (or empty) bodies are retried, since other requests cannot be safely replayed.
+///
+/// The delay before the `n`th retry is `base_delay * 2^(n - 1)`, capped at `max_delay`, with
+/// random jitter of up to half the delay subtracted so that many clients do not retry in lockstep.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use awc::{middleware::Retry, Client};
+///
+/// let client = Client::builder()
+///     .wrap(
+///         Retry::new()
+///             .max_retries(5)
+///             .backoff(Duration::from_millis(50), Duration::from_secs(2)),
+///     )
+///     .finish();
+/// ```
+pub struct Retry {
+    max_retries: u8,
+    base_delay: Duration,
+    max_delay: Duration,
+    statuses: Vec<StatusCode>,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Retry {
+    /// Constructs a `Retry` middleware that retries up to 3 times, starting with a 100ms delay.
+    pub fn new() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            statuses: vec![
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+        }
+    }
+
+    /// Set max number of retries for a single request.
+    ///
+    /// Max retries is set to 3 by default.
+    pub fn max_retries(mut self, retries: u8) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Set the delay before the first retry and the upper bound for delays between retries.
+    ///
+    /// Defaults to a base delay of 100ms and a max delay of 10s.
+    pub fn backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Set the response status codes that cause a request to be retried.
+    ///
+    /// Defaults to 502, 503 and 504. Pass an empty list to only retry on connection errors.
+    pub fn statuses(mut self, statuses: impl IntoIterator<Item = StatusCode>) -> Self {
+        self.statuses = statuses.into_iter().collect();
+        self
+    }
+}
+
+impl<S> Transform<S, ConnectRequest> for Retry
+where
+    S: Service<ConnectRequest, Response = ConnectResponse, Error = SendRequestError> + 'static,
+{
+    type Transform = RetryService<S>;
+
+    fn new_transform(self, service: S) -> Self::Transform {
+        RetryService {
+            config: Rc::new(self),
+            connector: Rc::new(service),
+        }
+    }
+}
+
+pub struct RetryService<S> {
+    config: Rc<Retry>,
+    connector: Rc<S>,
+}
+
+impl<S> Service<ConnectRequest> for RetryService<S>
+where
+    S: Service<ConnectRequest, Response = ConnectResponse, Error = SendRequestError> + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = LocalBoxFuture<'static, Result<ConnectResponse, SendRequestError>>;
+
+    actix_service::forward_ready!(connector);
+
+    fn call(&self, req: ConnectRequest) -> Self::Future {
+        let (head, body, addr) = match req {
+            ConnectRequest::Client(head, body, addr)
+                if self.config.max_retries > 0
+                    && is_idempotent(&head.as_ref().method)
+                    && !matches!(body, AnyBody::Body { .. }) =>
+            {
+                (head, body, addr)
+            }
+            req => return Box::pin(self.connector.call(req)),
+        };
+
+        let config = Rc::clone(&self.config);
+        let connector = Rc::clone(&self.connector);
+
+        Box::pin(async move {
+            let mut retries = 0;
+
+            loop {
+                let req = ConnectRequest::Client(clone_head(&head), clone_body(&body), addr);
+                let res = connector.call(req).await;
+
+                let retryable = match res {
+                    Ok(ConnectResponse::Client(ref res)) => {
+                        config.statuses.contains(&res.status())
+                    }
+                    Ok(_) => false,
+                    Err(ref err) => is_retryable_error(err),
+                };
+
+                if !retryable || retries >= config.max_retries {
+                    return res;
+                }
+
+                actix_rt::time::sleep(delay(&config, retries)).await;
+                retries += 1;
+            }
+        })
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET
+            | Method::HEAD
+            | Method::PUT
+            | Method::DELETE
+            | Method::OPTIONS
+            | Method::TRACE
+    )
+}
+
+fn is_retryable_error(err: &SendRequestError) -> bool {
+    matches!(
+        err,
+        SendRequestError::Connect(_) | SendRequestError::Send(_) | SendRequestError::H2(_)
+    )
+}
+
+fn clone_head(head: &RequestHeadType) -> RequestHeadType {
+    match head {
+        RequestHeadType::Owned(head) => RequestHeadType::Owned(head.clone()),
+        RequestHeadType::Rc(head, extra) => RequestHeadType::Rc(Rc::clone(head), extra.clone()),
+    }
+}
+
+fn clone_body(body: &AnyBody) -> AnyBody {
+    match body {
+        AnyBody::None => AnyBody::None,
+        AnyBody::Bytes { body } => AnyBody::Bytes { body: body.clone() },
+        AnyBody::Body { .. } => unreachable!("streaming bodies are not retried"),
+    }
+}
+
+/// Returns the delay before the retry following `retries` previous retries.
+fn delay(config: &Retry, retries: u8) -> Duration {
+    backoff::jittered_delay(config.base_delay, config.max_delay, u32::from(retries))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use actix_web::{web, App, HttpResponse};
+
+    use super::*;
+    use crate::ClientBuilder;
+
+    fn flaky_server(failures: usize) -> (actix_test::TestServer, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls2 = Arc::clone(&calls);
+
+        let srv = actix_test::start(move || {
+            let calls = Arc::clone(&calls2);
+
+            App::new().default_service(web::to(move || {
+                let prev = calls.fetch_add(1, Ordering::SeqCst);
+
+                let res = if prev < failures {
+                    HttpResponse::ServiceUnavailable().finish()
+                } else {
+                    HttpResponse::Ok().finish()
+                };
+
+                actix_utils::future::ok::<_, actix_web::Error>(res)
+            }))
+        });
+
+        (srv, calls)
+    }
+
+    fn client(max_retries: u8) -> crate::Client {
+        ClientBuilder::new()
+            .wrap(
+                Retry::new()
+                    .max_retries(max_retries)
+                    .backoff(Duration::from_millis(1), Duration::from_millis(5)),
+            )
+            .finish()
+    }
+
+    #[actix_rt::test]
+    async fn retries_idempotent_requests() {
+        let (srv, calls) = flaky_server(2);
+
+        let res = client(3).get(srv.url("/")).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let res = client(1).put(srv.url("/")).send_body("data").await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[actix_rt::test]
+    async fn stops_after_max_retries() {
+        let (srv, calls) = flaky_server(5);
+
+        let res = client(2).get(srv.url("/")).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[actix_rt::test]
+    async fn does_not_retry_non_idempotent_requests() {
+        let (srv, calls) = flaky_server(1);
+
+        let res = client(3).post(srv.url("/")).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn backoff_delay() {
+        let config = Retry::new().backoff(Duration::from_millis(100), Duration::from_secs(1));
+
+        for _ in 0..10 {
+            let first = delay(&config, 0);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+
+            let third = delay(&config, 2);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+
+            let capped = delay(&config, 200);
+            assert!(capped >= Duration::from_millis(500) && capped <= Duration::from_secs(1));
+        }
+    }
+}
@@ -35,6 +35,10 @@ use actix_service::Service as _;
 
 pub use actix_http::ws::{CloseCode, CloseReason, Codec, Frame, Message};
 
+mod reconnect;
+
+pub use self::reconnect::{Event, Reconnect, ReconnectingClient};
+
 use crate::{
     client::ClientConfig,
     connect::{BoxedSocket, ConnectRequest},
@@ -247,6 +251,30 @@ impl WebsocketsRequest {
         self.header(AUTHORIZATION, format!("Bearer {}", token))
     }
 
+    /// Complete request construction and return a client that connects to the WebSocket server
+    /// and reconnects whenever the connection is lost, according to `policy`.
+    ///
+    /// See [`ReconnectingClient`] for details.
+    pub fn reconnecting(self, policy: Reconnect) -> ReconnectingClient {
+        ReconnectingClient::new(self, policy)
+    }
+
+    /// Copies the request for another connection attempt, without the construction error.
+    fn duplicate(&self) -> Self {
+        WebsocketsRequest {
+            head: self.head.clone(),
+            err: None,
+            origin: self.origin.clone(),
+            protocols: self.protocols.clone(),
+            addr: self.addr,
+            max_size: self.max_size,
+            server_mode: self.server_mode,
+            config: self.config.clone(),
+            #[cfg(feature = "cookies")]
+            cookies: self.cookies.clone(),
+        }
+    }
+
     /// Complete request construction and connect to a WebSocket server.
     pub async fn connect(
         mut self,
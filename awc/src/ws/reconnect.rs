@@ -0,0 +1,501 @@
+//! Automatically reconnecting WebSocket client.
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use actix_codec::Framed;
+use actix_rt::time::{sleep, Sleep};
+use futures_core::{future::LocalBoxFuture, ready, Stream};
+use futures_util::sink::Sink;
+
+use super::{Codec, Frame, Message, WebsocketsRequest};
+use crate::{
+    backoff,
+    connect::BoxedSocket,
+    error::{SendRequestError, WsClientError},
+    ClientResponse,
+};
+
+type Connection = Framed<BoxedSocket, Codec>;
+
+/// Reconnection policy of a [`ReconnectingClient`].
+///
+/// The delay before each attempt doubles from the base delay up to the maximum delay, and is
+/// randomly shortened by up to half so that many clients losing their connection at once do not
+/// reconnect in lockstep. The delay is reset once a connection is established.
+#[derive(Debug, Clone)]
+pub struct Reconnect {
+    base_delay: Duration,
+    max_delay: Duration,
+    max_attempts: Option<u32>,
+    buffer: usize,
+}
+
+impl Default for Reconnect {
+    fn default() -> Self {
+        Reconnect {
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            max_attempts: None,
+            buffer: 64,
+        }
+    }
+}
+
+impl Reconnect {
+    /// Constructs the default policy, which retries forever.
+    pub fn new() -> Self {
+        Reconnect::default()
+    }
+
+    /// Sets the delay before the first attempt and the upper bound for delays between attempts.
+    ///
+    /// Defaults to a base delay of 500ms and a max delay of 30s.
+    pub fn backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Sets the number of consecutive failed attempts after which the client gives up. By
+    /// default, the client retries forever.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = Some(attempts);
+        self
+    }
+
+    /// Sets the number of outbound messages buffered while disconnected. Defaults to 64.
+    ///
+    /// Once the buffer is full, sending waits until the connection is re-established.
+    pub fn buffer(mut self, messages: usize) -> Self {
+        self.buffer = messages;
+        self
+    }
+
+    /// Returns the delay before the given attempt, counting from 1.
+    fn delay(&self, attempt: u32) -> Duration {
+        backoff::jittered_delay(self.base_delay, self.max_delay, attempt.saturating_sub(1))
+    }
+}
+
+/// Event yielded by a [`ReconnectingClient`].
+#[derive(Debug)]
+pub enum Event {
+    /// The handshake completed, either initially or after reconnecting.
+    Connected(ClientResponse),
+
+    /// A frame was received.
+    Frame(Frame),
+
+    /// The connection was lost.
+    Disconnected,
+
+    /// The connection is retried after `delay`; `attempt` counts from 1 since the last successful
+    /// connection.
+    Reconnecting { attempt: u32, delay: Duration },
+}
+
+enum State {
+    Connecting(LocalBoxFuture<'static, Result<(ClientResponse, Connection), WsClientError>>),
+    Open(Connection),
+    Waiting(Pin<Box<Sleep>>),
+    Failed(WsClientError),
+    Closed,
+}
+
+/// WebSocket client that transparently reconnects when its connection is lost.
+///
+/// Created by [`WebsocketsRequest::reconnecting`]. The handshake is repeated on every attempt, so
+/// the headers and protocols of the original request are sent again.
+///
+/// As a [`Stream`], the client yields received frames along with connection state [`Event`]s. It
+/// ends with an error when the policy gives up reconnecting, or without one once a close message
+/// sent by the application has been answered. A close frame sent by the server is yielded like
+/// any other frame; the client reconnects afterwards unless the application replies with a close
+/// message.
+///
+/// As a [`Sink`], it buffers messages sent while disconnected and writes them when the connection
+/// is re-established. Messages written to a connection that is then lost are not resent.
+///
+/// Connection attempts are driven by polling either the stream or the sink.
+///
+/// # Examples
+/// ```no_run
+/// use awc::{ws, Client};
+/// use futures_util::{sink::SinkExt as _, stream::StreamExt as _};
+///
+/// # #[actix_rt::main]
+/// # async fn main() {
+/// let mut client = Client::new()
+///     .ws("ws://example.com/feed")
+///     .reconnecting(ws::Reconnect::new().max_attempts(10));
+///
+/// while let Some(event) = client.next().await {
+///     match event.unwrap() {
+///         ws::Event::Connected(_) => {
+///             client.send(ws::Message::Text("subscribe".into())).await.unwrap();
+///         }
+///         ws::Event::Frame(frame) => println!("received {:?}", frame),
+///         _ => {}
+///     }
+/// }
+/// # }
+/// ```
+pub struct ReconnectingClient {
+    request: WebsocketsRequest,
+    policy: Reconnect,
+    state: State,
+    attempt: u32,
+    events: VecDeque<Event>,
+    buffer: VecDeque<Message>,
+    closing: bool,
+    stream_waker: Option<Waker>,
+    sink_waker: Option<Waker>,
+}
+
+impl ReconnectingClient {
+    pub(super) fn new(mut request: WebsocketsRequest, policy: Reconnect) -> Self {
+        let state = match request.err.take() {
+            Some(err) => State::Failed(err.into()),
+            None => State::Connecting(Box::pin(request.duplicate().connect())),
+        };
+
+        ReconnectingClient {
+            request,
+            policy,
+            state,
+            attempt: 0,
+            events: VecDeque::new(),
+            buffer: VecDeque::new(),
+            closing: false,
+            stream_waker: None,
+            sink_waker: None,
+        }
+    }
+
+    /// Returns true if the client is currently connected.
+    pub fn is_connected(&self) -> bool {
+        matches!(self.state, State::Open(_))
+    }
+
+    /// Advances connection attempts until a connection is open.
+    fn poll_connected(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), WsClientError>> {
+        loop {
+            match self.state {
+                State::Open(_) => return Poll::Ready(Ok(())),
+
+                State::Connecting(ref mut fut) => match ready!(fut.as_mut().poll(cx)) {
+                    Ok((res, conn)) => {
+                        self.attempt = 0;
+                        self.state = State::Open(conn);
+                        self.push_event(Event::Connected(res));
+                    }
+                    Err(err) => {
+                        log::debug!("WebSocket reconnection failed: {}", err);
+                        self.reconnect(err);
+                    }
+                },
+
+                State::Waiting(ref mut delay) => {
+                    ready!(delay.as_mut().poll(cx));
+                    self.state =
+                        State::Connecting(Box::pin(self.request.duplicate().connect()));
+                }
+
+                State::Failed(_) => {
+                    let err = match std::mem::replace(&mut self.state, State::Closed) {
+                        State::Failed(err) => err,
+                        _ => unreachable!(),
+                    };
+
+                    self.buffer.clear();
+                    self.wake();
+                    return Poll::Ready(Err(err));
+                }
+
+                State::Closed => return Poll::Ready(Err(closed())),
+            }
+        }
+    }
+
+    /// Writes buffered messages to the open connection and flushes it.
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let conn = match self.state {
+            State::Open(ref mut conn) => conn,
+            _ => return Poll::Ready(()),
+        };
+
+        let res = loop {
+            if self.buffer.is_empty() {
+                break ready!(Pin::new(&mut *conn).poll_flush(cx));
+            }
+
+            if let Err(err) = ready!(Pin::new(&mut *conn).poll_ready(cx)) {
+                break Err(err);
+            }
+
+            let msg = self.buffer.pop_front().unwrap();
+            if let Err(err) = Pin::new(&mut *conn).start_send(msg) {
+                break Err(err);
+            }
+        };
+
+        if let Err(err) = res {
+            self.lost(err.into());
+        }
+
+        Poll::Ready(())
+    }
+
+    fn lost(&mut self, err: WsClientError) {
+        if self.closing {
+            self.state = State::Closed;
+            self.wake();
+            return;
+        }
+
+        log::debug!("WebSocket connection lost: {}", err);
+        self.push_event(Event::Disconnected);
+        self.reconnect(err);
+    }
+
+    fn reconnect(&mut self, err: WsClientError) {
+        self.attempt += 1;
+
+        let fatal = matches!(
+            err,
+            WsClientError::SendRequest(SendRequestError::Url(_) | SendRequestError::Http(_))
+        );
+
+        if fatal || matches!(self.policy.max_attempts, Some(max) if self.attempt > max) {
+            self.state = State::Failed(err);
+            self.wake();
+            return;
+        }
+
+        let delay = self.policy.delay(self.attempt);
+        self.state = State::Waiting(Box::pin(sleep(delay)));
+        self.push_event(Event::Reconnecting {
+            attempt: self.attempt,
+            delay,
+        });
+    }
+
+    fn push_event(&mut self, event: Event) {
+        self.events.push_back(event);
+        self.wake();
+    }
+
+    /// Wakes the tasks waiting on the stream and sink, in case they have been split.
+    fn wake(&mut self) {
+        if let Some(waker) = self.stream_waker.take() {
+            waker.wake();
+        }
+
+        if let Some(waker) = self.sink_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Stream for ReconnectingClient {
+    type Item = Result<Event, WsClientError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        this.stream_waker = None;
+
+        loop {
+            if let Some(event) = this.events.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+
+            match this.state {
+                State::Closed => return Poll::Ready(None),
+                State::Open(_) => {}
+                _ => match this.poll_connected(cx) {
+                    Poll::Ready(Ok(())) => continue,
+                    Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err))),
+                    Poll::Pending => {
+                        this.stream_waker = Some(cx.waker().clone());
+                        return Poll::Pending;
+                    }
+                },
+            }
+
+            // write buffered messages without waiting for them to be flushed
+            let _ = this.poll_drain(cx);
+
+            let conn = match this.state {
+                State::Open(ref mut conn) => conn,
+                _ => continue,
+            };
+
+            match Pin::new(conn).poll_next(cx) {
+                Poll::Ready(Some(Ok(frame))) => {
+                    return Poll::Ready(Some(Ok(Event::Frame(frame))))
+                }
+                Poll::Ready(Some(Err(err))) => this.lost(err.into()),
+                Poll::Ready(None) => this.lost(io_error(io::ErrorKind::UnexpectedEof)),
+                Poll::Pending => {
+                    this.stream_waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
+
+impl Sink<Message> for ReconnectingClient {
+    type Error = WsClientError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        this.sink_waker = None;
+
+        let res = loop {
+            match this.state {
+                State::Closed => break Poll::Ready(Err(closed())),
+
+                State::Open(_) => {
+                    if this.buffer.is_empty() {
+                        break Poll::Ready(Ok(()));
+                    }
+
+                    if this.poll_drain(cx).is_pending() {
+                        break Poll::Pending;
+                    }
+                }
+
+                _ => {
+                    if this.buffer.len() < this.policy.buffer {
+                        break Poll::Ready(Ok(()));
+                    }
+
+                    match this.poll_connected(cx) {
+                        Poll::Ready(Ok(())) => {}
+                        Poll::Ready(Err(err)) => break Poll::Ready(Err(err)),
+                        Poll::Pending => break Poll::Pending,
+                    }
+                }
+            }
+        };
+
+        if res.is_pending() {
+            this.sink_waker = Some(cx.waker().clone());
+        }
+
+        res
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+
+        if let State::Closed = this.state {
+            return Err(closed());
+        }
+
+        if let Message::Close(_) = item {
+            this.closing = true;
+
+            // there is no connection to close
+            if !this.is_connected() {
+                this.state = State::Closed;
+                this.buffer.clear();
+                this.wake();
+                return Ok(());
+            }
+        }
+
+        this.buffer.push_back(item);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        this.sink_waker = None;
+
+        let res = loop {
+            match this.state {
+                State::Closed if this.buffer.is_empty() => break Poll::Ready(Ok(())),
+                State::Closed => break Poll::Ready(Err(closed())),
+
+                State::Open(_) => {
+                    if this.poll_drain(cx).is_pending() {
+                        break Poll::Pending;
+                    }
+
+                    if this.is_connected() {
+                        break Poll::Ready(Ok(()));
+                    }
+                }
+
+                _ => match this.poll_connected(cx) {
+                    Poll::Ready(Ok(())) => {}
+                    Poll::Ready(Err(err)) => break Poll::Ready(Err(err)),
+                    Poll::Pending => break Poll::Pending,
+                },
+            }
+        };
+
+        if res.is_pending() {
+            this.sink_waker = Some(cx.waker().clone());
+        }
+
+        res
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.closing = true;
+        ready!(self.as_mut().poll_flush(cx))?;
+
+        let this = self.get_mut();
+
+        if let State::Open(ref mut conn) = this.state {
+            ready!(Pin::new(conn).poll_close(cx))?;
+            this.state = State::Closed;
+            this.wake();
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn closed() -> WsClientError {
+    io_error(io::ErrorKind::NotConnected)
+}
+
+fn io_error(kind: io::ErrorKind) -> WsClientError {
+    WsClientError::Protocol(io::Error::from(kind).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff() {
+        let policy =
+            Reconnect::new().backoff(Duration::from_millis(100), Duration::from_secs(1));
+
+        for _ in 0..100 {
+            let delay = policy.delay(1);
+            assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+
+            let delay = policy.delay(3);
+            assert!(delay >= Duration::from_millis(200) && delay <= Duration::from_millis(400));
+
+            let delay = policy.delay(40);
+            assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_secs(1));
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use actix_rt::net::{TcpListener, TcpStream};
+use actix_web::{
+    http::{StatusCode, Uri},
+    web, App,
+};
+use awc::{Client, Connector, Proxy};
+use tokio::io::{copy_bidirectional, AsyncReadExt as _, AsyncWriteExt as _};
+
+/// Starts a minimal proxy server that handles each connection with `handler`.
+///
+/// Returns the proxy address and a counter of accepted connections.
+async fn start_proxy<F, Fut>(handler: F) -> (SocketAddr, Arc<AtomicUsize>)
+where
+    F: Fn(TcpStream) -> Fut + 'static,
+    Fut: std::future::Future<Output = ()> + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let accepted = Arc::new(AtomicUsize::new(0));
+    let accepted2 = Arc::clone(&accepted);
+
+    actix_rt::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            accepted2.fetch_add(1, Ordering::SeqCst);
+            actix_rt::spawn(handler(stream));
+        }
+    });
+
+    (addr, accepted)
+}
+
+async fn http_proxy(mut client: TcpStream) {
+    let mut head = Vec::new();
+
+    while !head.ends_with(b"\r\n\r\n") {
+        head.push(client.read_u8().await.unwrap());
+    }
+
+    let head = String::from_utf8(head).unwrap();
+    let (request_line, headers) = head.split_once("\r\n").unwrap();
+
+    // credentials are "user:pass"
+    if !headers.contains("proxy-authorization: Basic dXNlcjpwYXNz\r\n") {
+        client
+            .write_all(
+                b"HTTP/1.1 407 Proxy Authentication Required\r\ncontent-length: 0\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        return;
+    }
+
+    // plain HTTP requests are sent with the full URI as request target
+    let mut parts = request_line.split(' ');
+    let (method, target, version) = (parts.next(), parts.next(), parts.next());
+    let uri = target.unwrap().parse::<Uri>().unwrap();
+    assert_eq!(uri.scheme_str(), Some("http"));
+
+    let mut upstream = TcpStream::connect(uri.authority().unwrap().as_str())
+        .await
+        .unwrap();
+
+    let headers = headers
+        .split_inclusive("\r\n")
+        .filter(|line| !line.starts_with("proxy-authorization:"))
+        .collect::<String>();
+
+    let req = format!(
+        "{} {} {}\r\n{}",
+        method.unwrap(),
+        uri.path(),
+        version.unwrap(),
+        headers
+    );
+    upstream.write_all(req.as_bytes()).await.unwrap();
+
+    let _ = copy_bidirectional(&mut client, &mut upstream).await;
+}
+
+/// SOCKS5 proxy expecting host names to have been resolved by the client, or by the proxy if
+/// `remote_dns` is set.
+async fn socks5_proxy(mut client: TcpStream, remote_dns: bool) {
+    let mut buf = [0; 2];
+    client.read_exact(&mut buf).await.unwrap();
+    let mut methods = vec![0; buf[1] as usize];
+    client.read_exact(&mut methods).await.unwrap();
+    assert!(methods.contains(&0x00));
+    client.write_all(&[0x05, 0x00]).await.unwrap();
+
+    let mut req = [0; 4];
+    client.read_exact(&mut req).await.unwrap();
+    assert_eq!(req[..3], [0x05, 0x01, 0x00]);
+
+    let host = match req[3] {
+        0x01 if !remote_dns => {
+            let mut ip = [0; 4];
+            client.read_exact(&mut ip).await.unwrap();
+            Ipv4Addr::from(ip).to_string()
+        }
+        0x04 if !remote_dns => {
+            let mut ip = [0; 16];
+            client.read_exact(&mut ip).await.unwrap();
+            assert!(Ipv6Addr::from(ip).is_loopback());
+
+            // the test server only listens on IPv4
+            "127.0.0.1".to_owned()
+        }
+        0x03 if remote_dns => {
+            let len = client.read_u8().await.unwrap();
+            let mut host = vec![0; len as usize];
+            client.read_exact(&mut host).await.unwrap();
+            String::from_utf8(host).unwrap()
+        }
+        atyp => panic!("unexpected address type {}", atyp),
+    };
+    let port = client.read_u16().await.unwrap();
+
+    let mut upstream = TcpStream::connect((host.as_str(), port)).await.unwrap();
+
+    client
+        .write_all(&[0x05, 0x00, 0x00, 0x01, 127, 0, 0, 1, 0, 0])
+        .await
+        .unwrap();
+
+    let _ = copy_bidirectional(&mut client, &mut upstream).await;
+}
+
+fn test_server() -> actix_test::TestServer {
+    actix_test::start(|| App::new().service(web::resource("/").to(|| async { "proxied" })))
+}
+
+#[actix_rt::test]
+async fn http_proxy_with_auth() {
+    let srv = test_server();
+    let (addr, accepted) = start_proxy(http_proxy).await;
+
+    let proxy = Proxy::new(&format!("http://user:pass@{}", addr)).unwrap();
+    let client = Client::builder()
+        .connector(Connector::new().proxy(proxy))
+        .finish();
+
+    let mut res = client.get(srv.url("/")).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.body().await.unwrap(), "proxied");
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+    // wrong credentials are refused by the proxy
+    let proxy = Proxy::new(&format!("http://{}", addr))
+        .unwrap()
+        .basic_auth("user", "wrong");
+    let client = Client::builder()
+        .connector(Connector::new().proxy(proxy))
+        .finish();
+
+    let res = client.get(srv.url("/")).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::PROXY_AUTHENTICATION_REQUIRED);
+}
+
+#[actix_rt::test]
+async fn socks5_proxy_connect() {
+    let srv = test_server();
+
+    // host names are resolved locally with `socks5` and by the proxy with `socks5h`
+    for (scheme, remote_dns) in [("socks5", false), ("socks5h", true)] {
+        let (addr, accepted) = start_proxy(move |io| socks5_proxy(io, remote_dns)).await;
+
+        let proxy = Proxy::new(&format!("{}://{}", scheme, addr)).unwrap();
+        let client = Client::builder()
+            .connector(Connector::new().proxy(proxy))
+            .finish();
+
+        let mut res = client.get(srv.url("/")).send().await.unwrap();
+        assert!(res.status().is_success());
+        assert_eq!(res.body().await.unwrap(), "proxied");
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+}
+
+#[actix_rt::test]
+async fn no_proxy_hosts_connect_directly() {
+    let srv = test_server();
+    let (addr, accepted) = start_proxy(http_proxy).await;
+
+    let proxy = Proxy::new(&format!("http://{}", addr))
+        .unwrap()
+        .no_proxy(["localhost"]);
+    let client = Client::builder()
+        .connector(Connector::new().proxy(proxy))
+        .finish();
+
+    let res = client.get(srv.url("/")).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(accepted.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,51 @@
+#![cfg(unix)]
+
+use actix_http::ws;
+use actix_web::{http::header, web, App, HttpRequest, HttpResponse, HttpServer};
+use awc::{Client, Connector};
+
+async fn ws_handshake(req: HttpRequest) -> HttpResponse {
+    let key = req.headers().get(header::SEC_WEBSOCKET_KEY).unwrap();
+    let accept = ws::hash_key(key.as_bytes());
+
+    HttpResponse::SwitchingProtocols()
+        .upgrade("websocket")
+        .insert_header((header::SEC_WEBSOCKET_ACCEPT, &accept[..]))
+        .finish()
+}
+
+#[actix_rt::test]
+async fn client_over_uds() {
+    let dir = std::env::temp_dir().join(format!("awc-uds-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("server.sock");
+    let _ = std::fs::remove_file(&path);
+
+    let srv = HttpServer::new(|| {
+        App::new()
+            .route("/", web::get().to(|| async { "over uds" }))
+            .route("/ws", web::get().to(ws_handshake))
+    })
+    .workers(1)
+    .disable_signals()
+    .bind_uds(&path)
+    .unwrap()
+    .run();
+
+    let handle = srv.handle();
+    actix_rt::spawn(srv);
+
+    let client = Client::builder()
+        .connector(Connector::new().uds(&path))
+        .finish();
+
+    let mut res = client.get("http://localhost/").send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.body().await.unwrap(), "over uds");
+
+    let (res, _framed) = client.ws("http://localhost/ws").connect().await.unwrap();
+    assert_eq!(res.status().as_u16(), 101);
+
+    handle.stop(true).await;
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -1,9 +1,10 @@
-use std::io;
+use std::{io, time::Duration};
 
 use actix_codec::Framed;
 use actix_http::{body::BodySize, h1, ws, Error, HttpService, Request, Response};
 use actix_http_test::test_server;
 use actix_utils::future::ok;
+use awc::ws::{Event, Reconnect};
 use bytes::Bytes;
 use futures_util::{SinkExt as _, StreamExt as _};
 
@@ -66,3 +67,106 @@ async fn test_simple() {
     let item = framed.next().await.unwrap().unwrap();
     assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
 }
+
+#[actix_rt::test]
+async fn test_reconnecting() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .upgrade(|(req, mut framed): (Request, Framed<_, _>)| async move {
+                let res = ws::handshake_response(req.head()).finish();
+                framed
+                    .send(h1::Message::Item((res.drop_body(), BodySize::None)))
+                    .await
+                    .unwrap();
+
+                // echo a single text message, then drop the connection
+                let mut framed = framed.replace_codec(ws::Codec::new());
+                if let Some(Ok(ws::Frame::Text(text))) = framed.next().await {
+                    let text = String::from_utf8(text.to_vec()).unwrap();
+                    framed.send(ws::Message::Text(text.into())).await.unwrap();
+                }
+
+                Ok::<_, Error>(())
+            })
+            .finish(|_| ok::<_, Error>(Response::not_found()))
+            .tcp()
+    })
+    .await;
+
+    let mut client = awc::Client::new().ws(srv.url("/")).reconnecting(
+        Reconnect::new().backoff(Duration::from_millis(10), Duration::from_millis(100)),
+    );
+
+    assert!(matches!(client.next().await, Some(Ok(Event::Connected(_)))));
+    assert!(client.is_connected());
+
+    client.send(ws::Message::Text("one".into())).await.unwrap();
+    match client.next().await {
+        Some(Ok(Event::Frame(frame))) => {
+            assert_eq!(frame, ws::Frame::Text(Bytes::from_static(b"one")))
+        }
+        ev => panic!("unexpected event: {:?}", ev),
+    }
+
+    assert!(matches!(client.next().await, Some(Ok(Event::Disconnected))));
+    assert!(matches!(
+        client.next().await,
+        Some(Ok(Event::Reconnecting { attempt: 1, .. }))
+    ));
+    assert!(!client.is_connected());
+
+    // sent while disconnected; flushing waits for the connection to be re-established
+    client.send(ws::Message::Text("two".into())).await.unwrap();
+    assert!(client.is_connected());
+
+    assert!(matches!(client.next().await, Some(Ok(Event::Connected(_)))));
+    match client.next().await {
+        Some(Ok(Event::Frame(frame))) => {
+            assert_eq!(frame, ws::Frame::Text(Bytes::from_static(b"two")))
+        }
+        ev => panic!("unexpected event: {:?}", ev),
+    }
+
+    assert!(matches!(client.next().await, Some(Ok(Event::Disconnected))));
+
+    // closing while disconnected ends the stream
+    client.send(ws::Message::Close(None)).await.unwrap();
+    assert!(matches!(
+        client.next().await,
+        Some(Ok(Event::Reconnecting { attempt: 1, .. }))
+    ));
+    assert!(client.next().await.is_none());
+    assert!(client
+        .send(ws::Message::Text("three".into()))
+        .await
+        .is_err());
+}
+
+#[actix_rt::test]
+async fn test_reconnecting_max_attempts() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .finish(|_| ok::<_, Error>(Response::not_found()))
+            .tcp()
+    })
+    .await;
+
+    let mut client = awc::Client::new().ws(srv.url("/")).reconnecting(
+        Reconnect::new()
+            .backoff(Duration::from_millis(1), Duration::from_millis(10))
+            .max_attempts(2),
+    );
+
+    for attempt in 1..=2 {
+        match client.next().await {
+            Some(Ok(Event::Reconnecting { attempt: n, .. })) => assert_eq!(n, attempt),
+            ev => panic!("unexpected event: {:?}", ev),
+        }
+    }
+
+    assert!(matches!(
+        client.next().await,
+        Some(Err(awc::error::WsClientError::InvalidResponseStatus(_)))
+    ));
+    assert!(client.next().await.is_none());
+}